@@ -0,0 +1,248 @@
+#ifdef _WIN32
+#define _WIN32_WINNT 0x0A00
+#endif // _WIN32
+
+#include <algorithm>
+#include <array>
+#include <chrono>
+#include <cstddef>
+#include <cstdint>
+#include <cstdlib>
+#include <iostream>
+#include <memory>
+#include <stdexcept>
+#include <string>
+#include <variant>
+#include <vector>
+
+#include <thirdparty\asio\asio.hpp>
+#include <thirdparty\zpp_bits\zpp_bits.h>
+
+#include "framing.h"
+#include "protocol.h"
+
+// Timings for the server's fan-out paths, taken from outside over real
+// connections so they measure what a member waits for: the relay, the
+// framing, the queues and the gather writes. Run against a release server
+// started with no load caps, on the same machine:
+//
+//   benchmarks [--host H] [--port N] [--rounds N] [--idle-rooms N]
+//
+// Every case runs among idle_rooms rooms of idle_members each, the lobby
+// population of a busy evening, so lookups and queues are not empty-server
+// fast. Medians and p99 are printed per case, in microseconds.
+namespace {
+	using namespace Weyvelength;
+	using Clock = std::chrono::steady_clock;
+
+	struct Options {
+		std::string host = "127.0.0.1";
+		uint16_t port = 5555;
+		uint32_t rounds = 200; // measured, after rounds / 10 of warmup
+		uint32_t idle_rooms = 100;
+		uint32_t idle_members = 4;
+	};
+
+	// A bare connection speaking the wire protocol directly; blocking, as
+	// each case drives one step at a time.
+	struct BenchClient {
+		asio::ip::tcp::socket socket;
+		uint32_t id = 0;
+		std::string room;
+		uint64_t next_ping = 1;
+
+		explicit BenchClient(asio::io_context& context) : socket(context) {}
+
+		void Send(const Proto::ServerMessage& msg)
+		{
+			std::vector<std::byte> frame = Proto::FrameMessage(msg);
+			asio::write(socket, asio::buffer(frame));
+		}
+
+		Proto::ServerMessage Read()
+		{
+			std::vector<std::byte> body;
+			while (true) {
+				std::array<std::byte, Proto::frame_header_size> header;
+				asio::read(socket, asio::buffer(header));
+				uint32_t len;
+				bool more;
+				if (!Proto::DecodeFrameHeader(header, len, more))
+					throw std::runtime_error("bad frame header");
+				size_t base = body.size();
+				body.resize(base + len);
+				asio::read(socket, asio::buffer(body.data() + base, len));
+				if (!more)
+					break;
+			}
+			Proto::ServerMessage msg;
+			zpp::bits::in{ body }(msg).or_throw();
+			return msg;
+		}
+
+		template <typename T>
+		T ReadUntil()
+		{
+			while (true) {
+				Proto::ServerMessage msg = Read();
+				if (auto* wanted = std::get_if<T>(&msg))
+					return std::move(*wanted);
+			}
+		}
+
+		// Reads away everything the server queued for us so far: it answers
+		// a ping in order, so once the echo is back nothing older is left.
+		void Drain()
+		{
+			uint64_t ping = next_ping++;
+			Send(Proto::Heartbeat{ ping });
+			while (true) {
+				Proto::ServerMessage msg = Read();
+				if (auto* echo = std::get_if<Proto::Heartbeat>(&msg); echo && echo->timestamp == ping)
+					return;
+			}
+		}
+	};
+
+	using Clients = std::vector<std::unique_ptr<BenchClient>>;
+
+	std::unique_ptr<BenchClient> Connect(asio::io_context& context, const Options& options)
+	{
+		auto client = std::make_unique<BenchClient>(context);
+		asio::ip::tcp::resolver resolver{ context };
+		asio::connect(client->socket, resolver.resolve(options.host, std::to_string(options.port)));
+		client->socket.set_option(asio::ip::tcp::no_delay(true));
+		client->id = client->ReadUntil<Proto::AssignClientId>().id;
+		return client;
+	}
+
+	// One room: the first client creates it, the rest join. All of them are
+	// drained, so the room's own join traffic is not timed later.
+	Clients MakeRoom(asio::io_context& context, const Options& options, uint32_t members)
+	{
+		Clients room;
+		room.push_back(Connect(context, options));
+		room[0]->Send(Proto::CreateRoom{});
+		room[0]->room = room[0]->ReadUntil<Proto::AssignRoomId>().id;
+		for (uint32_t i = 1; i < members; i++) {
+			room.push_back(Connect(context, options));
+			room.back()->Send(Proto::JoinRoom{ room[0]->room, {} });
+			room.back()->room = room.back()->ReadUntil<Proto::AssignRoomId>().id;
+		}
+		for (auto& client : room)
+			client->Drain();
+		return room;
+	}
+
+	struct Timings {
+		std::vector<double> us;
+
+		void Print(const std::string& name) const
+		{
+			std::vector<double> sorted = us;
+			std::ranges::sort(sorted);
+			double median = sorted[sorted.size() / 2];
+			double p99 = sorted[std::min(sorted.size() - 1, sorted.size() * 99 / 100)];
+			std::cout << name << ": median " << (uint64_t)median << " us, p99 " << (uint64_t)p99 << " us (" << sorted.size() << " rounds)\n";
+		}
+	};
+
+	template <typename Round>
+	Timings Measure(const Options& options, Round round)
+	{
+		for (uint32_t i = 0; i < options.rounds / 10; i++)
+			round();
+
+		Timings timings;
+		for (uint32_t i = 0; i < options.rounds; i++) {
+			auto start = Clock::now();
+			round();
+			timings.us.push_back(std::chrono::duration<double, std::micro>(Clock::now() - start).count());
+		}
+		return timings;
+	}
+
+	// One RoomChat in, until the last of the room's members holds it. The
+	// server frames it once and shares the bytes across every queue.
+	void BenchChatBroadcast(asio::io_context& context, const Options& options, uint32_t members)
+	{
+		Clients room = MakeRoom(context, options, members);
+		Measure(options, [&] {
+			room[0]->Send(Proto::RoomChat{ 0, "gg, one more?" });
+			for (auto& member : room)
+				member->ReadUntil<Proto::RoomChat>();
+		}).Print("chat broadcast to " + std::to_string(members) + " members");
+	}
+
+	// A member opening links to everyone else, as on joining a full room:
+	// one candidate to each peer, until the last one has it. Each is a
+	// separate relay with its own framing.
+	void BenchSignalFanOut(asio::io_context& context, const Options& options, uint32_t members)
+	{
+		Clients room = MakeRoom(context, options, members);
+		const std::string candidate = "a=candidate:1 1 UDP 2122252543 192.168.1.20 50123 typ host";
+		Measure(options, [&] {
+			for (size_t i = 1; i < room.size(); i++)
+				room[0]->Send(Proto::P2PSignal{ room[i]->id, Proto::P2PSignalKind::Candidate, candidate });
+			for (size_t i = 1; i < room.size(); i++)
+				room[i]->ReadUntil<Proto::P2PSignal>();
+		}).Print("signal fan-out to " + std::to_string(members - 1) + " peers");
+	}
+
+	// The one list the server builds on request: the booking board, filled
+	// up to max_scheduled_rooms. There is no public room list to time.
+	void BenchScheduledList(asio::io_context& context, const Options& options)
+	{
+		auto client = Connect(context, options);
+		uint64_t start = (uint64_t)std::chrono::duration_cast<std::chrono::seconds>(std::chrono::system_clock::now().time_since_epoch()).count() + 24 * 60 * 60;
+		for (uint32_t i = 0; i < Proto::max_scheduled_rooms; i++)
+			client->Send(Proto::ScheduleRoom{ start + i * 60, "Weekly cup, round " + std::to_string(i + 1), 8, 16, {} }); // "full" once the board is, from an earlier run
+		client->Drain();
+
+		Measure(options, [&] {
+			client->Send(Proto::ListScheduledRooms{});
+			client->ReadUntil<Proto::ScheduledRoomList>();
+		}).Print("scheduled room list of " + std::to_string(Proto::max_scheduled_rooms));
+	}
+}
+
+int main(int argc, char* argv[])
+{
+	Options options;
+	for (int i = 1; i + 1 < argc; i += 2) {
+		std::string flag = argv[i];
+		uint32_t value = (uint32_t)std::strtoul(argv[i + 1], nullptr, 10);
+		if (flag == "--host")
+			options.host = argv[i + 1];
+		else if (flag == "--port")
+			options.port = (uint16_t)value;
+		else if (flag == "--rounds")
+			options.rounds = std::max(value, 1u);
+		else if (flag == "--idle-rooms")
+			options.idle_rooms = value;
+		else {
+			std::cerr << "usage: benchmarks [--host H] [--port N] [--rounds N] [--idle-rooms N]\n";
+			return 1;
+		}
+	}
+
+	try {
+		asio::io_context context;
+
+		std::vector<Clients> population;
+		for (uint32_t i = 0; i < options.idle_rooms; i++)
+			population.push_back(MakeRoom(context, options, options.idle_members));
+		std::cout << "population: " << options.idle_rooms << " rooms of " << options.idle_members << "\n";
+
+		for (uint32_t members : { 8u, 32u, 128u })
+			BenchChatBroadcast(context, options, members);
+		for (uint32_t members : { 8u, 32u, 128u })
+			BenchSignalFanOut(context, options, members);
+		BenchScheduledList(context, options);
+	}
+	catch (const std::exception& e) {
+		std::cerr << "benchmark failed: " << e.what() << " (is a server without load caps running?)\n";
+		return 1;
+	}
+	return 0;
+}
@@ -0,0 +1,175 @@
+#pragma once
+
+#include <chrono>
+#include <cstddef>
+#include <cstdint>
+#include <cstdio>
+#include <deque>
+#include <string>
+#include <vector>
+
+// The chat this client heard while running: room lines, and whispers both
+// ways. Memory only and capped; the server keeps no history to fetch, so an
+// export covers this run, back to the oldest line still held.
+namespace Weyvelength {
+	struct ChatLine {
+		uint64_t time = 0; // unix seconds, as heard
+		std::string room; // the room it was said in
+		uint32_t from = 0;
+		std::string name; // the sender's name at the time; empty = anonymous
+		uint32_t to = 0; // a whisper's target; 0 = room chat
+		std::string to_name; // the target's name at the time; empty = anonymous, or room chat
+		std::string text;
+	};
+
+	constexpr size_t max_chat_history = 2000; // lines, across every room; the oldest goes first
+
+	enum class ChatExportFormat : uint8_t {
+		Text, // "2026-01-31T18:04:05Z [ROOMCODE] Alice: hi", one line per message
+		Json, // an array of objects, one per message
+		Csv, // RFC 4180, header row first
+	};
+
+	// Lines said in room (empty = every room) holding every word of the query,
+	// case ignored (ASCII only), newest first. Indexes into lines; the ones
+	// either side of a match are its context. A linear scan: the history is
+	// capped small enough that an index would not pay for itself.
+	inline std::vector<size_t> SearchChat(const std::deque<ChatLine>& lines, const std::string& query, const std::string& room, size_t limit)
+	{
+		auto lower = [](std::string text) {
+			for (char& c : text)
+				c = (c >= 'A' && c <= 'Z') ? (char)(c - 'A' + 'a') : c;
+			return text;
+		};
+
+		std::vector<std::string> words;
+		std::string folded = lower(query);
+		for (size_t start = 0; start < folded.size();) {
+			size_t end = folded.find_first_of(" \t\n", start);
+			if (end == std::string::npos)
+				end = folded.size();
+			if (end > start)
+				words.push_back(folded.substr(start, end - start));
+			start = end + 1;
+		}
+
+		std::vector<size_t> found;
+		if (words.empty())
+			return found;
+
+		for (size_t i = lines.size(); i-- > 0 && found.size() < limit;) {
+			if (!room.empty() && lines[i].room != room)
+				continue;
+			std::string text = lower(lines[i].text);
+			bool all = true;
+			for (const std::string& word : words)
+				all = all && text.find(word) != std::string::npos;
+			if (all)
+				found.push_back(i);
+		}
+		return found;
+	}
+
+	namespace ChatExport {
+		inline std::string Timestamp(uint64_t unix) // ISO 8601, UTC
+		{
+			using namespace std::chrono;
+			sys_seconds at{ seconds{ unix } };
+			sys_days day = floor<days>(at);
+			year_month_day date{ day };
+			hh_mm_ss time{ at - day };
+
+			char out[32];
+			std::snprintf(out, sizeof(out), "%04d-%02u-%02uT%02d:%02d:%02dZ", (int)date.year(), (unsigned)date.month(), (unsigned)date.day(),
+				(int)time.hours().count(), (int)time.minutes().count(), (int)time.seconds().count());
+			return out;
+		}
+
+		inline std::string Who(const std::string& name, uint32_t id)
+		{
+			return name.empty() ? "client " + std::to_string(id) : name;
+		}
+
+		inline std::string Json(const std::string& text) // quoted; the text is already valid UTF-8
+		{
+			std::string out = "\"";
+			for (char c : text) {
+				if (c == '"' || c == '\\') {
+					out += '\\';
+					out += c;
+				}
+				else if (c == '\n') {
+					out += "\\n";
+				}
+				else if ((unsigned char)c < 0x20) {
+					char escaped[8];
+					std::snprintf(escaped, sizeof(escaped), "\\u%04x", (unsigned)c);
+					out += escaped;
+				}
+				else {
+					out += c;
+				}
+			}
+			return out + "\"";
+		}
+
+		inline std::string Csv(const std::string& text) // always quoted, so commas and line breaks need no thought
+		{
+			std::string out = "\"";
+			for (char c : text) {
+				if (c == '"')
+					out += '"';
+				out += c;
+			}
+			return out + "\"";
+		}
+
+		// Every held line said in room, oldest first; an empty room is every room.
+		inline std::string Format(const std::deque<ChatLine>& lines, const std::string& room, ChatExportFormat format)
+		{
+			std::string out;
+			if (format == ChatExportFormat::Json)
+				out += "[";
+			else if (format == ChatExportFormat::Csv)
+				out += "time,room,from,name,to,to_name,text\r\n";
+
+			bool first = true;
+			for (const ChatLine& line : lines) {
+				if (!room.empty() && line.room != room)
+					continue;
+
+				switch (format) {
+				case ChatExportFormat::Text: {
+					out += Timestamp(line.time) + " [" + line.room + "] " + Who(line.name, line.from);
+					if (line.to != 0)
+						out += " -> " + Who(line.to_name, line.to);
+					out += ": ";
+					for (char c : line.text) {
+						out += c;
+						if (c == '\n')
+							out += "    "; // continuation lines indented under the message
+					}
+					out += "\n";
+					break;
+				}
+				case ChatExportFormat::Json:
+					out += first ? "\n" : ",\n";
+					out += "  {\"time\":" + Json(Timestamp(line.time)) + ",\"room\":" + Json(line.room)
+						+ ",\"from\":" + std::to_string(line.from) + ",\"name\":" + Json(line.name)
+						+ ",\"to\":" + std::to_string(line.to) + ",\"to_name\":" + Json(line.to_name)
+						+ ",\"text\":" + Json(line.text) + "}";
+					break;
+				case ChatExportFormat::Csv:
+					out += Timestamp(line.time) + "," + Csv(line.room) + "," + std::to_string(line.from) + "," + Csv(line.name)
+						+ "," + std::to_string(line.to) + "," + Csv(line.to_name) + "," + Csv(line.text) + "\r\n";
+					break;
+				}
+				first = false;
+			}
+
+			if (format == ChatExportFormat::Json)
+				out += first ? "]\n" : "\n]\n";
+			return out;
+		}
+	}
+}
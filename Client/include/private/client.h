@@ -1,14 +1,29 @@
 #pragma once
 
+#include <chrono>
+#include <cstddef>
 #include <cstdint>
+#include <deque>
+#include <fstream>
 #include <map>
 #include <memory>
+#include <optional>
 #include <queue>
+#include <random>
+#include <span>
 #include <string>
 #include <utility>
 #include <vector>
 
+#include "chat_history.h"
+#include "client_log.h"
+#include "jitter_buffer.h"
+#include "network_path.h"
+#include "p2p_framing.h"
+#include "port_mapper.h"
+#include "process_manager.h"
 #include "protocol.h"
+#include "recording.h"
 
 namespace Weyvelength {
 	struct ClientAsioImpl;
@@ -16,51 +31,314 @@ namespace Weyvelength {
 	struct PeerLink;
 	struct JuiceEvent;
 
+	// A chat line or whisper sent while there was no room to carry it. Held
+	// until the app retries or discards it, so typed text is never lost.
+	struct UnsentMessage {
+		uint32_t id = 0; // local to this client; never 0
+		uint32_t to = 0; // the whisper target; 0 = room chat
+		std::string text;
+	};
+
+	constexpr size_t max_cached_blobs = 32;
+
+	// How hard the client works for latency. The client has no timers of its
+	// own, so most of the saving is the app polling less often; Low also gives
+	// up on a direct link sooner. The app stores the choice per device.
+	enum class PerformanceProfile : uint8_t {
+		Low, // on battery
+		Balanced,
+		High, // plugged in, mid-match
+	};
+
+	struct PerformanceSettings {
+		std::chrono::milliseconds poll_interval; // how long the app may sleep between Polls
+		uint32_t connection_attempts; // ICE tries per peer before giving up
+		std::chrono::milliseconds first_retry_delay; // doubles with every failed try
+	};
+
+	constexpr PerformanceSettings PerformanceFor(PerformanceProfile profile)
+	{
+		switch (profile) {
+		case PerformanceProfile::Low:
+			return { std::chrono::milliseconds{ 50 }, 2, std::chrono::milliseconds{ 4000 } };
+		case PerformanceProfile::High:
+			return { std::chrono::milliseconds{ 1 }, 5, std::chrono::milliseconds{ 500 } };
+		default:
+			return { std::chrono::milliseconds{ 10 }, 3, std::chrono::milliseconds{ 1000 } };
+		}
+	}
+
+	// How long a link may sit in one state short of connected (gathering,
+	// waiting on the peer's answer, checking, disconnected) before it counts
+	// as failed and is redialed. Slow relay paths want longer, a LAN shorter.
+	constexpr std::chrono::seconds default_link_timeout{ 30 };
+	constexpr std::chrono::seconds min_link_timeout{ 5 };
+	constexpr std::chrono::seconds max_link_timeout{ 300 };
+
+	constexpr std::chrono::milliseconds max_simulated_latency{ 2000 };
+
+	// Raised by the mesh, not the server: how one peer's direct link is faring.
+	struct PeerEvent {
+		enum class Kind : uint8_t {
+			Retrying, // a dial failed; the next starts in retry_in ms
+			Unreachable, // the last allowed dial failed; only RetryPeerConnection tries again
+			Closed, // closed on purpose, by us or by the peer; the next SendP2P dials afresh
+		};
+		Kind kind{};
+		uint32_t id = 0;
+		uint32_t attempt = 0; // failed dials in a row, this one included; 0 for Closed
+		uint32_t retry_in = 0; // ms; Retrying only
+		bool remote = false; // Closed only: the peer closed it
+	};
+
+	// Requests the client waits on an answer to. A server that never answers
+	// surfaces as a timeout instead of leaving the app waiting forever.
+	enum class RequestKind : uint8_t {
+		Room, // CreateRoom, JoinRoom or SpectateRoom: AssignRoomId, RoomError or ServerFull
+		Account, // CreateAccount, Login or GuestLogin: LoggedIn or AccountError
+	};
+
+	constexpr std::chrono::seconds request_timeout{ 10 }; // argon2 on a busy server stays well inside this
+
+	// What a join would run into, read off a fresh look at the room. Nothing
+	// is held for us: the room can change before the join goes in.
+	struct JoinCheck {
+		std::string id; // as the server has it; as asked, if it was not found
+		bool spectating = false; // the seat it was checked for
+		std::optional<Proto::RoomErrorCode> blocker; // the RoomError the join would get: NoSuchRoom, Banned, RoomClosed or RoomFull. None = it should go in
+		bool password = false; // needed unless the host let us in; a wrong one gets BadPassword
+		bool relay = false; // the server offers a TURN relay, for members no direct path reaches
+	};
+
+	inline JoinCheck CheckJoin(const Proto::RoomPreview& preview, bool spectating, bool relay)
+	{
+		JoinCheck check{ preview.id, spectating, {}, preview.passworded, relay };
+		bool full = spectating ? preview.max_spectators != 0 && preview.spectators >= preview.max_spectators
+			: preview.max_players != 0 && preview.players >= preview.max_players;
+		if (!preview.open)
+			check.blocker = Proto::RoomErrorCode::RoomClosed;
+		else if (full)
+			check.blocker = Proto::RoomErrorCode::RoomFull; // a line may be waiting too; QueueForRoom joins it
+		return check;
+	}
+
+	// Raised by Poll, not the server, as a CountdownStarted runs out on our
+	// own steady clock: one per whole second left, then 0 at the start.
+	struct CountdownTick {
+		uint32_t id = 0; // the CountdownStarted's
+		uint32_t remaining = 0; // whole seconds left; 0 = start now
+	};
+
 	struct ClientConfig {
 		std::string host = "127.0.0.1";
 		uint16_t port = 0;
 	};
 
+	// Enough to find the way back into a room after a restart. Account
+	// passwords are never kept; a guest logs in afresh under the same base
+	// name, with a new suffix.
+	struct SessionState {
+		ClientConfig server;
+		std::string room;
+		std::string password; // as joined with, or as last set while hosting
+		bool spectating = false;
+		std::string guest; // our guest name without its suffix; empty if not a guest
+	};
+
+	constexpr uint8_t session_state_version = 1;
+
 	struct Client {
 		Client();
 		~Client();
 
 		bool Connect(ClientConfig& config);
+		void SetAppVersion(std::string version); // sent in the ClientInfo of every connection from then on, for the server's breakdown
+		void Disconnect(); // leaves the room and closes every p2p link, telling the peers, before closing the socket; the destructor does this too
+
+		std::vector<std::byte> SaveSession() const; // opaque bytes for the app to store; empty when not in a room
+		bool ResumeSession(std::span<const std::byte> saved); // connects if need be, then rejoins; the server replies as for JoinRoom. False if saved is unreadable
+		bool NetworkChanged(); // the device moved networks: reconnects, rejoins and drops every p2p link for fresh ICE. False, connection untouched, if the server is unreachable
 
 		bool Poll();
+		void SetPerformanceProfile(PerformanceProfile profile); // takes effect on the next dial; Balanced until set
+		PerformanceProfile Performance() const;
+		void SetLinkTimeout(std::chrono::seconds timeout); // clamped to the limits above; 0 = the default. Links already dialing use it too
+		std::chrono::seconds LinkTimeout() const;
+		void SetPortMapping(uint16_t port); // asks the router to forward this UDP port, which every link built afterwards shares; 0 = off, each link on a port of its own
+		uint16_t PortMappingPort() const; // 0 when off
+		bool SetNetworkInterface(const std::string& name_or_address); // ICE gathers on that interface only, for links built afterwards; empty = every one. False, unchanged, if no up interface matches
+		const NetworkInterface& BoundInterface() const; // the one SetNetworkInterface picked; empty name = every one
+		bool StartRecording(const std::string& path); // every server message from now on, both ways, into a fixture for Proto::ReadRecording; replaces any running one. False if the file cannot be written, or this build lacks WEYVE_RECORDING
+		void StopRecording();
+		bool Recording() const;
 
 		bool Next(Proto::ServerMessage& out);
+		bool Next(Proto::ServerMessage& out, std::string& room); // room is the one we were in as it arrived; see WeyveEvent::room
 
 		bool SendServer(const Proto::ServerMessage& msg);
+		bool SendHeartbeat(uint64_t timestamp); // the server echoes it, unless 0, which answers its probes; the echo times RoundTrip()
 
 		bool CreateRoom(); // server replies AssignRoomId or RoomError
 		bool JoinRoom(const std::string& id, const std::string& password = {}); // server replies AssignRoomId or RoomError
+		bool SpectateRoom(const std::string& id, const std::string& password = {}); // JoinRoom without taking a player seat
 		bool LeaveRoom(); // server replies PeerLeft carrying our own id, or RoomError
+		bool RequestJoin(const std::string& id, const std::string& message = {}); // knock on a passworded room; server replies JoinRequestAnswered or RoomError
+		bool RespondJoinRequest(uint32_t id, bool approve); // host-only, to a JoinRequested; the knocker gets JoinRequestAnswered
+		bool QueueForRoom(const std::string& id, const std::string& password = {}); // wait for a player seat; server replies RoomQueuePosition, then SeatOffered, or RoomError
+		bool LeaveRoomQueue(); // server replies RoomQueuePosition with position 0
 
 		bool KickMember(uint32_t id); // host-only; the target gets KickedByHost, the room gets PeerLeft
 		bool BanMember(uint32_t id); // host-only; the target gets BannedByHost and is barred from rejoining
 		bool TransferHost(uint32_t id); // host-only; server replies HostChanged to the room
+		bool SplitRoom(const std::vector<uint32_t>& members); // host-only; moves them, in order, to a new room the first of them hosts. Server replies RoomSplit
+		bool MergeRoom(const std::string& id); // host-only; asks id's host to move their whole room into yours. Server replies MergeAnswered once they answer
+		bool AnswerMerge(const std::string& into, bool accept); // host-only, to a MergeRequested; accepting moves your room, you last
+		bool SetMemberRole(uint32_t id, Proto::MemberRole role); // host-only; server replies MemberRoleChanged to the room
+		bool StartCountdown(uint32_t seconds); // host-only, up to max_countdown; 0 cancels. Server replies CountdownStarted to the room, or RoomError
+		bool PauseMatch(); // host-only; server replies MatchPausedChanged to the room
+		bool ResumeMatch(); // host-only; sugar for an unpausing SetMatchPaused
+		bool GetSessionHealth(); // host or co-host; server replies SessionHealth, or RoomError
+		bool GetJoinReceipts(); // host or co-host; server replies JoinReceipts, or RoomError
+
+		// A dedicated game server on this machine, under the host's control.
+		// Launching and commanding it are host-only; it keeps running if the
+		// host status moves on, until stopped or the client is destroyed.
+		bool LaunchGameServer(const std::vector<std::string>& argv); // argv[0] is the program; false if not host, one runs already, or it could not start
+		bool SendConsoleCommand(const std::string& command); // one line to its stdin; false if not host or it is not running
+		void StopGameServer(); // kills it; its Exited follows from Poll
+		bool GameServerRunning() const;
+
+		bool SetPreferences(const Proto::NotificationPreferences& prefs); // server replies PreferencesChanged
+
+		bool CreateAccount(const std::string& name, const std::string& password, const std::string& email = {}); // server replies LoggedIn or AccountError
+		bool Login(const std::string& name, const std::string& password); // server replies LoggedIn or AccountError
+		bool GuestLogin(const std::string& name); // server replies LoggedIn with a suffixed name, or AccountError
+
+		// Bookings for rooms that open later; server replies ScheduledRoomChanged or RoomError.
+		bool ScheduleRoom(uint64_t start, const std::string& title, uint32_t max_players = 0, uint32_t max_spectators = 0, const std::string& password = {});
+		bool ListScheduledRooms(); // server replies ScheduledRoomList
+		bool RsvpScheduledRoom(uint32_t id, Proto::RsvpStatus status); // Going or Maybe = invited when it opens; Going is reminded too
+		bool ListAttendees(uint32_t id); // server replies ScheduledRoomAttendees
+		bool CancelScheduledRoom(uint32_t id); // owner only; server replies ScheduledRoomCancelled or RoomError
 
 		bool SetRoomJoinable(bool open); // host-only; server replies RoomAccessChanged to the room
 		bool SetRoomPassword(const std::string& password); // host-only; empty clears it
+		bool SetRoomCapacity(uint32_t max_players, uint32_t max_spectators); // host-only, 0 = no limit; server replies RoomCapacityChanged to the room
+		bool SetIdlePolicy(uint32_t afk_after, bool spectate); // host-only, seconds (0 = never); server replies IdlePolicyChanged to the room
+		bool SetRoomBanner(const std::string& id); // host-only, an uploaded blob's id; empty clears it. Server replies RoomBannerChanged to the room
+		bool SetRoomRules(const std::string& rules); // host-only, up to max_room_rules bytes; empty clears them. Server replies RoomRulesChanged to the room
+		bool SetHostState(const std::string& state); // host-only, up to max_host_state bytes; whoever hosts next gets the last one in HostHandoff. Empty clears it
+		bool ReportHostState(const std::string& state); // your view of it, relayed to the host; the one answer to each HostStateWanted, anything else is refused
+		bool SetSuccession(Proto::SuccessionPolicy policy, const std::vector<uint32_t>& successors); // host-only, up to max_successors; server replies SuccessionChanged to the room
+		bool ReportActivity(); // for input the server never sees, e.g. p2p play; any other request counts as activity too
+		bool GetServerInfo(); // server replies ServerInfo
+		bool GetRoomPreview(const std::string& id, bool watch = false); // server replies RoomPreview or RoomError; watch resends it on change for room_preview_watch
+		bool StopRoomPreview(); // ends a watch early
+		bool PreflightJoin(const std::string& id, bool spectating = false); // a GetRoomPreview whose answer also becomes a JoinCheck; one check at a time
+		bool SetBackground(bool background); // while set, the server holds room state updates back; going foreground replays the room from AssignRoomId
+		bool SearchUsers(const std::string& query); // server replies UserSearchResults
+		bool SetSearchable(bool searchable); // server replies SearchableChanged; an account keeps the choice
+		bool ExportMyData(); // server replies MyData, or AccountError TooSoon
+		bool GetTimeline(uint32_t after = 0); // the room's entries past seq after; server replies Timeline or RoomError. Joining sends the whole log already
 
-		bool SendChat(const std::string& text); // broadcast to everyone in the current room
+		bool SendChat(const std::string& text); // broadcast to everyone in the current room; held as unsent when not in one
+		bool SendWhisper(uint32_t id, const std::string& text); // to one member only; direct when a p2p link is up, else relayed by the server
+		bool RetryMessage(uint32_t id); // sends an unsent message again; false, and still held, if it cannot go yet
+		bool DiscardMessage(uint32_t id);
+		bool NextSendFailure(UnsentMessage& out); // one per send that was held, oldest first; the message stays held
+		void SetDraft(const std::string& scope, const std::string& text); // scope is the app's, e.g. a room id; empty text clears it
+		bool ExportChat(const std::string& room, ChatExportFormat format, const std::string& path) const; // the held history said in room (empty = every room); false if path could not be written
 		bool SetRoomData(const std::string& key, const std::string& value); // host-only; server replies RoomDataChanged or RoomError
 		bool DeleteRoomData(const std::string& key); // host-only; sugar for an empty-value SetRoomData
 		bool SetMemberData(const std::string& key, const std::string& value); // our own slots; server replies MemberDataChanged or RoomError
 		bool DeleteMemberData(const std::string& key); // sugar for an empty-value SetMemberData
+		bool SetMemberDataFor(uint32_t id, const std::string& key, const std::string& value); // host-only: any member's slots; an empty value deletes
+		bool CreateTeam(const std::string& name, uint32_t max_size = 0); // host-only; server replies TeamsChanged or RoomError. max_size 0 = no limit
+		bool RemoveTeam(uint32_t team); // host-only; its members end up on no team
+		bool AssignTeam(uint32_t id, uint32_t team); // host-only, players only; team 0 takes them off every team
+		bool BalanceTeams(); // host-only: every player dealt out again, see Proto::DealTeams
+		bool CreateChannel(const std::string& name); // host-only; server replies ChannelsChanged or RoomError
+		bool RemoveChannel(uint32_t channel); // host-only; its members end up in no channel
+		bool PlaceInChannel(uint32_t id, uint32_t channel); // host-only for others, anyone for themselves; channel 0 takes them out of every channel
+		bool SendChannelChat(const std::string& text); // to the members of our channel only; RoomError when we are in none
+
+		// Blobs: small assets stored on the server by content hash. Both return
+		// the transfer id the replies carry, or 0 if nothing was sent.
+		uint32_t UploadBlob(std::span<const std::byte> data); // server replies BlobUploaded or BlobError
+		uint32_t DownloadBlob(const std::string& id); // server replies BlobDownloaded, with the bytes in Blob(id) by then, or BlobError
+		const std::vector<std::byte>* GetBlob(const std::string& id); // the cached bytes, or null after starting (or joining) a download
 
 		bool SendP2P(uint32_t id, const Proto::P2PMessage& msg); // direct to a room member; the first send builds the link, queued until it connects
+		bool SendChannelP2P(const Proto::P2PMessage& msg); // SendP2P to each other member of our channel, over the room's links; false when we are in none or any send failed
 		bool NextP2P(uint32_t& from, Proto::P2PMessage& out); // one received datagram per call
 		bool PeerConnectedP2P(uint32_t id) const; // is a direct link to this member up right now?
+		void SetP2POrdering(bool ordered, std::chrono::milliseconds delay = default_jitter_delay); // sequences what we send; what arrives sequenced waits up to delay (capped at max_jitter_delay) for a gap. Raw until set
+		bool P2POrdered() const;
+		std::chrono::milliseconds JitterDelay() const;
+		void SetP2PFec(bool allowed); // ordered traffic only: parity is asked for when a peer's loss passes fec_on_permille and sent when a peer asks; both ends must allow it. Off until set
+		bool P2PFec() const;
+		void SimulateNetwork(uint32_t loss_permille, std::chrono::milliseconds latency); // development only: p2p datagrams arriving are dropped at that rate and held that long (capped at max_simulated_latency); 0, 0 = off
+		uint8_t PeerFecGroup(uint32_t id) const; // we send them a parity frame after every this many; 0 = none
+		uint32_t PeerLossPermille(uint32_t id) const; // ordered frames from them lost on the wire, as of the last reading
+		bool PeerLinkPath(uint32_t id, LinkPath& out) const; // the candidate pair it settled on; false unless connected
+		void SetHealthReporting(bool report, bool share = false); // our links, summed up for the host every health_report_interval; share also counts them, anonymously, in the server's totals. On, not shared, until set
+		bool RetryPeerConnection(uint32_t id); // redials now on a fresh attempt budget; true if dialing, or already up
+		bool ClosePeerConnection(uint32_t id, bool notify = true); // drops the link and any pending redial; notify tells the peer to drop theirs. False if there was none
+		bool NextPeerEvent(PeerEvent& out); // one per call, oldest first
+		bool NextTimeout(RequestKind& out); // one per request that went unanswered for request_timeout
+		bool NextJoinCheck(JoinCheck& out); // one per PreflightJoin answered, queued as its RoomPreview or RoomError is read
+		bool NextCountdownTick(CountdownTick& out); // oldest first
+		bool NextGameServerEvent(ProcessEvent& out); // its output lines, then Exited; read by Poll
+		bool NextPortMapping(PortMapping& out); // whether the router took it, then one per change as it is renewed
 
 		uint32_t Id() const;  // 0 until the server has assigned one
+		const std::string& Name() const; // our account name; empty until logged in
+		const std::string* MemberName(uint32_t id) const; // null for anonymous members
 		const std::string& RoomId() const; // empty until a room has been joined
 		uint32_t HostId() const; // 0 until a room has been joined
 		bool IsHost() const;
+		Proto::MemberRole Role(uint32_t id) const; // Member unless promoted; the host holds no role
+		const Proto::NotificationPreferences& Preferences() const; // as last confirmed by the server
+		const std::vector<Proto::ScheduledRoom>& ScheduledRooms() const; // soonest first; as of the last list, plus bookings heard of since
+		const std::vector<Proto::Attendee>* Attendees(uint32_t id) const; // null until a ScheduledRoomAttendees for that booking arrived
+		const Proto::RoomPreview* Preview() const; // the last RoomPreview heard; null until one arrives
+		const Proto::UserSearchResults& UserResults() const; // the last UserSearchResults heard
+		bool Searchable() const; // as last confirmed by the server
+		const Proto::MyData& OwnData() const; // the last MyData heard
+		const std::deque<Proto::TimelineEntry>& RoomTimeline() const; // the room's log, oldest first, at most max_timeline_entries
+		const std::vector<UnsentMessage>& UnsentMessages() const; // oldest first
+		const std::map<std::string, std::string>& Drafts() const; // by scope; the app persists them across runs if it wants to
+		const std::string* Draft(const std::string& scope) const; // null if none
+		const std::deque<ChatLine>& ChatHistory() const; // oldest first, at most max_chat_history lines
+		std::vector<size_t> SearchMessages(const std::string& query, const std::string& room, size_t limit) const; // SearchChat over ChatHistory()
+		const std::vector<std::byte>* Blob(const std::string& id) const; // null unless downloaded, and among the last max_cached_blobs
 
 		bool RoomJoinable() const; // can others join right now?
 		bool RoomPassworded() const; // the flag only; the password itself never reaches clients
+		uint32_t MaxPlayers() const; // 0 = no limit
+		uint32_t MaxSpectators() const; // 0 = no limit
+		uint32_t PlayerCount() const; // members not spectating, ourselves included
+		uint32_t SpectatorCount() const;
+		bool IsSpectator(uint32_t id) const;
+		bool IsAfk(uint32_t id) const;
+		uint32_t AfkAfter() const; // seconds; 0 = never
+		bool AfkSpectates() const; // do AFK players lose their seat?
+		Proto::SuccessionPolicy Succession() const;
+		const std::vector<uint32_t>& Successors() const; // the host's list, in order
+		const std::string& Banner() const; // a blob id; empty = none
+		const std::string& Rules() const; // empty = none
+		uint32_t RulesVersion() const; // 0 = never set
+		const std::vector<Proto::Team>& Teams() const; // in creation order; empty = the room has none
+		uint32_t TeamOf(uint32_t id) const; // 0 = on no team
+		const std::vector<Proto::Channel>& Channels() const; // in creation order; empty = the room has none
+		uint32_t ChannelOf(uint32_t id) const; // 0 = in no channel
+		bool InBackground() const;
+		std::chrono::milliseconds RoundTrip() const; // to the server, as of the last heartbeat echoed; 0 until one is
+		std::optional<std::chrono::milliseconds> CountdownRemaining() const; // until the running countdown starts us; none when nothing runs
+		bool MatchPaused() const;
+		const std::map<uint32_t, Proto::MemberHealth>& SessionHealth() const; // host-only: each member's last report by id, ours measured here; empty for anyone else
+		const Proto::SessionHealth& ServerHealth() const; // the last SessionHealth heard in this room
+		const Proto::JoinReceipts& Receipts() const; // the last JoinReceipts heard in this room
 
 		const std::vector<uint32_t>& Members() const; // everyone in the room, ourselves included
 		const std::map<std::string, std::string>& RoomData() const;
@@ -73,8 +351,20 @@ namespace Weyvelength {
 		bool CarveServer();
 		bool FlushServer();
 		bool DisconnectServer();
+		void OpenSignalLink(const Proto::SignalEndpoint& endpoint); // dials the server's signal port; if it cannot, signals stay on the main connection
+		void PollSignalLink(); // takes the signals it brought and sends ours; closes it on any error
+		bool SendSignaling(const Proto::ServerMessage& msg); // over the signal link when there is one, else as SendServer
+
+		bool HoldMessage(uint32_t to, std::string text); // always false, for the failed send to return
+		void RecordChat(uint32_t from, uint32_t to, const std::string& text); // into the history, said in the current room
+		bool SendKeyed(Proto::ServerMessage msg); // tagged with an Idempotent, and kept a while for NetworkChanged to resend
+		static std::mt19937_64 KeyEngine(); // seeded from several random_device draws, not one 32-bit one
+		void Record(Proto::RecordDirection direction, const Proto::ServerMessage& msg); // into the running recording, if any
 
 		void CacheRoomState(const Proto::ServerMessage& msg);
+		void Await(RequestKind kind); // restarts the clock if one is already pending
+		void PollTimeouts();
+		void PollCountdown();
 		void ClearRoomState();
 
 		void PollPeers();
@@ -84,30 +374,130 @@ namespace Weyvelength {
 
 		bool ShareLink(PeerLink& link, uint32_t id);
 		void FlushLink(PeerLink& link);
+		bool SendLink(PeerLink& link, Proto::P2PFrameKind kind, std::span<const std::byte> body);
+		bool SendAppLink(PeerLink& link, std::span<const std::byte> body); // Sequenced when ordering is on and the peer reads it, else App
+		void ReceiveSequenced(PeerLink& link, std::span<const std::byte> body);
+		void ReadLoss(PeerLink& link, uint32_t permille); // asks for parity, or for none, as the loss goes
+		void ReceiveLink(uint32_t id, PeerLink& link, std::vector<std::byte>& datagram);
+
+		void FailLink(uint32_t id); // tears the link down, then schedules a redial or gives up
+		void PollRetries();
+		void SweepLinks(); // fails links that sat too long without connecting
+		void PollJitter(); // releases sequenced datagrams whose turn has come
+		void PollHealth(); // sums up our links once an interval for the server, and keeps them too if we host
 
 		void DestroyLink(uint32_t id);
 		void DestroyAllLinks();
 
+		void SendSignal(Proto::P2PSignal&& sig); // sequenced, as every signal we send
+		void HandleSequencedSignal(const Proto::SequencedSignal& sig); // drops replays, then HandleP2PSignal
 		void HandleP2PSignal(const Proto::P2PSignal& sig);
 		void HandleP2PDescription(PeerLink* link, const Proto::P2PSignal& sig);
 		void HandleJuiceEvent(JuiceEvent& ev);
 		void HandleLinkState(PeerLink& link, const JuiceEvent& ev);
 
+		ClientConfig _server; // as last connected to
+		SessionState _joining; // password and seat of the join in flight; becomes the session on AssignRoomId
+		SessionState _session; // the room we are in, as SaveSession writes it
+
 		std::unique_ptr<ClientAsioImpl> _asio;
+		std::unique_ptr<ClientAsioImpl> _signal_link; // to the server's signal port, carrying signals both ways; null = they share _asio
 		std::unique_ptr<P2PMesh> _mesh;
+		std::unique_ptr<ProcessManager> _game;
+		std::queue<ProcessEvent> _game_events;
+		std::unique_ptr<PortMapper> _port_mapper; // null when off
 
 		Proto::IceServers _ice; // handed to us by the server; feeds every new link
+#ifdef WEYVE_RECORDING
+		std::ofstream _recording; // open while recording
+		std::chrono::steady_clock::time_point _recording_start;
+#endif
 
-		std::queue<Proto::ServerMessage> _inbox;
+		std::queue<std::pair<std::string, Proto::ServerMessage>> _inbox; // with the room it arrived in
 		std::queue<std::pair<uint32_t, Proto::P2PMessage>> _p2p_inbox;
+		std::queue<PeerEvent> _peer_events;
+		std::map<RequestKind, std::chrono::steady_clock::time_point> _awaiting; // deadlines
+		std::queue<RequestKind> _timeouts;
+		JoinCheck _checking; // the PreflightJoin in flight: the id as asked and the seat; id empty = none
+		std::queue<JoinCheck> _join_checks;
+		std::pair<uint64_t, std::chrono::steady_clock::time_point> _ping{}; // the last heartbeat sent: its timestamp and when
+		std::chrono::milliseconds _round_trip{ 0 };
+		struct {
+			uint32_t id = 0; // 0 = none running
+			std::chrono::steady_clock::time_point start;
+			uint32_t next = 0; // the next whole second to announce
+		} _countdown;
+		std::queue<CountdownTick> _ticks;
 
 		uint32_t _id = 0;
+		std::string _name;
 		std::string _room;
 		uint32_t _host = 0;
 		bool _room_open = true;
 		bool _room_passworded = false;
+		uint32_t _max_players = 0;
+		uint32_t _max_spectators = 0;
+		uint32_t _afk_after = 0;
+		bool _afk_spectate = false;
+		Proto::SuccessionPolicy _succession{};
+		std::vector<uint32_t> _successors;
+		std::string _banner;
+		std::string _rules;
+		bool _paused = false;
+		uint32_t _rules_version = 0;
+		bool _background = false; // as last told to the server; a new connection starts in the foreground
+		std::string _app_version; // empty = the app never said
+		PerformanceProfile _profile = PerformanceProfile::Balanced;
+		std::chrono::seconds _link_timeout = default_link_timeout;
+		NetworkInterface _interface; // ICE's only interface; empty name = every one
+		bool _ordered = false; // p2p datagrams we send are Sequenced
+		std::chrono::milliseconds _jitter_delay = default_jitter_delay; // applied to whatever arrives Sequenced, ordering on or off
+		bool _fec = false;
+		uint32_t _simulated_loss = 0; // permille
+		std::chrono::milliseconds _simulated_latency{ 0 };
+		bool _health_report = true;
+		bool _health_share = false;
+		std::chrono::steady_clock::time_point _health_window; // when the links' byte counts started
+		std::map<uint32_t, Proto::MemberHealth> _health; // host-only; by member
+		Proto::SessionHealth _server_health;
+		Proto::JoinReceipts _receipts;
 		std::vector<uint32_t> _members;
+		std::vector<uint32_t> _spectators; // a subset of _members
+		std::vector<uint32_t> _afk; // a subset of _members
+		std::map<uint32_t, std::string> _names; // named members only
 		std::map<std::string, std::string> _data;
 		std::map<uint32_t, std::map<std::string, std::string>> _member_data;
+		std::map<uint32_t, Proto::MemberRole> _roles;
+		std::deque<Proto::TimelineEntry> _timeline;
+		std::vector<Proto::Team> _teams;
+		std::vector<Proto::Channel> _channels;
+		Proto::NotificationPreferences _prefs; // outlives rooms; tied to the connection
+		std::vector<Proto::ScheduledRoom> _schedule; // outlives rooms too
+		std::map<uint32_t, std::vector<Proto::Attendee>> _attendees; // by booking id; dropped when the booking opens
+		Proto::RoomPreview _preview; // the last one heard; id empty until then
+		Proto::UserSearchResults _user_results;
+		bool _searchable = true; // a new connection starts searchable
+		Proto::MyData _own_data;
+
+		// the composer's side: outlives rooms and connections alike
+		std::vector<UnsentMessage> _unsent;
+		std::queue<uint32_t> _send_failures; // ids, for NextSendFailure
+		uint32_t _next_unsent = 1;
+		std::map<std::string, std::string> _drafts;
+		std::deque<ChatLine> _history;
+
+		struct KeyedRequest {
+			uint64_t key = 0;
+			Proto::ServerMessage msg;
+			std::chrono::steady_clock::time_point sent;
+		};
+		std::deque<KeyedRequest> _keyed; // oldest first; none older than idempotency_window
+		std::mt19937_64 _keys = KeyEngine(); // full-width seed: clients must not draw each other's keys
+		uint64_t _key_scope = _keys() | 1; // sent as KeyScope on every connection; never 0, which means none
+
+		uint32_t _next_transfer = 1;
+		std::map<uint32_t, std::pair<std::string, std::vector<std::byte>>> _downloads; // in flight, by transfer: the id and the bytes so far
+		std::map<std::string, std::vector<std::byte>> _blobs; // finished downloads, by id
+		std::deque<std::string> _blob_order; // least recently fetched first, for eviction
 	};
 }
@@ -0,0 +1,82 @@
+#pragma once
+
+#include <algorithm>
+#include <chrono>
+#include <cstddef>
+#include <cstdint>
+#include <deque>
+#include <mutex>
+#include <string>
+#include <vector>
+
+// What the client has to say about its connections: server disconnects and
+// protocol errors, direct links failing, and everything libjuice logs. Kept
+// in memory for the app's own log view, as a DLL has no console of its own.
+// One per process, like libjuice's log handler; lines below the level are
+// never kept.
+namespace Weyvelength {
+	enum class LogLevel : uint8_t { Debug, Info, Warn, Error };
+
+	struct LogEntry {
+		uint64_t time = 0; // unix milliseconds
+		LogLevel level{};
+		std::string text;
+	};
+
+	constexpr size_t max_log_entries = 512; // the oldest goes first
+
+	struct LogRing {
+		mutable std::mutex mutex; // libjuice logs from its own threads
+		std::deque<LogEntry> entries;
+		LogLevel level = LogLevel::Info;
+
+		void SetLevel(LogLevel least)
+		{
+			std::lock_guard lock{ mutex };
+			level = least;
+		}
+
+		LogLevel Level() const
+		{
+			std::lock_guard lock{ mutex };
+			return level;
+		}
+
+		void Add(LogLevel at, std::string text)
+		{
+			uint64_t now = (uint64_t)std::chrono::duration_cast<std::chrono::milliseconds>(std::chrono::system_clock::now().time_since_epoch()).count();
+			std::lock_guard lock{ mutex };
+			if (at < level)
+				return;
+			entries.push_back({ now, at, std::move(text) });
+			if (entries.size() > max_log_entries)
+				entries.pop_front();
+		}
+
+		// The newest limit entries at least that level, oldest first; 0 = all.
+		std::vector<LogEntry> Recent(LogLevel least, size_t limit) const
+		{
+			std::lock_guard lock{ mutex };
+			std::vector<LogEntry> out;
+			for (auto it = entries.rbegin(); it != entries.rend() && (limit == 0 || out.size() < limit); ++it) {
+				if (it->level >= least)
+					out.push_back(*it);
+			}
+			std::reverse(out.begin(), out.end());
+			return out;
+		}
+	};
+
+	inline LogRing& ClientLog()
+	{
+		static LogRing log;
+		return log;
+	}
+
+	inline void Log(LogLevel level, std::string text)
+	{
+		ClientLog().Add(level, std::move(text));
+	}
+
+	void SetLogLevel(LogLevel level); // ClientLog's and libjuice's, whose lines it collects; with the mesh, which owns libjuice
+}
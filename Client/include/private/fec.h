@@ -0,0 +1,131 @@
+#pragma once
+
+#include <cstddef>
+#include <cstdint>
+#include <map>
+#include <optional>
+#include <span>
+#include <utility>
+#include <vector>
+
+#include "jitter_buffer.h"
+#include "p2p_framing.h"
+
+// Forward error correction on ordered p2p traffic, for bad relay routes.
+// The receiver measures what goes missing; past fec_on_permille it asks the
+// sender for a parity frame after every fec_group frames, and from that it
+// rebuilds any one frame of a run lost. Both ends must allow FEC.
+namespace Weyvelength {
+	constexpr uint8_t fec_group = 4; // one parity frame per 4: a quarter more bytes
+	constexpr uint8_t max_fec_group = 16; // the most a request may ask
+	constexpr uint32_t fec_loss_window = 64; // Sequenced frames per loss reading
+	constexpr uint32_t fec_on_permille = 50; // ask for parity from 5% loss
+	constexpr uint32_t fec_off_permille = 10; // and stop asking below 1%
+	constexpr size_t fec_recent = 64; // received frames kept to rebuild from
+
+	inline void XorInto(std::vector<std::byte>& into, std::span<const std::byte> data)
+	{
+		if (into.size() < data.size())
+			into.resize(data.size());
+		for (size_t i = 0; i < data.size(); i++)
+			into[i] ^= data[i];
+	}
+
+	// The sender's half: folds each Sequenced frame into the run, and hands
+	// back the run's parity once it holds group of them.
+	struct ParityBuilder {
+		void SetGroup(uint8_t size)
+		{
+			group = size;
+			run = {};
+		}
+
+		uint8_t Group() const { return group; }
+
+		std::optional<Proto::P2PParity> Add(uint32_t seq, std::span<const std::byte> payload)
+		{
+			if (group == 0)
+				return std::nullopt;
+			if (run.count == 0)
+				run.first = seq;
+			run.count++;
+			run.length_xor ^= (uint16_t)payload.size();
+			XorInto(run.data_xor, payload);
+			if (run.count < group)
+				return std::nullopt;
+			return std::exchange(run, {});
+		}
+
+	private:
+		uint8_t group = 0; // 0 = off
+		Proto::P2PParity run;
+	};
+
+	// The receiver's half: keeps the last fec_recent frames, and rebuilds the
+	// one a parity frame's run is missing, if it is missing only one.
+	struct ParityRecovery {
+		void Keep(uint32_t seq, std::span<const std::byte> payload)
+		{
+			recent.try_emplace(seq, payload.begin(), payload.end());
+			while (recent.size() > fec_recent)
+				recent.erase(recent.begin());
+		}
+
+		std::optional<std::pair<uint32_t, std::vector<std::byte>>> Rebuild(const Proto::P2PParity& parity) const
+		{
+			if (parity.count == 0 || parity.count > max_fec_group)
+				return std::nullopt;
+
+			std::optional<uint32_t> missing;
+			uint16_t length = parity.length_xor;
+			std::vector<std::byte> data = parity.data_xor;
+			for (uint32_t seq = parity.first; seq != parity.first + parity.count; seq++) {
+				auto it = recent.find(seq);
+				if (it == recent.end()) {
+					if (missing)
+						return std::nullopt; // two gone: XOR cannot tell them apart
+					missing = seq;
+					continue;
+				}
+				length ^= (uint16_t)it->second.size();
+				XorInto(data, it->second);
+			}
+			if (!missing || length > data.size())
+				return std::nullopt; // nothing to do, or a parity frame that does not fit its run
+			data.resize(length);
+			return std::pair{ *missing, std::move(data) };
+		}
+
+	private:
+		std::map<uint32_t, std::vector<std::byte>, SequenceLess> recent;
+	};
+
+	// Loss on the wire, read off the gaps in sequence numbers: one reading,
+	// in permille, per fec_loss_window frames received.
+	struct LossMeter {
+		std::optional<uint32_t> Add(uint32_t seq)
+		{
+			if (received == 0) {
+				lowest = highest = seq;
+			}
+			else {
+				if (SequenceBefore(highest, seq))
+					highest = seq;
+				if (SequenceBefore(seq, lowest))
+					lowest = seq;
+			}
+			if (++received < fec_loss_window)
+				return std::nullopt;
+
+			uint32_t expected = highest - lowest + 1;
+			uint32_t permille = expected > received ? (uint32_t)((uint64_t)(expected - received) * 1000 / expected) : 0;
+			received = 0;
+			return permille;
+		}
+
+	private:
+		uint32_t lowest = 0;
+		uint32_t highest = 0;
+		uint32_t received = 0; // this window's; 0 = a new window
+	};
+}
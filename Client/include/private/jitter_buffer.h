@@ -0,0 +1,67 @@
+#pragma once
+
+#include <chrono>
+#include <cstddef>
+#include <cstdint>
+#include <map>
+#include <vector>
+
+// The receiving half of ordered p2p delivery: sequenced datagrams go in as
+// they arrive, and come out in order, each duplicate and latecomer dropped.
+// A gap is waited on for at most the delay, then skipped: the game gets
+// UDP's losses, never its reordering.
+namespace Weyvelength {
+	constexpr std::chrono::milliseconds default_jitter_delay{ 30 };
+	constexpr std::chrono::milliseconds max_jitter_delay{ 500 };
+	constexpr size_t max_jitter_held = 256; // per link; past it the oldest goes out, gap or not
+
+	// Serial number order (RFC 1982), so the count may wrap.
+	constexpr bool SequenceBefore(uint32_t a, uint32_t b)
+	{
+		return (int32_t)(a - b) < 0;
+	}
+
+	struct SequenceLess { // for maps keyed by sequence number
+		bool operator()(uint32_t a, uint32_t b) const { return SequenceBefore(a, b); }
+	};
+
+	struct JitterBuffer {
+		using Clock = std::chrono::steady_clock;
+
+		// False, and dropped, if it is a duplicate or its turn has passed.
+		bool Push(uint32_t seq, std::vector<std::byte> payload, Clock::time_point now)
+		{
+			if (started && SequenceBefore(seq, next))
+				return false;
+			return held.try_emplace(seq, Held{ std::move(payload), now }).second;
+		}
+
+		// Appends whatever is due to out, in order: the next in line at once,
+		// anything after a gap once it has waited delay.
+		void Pop(Clock::time_point now, std::chrono::milliseconds delay, std::vector<std::vector<std::byte>>& out)
+		{
+			while (!held.empty()) {
+				auto first = held.begin();
+				bool due = (started && first->first == next) || now - first->second.arrived >= delay || held.size() > max_jitter_held;
+				if (!due)
+					return;
+				out.push_back(std::move(first->second.payload));
+				started = true;
+				next = first->first + 1;
+				held.erase(first);
+			}
+		}
+
+		bool Empty() const { return held.empty(); }
+
+	private:
+		struct Held {
+			std::vector<std::byte> payload;
+			Clock::time_point arrived;
+		};
+
+		bool started = false; // until the first release any number may come first
+		uint32_t next = 0; // the one we wait on
+		std::map<uint32_t, Held, SequenceLess> held;
+	};
+}
@@ -1,10 +1,13 @@
 #pragma once
 
+#include <algorithm>
 #include <cstdint>
+#include <cstdlib>
 #include <iterator>
 #include <map>
 #include <string>
 
+#include "client.h"
 #include "protocol.h"
 #include "weyvelength.h"
 
@@ -28,6 +31,125 @@ namespace Weyvelength::Marshal {
 		return value->data();
 	}
 
+	inline void FillScheduledRoom(const Proto::ScheduledRoom& room, WeyveScheduledRoom* out) // the title borrows room's storage
+	{
+		out->id = room.id;
+		out->owner = room.owner;
+		out->start = room.start;
+		out->title = room.title.data();
+		out->title_len = (uint32_t)room.title.size();
+		out->max_players = room.max_players;
+		out->max_spectators = room.max_spectators;
+		out->passworded = room.passworded;
+		out->going = room.going;
+	}
+
+	inline void FillTimelineEntry(const Proto::TimelineEntry& entry, WeyveTimelineEntry* out) // the name borrows entry's storage
+	{
+		out->seq = entry.seq;
+		out->time = entry.time;
+		out->kind = (WeyveTimelineKind)entry.kind;
+		out->id = entry.id;
+		out->name = entry.name.data();
+		out->name_len = (uint32_t)entry.name.size();
+		out->by = entry.by;
+	}
+
+	inline void FillJoinReceipt(const Proto::JoinReceipt& receipt, WeyveJoinReceipt* out) // borrows receipt's storage
+	{
+		out->room = receipt.room.data();
+		out->room_len = (uint32_t)receipt.room.size();
+		out->id = receipt.id;
+		out->name = receipt.name.data();
+		out->name_len = (uint32_t)receipt.name.size();
+		out->role = (WeyveReceiptRole)receipt.role;
+		out->kind = (WeyveReceiptKind)receipt.kind;
+		out->time = receipt.time;
+		out->signature = receipt.signature.data();
+		out->signature_len = (uint32_t)receipt.signature.size();
+	}
+
+	inline void FillTeam(const Proto::Team& team, WeyveTeam* out) // borrows team's storage
+	{
+		out->id = team.id;
+		out->name = team.name.data();
+		out->name_len = (uint32_t)team.name.size();
+		out->max_size = team.max_size;
+		out->members = team.members.data();
+		out->member_count = (uint32_t)team.members.size();
+	}
+
+	inline void FillChannel(const Proto::Channel& channel, WeyveChannel* out) // borrows channel's storage
+	{
+		out->id = channel.id;
+		out->name = channel.name.data();
+		out->name_len = (uint32_t)channel.name.size();
+		out->members = channel.members.data();
+		out->member_count = (uint32_t)channel.members.size();
+	}
+
+	inline void FillLinkPath(const LinkPath& path, WeyveLinkPath* out) // strings borrow path's storage
+	{
+		out->local_address = path.local.address.data();
+		out->local_address_len = (uint32_t)path.local.address.size();
+		out->local_port = path.local.port;
+		out->local_type = (WeyveCandidateType)path.local.type;
+		out->remote_address = path.remote.address.data();
+		out->remote_address_len = (uint32_t)path.remote.address.size();
+		out->remote_port = path.remote.port;
+		out->remote_type = (WeyveCandidateType)path.remote.type;
+		out->interface_name = path.interface_name.data();
+		out->interface_name_len = (uint32_t)path.interface_name.size();
+	}
+
+	inline void FillNetworkInterface(const NetworkInterface& iface, WeyveNetworkInterface* out)
+	{
+		out->name = iface.name.data();
+		out->name_len = (uint32_t)iface.name.size();
+		out->address = iface.address.data();
+		out->address_len = (uint32_t)iface.address.size();
+	}
+
+	inline void FillLogEntry(const LogEntry& entry, WeyveLogEntry* out)
+	{
+		out->time = entry.time;
+		out->level = (WeyveLogLevel)entry.level;
+		out->text = entry.text.data();
+		out->text_len = (uint32_t)entry.text.size();
+	}
+
+	inline void FillMemberHealth(const Proto::MemberHealth& health, WeyveMemberHealth* out)
+	{
+		out->from = health.from;
+		out->server_rtt = health.server_rtt;
+		out->link_count = (uint32_t)health.links.size();
+	}
+
+	inline void FillLinkHealth(const Proto::LinkHealth& link, WeyveLinkHealth* out)
+	{
+		out->peer = link.peer;
+		out->direct = link.direct;
+		out->relayed = link.relayed;
+		out->loss_permille = link.loss_permille;
+		out->send_rate = link.send_rate;
+		out->receive_rate = link.receive_rate;
+	}
+
+	inline void FillChatLine(const ChatLine& line, WeyveChatLine* out) // the strings borrow line's storage
+	{
+		out->time = line.time;
+		out->room = line.room.data();
+		out->room_len = (uint32_t)line.room.size();
+		out->from = line.from;
+		out->name = line.name.data();
+		out->name_len = (uint32_t)line.name.size();
+		out->to = line.to;
+		out->to_name = line.to_name.data();
+		out->to_name_len = (uint32_t)line.to_name.size();
+		out->text = line.text.data();
+		out->text_len = (uint32_t)line.text.size();
+	}
+
 	inline const char* KeyAt(const std::map<std::string, std::string>& data, uint32_t index, uint32_t* key_len)
 	{
 		if (index >= data.size()) {
@@ -105,9 +227,811 @@ namespace Weyvelength::Marshal {
 			out->data.room_access.open = access->open;
 			out->data.room_access.passworded = access->passworded;
 		}
+		else if (auto* role = std::get_if<Proto::MemberRoleChanged>(&msg)) {
+			out->type = WEYVE_EVENT_MEMBER_ROLE_CHANGED;
+			out->data.member_role.id = role->id;
+			out->data.member_role.role = (WeyveMemberRole)role->role;
+		}
+		else if (auto* whisper = std::get_if<Proto::RoomWhisper>(&msg)) {
+			out->type = WEYVE_EVENT_WHISPER;
+			out->data.whisper.from = whisper->id;
+			out->data.whisper.text = whisper->text.data();
+			out->data.whisper.text_len = (uint32_t)whisper->text.size();
+		}
+		else if (auto* prefs = std::get_if<Proto::PreferencesChanged>(&msg)) {
+			out->type = WEYVE_EVENT_PREFERENCES_CHANGED;
+			out->data.preferences.dnd_until = prefs->prefs.dnd_until;
+			out->data.preferences.whispers = (WeyveWhisperPolicy)prefs->prefs.whispers;
+		}
+		else if (auto* spectator = std::get_if<Proto::MemberSpectating>(&msg)) {
+			out->type = WEYVE_EVENT_MEMBER_SPECTATING;
+			out->data.member_spectating.id = spectator->id;
+		}
+		else if (auto* capacity = std::get_if<Proto::RoomCapacityChanged>(&msg)) {
+			out->type = WEYVE_EVENT_ROOM_CAPACITY_CHANGED;
+			out->data.room_capacity.max_players = capacity->max_players;
+			out->data.room_capacity.max_spectators = capacity->max_spectators;
+		}
+		else if (auto* idle = std::get_if<Proto::IdlePolicyChanged>(&msg)) {
+			out->type = WEYVE_EVENT_IDLE_POLICY_CHANGED;
+			out->data.idle_policy.afk_after = idle->afk_after;
+			out->data.idle_policy.spectate = idle->spectate;
+		}
+		else if (auto* banner = std::get_if<Proto::RoomBannerChanged>(&msg)) {
+			out->type = WEYVE_EVENT_ROOM_BANNER_CHANGED;
+			out->data.room_banner.id = banner->id.data();
+			out->data.room_banner.id_len = (uint32_t)banner->id.size();
+		}
+		else if (auto* countdown = std::get_if<Proto::CountdownStarted>(&msg)) {
+			out->type = WEYVE_EVENT_COUNTDOWN;
+			out->data.countdown.id = countdown->id;
+			out->data.countdown.seconds = countdown->seconds;
+			out->data.countdown.by = countdown->by;
+		}
+		else if (auto* paused = std::get_if<Proto::MatchPausedChanged>(&msg)) {
+			out->type = WEYVE_EVENT_MATCH_PAUSED;
+			out->data.match_paused.paused = paused->paused;
+			out->data.match_paused.by = paused->by;
+		}
+		else if (auto* health = std::get_if<Proto::MemberHealth>(&msg)) {
+			out->type = WEYVE_EVENT_MEMBER_HEALTH;
+			FillMemberHealth(*health, &out->data.member_health);
+		}
+		else if (auto* session = std::get_if<Proto::SessionHealth>(&msg)) {
+			out->type = WEYVE_EVENT_SESSION_HEALTH;
+			out->data.session_health.count = (uint32_t)session->reports.size();
+		}
+		else if (auto* handoff = std::get_if<Proto::HostHandoff>(&msg)) {
+			out->type = WEYVE_EVENT_HOST_HANDOFF;
+			out->data.host_handoff.from = handoff->from;
+			out->data.host_handoff.reason = (WeyveHandoffReason)handoff->reason;
+			out->data.host_handoff.saved = handoff->saved;
+			out->data.host_handoff.state = (const uint8_t*)handoff->state.data();
+			out->data.host_handoff.state_len = (uint32_t)handoff->state.size();
+		}
+		else if (auto* wanted = std::get_if<Proto::HostStateWanted>(&msg)) {
+			out->type = WEYVE_EVENT_HOST_STATE_WANTED;
+			out->data.host_state_wanted.host = wanted->host;
+		}
+		else if (auto* view = std::get_if<Proto::MemberHostState>(&msg)) {
+			out->type = WEYVE_EVENT_MEMBER_HOST_STATE;
+			out->data.member_host_state.from = view->from;
+			out->data.member_host_state.state = (const uint8_t*)view->state.data();
+			out->data.member_host_state.state_len = (uint32_t)view->state.size();
+		}
+		else if (auto* succession = std::get_if<Proto::SuccessionChanged>(&msg)) {
+			out->type = WEYVE_EVENT_SUCCESSION_CHANGED;
+			out->data.succession.policy = (WeyveSuccessionPolicy)succession->policy;
+			out->data.succession.successors = succession->successors.data();
+			out->data.succession.count = (uint32_t)succession->successors.size();
+		}
+		else if (auto* split = std::get_if<Proto::RoomSplit>(&msg)) {
+			out->type = WEYVE_EVENT_ROOM_SPLIT;
+			out->data.room_split.id = split->id.data();
+			out->data.room_split.id_len = (uint32_t)split->id.size();
+			out->data.room_split.members = split->members;
+		}
+		else if (auto* merge = std::get_if<Proto::MergeRequested>(&msg)) {
+			out->type = WEYVE_EVENT_MERGE_REQUESTED;
+			out->data.merge_requested.into = merge->into.data();
+			out->data.merge_requested.into_len = (uint32_t)merge->into.size();
+			out->data.merge_requested.by = merge->by;
+			out->data.merge_requested.members = merge->members;
+		}
+		else if (auto* answer = std::get_if<Proto::MergeAnswered>(&msg)) {
+			out->type = WEYVE_EVENT_MERGE_ANSWERED;
+			out->data.merge_answered.id = answer->id.data();
+			out->data.merge_answered.id_len = (uint32_t)answer->id.size();
+			out->data.merge_answered.accepted = answer->accepted;
+		}
+		else if (auto* moved = std::get_if<Proto::RoomMoved>(&msg)) {
+			out->type = WEYVE_EVENT_ROOM_MOVED;
+			out->data.room_moved.id = moved->id.data();
+			out->data.room_moved.id_len = (uint32_t)moved->id.size();
+			out->data.room_moved.by = moved->by;
+		}
+		else if (auto* channels = std::get_if<Proto::ChannelsChanged>(&msg)) {
+			out->type = WEYVE_EVENT_CHANNELS_CHANGED;
+			out->data.channels.count = (uint32_t)channels->channels.size();
+		}
+		else if (auto* chat = std::get_if<Proto::ChannelChat>(&msg)) {
+			out->type = WEYVE_EVENT_CHANNEL_CHAT;
+			out->data.channel_chat.from = chat->from;
+			out->data.channel_chat.text = chat->text.data();
+			out->data.channel_chat.text_len = (uint32_t)chat->text.size();
+		}
+		else if (auto* receipts = std::get_if<Proto::JoinReceipts>(&msg)) {
+			out->type = WEYVE_EVENT_JOIN_RECEIPTS;
+			out->data.join_receipts.key = receipts->key.data();
+			out->data.join_receipts.key_len = (uint32_t)receipts->key.size();
+			out->data.join_receipts.count = (uint32_t)receipts->receipts.size();
+		}
+		else if (auto* rules = std::get_if<Proto::RoomRulesChanged>(&msg)) {
+			out->type = WEYVE_EVENT_ROOM_RULES_CHANGED;
+			out->data.room_rules.version = rules->version;
+			out->data.room_rules.rules = rules->rules.data();
+			out->data.room_rules.rules_len = (uint32_t)rules->rules.size();
+		}
+		else if (auto* afk = std::get_if<Proto::MemberAfk>(&msg)) {
+			out->type = WEYVE_EVENT_MEMBER_AFK;
+			out->data.member_afk.id = afk->id;
+			out->data.member_afk.afk = afk->afk;
+		}
+		else if (auto* booking = std::get_if<Proto::ScheduledRoomChanged>(&msg)) {
+			out->type = WEYVE_EVENT_SCHEDULED_ROOM_CHANGED;
+			FillScheduledRoom(booking->room, &out->data.scheduled_room);
+		}
+		else if (auto* list = std::get_if<Proto::ScheduledRoomList>(&msg)) {
+			out->type = WEYVE_EVENT_SCHEDULED_ROOM_LIST;
+			out->data.scheduled_room_list.count = (uint32_t)list->rooms.size();
+		}
+		else if (auto* reminder = std::get_if<Proto::ScheduledRoomReminder>(&msg)) {
+			out->type = WEYVE_EVENT_SCHEDULED_ROOM_REMINDER;
+			out->data.scheduled_room_reminder.id = reminder->id;
+			out->data.scheduled_room_reminder.start = reminder->start;
+		}
+		else if (auto* opened = std::get_if<Proto::ScheduledRoomOpened>(&msg)) {
+			out->type = WEYVE_EVENT_SCHEDULED_ROOM_OPENED;
+			out->data.scheduled_room_opened.id = opened->id;
+			out->data.scheduled_room_opened.room = opened->room.data();
+			out->data.scheduled_room_opened.room_len = (uint32_t)opened->room.size();
+		}
+		else if (auto* attendees = std::get_if<Proto::ScheduledRoomAttendees>(&msg)) {
+			out->type = WEYVE_EVENT_SCHEDULED_ROOM_ATTENDEES;
+			out->data.scheduled_room_attendees.id = attendees->id;
+			out->data.scheduled_room_attendees.count = (uint32_t)attendees->attendees.size();
+		}
+		else if (auto* cancelled = std::get_if<Proto::ScheduledRoomCancelled>(&msg)) {
+			out->type = WEYVE_EVENT_SCHEDULED_ROOM_CANCELLED;
+			out->data.scheduled_room_cancelled.id = cancelled->id;
+		}
+		else if (auto* preview = std::get_if<Proto::RoomPreview>(&msg)) {
+			out->type = WEYVE_EVENT_ROOM_PREVIEW;
+			out->data.room_preview.id = preview->id.data();
+			out->data.room_preview.id_len = (uint32_t)preview->id.size();
+			out->data.room_preview.host = preview->host;
+			out->data.room_preview.open = preview->open;
+			out->data.room_preview.passworded = preview->passworded;
+			out->data.room_preview.players = preview->players;
+			out->data.room_preview.spectators = preview->spectators;
+			out->data.room_preview.max_players = preview->max_players;
+			out->data.room_preview.max_spectators = preview->max_spectators;
+			out->data.room_preview.banner = preview->banner.data();
+			out->data.room_preview.banner_len = (uint32_t)preview->banner.size();
+			out->data.room_preview.member_count = (uint32_t)preview->members.size();
+			out->data.room_preview.data_count = (uint32_t)preview->data.size();
+		}
+		else if (auto* knock = std::get_if<Proto::JoinRequested>(&msg)) {
+			out->type = WEYVE_EVENT_JOIN_REQUESTED;
+			out->data.join_requested.id = knock->id;
+			out->data.join_requested.name = knock->name.data();
+			out->data.join_requested.name_len = (uint32_t)knock->name.size();
+			out->data.join_requested.message = knock->message.data();
+			out->data.join_requested.message_len = (uint32_t)knock->message.size();
+		}
+		else if (auto* answered = std::get_if<Proto::JoinRequestAnswered>(&msg)) {
+			out->type = WEYVE_EVENT_JOIN_REQUEST_ANSWERED;
+			out->data.join_answered.room = answered->room.data();
+			out->data.join_answered.room_len = (uint32_t)answered->room.size();
+			out->data.join_answered.approved = answered->approved;
+		}
+		else if (auto* position = std::get_if<Proto::RoomQueuePosition>(&msg)) {
+			out->type = WEYVE_EVENT_QUEUE_POSITION;
+			out->data.queue_position.room = position->room.data();
+			out->data.queue_position.room_len = (uint32_t)position->room.size();
+			out->data.queue_position.position = position->position;
+		}
+		else if (auto* offer = std::get_if<Proto::SeatOffered>(&msg)) {
+			out->type = WEYVE_EVENT_SEAT_OFFERED;
+			out->data.seat_offered.room = offer->room.data();
+			out->data.seat_offered.room_len = (uint32_t)offer->room.size();
+			out->data.seat_offered.expires_in = offer->expires_in;
+		}
+		else if (auto* results = std::get_if<Proto::UserSearchResults>(&msg)) {
+			out->type = WEYVE_EVENT_USER_SEARCH_RESULTS;
+			out->data.user_search.query = results->query.data();
+			out->data.user_search.query_len = (uint32_t)results->query.size();
+			out->data.user_search.count = (uint32_t)results->users.size();
+		}
+		else if (auto* searchable = std::get_if<Proto::SearchableChanged>(&msg)) {
+			out->type = WEYVE_EVENT_SEARCHABLE_CHANGED;
+			out->data.searchable.searchable = searchable->searchable;
+		}
+		else if (std::get_if<Proto::MyData>(&msg)) {
+			out->type = WEYVE_EVENT_MY_DATA;
+		}
+		else if (auto* timeline = std::get_if<Proto::Timeline>(&msg)) {
+			out->type = WEYVE_EVENT_TIMELINE;
+			out->data.timeline.count = (uint32_t)timeline->entries.size();
+		}
+		else if (auto* added = std::get_if<Proto::TimelineAdded>(&msg)) {
+			out->type = WEYVE_EVENT_TIMELINE_ENTRY;
+			FillTimelineEntry(added->entry, &out->data.timeline_entry);
+		}
+		else if (auto* teams = std::get_if<Proto::TeamsChanged>(&msg)) {
+			out->type = WEYVE_EVENT_TEAMS_CHANGED;
+			out->data.teams.count = (uint32_t)teams->teams.size();
+		}
+		else if (auto* logged_in = std::get_if<Proto::LoggedIn>(&msg)) {
+			out->type = WEYVE_EVENT_LOGGED_IN;
+			out->data.logged_in.name = logged_in->name.data();
+			out->data.logged_in.name_len = (uint32_t)logged_in->name.size();
+		}
+		else if (auto* error = std::get_if<Proto::AccountError>(&msg)) {
+			out->type = WEYVE_EVENT_ACCOUNT_ERROR;
+			out->data.account_error.code = (WeyveAccountError)error->code;
+			out->data.account_error.context = error->context.data();
+			out->data.account_error.context_len = (uint32_t)error->context.size();
+		}
+		else if (auto* name = std::get_if<Proto::MemberNameChanged>(&msg)) {
+			out->type = WEYVE_EVENT_MEMBER_NAME_CHANGED;
+			out->data.member_name.id = name->id;
+			out->data.member_name.name = name->name.data();
+			out->data.member_name.name_len = (uint32_t)name->name.size();
+		}
+		else if (auto* card = std::get_if<Proto::ChatLinkPreview>(&msg)) {
+			out->type = WEYVE_EVENT_CHAT_LINK_PREVIEW;
+			WeyveLinkPreview& preview = out->data.link_preview;
+			preview.from = card->from;
+			preview.url = card->preview.url.data();
+			preview.url_len = (uint32_t)card->preview.url.size();
+			preview.title = card->preview.title.data();
+			preview.title_len = (uint32_t)card->preview.title.size();
+			preview.description = card->preview.description.data();
+			preview.description_len = (uint32_t)card->preview.description.size();
+			preview.image = card->preview.image.data();
+			preview.image_len = (uint32_t)card->preview.image.size();
+			preview.site = card->preview.site.data();
+			preview.site_len = (uint32_t)card->preview.site.size();
+		}
+		else if (auto* uploaded = std::get_if<Proto::BlobUploaded>(&msg)) {
+			out->type = WEYVE_EVENT_BLOB_UPLOADED;
+			out->data.blob_uploaded.transfer = uploaded->transfer;
+			out->data.blob_uploaded.id = uploaded->id.data();
+			out->data.blob_uploaded.id_len = (uint32_t)uploaded->id.size();
+		}
+		else if (auto* downloaded = std::get_if<Proto::BlobDownloaded>(&msg)) {
+			out->type = WEYVE_EVENT_BLOB_DOWNLOADED;
+			out->data.blob_downloaded.transfer = downloaded->transfer;
+			out->data.blob_downloaded.id = downloaded->id.data();
+			out->data.blob_downloaded.id_len = (uint32_t)downloaded->id.size();
+		}
+		else if (auto* error = std::get_if<Proto::BlobError>(&msg)) {
+			out->type = WEYVE_EVENT_BLOB_ERROR;
+			out->data.blob_error.transfer = error->transfer;
+			out->data.blob_error.code = (WeyveBlobError)error->code;
+			out->data.blob_error.context = error->context.data();
+			out->data.blob_error.context_len = (uint32_t)error->context.size();
+		}
+		else if (auto* full = std::get_if<Proto::ServerFull>(&msg)) {
+			out->type = WEYVE_EVENT_SERVER_FULL;
+			out->data.server_full.limit = (WeyveServerLimit)full->limit;
+			out->data.server_full.retry_after = full->retry_after;
+		}
+		else if (auto* info = std::get_if<Proto::ServerInfo>(&msg)) {
+			out->type = WEYVE_EVENT_SERVER_INFO;
+			out->data.server_info.users = info->users;
+			out->data.server_info.max_users = info->max_users;
+			out->data.server_info.rooms = info->rooms;
+			out->data.server_info.max_rooms = info->max_rooms;
+		}
+		else if (auto* policy = std::get_if<Proto::ClientVersionPolicy>(&msg)) {
+			out->type = WEYVE_EVENT_CLIENT_VERSION_POLICY;
+			out->data.client_version.min_version = policy->min_app_version.data();
+			out->data.client_version.min_version_len = (uint32_t)policy->min_app_version.size();
+			out->data.client_version.update_url = policy->update_url.data();
+			out->data.client_version.update_url_len = (uint32_t)policy->update_url.size();
+		}
+		else if (auto* outdated = std::get_if<Proto::ClientOutdated>(&msg)) {
+			out->type = WEYVE_EVENT_CLIENT_OUTDATED;
+			out->data.client_version.min_version = outdated->min_app_version.data();
+			out->data.client_version.min_version_len = (uint32_t)outdated->min_app_version.size();
+			out->data.client_version.update_url = outdated->update_url.data();
+			out->data.client_version.update_url_len = (uint32_t)outdated->update_url.size();
+		}
 		else {
 			return false; // a client->server variant we never receive
 		}
 		return true;
 	}
+
+	inline WeyveScope EventScope(const WeyveEvent& e, uint32_t& id)
+	{
+		id = 0;
+		switch (e.type) {
+		case WEYVE_EVENT_WHISPER:
+			id = e.data.whisper.from;
+			return WEYVE_SCOPE_MEMBER;
+		case WEYVE_EVENT_MESSAGE_SEND_FAILED:
+			id = e.data.send_failed.to;
+			return id == 0 ? WEYVE_SCOPE_ROOM : WEYVE_SCOPE_MEMBER;
+		case WEYVE_EVENT_PEER_RETRYING:
+		case WEYVE_EVENT_PEER_UNREACHABLE:
+		case WEYVE_EVENT_PEER_CLOSED:
+			id = e.data.peer_link.id;
+			return WEYVE_SCOPE_MEMBER;
+		case WEYVE_EVENT_MEMBER_HEALTH:
+			id = e.data.member_health.from;
+			return WEYVE_SCOPE_MEMBER;
+		case WEYVE_EVENT_MEMBER_HOST_STATE:
+			id = e.data.member_host_state.from;
+			return WEYVE_SCOPE_MEMBER;
+		case WEYVE_EVENT_ROOM_ID_ASSIGNED:
+		case WEYVE_EVENT_ROOM_ERROR:
+		case WEYVE_EVENT_CHAT:
+		case WEYVE_EVENT_PEER_JOINED:
+		case WEYVE_EVENT_PEER_LEFT:
+		case WEYVE_EVENT_HOST_CHANGED:
+		case WEYVE_EVENT_ROOM_DATA_CHANGED:
+		case WEYVE_EVENT_MEMBER_DATA_CHANGED:
+		case WEYVE_EVENT_KICKED:
+		case WEYVE_EVENT_BANNED:
+		case WEYVE_EVENT_ROOM_ACCESS_CHANGED:
+		case WEYVE_EVENT_MEMBER_ROLE_CHANGED:
+		case WEYVE_EVENT_MEMBER_SPECTATING:
+		case WEYVE_EVENT_ROOM_CAPACITY_CHANGED:
+		case WEYVE_EVENT_IDLE_POLICY_CHANGED:
+		case WEYVE_EVENT_SUCCESSION_CHANGED:
+		case WEYVE_EVENT_ROOM_SPLIT:
+		case WEYVE_EVENT_MERGE_REQUESTED:
+		case WEYVE_EVENT_MERGE_ANSWERED:
+		case WEYVE_EVENT_ROOM_MOVED:
+		case WEYVE_EVENT_CHANNELS_CHANGED:
+		case WEYVE_EVENT_CHANNEL_CHAT:
+		case WEYVE_EVENT_MEMBER_AFK:
+		case WEYVE_EVENT_MEMBER_NAME_CHANGED:
+		case WEYVE_EVENT_CHAT_LINK_PREVIEW:
+		case WEYVE_EVENT_ROOM_BANNER_CHANGED:
+		case WEYVE_EVENT_JOIN_REQUESTED:
+		case WEYVE_EVENT_TIMELINE:
+		case WEYVE_EVENT_TIMELINE_ENTRY:
+		case WEYVE_EVENT_TEAMS_CHANGED:
+		case WEYVE_EVENT_ROOM_RULES_CHANGED:
+		case WEYVE_EVENT_COUNTDOWN:
+		case WEYVE_EVENT_COUNTDOWN_TICK:
+		case WEYVE_EVENT_MATCH_START:
+		case WEYVE_EVENT_MATCH_PAUSED:
+		case WEYVE_EVENT_SESSION_HEALTH:
+		case WEYVE_EVENT_JOIN_RECEIPTS:
+		case WEYVE_EVENT_HOST_HANDOFF:
+		case WEYVE_EVENT_HOST_STATE_WANTED:
+			return WEYVE_SCOPE_ROOM;
+		default:
+			return WEYVE_SCOPE_CLIENT;
+		}
+	}
+
+	// The event's name for bindings that dispatch by string, e.g. a web view
+	// listening for "peer_joined": the enumerator without its prefix, in
+	// lower case, never changed once shipped. A new type gets its name here
+	// in the same change that adds it.
+	inline const char* EventName(WeyveEventType type)
+	{
+		switch (type) {
+		case WEYVE_EVENT_HEARTBEAT: return "heartbeat";
+		case WEYVE_EVENT_ROOM_ID_ASSIGNED: return "room_id_assigned";
+		case WEYVE_EVENT_ROOM_ERROR: return "room_error";
+		case WEYVE_EVENT_CHAT: return "chat";
+		case WEYVE_EVENT_PEER_JOINED: return "peer_joined";
+		case WEYVE_EVENT_PEER_LEFT: return "peer_left";
+		case WEYVE_EVENT_HOST_CHANGED: return "host_changed";
+		case WEYVE_EVENT_ROOM_DATA_CHANGED: return "room_data_changed";
+		case WEYVE_EVENT_MEMBER_DATA_CHANGED: return "member_data_changed";
+		case WEYVE_EVENT_KICKED: return "kicked";
+		case WEYVE_EVENT_BANNED: return "banned";
+		case WEYVE_EVENT_ROOM_ACCESS_CHANGED: return "room_access_changed";
+		case WEYVE_EVENT_MEMBER_ROLE_CHANGED: return "member_role_changed";
+		case WEYVE_EVENT_WHISPER: return "whisper";
+		case WEYVE_EVENT_PREFERENCES_CHANGED: return "preferences_changed";
+		case WEYVE_EVENT_MEMBER_SPECTATING: return "member_spectating";
+		case WEYVE_EVENT_ROOM_CAPACITY_CHANGED: return "room_capacity_changed";
+		case WEYVE_EVENT_IDLE_POLICY_CHANGED: return "idle_policy_changed";
+		case WEYVE_EVENT_MEMBER_AFK: return "member_afk";
+		case WEYVE_EVENT_SCHEDULED_ROOM_CHANGED: return "scheduled_room_changed";
+		case WEYVE_EVENT_SCHEDULED_ROOM_LIST: return "scheduled_room_list";
+		case WEYVE_EVENT_SCHEDULED_ROOM_REMINDER: return "scheduled_room_reminder";
+		case WEYVE_EVENT_SCHEDULED_ROOM_OPENED: return "scheduled_room_opened";
+		case WEYVE_EVENT_SCHEDULED_ROOM_ATTENDEES: return "scheduled_room_attendees";
+		case WEYVE_EVENT_LOGGED_IN: return "logged_in";
+		case WEYVE_EVENT_ACCOUNT_ERROR: return "account_error";
+		case WEYVE_EVENT_MEMBER_NAME_CHANGED: return "member_name_changed";
+		case WEYVE_EVENT_MESSAGE_SEND_FAILED: return "message_send_failed";
+		case WEYVE_EVENT_CHAT_LINK_PREVIEW: return "chat_link_preview";
+		case WEYVE_EVENT_BLOB_UPLOADED: return "blob_uploaded";
+		case WEYVE_EVENT_BLOB_DOWNLOADED: return "blob_downloaded";
+		case WEYVE_EVENT_BLOB_ERROR: return "blob_error";
+		case WEYVE_EVENT_ROOM_BANNER_CHANGED: return "room_banner_changed";
+		case WEYVE_EVENT_PEER_RETRYING: return "peer_retrying";
+		case WEYVE_EVENT_PEER_UNREACHABLE: return "peer_unreachable";
+		case WEYVE_EVENT_PEER_CLOSED: return "peer_closed";
+		case WEYVE_EVENT_SERVER_FULL: return "server_full";
+		case WEYVE_EVENT_SERVER_INFO: return "server_info";
+		case WEYVE_EVENT_REQUEST_TIMED_OUT: return "request_timed_out";
+		case WEYVE_EVENT_ROOM_PREVIEW: return "room_preview";
+		case WEYVE_EVENT_JOIN_REQUESTED: return "join_requested";
+		case WEYVE_EVENT_JOIN_REQUEST_ANSWERED: return "join_request_answered";
+		case WEYVE_EVENT_QUEUE_POSITION: return "queue_position";
+		case WEYVE_EVENT_SEAT_OFFERED: return "seat_offered";
+		case WEYVE_EVENT_USER_SEARCH_RESULTS: return "user_search_results";
+		case WEYVE_EVENT_SEARCHABLE_CHANGED: return "searchable_changed";
+		case WEYVE_EVENT_MY_DATA: return "my_data";
+		case WEYVE_EVENT_JOIN_CHECKED: return "join_checked";
+		case WEYVE_EVENT_TIMELINE: return "timeline";
+		case WEYVE_EVENT_TIMELINE_ENTRY: return "timeline_entry";
+		case WEYVE_EVENT_TEAMS_CHANGED: return "teams_changed";
+		case WEYVE_EVENT_ROOM_RULES_CHANGED: return "room_rules_changed";
+		case WEYVE_EVENT_COUNTDOWN: return "countdown";
+		case WEYVE_EVENT_COUNTDOWN_TICK: return "countdown_tick";
+		case WEYVE_EVENT_MATCH_START: return "match_start";
+		case WEYVE_EVENT_MATCH_PAUSED: return "match_paused";
+		case WEYVE_EVENT_GAME_SERVER_OUTPUT: return "game_server_output";
+		case WEYVE_EVENT_GAME_SERVER_EXITED: return "game_server_exited";
+		case WEYVE_EVENT_PORT_MAPPING: return "port_mapping";
+		case WEYVE_EVENT_MEMBER_HEALTH: return "member_health";
+		case WEYVE_EVENT_SESSION_HEALTH: return "session_health";
+		case WEYVE_EVENT_CLIENT_VERSION_POLICY: return "client_version_policy";
+		case WEYVE_EVENT_CLIENT_OUTDATED: return "client_outdated";
+		case WEYVE_EVENT_JOIN_RECEIPTS: return "join_receipts";
+		case WEYVE_EVENT_HOST_HANDOFF: return "host_handoff";
+		case WEYVE_EVENT_HOST_STATE_WANTED: return "host_state_wanted";
+		case WEYVE_EVENT_MEMBER_HOST_STATE: return "member_host_state";
+		case WEYVE_EVENT_SUCCESSION_CHANGED: return "succession_changed";
+		case WEYVE_EVENT_ROOM_SPLIT: return "room_split";
+		case WEYVE_EVENT_MERGE_REQUESTED: return "merge_requested";
+		case WEYVE_EVENT_MERGE_ANSWERED: return "merge_answered";
+		case WEYVE_EVENT_ROOM_MOVED: return "room_moved";
+		case WEYVE_EVENT_CHANNELS_CHANGED: return "channels_changed";
+		case WEYVE_EVENT_CHANNEL_CHAT: return "channel_chat";
+		case WEYVE_EVENT_SCHEDULED_ROOM_CANCELLED: return "scheduled_room_cancelled";
+		case WEYVE_EVENT_NONE: break;
+		}
+		return nullptr;
+	}
+
+	// What an error code says, for people and for translation tables: key
+	// never changes once shipped, param names what the event's context holds
+	// (null when the server sends none) so a translated line can place it as
+	// {param}.
+	struct ErrorText {
+		const char* key;
+		const char* param;
+		const char* message;
+	};
+
+	inline ErrorText RoomErrorText(WeyveRoomError code)
+	{
+		switch (code) {
+		case WEYVE_ROOM_ERROR_ALREADY_IN_ROOM: return { "room.already_in_room", "room", "You are already in a room." };
+		case WEYVE_ROOM_ERROR_NO_SUCH_ROOM: return { "room.no_such_room", "room", "There is no room with that code." };
+		case WEYVE_ROOM_ERROR_NOT_IN_ROOM: return { "room.not_in_room", nullptr, "You are not in a room." };
+		case WEYVE_ROOM_ERROR_NOT_HOST: return { "room.not_host", "member", "Only the host can do that." };
+		case WEYVE_ROOM_ERROR_BAD_ROOM_DATA: return { "room.bad_data", "key", "That data is too large, or there are too many keys." };
+		case WEYVE_ROOM_ERROR_NO_SUCH_MEMBER: return { "room.no_such_member", "member", "That member is not in the room." };
+		case WEYVE_ROOM_ERROR_ROOM_CLOSED: return { "room.closed", "room", "The room is not taking new members." };
+		case WEYVE_ROOM_ERROR_BAD_PASSWORD: return { "room.bad_password", "room", "Wrong room password." };
+		case WEYVE_ROOM_ERROR_BANNED: return { "room.banned", "room", "You are banned from this room." };
+		case WEYVE_ROOM_ERROR_WHISPER_BLOCKED: return { "room.whisper_blocked", "member", "They are not taking whispers from you right now." };
+		case WEYVE_ROOM_ERROR_ROOM_FULL: return { "room.full", "seat", "The room is full." };
+		case WEYVE_ROOM_ERROR_NO_SUCH_SCHEDULE: return { "room.no_such_schedule", "schedule", "There is no such booking, or it has already opened." };
+		case WEYVE_ROOM_ERROR_BAD_SCHEDULE: return { "room.bad_schedule", "field", "That booking cannot be made." };
+		case WEYVE_ROOM_ERROR_CHAT_TOO_LONG: return { "room.chat_too_long", "max_chars", "That message is too long." };
+		case WEYVE_ROOM_ERROR_BAD_CHAT: return { "room.bad_chat", "reason", "That message cannot be sent." };
+		case WEYVE_ROOM_ERROR_BAD_BANNER: return { "room.bad_banner", "blob", "That banner is not on the server." };
+		case WEYVE_ROOM_ERROR_NO_SUCH_TEAM: return { "room.no_such_team", "team", "There is no such team." };
+		case WEYVE_ROOM_ERROR_TEAM_FULL: return { "room.team_full", "team", "That team is full." };
+		case WEYVE_ROOM_ERROR_BAD_TEAM: return { "room.bad_team", "reason", "That team change cannot be made." };
+		case WEYVE_ROOM_ERROR_BAD_COUNTDOWN: return { "room.bad_countdown", "max_seconds", "That countdown is too long." };
+		case WEYVE_ROOM_ERROR_NO_SUCH_CHANNEL: return { "room.no_such_channel", "channel", "There is no such channel." };
+		case WEYVE_ROOM_ERROR_BAD_CHANNEL: return { "room.bad_channel", "reason", "That channel cannot be made." };
+		case WEYVE_ROOM_ERROR_BAD_ROLE: return { "room.bad_role", "role", "That role does not exist." };
+		case WEYVE_ROOM_ERROR_BAD_SUCCESSION: return { "room.bad_succession", "policy", "That succession policy does not exist." };
+		}
+		return { "room.unknown", nullptr, "The room request failed." };
+	}
+
+	inline ErrorText AccountErrorText(WeyveAccountError code)
+	{
+		switch (code) {
+		case WEYVE_ACCOUNT_ERROR_DISABLED: return { "account.disabled", nullptr, "This server has no accounts." };
+		case WEYVE_ACCOUNT_ERROR_ALREADY_LOGGED_IN: return { "account.already_logged_in", "name", "You are already logged in." };
+		case WEYVE_ACCOUNT_ERROR_BAD_NAME: return { "account.bad_name", "name", "Names are 3 to 24 letters, digits, _ or -." };
+		case WEYVE_ACCOUNT_ERROR_NAME_TAKEN: return { "account.name_taken", "name", "That name is taken." };
+		case WEYVE_ACCOUNT_ERROR_BAD_PASSWORD: return { "account.bad_password", nullptr, "Passwords are 8 to 128 characters." };
+		case WEYVE_ACCOUNT_ERROR_BAD_EMAIL: return { "account.bad_email", nullptr, "That email address is not valid." };
+		case WEYVE_ACCOUNT_ERROR_WRONG_CREDENTIALS: return { "account.wrong_credentials", nullptr, "Wrong name or password." };
+		case WEYVE_ACCOUNT_ERROR_IN_USE: return { "account.in_use", "name", "That account is logged in elsewhere." };
+		case WEYVE_ACCOUNT_ERROR_STORE_FAILED: return { "account.store_failed", "name", "The server could not save the account." };
+		case WEYVE_ACCOUNT_ERROR_TOO_SOON: return { "account.too_soon", "seconds", "Wait a minute before exporting your data again." };
+		case WEYVE_ACCOUNT_ERROR_BAD_PREFERENCES: return { "account.bad_preferences", "whispers", "That whisper setting does not exist." };
+		}
+		return { "account.unknown", nullptr, "The account request failed." };
+	}
+
+	inline ErrorText BlobErrorText(WeyveBlobError code)
+	{
+		switch (code) {
+		case WEYVE_BLOB_ERROR_DISABLED: return { "blob.disabled", nullptr, "This server does not store files." };
+		case WEYVE_BLOB_ERROR_TOO_LARGE: return { "blob.too_large", "max_bytes", "That file is too large." };
+		case WEYVE_BLOB_ERROR_BUSY: return { "blob.busy", nullptr, "Too many uploads at once." };
+		case WEYVE_BLOB_ERROR_BAD_TRANSFER: return { "blob.bad_transfer", nullptr, "The transfer went wrong." };
+		case WEYVE_BLOB_ERROR_NO_SUCH_BLOB: return { "blob.no_such_blob", "blob", "That file is not on the server." };
+		case WEYVE_BLOB_ERROR_STORE_FAILED: return { "blob.store_failed", nullptr, "The server could not save the file." };
+		case WEYVE_BLOB_ERROR_QUOTA: return { "blob.quota", "reason", "No room for more uploads right now." };
+		}
+		return { "blob.unknown", nullptr, "The transfer failed." };
+	}
+
+	inline ErrorText ServerFullText(WeyveServerLimit limit)
+	{
+		switch (limit) {
+		case WEYVE_LIMIT_USERS: return { "server.full.users", nullptr, "The server is full." };
+		case WEYVE_LIMIT_ROOMS: return { "server.full.rooms", nullptr, "The server cannot host more rooms right now." };
+		case WEYVE_LIMIT_PER_ADDRESS: return { "server.full.per_address", nullptr, "Too many connections from your address." };
+		case WEYVE_LIMIT_REQUESTS: return { "server.full.requests", nullptr, "Too many requests at once." };
+		}
+		return { "server.full", nullptr, "The server is full." };
+	}
+
+	inline ErrorText TimeoutText(WeyveRequest request)
+	{
+		return request == WEYVE_REQUEST_ACCOUNT
+			? ErrorText{ "timeout.account", nullptr, "The server did not answer the login." }
+			: ErrorText{ "timeout.room", nullptr, "The server did not answer the room request." };
+	}
+
+	// A data export as JSON, for the app to save or show; field names follow MyData.
+	inline std::string MyDataJson(const Proto::MyData& data)
+	{
+		using ChatExport::Json;
+		static constexpr const char* statuses[] = { "no", "going", "maybe" };
+		static constexpr const char* policies[] = { "everyone", "staff", "nobody" };
+		size_t policy = std::min<size_t>((size_t)data.prefs.whispers, std::size(policies) - 1);
+
+		std::string out = "{\n  \"id\": " + std::to_string(data.id) + ",\n  \"name\": " + Json(data.name)
+			+ ",\n  \"guest\": " + (data.guest ? "true" : "false") + ",\n  \"email\": " + Json(data.email)
+			+ ",\n  \"searchable\": " + (data.searchable ? "true" : "false")
+			+ ",\n  \"prefs\": { \"dnd_until\": " + std::to_string(data.prefs.dnd_until) + ", \"whispers\": " + Json(policies[policy]) + " }"
+			+ ",\n  \"room\": " + Json(data.room) + ",\n  \"queued\": " + Json(data.queued) + ",\n  \"bookings\": [";
+
+		for (size_t i = 0; i < data.bookings.size(); i++) {
+			const Proto::ScheduledRoom& room = data.bookings[i];
+			out += std::string(i ? ", " : "") + "{ \"id\": " + std::to_string(room.id) + ", \"start\": " + std::to_string(room.start)
+				+ ", \"title\": " + Json(room.title) + ", \"max_players\": " + std::to_string(room.max_players)
+				+ ", \"max_spectators\": " + std::to_string(room.max_spectators) + ", \"passworded\": " + (room.passworded ? "true" : "false") + " }";
+		}
+		out += "],\n  \"rsvps\": [";
+		for (size_t i = 0; i < data.rsvps.size(); i++) {
+			size_t status = std::min<size_t>((size_t)data.rsvps[i].status, std::size(statuses) - 1);
+			out += std::string(i ? ", " : "") + "{ \"booking\": " + std::to_string(data.rsvps[i].booking) + ", \"status\": " + Json(statuses[status]) + " }";
+		}
+		out += "],\n  \"knocks\": [";
+		for (size_t i = 0; i < data.knocks.size(); i++)
+			out += std::string(i ? ", " : "") + Json(data.knocks[i]);
+		return out + "]\n}\n";
+	}
+
+	// The one error view over every failure event; false for anything else.
+	inline bool FillError(const WeyveEvent& e, WeyveError* out)
+	{
+		WeyveError error{};
+		ErrorText text{};
+		switch (e.type) {
+		case WEYVE_EVENT_ROOM_ERROR: {
+			WeyveRoomError code = e.data.room_error.code;
+			text = RoomErrorText(code);
+			error = { WEYVE_ERROR_ROOM, code, code == WEYVE_ROOM_ERROR_ROOM_FULL || code == WEYVE_ROOM_ERROR_ROOM_CLOSED };
+			error.context = e.data.room_error.context, error.context_len = e.data.room_error.context_len;
+			break;
+		}
+		case WEYVE_EVENT_ACCOUNT_ERROR: {
+			WeyveAccountError code = e.data.account_error.code;
+			text = AccountErrorText(code);
+			error = { WEYVE_ERROR_ACCOUNT, code, code == WEYVE_ACCOUNT_ERROR_IN_USE || code == WEYVE_ACCOUNT_ERROR_STORE_FAILED || code == WEYVE_ACCOUNT_ERROR_TOO_SOON };
+			error.context = e.data.account_error.context, error.context_len = e.data.account_error.context_len;
+			if (code == WEYVE_ACCOUNT_ERROR_TOO_SOON)
+				error.retry_after = (uint32_t)std::strtoul(std::string(error.context, error.context_len).c_str(), nullptr, 10);
+			break;
+		}
+		case WEYVE_EVENT_BLOB_ERROR: {
+			WeyveBlobError code = e.data.blob_error.code;
+			text = BlobErrorText(code);
+			error = { WEYVE_ERROR_BLOB, code, code == WEYVE_BLOB_ERROR_BUSY || code == WEYVE_BLOB_ERROR_STORE_FAILED || code == WEYVE_BLOB_ERROR_QUOTA };
+			error.context = e.data.blob_error.context, error.context_len = e.data.blob_error.context_len;
+			break;
+		}
+		case WEYVE_EVENT_SERVER_FULL:
+			text = ServerFullText(e.data.server_full.limit);
+			error = { WEYVE_ERROR_SERVER_FULL, e.data.server_full.limit, true, e.data.server_full.retry_after };
+			break;
+		case WEYVE_EVENT_REQUEST_TIMED_OUT:
+			text = TimeoutText(e.data.request_timeout.request);
+			error = { WEYVE_ERROR_TIMEOUT, e.data.request_timeout.request, true };
+			break;
+		case WEYVE_EVENT_CLIENT_OUTDATED:
+			text = { "client.outdated", "min_version", "This server needs version {min_version} of the app or newer." };
+			error = { WEYVE_ERROR_CLIENT_OUTDATED, 0, false };
+			error.context = e.data.client_version.min_version, error.context_len = e.data.client_version.min_version_len;
+			break;
+		default:
+			return false;
+		}
+		error.key = text.key;
+		error.param = error.context_len ? text.param : nullptr;
+		error.message = text.message;
+		*out = error;
+		return true;
+	}
+
+	// The line to show for an error: text (a translation of error.key) if
+	// given, else the English message, with every {param} in it replaced by
+	// the context. Any other braces are left as they are.
+	inline std::string FormatError(const WeyveError& error, const char* text)
+	{
+		std::string line = text ? text : error.message;
+		if (!error.param)
+			return line;
+
+		std::string placeholder = std::string("{") + error.param + "}";
+		std::string context(error.context, error.context_len);
+		for (size_t at = line.find(placeholder); at != std::string::npos; at = line.find(placeholder, at + context.size()))
+			line.replace(at, placeholder.size(), context);
+		return line;
+	}
+
+	// Not server messages either: the mesh raises these as links fail.
+	inline void FillPeerEvent(const PeerEvent& ev, WeyveEvent* out)
+	{
+		switch (ev.kind) {
+		case PeerEvent::Kind::Retrying: out->type = WEYVE_EVENT_PEER_RETRYING; break;
+		case PeerEvent::Kind::Unreachable: out->type = WEYVE_EVENT_PEER_UNREACHABLE; break;
+		case PeerEvent::Kind::Closed: out->type = WEYVE_EVENT_PEER_CLOSED; break;
+		}
+		out->data.peer_link.id = ev.id;
+		out->data.peer_link.attempt = ev.attempt;
+		out->data.peer_link.retry_in = ev.retry_in;
+		out->data.peer_link.remote = ev.remote;
+	}
+
+	// Nor this: the client raises it when an answer is overdue.
+	inline void FillTimeout(RequestKind kind, WeyveEvent* out)
+	{
+		out->type = WEYVE_EVENT_REQUEST_TIMED_OUT;
+		out->data.request_timeout.request = (WeyveRequest)kind;
+	}
+
+	// Nor this: the client reads it off the answer to a PreflightJoin.
+	inline void FillJoinCheck(const JoinCheck& check, WeyveEvent* out)
+	{
+		out->type = WEYVE_EVENT_JOIN_CHECKED;
+		out->data.join_check.id = check.id.data();
+		out->data.join_check.id_len = (uint32_t)check.id.size();
+		out->data.join_check.spectating = check.spectating;
+		out->data.join_check.ready = !check.blocker;
+		out->data.join_check.blocker = (WeyveRoomError)check.blocker.value_or(Proto::RoomErrorCode{});
+		out->data.join_check.password = check.password;
+		out->data.join_check.relay = check.relay;
+	}
+
+	// Nor this: the client counts the server's countdown down itself.
+	inline void FillCountdownTick(const CountdownTick& tick, WeyveEvent* out)
+	{
+		out->type = tick.remaining == 0 ? WEYVE_EVENT_MATCH_START : WEYVE_EVENT_COUNTDOWN_TICK;
+		out->data.countdown_tick.id = tick.id;
+		out->data.countdown_tick.remaining = tick.remaining;
+	}
+
+	// Nor this: it comes from the local game server process.
+	inline void FillGameServerEvent(const ProcessEvent& ev, WeyveEvent* out) // the line borrows ev's storage
+	{
+		if (ev.kind == ProcessEvent::Kind::Exited) {
+			out->type = WEYVE_EVENT_GAME_SERVER_EXITED;
+			out->data.game_exited.exit_code = ev.exit_code;
+			return;
+		}
+		out->type = WEYVE_EVENT_GAME_SERVER_OUTPUT;
+		out->data.game_output.line = ev.line.data();
+		out->data.game_output.line_len = (uint32_t)ev.line.size();
+	}
+
+	// Nor this: the router's answer, from the port mapper's worker.
+	inline void FillPortMapping(const PortMapping& mapping, WeyveEvent* out) // strings borrow mapping's storage
+	{
+		out->type = WEYVE_EVENT_PORT_MAPPING;
+		out->data.port_mapping.mapped = mapping.mapped;
+		out->data.port_mapping.method = (WeyvePortMappingMethod)mapping.method;
+		out->data.port_mapping.internal_port = mapping.internal_port;
+		out->data.port_mapping.external_port = mapping.external_port;
+		out->data.port_mapping.external_address = mapping.external_address.data();
+		out->data.port_mapping.external_address_len = (uint32_t)mapping.external_address.size();
+		out->data.port_mapping.error = mapping.error.data();
+		out->data.port_mapping.error_len = (uint32_t)mapping.error.size();
+	}
+
+	// Not a server message: the client raises it itself when a send is held.
+	inline void FillSendFailed(const UnsentMessage& msg, WeyveEvent* out)
+	{
+		out->type = WEYVE_EVENT_MESSAGE_SEND_FAILED;
+		out->data.send_failed.id = msg.id;
+		out->data.send_failed.to = msg.to;
+		out->data.send_failed.text = msg.text.data();
+		out->data.send_failed.text_len = (uint32_t)msg.text.size();
+	}
+
+	// What the last event from NextEvent borrows its strings from, kept by
+	// the caller until the next pull.
+	struct EventScratch {
+		Proto::ServerMessage event;
+		UnsentMessage unsent;
+		JoinCheck join_check;
+		ProcessEvent game_event;
+		PortMapping port_mapping;
+		std::string room;
+	};
+
+	// The order weyve_next hands events out in, which apps may rely on: a
+	// held send first, as its send call raised it ahead of whatever the server
+	// said since; then the client's own events, link changes, timeouts, join
+	// checks, countdown ticks, the game server and the router, each oldest
+	// first; the server's messages last, in arrival order, those with no event
+	// skipped. Source is the Client, or a scripted stand-in in tests.
+	template <typename Source>
+	bool PullEvent(Source& source, EventScratch& scratch, WeyveEvent* out)
+	{
+		if (source.NextSendFailure(scratch.unsent)) {
+			FillSendFailed(scratch.unsent, out);
+			return true;
+		}
+
+		if (PeerEvent peer; source.NextPeerEvent(peer)) {
+			FillPeerEvent(peer, out);
+			return true;
+		}
+
+		if (RequestKind kind; source.NextTimeout(kind)) {
+			FillTimeout(kind, out);
+			return true;
+		}
+
+		if (source.NextJoinCheck(scratch.join_check)) {
+			FillJoinCheck(scratch.join_check, out);
+			return true;
+		}
+
+		if (CountdownTick tick; source.NextCountdownTick(tick)) {
+			FillCountdownTick(tick, out);
+			return true;
+		}
+
+		if (source.NextGameServerEvent(scratch.game_event)) {
+			FillGameServerEvent(scratch.game_event, out);
+			return true;
+		}
+
+		if (source.NextPortMapping(scratch.port_mapping)) {
+			FillPortMapping(scratch.port_mapping, out);
+			return true;
+		}
+
+		while (source.Next(scratch.event, scratch.room)) {
+			if (FillEvent(scratch.event, out))
+				return true;
+		}
+		return false;
+	}
+
+	// PullEvent, with the envelope filled in. The client's own events belong
+	// to the room we are in now; the server's to the one they arrived in.
+	template <typename Source>
+	bool NextEvent(Source& source, EventScratch& scratch, WeyveEvent* out)
+	{
+		scratch.room = source.RoomId();
+		if (!PullEvent(source, scratch, out))
+			return false;
+
+		out->scope = EventScope(*out, out->scope_id);
+		bool roomless = out->scope == WEYVE_SCOPE_CLIENT || scratch.room.empty();
+		out->room = roomless ? nullptr : scratch.room.data();
+		out->room_len = roomless ? 0 : (uint32_t)scratch.room.size();
+		return true;
+	}
 }
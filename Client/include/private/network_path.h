@@ -0,0 +1,70 @@
+#pragma once
+
+#include <cstdint>
+#include <optional>
+#include <sstream>
+#include <string>
+#include <string_view>
+#include <vector>
+
+// Which of the machine's networks a p2p link runs over. A VPN user picks
+// the interface ICE may gather on; afterwards they can see which one, and
+// which kind of candidate, a link ended up using.
+namespace Weyvelength {
+	struct NetworkInterface {
+		std::string name; // the OS's: "eth0", "Wi-Fi"; on Windows the friendly name
+		std::string address; // one of its unicast addresses, IPv4 or IPv6, as text
+	};
+
+	// Every up interface's addresses, one entry per address; loopback left out.
+	std::vector<NetworkInterface> ListNetworkInterfaces();
+
+	enum class CandidateType : uint8_t {
+		Host, // an interface's own address: LAN or VPN, no NAT in the way
+		ServerReflexive, // our NAT's public side, learned from STUN
+		PeerReflexive, // a NAT mapping learned from the peer's checks
+		Relayed, // through TURN
+	};
+
+	struct IceCandidate {
+		std::string address;
+		uint16_t port = 0;
+		CandidateType type{};
+	};
+
+	// One candidate line as libjuice writes it: "a=candidate:1 1 UDP
+	// 2122317823 192.168.1.2 50000 typ host", with or without the "a=".
+	inline std::optional<IceCandidate> ParseIceCandidate(std::string_view sdp)
+	{
+		if (sdp.substr(0, 2) == "a=")
+			sdp.remove_prefix(2);
+		if (sdp.substr(0, 10) != "candidate:")
+			return std::nullopt;
+
+		std::istringstream fields{ std::string(sdp.substr(10)) };
+		std::string foundation, component, transport, priority, typ, type;
+		uint32_t port = 0;
+		IceCandidate out;
+		if (!(fields >> foundation >> component >> transport >> priority >> out.address >> port >> typ >> type) || typ != "typ" || port == 0 || port > 65535)
+			return std::nullopt;
+		out.port = (uint16_t)port;
+
+		if (type == "host")
+			out.type = CandidateType::Host;
+		else if (type == "srflx")
+			out.type = CandidateType::ServerReflexive;
+		else if (type == "prflx")
+			out.type = CandidateType::PeerReflexive;
+		else if (type == "relay")
+			out.type = CandidateType::Relayed;
+		else
+			return std::nullopt;
+		return out;
+	}
+
+	struct LinkPath { // the candidate pair a connected link settled on
+		IceCandidate local;
+		IceCandidate remote;
+		std::string interface_name; // the interface carrying it; empty when it cannot be told, e.g. behind a relay
+	};
+}
@@ -1,22 +1,27 @@
 #pragma once
 
+#include <chrono>
 #include <cstddef>
 #include <cstdint>
 #include <deque>
 #include <map>
 #include <memory>
 #include <mutex>
+#include <random>
 #include <string>
 #include <vector>
 
 #include <juice/juice.h>
 
+#include "fec.h"
+#include "jitter_buffer.h"
+
 // Client-internal state behind the p2p mesh; keeps juice types out of the API.
 namespace Weyvelength {
 	struct JuiceEvent { // one callback crossing from juice's threads into Poll
 		enum class Kind { State, Candidate, GatheringDone, Recv };
 		Kind kind{};
-		juice_agent_t* agent = nullptr; // which agent queued it; a rebuilt link ignores strays
+		uint32_t generation = 0; // which link queued it; a rebuilt link ignores strays, even if juice reused the agent's address
 		uint32_t peer = 0;
 		juice_state_t state = JUICE_STATE_DISCONNECTED; // Kind::State only
 		std::vector<std::byte> payload; // candidate sdp or Kind::Recv datagram
@@ -25,14 +30,34 @@ namespace Weyvelength {
 	struct JuiceCallbackContext { // one agent's user_ptr; freed after juice_destroy
 		struct P2PMesh* mesh = nullptr;
 		uint32_t peer = 0;
+		uint32_t generation = 0;
 	};
 
 	struct PeerLink { // one lazily built direct connection
 		juice_agent_t* agent = nullptr;
 		std::unique_ptr<JuiceCallbackContext> ctx;
+		uint32_t generation = 0; // unique per link built, mesh-wide; never 0
+		juice_state_t state = JUICE_STATE_DISCONNECTED; // as last reported
+		std::chrono::steady_clock::time_point since; // when state last changed; a link stuck short of connected is swept
 		bool remote_set = false; // juice_set_remote_description may only run once
 		bool connected = false;
+		uint8_t framing = 0; // the peer's p2p framing version; 0 = raw, it never sent one
 		std::deque<std::vector<std::byte>> outbox; // datagrams queued until the link connects
+		uint32_t next_seq = 0; // our next Sequenced frame's number, while ordering is on
+		JitterBuffer jitter; // their Sequenced frames, waiting their turn
+		ParityBuilder parity; // parity on what we send, at the group the peer asked for
+		ParityRecovery recovery; // what they sent lately, to rebuild a lost frame from their parity
+		LossMeter loss; // gaps in what they send
+		uint32_t loss_permille = 0; // the last reading
+		uint8_t fec_asked = 0; // the group we last asked them for
+		bool fec_spoken = false; // we ever asked; from then on every reading says so again, in case one was lost
+		uint64_t bytes_sent = 0; // whole datagrams, since the last health report
+		uint64_t bytes_received = 0;
+	};
+
+	struct PeerRetry { // a failed link waiting out its backoff before we redial
+		std::chrono::steady_clock::time_point due;
+		std::deque<std::vector<std::byte>> outbox; // the failed link's queued datagrams, handed to the next one
 	};
 
 	struct P2PMesh {
@@ -41,5 +66,11 @@ namespace Weyvelength {
 		std::vector<JuiceEvent> scratch; // the drained batch; reused so capacity sticks
 		std::map<uint32_t, PeerLink> links;
 		std::map<uint32_t, uint32_t> attempts; // per-peer ICE tries; outlives links to cap retries
+		std::map<uint32_t, PeerRetry> retries; // never for a peer that also has a link
+		std::minstd_rand rng{ std::random_device{}() }; // retry jitter and simulated loss only
+		std::deque<std::pair<std::chrono::steady_clock::time_point, JuiceEvent>> delayed; // Recv events held back by SimulateNetwork, due oldest first
+		uint32_t next_generation = 1;
+		uint64_t signal_seq = 0; // the last sequence number we stamped a signal with
+		std::map<uint32_t, uint64_t> signal_seq_from; // the last each sender's signals carried; per server connection, as ids are
 	};
 }
@@ -0,0 +1,279 @@
+#pragma once
+
+#include <algorithm>
+#include <array>
+#include <cctype>
+#include <cstddef>
+#include <cstdint>
+#include <memory>
+#include <optional>
+#include <string>
+#include <string_view>
+#include <utility>
+#include <vector>
+
+// Opens the p2p port on the home router so peers can dial us directly
+// rather than through TURN. NAT-PMP first, it is one datagram to the
+// gateway; UPnP IGD second, found by SSDP and spoken to over SOAP. A worker
+// thread does the slow part; the parsing below is plain functions so it can
+// be checked without a router.
+namespace Weyvelength {
+	struct PortMapping {
+		enum class Method : uint8_t {
+			None, // nothing answered, or everything refused
+			NatPmp,
+			Upnp,
+		};
+		bool mapped = false;
+		Method method = Method::None;
+		uint16_t internal_port = 0;
+		uint16_t external_port = 0; // what peers dial; may differ from the one asked for
+		std::string external_address; // the router's public address; empty if it would not say
+		std::string error; // why not, when !mapped
+	};
+
+	constexpr uint16_t nat_pmp_port = 5351;
+	constexpr uint32_t port_mapping_lifetime = 3600; // seconds asked for; renewed at half
+
+	// NAT-PMP (RFC 6886), version 0, UDP only.
+	inline std::array<uint8_t, 12> NatPmpMapRequest(uint16_t internal_port, uint16_t external_port, uint32_t lifetime)
+	{
+		return { 0, 1, 0, 0,
+			(uint8_t)(internal_port >> 8), (uint8_t)internal_port,
+			(uint8_t)(external_port >> 8), (uint8_t)external_port,
+			(uint8_t)(lifetime >> 24), (uint8_t)(lifetime >> 16), (uint8_t)(lifetime >> 8), (uint8_t)lifetime };
+	}
+
+	struct NatPmpMapResult {
+		uint16_t result = 0; // 0 = success; see NatPmpResultText
+		uint16_t internal_port = 0;
+		uint16_t external_port = 0;
+		uint32_t lifetime = 0;
+	};
+
+	// Null unless it is a UDP map response at all; a refusal still parses.
+	inline std::optional<NatPmpMapResult> ParseNatPmpMapResponse(const uint8_t* data, size_t size)
+	{
+		if (size < 16 || data[0] != 0 || data[1] != 128 + 1)
+			return std::nullopt;
+		auto u16 = [&](size_t at) { return (uint16_t)(data[at] << 8 | data[at + 1]); };
+		return NatPmpMapResult{ u16(2), u16(8), u16(10), (uint32_t)u16(12) << 16 | u16(14) };
+	}
+
+	// The public address, if this is a successful address response.
+	inline std::optional<std::array<uint8_t, 4>> ParseNatPmpAddressResponse(const uint8_t* data, size_t size)
+	{
+		if (size < 12 || data[0] != 0 || data[1] != 128 || data[2] != 0 || data[3] != 0)
+			return std::nullopt;
+		return std::array<uint8_t, 4>{ data[8], data[9], data[10], data[11] };
+	}
+
+	inline std::string NatPmpResultText(uint16_t result)
+	{
+		switch (result) {
+		case 1: return "NAT-PMP: unsupported version";
+		case 2: return "NAT-PMP: refused by the gateway";
+		case 3: return "NAT-PMP: gateway has no public address";
+		case 4: return "NAT-PMP: gateway is out of mappings";
+		case 5: return "NAT-PMP: unsupported opcode";
+		default: return "NAT-PMP: result " + std::to_string(result);
+		}
+	}
+
+	// A header's value from an SSDP or HTTP head, the name matched without
+	// regard to case; empty if absent.
+	inline std::string HttpHeader(std::string_view head, std::string_view name)
+	{
+		for (size_t start = 0; start < head.size();) {
+			size_t end = head.find("\r\n", start);
+			std::string_view line = head.substr(start, end == std::string_view::npos ? std::string_view::npos : end - start);
+			size_t colon = line.find(':');
+			if (colon == name.size() && std::equal(name.begin(), name.end(), line.begin(),
+				[](char a, char b) { return std::tolower((unsigned char)a) == std::tolower((unsigned char)b); })) {
+				std::string_view value = line.substr(colon + 1);
+				while (!value.empty() && (value.front() == ' ' || value.front() == '\t'))
+					value.remove_prefix(1);
+				while (!value.empty() && (value.back() == ' ' || value.back() == '\t'))
+					value.remove_suffix(1);
+				return std::string(value);
+			}
+			if (end == std::string_view::npos)
+				break;
+			start = end + 2;
+		}
+		return {};
+	}
+
+	struct HttpUrl {
+		std::string host;
+		uint16_t port = 80;
+		std::string path = "/";
+	};
+
+	// Plain http:// only: that is all an IGD serves on the LAN.
+	inline std::optional<HttpUrl> ParseHttpUrl(std::string_view url)
+	{
+		constexpr std::string_view scheme = "http://";
+		if (url.substr(0, scheme.size()) != scheme)
+			return std::nullopt;
+		url.remove_prefix(scheme.size());
+
+		HttpUrl out;
+		size_t slash = url.find('/');
+		std::string_view authority = url.substr(0, slash);
+		if (slash != std::string_view::npos)
+			out.path = std::string(url.substr(slash));
+		size_t colon = authority.rfind(':');
+		if (colon != std::string_view::npos) {
+			uint32_t port = 0;
+			std::string_view digits = authority.substr(colon + 1);
+			for (char c : digits) {
+				if (c < '0' || c > '9' || (port = port * 10 + (c - '0')) > 65535)
+					return std::nullopt;
+			}
+			if (digits.empty() || port == 0)
+				return std::nullopt;
+			out.port = (uint16_t)port;
+			authority = authority.substr(0, colon);
+		}
+		if (authority.empty())
+			return std::nullopt;
+		out.host = std::string(authority);
+		return out;
+	}
+
+	struct HttpResponse {
+		uint32_t status = 0;
+		std::string body; // de-chunked
+	};
+
+	// A whole response as read up to the server closing; null if it is not
+	// one, or its chunking is broken.
+	inline std::optional<HttpResponse> ParseHttpResponse(std::string_view raw)
+	{
+		size_t head_end = raw.find("\r\n\r\n");
+		if (raw.substr(0, 5) != "HTTP/" || head_end == std::string_view::npos)
+			return std::nullopt;
+		std::string_view head = raw.substr(0, head_end + 2);
+		std::string_view body = raw.substr(head_end + 4);
+
+		HttpResponse out;
+		size_t space = head.find(' ');
+		for (size_t i = space + 1; space != std::string_view::npos && i < head.size() && head[i] >= '0' && head[i] <= '9'; i++)
+			out.status = out.status * 10 + (head[i] - '0');
+		if (out.status == 0)
+			return std::nullopt;
+
+		std::string encoding = HttpHeader(head, "Transfer-Encoding");
+		std::transform(encoding.begin(), encoding.end(), encoding.begin(), [](unsigned char c) { return (char)std::tolower(c); });
+		if (encoding != "chunked") {
+			out.body = std::string(body);
+			return out;
+		}
+
+		for (;;) {
+			size_t line_end = body.find("\r\n");
+			if (line_end == std::string_view::npos)
+				return std::nullopt;
+			size_t size = 0;
+			size_t digits = 0;
+			for (; digits < line_end && std::isxdigit((unsigned char)body[digits]); digits++) {
+				if (size > body.size())
+					return std::nullopt;
+				size = size * 16 + (size_t)(std::isdigit((unsigned char)body[digits]) ? body[digits] - '0' : std::tolower((unsigned char)body[digits]) - 'a' + 10);
+			}
+			if (digits == 0)
+				return std::nullopt;
+			body.remove_prefix(line_end + 2);
+			if (size == 0)
+				return out;
+			if (body.size() < size + 2)
+				return std::nullopt;
+			out.body.append(body.substr(0, size));
+			body.remove_prefix(size + 2);
+		}
+	}
+
+	// The text of the first <tag> element, namespace prefixes ignored
+	// (<u:NewExternalIPAddress> matches NewExternalIPAddress); empty if none.
+	inline std::string XmlValue(std::string_view xml, std::string_view tag)
+	{
+		for (size_t at = xml.find('<'); at != std::string_view::npos; at = xml.find('<', at + 1)) {
+			size_t close = xml.find('>', at);
+			if (close == std::string_view::npos)
+				return {};
+			std::string_view name = xml.substr(at + 1, close - at - 1);
+			if (name.empty() || name[0] == '/' || name[0] == '?' || name[0] == '!')
+				continue;
+			name = name.substr(0, name.find_first_of(" \t\r\n/"));
+			if (size_t colon = name.find(':'); colon != std::string_view::npos)
+				name.remove_prefix(colon + 1);
+			if (name != tag)
+				continue;
+			size_t end = xml.find("</", close);
+			return end == std::string_view::npos ? std::string{} : std::string(xml.substr(close + 1, end - close - 1));
+		}
+		return {};
+	}
+
+	struct UpnpService {
+		std::string type; // urn:schemas-upnp-org:service:WANIPConnection:1, or the PPP one
+		std::string control_url; // as written; may be relative to the description
+	};
+
+	// The IGD's WAN connection service from its device description; IP
+	// connections are preferred over PPP when a router lists both.
+	inline std::optional<UpnpService> FindWanService(std::string_view xml)
+	{
+		std::optional<UpnpService> found;
+		for (size_t at = xml.find("<service>"); at != std::string_view::npos; at = xml.find("<service>", at + 1)) {
+			size_t end = xml.find("</service>", at);
+			std::string_view block = xml.substr(at, end == std::string_view::npos ? std::string_view::npos : end - at);
+			UpnpService service{ XmlValue(block, "serviceType"), XmlValue(block, "controlURL") };
+			if (service.control_url.empty())
+				continue;
+			if (service.type.find(":service:WANIPConnection:") != std::string::npos)
+				return service;
+			if (!found && service.type.find(":service:WANPPPConnection:") != std::string::npos)
+				found = std::move(service);
+		}
+		return found;
+	}
+
+	// A control URL as the description gave it, against where the
+	// description came from.
+	inline std::optional<HttpUrl> ResolveControlUrl(const HttpUrl& description, const std::string& control_url)
+	{
+		if (control_url.rfind("http://", 0) == 0)
+			return ParseHttpUrl(control_url);
+		HttpUrl out = description;
+		out.path = control_url.empty() || control_url[0] != '/' ? "/" + control_url : control_url;
+		return out;
+	}
+
+	inline std::string UpnpSoapBody(const std::string& service_type, std::string_view action, const std::vector<std::pair<std::string, std::string>>& args)
+	{
+		std::string out = "<?xml version=\"1.0\"?>\r\n"
+			"<s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\">"
+			"<s:Body><u:" + std::string(action) + " xmlns:u=\"" + service_type + "\">";
+		for (const auto& [name, value] : args)
+			out += "<" + name + ">" + value + "</" + name + ">";
+		return out + "</u:" + std::string(action) + "></s:Body></s:Envelope>\r\n";
+	}
+
+	// Keeps one UDP port mapped while it lives: maps on construction, renews
+	// at half the lifetime, removes the mapping when destroyed. Results land
+	// in a queue Next reads from the poll thread.
+	struct PortMapper {
+		struct Impl; // the worker and its sockets; public only so port_mapper.cpp's helpers can name it
+
+		explicit PortMapper(uint16_t port);
+		~PortMapper(); // may block a moment while the router is told
+
+		uint16_t Port() const;
+		bool Next(PortMapping& out); // the first attempt's outcome, then one per change after a renewal
+
+	private:
+		std::unique_ptr<Impl> _impl;
+	};
+}
@@ -0,0 +1,65 @@
+#pragma once
+
+#include <cstddef>
+#include <cstdint>
+#include <memory>
+#include <queue>
+#include <string>
+#include <string_view>
+#include <vector>
+
+// A dedicated game server the host runs next to the client: launched from
+// an argv, fed console commands on its stdin, its stdout and stderr read
+// back line by line. Everything stays on the host's machine; nothing here
+// touches the network. One process at a time.
+namespace Weyvelength {
+	struct ProcessEvent {
+		enum class Kind : uint8_t {
+			Output, // one line it printed, to stdout or stderr
+			Exited, // the last event for the process
+		};
+		Kind kind{};
+		std::string line; // Output only, without its line break
+		int exit_code = 0; // Exited only; 128 + the signal if one killed it (POSIX)
+	};
+
+	constexpr size_t max_console_line = 4096; // bytes; longer output arrives split
+
+	// Appends data to pending and moves each finished line to out, "\r\n"
+	// and "\n" both ending one. A line that grows past max_console_line is
+	// cut there. Whatever is left waits in pending for the next read.
+	inline void SplitConsoleLines(std::string& pending, std::string_view data, std::vector<std::string>& out)
+	{
+		for (char c : data) {
+			if (c == '\n') {
+				if (!pending.empty() && pending.back() == '\r')
+					pending.pop_back();
+				out.push_back(std::move(pending));
+				pending.clear();
+				continue;
+			}
+			pending += c;
+			if (pending.size() == max_console_line) {
+				out.push_back(std::move(pending));
+				pending.clear();
+			}
+		}
+	}
+
+	struct ProcessManager {
+		struct Impl; // the platform's handles; public only so process_manager.cpp's helpers can name it
+
+		ProcessManager();
+		~ProcessManager(); // kills a process still running
+
+		bool LaunchGameServer(const std::vector<std::string>& argv); // argv[0] is the program; false if it could not start or one is running
+		bool SendConsoleCommand(const std::string& command); // written as one line to its stdin; false unless running
+		void StopGameServer(); // kills it; Poll then reports the exit
+		bool Running() const; // launched and not yet reported Exited
+		void Poll(std::queue<ProcessEvent>& out); // what it printed since, then Exited once it is gone
+
+	private:
+		std::unique_ptr<Impl> _impl;
+		std::string _pending; // a line it has not finished printing
+	};
+}
@@ -32,13 +32,250 @@ typedef enum WeyveRoomError {
 	WEYVE_ROOM_ERROR_NO_SUCH_ROOM,
 	WEYVE_ROOM_ERROR_NOT_IN_ROOM,
 	WEYVE_ROOM_ERROR_NOT_HOST, // host-only action attempted by a non-host
-	WEYVE_ROOM_ERROR_BAD_ROOM_DATA, // key/value over the size limits, or too many keys
+	WEYVE_ROOM_ERROR_BAD_ROOM_DATA, // key/value over the size limits, or too many keys; context "unasked" for an unwanted weyve_report_host_state
 	WEYVE_ROOM_ERROR_NO_SUCH_MEMBER, // target id is not another member of the room
 	WEYVE_ROOM_ERROR_ROOM_CLOSED, // the room is not joinable right now
 	WEYVE_ROOM_ERROR_BAD_PASSWORD, // wrong password on join, or an over-long one on set
 	WEYVE_ROOM_ERROR_BANNED, // the host has barred this client from the room
+	WEYVE_ROOM_ERROR_WHISPER_BLOCKED, // the target's preferences turn your whisper away
+	WEYVE_ROOM_ERROR_ROOM_FULL, // no free seat; context is "players" or "spectators"
+	WEYVE_ROOM_ERROR_NO_SUCH_SCHEDULE, // no booking with that id, or it already opened
+	WEYVE_ROOM_ERROR_BAD_SCHEDULE, // context is "start", "title", "full", "owner" (you hold too many bookings) or "rsvp"
+	WEYVE_ROOM_ERROR_CHAT_TOO_LONG, // context is the limit in characters
+	WEYVE_ROOM_ERROR_BAD_CHAT, // context is "empty", "utf8" or "zero-width"
+	WEYVE_ROOM_ERROR_BAD_BANNER, // not a blob the server holds; context is the id
+	WEYVE_ROOM_ERROR_NO_SUCH_TEAM, // context is the team id
+	WEYVE_ROOM_ERROR_TEAM_FULL, // context is the team id
+	WEYVE_ROOM_ERROR_BAD_TEAM, // context is "name", "count" (16 teams already) or "spectator"
+	WEYVE_ROOM_ERROR_BAD_COUNTDOWN, // too long; context is the limit in seconds
+	WEYVE_ROOM_ERROR_NO_SUCH_CHANNEL, // no such channel, or you are in none; context is the id
+	WEYVE_ROOM_ERROR_BAD_CHANNEL, // context is "name" or "count"
+	WEYVE_ROOM_ERROR_BAD_ROLE, // not a role this server knows; context is the value sent
+	WEYVE_ROOM_ERROR_BAD_SUCCESSION, // not a succession policy this server knows; context is the value sent
 } WeyveRoomError;
 
+// Mirrors Proto::AccountErrorCode; carried by WEYVE_EVENT_ACCOUNT_ERROR.
+typedef enum WeyveAccountError {
+	WEYVE_ACCOUNT_ERROR_DISABLED, // this server keeps no accounts
+	WEYVE_ACCOUNT_ERROR_ALREADY_LOGGED_IN,
+	WEYVE_ACCOUNT_ERROR_BAD_NAME, // 3-24 letters, digits, '_' or '-'
+	WEYVE_ACCOUNT_ERROR_NAME_TAKEN, // names are unique ignoring case
+	WEYVE_ACCOUNT_ERROR_BAD_PASSWORD, // 8-128 bytes
+	WEYVE_ACCOUNT_ERROR_BAD_EMAIL,
+	WEYVE_ACCOUNT_ERROR_WRONG_CREDENTIALS,
+	WEYVE_ACCOUNT_ERROR_IN_USE, // logged in on another connection
+	WEYVE_ACCOUNT_ERROR_STORE_FAILED, // the server could not save the account
+	WEYVE_ACCOUNT_ERROR_TOO_SOON, // one data export a minute; context is the seconds left
+	WEYVE_ACCOUNT_ERROR_BAD_PREFERENCES, // not a whisper policy this server knows; context is the value sent
+} WeyveAccountError;
+
+// Mirrors Proto::BlobErrorCode; carried by WEYVE_EVENT_BLOB_ERROR.
+typedef enum WeyveBlobError {
+	WEYVE_BLOB_ERROR_DISABLED, // this server keeps no blobs
+	WEYVE_BLOB_ERROR_TOO_LARGE, // context is the limit in bytes
+	WEYVE_BLOB_ERROR_BUSY, // too many uploads in flight
+	WEYVE_BLOB_ERROR_BAD_TRANSFER,
+	WEYVE_BLOB_ERROR_NO_SUCH_BLOB, // never stored, or collected; context is the id
+	WEYVE_BLOB_ERROR_STORE_FAILED, // the server could not save it
+	WEYVE_BLOB_ERROR_QUOTA, // context is "connection" (your uploads this hour are used up) or "server" (its storage is full)
+} WeyveBlobError;
+
+// Mirrors Proto::MemberRole, ranked lowest first; the host outranks every role.
+typedef enum WeyveMemberRole {
+	WEYVE_ROLE_MEMBER,
+	WEYVE_ROLE_MODERATOR, // can kick plain members
+	WEYVE_ROLE_CO_HOST, // can kick members and moderators, and open/close the room; first in line as host
+} WeyveMemberRole;
+
+// Mirrors Proto::WhisperPolicy: who may whisper you.
+typedef enum WeyveWhisperPolicy {
+	WEYVE_WHISPERS_EVERYONE,
+	WEYVE_WHISPERS_STAFF, // the host, co-hosts and moderators of your room
+	WEYVE_WHISPERS_NOBODY,
+} WeyveWhisperPolicy;
+
+// Mirrors Proto::ServerLimit: which cap a refusal hit.
+typedef enum WeyveServerLimit {
+	WEYVE_LIMIT_USERS, // connections, server-wide
+	WEYVE_LIMIT_ROOMS,
+	WEYVE_LIMIT_PER_ADDRESS, // connections from your IP address
+	WEYVE_LIMIT_REQUESTS, // lists and searches in the last few seconds, or logins and sign-ups in the last minute; you stay connected
+} WeyveServerLimit;
+
+// Mirrors Weyvelength::RequestKind: which kind of request went unanswered.
+typedef enum WeyveRequest {
+	WEYVE_REQUEST_ROOM, // create, join or spectate
+	WEYVE_REQUEST_ACCOUNT, // create account, login or guest login
+} WeyveRequest;
+
+// Mirrors Proto::RsvpStatus.
+typedef enum WeyveRsvpStatus {
+	WEYVE_RSVP_NO,
+	WEYVE_RSVP_GOING, // reminded ahead of the start, and invited when the room opens
+	WEYVE_RSVP_MAYBE, // invited when the room opens, not reminded
+} WeyveRsvpStatus;
+
+// One booking for a room that opens later. The title borrows client-owned storage.
+typedef struct WeyveScheduledRoom {
+	uint32_t id;
+	uint32_t owner; // who booked it, as connected now; 0 while their account is offline
+	uint64_t start; // unix seconds
+	const char* title;
+	uint32_t title_len;
+	uint32_t max_players; // 0 = no limit
+	uint32_t max_spectators; // 0 = no limit
+	bool passworded;
+	uint32_t going; // RSVPs currently going
+} WeyveScheduledRoom;
+
+// Mirrors Proto::TimelineKind.
+typedef enum WeyveTimelineKind {
+	WEYVE_TIMELINE_JOINED, // id took a player seat
+	WEYVE_TIMELINE_SPECTATING, // id joined as a spectator
+	WEYVE_TIMELINE_LEFT, // id left, or dropped
+	WEYVE_TIMELINE_KICKED, // by removed id
+	WEYVE_TIMELINE_BANNED, // by removed and barred id
+	WEYVE_TIMELINE_HOST_CHANGED, // id hosts now; by handed it over, 0 = the old host left
+	WEYVE_TIMELINE_OPENED, // by opened the room; id is 0
+	WEYVE_TIMELINE_CLOSED, // by closed it; id is 0
+	WEYVE_TIMELINE_MOVED, // id went to another room in by's split or merge
+} WeyveTimelineKind;
+
+// One line of a room's history, numbered the same at every member, for
+// system lines in a chat view. The name borrows client-owned storage.
+typedef struct WeyveTimelineEntry {
+	uint32_t seq; // per room, from 1, no gaps
+	uint64_t time; // unix seconds, the server's clock
+	WeyveTimelineKind kind;
+	uint32_t id;
+	const char* name; uint32_t name_len; // id's account name then; name_len 0 = anonymous
+	uint32_t by; // whoever did it; 0 = nobody in particular
+} WeyveTimelineEntry;
+
+// Mirrors Proto::HandoffReason.
+typedef enum WeyveHandoffReason {
+	WEYVE_HANDOFF_TRANSFERRED, // the old host handed it over
+	WEYVE_HANDOFF_LEFT, // the old host left the room
+	WEYVE_HANDOFF_DROPPED, // the old host's connection dropped: the state may be stale, and the others were asked for theirs
+} WeyveHandoffReason;
+
+// Mirrors Proto::SuccessionPolicy: who hosts when the host leaves. Ties go to whoever joined first.
+typedef enum WeyveSuccessionPolicy {
+	WEYVE_SUCCESSION_COHOST_FIRST, // a co-host, else a player, else a spectator; the default
+	WEYVE_SUCCESSION_LONGEST_CONNECTED, // the player connected to the server longest
+	WEYVE_SUCCESSION_LOWEST_LATENCY, // the player with the lowest round trip to the server in their last health report
+	WEYVE_SUCCESSION_SUCCESSORS, // the first of the host's successors still in the room; else as COHOST_FIRST
+} WeyveSuccessionPolicy;
+
+// Mirrors Proto::ReceiptKind.
+typedef enum WeyveReceiptKind {
+	WEYVE_RECEIPT_JOINED,
+	WEYVE_RECEIPT_LEFT, // kicks, bans and drops too
+} WeyveReceiptKind;
+
+// Mirrors Proto::ReceiptRole.
+typedef enum WeyveReceiptRole {
+	WEYVE_RECEIPT_HOST,
+	WEYVE_RECEIPT_PLAYER,
+	WEYVE_RECEIPT_SPECTATOR,
+} WeyveReceiptRole;
+
+// The server's signed word that a member entered or left the room, for a
+// tournament organizer to keep as proof of who played. Strings borrow
+// client-owned storage.
+typedef struct WeyveJoinReceipt {
+	const char* room; uint32_t room_len;
+	uint32_t id;
+	const char* name; uint32_t name_len; // id's account name then; name_len 0 = anonymous
+	WeyveReceiptRole role; // as of the join or leave
+	WeyveReceiptKind kind;
+	uint64_t time; // unix seconds, the server's clock
+	const char* signature; uint32_t signature_len; // hex ed25519 over weyve_join_receipt_text, by the reply's key
+} WeyveJoinReceipt;
+
+// One of the room's teams. The name and members borrow client-owned storage.
+typedef struct WeyveTeam {
+	uint32_t id; // per room, from 1
+	const char* name; uint32_t name_len;
+	uint32_t max_size; // 0 = no limit
+	const uint32_t* members; uint32_t member_count; // in the order they were placed
+} WeyveTeam;
+
+// One of the room's breakout channels. The name and members borrow client-owned storage.
+typedef struct WeyveChannel {
+	uint32_t id; // per room, from 1
+	const char* name; uint32_t name_len;
+	const uint32_t* members; uint32_t member_count; // in the order they were placed
+} WeyveChannel;
+
+// Mirrors Weyvelength::PortMapping::Method.
+typedef enum WeyvePortMappingMethod {
+	WEYVE_PORT_MAPPING_NONE, // nothing answered, or everything refused
+	WEYVE_PORT_MAPPING_NAT_PMP,
+	WEYVE_PORT_MAPPING_UPNP,
+} WeyvePortMappingMethod;
+
+// Mirrors Weyvelength::CandidateType: how one end of a p2p link is reached.
+typedef enum WeyveCandidateType {
+	WEYVE_CANDIDATE_HOST, // an interface's own address: LAN or VPN
+	WEYVE_CANDIDATE_SERVER_REFLEXIVE, // a NAT's public side, learned from STUN
+	WEYVE_CANDIDATE_PEER_REFLEXIVE, // a NAT mapping learned from the other end's checks
+	WEYVE_CANDIDATE_RELAYED, // through TURN
+} WeyveCandidateType;
+
+// The candidate pair a connected link settled on. Strings borrow
+// client-owned storage, valid until the next weyve_peer_link_path.
+typedef struct WeyveLinkPath {
+	const char* local_address; uint32_t local_address_len;
+	uint16_t local_port;
+	WeyveCandidateType local_type;
+	const char* remote_address; uint32_t remote_address_len;
+	uint16_t remote_port;
+	WeyveCandidateType remote_type;
+	const char* interface_name; uint32_t interface_name_len; // the interface carrying it; empty when it cannot be told, e.g. behind a relay
+} WeyveLinkPath;
+
+// One address of one of the machine's interfaces. Strings borrow
+// client-owned storage, valid until the next weyve_list_network_interfaces.
+typedef struct WeyveNetworkInterface {
+	const char* name; uint32_t name_len; // the OS's: "eth0", "Wi-Fi"
+	const char* address; uint32_t address_len; // IPv4 or IPv6, as text
+} WeyveNetworkInterface;
+
+// One member's p2p links as they last reported them, for the host's
+// session-health view. Read the links with weyve_session_health_link_at.
+typedef struct WeyveMemberHealth {
+	uint32_t from;
+	uint32_t server_rtt; // ms; 0 = not measured
+	uint32_t link_count;
+} WeyveMemberHealth;
+
+// One link, from one end: who to, how it runs and how busy it is.
+typedef struct WeyveLinkHealth {
+	uint32_t peer;
+	bool direct; // false = down or still dialing
+	bool relayed; // direct, but through TURN
+	uint32_t loss_permille; // ordered datagrams lost on the wire, last reading; 0 = none yet
+	uint32_t send_rate; // bytes per second over the last report interval
+	uint32_t receive_rate;
+} WeyveLinkHealth;
+
+// A link card the server fetched for a chat line. Strings borrow client-owned
+// storage; description, image and site may be empty.
+typedef struct WeyveLinkPreview {
+	uint32_t from; // who posted the link
+	const char* url; // as it appeared in their chat line
+	uint32_t url_len;
+	const char* title;
+	uint32_t title_len;
+	const char* description;
+	uint32_t description_len;
+	const char* image; // an https url; fetching it is up to you
+	uint32_t image_len;
+	const char* site;
+	uint32_t site_len;
+} WeyveLinkPreview;
+
 // The kinds of event weyve_next can hand back. These are exactly the server
 // messages the client surfaces; id/ice/signaling frames are consumed inside.
 typedef enum WeyveEventType {
@@ -55,10 +292,83 @@ typedef enum WeyveEventType {
 	WEYVE_EVENT_KICKED, // the host removed you from the room
 	WEYVE_EVENT_BANNED, // the host removed you and barred you from rejoining
 	WEYVE_EVENT_ROOM_ACCESS_CHANGED, // the room's joinability/password flag changed
+	WEYVE_EVENT_MEMBER_ROLE_CHANGED, // a member's role; WEYVE_ROLE_MEMBER means demoted
+	WEYVE_EVENT_WHISPER, // a member sent text to you alone
+	WEYVE_EVENT_PREFERENCES_CHANGED, // your stored preferences; once after connect, on login and on every change
+	WEYVE_EVENT_MEMBER_SPECTATING, // follows a member's WEYVE_EVENT_PEER_JOINED (or your own join) when they only watch
+	WEYVE_EVENT_ROOM_CAPACITY_CHANGED, // the room's seat limits; 0 = no limit
+	WEYVE_EVENT_IDLE_POLICY_CHANGED, // when members count as AFK (0 = never) and whether they lose their seat
+	WEYVE_EVENT_MEMBER_AFK, // a member went idle, or came back
+	WEYVE_EVENT_SCHEDULED_ROOM_CHANGED, // a booking you made or RSVP'd to, as it stands now
+	WEYVE_EVENT_SCHEDULED_ROOM_LIST, // the board was refreshed; read it with weyve_scheduled_room_at
+	WEYVE_EVENT_SCHEDULED_ROOM_REMINDER, // a booking you are going to starts soon
+	WEYVE_EVENT_SCHEDULED_ROOM_OPENED, // a booking you made or answered going/maybe is now a room; join it by id, no password needed
+	WEYVE_EVENT_SCHEDULED_ROOM_ATTENDEES, // who answered going/maybe; read them with weyve_attendee_at
+	WEYVE_EVENT_LOGGED_IN, // create/login succeeded; the name as the server stores it
+	WEYVE_EVENT_ACCOUNT_ERROR, // an account request failed
+	WEYVE_EVENT_MEMBER_NAME_CHANGED, // a member's account name, bound by their login
+	WEYVE_EVENT_MESSAGE_SEND_FAILED, // a chat or whisper had no room to go to; it is held for weyve_retry_message
+	WEYVE_EVENT_CHAT_LINK_PREVIEW, // a card for the link in a member's recent chat line; servers only send these when set up to
+	WEYVE_EVENT_BLOB_UPLOADED, // an upload is stored; put its id in room or member data to keep it
+	WEYVE_EVENT_BLOB_DOWNLOADED, // a download finished; read the bytes with weyve_blob
+	WEYVE_EVENT_BLOB_ERROR, // an upload or download failed
+	WEYVE_EVENT_ROOM_BANNER_CHANGED, // the room's banner blob; empty id = none. Fetch it with weyve_get_blob
+	WEYVE_EVENT_PEER_RETRYING, // a direct link to a member failed; the client redials by itself after retry_in ms
+	WEYVE_EVENT_PEER_UNREACHABLE, // the last redial failed too; weyve_retry_peer_connection starts over
+	WEYVE_EVENT_PEER_CLOSED, // a direct link was closed on purpose, by you or (remote) by the peer; the last event for that link
+	WEYVE_EVENT_SERVER_FULL, // refused for load, try again after retry_after seconds. On connect the server hangs up next; on a room create or a list you stay connected
+	WEYVE_EVENT_SERVER_INFO, // the server's load; 0 max = no limit
+	WEYVE_EVENT_REQUEST_TIMED_OUT, // no answer in 10 s; the server may be hung. A late answer still arrives as usual
+	WEYVE_EVENT_ROOM_PREVIEW, // a room seen from outside; read its members and data with weyve_preview_member_at and weyve_preview_data_at
+	WEYVE_EVENT_JOIN_REQUESTED, // host only: someone knocked; answer with weyve_respond_join_request
+	WEYVE_EVENT_JOIN_REQUEST_ANSWERED, // your knock was answered; if approved, weyve_join_room works without the password
+	WEYVE_EVENT_QUEUE_POSITION, // your place in a room's line; 0 = out of it (left, offer lapsed, or the room closed)
+	WEYVE_EVENT_SEAT_OFFERED, // a player seat is held for you for expires_in seconds; weyve_join_room to take it
+	WEYVE_EVENT_USER_SEARCH_RESULTS, // read the matches with weyve_user_result_at
+	WEYVE_EVENT_SEARCHABLE_CHANGED, // whether user searches find you; sent on login too
+	WEYVE_EVENT_MY_DATA, // your data export arrived; read it with weyve_my_data
+	WEYVE_EVENT_JOIN_CHECKED, // what a join would run into, from weyve_preflight_join; nothing is held for you
+	WEYVE_EVENT_TIMELINE, // the room's history arrived, on joining or from weyve_get_timeline; read it with weyve_timeline_at
+	WEYVE_EVENT_TIMELINE_ENTRY, // something just happened in the room; also kept for weyve_timeline_at
+	WEYVE_EVENT_TEAMS_CHANGED, // the room's teams, on joining and after every change; read them with weyve_team_at
+	WEYVE_EVENT_ROOM_RULES_CHANGED, // the room's rules, on joining when set and after every change; empty = cleared
+	WEYVE_EVENT_COUNTDOWN, // the host started a countdown (again to a joiner while it runs); seconds 0 = cancelled
+	WEYVE_EVENT_COUNTDOWN_TICK, // raised by the client: remaining whole seconds until the start
+	WEYVE_EVENT_MATCH_START, // raised by the client when the countdown runs out, at the same instant at every member give or take the network's jitter
+	WEYVE_EVENT_MATCH_PAUSED, // the host paused or resumed the game; also on joining a paused room
+	WEYVE_EVENT_GAME_SERVER_OUTPUT, // raised by the client: a line your game server printed, stdout or stderr
+	WEYVE_EVENT_GAME_SERVER_EXITED, // raised by the client: your game server is gone, stopped or on its own; the last event for it
+	WEYVE_EVENT_PORT_MAPPING, // raised by the client: whether the router forwards the p2p port, after weyve_set_port_mapping and on any change
+	WEYVE_EVENT_MEMBER_HEALTH, // host-only: a member reported their links; weyve_get_session_health has it now
+	WEYVE_EVENT_SESSION_HEALTH, // the server's copy of every member's last report, from weyve_request_session_health; read it with weyve_reported_health_at
+	WEYVE_EVENT_CLIENT_VERSION_POLICY, // the app versions the server takes, after every WEYVE_EVENT_SERVER_INFO; check yours with weyve_compare_versions
+	WEYVE_EVENT_CLIENT_OUTDATED, // refused: your weyve_set_app_version is older than min_version. The server hangs up next
+	WEYVE_EVENT_JOIN_RECEIPTS, // the room's signed join and leave receipts, from weyve_get_join_receipts; read them with weyve_join_receipt_at
+	WEYVE_EVENT_HOST_HANDOFF, // you host now: the last state the old host saved with weyve_set_host_state, right after WEYVE_EVENT_HOST_CHANGED
+	WEYVE_EVENT_HOST_STATE_WANTED, // the host dropped; the new one rebuilds its state from the members. Answer with weyve_report_host_state
+	WEYVE_EVENT_MEMBER_HOST_STATE, // host-only: a member's weyve_report_host_state
+	WEYVE_EVENT_SUCCESSION_CHANGED, // who hosts next when the host leaves; on join and after every change
+	WEYVE_EVENT_ROOM_SPLIT, // your weyve_split_room made this room; the members you named move there next
+	WEYVE_EVENT_MERGE_REQUESTED, // host-only: another room's host asks you to move your room into theirs; answer with weyve_answer_merge
+	WEYVE_EVENT_MERGE_ANSWERED, // your weyve_merge_room was answered; if accepted, its members are on their way in
+	WEYVE_EVENT_ROOM_MOVED, // a split or merge moved you; WEYVE_EVENT_ROOM_ID_ASSIGNED for the new room follows, and p2p links start over
+	WEYVE_EVENT_CHANNELS_CHANGED, // the room's breakout channels, on joining and after every change; read them with weyve_channel_at
+	WEYVE_EVENT_CHANNEL_CHAT, // someone in your channel sent it text; the rest of the room did not get it
+	WEYVE_EVENT_SCHEDULED_ROOM_CANCELLED, // its owner called off a booking you made or answered going/maybe; it will not open
 } WeyveEventType;
 
+// Which conversation an event belongs to, for apps that show each one in its
+// own window: one loop drains weyve_next and hands events on by scope.
+typedef enum WeyveScope {
+	WEYVE_SCOPE_CLIENT, // the connection itself: account, preferences, bookings, blobs, pongs
+	WEYVE_SCOPE_ROOM, // the current room, membership and room chat included
+	WEYVE_SCOPE_MEMBER, // one member one-to-one: whispers and the direct link; the id says who
+} WeyveScope;
+
 // One decoded server message. String pointers borrow client-owned storage and
 // stay valid only until the next weyve_next or weyve_poll on the same client.
+// weyve_next also fills in where it belongs, so a binding forwards one
+// envelope shape for every type: the name, the scope and room, then data.
 typedef struct WeyveEvent {
 	WeyveEventType type;
 	union {
@@ -72,55 +382,543 @@ typedef struct WeyveEvent {
 		struct { const char* key; uint32_t key_len; const char* value; uint32_t value_len; } room_data; // WEYVE_EVENT_ROOM_DATA_CHANGED
 		struct { uint32_t id; const char* key; uint32_t key_len; const char* value; uint32_t value_len; } member_data; // WEYVE_EVENT_MEMBER_DATA_CHANGED
 		struct { bool open; bool passworded; } room_access; // WEYVE_EVENT_ROOM_ACCESS_CHANGED
+		struct { uint32_t id; WeyveMemberRole role; } member_role; // WEYVE_EVENT_MEMBER_ROLE_CHANGED
+		struct { uint32_t from; const char* text; uint32_t text_len; } whisper; // WEYVE_EVENT_WHISPER
+		struct { uint64_t dnd_until; WeyveWhisperPolicy whispers; } preferences; // WEYVE_EVENT_PREFERENCES_CHANGED
+		struct { uint32_t id; } member_spectating; // WEYVE_EVENT_MEMBER_SPECTATING
+		struct { uint32_t max_players; uint32_t max_spectators; } room_capacity; // WEYVE_EVENT_ROOM_CAPACITY_CHANGED
+		struct { uint32_t afk_after; bool spectate; } idle_policy; // WEYVE_EVENT_IDLE_POLICY_CHANGED
+		struct { uint32_t id; bool afk; } member_afk; // WEYVE_EVENT_MEMBER_AFK
+		WeyveScheduledRoom scheduled_room; // WEYVE_EVENT_SCHEDULED_ROOM_CHANGED
+		struct { uint32_t count; } scheduled_room_list; // WEYVE_EVENT_SCHEDULED_ROOM_LIST
+		struct { uint32_t id; uint64_t start; } scheduled_room_reminder; // WEYVE_EVENT_SCHEDULED_ROOM_REMINDER
+		struct { uint32_t id; const char* room; uint32_t room_len; } scheduled_room_opened; // WEYVE_EVENT_SCHEDULED_ROOM_OPENED
+		struct { uint32_t id; uint32_t count; } scheduled_room_attendees; // WEYVE_EVENT_SCHEDULED_ROOM_ATTENDEES
+		struct { const char* name; uint32_t name_len; } logged_in; // WEYVE_EVENT_LOGGED_IN
+		struct { WeyveAccountError code; const char* context; uint32_t context_len; } account_error; // WEYVE_EVENT_ACCOUNT_ERROR
+		struct { uint32_t id; const char* name; uint32_t name_len; } member_name; // WEYVE_EVENT_MEMBER_NAME_CHANGED
+		struct { uint32_t id; uint32_t to; const char* text; uint32_t text_len; } send_failed; // WEYVE_EVENT_MESSAGE_SEND_FAILED; to = 0 for room chat
+		WeyveLinkPreview link_preview; // WEYVE_EVENT_CHAT_LINK_PREVIEW
+		struct { uint32_t transfer; const char* id; uint32_t id_len; } blob_uploaded; // WEYVE_EVENT_BLOB_UPLOADED
+		struct { uint32_t transfer; const char* id; uint32_t id_len; } blob_downloaded; // WEYVE_EVENT_BLOB_DOWNLOADED
+		struct { uint32_t transfer; WeyveBlobError code; const char* context; uint32_t context_len; } blob_error; // WEYVE_EVENT_BLOB_ERROR
+		struct { const char* id; uint32_t id_len; } room_banner; // WEYVE_EVENT_ROOM_BANNER_CHANGED
+		struct { uint32_t id; uint32_t attempt; uint32_t retry_in; bool remote; } peer_link; // WEYVE_EVENT_PEER_RETRYING, _UNREACHABLE and _CLOSED; retry_in is ms, 0 unless retrying
+		struct { WeyveServerLimit limit; uint32_t retry_after; } server_full; // WEYVE_EVENT_SERVER_FULL
+		struct { uint32_t users; uint32_t max_users; uint32_t rooms; uint32_t max_rooms; } server_info; // WEYVE_EVENT_SERVER_INFO
+		struct { WeyveRequest request; } request_timeout; // WEYVE_EVENT_REQUEST_TIMED_OUT
+		struct {
+			const char* id; uint32_t id_len;
+			uint32_t host; // 0 while nobody is in it
+			bool open; bool passworded; // a passworded room shows no members or data, unless you are invited
+			uint32_t players; uint32_t spectators;
+			uint32_t max_players; uint32_t max_spectators; // 0 = no limit
+			const char* banner; uint32_t banner_len; // a blob id; len 0 = none
+			uint32_t member_count; uint32_t data_count; // up to 32 members, and the data keys that fit in 4 KiB
+		} room_preview; // WEYVE_EVENT_ROOM_PREVIEW
+		struct { uint32_t id; const char* name; uint32_t name_len; const char* message; uint32_t message_len; } join_requested; // WEYVE_EVENT_JOIN_REQUESTED; name_len 0 = anonymous
+		struct { const char* room; uint32_t room_len; bool approved; } join_answered; // WEYVE_EVENT_JOIN_REQUEST_ANSWERED
+		struct { const char* room; uint32_t room_len; uint32_t position; } queue_position; // WEYVE_EVENT_QUEUE_POSITION
+		struct { const char* room; uint32_t room_len; uint32_t expires_in; } seat_offered; // WEYVE_EVENT_SEAT_OFFERED
+		struct { const char* query; uint32_t query_len; uint32_t count; } user_search; // WEYVE_EVENT_USER_SEARCH_RESULTS
+		struct { bool searchable; } searchable; // WEYVE_EVENT_SEARCHABLE_CHANGED
+		struct {
+			const char* id; uint32_t id_len; // as the server has it; as asked, if no such room
+			bool spectating; // the seat it was checked for
+			bool ready; WeyveRoomError blocker; // blocker is the error the join would get: no such room, banned, closed or full; meaningful only when !ready
+			bool password; // the join needs one, unless the host let you in
+			bool relay; // the server offers a TURN relay; without one, members behind strict NATs may be unreachable
+		} join_check; // WEYVE_EVENT_JOIN_CHECKED
+		struct { uint32_t count; } timeline; // WEYVE_EVENT_TIMELINE; entries in this reply
+		WeyveTimelineEntry timeline_entry; // WEYVE_EVENT_TIMELINE_ENTRY
+		struct { uint32_t count; } teams; // WEYVE_EVENT_TEAMS_CHANGED; 0 = the last team was removed
+		struct { uint32_t version; const char* rules; uint32_t rules_len; } room_rules; // WEYVE_EVENT_ROOM_RULES_CHANGED
+		struct { uint32_t id; uint32_t seconds; uint32_t by; } countdown; // WEYVE_EVENT_COUNTDOWN
+		struct { uint32_t id; uint32_t remaining; } countdown_tick; // WEYVE_EVENT_COUNTDOWN_TICK and WEYVE_EVENT_MATCH_START (remaining 0)
+		struct { bool paused; uint32_t by; } match_paused; // WEYVE_EVENT_MATCH_PAUSED
+		struct { const char* line; uint32_t line_len; } game_output; // WEYVE_EVENT_GAME_SERVER_OUTPUT; no line break, cut at 4096 bytes
+		struct { int32_t exit_code; } game_exited; // WEYVE_EVENT_GAME_SERVER_EXITED; 128 + the signal if one killed it (POSIX)
+		struct {
+			bool mapped;
+			WeyvePortMappingMethod method; // the one that worked, or the last one tried
+			uint16_t internal_port;
+			uint16_t external_port; // what peers dial; may differ from the one asked for
+			const char* external_address; uint32_t external_address_len; // the router's public address; may be empty
+			const char* error; uint32_t error_len; // why not, when !mapped; English, for logs
+		} port_mapping; // WEYVE_EVENT_PORT_MAPPING
+		WeyveMemberHealth member_health; // WEYVE_EVENT_MEMBER_HEALTH
+		struct { uint32_t count; } session_health; // WEYVE_EVENT_SESSION_HEALTH; reports in the reply
+		struct {
+			const char* min_version; uint32_t min_version_len; // len 0 = any
+			const char* update_url; uint32_t update_url_len; // where to send the user for a newer app; len 0 = not said
+		} client_version; // WEYVE_EVENT_CLIENT_VERSION_POLICY and WEYVE_EVENT_CLIENT_OUTDATED
+		struct { const char* key; uint32_t key_len; uint32_t count; } join_receipts; // WEYVE_EVENT_JOIN_RECEIPTS; key is the server's hex ed25519 public key
+		struct {
+			uint32_t from; // the old host; 0 when the room sat empty in between
+			WeyveHandoffReason reason;
+			uint64_t saved; // unix seconds the state was saved; 0 = none was
+			const uint8_t* state; uint32_t state_len;
+		} host_handoff; // WEYVE_EVENT_HOST_HANDOFF
+		struct { uint32_t host; } host_state_wanted; // WEYVE_EVENT_HOST_STATE_WANTED; the new host
+		struct { uint32_t from; const uint8_t* state; uint32_t state_len; } member_host_state; // WEYVE_EVENT_MEMBER_HOST_STATE
+		struct { WeyveSuccessionPolicy policy; const uint32_t* successors; uint32_t count; } succession; // WEYVE_EVENT_SUCCESSION_CHANGED
+		struct { const char* id; uint32_t id_len; uint32_t members; } room_split; // WEYVE_EVENT_ROOM_SPLIT
+		struct { const char* into; uint32_t into_len; uint32_t by; uint32_t members; } merge_requested; // WEYVE_EVENT_MERGE_REQUESTED; by hosts into
+		struct { const char* id; uint32_t id_len; bool accepted; } merge_answered; // WEYVE_EVENT_MERGE_ANSWERED
+		struct { const char* id; uint32_t id_len; uint32_t by; } room_moved; // WEYVE_EVENT_ROOM_MOVED; by is the host who split or accepted
+		struct { uint32_t count; } channels; // WEYVE_EVENT_CHANNELS_CHANGED; 0 = the last channel was removed
+		struct { uint32_t from; const char* text; uint32_t text_len; } channel_chat; // WEYVE_EVENT_CHANNEL_CHAT
+		struct { uint32_t id; } scheduled_room_cancelled; // WEYVE_EVENT_SCHEDULED_ROOM_CANCELLED
 	} data;
+	WeyveScope scope; uint32_t scope_id; // as weyve_event_scope gives them
+	const char* room; uint32_t room_len; // the room it came from, as we were in it then: a kick names the room left, WEYVE_EVENT_ROOM_ID_ASSIGNED the one joined. len 0 for WEYVE_SCOPE_CLIENT, or outside a room
 } WeyveEvent;
 
+// Mirrors Weyvelength::PerformanceProfile; store it per device and set it at
+// startup. Low is for running on battery.
+typedef enum WeyvePerformanceProfile {
+	WEYVE_PERFORMANCE_LOW, // poll every 50 ms; two dials per peer
+	WEYVE_PERFORMANCE_BALANCED, // the default: every 10 ms; three dials
+	WEYVE_PERFORMANCE_HIGH, // every 1 ms; five dials, redialing sooner
+} WeyvePerformanceProfile;
+
+// Mirrors Weyvelength::LogLevel.
+typedef enum WeyveLogLevel {
+	WEYVE_LOG_DEBUG, // libjuice's ICE chatter included
+	WEYVE_LOG_INFO, // the default
+	WEYVE_LOG_WARN, // lost connections, failed links, unanswered requests
+	WEYVE_LOG_ERROR, // what ends a connection or a link for good
+} WeyveLogLevel;
+
+// One line of the client's log. text borrows client-owned storage, valid
+// until the next weyve_recent_logs.
+typedef struct WeyveLogEntry {
+	uint64_t time; // unix milliseconds
+	WeyveLogLevel level;
+	const char* text; uint32_t text_len;
+} WeyveLogEntry;
+
+// Mirrors Weyvelength::ChatExportFormat. Timestamps are ISO 8601, UTC.
+typedef enum WeyveChatFormat {
+	WEYVE_CHAT_TEXT, // one line per message: time, [room], sender (-> target for whispers), text
+	WEYVE_CHAT_JSON, // an array of { time, room, from, name, to, to_name, text }; to = 0 for room chat
+	WEYVE_CHAT_CSV, // the same columns, header row first
+} WeyveChatFormat;
+
+// One line of the chat history. Strings borrow client-owned storage, valid
+// until the next weyve_poll.
+typedef struct WeyveChatLine {
+	uint64_t time; // unix seconds, as heard
+	const char* room; uint32_t room_len;
+	uint32_t from; const char* name; uint32_t name_len; // name_len 0 = anonymous
+	uint32_t to; const char* to_name; uint32_t to_name_len; // to = 0 for room chat
+	const char* text; uint32_t text_len;
+} WeyveChatLine;
+
+// Every failure event read the same way, so an app can handle errors in one
+// place: which enum code is in, whether the same request can simply be sent
+// again later, and a plain English line to fall back on. key is stable across
+// releases (e.g. "room.full") for looking up a translation; param names what
+// context holds (e.g. "max_chars"), which a translated line places as
+// {max_chars}. The server sends codes and parameters, never prose.
+typedef enum WeyveErrorDomain {
+	WEYVE_ERROR_ROOM, // code is a WeyveRoomError
+	WEYVE_ERROR_ACCOUNT, // code is a WeyveAccountError
+	WEYVE_ERROR_BLOB, // code is a WeyveBlobError
+	WEYVE_ERROR_SERVER_FULL, // code is a WeyveServerLimit
+	WEYVE_ERROR_TIMEOUT, // code is a WeyveRequest
+	WEYVE_ERROR_CLIENT_OUTDATED, // code is 0; context is the version the server wants at least
+} WeyveErrorDomain;
+
+typedef struct WeyveError {
+	WeyveErrorDomain domain;
+	int32_t code;
+	bool retryable; // the same request may work later, unchanged
+	uint32_t retry_after; // seconds; 0 = no hint
+	const char* message; // English, static storage
+	const char* context; uint32_t context_len; // as on the event, borrowed from it; len 0 = none
+	const char* key; // static storage
+	const char* param; // static storage; null when there is no context
+} WeyveError;
+
+// Raised with every release that adds calls or events. An app built against
+// a newer header than the DLL it finds checks weyve_api_version before
+// calling what the DLL lacks.
+#define WEYVE_API_VERSION 1
+
+// What this DLL can do beyond the calls every build has, for apps that
+// adapt their UI instead of offering what fails. A bit an older DLL does
+// not know is never set.
+typedef enum WeyveCapability {
+	WEYVE_CAPABILITY_RECORDING = 1u << 0, // weyve_start_recording works; builds with WEYVE_RECORDING only
+	WEYVE_CAPABILITY_PORT_MAPPING = 1u << 1, // weyve_set_port_mapping, over NAT-PMP and UPnP; not in builds with WEYVE_NO_PORT_MAPPING
+	WEYVE_CAPABILITY_GAME_SERVER = 1u << 2, // weyve_launch_game_server; not in builds with WEYVE_NO_GAME_SERVER
+	WEYVE_CAPABILITY_BLOBS = 1u << 3, // weyve_upload_blob and weyve_get_blob; the server may still refuse
+	WEYVE_CAPABILITY_P2P_FEC = 1u << 4, // weyve_set_p2p_fec
+	WEYVE_CAPABILITY_LOG = 1u << 5, // weyve_recent_logs
+} WeyveCapability;
+
 // --- lifecycle ---
 
+// A client is in one room at a time. To sit in a lobby while playing in a
+// match, create a client for each: they share nothing, each with its own
+// connection, events and p2p links, and poll side by side on one thread.
+// An account logs in on one connection at a time; the others go as guests.
 WEYVE_API WeyveClient* weyve_client_create(void); // never null; pair with weyve_client_destroy
-WEYVE_API void weyve_client_destroy(WeyveClient* client); // null is a no-op
+WEYVE_API void weyve_client_destroy(WeyveClient* client); // null is a no-op; disconnects first, as weyve_disconnect
+WEYVE_API uint32_t weyve_api_version(void); // the DLL's WEYVE_API_VERSION
+WEYVE_API uint32_t weyve_protocol_version(void); // the wire protocol it speaks to servers
+WEYVE_API uint32_t weyve_capabilities(void); // WeyveCapability bits
 
 WEYVE_API bool weyve_connect(WeyveClient* client, const char* host, uint16_t port);
+// Your app's version ("1.4.2"), told to the server on every connection made
+// after it along with the platform and this DLL's WEYVE_API_VERSION, so the
+// operator sees which clients are still about. Up to 32 bytes; null or ""
+// clears it. Set it once, before weyve_connect.
+WEYVE_API void weyve_set_app_version(WeyveClient* client, const char* version);
+// <0, 0 or >0 as version a is older than, the same as or newer than b:
+// dotted numbers compared part by part ("1.10" is newer than "1.9"), the
+// way the server compares yours with its minimum. Null counts as "".
+WEYVE_API int32_t weyve_compare_versions(const char* a, const char* b);
+// The current room as opaque bytes to store across runs (null, len 0, when
+// not in one); borrowed until the next weyve_save_session. Account passwords
+// are not in it; a guest is logged back in under the same base name.
+WEYVE_API const uint8_t* weyve_save_session(WeyveClient* client, uint32_t* len);
+// Connects to the saved server unless already connected, then rejoins the saved
+// room. -> WEYVE_EVENT_ROOM_ID_ASSIGNED or WEYVE_EVENT_ROOM_ERROR, as a join.
+// False if the bytes are unreadable, you are in a room, or the connect failed.
+// The server holds a dropped member's seat for their account only: connect
+// and log in first, then resume, to get it (and the host, if the room sat
+// empty) back. Anyone else rejoins as a newcomer would.
+WEYVE_API bool weyve_resume_session(WeyveClient* client, const void* data, uint32_t len);
+// Call from the platform's network change notification (Wi-Fi to cellular
+// and back). Opens a fresh connection, rejoins the room on it and drops every
+// direct link so it reconnects over the new network. Expect a new client id
+// and WEYVE_EVENT_ROOM_ID_ASSIGNED again. False, with the old connection
+// kept, if the server cannot be reached yet; call it again later.
+WEYVE_API bool weyve_network_changed(WeyveClient* client);
+
+// Leaves the room and closes every direct link, telling the peers, then the
+// connection; blocks up to half a second for those goodbyes to go out. Call
+// it from your app's exit or window-close handler.
+WEYVE_API void weyve_disconnect(WeyveClient* client);
 WEYVE_API bool weyve_poll(WeyveClient* client); // instant, non-blocking; false once the connection is gone
+WEYVE_API void weyve_set_performance_profile(WeyveClient* client, WeyvePerformanceProfile profile);
+WEYVE_API WeyvePerformanceProfile weyve_performance_profile(const WeyveClient* client);
+WEYVE_API uint32_t weyve_poll_interval(const WeyveClient* client); // ms to sleep between weyve_poll calls, per the profile
+// Seconds a direct link may sit in one state short of connected before it is
+// redialed: longer for slow TURN paths, shorter to fail fast on a LAN. 5 to
+// 300, 0 = the default of 30; store it per device like the profile.
+WEYVE_API void weyve_set_link_timeout(WeyveClient* client, uint32_t seconds);
+WEYVE_API uint32_t weyve_link_timeout(const WeyveClient* client);
+// UPnP/NAT-PMP: ask the router to forward one UDP port to us, so peers reach
+// us directly more often before falling back to TURN. Links built from then
+// on share that port; those already up keep theirs. 0 turns it off and
+// removes the mapping; destroying the client removes it too.
+WEYVE_API void weyve_set_port_mapping(WeyveClient* client, uint16_t port);
+WEYVE_API uint16_t weyve_port_mapping(const WeyveClient* client); // 0 when off
+// Which network p2p links use, for VPN users: ICE gathers on the chosen
+// interface alone, picked by name or by one of its addresses, for links built
+// from then on. Null or "" goes back to every interface. False, unchanged,
+// if no up interface matches.
+WEYVE_API bool weyve_set_network_interface(WeyveClient* client, const char* name_or_address);
+WEYVE_API const char* weyve_network_interface(const WeyveClient* client, uint32_t* len); // the chosen interface's name; empty = every one
+WEYVE_API uint32_t weyve_list_network_interfaces(WeyveClient* client); // enumerates them afresh, one entry per address; read with weyve_network_interface_at
+WEYVE_API bool weyve_network_interface_at(const WeyveClient* client, uint32_t index, WeyveNetworkInterface* out); // false past the end
+// Session recording, for turning a bug met by hand into a regression test:
+// every message to and from the server, timed, written to path as it goes.
+// The Tests project replays such files. Only a DLL built with
+// WEYVE_RECORDING records; any other returns false.
+WEYVE_API bool weyve_start_recording(WeyveClient* client, const char* path); // replaces a running recording
+WEYVE_API void weyve_stop_recording(WeyveClient* client);
+// The client's log, for an in-app view: connection losses, failed links and
+// libjuice's own lines, the last 512 kept in memory. Process-wide, as
+// libjuice's logging is; lines below the level are never kept.
+WEYVE_API void weyve_set_log_level(WeyveLogLevel level);
+WEYVE_API WeyveLogLevel weyve_log_level(void);
+WEYVE_API uint32_t weyve_recent_logs(WeyveClient* client, WeyveLogLevel least, uint32_t limit); // the newest limit lines at least that level (0 = all), oldest first; read with weyve_log_at
+WEYVE_API bool weyve_log_at(const WeyveClient* client, uint32_t index, WeyveLogEntry* out); // false past the end
 WEYVE_API bool weyve_next(WeyveClient* client, WeyveEvent* out); // one queued event per call; false when drained
+WEYVE_API WeyveScope weyve_event_scope(const WeyveEvent* event, uint32_t* id); // id is the member for WEYVE_SCOPE_MEMBER, else 0
+WEYVE_API const char* weyve_event_name(WeyveEventType type); // stable, e.g. "peer_joined", for bindings that dispatch by name; static storage. null for NONE or a type this build does not know
+WEYVE_API bool weyve_event_error(const WeyveEvent* event, WeyveError* out); // false, out untouched, unless the event reports a failure
+WEYVE_API void weyve_set_error_text(WeyveClient* client, const char* key, const char* text); // a translation for key; may use {param}. null text restores English
+WEYVE_API const char* weyve_format_error(WeyveClient* client, const WeyveError* error, uint32_t* len); // the translated (else English) line, {param} filled in. Borrowed, valid until the next call
 
-WEYVE_API bool weyve_send_heartbeat(WeyveClient* client, uint64_t timestamp); // server pongs it back
+WEYVE_API bool weyve_send_heartbeat(WeyveClient* client, uint64_t timestamp); // server pongs it back, unless 0 (reserved); the pong also times weyve_round_trip_ms
+WEYVE_API uint32_t weyve_round_trip_ms(const WeyveClient* client); // as of the last heartbeat echoed; 0 until one is. Countdowns start half of it early
+WEYVE_API bool weyve_report_activity(WeyveClient* client); // keeps you from going AFK while the server sees nothing (p2p play)
+WEYVE_API bool weyve_get_server_info(WeyveClient* client); // -> WEYVE_EVENT_SERVER_INFO
+
+// For a minimized window or a suspended mobile app. In the background the
+// server holds back room state updates (data, roles, AFK flags, banner and
+// the like); membership, chat, whispers and direct links stay live. Coming
+// back replays the room as it stands, WEYVE_EVENT_ROOM_ID_ASSIGNED first, if
+// anything was held back.
+WEYVE_API bool weyve_set_background_mode(WeyveClient* client, bool background);
+WEYVE_API bool weyve_in_background(const WeyveClient* client);
+
+// --- accounts ---
+
+WEYVE_API bool weyve_create_account(WeyveClient* client, const char* name, const char* password, const char* email); // email may be null; -> WEYVE_EVENT_LOGGED_IN or WEYVE_EVENT_ACCOUNT_ERROR
+WEYVE_API bool weyve_login(WeyveClient* client, const char* name, const char* password); // -> WEYVE_EVENT_LOGGED_IN or WEYVE_EVENT_ACCOUNT_ERROR
+
+// No password: the server answers with the name made unique ("Alice#4821").
+// weyve_create_account with that exact name keeps it as a real account.
+WEYVE_API bool weyve_guest_login(WeyveClient* client, const char* name); // -> WEYVE_EVENT_LOGGED_IN or WEYVE_EVENT_ACCOUNT_ERROR
+
+// Finding someone by name across the whole server: online users first (whom
+// you can invite or whisper now), then accounts nobody is logged in to. The
+// query needs 2 to 32 bytes. Anyone can opt out; an account remembers it.
+WEYVE_API bool weyve_search_users(WeyveClient* client, const char* query); // -> WEYVE_EVENT_USER_SEARCH_RESULTS
+WEYVE_API bool weyve_set_searchable(WeyveClient* client, bool searchable); // -> WEYVE_EVENT_SEARCHABLE_CHANGED
+WEYVE_API bool weyve_searchable(const WeyveClient* client);
+
+// Everything the server holds about you (account, settings, bookings, RSVPs,
+// knocks), for a data access request. Once a minute at most.
+WEYVE_API bool weyve_export_my_data(WeyveClient* client); // -> WEYVE_EVENT_MY_DATA or WEYVE_EVENT_ACCOUNT_ERROR
+WEYVE_API const char* weyve_my_data(WeyveClient* client, uint32_t* len); // the last export as a JSON object; "{}" before one arrives. Borrowed, valid until the next call
+
+// --- preferences ---
+
+// dnd_until is unix seconds, 0 = off; do-not-disturb turns every whisper away until then.
+// An account keeps its preferences on the server and gets them back on every
+// login; otherwise they last as long as the connection. Whispers are all they
+// cover. Every invitation the server sends answers something you asked for
+// (a knock the host approved, a booking you own or RSVPed to), so there is no
+// invite permission. An unknown whispers value -> WEYVE_ACCOUNT_ERROR_BAD_PREFERENCES.
+WEYVE_API bool weyve_set_preferences(WeyveClient* client, uint64_t dnd_until, WeyveWhisperPolicy whispers); // -> WEYVE_EVENT_PREFERENCES_CHANGED
 
 // --- rooms ---
 
 WEYVE_API bool weyve_create_room(WeyveClient* client); // -> WEYVE_EVENT_ROOM_ID_ASSIGNED or WEYVE_EVENT_ROOM_ERROR
 WEYVE_API bool weyve_join_room(WeyveClient* client, const char* id, const char* password); // password may be null or ""
+WEYVE_API bool weyve_spectate_room(WeyveClient* client, const char* id, const char* password); // join without taking a player seat
 WEYVE_API bool weyve_leave_room(WeyveClient* client); // -> WEYVE_EVENT_PEER_LEFT carrying your own id
 
+// A lobby's detail panel: who is in a room and how it is set, without joining.
+// watch sends a fresh WEYVE_EVENT_ROOM_PREVIEW whenever that changes, for a
+// minute or until you look at another room; one watch at a time.
+WEYVE_API bool weyve_get_room_preview(WeyveClient* client, const char* id, bool watch); // -> WEYVE_EVENT_ROOM_PREVIEW or WEYVE_EVENT_ROOM_ERROR
+WEYVE_API bool weyve_stop_room_preview(WeyveClient* client);
+
+// Before committing to a join: looks at the room and says whether the join
+// would go in, for a seat of either kind. One check at a time.
+WEYVE_API bool weyve_preflight_join(WeyveClient* client, const char* id, bool spectating); // -> WEYVE_EVENT_JOIN_CHECKED, just ahead of the WEYVE_EVENT_ROOM_PREVIEW or WEYVE_EVENT_ROOM_ERROR it was read from
+
+// Knocking on a passworded room: the host sees WEYVE_EVENT_JOIN_REQUESTED and
+// approves or denies; approval lets you join or spectate without the password.
+WEYVE_API bool weyve_request_join(WeyveClient* client, const char* id, const char* message); // message may be null; -> WEYVE_EVENT_JOIN_REQUEST_ANSWERED or WEYVE_EVENT_ROOM_ERROR
+WEYVE_API bool weyve_respond_join_request(WeyveClient* client, uint32_t id, bool approve); // host-only
+
+// Instead of giving up on WEYVE_ROOM_ERROR_ROOM_FULL: wait in line for a
+// player seat. Nobody walks in past the line, and a seat offered to you is
+// held a short while. One line at a time; joining any room leaves it.
+WEYVE_API bool weyve_queue_for_room(WeyveClient* client, const char* id, const char* password); // password may be null; -> WEYVE_EVENT_QUEUE_POSITION or WEYVE_EVENT_ROOM_ERROR
+WEYVE_API bool weyve_leave_room_queue(WeyveClient* client); // -> WEYVE_EVENT_QUEUE_POSITION with position 0
+
 WEYVE_API bool weyve_kick_member(WeyveClient* client, uint32_t id); // host-only
 WEYVE_API bool weyve_ban_member(WeyveClient* client, uint32_t id); // host-only
 WEYVE_API bool weyve_transfer_host(WeyveClient* client, uint32_t id); // host-only
 
+// Moving members between rooms, e.g. one room per match out of a big lobby.
+// Whoever moves gets WEYVE_EVENT_ROOM_MOVED, then the new room as on any join.
+WEYVE_API bool weyve_split_room(WeyveClient* client, const uint32_t* members, uint32_t count); // host-only; to a new room the first of them hosts -> WEYVE_EVENT_ROOM_SPLIT or WEYVE_EVENT_ROOM_ERROR
+WEYVE_API bool weyve_merge_room(WeyveClient* client, const char* id); // host-only; asks room id's host to move their room into yours -> WEYVE_EVENT_MERGE_ANSWERED
+WEYVE_API bool weyve_answer_merge(WeyveClient* client, const char* into, bool accept); // host-only; accepting moves everyone, you last, or fails whole with WEYVE_EVENT_ROOM_ERROR
+WEYVE_API bool weyve_set_member_role(WeyveClient* client, uint32_t id, WeyveMemberRole role); // host-only
+
+// A synchronized start: host-only, up to 60 seconds, 0 cancels. Every member
+// gets WEYVE_EVENT_COUNTDOWN, then the client's own ticks and
+// WEYVE_EVENT_MATCH_START, timed off the server's clock rather than any
+// peer's. Ticks are only as punctual as weyve_poll; for a frame-exact start
+// read weyve_countdown_remaining_ms each frame. Heartbeats sharpen it.
+WEYVE_API bool weyve_start_countdown(WeyveClient* client, uint32_t seconds);
+WEYVE_API bool weyve_countdown_remaining_ms(const WeyveClient* client, uint32_t* ms); // false when none is running
+WEYVE_API bool weyve_pause_match(WeyveClient* client); // host-only; calls off a running countdown -> WEYVE_EVENT_MATCH_PAUSED
+WEYVE_API bool weyve_resume_match(WeyveClient* client); // host-only -> WEYVE_EVENT_MATCH_PAUSED
+WEYVE_API bool weyve_match_paused(const WeyveClient* client);
+
+// A dedicated game server run on the host's machine: the host's lobby and
+// the game's own console in one place. Launching and commanding are
+// host-only; the process outlives a host change, not the client. Its output
+// arrives as WEYVE_EVENT_GAME_SERVER_OUTPUT lines from weyve_poll.
+WEYVE_API bool weyve_launch_game_server(WeyveClient* client, const char* const* argv, uint32_t argc); // argv[0] is the program, found on PATH; false if not host, one runs already, or it could not start
+WEYVE_API bool weyve_send_console_command(WeyveClient* client, const char* command); // one line to its stdin; false if not host, not running, or it is not reading
+WEYVE_API void weyve_stop_game_server(WeyveClient* client); // kills it -> WEYVE_EVENT_GAME_SERVER_EXITED
+WEYVE_API bool weyve_game_server_running(const WeyveClient* client);
+
 WEYVE_API bool weyve_set_room_joinable(WeyveClient* client, bool open); // host-only
 WEYVE_API bool weyve_set_room_password(WeyveClient* client, const char* password); // host-only; null or "" clears it
+WEYVE_API bool weyve_set_room_capacity(WeyveClient* client, uint32_t max_players, uint32_t max_spectators); // host-only; 0 = no limit
+WEYVE_API bool weyve_set_idle_policy(WeyveClient* client, uint32_t afk_after, bool spectate); // host-only; seconds, 0 = never
+WEYVE_API bool weyve_set_room_banner(WeyveClient* client, const char* id); // host-only, an uploaded blob; null or "" clears it
+WEYVE_API bool weyve_set_room_rules(WeyveClient* client, const char* rules); // host-only, up to 8 KiB of match settings (JSON by convention); null or "" clears them
+
+// Host hand-off, for state a game keeps at its host: a history buffer for
+// resync, a dedicated server's world. Rules, teams, data and countdowns live
+// on the server and need none of this. The host saves its state now and
+// then, and once more just before leaving or handing over; whoever hosts
+// next gets the last save in WEYVE_EVENT_HOST_HANDOFF.
+WEYVE_API bool weyve_set_host_state(WeyveClient* client, const void* data, uint32_t len); // host-only, up to 12 KiB, opaque to the server; len 0 clears it
+WEYVE_API bool weyve_report_host_state(WeyveClient* client, const void* data, uint32_t len); // your view of it, for the new host: once per WEYVE_EVENT_HOST_STATE_WANTED; any other is refused with WEYVE_ROOM_ERROR_BAD_ROOM_DATA
+WEYVE_API bool weyve_set_succession(WeyveClient* client, WeyveSuccessionPolicy policy, const uint32_t* successors, uint32_t count); // host-only; up to 16 successors, in order, read only under WEYVE_SUCCESSION_SUCCESSORS
 
 WEYVE_API bool weyve_send_chat(WeyveClient* client, const char* text);
+WEYVE_API bool weyve_send_whisper(WeyveClient* client, uint32_t id, const char* text); // direct when a p2p link is up, else via the server
 WEYVE_API bool weyve_set_room_data(WeyveClient* client, const char* key, const char* value); // host-only
 WEYVE_API bool weyve_delete_room_data(WeyveClient* client, const char* key); // host-only
 WEYVE_API bool weyve_set_member_data(WeyveClient* client, const char* key, const char* value); // your own slots
 WEYVE_API bool weyve_delete_member_data(WeyveClient* client, const char* key);
+WEYVE_API bool weyve_set_member_data_for(WeyveClient* client, uint32_t id, const char* key, const char* value); // host-only: anyone's slots (teams, colors); "" deletes
+
+// Teams, host-only; each change reaches the room as WEYVE_EVENT_TEAMS_CHANGED,
+// a refusal as WEYVE_EVENT_ROOM_ERROR. Spectators are on no team.
+WEYVE_API bool weyve_create_team(WeyveClient* client, const char* name, uint32_t max_size); // max_size 0 = no limit
+WEYVE_API bool weyve_remove_team(WeyveClient* client, uint32_t team); // its members end up on no team
+WEYVE_API bool weyve_assign_team(WeyveClient* client, uint32_t id, uint32_t team); // team 0 = off every team
+WEYVE_API bool weyve_balance_teams(WeyveClient* client); // every player dealt out again in join order, to the emptiest team with room
+
+// Breakout channels: sub-groups inside the room, e.g. a team's own talk. The
+// host makes them and may place anyone; members move themselves. Each change
+// reaches the room as WEYVE_EVENT_CHANNELS_CHANGED, a refusal as
+// WEYVE_EVENT_ROOM_ERROR. Nobody leaves the room, and p2p links stay up.
+WEYVE_API bool weyve_create_channel(WeyveClient* client, const char* name); // host-only
+WEYVE_API bool weyve_remove_channel(WeyveClient* client, uint32_t channel); // host-only; its members end up in none
+WEYVE_API bool weyve_place_in_channel(WeyveClient* client, uint32_t id, uint32_t channel); // yourself, or anyone as host; channel 0 = out of every channel
+WEYVE_API bool weyve_send_channel_chat(WeyveClient* client, const char* text); // -> WEYVE_EVENT_CHANNEL_CHAT to your channel only
+
+// --- composing ---
+
+// A chat or whisper sent outside a room is held rather than dropped: the send
+// returns false and WEYVE_EVENT_MESSAGE_SEND_FAILED follows with its id.
+WEYVE_API bool weyve_retry_message(WeyveClient* client, uint32_t id); // false, and still held, while not in a room
+WEYVE_API bool weyve_discard_message(WeyveClient* client, uint32_t id);
+
+// Unsent drafts, one per scope (any key the app likes, e.g. a room id). Kept
+// in memory only; walk them with weyve_draft_scope_at to save them yourself.
+WEYVE_API void weyve_set_draft(WeyveClient* client, const char* scope, const char* text); // null or "" clears it
+WEYVE_API const char* weyve_draft(const WeyveClient* client, const char* scope, uint32_t* len); // null if none
+WEYVE_API uint32_t weyve_draft_count(const WeyveClient* client);
+WEYVE_API const char* weyve_draft_scope_at(const WeyveClient* client, uint32_t index, uint32_t* len);
+
+// Chat heard since the client was created, room lines and whispers both ways,
+// up to the last 2000 across every room; kept in memory only, and the server
+// keeps none. Writes it to path, for a match record or the like.
+WEYVE_API bool weyve_export_chat(const WeyveClient* client, const char* room, WeyveChatFormat format, const char* path); // room null or "" = every room; false if path could not be written
+WEYVE_API uint32_t weyve_chat_history_count(const WeyveClient* client);
+WEYVE_API bool weyve_chat_line_at(const WeyveClient* client, uint32_t index, WeyveChatLine* out); // oldest first; false past the end
+
+// Finds history lines holding every word of query, case ignored, newest
+// first. Writes up to max line indexes to lines and returns how many; read
+// them, and the lines around them for context, with weyve_chat_line_at
+// before the next weyve_poll moves the history on.
+WEYVE_API uint32_t weyve_search_messages(const WeyveClient* client, const char* query, const char* room, uint32_t* lines, uint32_t max); // room null or "" = every room
+
+// --- blobs ---
+
+// Small assets (avatars, thumbnails) stored on the server under the hash of
+// their bytes. Both return the transfer id the events carry, 0 if nothing was
+// sent. A blob no room or member data refers to is collected after a while.
+WEYVE_API uint32_t weyve_upload_blob(WeyveClient* client, const void* data, uint32_t len); // -> WEYVE_EVENT_BLOB_UPLOADED or WEYVE_EVENT_BLOB_ERROR
+WEYVE_API uint32_t weyve_download_blob(WeyveClient* client, const char* id); // -> WEYVE_EVENT_BLOB_DOWNLOADED or WEYVE_EVENT_BLOB_ERROR
+// A downloaded blob's bytes; null unless it is among the last few downloaded.
+// Borrowed, valid until the next weyve_poll.
+WEYVE_API const uint8_t* weyve_blob(const WeyveClient* client, const char* id, uint32_t* len);
+// weyve_blob for banners and the like: on a miss it also starts the download,
+// once however often it is asked, and WEYVE_EVENT_BLOB_DOWNLOADED says when to ask again.
+WEYVE_API const uint8_t* weyve_get_blob(WeyveClient* client, const char* id, uint32_t* len);
+
+// --- scheduled rooms ---
+
+// Log in first to keep a booking or an RSVP across reconnects: an account's
+// are held by name, anyone else's go when the connection does.
+// start is unix seconds; password may be null or "". -> WEYVE_EVENT_SCHEDULED_ROOM_CHANGED or WEYVE_EVENT_ROOM_ERROR
+WEYVE_API bool weyve_schedule_room(WeyveClient* client, uint64_t start, const char* title, uint32_t max_players, uint32_t max_spectators, const char* password);
+WEYVE_API bool weyve_list_scheduled_rooms(WeyveClient* client); // -> WEYVE_EVENT_SCHEDULED_ROOM_LIST
+WEYVE_API bool weyve_rsvp_scheduled_room(WeyveClient* client, uint32_t id, WeyveRsvpStatus status); // -> WEYVE_EVENT_SCHEDULED_ROOM_CHANGED
+WEYVE_API bool weyve_list_attendees(WeyveClient* client, uint32_t id); // -> WEYVE_EVENT_SCHEDULED_ROOM_ATTENDEES
+WEYVE_API bool weyve_cancel_scheduled_room(WeyveClient* client, uint32_t id); // owner only. -> WEYVE_EVENT_SCHEDULED_ROOM_CANCELLED or WEYVE_EVENT_ROOM_ERROR
 
 // --- peer to peer ---
 
 WEYVE_API bool weyve_send_p2p(WeyveClient* client, uint32_t id, const void* data, uint32_t len); // first send builds the link
+WEYVE_API bool weyve_send_p2p_channel(WeyveClient* client, const void* data, uint32_t len); // weyve_send_p2p to each other member of your channel; false if in none
 // One received datagram; returns its bytes or null when none are queued. The
 // buffer borrows client-owned storage, valid until the next weyve_next_p2p call.
 WEYVE_API const uint8_t* weyve_next_p2p(WeyveClient* client, uint32_t* from, uint32_t* len);
+// Ordered mode; raw, the default, hands datagrams on as they land. Ordered,
+// what you send carries a sequence number and what arrives numbered comes
+// out of weyve_next_p2p in order, duplicates and stragglers dropped. A gap
+// is waited on for up to jitter_ms (at most 500), then skipped: losses stay,
+// reordering goes. Peers on older versions still get raw datagrams.
+WEYVE_API void weyve_set_p2p_ordering(WeyveClient* client, bool ordered, uint32_t jitter_ms);
+WEYVE_API bool weyve_p2p_ordered(const WeyveClient* client);
+WEYVE_API uint32_t weyve_jitter_delay_ms(const WeyveClient* client);
+// Forward error correction for ordered mode on lossy routes, off by
+// default. Allowed on both ends, a receiver losing 5% or more asks the
+// sender for an XOR parity frame after every 4 datagrams, a quarter more
+// bytes, and rebuilds any one lost among them; below 1% it asks them to stop.
+WEYVE_API void weyve_set_p2p_fec(WeyveClient* client, bool allowed);
+WEYVE_API bool weyve_p2p_fec(const WeyveClient* client);
+WEYVE_API uint32_t weyve_peer_fec_group(const WeyveClient* client, uint32_t id); // we send them parity after every this many; 0 = none
+// For development: a bad network between the mesh and this client. Every
+// datagram arriving from a peer is dropped at loss_permille and held for
+// latency_ms (at most 2000) first, so ordering, FEC and the game's own
+// netcode can be watched under loss on a LAN. 0, 0 turns it off.
+WEYVE_API void weyve_simulate_network(WeyveClient* client, uint32_t loss_permille, uint32_t latency_ms);
+WEYVE_API uint32_t weyve_peer_loss_permille(const WeyveClient* client, uint32_t id); // ordered datagrams from them lost on the wire, last reading
 WEYVE_API bool weyve_peer_connected(WeyveClient* client, uint32_t id); // is a direct link up right now?
+WEYVE_API bool weyve_peer_link_path(WeyveClient* client, uint32_t id, WeyveLinkPath* out); // which addresses and interface it runs over; false unless connected
+WEYVE_API bool weyve_retry_peer_connection(WeyveClient* client, uint32_t id); // redial now, with a fresh set of attempts; true if dialing or already up
+// Session health: every 10 seconds each member sums up its links (up or
+// not, relayed or not, loss, bytes per second) and reports it to the host,
+// who gets WEYVE_EVENT_MEMBER_HEALTH and reads the whole who-reaches-whom
+// matrix here, their own row included. The server keeps each member's last
+// report while they stay, for the host or a co-host to fetch, e.g. after
+// taking over. share also lets the server count the report, with no ids, in
+// the totals it logs for its operator. Reporting is on and unshared by
+// default.
+WEYVE_API void weyve_set_health_reporting(WeyveClient* client, bool report, bool share);
+WEYVE_API uint32_t weyve_get_session_health(WeyveClient* client); // host-only: takes a snapshot, one row per member that reported; 0 for anyone else
+WEYVE_API bool weyve_session_health_at(const WeyveClient* client, uint32_t index, WeyveMemberHealth* out); // a snapshot row; false past the end
+WEYVE_API bool weyve_session_health_link_at(const WeyveClient* client, uint32_t row, uint32_t index, WeyveLinkHealth* out); // one of its links; false past the end
+WEYVE_API bool weyve_request_session_health(WeyveClient* client); // host or co-host -> WEYVE_EVENT_SESSION_HEALTH or WEYVE_EVENT_ROOM_ERROR
+WEYVE_API bool weyve_reported_health_at(const WeyveClient* client, uint32_t index, WeyveMemberHealth* out, uint32_t* age); // from the last reply; age (may be null) is seconds since the server got it. False past the end
+WEYVE_API bool weyve_reported_health_link_at(const WeyveClient* client, uint32_t row, uint32_t index, WeyveLinkHealth* out); // false past the end
+WEYVE_API bool weyve_get_join_receipts(WeyveClient* client); // host or co-host -> WEYVE_EVENT_JOIN_RECEIPTS or WEYVE_EVENT_ROOM_ERROR
+WEYVE_API bool weyve_join_receipt_at(const WeyveClient* client, uint32_t index, WeyveJoinReceipt* out); // from the last reply, oldest first; false past the end
+WEYVE_API const char* weyve_join_receipt_text(WeyveClient* client, uint32_t index, uint32_t* len); // the bytes its signature covers, to check it against the key; null past the end. Borrowed, valid until the next call
+// Drops the direct link (and anything queued on it); notify asks the peer to
+// drop theirs too. -> WEYVE_EVENT_PEER_CLOSED. False if there was no link.
+WEYVE_API bool weyve_close_peer_connection(WeyveClient* client, uint32_t id, bool notify);
 
 // --- cached room state ---
 // Getters below read the client's cache; nothing blocks or hits the network.
 
 WEYVE_API uint32_t weyve_id(const WeyveClient* client); // 0 until the server assigns one
+// Account names as byte ranges; null for anonymous. Borrowed, valid until the next weyve_poll.
+WEYVE_API const char* weyve_name(const WeyveClient* client, uint32_t* len);
+WEYVE_API const char* weyve_member_name(const WeyveClient* client, uint32_t id, uint32_t* len);
 WEYVE_API uint32_t weyve_host_id(const WeyveClient* client); // 0 until a room is joined
 WEYVE_API bool weyve_is_host(const WeyveClient* client);
+WEYVE_API WeyveMemberRole weyve_member_role(const WeyveClient* client, uint32_t id); // WEYVE_ROLE_MEMBER unless promoted
+WEYVE_API uint64_t weyve_dnd_until(const WeyveClient* client); // 0 = do-not-disturb off
+WEYVE_API WeyveWhisperPolicy weyve_whisper_policy(const WeyveClient* client);
 WEYVE_API bool weyve_room_joinable(const WeyveClient* client);
 WEYVE_API bool weyve_room_passworded(const WeyveClient* client); // the flag only; the password never reaches clients
+WEYVE_API uint32_t weyve_max_players(const WeyveClient* client); // 0 = no limit
+WEYVE_API uint32_t weyve_max_spectators(const WeyveClient* client); // 0 = no limit
+WEYVE_API uint32_t weyve_player_count(const WeyveClient* client); // members not spectating, you included
+WEYVE_API uint32_t weyve_spectator_count(const WeyveClient* client);
+WEYVE_API bool weyve_is_spectator(const WeyveClient* client, uint32_t id);
+WEYVE_API bool weyve_is_afk(const WeyveClient* client, uint32_t id);
+WEYVE_API uint32_t weyve_afk_after(const WeyveClient* client); // seconds; 0 = never
+WEYVE_API bool weyve_afk_spectates(const WeyveClient* client); // do AFK players lose their seat?
+WEYVE_API WeyveSuccessionPolicy weyve_succession_policy(const WeyveClient* client);
+WEYVE_API const uint32_t* weyve_successors(const WeyveClient* client, uint32_t* count); // the host's list, in order. Borrowed, valid until the next weyve_poll
+WEYVE_API const char* weyve_room_banner(const WeyveClient* client, uint32_t* len); // a blob id; len 0 = none. Borrowed, valid until the next weyve_poll
+WEYVE_API const char* weyve_room_rules(const WeyveClient* client, uint32_t* len, uint32_t* version); // len 0 = none; version 0 = never set. Borrowed, valid until the next weyve_poll
 
 // Current room id as a byte range; len 0 means not in a room. Borrowed, valid until the next weyve_poll.
 WEYVE_API const char* weyve_room_id(const WeyveClient* client, uint32_t* len);
@@ -128,6 +926,22 @@ WEYVE_API const char* weyve_room_id(const WeyveClient* client, uint32_t* len);
 // Room members, ourselves included. Borrowed, valid until the next weyve_poll.
 WEYVE_API const uint32_t* weyve_members(const WeyveClient* client, uint32_t* count);
 
+// The room's history, oldest first, up to its last 100 entries: the log as
+// joined, plus each WEYVE_EVENT_TIMELINE_ENTRY since. Borrowed, valid until the next weyve_poll.
+WEYVE_API uint32_t weyve_timeline_count(const WeyveClient* client);
+WEYVE_API bool weyve_timeline_at(const WeyveClient* client, uint32_t index, WeyveTimelineEntry* out); // false past the end
+WEYVE_API bool weyve_get_timeline(WeyveClient* client, uint32_t after); // fetches it again past seq after -> WEYVE_EVENT_TIMELINE or WEYVE_EVENT_ROOM_ERROR
+
+// The room's teams, in creation order. Borrowed, valid until the next weyve_poll.
+WEYVE_API uint32_t weyve_team_count(const WeyveClient* client);
+WEYVE_API bool weyve_team_at(const WeyveClient* client, uint32_t index, WeyveTeam* out); // false past the end
+WEYVE_API uint32_t weyve_team_of(const WeyveClient* client, uint32_t id); // the member's team id; 0 = none
+
+// The room's breakout channels, in creation order. Borrowed, valid until the next weyve_poll.
+WEYVE_API uint32_t weyve_channel_count(const WeyveClient* client);
+WEYVE_API bool weyve_channel_at(const WeyveClient* client, uint32_t index, WeyveChannel* out); // false past the end
+WEYVE_API uint32_t weyve_channel_of(const WeyveClient* client, uint32_t id); // the member's channel id; 0 = none
+
 // Room metadata: look one key up, or walk the keys by index. Values are byte
 // ranges; a null return means the key is unset. Borrowed, valid until the next weyve_poll.
 WEYVE_API const char* weyve_room_data(const WeyveClient* client, const char* key, uint32_t* value_len);
@@ -139,6 +953,26 @@ WEYVE_API const char* weyve_member_data(const WeyveClient* client, uint32_t id,
 WEYVE_API uint32_t weyve_member_data_count(const WeyveClient* client, uint32_t id);
 WEYVE_API const char* weyve_member_data_key_at(const WeyveClient* client, uint32_t id, uint32_t index, uint32_t* key_len);
 
+// The cached booking board, soonest first; false past the end. The title is
+// borrowed, valid until the next weyve_poll.
+WEYVE_API uint32_t weyve_scheduled_room_count(const WeyveClient* client);
+WEYVE_API bool weyve_scheduled_room_at(const WeyveClient* client, uint32_t index, WeyveScheduledRoom* out);
+
+// A booking's attendees as last heard, in client id order; false past the end.
+WEYVE_API uint32_t weyve_attendee_count(const WeyveClient* client, uint32_t booking);
+WEYVE_API bool weyve_attendee_at(const WeyveClient* client, uint32_t booking, uint32_t index, uint32_t* id, WeyveRsvpStatus* status);
+
+// The last room preview heard: members in join order, data in key order;
+// false past the end. Names (null for anonymous) and data are borrowed,
+// valid until the next weyve_poll.
+WEYVE_API bool weyve_preview_member_at(const WeyveClient* client, uint32_t index, uint32_t* id, const char** name, uint32_t* name_len, bool* spectating);
+WEYVE_API bool weyve_preview_data_at(const WeyveClient* client, uint32_t index, const char** key, uint32_t* key_len, const char** value, uint32_t* value_len);
+
+// The last user search's matches, best first; false past the end. id is 0 for
+// someone offline; room (len 0 = not shown) is set only for rooms anyone may
+// walk into. Borrowed, valid until the next weyve_poll.
+WEYVE_API bool weyve_user_result_at(const WeyveClient* client, uint32_t index, const char** name, uint32_t* name_len, uint32_t* id, const char** room, uint32_t* room_len);
+
 #ifdef __cplusplus
 }
 #endif
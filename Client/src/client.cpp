@@ -4,8 +4,13 @@
 #define _WIN32_WINNT 0x0A00
 #endif // _WIN32
 
+#include <algorithm>
 #include <array>
+#include <cctype>
+#include <chrono>
+#include <fstream>
 #include <span>
+#include <thread>
 #include <vector>
 
 #include <thirdparty\asio\asio.hpp>
@@ -13,8 +18,21 @@
 
 #include "framing.h"
 #include "p2p_mesh.h"
+#include "unix_time.h"
+#include "weyvelength.h" // WEYVE_API_VERSION, for ClientInfo
 
 namespace Weyvelength {
+	constexpr std::chrono::milliseconds disconnect_flush_timeout{ 500 }; // how long Disconnect waits on the goodbyes
+#if defined(_WIN32)
+	constexpr const char* platform_name = "windows"; // in every ClientInfo
+#elif defined(__APPLE__)
+	constexpr const char* platform_name = "macos";
+#elif defined(__linux__)
+	constexpr const char* platform_name = "linux";
+#else
+	constexpr const char* platform_name = "other";
+#endif
+
 	struct ClientAsioImpl {
 		asio::io_context context;
 		asio::ip::tcp::socket socket{ context };
@@ -23,144 +41,1098 @@ namespace Weyvelength {
 		std::vector<std::byte> tx;   // framed bytes queued to send
 	};
 
-	Client::Client() :
-		_asio(std::make_unique<ClientAsioImpl>()),
-		_mesh(std::make_unique<P2PMesh>()) {
+	Client::Client() :
+		_asio(std::make_unique<ClientAsioImpl>()),
+		_mesh(std::make_unique<P2PMesh>()),
+		_game(std::make_unique<ProcessManager>()) {
+		SetLogLevel(ClientLog().Level()); // hooks libjuice's log up, once per process
+	}
+
+	Client::~Client()
+	{
+		Disconnect();
+		DestroyAllLinks();
+	}
+
+	bool Client::Connect(ClientConfig& config)
+	{
+		auto& impl = *_asio;
+		asio::error_code ec;
+
+		asio::ip::tcp::resolver resolver{ impl.context };
+		auto endpoints = resolver.resolve(config.host, std::to_string(config.port), ec);
+		if (ec) return false;
+
+		asio::connect(impl.socket, endpoints, ec);
+		if (ec) return false;
+
+		impl.socket.non_blocking(true, ec);
+		if (ec)
+			return false;
+
+		_server = config;
+		SendServer(Proto::ClientInfo{ _app_version, platform_name, WEYVE_API_VERSION });
+		SendServer(Proto::KeyScope{ _key_scope });
+		return true;
+	}
+
+	void Client::SetAppVersion(std::string version)
+	{
+		if (version.size() > Proto::max_client_info_field)
+			version.resize(Proto::max_client_info_field);
+		_app_version = std::move(version);
+	}
+
+	std::vector<std::byte> Client::SaveSession() const
+	{
+		std::vector<std::byte> data;
+		if (_room.empty())
+			return data;
+
+		SessionState state = _session;
+		state.server = _server;
+		state.room = _room;
+		if (failure(zpp::bits::out{ data }(session_state_version, state.server.host, state.server.port, state.room, state.password, state.spectating, state.guest)))
+			data.clear();
+		return data;
+	}
+
+	bool Client::ResumeSession(std::span<const std::byte> saved)
+	{
+		uint8_t version = 0;
+		SessionState state;
+		zpp::bits::in in{ saved };
+		if (failure(in(version)) || version != session_state_version)
+			return false; // from a client too old or too new to read
+		if (failure(in(state.server.host, state.server.port, state.room, state.password, state.spectating, state.guest)) || state.room.empty())
+			return false;
+
+		if (!_room.empty())
+			return false;
+		if (!_asio->socket.is_open() && !Connect(state.server))
+			return false;
+
+		if (!state.guest.empty())
+			GuestLogin(state.guest); // ahead of the join, so the room hears our name with it
+
+		// the mesh is lazy, so direct links come back with the first SendP2P to each member
+		return state.spectating ? SpectateRoom(state.room, state.password) : JoinRoom(state.room, state.password);
+	}
+
+	// A TCP connection does not survive its local address going away, and ICE
+	// candidates gathered on the old network are dead too. The new connection
+	// rejoins before the old one is dropped, so a room we are alone in is
+	// still there to come back to. The old connection goes without goodbyes:
+	// it may have no route left, and the server times it out as usual.
+	bool Client::NetworkChanged()
+	{
+		if (!_asio->socket.is_open())
+			return false;
+
+		std::vector<std::byte> saved = SaveSession();
+		auto old = std::exchange(_asio, std::make_unique<ClientAsioImpl>());
+		if (!Connect(_server)) {
+			_asio = std::move(old); // maybe the old network still works
+			return false;
+		}
+		_signal_link.reset(); // on the old network too; the new connection says where to dial
+
+		std::deque<KeyedRequest> keyed = std::move(_keyed); // whatever may have died with the old connection
+		_keyed.clear();
+		ClearRoomState(); // every link included; they rebuild on the next SendP2P
+		if (uint16_t port = PortMappingPort()) {
+			_port_mapper.reset(); // a new router, most likely; ask it afresh
+			_port_mapper = std::make_unique<PortMapper>(port);
+		}
+		_downloads.clear(); // their chunks were on the old connection
+		_checking = {}; // its preview went with the old connection; the app can ask again
+		_id = 0; // the server assigns a new one
+		_name.clear(); // a guest gets theirs back with the rejoin; an account logs in again
+		SetPreferences(_prefs); // they lived with the old connection
+		if (!saved.empty())
+			ResumeSession(saved);
+		if (_background)
+			SendServer(Proto::SetBackground{ true });
+		if (!_searchable)
+			SendServer(Proto::SetSearchable{ false }); // an account's login says so again anyway
+
+		// resent under the same keys, and the same KeyScope went out with
+		// Connect: the server drops what it already handled, logged in again
+		// or not. Chat only follows a rejoin, and a room create only matters
+		// if there was no room to rejoin
+		auto now = std::chrono::steady_clock::now();
+		for (KeyedRequest& request : keyed) {
+			if (now - request.sent >= std::chrono::seconds(Proto::idempotency_window))
+				continue;
+			if (std::holds_alternative<Proto::CreateRoom>(request.msg) != saved.empty())
+				continue;
+			if (std::holds_alternative<Proto::CreateRoom>(request.msg))
+				Await(RequestKind::Room);
+			SendServer(Proto::Idempotent{ request.key });
+			SendServer(request.msg);
+			_keyed.push_back(std::move(request));
+		}
+
+		FlushServer();
+		asio::error_code ec;
+		old->socket.close(ec);
+		return true;
+	}
+
+	// The polite way out: members see a PeerLeft and peers a Closed signal
+	// right away, rather than waiting for the server to notice the socket
+	// drop or for ICE to time out.
+	void Client::Disconnect()
+	{
+		auto& impl = *_asio;
+		if (!impl.socket.is_open())
+			return;
+
+		_signal_link.reset(); // so the goodbyes below go out with the main connection's flush
+		std::vector<uint32_t> peers;
+		for (const auto& [id, link] : _mesh->links)
+			peers.push_back(id);
+		for (const auto& [id, retry] : _mesh->retries)
+			peers.push_back(id);
+		for (uint32_t id : peers)
+			ClosePeerConnection(id);
+
+		if (!_room.empty())
+			LeaveRoom();
+
+		// the socket is non-blocking, so give the goodbyes a moment to go out
+		auto deadline = std::chrono::steady_clock::now() + disconnect_flush_timeout;
+		while (FlushServer() && !impl.tx.empty() && std::chrono::steady_clock::now() < deadline)
+			std::this_thread::sleep_for(std::chrono::milliseconds(1));
+
+		asio::error_code ec;
+		impl.socket.shutdown(asio::ip::tcp::socket::shutdown_both, ec);
+		DisconnectServer();
+	}
+
+	bool Client::Poll()
+	{
+		_game->Poll(_game_events); // a local process; it runs with or without the server
+
+		if (!_asio->socket.is_open())
+			return false;
+
+		if (!DrainServer() || !CarveServer())
+			return false;
+
+		PollPeers(); // may queue signal frames; the flushes below send them
+		PollSignalLink();
+		PollTimeouts();
+		PollCountdown();
+		PollHealth();
+		return FlushServer();
+	}
+
+	void Client::Await(RequestKind kind)
+	{
+		_awaiting[kind] = std::chrono::steady_clock::now() + request_timeout;
+	}
+
+	void Client::PollTimeouts()
+	{
+		auto now = std::chrono::steady_clock::now();
+		for (auto it = _awaiting.begin(); it != _awaiting.end();) {
+			if (now < it->second) {
+				++it;
+				continue;
+			}
+			_timeouts.push(it->first);
+			Log(LogLevel::Warn, "The server did not answer a request in time");
+			it = _awaiting.erase(it);
+		}
+	}
+
+	bool Client::NextTimeout(RequestKind& out)
+	{
+		if (_timeouts.empty())
+			return false;
+
+		out = _timeouts.front();
+		_timeouts.pop();
+		return true;
+	}
+
+	void Client::SetPerformanceProfile(PerformanceProfile profile)
+	{
+		_profile = profile;
+	}
+
+	PerformanceProfile Client::Performance() const
+	{
+		return _profile;
+	}
+
+	void Client::SetLinkTimeout(std::chrono::seconds timeout)
+	{
+		_link_timeout = timeout.count() == 0 ? default_link_timeout : std::clamp(timeout, min_link_timeout, max_link_timeout);
+	}
+
+	std::chrono::seconds Client::LinkTimeout() const
+	{
+		return _link_timeout;
+	}
+
+	void Client::SetPortMapping(uint16_t port)
+	{
+		if (port == PortMappingPort())
+			return;
+		_port_mapper.reset(); // the old mapping goes first, in case the router holds one per client
+		if (port != 0)
+			_port_mapper = std::make_unique<PortMapper>(port);
+	}
+
+	uint16_t Client::PortMappingPort() const
+	{
+		return _port_mapper ? _port_mapper->Port() : 0;
+	}
+
+	bool Client::SetNetworkInterface(const std::string& name_or_address)
+	{
+		if (name_or_address.empty()) {
+			_interface = {};
+			return true;
+		}
+
+		// by name, an IPv4 address first: that is what a VPN's peers mostly
+		// reach; else by one of the addresses itself
+		auto same_name = [&](const NetworkInterface& iface) {
+			return std::equal(iface.name.begin(), iface.name.end(), name_or_address.begin(), name_or_address.end(),
+				[](char a, char b) { return std::tolower((unsigned char)a) == std::tolower((unsigned char)b); });
+		};
+		std::vector<NetworkInterface> interfaces = ListNetworkInterfaces();
+		std::stable_partition(interfaces.begin(), interfaces.end(), [](const NetworkInterface& iface) { return iface.address.find(':') == std::string::npos; });
+		auto match = std::find_if(interfaces.begin(), interfaces.end(), same_name);
+		if (match == interfaces.end())
+			match = std::find_if(interfaces.begin(), interfaces.end(), [&](const NetworkInterface& iface) { return iface.address == name_or_address; });
+		if (match == interfaces.end())
+			return false;
+
+		_interface = *match;
+		return true;
+	}
+
+	const NetworkInterface& Client::BoundInterface() const
+	{
+		return _interface;
+	}
+
+	bool Client::StartRecording(const std::string& path)
+	{
+#ifdef WEYVE_RECORDING
+		StopRecording();
+		_recording.open(path, std::ios::binary | std::ios::trunc);
+		if (!_recording || !Proto::WriteRecordingHeader(_recording)) {
+			_recording.close();
+			return false;
+		}
+		_recording_start = std::chrono::steady_clock::now();
+		return true;
+#else
+		(void)path;
+		return false; // a release build never writes traffic to disk
+#endif
+	}
+
+	void Client::StopRecording()
+	{
+#ifdef WEYVE_RECORDING
+		_recording.close();
+#endif
+	}
+
+	bool Client::Recording() const
+	{
+#ifdef WEYVE_RECORDING
+		return _recording.is_open();
+#else
+		return false;
+#endif
+	}
+
+	void Client::Record(Proto::RecordDirection direction, const Proto::ServerMessage& msg)
+	{
+#ifdef WEYVE_RECORDING
+		if (!_recording.is_open())
+			return;
+		uint64_t at = (uint64_t)std::chrono::duration_cast<std::chrono::milliseconds>(std::chrono::steady_clock::now() - _recording_start).count();
+		if (!Proto::WriteRecorded(_recording, { at, direction, msg }))
+			_recording.close(); // a full disk: stop, rather than leave a torn message mid-file
+		_recording.flush(); // the session being recorded may well end in a crash
+#else
+		(void)direction;
+		(void)msg;
+#endif
+	}
+
+	bool Client::Next(Proto::ServerMessage& out)
+	{
+		std::string room;
+		return Next(out, room);
+	}
+
+	bool Client::Next(Proto::ServerMessage& out, std::string& room)
+	{
+		if (_inbox.empty())
+			return false;
+
+		room = std::move(_inbox.front().first);
+		out = std::move(_inbox.front().second);
+		_inbox.pop();
+		return true;
+	}
+
+	bool Client::SendServer(const Proto::ServerMessage& msg)
+	{
+		Record(Proto::RecordDirection::Sent, msg);
+		auto frame = Proto::FrameMessage(msg);
+		_asio->tx.insert(_asio->tx.end(), frame.begin(), frame.end());
+		return true;
+	}
+
+	bool Client::CreateRoom()
+	{
+		_joining.password.clear();
+		_joining.spectating = false;
+		Await(RequestKind::Room);
+		return SendKeyed(Proto::CreateRoom{});
+	}
+
+	bool Client::JoinRoom(const std::string& id, const std::string& password)
+	{
+		_joining.password = password;
+		_joining.spectating = false;
+		Await(RequestKind::Room);
+		return SendServer(Proto::JoinRoom{ id, password });
+	}
+
+	bool Client::SpectateRoom(const std::string& id, const std::string& password)
+	{
+		_joining.password = password;
+		_joining.spectating = true;
+		Await(RequestKind::Room);
+		return SendServer(Proto::SpectateRoom{ id, password });
+	}
+
+	bool Client::LeaveRoom()
+	{
+		return SendServer(Proto::LeaveRoom{});
+	}
+
+	bool Client::KickMember(uint32_t id)
+	{
+		return SendServer(Proto::KickMember{ id });
+	}
+
+	bool Client::BanMember(uint32_t id)
+	{
+		return SendServer(Proto::BanMember{ id });
+	}
+
+	bool Client::TransferHost(uint32_t id)
+	{
+		return SendServer(Proto::TransferHost{ id });
+	}
+
+	bool Client::SplitRoom(const std::vector<uint32_t>& members)
+	{
+		return SendServer(Proto::SplitRoom{ members });
+	}
+
+	bool Client::MergeRoom(const std::string& id)
+	{
+		return SendServer(Proto::MergeRoom{ id });
+	}
+
+	bool Client::AnswerMerge(const std::string& into, bool accept)
+	{
+		return SendServer(Proto::AnswerMerge{ into, accept });
+	}
+
+	bool Client::SetMemberRole(uint32_t id, Proto::MemberRole role)
+	{
+		return SendServer(Proto::SetMemberRole{ id, role });
+	}
+
+	bool Client::SetPreferences(const Proto::NotificationPreferences& prefs)
+	{
+		return SendServer(Proto::SetPreferences{ prefs });
+	}
+
+	bool Client::CreateAccount(const std::string& name, const std::string& password, const std::string& email)
+	{
+		Await(RequestKind::Account);
+		return SendServer(Proto::CreateAccount{ name, password, email });
+	}
+
+	bool Client::Login(const std::string& name, const std::string& password)
+	{
+		Await(RequestKind::Account);
+		return SendServer(Proto::Login{ name, password });
+	}
+
+	bool Client::GuestLogin(const std::string& name)
+	{
+		Await(RequestKind::Account);
+		return SendServer(Proto::GuestLogin{ name });
+	}
+
+	bool Client::ScheduleRoom(uint64_t start, const std::string& title, uint32_t max_players, uint32_t max_spectators, const std::string& password)
+	{
+		return SendServer(Proto::ScheduleRoom{ start, title, max_players, max_spectators, password });
+	}
+
+	bool Client::ListScheduledRooms()
+	{
+		return SendServer(Proto::ListScheduledRooms{});
+	}
+
+	bool Client::RsvpScheduledRoom(uint32_t id, Proto::RsvpStatus status)
+	{
+		return SendServer(Proto::RsvpScheduledRoom{ id, status });
+	}
+
+	bool Client::ListAttendees(uint32_t id)
+	{
+		return SendServer(Proto::ListAttendees{ id });
+	}
+
+	bool Client::CancelScheduledRoom(uint32_t id)
+	{
+		return SendServer(Proto::CancelScheduledRoom{ id });
+	}
+
+	bool Client::SetRoomJoinable(bool open)
+	{
+		return SendServer(Proto::SetRoomJoinable{ open });
+	}
+
+	bool Client::SetRoomPassword(const std::string& password)
+	{
+		if (IsHost())
+			_session.password = password; // what the next join will need
+		return SendServer(Proto::SetRoomPassword{ password });
+	}
+
+	bool Client::SetRoomCapacity(uint32_t max_players, uint32_t max_spectators)
+	{
+		return SendServer(Proto::SetRoomCapacity{ max_players, max_spectators });
+	}
+
+	bool Client::SetIdlePolicy(uint32_t afk_after, bool spectate)
+	{
+		return SendServer(Proto::SetIdlePolicy{ afk_after, spectate });
+	}
+
+	bool Client::SetRoomBanner(const std::string& id)
+	{
+		return SendServer(Proto::SetRoomBanner{ id });
+	}
+
+	bool Client::SetRoomRules(const std::string& rules)
+	{
+		if (rules.size() > Proto::max_room_rules)
+			return false;
+		return SendServer(Proto::SetRoomRules{ rules });
+	}
+
+	bool Client::SetHostState(const std::string& state)
+	{
+		if (state.size() > Proto::max_host_state)
+			return false;
+		return SendServer(Proto::SetHostState{ state });
+	}
+
+	bool Client::ReportHostState(const std::string& state)
+	{
+		if (state.size() > Proto::max_host_state)
+			return false;
+		return SendServer(Proto::ReportHostState{ state });
+	}
+
+	bool Client::SetSuccession(Proto::SuccessionPolicy policy, const std::vector<uint32_t>& successors)
+	{
+		if (successors.size() > Proto::max_successors)
+			return false;
+		return SendServer(Proto::SetSuccession{ policy, successors });
+	}
+
+	bool Client::ReportActivity()
+	{
+		return SendServer(Proto::ReportActivity{});
+	}
+
+	bool Client::GetServerInfo()
+	{
+		return SendServer(Proto::GetServerInfo{});
+	}
+
+	bool Client::RequestJoin(const std::string& id, const std::string& message)
+	{
+		return SendServer(Proto::RequestJoin{ id, message });
+	}
+
+	bool Client::RespondJoinRequest(uint32_t id, bool approve)
+	{
+		return SendServer(Proto::RespondJoinRequest{ id, approve });
+	}
+
+	bool Client::QueueForRoom(const std::string& id, const std::string& password)
+	{
+		return SendServer(Proto::QueueForRoom{ id, password });
+	}
+
+	bool Client::LeaveRoomQueue()
+	{
+		return SendServer(Proto::LeaveRoomQueue{});
+	}
+
+	bool Client::GetRoomPreview(const std::string& id, bool watch)
+	{
+		return SendServer(Proto::GetRoomPreview{ id, watch });
+	}
+
+	bool Client::StopRoomPreview()
+	{
+		return SendServer(Proto::StopRoomPreview{});
+	}
+
+	bool Client::GetTimeline(uint32_t after)
+	{
+		return SendServer(Proto::GetTimeline{ after });
+	}
+
+	bool Client::PreflightJoin(const std::string& id, bool spectating)
+	{
+		if (id.empty() || !SendServer(Proto::GetRoomPreview{ id, false }))
+			return false;
+
+		_checking = { id, spectating };
+		return true;
+	}
+
+	bool Client::SendHeartbeat(uint64_t timestamp)
+	{
+		_ping = { timestamp, std::chrono::steady_clock::now() };
+		return SendServer(Proto::Heartbeat{ timestamp });
+	}
+
+	bool Client::StartCountdown(uint32_t seconds)
+	{
+		return SendServer(Proto::StartCountdown{ seconds });
+	}
+
+	bool Client::PauseMatch()
+	{
+		return SendServer(Proto::SetMatchPaused{ true });
+	}
+
+	bool Client::ResumeMatch()
+	{
+		return SendServer(Proto::SetMatchPaused{ false });
+	}
+
+	bool Client::GetSessionHealth()
+	{
+		return SendServer(Proto::GetSessionHealth{});
+	}
+
+	bool Client::GetJoinReceipts()
+	{
+		return SendServer(Proto::GetJoinReceipts{});
+	}
+
+	bool Client::LaunchGameServer(const std::vector<std::string>& argv)
+	{
+		return IsHost() && _game->LaunchGameServer(argv);
+	}
+
+	bool Client::SendConsoleCommand(const std::string& command)
+	{
+		return IsHost() && _game->SendConsoleCommand(command);
+	}
+
+	void Client::StopGameServer()
+	{
+		_game->StopGameServer();
+	}
+
+	bool Client::GameServerRunning() const
+	{
+		return _game->Running();
+	}
+
+	bool Client::NextPortMapping(PortMapping& out)
+	{
+		return _port_mapper && _port_mapper->Next(out);
+	}
+
+	bool Client::NextGameServerEvent(ProcessEvent& out)
+	{
+		if (_game_events.empty())
+			return false;
+
+		out = std::move(_game_events.front());
+		_game_events.pop();
+		return true;
+	}
+
+	void Client::PollCountdown()
+	{
+		if (_countdown.id == 0)
+			return;
+
+		auto left = _countdown.start - std::chrono::steady_clock::now();
+		while (left <= std::chrono::seconds(_countdown.next)) {
+			_ticks.push({ _countdown.id, _countdown.next });
+			if (_countdown.next == 0) {
+				_countdown = {};
+				return;
+			}
+			_countdown.next--;
+		}
+	}
+
+	bool Client::NextCountdownTick(CountdownTick& out)
+	{
+		if (_ticks.empty())
+			return false;
+
+		out = _ticks.front();
+		_ticks.pop();
+		return true;
+	}
+
+	bool Client::NextJoinCheck(JoinCheck& out)
+	{
+		if (_join_checks.empty())
+			return false;
+
+		out = std::move(_join_checks.front());
+		_join_checks.pop();
+		return true;
+	}
+
+	bool Client::SetBackground(bool background)
+	{
+		if (!SendServer(Proto::SetBackground{ background }))
+			return false;
+
+		_background = background;
+		return true;
+	}
+
+	bool Client::SearchUsers(const std::string& query)
+	{
+		return SendServer(Proto::SearchUsers{ query });
+	}
+
+	bool Client::SetSearchable(bool searchable)
+	{
+		return SendServer(Proto::SetSearchable{ searchable });
+	}
+
+	bool Client::ExportMyData()
+	{
+		return SendServer(Proto::ExportMyData{});
+	}
+
+	bool Client::SendChat(const std::string& text)
+	{
+		if (_room.empty()) // also the case once the connection is gone
+			return HoldMessage(0, text);
+
+		return SendKeyed(Proto::RoomChat{ 0, text }); // server fills in the sender id
+	}
+
+	bool Client::SendKeyed(Proto::ServerMessage msg)
+	{
+		auto now = std::chrono::steady_clock::now();
+		while (!_keyed.empty() && now - _keyed.front().sent >= std::chrono::seconds(Proto::idempotency_window))
+			_keyed.pop_front();
+
+		uint64_t key = _keys() | 1; // never 0, which means none
+		SendServer(Proto::Idempotent{ key });
+		SendServer(msg);
+		_keyed.push_back({ key, std::move(msg), now });
+		return true;
+	}
+
+	std::mt19937_64 Client::KeyEngine()
+	{
+		std::random_device device;
+		std::array<uint32_t, 8> entropy;
+		for (uint32_t& word : entropy)
+			word = device();
+		std::seed_seq seed(entropy.begin(), entropy.end());
+		return std::mt19937_64{ seed };
+	}
+
+	bool Client::HoldMessage(uint32_t to, std::string text)
+	{
+		uint32_t id = _next_unsent++;
+		_unsent.push_back({ id, to, std::move(text) });
+		_send_failures.push(id);
+		return false;
+	}
+
+	bool Client::RetryMessage(uint32_t id)
+	{
+		auto it = std::ranges::find(_unsent, id, &UnsentMessage::id);
+		if (it == _unsent.end() || _room.empty())
+			return false;
+
+		UnsentMessage msg = std::move(*it);
+		_unsent.erase(it);
+		return msg.to == 0 ? SendChat(msg.text) : SendWhisper(msg.to, msg.text);
+	}
+
+	bool Client::DiscardMessage(uint32_t id)
+	{
+		return std::erase_if(_unsent, [&](const UnsentMessage& msg) { return msg.id == id; }) != 0;
+	}
+
+	bool Client::NextSendFailure(UnsentMessage& out)
+	{
+		while (!_send_failures.empty()) {
+			uint32_t id = _send_failures.front();
+			_send_failures.pop();
+
+			auto it = std::ranges::find(_unsent, id, &UnsentMessage::id);
+			if (it != _unsent.end()) { // else retried or discarded before the app looked
+				out = *it;
+				return true;
+			}
+		}
+		return false;
+	}
+
+	void Client::SetDraft(const std::string& scope, const std::string& text)
+	{
+		if (text.empty())
+			_drafts.erase(scope);
+		else
+			_drafts[scope] = text;
+	}
+
+	bool Client::ExportChat(const std::string& room, ChatExportFormat format, const std::string& path) const
+	{
+		std::string text = ChatExport::Format(_history, room, format);
+		std::ofstream file(path, std::ios::binary | std::ios::trunc); // binary: CSV's CRLF must not double up on Windows
+		return file && file.write(text.data(), (std::streamsize)text.size()) && file.flush();
+	}
+
+	void Client::RecordChat(uint32_t from, uint32_t to, const std::string& text)
+	{
+		const std::string* name = MemberName(from);
+		const std::string* to_name = to == 0 ? nullptr : MemberName(to);
+		_history.push_back({ Proto::UnixNow(), _room, from, name ? *name : std::string{}, to, to_name ? *to_name : std::string{}, text });
+		if (_history.size() > max_chat_history)
+			_history.pop_front();
+	}
+
+	bool Client::SetRoomData(const std::string& key, const std::string& value)
+	{
+		return SendServer(Proto::SetRoomData{ key, value });
+	}
+
+	bool Client::DeleteRoomData(const std::string& key)
+	{
+		return SendServer(Proto::SetRoomData{ key, {} }); // empty value = delete
+	}
+
+	bool Client::SetMemberData(const std::string& key, const std::string& value)
+	{
+		return SendServer(Proto::SetMemberData{ key, value });
+	}
+
+	bool Client::DeleteMemberData(const std::string& key)
+	{
+		return SendServer(Proto::SetMemberData{ key, {} }); // empty value = delete
+	}
+
+	bool Client::SetMemberDataFor(uint32_t id, const std::string& key, const std::string& value)
+	{
+		return SendServer(Proto::SetMemberDataFor{ id, key, value });
+	}
+
+	bool Client::CreateTeam(const std::string& name, uint32_t max_size)
+	{
+		return SendServer(Proto::CreateTeam{ name, max_size });
+	}
+
+	bool Client::RemoveTeam(uint32_t team)
+	{
+		return SendServer(Proto::RemoveTeam{ team });
+	}
+
+	bool Client::AssignTeam(uint32_t id, uint32_t team)
+	{
+		return SendServer(Proto::AssignTeam{ id, team });
+	}
+
+	bool Client::BalanceTeams()
+	{
+		return SendServer(Proto::BalanceTeams{});
+	}
+
+	bool Client::CreateChannel(const std::string& name)
+	{
+		return SendServer(Proto::CreateChannel{ name });
+	}
+
+	bool Client::RemoveChannel(uint32_t channel)
+	{
+		return SendServer(Proto::RemoveChannel{ channel });
+	}
+
+	bool Client::PlaceInChannel(uint32_t id, uint32_t channel)
+	{
+		return SendServer(Proto::PlaceInChannel{ id, channel });
+	}
+
+	bool Client::SendChannelChat(const std::string& text)
+	{
+		return SendServer(Proto::ChannelChat{ 0, text }); // server fills in the sender id
 	}
 
-	Client::~Client()
+	uint32_t Client::UploadBlob(std::span<const std::byte> data)
 	{
-		DestroyAllLinks();
+		if (data.empty() || data.size() > Proto::max_blob_size)
+			return 0;
+
+		uint32_t transfer = _next_transfer++;
+		if (!SendServer(Proto::UploadBlob{ transfer, (uint32_t)data.size() }))
+			return 0;
+
+		for (size_t offset = 0; offset < data.size(); offset += Proto::max_blob_chunk) {
+			auto chunk = data.subspan(offset, std::min<size_t>(Proto::max_blob_chunk, data.size() - offset));
+			if (!SendServer(Proto::BlobChunk{ transfer, { chunk.begin(), chunk.end() } }))
+				return 0;
+		}
+		return transfer;
 	}
 
-	bool Client::Connect(ClientConfig& config)
+	uint32_t Client::DownloadBlob(const std::string& id)
 	{
-		auto& impl = *_asio;
-		asio::error_code ec;
+		uint32_t transfer = _next_transfer++;
+		if (!SendServer(Proto::DownloadBlob{ transfer, id }))
+			return 0;
 
-		asio::ip::tcp::resolver resolver{ impl.context };
-		auto endpoints = resolver.resolve(config.host, std::to_string(config.port), ec);
-		if (ec) return false;
+		_downloads[transfer].first = id; // chunks for any other transfer are dropped
+		return transfer;
+	}
 
-		asio::connect(impl.socket, endpoints, ec);
-		if (ec) return false;
+	const std::vector<std::byte>* Client::GetBlob(const std::string& id)
+	{
+		auto it = _blobs.find(id);
+		if (it != _blobs.end()) {
+			std::erase(_blob_order, id);
+			_blob_order.push_back(id); // fetched again, so evicted last
+			return &it->second;
+		}
 
-		impl.socket.non_blocking(true, ec);
-		return !ec;
+		bool pending = std::ranges::any_of(_downloads, [&](const auto& download) { return download.second.first == id; });
+		if (!pending)
+			DownloadBlob(id);
+		return nullptr;
 	}
 
-	bool Client::Poll()
+	uint32_t Client::Id() const
 	{
-		if (!_asio->socket.is_open())
-			return false;
+		return _id;
+	}
 
-		if (!DrainServer() || !CarveServer())
-			return false;
+	const std::string& Client::Name() const
+	{
+		return _name;
+	}
 
-		PollPeers(); // may queue signal frames; the flush below sends them
-		return FlushServer();
+	const std::string* Client::MemberName(uint32_t id) const
+	{
+		if (id == _id && !_name.empty())
+			return &_name; // room events only name us if we logged in while inside
+		auto it = _names.find(id);
+		return it == _names.end() ? nullptr : &it->second;
 	}
 
-	bool Client::Next(Proto::ServerMessage& out)
+	const std::string& Client::RoomId() const
 	{
-		if (_inbox.empty())
-			return false;
+		return _room;
+	}
 
-		out = std::move(_inbox.front());
-		_inbox.pop();
-		return true;
+	uint32_t Client::HostId() const
+	{
+		return _host;
 	}
 
-	bool Client::SendServer(const Proto::ServerMessage& msg)
+	bool Client::IsHost() const
 	{
-		auto frame = Proto::FrameMessage(msg);
-		_asio->tx.insert(_asio->tx.end(), frame.begin(), frame.end());
-		return true;
+		return _id != 0 && _id == _host;
 	}
 
-	bool Client::CreateRoom()
+	Proto::MemberRole Client::Role(uint32_t id) const
 	{
-		return SendServer(Proto::CreateRoom{});
+		auto it = _roles.find(id);
+		return it == _roles.end() ? Proto::MemberRole::Member : it->second;
 	}
 
-	bool Client::JoinRoom(const std::string& id, const std::string& password)
+	const Proto::NotificationPreferences& Client::Preferences() const
 	{
-		return SendServer(Proto::JoinRoom{ id, password });
+		return _prefs;
 	}
 
-	bool Client::LeaveRoom()
+	const std::vector<Proto::ScheduledRoom>& Client::ScheduledRooms() const
 	{
-		return SendServer(Proto::LeaveRoom{});
+		return _schedule;
 	}
 
-	bool Client::KickMember(uint32_t id)
+	const std::vector<Proto::Attendee>* Client::Attendees(uint32_t id) const
 	{
-		return SendServer(Proto::KickMember{ id });
+		auto it = _attendees.find(id);
+		return it == _attendees.end() ? nullptr : &it->second;
 	}
 
-	bool Client::BanMember(uint32_t id)
+	const Proto::RoomPreview* Client::Preview() const
 	{
-		return SendServer(Proto::BanMember{ id });
+		return _preview.id.empty() ? nullptr : &_preview;
 	}
 
-	bool Client::TransferHost(uint32_t id)
+	const std::deque<ChatLine>& Client::ChatHistory() const
 	{
-		return SendServer(Proto::TransferHost{ id });
+		return _history;
 	}
 
-	bool Client::SetRoomJoinable(bool open)
+	std::vector<size_t> Client::SearchMessages(const std::string& query, const std::string& room, size_t limit) const
 	{
-		return SendServer(Proto::SetRoomJoinable{ open });
+		return SearchChat(_history, query, room, limit);
 	}
 
-	bool Client::SetRoomPassword(const std::string& password)
+	const Proto::UserSearchResults& Client::UserResults() const
 	{
-		return SendServer(Proto::SetRoomPassword{ password });
+		return _user_results;
 	}
 
-	bool Client::SendChat(const std::string& text)
+	bool Client::Searchable() const
 	{
-		return SendServer(Proto::RoomChat{ 0, text }); // server fills in the sender id
+		return _searchable;
 	}
 
-	bool Client::SetRoomData(const std::string& key, const std::string& value)
+	const Proto::MyData& Client::OwnData() const
 	{
-		return SendServer(Proto::SetRoomData{ key, value });
+		return _own_data;
 	}
 
-	bool Client::DeleteRoomData(const std::string& key)
+	const std::deque<Proto::TimelineEntry>& Client::RoomTimeline() const
 	{
-		return SendServer(Proto::SetRoomData{ key, {} }); // empty value = delete
+		return _timeline;
 	}
 
-	bool Client::SetMemberData(const std::string& key, const std::string& value)
+	const std::vector<UnsentMessage>& Client::UnsentMessages() const
 	{
-		return SendServer(Proto::SetMemberData{ key, value });
+		return _unsent;
 	}
 
-	bool Client::DeleteMemberData(const std::string& key)
+	const std::map<std::string, std::string>& Client::Drafts() const
 	{
-		return SendServer(Proto::SetMemberData{ key, {} }); // empty value = delete
+		return _drafts;
 	}
 
-	uint32_t Client::Id() const
+	const std::string* Client::Draft(const std::string& scope) const
 	{
-		return _id;
+		auto it = _drafts.find(scope);
+		return it == _drafts.end() ? nullptr : &it->second;
 	}
 
-	const std::string& Client::RoomId() const
+	const std::vector<std::byte>* Client::Blob(const std::string& id) const
 	{
-		return _room;
+		auto it = _blobs.find(id);
+		return it == _blobs.end() ? nullptr : &it->second;
 	}
 
-	uint32_t Client::HostId() const
+	const std::string& Client::Banner() const
 	{
-		return _host;
+		return _banner;
 	}
 
-	bool Client::IsHost() const
+	const std::string& Client::Rules() const
 	{
-		return _id != 0 && _id == _host;
+		return _rules;
+	}
+
+	uint32_t Client::RulesVersion() const
+	{
+		return _rules_version;
+	}
+
+	const std::vector<Proto::Team>& Client::Teams() const
+	{
+		return _teams;
+	}
+
+	uint32_t Client::TeamOf(uint32_t id) const
+	{
+		for (const Proto::Team& team : _teams) {
+			if (std::ranges::find(team.members, id) != team.members.end())
+				return team.id;
+		}
+		return 0;
+	}
+
+	const std::vector<Proto::Channel>& Client::Channels() const
+	{
+		return _channels;
+	}
+
+	uint32_t Client::ChannelOf(uint32_t id) const
+	{
+		for (const Proto::Channel& channel : _channels) {
+			if (std::ranges::find(channel.members, id) != channel.members.end())
+				return channel.id;
+		}
+		return 0;
+	}
+
+	bool Client::InBackground() const
+	{
+		return _background;
+	}
+
+	std::chrono::milliseconds Client::RoundTrip() const
+	{
+		return _round_trip;
+	}
+
+	bool Client::MatchPaused() const
+	{
+		return _paused;
+	}
+
+	const std::map<uint32_t, Proto::MemberHealth>& Client::SessionHealth() const
+	{
+		return _health;
+	}
+
+	const Proto::SessionHealth& Client::ServerHealth() const
+	{
+		return _server_health;
+	}
+
+	const Proto::JoinReceipts& Client::Receipts() const
+	{
+		return _receipts;
+	}
+
+	std::optional<std::chrono::milliseconds> Client::CountdownRemaining() const
+	{
+		if (_countdown.id == 0)
+			return std::nullopt;
+		auto left = std::chrono::duration_cast<std::chrono::milliseconds>(_countdown.start - std::chrono::steady_clock::now());
+		return std::max(left, std::chrono::milliseconds{ 0 });
 	}
 
 	bool Client::RoomJoinable() const
@@ -173,6 +1145,56 @@ namespace Weyvelength {
 		return _room_passworded;
 	}
 
+	uint32_t Client::MaxPlayers() const
+	{
+		return _max_players;
+	}
+
+	uint32_t Client::MaxSpectators() const
+	{
+		return _max_spectators;
+	}
+
+	uint32_t Client::PlayerCount() const
+	{
+		return (uint32_t)(_members.size() - _spectators.size());
+	}
+
+	uint32_t Client::SpectatorCount() const
+	{
+		return (uint32_t)_spectators.size();
+	}
+
+	bool Client::IsSpectator(uint32_t id) const
+	{
+		return std::ranges::find(_spectators, id) != _spectators.end();
+	}
+
+	bool Client::IsAfk(uint32_t id) const
+	{
+		return std::ranges::find(_afk, id) != _afk.end();
+	}
+
+	uint32_t Client::AfkAfter() const
+	{
+		return _afk_after;
+	}
+
+	bool Client::AfkSpectates() const
+	{
+		return _afk_spectate;
+	}
+
+	Proto::SuccessionPolicy Client::Succession() const
+	{
+		return _succession;
+	}
+
+	const std::vector<uint32_t>& Client::Successors() const
+	{
+		return _successors;
+	}
+
 	const std::vector<uint32_t>& Client::Members() const
 	{
 		return _members;
@@ -205,10 +1227,11 @@ namespace Weyvelength {
 		return it == data->end() ? nullptr : &it->second;
 	}
 
-	bool Client::DrainServer()
+	// The socket halves shared by the server connection and the signal link.
+	// Each false means the stream is done for: eof, a hard error, or bytes
+	// it cannot recover from, as error says.
+	static bool DrainSocket(ClientAsioImpl& impl, std::string& error)
 	{
-		auto& impl = *_asio;
-
 		std::array<std::byte, 4096> chunk;
 		asio::error_code ec;
 
@@ -219,28 +1242,50 @@ namespace Weyvelength {
 		}
 
 		if (ec != asio::error::would_block && ec != asio::error::try_again) {
-			return DisconnectServer();   // eof or hard error
+			error = ec.message();
+			return false;   // eof or hard error
+		}
+		return true;
+	}
+
+	static bool FlushSocket(ClientAsioImpl& impl, std::string& error)
+	{
+		asio::error_code ec;
+
+		while (!impl.tx.empty()) {
+			size_t sent = impl.socket.write_some(asio::buffer(impl.tx), ec);
+			if (ec) break;
+			impl.tx.erase(impl.tx.begin(), impl.tx.begin() + sent);
 		}
 
+		if (ec && ec != asio::error::would_block && ec != asio::error::try_again) {
+			error = ec.message();
+			return false;   // hard error (empty tx leaves ec unset)
+		}
 		return true;
 	}
 
-	bool Client::CarveServer()
+	// Cuts every whole message off rx, in order, and hands each to take.
+	template <typename Take>
+	static bool CarveMessages(ClientAsioImpl& impl, std::string& error, Take&& take)
 	{
-		auto& impl = *_asio;
 		constexpr size_t header_size = sizeof(uint32_t);
 
 		std::span<std::byte> remaining{ impl.rx };
 		while (remaining.size() >= header_size) {
 			uint32_t len;
 			bool more;
-			if (!Proto::DecodeFrameHeader(remaining.first(header_size), len, more))
-				return DisconnectServer();
+			if (!Proto::DecodeFrameHeader(remaining.first(header_size), len, more)) {
+				error = "a bad frame header";
+				return false;
+			}
 			if (remaining.size() < header_size + len)
 				break;   // full fragment hasn't arrived yet
 
-			if (!Proto::AppendFragment(impl.rx_msg, remaining.subspan(header_size, len)))
-				return DisconnectServer(); // reassembled message too large
+			if (!Proto::AppendFragment(impl.rx_msg, remaining.subspan(header_size, len))) {
+				error = "an oversized message"; // reassembled message too large
+				return false;
+			}
 			remaining = remaining.subspan(header_size + len);
 
 			if (more)
@@ -249,44 +1294,142 @@ namespace Weyvelength {
 			Proto::ServerMessage msg;
 			bool bad = failure(zpp::bits::in{ impl.rx_msg }(msg));
 			impl.rx_msg.clear();
-			if (bad)
-				return DisconnectServer();
+			if (bad) {
+				error = "an unreadable message (a newer protocol?)";
+				return false;
+			}
+			take(std::move(msg));
+		}
+
+		size_t consumed = impl.rx.size() - remaining.size();
+		impl.rx.erase(impl.rx.begin(), impl.rx.begin() + consumed);
+		return true;
+	}
+
+	bool Client::DrainServer()
+	{
+		std::string error;
+		if (DrainSocket(*_asio, error))
+			return true;
+		Log(LogLevel::Warn, "Server connection lost: " + error);
+		return DisconnectServer();
+	}
+
+	bool Client::CarveServer()
+	{
+		std::string error;
+		bool carved = CarveMessages(*_asio, error, [&](Proto::ServerMessage&& msg) {
+			Record(Proto::RecordDirection::Received, msg);
 
 			if (auto* assign = std::get_if<Proto::AssignClientId>(&msg)) {
 				_id = assign->id;   // transport metadata; not surfaced via Next()
+				_mesh->signal_seq_from.clear(); // a new connection, maybe to a restarted server handing out the same ids
+			}
+			else if (auto* beat = std::get_if<Proto::Heartbeat>(&msg); beat && beat->timestamp == 0) {
+				SendServer(Proto::Heartbeat{ 0 });   // the server's liveness probe; answered, not surfaced via Next()
 			}
 			else if (auto* ice = std::get_if<Proto::IceServers>(&msg)) {
 				_ice = std::move(*ice);   // transport metadata; not surfaced via Next()
 			}
+			else if (auto* endpoint = std::get_if<Proto::SignalEndpoint>(&msg)) {
+				OpenSignalLink(*endpoint);   // same
+			}
 			else if (auto* signal = std::get_if<Proto::P2PSignal>(&msg)) {
 				HandleP2PSignal(*signal);   // ICE plumbing; not surfaced via Next()
 			}
+			else if (auto* sequenced = std::get_if<Proto::SequencedSignal>(&msg)) {
+				HandleSequencedSignal(*sequenced);   // same
+			}
+			else if (auto* chunk = std::get_if<Proto::BlobChunk>(&msg)) {
+				auto it = _downloads.find(chunk->transfer);   // collected for Blob(); not surfaced via Next()
+				if (it != _downloads.end()) {
+					std::vector<std::byte>& data = it->second.second;
+					if (data.size() + chunk->data.size() > Proto::max_blob_size)
+						_downloads.erase(it); // more than any blob can be; its BlobDownloaded will find nothing
+					else
+						data.insert(data.end(), chunk->data.begin(), chunk->data.end());
+				}
+			}
 			else {
+				std::string room = _room; // before a kick or move clears it
 				CacheRoomState(msg); // cached for the accessors, but still surfaced via Next()
-				_inbox.push(std::move(msg));
+				if (std::holds_alternative<Proto::AssignRoomId>(msg))
+					room = _room;
+				_inbox.emplace(std::move(room), std::move(msg));
 			}
-		}
-
-		size_t consumed = impl.rx.size() - remaining.size();
-		impl.rx.erase(impl.rx.begin(), impl.rx.begin() + consumed);
-		return true;
+		});
+		if (carved)
+			return true;
+		Log(LogLevel::Error, "Server sent " + error + "; disconnecting");
+		return DisconnectServer();
 	}
 
 	bool Client::FlushServer()
 	{
-		auto& impl = *_asio;
-		asio::error_code ec;
+		std::string error;
+		if (FlushSocket(*_asio, error))
+			return true;
+		Log(LogLevel::Warn, "Server connection lost: " + error);
+		return DisconnectServer();
+	}
 
-		while (!impl.tx.empty()) {
-			size_t sent = impl.socket.write_some(asio::buffer(impl.tx), ec);
-			if (ec) break;
-			impl.tx.erase(impl.tx.begin(), impl.tx.begin() + sent);
+	// Dialed like the server itself, blocking, once per connection: the
+	// endpoint comes right after AssignClientId, before any room and so
+	// before any signal. Signals already queued on the main connection go out
+	// there, ahead of the attach.
+	void Client::OpenSignalLink(const Proto::SignalEndpoint& endpoint)
+	{
+		_signal_link.reset();
+		auto link = std::make_unique<ClientAsioImpl>();
+		asio::error_code ec;
+		asio::ip::tcp::resolver resolver{ link->context };
+		auto endpoints = resolver.resolve(_server.host, std::to_string(endpoint.port), ec);
+		if (!ec)
+			asio::connect(link->socket, endpoints, ec);
+		if (!ec)
+			link->socket.non_blocking(true, ec);
+		if (ec) {
+			Log(LogLevel::Warn, "Cannot reach the server's signal port " + std::to_string(endpoint.port) + " (" + ec.message() + "); signals stay on the main connection");
+			return;
 		}
 
-		if (ec && ec != asio::error::would_block && ec != asio::error::try_again) {
-			return DisconnectServer();   // hard error (empty tx leaves ec unset)
+		Proto::ServerMessage attach = Proto::AttachSignalLink{ _id, endpoint.token };
+		Record(Proto::RecordDirection::Sent, attach);
+		auto frame = Proto::FrameMessage(attach);
+		link->tx.insert(link->tx.end(), frame.begin(), frame.end());
+		_signal_link = std::move(link);
+		Log(LogLevel::Info, "Signals go over the server's port " + std::to_string(endpoint.port));
+	}
+
+	// A lost link is not redialed: the server relays over the main connection
+	// again as soon as it sees the link close, and so do we.
+	void Client::PollSignalLink()
+	{
+		if (!_signal_link)
+			return;
+
+		std::string error;
+		bool up = DrainSocket(*_signal_link, error) && CarveMessages(*_signal_link, error, [&](Proto::ServerMessage&& msg) {
+			Record(Proto::RecordDirection::Received, msg);
+			if (auto* signal = std::get_if<Proto::P2PSignal>(&msg))
+				HandleP2PSignal(*signal);
+			else if (auto* sequenced = std::get_if<Proto::SequencedSignal>(&msg))
+				HandleSequencedSignal(*sequenced);
+		}) && FlushSocket(*_signal_link, error);
+		if (!up) {
+			Log(LogLevel::Warn, "Signal link lost: " + error + "; signals go over the main connection");
+			_signal_link.reset();
 		}
+	}
+
+	bool Client::SendSignaling(const Proto::ServerMessage& msg)
+	{
+		if (!_signal_link)
+			return SendServer(msg);
 
+		Record(Proto::RecordDirection::Sent, msg);
+		auto frame = Proto::FrameMessage(msg);
+		_signal_link->tx.insert(_signal_link->tx.end(), frame.begin(), frame.end());
 		return true;
 	}
 
@@ -294,14 +1437,67 @@ namespace Weyvelength {
 	// accessors are already current when Next() hands the event to the app.
 	void Client::CacheRoomState(const Proto::ServerMessage& msg)
 	{
+		auto* full = std::get_if<Proto::ServerFull>(&msg);
+		if (std::holds_alternative<Proto::AssignRoomId>(msg) || std::holds_alternative<Proto::RoomError>(msg) || (full && full->limit != Proto::ServerLimit::Requests))
+			_awaiting.erase(RequestKind::Room); // a RoomError may answer something else; close enough. A refused lookup never answers a room request
+		if (std::holds_alternative<Proto::LoggedIn>(msg) || std::holds_alternative<Proto::AccountError>(msg) || (full && full->limit == Proto::ServerLimit::Requests))
+			_awaiting.erase(RequestKind::Account); // a refused attempt is refused as a lookup is
+
+		// a preflight's answer: the preview says what the join would meet, an
+		// error echoing the code as asked says it would not get that far
+		if (auto* error = std::get_if<Proto::RoomError>(&msg); error && !_checking.id.empty() && error->context == _checking.id
+			&& (error->code == Proto::RoomErrorCode::NoSuchRoom || error->code == Proto::RoomErrorCode::Banned)) {
+			_checking.blocker = error->code;
+			_checking.relay = !_ice.turn.empty();
+			_join_checks.push(std::exchange(_checking, {}));
+		}
+		else if (auto* preview = std::get_if<Proto::RoomPreview>(&msg); preview && !_checking.id.empty()
+			&& Proto::RoomCodeKey(preview->id) == Proto::RoomCodeKey(_checking.id)) {
+			_join_checks.push(CheckJoin(*preview, _checking.spectating, !_ice.turn.empty()));
+			_checking = {};
+		}
+
+		if (auto* beat = std::get_if<Proto::Heartbeat>(&msg); beat && beat->timestamp == _ping.first && _ping.second != std::chrono::steady_clock::time_point{}) {
+			_round_trip = std::chrono::duration_cast<std::chrono::milliseconds>(std::chrono::steady_clock::now() - _ping.second);
+			_ping = {};
+		}
+
+		if (auto* chat = std::get_if<Proto::RoomChat>(&msg))
+			RecordChat(chat->from, 0, chat->text);
+		else if (auto* whisper = std::get_if<Proto::RoomWhisper>(&msg))
+			RecordChat(whisper->id, _id, whisper->text);
+
 		if (auto* room = std::get_if<Proto::AssignRoomId>(&msg)) {
 			_room = room->id;
+			_session.password = _joining.password;
+			_session.spectating = _joining.spectating;
 			_host = 0;
 			_room_open = true;
 			_room_passworded = false;
 			_members.assign(1, _id); // events only ever announce the others
+			_spectators.clear();
+			_afk.clear();
+			_names.clear();
+			_max_players = 0;
+			_max_spectators = 0;
+			_afk_after = 0;
+			_afk_spectate = false;
+			_succession = {};
+			_successors.clear();
+			_banner.clear();
+			_rules.clear();
+			_rules_version = 0;
+			_teams.clear();
+			_channels.clear();
+			_countdown = {};
+			_paused = false;
 			_data.clear();
 			_member_data.clear();
+			_roles.clear();
+			_health.clear();
+			_server_health = {};
+			_receipts = {};
+			_health_window = std::chrono::steady_clock::now();
 		}
 		else if (auto* joined = std::get_if<Proto::PeerJoined>(&msg)) {
 			_members.push_back(joined->id);
@@ -312,13 +1508,20 @@ namespace Weyvelength {
 			}
 			else {
 				std::erase(_members, left->id);
+				std::erase(_spectators, left->id);
+				std::erase(_afk, left->id);
+				_names.erase(left->id);
 				_member_data.erase(left->id);
+				_roles.erase(left->id);
+				_health.erase(left->id);
 				DestroyLink(left->id); // no member, no mesh link
 				_mesh->attempts.erase(left->id); // and no grudge if they rejoin
+				_mesh->retries.erase(left->id);
 			}
 		}
 		else if (auto* host = std::get_if<Proto::HostChanged>(&msg)) {
 			_host = host->id;
+			_health.clear(); // reports go to the new host from now on
 		}
 		else if (auto* data = std::get_if<Proto::RoomDataChanged>(&msg)) {
 			if (data->value.empty())
@@ -345,10 +1548,150 @@ namespace Weyvelength {
 		else if (std::get_if<Proto::BannedByHost>(&msg)) {
 			ClearRoomState(); // removed and barred; same cleanup as a kick
 		}
+		else if (std::get_if<Proto::RoomMoved>(&msg)) {
+			ClearRoomState(); // the old mesh goes; links to the new room's members start on first use, as after any join
+			_joining.password.clear(); // let in as invited, so a rejoin needs none
+			_joining.spectating = _session.spectating; // moved in the same seat kind
+		}
 		else if (auto* access = std::get_if<Proto::RoomAccessChanged>(&msg)) {
 			_room_open = access->open;
 			_room_passworded = access->passworded;
 		}
+		else if (auto* role = std::get_if<Proto::MemberRoleChanged>(&msg)) {
+			if (role->role == Proto::MemberRole::Member)
+				_roles.erase(role->id);
+			else
+				_roles[role->id] = role->role;
+		}
+		else if (auto* prefs = std::get_if<Proto::PreferencesChanged>(&msg)) {
+			_prefs = prefs->prefs;
+		}
+		else if (auto* spectator = std::get_if<Proto::MemberSpectating>(&msg)) {
+			if (!IsSpectator(spectator->id))
+				_spectators.push_back(spectator->id);
+			if (spectator->id == _id)
+				_session.spectating = true; // e.g. moved there for idling; rejoin where we were
+		}
+		else if (auto* capacity = std::get_if<Proto::RoomCapacityChanged>(&msg)) {
+			_max_players = capacity->max_players;
+			_max_spectators = capacity->max_spectators;
+		}
+		else if (auto* idle = std::get_if<Proto::IdlePolicyChanged>(&msg)) {
+			_afk_after = idle->afk_after;
+			_afk_spectate = idle->spectate;
+		}
+		else if (auto* succession = std::get_if<Proto::SuccessionChanged>(&msg)) {
+			_succession = succession->policy;
+			_successors = succession->successors;
+		}
+		else if (auto* banner = std::get_if<Proto::RoomBannerChanged>(&msg)) {
+			_banner = banner->id;
+		}
+		else if (auto* countdown = std::get_if<Proto::CountdownStarted>(&msg)) {
+			// the server's remaining had half a round trip to travel already;
+			// the first tick is the next whole second down, after this event
+			auto left = std::max(std::chrono::milliseconds(countdown->remaining) - _round_trip / 2, std::chrono::milliseconds{ 0 });
+			_countdown = {};
+			if (countdown->seconds != 0)
+				_countdown = { countdown->id, std::chrono::steady_clock::now() + left, left.count() > 0 ? (uint32_t)((left.count() - 1) / 1000) : 0 };
+		}
+		else if (auto* paused = std::get_if<Proto::MatchPausedChanged>(&msg)) {
+			_paused = paused->paused;
+		}
+		else if (auto* health = std::get_if<Proto::MemberHealth>(&msg)) {
+			_health[health->from] = *health;
+		}
+		else if (auto* session = std::get_if<Proto::SessionHealth>(&msg)) {
+			_server_health = *session;
+		}
+		else if (auto* receipts = std::get_if<Proto::JoinReceipts>(&msg)) {
+			_receipts = *receipts;
+		}
+		else if (auto* rules = std::get_if<Proto::RoomRulesChanged>(&msg)) {
+			_rules = rules->rules;
+			_rules_version = rules->version;
+		}
+		else if (auto* teams = std::get_if<Proto::TeamsChanged>(&msg)) {
+			_teams = teams->teams;
+		}
+		else if (auto* channels = std::get_if<Proto::ChannelsChanged>(&msg)) {
+			_channels = channels->channels;
+		}
+		else if (auto* afk = std::get_if<Proto::MemberAfk>(&msg)) {
+			std::erase(_afk, afk->id);
+			if (afk->afk)
+				_afk.push_back(afk->id);
+		}
+		else if (auto* list = std::get_if<Proto::ScheduledRoomList>(&msg)) {
+			_schedule = list->rooms;
+		}
+		else if (auto* booking = std::get_if<Proto::ScheduledRoomChanged>(&msg)) {
+			std::erase_if(_schedule, [&](const Proto::ScheduledRoom& room) { return room.id == booking->room.id; });
+			_schedule.insert(std::ranges::upper_bound(_schedule, booking->room.start, {}, &Proto::ScheduledRoom::start), booking->room);
+		}
+		else if (auto* opened = std::get_if<Proto::ScheduledRoomOpened>(&msg)) {
+			std::erase_if(_schedule, [&](const Proto::ScheduledRoom& room) { return room.id == opened->id; });
+			_attendees.erase(opened->id);
+		}
+		else if (auto* cancelled = std::get_if<Proto::ScheduledRoomCancelled>(&msg)) {
+			std::erase_if(_schedule, [&](const Proto::ScheduledRoom& room) { return room.id == cancelled->id; });
+			_attendees.erase(cancelled->id);
+		}
+		else if (auto* attendees = std::get_if<Proto::ScheduledRoomAttendees>(&msg)) {
+			_attendees[attendees->id] = attendees->attendees;
+		}
+		else if (auto* preview = std::get_if<Proto::RoomPreview>(&msg)) {
+			_preview = *preview;
+		}
+		else if (auto* results = std::get_if<Proto::UserSearchResults>(&msg)) {
+			_user_results = *results;
+		}
+		else if (auto* searchable = std::get_if<Proto::SearchableChanged>(&msg)) {
+			_searchable = searchable->searchable;
+		}
+		else if (auto* data = std::get_if<Proto::MyData>(&msg)) {
+			_own_data = *data;
+		}
+		else if (auto* timeline = std::get_if<Proto::Timeline>(&msg)) {
+			if (!timeline->entries.empty()) { // from its first seq on, the reply is the log
+				uint32_t from = timeline->entries.front().seq;
+				std::erase_if(_timeline, [&](const Proto::TimelineEntry& entry) { return entry.seq >= from; });
+				_timeline.insert(_timeline.end(), timeline->entries.begin(), timeline->entries.end());
+			}
+			while (_timeline.size() > Proto::max_timeline_entries)
+				_timeline.pop_front();
+		}
+		else if (auto* added = std::get_if<Proto::TimelineAdded>(&msg)) {
+			if (_timeline.empty() || added->entry.seq > _timeline.back().seq)
+				_timeline.push_back(added->entry);
+			if (_timeline.size() > Proto::max_timeline_entries)
+				_timeline.pop_front();
+		}
+		else if (auto* logged_in = std::get_if<Proto::LoggedIn>(&msg)) {
+			_name = logged_in->name;
+			size_t suffix = _name.find(Proto::guest_separator); // account names never contain it
+			_session.guest = suffix == std::string::npos ? std::string{} : _name.substr(0, suffix);
+		}
+		else if (auto* name = std::get_if<Proto::MemberNameChanged>(&msg)) {
+			_names[name->id] = name->name;
+		}
+		else if (auto* downloaded = std::get_if<Proto::BlobDownloaded>(&msg)) {
+			auto it = _downloads.find(downloaded->transfer);
+			if (it != _downloads.end()) {
+				if (!_blobs.contains(downloaded->id)) {
+					_blob_order.push_back(downloaded->id);
+					if (_blob_order.size() > max_cached_blobs) {
+						_blobs.erase(_blob_order.front());
+						_blob_order.pop_front();
+					}
+				}
+				_blobs[downloaded->id] = std::move(it->second.second);
+				_downloads.erase(it);
+			}
+		}
+		else if (auto* error = std::get_if<Proto::BlobError>(&msg)) {
+			_downloads.erase(error->transfer);
+		}
 	}
 
 	void Client::ClearRoomState()
@@ -359,14 +1702,42 @@ namespace Weyvelength {
 		_room_open = true;
 		_room_passworded = false;
 		_members.clear();
+		_spectators.clear();
+		_afk.clear();
+		_names.clear();
+		_max_players = 0;
+		_max_spectators = 0;
+		_afk_after = 0;
+		_afk_spectate = false;
+		_succession = {};
+		_successors.clear();
+		_banner.clear();
 		_data.clear();
 		_member_data.clear();
+		_roles.clear();
+		_timeline.clear();
+		_teams.clear();
+		_channels.clear();
+		_rules.clear();
+		_rules_version = 0;
+		_countdown = {};
+		_paused = false;
+		_health.clear();
+		_server_health = {};
+		_receipts = {};
 	}
 
 	bool Client::DisconnectServer()
 	{
-		_asio->socket.close();
+		asio::error_code ec;
+		_asio->socket.close(ec); // already closed after a hard error in Disconnect's flush
+		_signal_link.reset(); // the server drops its end with the main connection anyway
 		ClearRoomState();
+		_downloads.clear(); // their chunks will never come
+		_awaiting.clear(); // a closed connection says so itself
+		_checking = {};
+		_background = false;
+		_searchable = true;
 		return false;
 	}
 }
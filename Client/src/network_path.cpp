@@ -0,0 +1,75 @@
+#include "network_path.h"
+
+#ifdef _WIN32
+#define WIN32_LEAN_AND_MEAN
+#define NOMINMAX
+#include <winsock2.h>
+#include <ws2tcpip.h>
+#include <iphlpapi.h>
+#pragma comment(lib, "iphlpapi.lib")
+#else
+#include <arpa/inet.h>
+#include <ifaddrs.h>
+#include <net/if.h>
+#include <netinet/in.h>
+#endif
+
+namespace Weyvelength {
+	// An address as text, or empty for families ICE cannot use.
+	static std::string AddressText(const sockaddr* address)
+	{
+		char text[INET6_ADDRSTRLEN] = {};
+		if (address->sa_family == AF_INET)
+			inet_ntop(AF_INET, &((const sockaddr_in*)address)->sin_addr, text, sizeof(text));
+		else if (address->sa_family == AF_INET6)
+			inet_ntop(AF_INET6, &((const sockaddr_in6*)address)->sin6_addr, text, sizeof(text));
+		return text;
+	}
+
+#ifdef _WIN32
+	std::vector<NetworkInterface> ListNetworkInterfaces()
+	{
+		std::vector<NetworkInterface> out;
+		std::vector<char> buffer(16 * 1024);
+		ULONG size = (ULONG)buffer.size();
+		constexpr ULONG flags = GAA_FLAG_SKIP_ANYCAST | GAA_FLAG_SKIP_MULTICAST | GAA_FLAG_SKIP_DNS_SERVER;
+		ULONG result = GetAdaptersAddresses(AF_UNSPEC, flags, nullptr, (IP_ADAPTER_ADDRESSES*)buffer.data(), &size);
+		if (result == ERROR_BUFFER_OVERFLOW) {
+			buffer.resize(size);
+			result = GetAdaptersAddresses(AF_UNSPEC, flags, nullptr, (IP_ADAPTER_ADDRESSES*)buffer.data(), &size);
+		}
+		if (result != NO_ERROR)
+			return out;
+
+		for (auto* adapter = (IP_ADAPTER_ADDRESSES*)buffer.data(); adapter; adapter = adapter->Next) {
+			if (adapter->OperStatus != IfOperStatusUp || adapter->IfType == IF_TYPE_SOFTWARE_LOOPBACK)
+				continue;
+			int length = WideCharToMultiByte(CP_UTF8, 0, adapter->FriendlyName, -1, nullptr, 0, nullptr, nullptr);
+			std::string name(length > 0 ? length - 1 : 0, '\0');
+			WideCharToMultiByte(CP_UTF8, 0, adapter->FriendlyName, -1, name.data(), length, nullptr, nullptr);
+			for (auto* unicast = adapter->FirstUnicastAddress; unicast; unicast = unicast->Next) {
+				if (std::string address = AddressText(unicast->Address.lpSockaddr); !address.empty())
+					out.push_back({ name, address });
+			}
+		}
+		return out;
+	}
+#else
+	std::vector<NetworkInterface> ListNetworkInterfaces()
+	{
+		std::vector<NetworkInterface> out;
+		ifaddrs* list = nullptr;
+		if (getifaddrs(&list) != 0)
+			return out;
+
+		for (ifaddrs* entry = list; entry; entry = entry->ifa_next) {
+			if (!entry->ifa_addr || !(entry->ifa_flags & IFF_UP) || (entry->ifa_flags & IFF_LOOPBACK))
+				continue;
+			if (std::string address = AddressText(entry->ifa_addr); !address.empty())
+				out.push_back({ entry->ifa_name, address });
+		}
+		freeifaddrs(list);
+		return out;
+	}
+#endif
+}
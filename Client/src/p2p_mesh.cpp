@@ -1,20 +1,48 @@
+#include "chat_text.h"
 #include "client.h"
 #include "p2p_mesh.h"
+#include "unix_time.h"
 
 #include <algorithm>
+#include <chrono>
+#include <cstdlib>
 #include <cstring>
+#include <mutex>
+#include <string>
 
 // The p2p half of the client: a lazily built mesh of libjuice links,
-// signaled through the server as P2PSignal frames.
+// signaled through the server as SequencedSignal frames.
 namespace Weyvelength {
 
-	constexpr uint32_t max_connection_attempts = 3; // ICE tries per peer before we give up
+	constexpr uint32_t max_retry_jitter = 500; // ms; two peers that failed together rarely redial at once
+
+	static void OnJuiceLog(juice_log_level_t level, const char* message)
+	{
+		LogLevel ours = level <= JUICE_LOG_LEVEL_DEBUG ? LogLevel::Debug
+			: level == JUICE_LOG_LEVEL_INFO ? LogLevel::Info
+			: level == JUICE_LOG_LEVEL_WARN ? LogLevel::Warn
+			: LogLevel::Error;
+		Log(ours, std::string("juice: ") + message);
+	}
+
+	// libjuice's level follows ours, so it never formats a line we would drop.
+	void SetLogLevel(LogLevel level)
+	{
+		static std::once_flag installed;
+		std::call_once(installed, [] { juice_set_log_handler(OnJuiceLog); });
+
+		ClientLog().SetLevel(level);
+		juice_set_log_level(level == LogLevel::Debug ? JUICE_LOG_LEVEL_DEBUG
+			: level == LogLevel::Info ? JUICE_LOG_LEVEL_INFO
+			: level == LogLevel::Warn ? JUICE_LOG_LEVEL_WARN
+			: JUICE_LOG_LEVEL_ERROR);
+	}
 
 	// Juice callbacks run on juice's threads; they only queue, Poll does the rest.
 	static void PushJuiceEvent(juice_agent_t* agent, void* user_ptr, JuiceEvent ev)
 	{
 		auto* ctx = static_cast<JuiceCallbackContext*>(user_ptr);
-		ev.agent = agent;
+		ev.generation = ctx->generation;
 		ev.peer = ctx->peer;
 
 		std::lock_guard lock(ctx->mesh->mutex);
@@ -51,6 +79,11 @@ namespace Weyvelength {
 		if (id == _id || std::ranges::find(_members, id) == _members.end())
 			return false;
 
+		if (auto retry = _mesh->retries.find(id); retry != _mesh->retries.end()) {
+			retry->second.outbox.push_back(msg); // the redial picks it up
+			return true;
+		}
+
 		PeerLink* link = FindLink(id);
 		if (!link) {
 			link = CreateLink(id); // lazy: the first message to a peer starts ICE
@@ -67,7 +100,93 @@ namespace Weyvelength {
 			return true;
 		}
 
-		return juice_send(link->agent, (const char*)msg.data(), msg.size()) == JUICE_ERR_SUCCESS;
+		return SendAppLink(*link, msg);
+	}
+
+	// Breakouts need no links of their own: the room's are reused, and only
+	// which peers get the message narrows.
+	bool Client::SendChannelP2P(const Proto::P2PMessage& msg)
+	{
+		auto channel = std::ranges::find_if(_channels, [this](const Proto::Channel& channel) { return std::ranges::find(channel.members, _id) != channel.members.end(); });
+		if (channel == _channels.end())
+			return false;
+
+		bool sent = true;
+		for (uint32_t id : channel->members) {
+			if (id != _id)
+				sent = SendP2P(id, msg) && sent;
+		}
+		return sent;
+	}
+
+	bool Client::SendWhisper(uint32_t id, const std::string& text)
+	{
+		if (_room.empty())
+			return HoldMessage(id, text);
+
+		if (id == _id || std::ranges::find(_members, id) == _members.end())
+			return false;
+
+		// never builds a link: a whisper rides one that is already up, or the server
+		PeerLink* link = FindLink(id);
+		bool sent = link && link->connected && link->framing != 0 && text.size() <= Proto::max_p2p_message_size
+			? SendLink(*link, Proto::P2PFrameKind::Whisper, std::as_bytes(std::span{ text }))
+			: SendKeyed(Proto::RoomWhisper{ id, text });
+		if (sent)
+			RecordChat(_id, id, text); // no echo comes back, unlike room chat
+		return sent;
+	}
+
+	bool Client::RetryPeerConnection(uint32_t id)
+	{
+		if (id == _id || std::ranges::find(_members, id) == _members.end())
+			return false;
+
+		std::deque<std::vector<std::byte>> outbox;
+		if (PeerLink* link = FindLink(id)) {
+			if (link->connected)
+				return true;
+			outbox = std::move(link->outbox);
+			DestroyLink(id); // a dial still in progress starts over too
+		}
+
+		_mesh->attempts.erase(id);
+		PeerLink* link = CreateLink(id); // takes over a scheduled retry, and its outbox
+		if (!link)
+			return false;
+
+		link->outbox.insert(link->outbox.begin(), outbox.begin(), outbox.end());
+		if (!ShareLink(*link, id)) {
+			FailLink(id);
+			return false;
+		}
+		return true;
+	}
+
+	bool Client::ClosePeerConnection(uint32_t id, bool notify)
+	{
+		bool had = FindLink(id) || _mesh->retries.contains(id);
+		if (!had)
+			return false;
+
+		DestroyLink(id);
+		_mesh->retries.erase(id); // queued datagrams go with it
+		_mesh->attempts.erase(id);
+		if (notify)
+			SendSignal(Proto::P2PSignal{ id, Proto::P2PSignalKind::Closed, {} });
+
+		_peer_events.push({ .kind = PeerEvent::Kind::Closed, .id = id });
+		return true;
+	}
+
+	bool Client::NextPeerEvent(PeerEvent& out)
+	{
+		if (_peer_events.empty())
+			return false;
+
+		out = _peer_events.front();
+		_peer_events.pop();
+		return true;
 	}
 
 	bool Client::NextP2P(uint32_t& from, Proto::P2PMessage& out)
@@ -87,6 +206,117 @@ namespace Weyvelength {
 		return it != _mesh->links.end() && it->second.connected;
 	}
 
+	void Client::SetP2POrdering(bool ordered, std::chrono::milliseconds delay)
+	{
+		_ordered = ordered;
+		_jitter_delay = std::clamp(delay, std::chrono::milliseconds{ 0 }, max_jitter_delay);
+	}
+
+	bool Client::P2POrdered() const
+	{
+		return _ordered;
+	}
+
+	std::chrono::milliseconds Client::JitterDelay() const
+	{
+		return _jitter_delay;
+	}
+
+	void Client::SetP2PFec(bool allowed)
+	{
+		_fec = allowed;
+		if (allowed)
+			return;
+		for (auto& [id, link] : _mesh->links)
+			link.parity.SetGroup(0); // the next readings tell the peers to stop theirs
+	}
+
+	bool Client::P2PFec() const
+	{
+		return _fec;
+	}
+
+	void Client::SimulateNetwork(uint32_t loss_permille, std::chrono::milliseconds latency)
+	{
+		_simulated_loss = std::min<uint32_t>(loss_permille, 1000);
+		_simulated_latency = std::clamp(latency, std::chrono::milliseconds{ 0 }, max_simulated_latency);
+	}
+
+	uint8_t Client::PeerFecGroup(uint32_t id) const
+	{
+		auto it = _mesh->links.find(id);
+		return it == _mesh->links.end() ? 0 : it->second.parity.Group();
+	}
+
+	uint32_t Client::PeerLossPermille(uint32_t id) const
+	{
+		auto it = _mesh->links.find(id);
+		return it == _mesh->links.end() ? 0 : it->second.loss_permille;
+	}
+
+	bool Client::PeerLinkPath(uint32_t id, LinkPath& out) const
+	{
+		auto it = _mesh->links.find(id);
+		if (it == _mesh->links.end() || !it->second.connected)
+			return false;
+
+		char local[JUICE_MAX_CANDIDATE_SDP_STRING_LEN], remote[JUICE_MAX_CANDIDATE_SDP_STRING_LEN];
+		if (juice_get_selected_candidates(it->second.agent, local, sizeof(local), remote, sizeof(remote)) != JUICE_ERR_SUCCESS)
+			return false;
+		auto ours = ParseIceCandidate(local);
+		auto theirs = ParseIceCandidate(remote);
+		if (!ours || !theirs)
+			return false;
+
+		out = { *ours, *theirs, _interface.name };
+		if (ours->type == CandidateType::Host && out.interface_name.empty()) { // only a host candidate names its interface by its address
+			for (const NetworkInterface& iface : ListNetworkInterfaces()) {
+				if (iface.address == ours->address)
+					out.interface_name = iface.name;
+			}
+		}
+		return true;
+	}
+
+	void Client::SetHealthReporting(bool report, bool share)
+	{
+		_health_report = report;
+		_health_share = share;
+	}
+
+	// The host's own row is built the same way, so the matrix covers every
+	// link from both ends; a link never dialed shows as down. The rates are
+	// averages over the whole interval, the byte counts starting over each time.
+	void Client::PollHealth()
+	{
+		auto now = std::chrono::steady_clock::now();
+		if (_room.empty() || now - _health_window < std::chrono::seconds(Proto::health_report_interval))
+			return;
+		uint64_t elapsed = (uint64_t)std::max<int64_t>(std::chrono::duration_cast<std::chrono::milliseconds>(now - _health_window).count(), 1);
+		_health_window = now;
+
+		Proto::MemberHealth row{ _id, (uint32_t)_round_trip.count(), {} };
+		for (uint32_t id : _members) {
+			if (id == _id || row.links.size() == Proto::max_health_links)
+				continue;
+			Proto::LinkHealth health{ id };
+			if (PeerLink* link = FindLink(id)) {
+				LinkPath path;
+				health.direct = link->connected;
+				health.relayed = PeerLinkPath(id, path) && (path.local.type == CandidateType::Relayed || path.remote.type == CandidateType::Relayed);
+				health.loss_permille = link->loss_permille;
+				health.send_rate = (uint32_t)std::min<uint64_t>(std::exchange(link->bytes_sent, 0) * 1000 / elapsed, UINT32_MAX);
+				health.receive_rate = (uint32_t)std::min<uint64_t>(std::exchange(link->bytes_received, 0) * 1000 / elapsed, UINT32_MAX);
+			}
+			row.links.push_back(health);
+		}
+
+		if (_health_report)
+			SendServer(Proto::ReportHealth{ row.server_rtt, row.links, _health_share });
+		if (IsHost())
+			_health[_id] = std::move(row);
+	}
+
 	PeerLink* Client::FindLink(uint32_t id)
 	{
 		auto it = _mesh->links.find(id);
@@ -95,13 +325,15 @@ namespace Weyvelength {
 
 	PeerLink* Client::CreateLink(uint32_t id)
 	{
-		if (_mesh->attempts[id] >= max_connection_attempts)
+		if (_mesh->attempts[id] >= PerformanceFor(_profile).connection_attempts)
 			return nullptr; // gave up on this peer until it reconnects or the room resets
 
 		PeerLink link;
 		link.ctx = std::make_unique<JuiceCallbackContext>();
 		link.ctx->mesh = _mesh.get();
 		link.ctx->peer = id;
+		link.ctx->generation = link.generation = _mesh->next_generation++;
+		link.since = std::chrono::steady_clock::now();
 
 		std::vector<juice_turn_server_t> turn;
 		for (const Proto::TurnServer& relay : _ice.turn) {
@@ -110,12 +342,17 @@ namespace Weyvelength {
 
 		juice_config_t config{};
 		config.concurrency_mode = JUICE_CONCURRENCY_MODE_POLL; // every link shares one juice thread
+		if (_port_mapper) { // and with a mapped port, one socket too: the one the router forwards
+			config.concurrency_mode = JUICE_CONCURRENCY_MODE_MUX;
+			config.local_port_range_begin = config.local_port_range_end = _port_mapper->Port();
+		}
 		if (!_ice.stun_host.empty()) {
 			config.stun_server_host = _ice.stun_host.c_str();
 			config.stun_server_port = _ice.stun_port;
 		}
 		config.turn_servers = turn.empty() ? nullptr : turn.data();
 		config.turn_servers_count = (int)turn.size();
+		config.bind_address = _interface.address.empty() ? nullptr : _interface.address.c_str(); // gathers on that interface alone
 		config.cb_state_changed = OnJuiceState;
 		config.cb_candidate = OnJuiceCandidate;
 		config.cb_gathering_done = OnJuiceGatheringDone;
@@ -127,7 +364,14 @@ namespace Weyvelength {
 			return nullptr;
 
 		_mesh->attempts[id]++; // count only agents that actually started an ICE round
-		return &_mesh->links.emplace(id, std::move(link)).first->second;
+		PeerLink& created = _mesh->links.emplace(id, std::move(link)).first->second;
+
+		// a dial from either side ends any wait for our redial
+		if (auto retry = _mesh->retries.find(id); retry != _mesh->retries.end()) {
+			created.outbox = std::move(retry->second.outbox);
+			_mesh->retries.erase(retry);
+		}
+		return &created;
 	}
 
 	// Sends the local description; gathering then trickles the candidates.
@@ -137,18 +381,199 @@ namespace Weyvelength {
 		if (juice_get_local_description(link.agent, sdp, sizeof(sdp)) != JUICE_ERR_SUCCESS)
 			return false;
 
-		SendServer(Proto::P2PSignal{ id, Proto::P2PSignalKind::Description, sdp });
+		SendSignal(Proto::P2PSignal{ id, Proto::P2PSignalKind::Description, sdp });
+		SendSignal(Proto::P2PSignal{ id, Proto::P2PSignalKind::Framing, std::to_string(Proto::p2p_framing_version) });
 		return juice_gather_candidates(link.agent) == JUICE_ERR_SUCCESS;
 	}
 
+	static bool Transmit(PeerLink& link, std::span<const std::byte> datagram) // juice_send, counted for the health report
+	{
+		link.bytes_sent += datagram.size();
+		return juice_send(link.agent, (const char*)datagram.data(), datagram.size()) == JUICE_ERR_SUCCESS;
+	}
+
 	void Client::FlushLink(PeerLink& link)
 	{
 		for (const std::vector<std::byte>& data : link.outbox) {
-			juice_send(link.agent, (const char*)data.data(), data.size());
+			SendAppLink(link, data);
 		}
 		link.outbox.clear();
 	}
 
+	// Raw links only carry app datagrams; anything else needs a framing peer.
+	bool Client::SendLink(PeerLink& link, Proto::P2PFrameKind kind, std::span<const std::byte> body)
+	{
+		if (link.framing == 0) {
+			if (kind != Proto::P2PFrameKind::App)
+				return false;
+			return Transmit(link, body);
+		}
+
+		return Transmit(link, Proto::FrameP2P(kind, body));
+	}
+
+	bool Client::SendAppLink(PeerLink& link, std::span<const std::byte> body)
+	{
+		if (!_ordered || link.framing < 2) // a version 1 peer would drop Sequenced frames
+			return SendLink(link, Proto::P2PFrameKind::App, body);
+
+		uint32_t seq = link.next_seq++;
+		bool sent = Transmit(link, Proto::FrameSequenced(seq, body));
+		if (auto parity = link.parity.Add(seq, body))
+			Transmit(link, Proto::FrameParity(*parity));
+		return sent;
+	}
+
+	void Client::ReceiveSequenced(PeerLink& link, std::span<const std::byte> body)
+	{
+		uint32_t seq = 0;
+		std::span<const std::byte> payload;
+		if (!Proto::UnframeSequenced(body, seq, payload))
+			return;
+
+		link.recovery.Keep(seq, payload);
+		if (!link.jitter.Push(seq, { payload.begin(), payload.end() }, std::chrono::steady_clock::now())) // PollJitter hands it on
+			return;
+		if (auto permille = link.loss.Add(seq))
+			ReadLoss(link, *permille);
+	}
+
+	void Client::ReadLoss(PeerLink& link, uint32_t permille)
+	{
+		link.loss_permille = permille;
+		if (link.framing < 3 || (!_fec && !link.fec_spoken))
+			return;
+
+		uint8_t want = link.fec_asked;
+		if (!_fec || permille < fec_off_permille)
+			want = 0;
+		else if (permille >= fec_on_permille)
+			want = fec_group;
+		std::byte request{ want };
+		SendLink(link, Proto::P2PFrameKind::FecRequest, { &request, 1 });
+		link.fec_asked = want;
+		link.fec_spoken = true;
+	}
+
+	void Client::ReceiveLink(uint32_t id, PeerLink& link, std::vector<std::byte>& datagram)
+	{
+		if (link.framing == 0) {
+			_p2p_inbox.emplace(id, std::move(datagram));
+			return;
+		}
+
+		Proto::P2PFrameKind kind;
+		std::span<const std::byte> body;
+		if (!Proto::UnframeP2P(datagram, kind, body))
+			return;
+
+		switch (kind) {
+		case Proto::P2PFrameKind::App:
+			_p2p_inbox.emplace(id, Proto::P2PMessage{ body.begin(), body.end() });
+			break;
+		case Proto::P2PFrameKind::Whisper: {
+			// the server never sees these, so we hold the line it would have
+			if (!Proto::WhisperAllowed(_prefs, id == _host || Role(id) != Proto::MemberRole::Member, Proto::UnixNow()))
+				break;
+			std::string text{ (const char*)body.data(), body.size() };
+			if (Proto::SanitizeChat(text) != Proto::ChatTextIssue::None)
+				break;
+			RecordChat(id, _id, text);
+			_inbox.emplace(_room, Proto::RoomWhisper{ id, std::move(text) });
+			break;
+		}
+		case Proto::P2PFrameKind::Sequenced:
+			ReceiveSequenced(link, body);
+			break;
+		case Proto::P2PFrameKind::Parity: {
+			Proto::P2PParity parity;
+			if (!Proto::UnframeParity(body, parity))
+				break;
+			if (auto rebuilt = link.recovery.Rebuild(parity)) {
+				link.recovery.Keep(rebuilt->first, rebuilt->second);
+				link.jitter.Push(rebuilt->first, std::move(rebuilt->second), std::chrono::steady_clock::now()); // not counted: the wire still lost it
+			}
+			break;
+		}
+		case Proto::P2PFrameKind::FecRequest: {
+			uint8_t group = body.empty() ? 0 : std::min((uint8_t)body[0], max_fec_group);
+			if (!_fec)
+				group = 0;
+			if (group != link.parity.Group())
+				link.parity.SetGroup(group);
+			break;
+		}
+		default:
+			break; // a kind from a newer peer; drop it
+		}
+	}
+
+	void Client::FailLink(uint32_t id)
+	{
+		std::deque<std::vector<std::byte>> outbox;
+		if (PeerLink* link = FindLink(id))
+			outbox = std::move(link->outbox);
+		DestroyLink(id);
+
+		PerformanceSettings settings = PerformanceFor(_profile);
+		uint32_t attempt = std::max<uint32_t>(_mesh->attempts[id], 1); // 0 after a link that had connected
+		if (attempt >= settings.connection_attempts) {
+			_mesh->retries.erase(id);
+			_peer_events.push({ PeerEvent::Kind::Unreachable, id, attempt, 0 });
+			Log(LogLevel::Error, "Link to " + std::to_string(id) + " failed " + std::to_string(attempt) + " time(s); giving up");
+			return;
+		}
+
+		auto delay = settings.first_retry_delay * (1u << (attempt - 1))
+			+ std::chrono::milliseconds(std::uniform_int_distribution<uint32_t>(0, max_retry_jitter)(_mesh->rng));
+
+		PeerRetry& retry = _mesh->retries[id];
+		retry.due = std::chrono::steady_clock::now() + delay;
+		retry.outbox = std::move(outbox);
+		_peer_events.push({ PeerEvent::Kind::Retrying, id, attempt, (uint32_t)delay.count() });
+		Log(LogLevel::Warn, "Link to " + std::to_string(id) + " failed (attempt " + std::to_string(attempt) + "); redialing in " + std::to_string(delay.count()) + " ms");
+	}
+
+	void Client::PollRetries()
+	{
+		auto now = std::chrono::steady_clock::now();
+		std::vector<uint32_t> due;
+		for (const auto& [id, retry] : _mesh->retries) {
+			if (retry.due <= now)
+				due.push_back(id);
+		}
+
+		for (uint32_t id : due) {
+			PeerLink* link = CreateLink(id); // takes the retry's outbox and clears it
+			if (!link) {
+				_mesh->retries.erase(id);
+				_peer_events.push({ PeerEvent::Kind::Unreachable, id, _mesh->attempts[id], 0 });
+				Log(LogLevel::Error, "Link to " + std::to_string(id) + " could not be redialed: no ICE agent");
+			}
+			else if (!ShareLink(*link, id)) {
+				FailLink(id);
+			}
+		}
+	}
+
+	// Juice reports FAILED for a link whose checks ran out, but a peer that
+	// never answers our description leaves ours gathering forever, and a
+	// DISCONNECTED link may never settle either way. Those go through the
+	// same failure path, so they are redialed or given up on like any other.
+	void Client::SweepLinks()
+	{
+		auto now = std::chrono::steady_clock::now();
+		std::vector<uint32_t> stale;
+		for (const auto& [id, link] : _mesh->links) {
+			if (!link.connected && now - link.since >= _link_timeout)
+				stale.push_back(id);
+		}
+
+		for (uint32_t id : stale) {
+			FailLink(id);
+		}
+	}
+
 	void Client::DestroyLink(uint32_t id)
 	{
 		auto it = _mesh->links.find(id);
@@ -166,10 +591,31 @@ namespace Weyvelength {
 		}
 		_mesh->links.clear();
 		_mesh->attempts.clear();
+		_mesh->retries.clear();
+	}
+
+	void Client::SendSignal(Proto::P2PSignal&& sig)
+	{
+		SendSignaling(Proto::SequencedSignal{ ++_mesh->signal_seq, std::move(sig) });
+	}
+
+	void Client::HandleSequencedSignal(const Proto::SequencedSignal& sig)
+	{
+		uint64_t& last = _mesh->signal_seq_from[sig.signal.id];
+		if (!Proto::FreshSignal(last, sig.seq)) {
+			Log(LogLevel::Warn, "Dropped a replayed signal from " + std::to_string(sig.signal.id) + " (seq " + std::to_string(sig.seq) + ", last " + std::to_string(last) + ")");
+			return;
+		}
+		HandleP2PSignal(sig.signal);
 	}
 
 	void Client::HandleP2PSignal(const Proto::P2PSignal& sig)
 	{
+		if (!Proto::ValidSignalPayload(sig.kind, sig.payload)) {
+			Log(LogLevel::Warn, "Dropped a malformed signal from " + std::to_string(sig.id) + " (" + std::to_string(sig.payload.size()) + " bytes)");
+			return; // the server checks too; an older one may not
+		}
+
 		PeerLink* link = FindLink(sig.id);
 
 		switch (sig.kind) {
@@ -184,6 +630,15 @@ namespace Weyvelength {
 			if (link)
 				juice_set_remote_gathering_done(link->agent);
 			break;
+		case Proto::P2PSignalKind::Framing:
+			// trails the description, so it lands before the link can connect
+			if (link)
+				link->framing = (uint8_t)std::min<unsigned long>(std::strtoul(sig.payload.c_str(), nullptr, 10), Proto::p2p_framing_version);
+			break;
+		case Proto::P2PSignalKind::Closed:
+			if (ClosePeerConnection(sig.id, false)) // nothing to echo back; they are already gone
+				_peer_events.back().remote = true;
+			break;
 		}
 	}
 
@@ -192,8 +647,10 @@ namespace Weyvelength {
 		// a second description on a link that already spent its one
 		// juice_set_remote_description is the peer redialing with a fresh agent
 		// (theirs died, ours survived); ours is pinned to the old credentials,
-		// so tear it down and answer the new one
+		// so tear it down and answer the new one, keeping what it had queued
+		std::deque<std::vector<std::byte>> outbox;
 		if (link && link->remote_set) {
+			outbox = std::move(link->outbox);
 			DestroyLink(sig.id);
 			link = nullptr;
 		}
@@ -204,6 +661,7 @@ namespace Weyvelength {
 			link = CreateLink(sig.id); // a peer reached out with no link of ours yet
 			if (!link)
 				return;
+			link->outbox.insert(link->outbox.begin(), outbox.begin(), outbox.end());
 		}
 
 		if (!link->remote_set) { // glare aside, apply the remote description once
@@ -223,16 +681,46 @@ namespace Weyvelength {
 			events.swap(_mesh->events);
 		}
 
+		// a simulated bad network sits between juice and the links, so
+		// everything above sees the loss and lateness as if on the wire
+		auto now = std::chrono::steady_clock::now();
+		bool simulating = _simulated_loss != 0 || _simulated_latency.count() != 0;
 		for (JuiceEvent& ev : events) {
-			HandleJuiceEvent(ev);
+			if (ev.kind != JuiceEvent::Kind::Recv || !simulating)
+				HandleJuiceEvent(ev);
+			else if (std::uniform_int_distribution<uint32_t>(0, 999)(_mesh->rng) >= _simulated_loss)
+				_mesh->delayed.emplace_back(now + _simulated_latency, std::move(ev));
 		}
 		events.clear(); // keeps its capacity for the next poll
+
+		while (!_mesh->delayed.empty() && _mesh->delayed.front().first <= now) {
+			HandleJuiceEvent(_mesh->delayed.front().second);
+			_mesh->delayed.pop_front();
+		}
+
+		SweepLinks();
+		PollRetries();
+		PollJitter();
+	}
+
+	void Client::PollJitter()
+	{
+		auto now = std::chrono::steady_clock::now();
+		std::vector<std::vector<std::byte>> due;
+		for (auto& [id, link] : _mesh->links) {
+			if (link.jitter.Empty())
+				continue;
+			link.jitter.Pop(now, _jitter_delay, due);
+			for (std::vector<std::byte>& payload : due)
+				_p2p_inbox.emplace(id, std::move(payload));
+			due.clear();
+		}
 	}
 
 	void Client::HandleJuiceEvent(JuiceEvent& ev)
 	{
 		PeerLink* link = FindLink(ev.peer);
-		if (!link || link->agent != ev.agent)
+		if (!link || link->generation != ev.generation)
 			return; // the link was torn down (or rebuilt) after this event was queued
 
 		switch (ev.kind) {
@@ -240,19 +728,25 @@ namespace Weyvelength {
 			HandleLinkState(*link, ev);
 			break;
 		case JuiceEvent::Kind::Candidate:
-			SendServer(Proto::P2PSignal{ ev.peer, Proto::P2PSignalKind::Candidate, std::string{ (const char*)ev.payload.data(), ev.payload.size() } });
+			SendSignal(Proto::P2PSignal{ ev.peer, Proto::P2PSignalKind::Candidate, std::string{ (const char*)ev.payload.data(), ev.payload.size() } });
 			break;
 		case JuiceEvent::Kind::GatheringDone:
-			SendServer(Proto::P2PSignal{ ev.peer, Proto::P2PSignalKind::GatheringDone, {} });
+			SendSignal(Proto::P2PSignal{ ev.peer, Proto::P2PSignalKind::GatheringDone, {} });
 			break;
 		case JuiceEvent::Kind::Recv:
-			_p2p_inbox.emplace(ev.peer, std::move(ev.payload));
+			link->bytes_received += ev.payload.size();
+			ReceiveLink(ev.peer, *link, ev.payload);
 			break;
 		}
 	}
 
 	void Client::HandleLinkState(PeerLink& link, const JuiceEvent& ev)
 	{
+		if (link.state != ev.state) {
+			link.state = ev.state;
+			link.since = std::chrono::steady_clock::now();
+		}
+
 		switch (ev.state) {
 		case JUICE_STATE_CONNECTED:
 		case JUICE_STATE_COMPLETED:
@@ -264,7 +758,7 @@ namespace Weyvelength {
 			link.connected = false; // may still recover; sends queue meanwhile
 			break;
 		case JUICE_STATE_FAILED:
-			DestroyLink(ev.peer); // the next SendP2P retries, up to max_connection_attempts
+			FailLink(ev.peer); // redials after a backoff, up to the profile's connection_attempts
 			break;
 		default:
 			break;
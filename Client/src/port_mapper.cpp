@@ -0,0 +1,444 @@
+#include "port_mapper.h"
+
+#ifdef _WIN32
+#define _WIN32_WINNT 0x0A00
+#endif // _WIN32
+
+#include <chrono>
+#include <condition_variable>
+#include <cstring>
+#include <fstream>
+#include <mutex>
+#include <queue>
+#include <span>
+#include <sstream>
+#include <thread>
+
+#include <thirdparty\asio\asio.hpp>
+
+#if defined(_WIN32) && !defined(WEYVE_NO_PORT_MAPPING)
+#include <iphlpapi.h>
+#pragma comment(lib, "iphlpapi.lib")
+#endif // _WIN32
+
+namespace Weyvelength {
+#ifndef WEYVE_NO_PORT_MAPPING
+	constexpr std::chrono::milliseconds nat_pmp_first_wait{ 250 }; // doubled per retry, as RFC 6886 asks
+	constexpr int nat_pmp_attempts = 3;
+	constexpr std::chrono::milliseconds ssdp_wait{ 2000 };
+	constexpr std::chrono::milliseconds http_wait{ 2000 }; // per connect, and again for the whole reply
+	constexpr std::chrono::milliseconds quick_wait{ 500 }; // removing a mapping on the way out; best effort
+	constexpr size_t max_http_reply = 256 * 1024;
+
+	struct UpnpTarget { // where a UPnP mapping was made; kept to renew and remove it
+		HttpUrl control;
+		std::string service_type;
+		std::string local_address; // ours, as the router sees us
+	};
+
+	struct PortMapper::Impl {
+		uint16_t port = 0;
+		asio::io_context io; // the worker's only; stop() is the one call from outside
+
+		std::mutex mutex; // guards stopping and results
+		std::condition_variable wake;
+		bool stopping = false;
+		std::queue<PortMapping> results;
+
+		// the worker's own
+		std::optional<asio::ip::address_v4> gateway;
+		PortMapping current;
+		std::optional<UpnpTarget> upnp;
+		std::chrono::seconds renew_after{ port_mapping_lifetime / 2 };
+		bool unmapping = false; // past stopping on purpose, to remove the mapping on the way out
+
+		std::thread worker; // last, so it starts once the rest exists
+	};
+
+	// The route to everywhere else; null where the platform will not say,
+	// and then only UPnP is tried.
+	static std::optional<asio::ip::address_v4> DefaultGateway()
+	{
+#ifdef _WIN32
+		MIB_IPFORWARDROW row{};
+		if (GetBestRoute(0, 0, &row) != NO_ERROR || row.dwForwardNextHop == 0)
+			return std::nullopt;
+		asio::ip::address_v4::bytes_type bytes;
+		std::memcpy(bytes.data(), &row.dwForwardNextHop, bytes.size()); // network order already
+		return asio::ip::address_v4(bytes);
+#elif defined(__linux__)
+		std::ifstream routes("/proc/net/route");
+		std::string line;
+		std::getline(routes, line); // the column names
+		while (std::getline(routes, line)) {
+			std::istringstream fields(line);
+			std::string iface, destination, gateway;
+			unsigned flags = 0;
+			if (!(fields >> iface >> destination >> gateway >> std::hex >> flags) || destination != "00000000" || !(flags & 0x2)) // RTF_GATEWAY
+				continue;
+			uint32_t raw = (uint32_t)std::stoul(gateway, nullptr, 16); // the address's bytes, printed as a host-order word
+			asio::ip::address_v4::bytes_type bytes;
+			std::memcpy(bytes.data(), &raw, bytes.size());
+			return asio::ip::address_v4(bytes);
+		}
+		return std::nullopt;
+#else
+		return std::nullopt;
+#endif
+	}
+
+	static bool Stopping(PortMapper::Impl& impl)
+	{
+		std::lock_guard lock(impl.mutex);
+		return impl.stopping;
+	}
+
+	struct Outcome {
+		asio::error_code ec = asio::error::timed_out;
+		size_t bytes = 0;
+	};
+
+	// Runs one async operation for at most limit. On a timeout, or the
+	// destructor stopping the context, the operation is cancelled and waited
+	// out so its handler never outlives this frame. restart() would clear a
+	// stop() that landed between two calls, so the flag is read after it:
+	// once stopping, nothing waits but Unmap.
+	template <typename Socket, typename Start>
+	static Outcome Within(PortMapper::Impl& impl, Socket& socket, std::chrono::milliseconds limit, Start start)
+	{
+		Outcome out;
+		bool done = false;
+		start([&](asio::error_code ec, auto... bytes) {
+			done = true;
+			out.ec = ec;
+			((out.bytes = (size_t)bytes), ...);
+		});
+		impl.io.restart();
+		if (impl.unmapping || !Stopping(impl))
+			impl.io.run_for(limit);
+		if (done)
+			return out;
+
+		asio::error_code ignored;
+		socket.cancel(ignored);
+		while (!done) {
+			impl.io.restart();
+			impl.io.run();
+		}
+		return {};
+	}
+
+	// One request to the gateway, retried on silence; the reply, or empty.
+	// The first try goes out even when stopping: that is how Unmap speaks.
+	static std::vector<uint8_t> AskNatPmp(PortMapper::Impl& impl, asio::ip::address_v4 gateway, std::span<const uint8_t> request, int attempts)
+	{
+		asio::error_code ec;
+		asio::ip::udp::socket socket(impl.io);
+		socket.open(asio::ip::udp::v4(), ec);
+		if (ec)
+			return {};
+
+		asio::ip::udp::endpoint to{ gateway, nat_pmp_port };
+		std::chrono::milliseconds wait = nat_pmp_first_wait;
+		for (int attempt = 0; attempt < attempts && (attempt == 0 || !Stopping(impl)); attempt++, wait *= 2) {
+			socket.send_to(asio::buffer(request.data(), request.size()), to, 0, ec);
+			if (ec)
+				return {};
+
+			auto deadline = std::chrono::steady_clock::now() + wait;
+			for (auto now = std::chrono::steady_clock::now(); now < deadline; now = std::chrono::steady_clock::now()) {
+				std::vector<uint8_t> reply(16);
+				asio::ip::udp::endpoint from;
+				Outcome got = Within(impl, socket, std::chrono::duration_cast<std::chrono::milliseconds>(deadline - now),
+					[&](auto handler) { socket.async_receive_from(asio::buffer(reply), from, handler); });
+				if (got.ec)
+					break;
+				if (from == to && got.bytes >= 2 && reply[1] == 128 + request[1]) { // strays and other opcodes' replies ignored
+					reply.resize(got.bytes);
+					return reply;
+				}
+			}
+		}
+		return {};
+	}
+
+	static PortMapping MapNatPmp(PortMapper::Impl& impl)
+	{
+		PortMapping out{ false, PortMapping::Method::NatPmp, impl.port };
+		auto request = NatPmpMapRequest(impl.port, impl.port, port_mapping_lifetime);
+		std::vector<uint8_t> reply = AskNatPmp(impl, *impl.gateway, request, nat_pmp_attempts);
+		auto map = ParseNatPmpMapResponse(reply.data(), reply.size());
+		if (!map) {
+			out.error = "NAT-PMP: no answer from " + impl.gateway->to_string();
+			return out;
+		}
+		if (map->result != 0) {
+			out.error = NatPmpResultText(map->result);
+			return out;
+		}
+
+		out.mapped = true;
+		out.external_port = map->external_port;
+		impl.renew_after = std::chrono::seconds(std::max<uint32_t>(map->lifetime / 2, 60));
+
+		constexpr std::array<uint8_t, 2> address_request{ 0, 0 };
+		std::vector<uint8_t> address = AskNatPmp(impl, *impl.gateway, address_request, nat_pmp_attempts);
+		if (auto bytes = ParseNatPmpAddressResponse(address.data(), address.size()))
+			out.external_address = asio::ip::address_v4(*bytes).to_string();
+		return out;
+	}
+
+	// A description URL from the first gateway to answer an SSDP search.
+	static std::optional<HttpUrl> DiscoverUpnp(PortMapper::Impl& impl)
+	{
+		asio::error_code ec;
+		asio::ip::udp::socket socket(impl.io);
+		socket.open(asio::ip::udp::v4(), ec);
+		if (ec)
+			return std::nullopt;
+
+		constexpr std::string_view search =
+			"M-SEARCH * HTTP/1.1\r\n"
+			"HOST: 239.255.255.250:1900\r\n"
+			"MAN: \"ssdp:discover\"\r\n"
+			"MX: 2\r\n"
+			"ST: urn:schemas-upnp-org:device:InternetGatewayDevice:1\r\n"
+			"\r\n";
+		asio::ip::udp::endpoint multicast{ asio::ip::make_address_v4("239.255.255.250"), 1900 };
+		socket.send_to(asio::buffer(search), multicast, 0, ec);
+		if (ec)
+			return std::nullopt;
+
+		auto deadline = std::chrono::steady_clock::now() + ssdp_wait;
+		for (auto now = std::chrono::steady_clock::now(); now < deadline && !Stopping(impl); now = std::chrono::steady_clock::now()) {
+			std::string reply(2048, '\0');
+			asio::ip::udp::endpoint from;
+			Outcome got = Within(impl, socket, std::chrono::duration_cast<std::chrono::milliseconds>(deadline - now),
+				[&](auto handler) { socket.async_receive_from(asio::buffer(reply), from, handler); });
+			if (got.ec)
+				return std::nullopt;
+			reply.resize(got.bytes);
+			if (auto location = ParseHttpUrl(HttpHeader(reply, "LOCATION")))
+				return location;
+		}
+		return std::nullopt;
+	}
+
+	// One request, one reply read to the close. Hosts must be addresses: an
+	// IGD on the LAN never names itself otherwise. local gets our end's.
+	static std::optional<HttpResponse> HttpExchange(PortMapper::Impl& impl, const HttpUrl& url, const std::string& request, std::chrono::milliseconds limit, std::string* local = nullptr)
+	{
+		asio::error_code ec;
+		asio::ip::address address = asio::ip::make_address(url.host, ec);
+		if (ec)
+			return std::nullopt;
+
+		asio::ip::tcp::socket socket(impl.io);
+		Outcome connected = Within(impl, socket, limit, [&](auto handler) { socket.async_connect({ address, url.port }, handler); });
+		if (connected.ec)
+			return std::nullopt;
+		if (local)
+			*local = socket.local_endpoint(ec).address().to_string();
+
+		Outcome sent = Within(impl, socket, limit, [&](auto handler) { asio::async_write(socket, asio::buffer(request), handler); });
+		if (sent.ec)
+			return std::nullopt;
+
+		std::string raw;
+		Outcome read = Within(impl, socket, limit, [&](auto handler) { asio::async_read(socket, asio::dynamic_buffer(raw, max_http_reply), handler); });
+		if (read.ec && read.ec != asio::error::eof)
+			return std::nullopt;
+		return ParseHttpResponse(raw);
+	}
+
+	static std::optional<HttpResponse> SoapCall(PortMapper::Impl& impl, const UpnpTarget& target, std::string_view action,
+		const std::vector<std::pair<std::string, std::string>>& args, std::chrono::milliseconds limit)
+	{
+		std::string body = UpnpSoapBody(target.service_type, action, args);
+		std::string request = "POST " + target.control.path + " HTTP/1.1\r\n"
+			"Host: " + target.control.host + ":" + std::to_string(target.control.port) + "\r\n"
+			"Content-Type: text/xml; charset=\"utf-8\"\r\n"
+			"SOAPAction: \"" + target.service_type + "#" + std::string(action) + "\"\r\n"
+			"Content-Length: " + std::to_string(body.size()) + "\r\n"
+			"Connection: close\r\n"
+			"\r\n" + body;
+		return HttpExchange(impl, target.control, request, limit);
+	}
+
+	static std::string SoapError(const HttpResponse& reply)
+	{
+		std::string code = XmlValue(reply.body, "errorCode");
+		std::string text = XmlValue(reply.body, "errorDescription");
+		if (code.empty())
+			return "HTTP " + std::to_string(reply.status);
+		return text.empty() ? code : code + " " + text;
+	}
+
+	static PortMapping MapUpnp(PortMapper::Impl& impl)
+	{
+		PortMapping out{ false, PortMapping::Method::Upnp, impl.port };
+		std::optional<HttpUrl> location = DiscoverUpnp(impl);
+		if (!location) {
+			out.error = "UPnP: no gateway answered";
+			return out;
+		}
+
+		UpnpTarget target;
+		std::string get = "GET " + location->path + " HTTP/1.1\r\n"
+			"Host: " + location->host + ":" + std::to_string(location->port) + "\r\n"
+			"Connection: close\r\n"
+			"\r\n";
+		std::optional<HttpResponse> description = HttpExchange(impl, *location, get, http_wait, &target.local_address);
+		std::optional<UpnpService> service = description && description->status == 200 ? FindWanService(description->body) : std::nullopt;
+		std::optional<HttpUrl> control = service ? ResolveControlUrl(*location, service->control_url) : std::nullopt;
+		if (!control) {
+			out.error = "UPnP: the gateway offers no WAN connection";
+			return out;
+		}
+		target.control = *control;
+		target.service_type = service->type;
+
+		// some routers only take permanent leases; the destructor still
+		// removes it, and a crash leaves what any UPnP app would
+		for (uint32_t lease : { port_mapping_lifetime, 0u }) {
+			std::optional<HttpResponse> reply = SoapCall(impl, target, "AddPortMapping", {
+				{ "NewRemoteHost", "" },
+				{ "NewExternalPort", std::to_string(impl.port) },
+				{ "NewProtocol", "UDP" },
+				{ "NewInternalPort", std::to_string(impl.port) },
+				{ "NewInternalClient", target.local_address },
+				{ "NewEnabled", "1" },
+				{ "NewPortMappingDescription", "Weyvelength" },
+				{ "NewLeaseDuration", std::to_string(lease) },
+			}, http_wait);
+			if (!reply) {
+				out.error = "UPnP: no answer from the gateway";
+				return out;
+			}
+			if (reply->status == 200)
+				break;
+			if (lease != 0 && XmlValue(reply->body, "errorCode") == "725") // OnlyPermanentLeasesSupported
+				continue;
+			out.error = "UPnP: " + SoapError(*reply);
+			return out;
+		}
+
+		out.mapped = true;
+		out.external_port = impl.port;
+		impl.renew_after = std::chrono::seconds(port_mapping_lifetime / 2);
+		if (auto reply = SoapCall(impl, target, "GetExternalIPAddress", {}, http_wait); reply && reply->status == 200)
+			out.external_address = XmlValue(reply->body, "NewExternalIPAddress");
+		impl.upnp = std::move(target);
+		return out;
+	}
+
+	static PortMapping Map(PortMapper::Impl& impl)
+	{
+		PortMapping nat_pmp;
+		if (impl.gateway) {
+			nat_pmp = MapNatPmp(impl);
+			if (nat_pmp.mapped || Stopping(impl))
+				return nat_pmp;
+		}
+
+		PortMapping upnp = MapUpnp(impl);
+		if (!upnp.mapped && !nat_pmp.error.empty())
+			upnp.error = nat_pmp.error + "; " + upnp.error;
+		return upnp;
+	}
+
+	static void Unmap(PortMapper::Impl& impl)
+	{
+		if (!impl.current.mapped)
+			return;
+
+		if (impl.current.method == PortMapping::Method::NatPmp && impl.gateway) {
+			auto request = NatPmpMapRequest(impl.port, 0, 0); // lifetime 0 deletes
+			AskNatPmp(impl, *impl.gateway, request, 1);
+		}
+		else if (impl.current.method == PortMapping::Method::Upnp && impl.upnp) {
+			SoapCall(impl, *impl.upnp, "DeletePortMapping", {
+				{ "NewRemoteHost", "" },
+				{ "NewExternalPort", std::to_string(impl.current.external_port) },
+				{ "NewProtocol", "UDP" },
+			}, quick_wait);
+		}
+	}
+
+	static void Run(PortMapper::Impl& impl)
+	{
+		impl.gateway = DefaultGateway();
+		for (bool first = true;; first = false) {
+			PortMapping result = Map(impl);
+			bool changed = result.mapped != impl.current.mapped || result.method != impl.current.method
+				|| result.external_port != impl.current.external_port || result.external_address != impl.current.external_address
+				|| result.error != impl.current.error;
+			impl.current = result;
+
+			std::unique_lock lock(impl.mutex);
+			if (impl.stopping)
+				break;
+			if (first || changed)
+				impl.results.push(result);
+			if (!result.mapped) {
+				impl.wake.wait(lock, [&] { return impl.stopping; }); // no retry: a new network gets a new mapper
+				break;
+			}
+			if (impl.wake.wait_for(lock, impl.renew_after, [&] { return impl.stopping; }))
+				break;
+		}
+		impl.unmapping = true;
+		Unmap(impl);
+	}
+
+	PortMapper::PortMapper(uint16_t port) :
+		_impl(std::make_unique<Impl>())
+	{
+		_impl->port = port;
+		_impl->worker = std::thread(Run, std::ref(*_impl));
+	}
+
+	PortMapper::~PortMapper()
+	{
+		{
+			std::lock_guard lock(_impl->mutex);
+			_impl->stopping = true;
+		}
+		_impl->wake.notify_all();
+		_impl->io.stop(); // cuts short whatever it is waiting on
+		_impl->worker.join();
+	}
+#else
+	// Built without the router protocols: every mapper answers once,
+	// unmapped, and does nothing more.
+	struct PortMapper::Impl {
+		uint16_t port = 0;
+		std::mutex mutex;
+		std::queue<PortMapping> results;
+	};
+
+	PortMapper::PortMapper(uint16_t port) :
+		_impl(std::make_unique<Impl>())
+	{
+		_impl->port = port;
+		_impl->results.push({ false, PortMapping::Method::None, port, 0, {}, "this build has no port mapping (WEYVE_NO_PORT_MAPPING)" });
+	}
+
+	PortMapper::~PortMapper() = default;
+#endif
+
+	uint16_t PortMapper::Port() const
+	{
+		return _impl->port;
+	}
+
+	bool PortMapper::Next(PortMapping& out)
+	{
+		std::lock_guard lock(_impl->mutex);
+		if (_impl->results.empty())
+			return false;
+		out = std::move(_impl->results.front());
+		_impl->results.pop();
+		return true;
+	}
+}
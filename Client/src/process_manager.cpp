@@ -0,0 +1,360 @@
+#include "process_manager.h"
+
+#if defined(WEYVE_NO_GAME_SERVER)
+#elif defined(_WIN32)
+#define WIN32_LEAN_AND_MEAN
+#define NOMINMAX
+#include <windows.h>
+#else
+#include <cerrno>
+#include <fcntl.h>
+#include <signal.h>
+#include <spawn.h>
+#include <sys/socket.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+extern char** environ;
+#endif
+
+#include <algorithm>
+
+// The platform halves are the same five steps: spawn with redirected
+// stdio, write a command, read what is there without blocking, notice the
+// exit, and kill. Everything else is shared below them. A build with
+// WEYVE_NO_GAME_SERVER gets a third half that never spawns anything.
+namespace Weyvelength {
+
+#if defined(WEYVE_NO_GAME_SERVER)
+	struct ProcessManager::Impl {};
+
+	static std::unique_ptr<ProcessManager::Impl> Spawn(const std::vector<std::string>&)
+	{
+		return nullptr;
+	}
+
+	static bool WriteAll(ProcessManager::Impl&, const std::string&) { return false; }
+	static void ReadAvailable(ProcessManager::Impl&, std::string&) {}
+	static bool Reap(ProcessManager::Impl&, int&, bool = false) { return true; }
+	static void Kill(ProcessManager::Impl&) {}
+	static void Close(ProcessManager::Impl&) {}
+#elif defined(_WIN32)
+	struct ProcessManager::Impl {
+		HANDLE process = nullptr;
+		HANDLE input = nullptr; // our end of its stdin
+		HANDLE output = nullptr; // our end of its stdout and stderr
+	};
+
+	// CommandLineToArgvW's rules, backwards: quoted when it has to be,
+	// backslashes doubled only where they precede a quote.
+	static std::string QuoteArg(const std::string& arg)
+	{
+		if (!arg.empty() && arg.find_first_of(" \t\n\v\"") == std::string::npos)
+			return arg;
+
+		std::string out = "\"";
+		for (auto it = arg.begin();; ++it) {
+			size_t slashes = 0;
+			while (it != arg.end() && *it == '\\') {
+				++it;
+				++slashes;
+			}
+			if (it == arg.end()) {
+				out.append(slashes * 2, '\\');
+				break;
+			}
+			if (*it == '"')
+				out.append(slashes * 2 + 1, '\\');
+			else
+				out.append(slashes, '\\');
+			out += *it;
+		}
+		return out + "\"";
+	}
+
+	static std::wstring Widen(const std::string& text)
+	{
+		int size = MultiByteToWideChar(CP_UTF8, 0, text.data(), (int)text.size(), nullptr, 0);
+		std::wstring out(size, L'\0');
+		MultiByteToWideChar(CP_UTF8, 0, text.data(), (int)text.size(), out.data(), size);
+		return out;
+	}
+
+	static std::unique_ptr<ProcessManager::Impl> Spawn(const std::vector<std::string>& argv)
+	{
+		std::string line;
+		for (const std::string& arg : argv)
+			line += (line.empty() ? "" : " ") + QuoteArg(arg);
+		std::wstring wide = Widen(line);
+
+		// created uninheritable, so our ends never are; the game's ends are
+		// marked after, and the handle list below passes it those two alone
+		// rather than every inheritable handle the app happens to hold
+		HANDLE in_read = nullptr, in_write = nullptr, out_read = nullptr, out_write = nullptr;
+		if (!CreatePipe(&in_read, &in_write, nullptr, 0))
+			return nullptr;
+		if (!CreatePipe(&out_read, &out_write, nullptr, 0)) {
+			CloseHandle(in_read);
+			CloseHandle(in_write);
+			return nullptr;
+		}
+		SetHandleInformation(in_read, HANDLE_FLAG_INHERIT, HANDLE_FLAG_INHERIT);
+		SetHandleInformation(out_write, HANDLE_FLAG_INHERIT, HANDLE_FLAG_INHERIT);
+		DWORD nowait = PIPE_READMODE_BYTE | PIPE_NOWAIT; // a game not reading its stdin fails the write, as on POSIX
+		SetNamedPipeHandleState(in_write, &nowait, nullptr, nullptr);
+
+		HANDLE handles[] = { in_read, out_write };
+		SIZE_T size = 0;
+		InitializeProcThreadAttributeList(nullptr, 1, 0, &size);
+		std::vector<std::byte> attributes(size);
+		auto* list = (LPPROC_THREAD_ATTRIBUTE_LIST)attributes.data();
+		bool listed = InitializeProcThreadAttributeList(list, 1, 0, &size);
+		BOOL started = listed && UpdateProcThreadAttribute(list, 0, PROC_THREAD_ATTRIBUTE_HANDLE_LIST, handles, sizeof(handles), nullptr, nullptr);
+
+		STARTUPINFOEXW startup{};
+		startup.StartupInfo.cb = sizeof(startup);
+		startup.StartupInfo.dwFlags = STARTF_USESTDHANDLES;
+		startup.StartupInfo.hStdInput = in_read;
+		startup.StartupInfo.hStdOutput = out_write;
+		startup.StartupInfo.hStdError = out_write;
+		startup.lpAttributeList = list;
+
+		PROCESS_INFORMATION info{};
+		if (started) {
+			started = CreateProcessW(nullptr, wide.data(), nullptr, nullptr, TRUE, CREATE_NO_WINDOW | EXTENDED_STARTUPINFO_PRESENT,
+				nullptr, nullptr, &startup.StartupInfo, &info);
+		}
+		if (listed)
+			DeleteProcThreadAttributeList(list);
+		CloseHandle(in_read);
+		CloseHandle(out_write);
+		if (!started) {
+			CloseHandle(in_write);
+			CloseHandle(out_read);
+			return nullptr;
+		}
+
+		CloseHandle(info.hThread);
+		return std::make_unique<ProcessManager::Impl>(ProcessManager::Impl{ info.hProcess, in_write, out_read });
+	}
+
+	static bool WriteAll(ProcessManager::Impl& impl, const std::string& data)
+	{
+		for (size_t sent = 0; sent < data.size();) {
+			DWORD written = 0;
+			if (!WriteFile(impl.input, data.data() + sent, (DWORD)(data.size() - sent), &written, nullptr) || written == 0)
+				return false; // gone, or not reading its stdin and the buffer is full
+			sent += written;
+		}
+		return true;
+	}
+
+	static void ReadAvailable(ProcessManager::Impl& impl, std::string& out)
+	{
+		char buffer[4096];
+		DWORD available = 0, read = 0;
+		while (PeekNamedPipe(impl.output, nullptr, 0, nullptr, &available, nullptr) && available > 0
+			&& ReadFile(impl.output, buffer, std::min<DWORD>(available, sizeof(buffer)), &read, nullptr) && read > 0) {
+			out.append(buffer, read);
+		}
+	}
+
+	static bool Reap(ProcessManager::Impl& impl, int& exit_code, bool block = false)
+	{
+		if (WaitForSingleObject(impl.process, block ? INFINITE : 0) != WAIT_OBJECT_0)
+			return false;
+		DWORD code = 0;
+		GetExitCodeProcess(impl.process, &code);
+		exit_code = (int)code;
+		return true;
+	}
+
+	static void Kill(ProcessManager::Impl& impl)
+	{
+		TerminateProcess(impl.process, 1);
+	}
+
+	static void Close(ProcessManager::Impl& impl) // after it exited
+	{
+		CloseHandle(impl.input);
+		CloseHandle(impl.output);
+		CloseHandle(impl.process);
+	}
+#else
+	struct ProcessManager::Impl {
+		pid_t pid = -1;
+		int input = -1; // our end of its stdin
+		int output = -1; // our end of its stdout and stderr
+	};
+
+	static std::unique_ptr<ProcessManager::Impl> Spawn(const std::vector<std::string>& argv)
+	{
+		// socket pairs rather than pipes: a write to a game that already
+		// quit fails with EPIPE instead of raising SIGPIPE in the app
+		int in[2], out[2];
+		if (socketpair(AF_UNIX, SOCK_STREAM, 0, in) != 0)
+			return nullptr;
+		if (socketpair(AF_UNIX, SOCK_STREAM, 0, out) != 0) {
+			close(in[0]);
+			close(in[1]);
+			return nullptr;
+		}
+		for (int fd : { in[0], out[0] }) {
+			fcntl(fd, F_SETFD, FD_CLOEXEC); // our ends stay ours
+			fcntl(fd, F_SETFL, fcntl(fd, F_GETFL) | O_NONBLOCK);
+#ifdef SO_NOSIGPIPE
+			int on = 1;
+			setsockopt(fd, SOL_SOCKET, SO_NOSIGPIPE, &on, sizeof(on));
+#endif
+		}
+
+		posix_spawn_file_actions_t actions;
+		posix_spawn_file_actions_init(&actions);
+		posix_spawn_file_actions_adddup2(&actions, in[1], STDIN_FILENO);
+		posix_spawn_file_actions_adddup2(&actions, out[1], STDOUT_FILENO);
+		posix_spawn_file_actions_adddup2(&actions, out[1], STDERR_FILENO);
+		posix_spawn_file_actions_addclose(&actions, in[1]);
+		posix_spawn_file_actions_addclose(&actions, out[1]);
+
+		std::vector<char*> args;
+		for (const std::string& arg : argv)
+			args.push_back(const_cast<char*>(arg.c_str()));
+		args.push_back(nullptr);
+
+		pid_t pid = -1;
+		int failed = posix_spawnp(&pid, args[0], &actions, nullptr, args.data(), environ);
+		posix_spawn_file_actions_destroy(&actions);
+		close(in[1]);
+		close(out[1]);
+		if (failed) {
+			close(in[0]);
+			close(out[0]);
+			return nullptr;
+		}
+
+		return std::make_unique<ProcessManager::Impl>(ProcessManager::Impl{ pid, in[0], out[0] });
+	}
+
+	static bool WriteAll(ProcessManager::Impl& impl, const std::string& data)
+	{
+#ifdef MSG_NOSIGNAL
+		constexpr int flags = MSG_NOSIGNAL;
+#else
+		constexpr int flags = 0; // SO_NOSIGPIPE covers it
+#endif
+		for (size_t sent = 0; sent < data.size();) {
+			ssize_t n = send(impl.input, data.data() + sent, data.size() - sent, flags);
+			if (n < 0 && errno == EINTR)
+				continue;
+			if (n <= 0)
+				return false; // gone, or not reading its stdin and the buffer is full
+			sent += (size_t)n;
+		}
+		return true;
+	}
+
+	static void ReadAvailable(ProcessManager::Impl& impl, std::string& out)
+	{
+		char buffer[4096];
+		for (;;) {
+			ssize_t n = read(impl.output, buffer, sizeof(buffer));
+			if (n < 0 && errno == EINTR)
+				continue;
+			if (n <= 0)
+				return; // nothing more for now, or it closed its end
+			out.append(buffer, (size_t)n);
+		}
+	}
+
+	static bool Reap(ProcessManager::Impl& impl, int& exit_code, bool block = false)
+	{
+		int status = 0;
+		pid_t reaped = -1;
+		do
+			reaped = waitpid(impl.pid, &status, block ? 0 : WNOHANG);
+		while (reaped < 0 && errno == EINTR);
+		if (reaped != impl.pid)
+			return false;
+		exit_code = WIFEXITED(status) ? WEXITSTATUS(status) : WIFSIGNALED(status) ? 128 + WTERMSIG(status) : -1;
+		return true;
+	}
+
+	static void Kill(ProcessManager::Impl& impl)
+	{
+		kill(impl.pid, SIGKILL);
+	}
+
+	static void Close(ProcessManager::Impl& impl) // after it exited
+	{
+		close(impl.input);
+		close(impl.output);
+	}
+#endif
+
+	ProcessManager::ProcessManager() = default;
+
+	ProcessManager::~ProcessManager()
+	{
+		if (!_impl)
+			return;
+
+		Kill(*_impl);
+		int code = 0;
+		Reap(*_impl, code, true); // SIGKILL and TerminateProcess land promptly
+		Close(*_impl);
+	}
+
+	bool ProcessManager::LaunchGameServer(const std::vector<std::string>& argv)
+	{
+		if (argv.empty() || argv[0].empty() || _impl)
+			return false;
+
+		_impl = Spawn(argv);
+		_pending.clear();
+		return _impl != nullptr;
+	}
+
+	bool ProcessManager::SendConsoleCommand(const std::string& command)
+	{
+		if (!_impl || command.find_first_of("\r\n") != std::string::npos)
+			return false; // one command, one line
+		return WriteAll(*_impl, command + "\n");
+	}
+
+	void ProcessManager::StopGameServer()
+	{
+		if (_impl)
+			Kill(*_impl);
+	}
+
+	bool ProcessManager::Running() const
+	{
+		return _impl != nullptr;
+	}
+
+	void ProcessManager::Poll(std::queue<ProcessEvent>& out)
+	{
+		if (!_impl)
+			return;
+
+		int code = 0;
+		bool exited = Reap(*_impl, code); // first, so the read below drains everything it printed
+
+		std::string data;
+		ReadAvailable(*_impl, data);
+		std::vector<std::string> lines;
+		SplitConsoleLines(_pending, data, lines);
+		if (exited && !_pending.empty())
+			lines.push_back(std::move(_pending)); // its last words, unterminated
+		for (std::string& line : lines)
+			out.push({ ProcessEvent::Kind::Output, std::move(line) });
+
+		if (!exited)
+			return;
+
+		out.push({ ProcessEvent::Kind::Exited, {}, code });
+		Close(*_impl);
+		_impl.reset();
+		_pending.clear();
+	}
+}
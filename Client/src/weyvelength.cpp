@@ -1,6 +1,9 @@
 #include "weyvelength.h"
 
+#include <map>
 #include <string>
+#include <string_view>
+#include <vector>
 
 #include "client.h"
 #include "marshal.h"
@@ -11,8 +14,17 @@ using namespace Weyvelength;
 // back alive until the caller's next pull.
 struct WeyveClient {
 	Client client;
-	Proto::ServerMessage event; // backs the string pointers in the last WeyveEvent
+	Marshal::EventScratch pulled; // backs the string pointers in the last WeyveEvent
+	LinkPath link_path; // backs the strings from the last weyve_peer_link_path
+	std::vector<NetworkInterface> interfaces; // as of the last weyve_list_network_interfaces
+	std::vector<LogEntry> logs; // as of the last weyve_recent_logs
+	std::vector<Proto::MemberHealth> health; // as of the last weyve_get_session_health
 	Proto::P2PMessage p2p; // backs the buffer from the last weyve_next_p2p
+	std::vector<std::byte> session; // backs the bytes from the last weyve_save_session
+	std::map<std::string, std::string> error_texts; // by error key, from weyve_set_error_text
+	std::string error_line; // backs the text from the last weyve_format_error
+	std::string my_data; // backs the text from the last weyve_my_data
+	std::string receipt_text; // backs the text from the last weyve_join_receipt_text
 };
 
 // --- lifecycle ---
@@ -27,6 +39,31 @@ void weyve_client_destroy(WeyveClient* client)
 	delete client;
 }
 
+uint32_t weyve_api_version(void)
+{
+	return WEYVE_API_VERSION;
+}
+
+uint32_t weyve_protocol_version(void)
+{
+	return Proto::protocol_version;
+}
+
+uint32_t weyve_capabilities(void)
+{
+	uint32_t capabilities = WEYVE_CAPABILITY_BLOBS | WEYVE_CAPABILITY_P2P_FEC | WEYVE_CAPABILITY_LOG;
+#ifdef WEYVE_RECORDING
+	capabilities |= WEYVE_CAPABILITY_RECORDING;
+#endif
+#ifndef WEYVE_NO_PORT_MAPPING
+	capabilities |= WEYVE_CAPABILITY_PORT_MAPPING;
+#endif
+#ifndef WEYVE_NO_GAME_SERVER
+	capabilities |= WEYVE_CAPABILITY_GAME_SERVER;
+#endif
+	return capabilities;
+}
+
 bool weyve_connect(WeyveClient* client, const char* host, uint16_t port)
 {
 	ClientConfig config;
@@ -36,23 +73,264 @@ bool weyve_connect(WeyveClient* client, const char* host, uint16_t port)
 	return client->client.Connect(config);
 }
 
+void weyve_set_app_version(WeyveClient* client, const char* version)
+{
+	client->client.SetAppVersion(version ? version : "");
+}
+
+int32_t weyve_compare_versions(const char* a, const char* b)
+{
+	std::string_view left = a ? a : "", right = b ? b : "";
+	return Proto::OlderVersion(left, right) ? -1 : Proto::OlderVersion(right, left) ? 1 : 0;
+}
+
+const uint8_t* weyve_save_session(WeyveClient* client, uint32_t* len)
+{
+	client->session = client->client.SaveSession();
+	if (len)
+		*len = (uint32_t)client->session.size();
+	return client->session.empty() ? nullptr : (const uint8_t*)client->session.data();
+}
+
+bool weyve_resume_session(WeyveClient* client, const void* data, uint32_t len)
+{
+	auto* bytes = (const std::byte*)data;
+	return client->client.ResumeSession({ bytes, len });
+}
+
+bool weyve_network_changed(WeyveClient* client)
+{
+	return client->client.NetworkChanged();
+}
+
+void weyve_disconnect(WeyveClient* client)
+{
+	client->client.Disconnect();
+}
+
 bool weyve_poll(WeyveClient* client)
 {
 	return client->client.Poll();
 }
 
+void weyve_set_performance_profile(WeyveClient* client, WeyvePerformanceProfile profile)
+{
+	client->client.SetPerformanceProfile((PerformanceProfile)profile);
+}
+
+WeyvePerformanceProfile weyve_performance_profile(const WeyveClient* client)
+{
+	return (WeyvePerformanceProfile)client->client.Performance();
+}
+
+uint32_t weyve_poll_interval(const WeyveClient* client)
+{
+	return (uint32_t)PerformanceFor(client->client.Performance()).poll_interval.count();
+}
+
+void weyve_set_link_timeout(WeyveClient* client, uint32_t seconds)
+{
+	client->client.SetLinkTimeout(std::chrono::seconds{ seconds });
+}
+
+uint32_t weyve_link_timeout(const WeyveClient* client)
+{
+	return (uint32_t)client->client.LinkTimeout().count();
+}
+
+void weyve_set_port_mapping(WeyveClient* client, uint16_t port)
+{
+	client->client.SetPortMapping(port);
+}
+
+uint16_t weyve_port_mapping(const WeyveClient* client)
+{
+	return client->client.PortMappingPort();
+}
+
+bool weyve_set_network_interface(WeyveClient* client, const char* name_or_address)
+{
+	return client->client.SetNetworkInterface(Marshal::Str(name_or_address));
+}
+
+const char* weyve_network_interface(const WeyveClient* client, uint32_t* len)
+{
+	const std::string& name = client->client.BoundInterface().name;
+	if (len)
+		*len = (uint32_t)name.size();
+	return name.c_str();
+}
+
+uint32_t weyve_list_network_interfaces(WeyveClient* client)
+{
+	client->interfaces = ListNetworkInterfaces();
+	return (uint32_t)client->interfaces.size();
+}
+
+bool weyve_network_interface_at(const WeyveClient* client, uint32_t index, WeyveNetworkInterface* out)
+{
+	if (index >= client->interfaces.size())
+		return false;
+	Marshal::FillNetworkInterface(client->interfaces[index], out);
+	return true;
+}
+
+bool weyve_start_recording(WeyveClient* client, const char* path)
+{
+	return path && client->client.StartRecording(path);
+}
+
+void weyve_stop_recording(WeyveClient* client)
+{
+	client->client.StopRecording();
+}
+
+void weyve_set_log_level(WeyveLogLevel level)
+{
+	SetLogLevel((LogLevel)level);
+}
+
+WeyveLogLevel weyve_log_level(void)
+{
+	return (WeyveLogLevel)ClientLog().Level();
+}
+
+uint32_t weyve_recent_logs(WeyveClient* client, WeyveLogLevel least, uint32_t limit)
+{
+	client->logs = ClientLog().Recent((LogLevel)least, limit);
+	return (uint32_t)client->logs.size();
+}
+
+bool weyve_log_at(const WeyveClient* client, uint32_t index, WeyveLogEntry* out)
+{
+	if (index >= client->logs.size())
+		return false;
+	Marshal::FillLogEntry(client->logs[index], out);
+	return true;
+}
+
 bool weyve_next(WeyveClient* client, WeyveEvent* out)
 {
-	while (client->client.Next(client->event)) {
-		if (Marshal::FillEvent(client->event, out))
-			return true;
-	}
-	return false;
+	return Marshal::NextEvent(client->client, client->pulled, out);
+}
+
+WeyveScope weyve_event_scope(const WeyveEvent* event, uint32_t* id)
+{
+	uint32_t member = 0;
+	WeyveScope scope = Marshal::EventScope(*event, member);
+	if (id)
+		*id = member;
+	return scope;
+}
+
+const char* weyve_event_name(WeyveEventType type)
+{
+	return Marshal::EventName(type);
+}
+
+bool weyve_event_error(const WeyveEvent* event, WeyveError* out)
+{
+	return Marshal::FillError(*event, out);
+}
+
+void weyve_set_error_text(WeyveClient* client, const char* key, const char* text)
+{
+	if (text)
+		client->error_texts[Marshal::Str(key)] = text;
+	else
+		client->error_texts.erase(Marshal::Str(key));
+}
+
+const char* weyve_format_error(WeyveClient* client, const WeyveError* error, uint32_t* len)
+{
+	auto it = client->error_texts.find(Marshal::Str(error->key));
+	client->error_line = Marshal::FormatError(*error, it != client->error_texts.end() ? it->second.c_str() : nullptr);
+	if (len)
+		*len = (uint32_t)client->error_line.size();
+	return client->error_line.c_str();
 }
 
 bool weyve_send_heartbeat(WeyveClient* client, uint64_t timestamp)
 {
-	return client->client.SendServer(Proto::Heartbeat{ timestamp });
+	return client->client.SendHeartbeat(timestamp);
+}
+
+uint32_t weyve_round_trip_ms(const WeyveClient* client)
+{
+	return (uint32_t)client->client.RoundTrip().count();
+}
+
+bool weyve_report_activity(WeyveClient* client)
+{
+	return client->client.ReportActivity();
+}
+
+bool weyve_get_server_info(WeyveClient* client)
+{
+	return client->client.GetServerInfo();
+}
+
+bool weyve_set_background_mode(WeyveClient* client, bool background)
+{
+	return client->client.SetBackground(background);
+}
+
+bool weyve_in_background(const WeyveClient* client)
+{
+	return client->client.InBackground();
+}
+
+// --- accounts ---
+
+bool weyve_create_account(WeyveClient* client, const char* name, const char* password, const char* email)
+{
+	return client->client.CreateAccount(Marshal::Str(name), Marshal::Str(password), Marshal::Str(email));
+}
+
+bool weyve_login(WeyveClient* client, const char* name, const char* password)
+{
+	return client->client.Login(Marshal::Str(name), Marshal::Str(password));
+}
+
+bool weyve_guest_login(WeyveClient* client, const char* name)
+{
+	return client->client.GuestLogin(Marshal::Str(name));
+}
+
+bool weyve_search_users(WeyveClient* client, const char* query)
+{
+	return client->client.SearchUsers(Marshal::Str(query));
+}
+
+bool weyve_set_searchable(WeyveClient* client, bool searchable)
+{
+	return client->client.SetSearchable(searchable);
+}
+
+bool weyve_searchable(const WeyveClient* client)
+{
+	return client->client.Searchable();
+}
+
+bool weyve_export_my_data(WeyveClient* client)
+{
+	return client->client.ExportMyData();
+}
+
+const char* weyve_my_data(WeyveClient* client, uint32_t* len)
+{
+	const Proto::MyData& data = client->client.OwnData();
+	client->my_data = data.id == 0 ? "{}" : Marshal::MyDataJson(data);
+	if (len)
+		*len = (uint32_t)client->my_data.size();
+	return client->my_data.c_str();
+}
+
+// --- preferences ---
+
+bool weyve_set_preferences(WeyveClient* client, uint64_t dnd_until, WeyveWhisperPolicy whispers)
+{
+	return client->client.SetPreferences({ dnd_until, (Proto::WhisperPolicy)whispers });
 }
 
 // --- rooms ---
@@ -67,11 +345,51 @@ bool weyve_join_room(WeyveClient* client, const char* id, const char* password)
 	return client->client.JoinRoom(Marshal::Str(id), Marshal::Str(password));
 }
 
+bool weyve_spectate_room(WeyveClient* client, const char* id, const char* password)
+{
+	return client->client.SpectateRoom(Marshal::Str(id), Marshal::Str(password));
+}
+
 bool weyve_leave_room(WeyveClient* client)
 {
 	return client->client.LeaveRoom();
 }
 
+bool weyve_request_join(WeyveClient* client, const char* id, const char* message)
+{
+	return client->client.RequestJoin(Marshal::Str(id), Marshal::Str(message));
+}
+
+bool weyve_respond_join_request(WeyveClient* client, uint32_t id, bool approve)
+{
+	return client->client.RespondJoinRequest(id, approve);
+}
+
+bool weyve_queue_for_room(WeyveClient* client, const char* id, const char* password)
+{
+	return client->client.QueueForRoom(Marshal::Str(id), Marshal::Str(password));
+}
+
+bool weyve_leave_room_queue(WeyveClient* client)
+{
+	return client->client.LeaveRoomQueue();
+}
+
+bool weyve_get_room_preview(WeyveClient* client, const char* id, bool watch)
+{
+	return client->client.GetRoomPreview(Marshal::Str(id), watch);
+}
+
+bool weyve_stop_room_preview(WeyveClient* client)
+{
+	return client->client.StopRoomPreview();
+}
+
+bool weyve_preflight_join(WeyveClient* client, const char* id, bool spectating)
+{
+	return client->client.PreflightJoin(Marshal::Str(id), spectating);
+}
+
 bool weyve_kick_member(WeyveClient* client, uint32_t id)
 {
 	return client->client.KickMember(id);
@@ -87,6 +405,82 @@ bool weyve_transfer_host(WeyveClient* client, uint32_t id)
 	return client->client.TransferHost(id);
 }
 
+bool weyve_split_room(WeyveClient* client, const uint32_t* members, uint32_t count)
+{
+	std::vector<uint32_t> list;
+	if (members)
+		list.assign(members, members + count);
+	return client->client.SplitRoom(list);
+}
+
+bool weyve_merge_room(WeyveClient* client, const char* id)
+{
+	return client->client.MergeRoom(Marshal::Str(id));
+}
+
+bool weyve_answer_merge(WeyveClient* client, const char* into, bool accept)
+{
+	return client->client.AnswerMerge(Marshal::Str(into), accept);
+}
+
+bool weyve_set_member_role(WeyveClient* client, uint32_t id, WeyveMemberRole role)
+{
+	return client->client.SetMemberRole(id, (Proto::MemberRole)role);
+}
+
+bool weyve_start_countdown(WeyveClient* client, uint32_t seconds)
+{
+	return client->client.StartCountdown(seconds);
+}
+
+bool weyve_pause_match(WeyveClient* client)
+{
+	return client->client.PauseMatch();
+}
+
+bool weyve_resume_match(WeyveClient* client)
+{
+	return client->client.ResumeMatch();
+}
+
+bool weyve_match_paused(const WeyveClient* client)
+{
+	return client->client.MatchPaused();
+}
+
+bool weyve_launch_game_server(WeyveClient* client, const char* const* argv, uint32_t argc)
+{
+	std::vector<std::string> args;
+	for (uint32_t i = 0; argv && i < argc; i++)
+		args.push_back(Marshal::Str(argv[i]));
+	return client->client.LaunchGameServer(args);
+}
+
+bool weyve_send_console_command(WeyveClient* client, const char* command)
+{
+	return client->client.SendConsoleCommand(Marshal::Str(command));
+}
+
+void weyve_stop_game_server(WeyveClient* client)
+{
+	client->client.StopGameServer();
+}
+
+bool weyve_game_server_running(const WeyveClient* client)
+{
+	return client->client.GameServerRunning();
+}
+
+bool weyve_countdown_remaining_ms(const WeyveClient* client, uint32_t* ms)
+{
+	auto left = client->client.CountdownRemaining();
+	if (!left)
+		return false;
+	if (ms)
+		*ms = (uint32_t)left->count();
+	return true;
+}
+
 bool weyve_set_room_joinable(WeyveClient* client, bool open)
 {
 	return client->client.SetRoomJoinable(open);
@@ -97,11 +491,54 @@ bool weyve_set_room_password(WeyveClient* client, const char* password)
 	return client->client.SetRoomPassword(Marshal::Str(password));
 }
 
+bool weyve_set_room_capacity(WeyveClient* client, uint32_t max_players, uint32_t max_spectators)
+{
+	return client->client.SetRoomCapacity(max_players, max_spectators);
+}
+
+bool weyve_set_idle_policy(WeyveClient* client, uint32_t afk_after, bool spectate)
+{
+	return client->client.SetIdlePolicy(afk_after, spectate);
+}
+
+bool weyve_set_room_banner(WeyveClient* client, const char* id)
+{
+	return client->client.SetRoomBanner(Marshal::Str(id));
+}
+
+bool weyve_set_room_rules(WeyveClient* client, const char* rules)
+{
+	return client->client.SetRoomRules(Marshal::Str(rules));
+}
+
+bool weyve_set_host_state(WeyveClient* client, const void* data, uint32_t len)
+{
+	return client->client.SetHostState(data ? std::string((const char*)data, len) : std::string{});
+}
+
+bool weyve_report_host_state(WeyveClient* client, const void* data, uint32_t len)
+{
+	return client->client.ReportHostState(data ? std::string((const char*)data, len) : std::string{});
+}
+
+bool weyve_set_succession(WeyveClient* client, WeyveSuccessionPolicy policy, const uint32_t* successors, uint32_t count)
+{
+	std::vector<uint32_t> list;
+	if (successors)
+		list.assign(successors, successors + count);
+	return client->client.SetSuccession((Proto::SuccessionPolicy)policy, list);
+}
+
 bool weyve_send_chat(WeyveClient* client, const char* text)
 {
 	return client->client.SendChat(Marshal::Str(text));
 }
 
+bool weyve_send_whisper(WeyveClient* client, uint32_t id, const char* text)
+{
+	return client->client.SendWhisper(id, Marshal::Str(text));
+}
+
 bool weyve_set_room_data(WeyveClient* client, const char* key, const char* value)
 {
 	return client->client.SetRoomData(Marshal::Str(key), Marshal::Str(value));
@@ -122,6 +559,166 @@ bool weyve_delete_member_data(WeyveClient* client, const char* key)
 	return client->client.DeleteMemberData(Marshal::Str(key));
 }
 
+bool weyve_set_member_data_for(WeyveClient* client, uint32_t id, const char* key, const char* value)
+{
+	return client->client.SetMemberDataFor(id, Marshal::Str(key), Marshal::Str(value));
+}
+
+bool weyve_create_team(WeyveClient* client, const char* name, uint32_t max_size)
+{
+	return client->client.CreateTeam(Marshal::Str(name), max_size);
+}
+
+bool weyve_remove_team(WeyveClient* client, uint32_t team)
+{
+	return client->client.RemoveTeam(team);
+}
+
+bool weyve_assign_team(WeyveClient* client, uint32_t id, uint32_t team)
+{
+	return client->client.AssignTeam(id, team);
+}
+
+bool weyve_balance_teams(WeyveClient* client)
+{
+	return client->client.BalanceTeams();
+}
+
+bool weyve_create_channel(WeyveClient* client, const char* name)
+{
+	return client->client.CreateChannel(Marshal::Str(name));
+}
+
+bool weyve_remove_channel(WeyveClient* client, uint32_t channel)
+{
+	return client->client.RemoveChannel(channel);
+}
+
+bool weyve_place_in_channel(WeyveClient* client, uint32_t id, uint32_t channel)
+{
+	return client->client.PlaceInChannel(id, channel);
+}
+
+bool weyve_send_channel_chat(WeyveClient* client, const char* text)
+{
+	return client->client.SendChannelChat(Marshal::Str(text));
+}
+
+// --- composing ---
+
+bool weyve_retry_message(WeyveClient* client, uint32_t id)
+{
+	return client->client.RetryMessage(id);
+}
+
+bool weyve_discard_message(WeyveClient* client, uint32_t id)
+{
+	return client->client.DiscardMessage(id);
+}
+
+void weyve_set_draft(WeyveClient* client, const char* scope, const char* text)
+{
+	client->client.SetDraft(Marshal::Str(scope), Marshal::Str(text));
+}
+
+const char* weyve_draft(const WeyveClient* client, const char* scope, uint32_t* len)
+{
+	return Marshal::Bytes(client->client.Draft(Marshal::Str(scope)), len);
+}
+
+uint32_t weyve_draft_count(const WeyveClient* client)
+{
+	return (uint32_t)client->client.Drafts().size();
+}
+
+const char* weyve_draft_scope_at(const WeyveClient* client, uint32_t index, uint32_t* len)
+{
+	return Marshal::KeyAt(client->client.Drafts(), index, len);
+}
+
+bool weyve_export_chat(const WeyveClient* client, const char* room, WeyveChatFormat format, const char* path)
+{
+	return client->client.ExportChat(Marshal::Str(room), (ChatExportFormat)format, Marshal::Str(path));
+}
+
+uint32_t weyve_chat_history_count(const WeyveClient* client)
+{
+	return (uint32_t)client->client.ChatHistory().size();
+}
+
+bool weyve_chat_line_at(const WeyveClient* client, uint32_t index, WeyveChatLine* out)
+{
+	const auto& history = client->client.ChatHistory();
+	if (index >= history.size())
+		return false;
+	Marshal::FillChatLine(history[index], out);
+	return true;
+}
+
+uint32_t weyve_search_messages(const WeyveClient* client, const char* query, const char* room, uint32_t* lines, uint32_t max)
+{
+	std::vector<size_t> found = client->client.SearchMessages(Marshal::Str(query), Marshal::Str(room), lines ? max : 0);
+	for (size_t i = 0; i < found.size(); i++)
+		lines[i] = (uint32_t)found[i];
+	return (uint32_t)found.size();
+}
+
+// --- blobs ---
+
+uint32_t weyve_upload_blob(WeyveClient* client, const void* data, uint32_t len)
+{
+	auto* bytes = (const std::byte*)data;
+	return client->client.UploadBlob({ bytes, len });
+}
+
+uint32_t weyve_download_blob(WeyveClient* client, const char* id)
+{
+	return client->client.DownloadBlob(Marshal::Str(id));
+}
+
+const uint8_t* weyve_blob(const WeyveClient* client, const char* id, uint32_t* len)
+{
+	const std::vector<std::byte>* blob = client->client.Blob(Marshal::Str(id));
+	if (len)
+		*len = blob ? (uint32_t)blob->size() : 0;
+	return blob ? (const uint8_t*)blob->data() : nullptr;
+}
+
+const uint8_t* weyve_get_blob(WeyveClient* client, const char* id, uint32_t* len)
+{
+	const std::vector<std::byte>* blob = client->client.GetBlob(Marshal::Str(id));
+	if (len)
+		*len = blob ? (uint32_t)blob->size() : 0;
+	return blob ? (const uint8_t*)blob->data() : nullptr;
+}
+
+// --- scheduled rooms ---
+
+bool weyve_schedule_room(WeyveClient* client, uint64_t start, const char* title, uint32_t max_players, uint32_t max_spectators, const char* password)
+{
+	return client->client.ScheduleRoom(start, Marshal::Str(title), max_players, max_spectators, Marshal::Str(password));
+}
+
+bool weyve_list_scheduled_rooms(WeyveClient* client)
+{
+	return client->client.ListScheduledRooms();
+}
+
+bool weyve_rsvp_scheduled_room(WeyveClient* client, uint32_t id, WeyveRsvpStatus status)
+{
+	return client->client.RsvpScheduledRoom(id, (Proto::RsvpStatus)status);
+}
+
+bool weyve_list_attendees(WeyveClient* client, uint32_t id)
+{
+	return client->client.ListAttendees(id);
+}
+
+bool weyve_cancel_scheduled_room(WeyveClient* client, uint32_t id)
+{
+	return client->client.CancelScheduledRoom(id);
+}
+
 // --- peer to peer ---
 
 bool weyve_send_p2p(WeyveClient* client, uint32_t id, const void* data, uint32_t len)
@@ -130,6 +727,12 @@ bool weyve_send_p2p(WeyveClient* client, uint32_t id, const void* data, uint32_t
 	return client->client.SendP2P(id, { bytes, bytes + len });
 }
 
+bool weyve_send_p2p_channel(WeyveClient* client, const void* data, uint32_t len)
+{
+	auto* bytes = (const std::byte*)data;
+	return client->client.SendChannelP2P({ bytes, bytes + len });
+}
+
 const uint8_t* weyve_next_p2p(WeyveClient* client, uint32_t* from, uint32_t* len)
 {
 	uint32_t sender = 0;
@@ -147,6 +750,143 @@ bool weyve_peer_connected(WeyveClient* client, uint32_t id)
 	return client->client.PeerConnectedP2P(id);
 }
 
+void weyve_set_p2p_ordering(WeyveClient* client, bool ordered, uint32_t jitter_ms)
+{
+	client->client.SetP2POrdering(ordered, std::chrono::milliseconds{ jitter_ms });
+}
+
+bool weyve_p2p_ordered(const WeyveClient* client)
+{
+	return client->client.P2POrdered();
+}
+
+uint32_t weyve_jitter_delay_ms(const WeyveClient* client)
+{
+	return (uint32_t)client->client.JitterDelay().count();
+}
+
+void weyve_set_p2p_fec(WeyveClient* client, bool allowed)
+{
+	client->client.SetP2PFec(allowed);
+}
+
+bool weyve_p2p_fec(const WeyveClient* client)
+{
+	return client->client.P2PFec();
+}
+
+void weyve_simulate_network(WeyveClient* client, uint32_t loss_permille, uint32_t latency_ms)
+{
+	client->client.SimulateNetwork(loss_permille, std::chrono::milliseconds(latency_ms));
+}
+
+uint32_t weyve_peer_fec_group(const WeyveClient* client, uint32_t id)
+{
+	return client->client.PeerFecGroup(id);
+}
+
+uint32_t weyve_peer_loss_permille(const WeyveClient* client, uint32_t id)
+{
+	return client->client.PeerLossPermille(id);
+}
+
+bool weyve_peer_link_path(WeyveClient* client, uint32_t id, WeyveLinkPath* out)
+{
+	if (!client->client.PeerLinkPath(id, client->link_path))
+		return false;
+	Marshal::FillLinkPath(client->link_path, out);
+	return true;
+}
+
+bool weyve_retry_peer_connection(WeyveClient* client, uint32_t id)
+{
+	return client->client.RetryPeerConnection(id);
+}
+
+void weyve_set_health_reporting(WeyveClient* client, bool report, bool share)
+{
+	client->client.SetHealthReporting(report, share);
+}
+
+uint32_t weyve_get_session_health(WeyveClient* client)
+{
+	client->health.clear();
+	for (const auto& [id, row] : client->client.SessionHealth())
+		client->health.push_back(row);
+	return (uint32_t)client->health.size();
+}
+
+bool weyve_session_health_at(const WeyveClient* client, uint32_t index, WeyveMemberHealth* out)
+{
+	if (index >= client->health.size())
+		return false;
+	Marshal::FillMemberHealth(client->health[index], out);
+	return true;
+}
+
+bool weyve_session_health_link_at(const WeyveClient* client, uint32_t row, uint32_t index, WeyveLinkHealth* out)
+{
+	if (row >= client->health.size() || index >= client->health[row].links.size())
+		return false;
+	Marshal::FillLinkHealth(client->health[row].links[index], out);
+	return true;
+}
+
+bool weyve_request_session_health(WeyveClient* client)
+{
+	return client->client.GetSessionHealth();
+}
+
+bool weyve_reported_health_at(const WeyveClient* client, uint32_t index, WeyveMemberHealth* out, uint32_t* age)
+{
+	const auto& reports = client->client.ServerHealth().reports;
+	if (index >= reports.size())
+		return false;
+	Marshal::FillMemberHealth(reports[index].member, out);
+	if (age)
+		*age = reports[index].age;
+	return true;
+}
+
+bool weyve_reported_health_link_at(const WeyveClient* client, uint32_t row, uint32_t index, WeyveLinkHealth* out)
+{
+	const auto& reports = client->client.ServerHealth().reports;
+	if (row >= reports.size() || index >= reports[row].member.links.size())
+		return false;
+	Marshal::FillLinkHealth(reports[row].member.links[index], out);
+	return true;
+}
+
+bool weyve_get_join_receipts(WeyveClient* client)
+{
+	return client->client.GetJoinReceipts();
+}
+
+bool weyve_join_receipt_at(const WeyveClient* client, uint32_t index, WeyveJoinReceipt* out)
+{
+	const auto& receipts = client->client.Receipts().receipts;
+	if (index >= receipts.size())
+		return false;
+	Marshal::FillJoinReceipt(receipts[index], out);
+	return true;
+}
+
+const char* weyve_join_receipt_text(WeyveClient* client, uint32_t index, uint32_t* len)
+{
+	const auto& receipts = client->client.Receipts().receipts;
+	if (index >= receipts.size())
+		return nullptr;
+	client->receipt_text = Proto::ReceiptText(receipts[index]);
+	if (len)
+		*len = (uint32_t)client->receipt_text.size();
+	return client->receipt_text.c_str();
+}
+
+bool weyve_close_peer_connection(WeyveClient* client, uint32_t id, bool notify)
+{
+	return client->client.ClosePeerConnection(id, notify);
+}
+
 // --- cached room state ---
 
 uint32_t weyve_id(const WeyveClient* client)
@@ -154,6 +894,17 @@ uint32_t weyve_id(const WeyveClient* client)
 	return client->client.Id();
 }
 
+const char* weyve_name(const WeyveClient* client, uint32_t* len)
+{
+	const std::string& name = client->client.Name();
+	return Marshal::Bytes(name.empty() ? nullptr : &name, len);
+}
+
+const char* weyve_member_name(const WeyveClient* client, uint32_t id, uint32_t* len)
+{
+	return Marshal::Bytes(client->client.MemberName(id), len);
+}
+
 uint32_t weyve_host_id(const WeyveClient* client)
 {
 	return client->client.HostId();
@@ -164,6 +915,21 @@ bool weyve_is_host(const WeyveClient* client)
 	return client->client.IsHost();
 }
 
+WeyveMemberRole weyve_member_role(const WeyveClient* client, uint32_t id)
+{
+	return (WeyveMemberRole)client->client.Role(id);
+}
+
+uint64_t weyve_dnd_until(const WeyveClient* client)
+{
+	return client->client.Preferences().dnd_until;
+}
+
+WeyveWhisperPolicy weyve_whisper_policy(const WeyveClient* client)
+{
+	return (WeyveWhisperPolicy)client->client.Preferences().whispers;
+}
+
 bool weyve_room_joinable(const WeyveClient* client)
 {
 	return client->client.RoomJoinable();
@@ -174,6 +940,71 @@ bool weyve_room_passworded(const WeyveClient* client)
 	return client->client.RoomPassworded();
 }
 
+uint32_t weyve_max_players(const WeyveClient* client)
+{
+	return client->client.MaxPlayers();
+}
+
+uint32_t weyve_max_spectators(const WeyveClient* client)
+{
+	return client->client.MaxSpectators();
+}
+
+uint32_t weyve_player_count(const WeyveClient* client)
+{
+	return client->client.PlayerCount();
+}
+
+uint32_t weyve_spectator_count(const WeyveClient* client)
+{
+	return client->client.SpectatorCount();
+}
+
+bool weyve_is_spectator(const WeyveClient* client, uint32_t id)
+{
+	return client->client.IsSpectator(id);
+}
+
+bool weyve_is_afk(const WeyveClient* client, uint32_t id)
+{
+	return client->client.IsAfk(id);
+}
+
+uint32_t weyve_afk_after(const WeyveClient* client)
+{
+	return client->client.AfkAfter();
+}
+
+bool weyve_afk_spectates(const WeyveClient* client)
+{
+	return client->client.AfkSpectates();
+}
+
+WeyveSuccessionPolicy weyve_succession_policy(const WeyveClient* client)
+{
+	return (WeyveSuccessionPolicy)client->client.Succession();
+}
+
+const uint32_t* weyve_successors(const WeyveClient* client, uint32_t* count)
+{
+	const std::vector<uint32_t>& successors = client->client.Successors();
+	if (count)
+		*count = (uint32_t)successors.size();
+	return successors.data();
+}
+
+const char* weyve_room_banner(const WeyveClient* client, uint32_t* len)
+{
+	return Marshal::Bytes(&client->client.Banner(), len);
+}
+
+const char* weyve_room_rules(const WeyveClient* client, uint32_t* len, uint32_t* version)
+{
+	if (version)
+		*version = client->client.RulesVersion();
+	return Marshal::Bytes(&client->client.Rules(), len);
+}
+
 const char* weyve_room_id(const WeyveClient* client, uint32_t* len)
 {
 	const std::string& room = client->client.RoomId();
@@ -190,6 +1021,63 @@ const uint32_t* weyve_members(const WeyveClient* client, uint32_t* count)
 	return members.data();
 }
 
+uint32_t weyve_timeline_count(const WeyveClient* client)
+{
+	return (uint32_t)client->client.RoomTimeline().size();
+}
+
+bool weyve_timeline_at(const WeyveClient* client, uint32_t index, WeyveTimelineEntry* out)
+{
+	const auto& timeline = client->client.RoomTimeline();
+	if (index >= timeline.size())
+		return false;
+	Marshal::FillTimelineEntry(timeline[index], out);
+	return true;
+}
+
+bool weyve_get_timeline(WeyveClient* client, uint32_t after)
+{
+	return client->client.GetTimeline(after);
+}
+
+uint32_t weyve_team_count(const WeyveClient* client)
+{
+	return (uint32_t)client->client.Teams().size();
+}
+
+bool weyve_team_at(const WeyveClient* client, uint32_t index, WeyveTeam* out)
+{
+	const auto& teams = client->client.Teams();
+	if (index >= teams.size())
+		return false;
+	Marshal::FillTeam(teams[index], out);
+	return true;
+}
+
+uint32_t weyve_team_of(const WeyveClient* client, uint32_t id)
+{
+	return client->client.TeamOf(id);
+}
+
+uint32_t weyve_channel_count(const WeyveClient* client)
+{
+	return (uint32_t)client->client.Channels().size();
+}
+
+bool weyve_channel_at(const WeyveClient* client, uint32_t index, WeyveChannel* out)
+{
+	const auto& channels = client->client.Channels();
+	if (index >= channels.size())
+		return false;
+	Marshal::FillChannel(channels[index], out);
+	return true;
+}
+
+uint32_t weyve_channel_of(const WeyveClient* client, uint32_t id)
+{
+	return client->client.ChannelOf(id);
+}
+
 const char* weyve_room_data(const WeyveClient* client, const char* key, uint32_t* value_len)
 {
 	return Marshal::Bytes(client->client.RoomData(Marshal::Str(key)), value_len);
@@ -226,3 +1114,91 @@ const char* weyve_member_data_key_at(const WeyveClient* client, uint32_t id, uin
 	}
 	return Marshal::KeyAt(*data, index, key_len);
 }
+
+uint32_t weyve_scheduled_room_count(const WeyveClient* client)
+{
+	return (uint32_t)client->client.ScheduledRooms().size();
+}
+
+bool weyve_scheduled_room_at(const WeyveClient* client, uint32_t index, WeyveScheduledRoom* out)
+{
+	const std::vector<Proto::ScheduledRoom>& rooms = client->client.ScheduledRooms();
+	if (index >= rooms.size())
+		return false;
+	Marshal::FillScheduledRoom(rooms[index], out);
+	return true;
+}
+
+uint32_t weyve_attendee_count(const WeyveClient* client, uint32_t booking)
+{
+	const auto* attendees = client->client.Attendees(booking);
+	return attendees ? (uint32_t)attendees->size() : 0;
+}
+
+bool weyve_attendee_at(const WeyveClient* client, uint32_t booking, uint32_t index, uint32_t* id, WeyveRsvpStatus* status)
+{
+	const auto* attendees = client->client.Attendees(booking);
+	if (!attendees || index >= attendees->size())
+		return false;
+	if (id)
+		*id = (*attendees)[index].id;
+	if (status)
+		*status = (WeyveRsvpStatus)(*attendees)[index].status;
+	return true;
+}
+
+bool weyve_preview_member_at(const WeyveClient* client, uint32_t index, uint32_t* id, const char** name, uint32_t* name_len, bool* spectating)
+{
+	const auto* preview = client->client.Preview();
+	if (!preview || index >= preview->members.size())
+		return false;
+
+	const Proto::PreviewMember& member = preview->members[index];
+	if (id)
+		*id = member.id;
+	if (name)
+		*name = member.name.empty() ? nullptr : member.name.data();
+	if (name_len)
+		*name_len = (uint32_t)member.name.size();
+	if (spectating)
+		*spectating = member.spectating;
+	return true;
+}
+
+bool weyve_preview_data_at(const WeyveClient* client, uint32_t index, const char** key, uint32_t* key_len, const char** value, uint32_t* value_len)
+{
+	const auto* preview = client->client.Preview();
+	if (!preview || index >= preview->data.size())
+		return false;
+
+	const Proto::RoomDataEntry& entry = preview->data[index];
+	if (key)
+		*key = entry.key.data();
+	if (key_len)
+		*key_len = (uint32_t)entry.key.size();
+	if (value)
+		*value = entry.value.data();
+	if (value_len)
+		*value_len = (uint32_t)entry.value.size();
+	return true;
+}
+
+bool weyve_user_result_at(const WeyveClient* client, uint32_t index, const char** name, uint32_t* name_len, uint32_t* id, const char** room, uint32_t* room_len)
+{
+	const auto& users = client->client.UserResults().users;
+	if (index >= users.size())
+		return false;
+
+	const Proto::UserMatch& user = users[index];
+	if (name)
+		*name = user.name.data();
+	if (name_len)
+		*name_len = (uint32_t)user.name.size();
+	if (id)
+		*id = user.id;
+	if (room)
+		*room = user.room.data();
+	if (room_len)
+		*room_len = (uint32_t)user.room.size();
+	return true;
+}
@@ -1,13 +1,33 @@
+#include <algorithm>
+#include <atomic>
 #include <chrono>
+#include <csignal>
+#include <cstdio>
 #include <cstdlib>
+#include <fstream>
 #include <iostream>
+#include <iterator>
 #include <mutex>
 #include <queue>
+#include <sstream>
 #include <string>
 #include <thread>
+#include <vector>
 
 #include "weyvelength.h" // the C API only; this example never touches the C++ client
 
+// Set by Ctrl+C or /quit; the loops stop and main's weyve_client_destroy says goodbye.
+static std::atomic<bool> quitting = false;
+static const char* const example_version = "1.0"; // what the server's client breakdown lists us as
+
+// The room asked for on the command line; a full one is waited for in line.
+static std::string wanted_room, wanted_password;
+
+static void OnInterrupt(int)
+{
+	quitting = true;
+}
+
 // Typed lines cross from the blocking console reader to the poll loop here.
 static std::mutex input_mutex;
 static std::queue<std::string> input_lines;
@@ -45,11 +65,18 @@ static void PrintPong(WeyveClient* client, uint64_t timestamp)
 	std::cout << "Pong: " << rtt_ms << " ms (i am client " << weyve_id(client) << ")\n";
 }
 
-static void PrintRoomError(const WeyveEvent& event)
+// Room, account and blob failures all print through the one error view.
+static void PrintError(WeyveClient* client, const WeyveEvent& event)
 {
-	std::cout << "Room error " << (int)event.data.room_error.code;
-	if (event.data.room_error.context_len)
-		std::cout << " (" << std::string(event.data.room_error.context, event.data.room_error.context_len) << ")";
+	WeyveError error{};
+	if (!weyve_event_error(&event, &error))
+		return;
+
+	uint32_t len = 0;
+	const char* line = weyve_format_error(client, &error, &len);
+	std::cout << std::string(line, len);
+	if (error.retryable)
+		std::cout << " Try again later.";
 	std::cout << "\n";
 }
 
@@ -59,6 +86,31 @@ static uint32_t ParseId(const std::string& arg)
 	return (uint32_t)std::strtoul(arg.c_str(), nullptr, 10);
 }
 
+// "/split 3 4 7" style list of ids; garbage ones are skipped.
+static std::vector<uint32_t> ParseIds(const std::string& args)
+{
+	std::vector<uint32_t> ids;
+	std::istringstream in(args);
+	for (std::string arg; in >> arg;) {
+		if (uint32_t id = ParseId(arg); id != 0)
+			ids.push_back(id);
+	}
+	return ids;
+}
+
+// "Alice" once the member logged in, else "client 3".
+static std::string MemberLabel(WeyveClient* client, uint32_t id)
+{
+	uint32_t len = 0;
+	const char* name = weyve_member_name(client, id, &len);
+	return name ? std::string(name, len) : "client " + std::to_string(id);
+}
+
+static uint64_t UnixNow()
+{
+	return (uint64_t)std::chrono::duration_cast<std::chrono::seconds>(std::chrono::system_clock::now().time_since_epoch()).count();
+}
+
 // Borrowed room id as an owned string; empty means we are not in a room.
 static std::string RoomId(WeyveClient* client)
 {
@@ -67,10 +119,308 @@ static std::string RoomId(WeyveClient* client)
 	return std::string(id, len);
 }
 
+static const char* RoleName(WeyveMemberRole role)
+{
+	switch (role) {
+	case WEYVE_ROLE_MODERATOR: return "moderator";
+	case WEYVE_ROLE_CO_HOST: return "co-host";
+	default: return "member";
+	}
+}
+
+// "/role 3 cohost"; mod, cohost or member (= demote).
+static void SendRoleCommand(WeyveClient* client, const std::string& args)
+{
+	size_t space = args.find(' ');
+	uint32_t id = ParseId(args.substr(0, space));
+	std::string role = space == std::string::npos ? "" : args.substr(space + 1);
+	if (id == 0 || (role != "mod" && role != "cohost" && role != "member")) {
+		std::cout << "usage: /role ID mod|cohost|member\n";
+		return;
+	}
+
+	weyve_set_member_role(client, id, role == "mod" ? WEYVE_ROLE_MODERATOR : role == "cohost" ? WEYVE_ROLE_CO_HOST : WEYVE_ROLE_MEMBER);
+}
+
+// "/server ./gameserver -port 7777"; split on spaces, no quoting.
+static void LaunchServerCommand(WeyveClient* client, const std::string& args)
+{
+	std::vector<std::string> words;
+	for (size_t start = 0; start < args.size();) {
+		size_t space = args.find(' ', start);
+		if (space == std::string::npos)
+			space = args.size();
+		if (space > start)
+			words.push_back(args.substr(start, space - start));
+		start = space + 1;
+	}
+	std::vector<const char*> argv;
+	for (const std::string& word : words)
+		argv.push_back(word.c_str());
+
+	if (argv.empty() || !weyve_launch_game_server(client, argv.data(), (uint32_t)argv.size()))
+		std::cout << "* could not start it (host only, one at a time)\n";
+}
+
+static const char* CandidateLabel(WeyveCandidateType type)
+{
+	switch (type) {
+	case WEYVE_CANDIDATE_HOST: return "host";
+	case WEYVE_CANDIDATE_SERVER_REFLEXIVE: return "srflx";
+	case WEYVE_CANDIDATE_PEER_REFLEXIVE: return "prflx";
+	default: return "relay";
+	}
+}
+
+// "/iface" lists them, "/iface tun0" or "/iface 10.8.0.2" picks one, "/iface -" any.
+static void InterfaceCommand(WeyveClient* client, const std::string& args)
+{
+	if (args.empty()) {
+		uint32_t count = weyve_list_network_interfaces(client);
+		for (uint32_t i = 0; i < count; i++) {
+			WeyveNetworkInterface iface{};
+			weyve_network_interface_at(client, i, &iface);
+			std::cout << "  " << std::string(iface.name, iface.name_len) << " " << std::string(iface.address, iface.address_len) << "\n";
+		}
+		uint32_t len = 0;
+		const char* chosen = weyve_network_interface(client, &len);
+		std::cout << "* links use " << (len ? std::string(chosen, len) : "every interface") << "\n";
+		return;
+	}
+	if (!weyve_set_network_interface(client, args == "-" ? "" : args.c_str()))
+		std::cout << "* no interface " << args << "\n";
+}
+
+// "/path 3": the addresses a live link to member 3 runs over.
+static void PrintLinkPath(WeyveClient* client, uint32_t id)
+{
+	WeyveLinkPath path{};
+	if (!weyve_peer_link_path(client, id, &path)) {
+		std::cout << "* no direct link to " << MemberLabel(client, id) << "\n";
+		return;
+	}
+	std::cout << "* " << std::string(path.local_address, path.local_address_len) << ":" << path.local_port << " (" << CandidateLabel(path.local_type) << ") -> "
+		<< std::string(path.remote_address, path.remote_address_len) << ":" << path.remote_port << " (" << CandidateLabel(path.remote_type) << ")";
+	if (path.interface_name_len)
+		std::cout << " on " << std::string(path.interface_name, path.interface_name_len);
+	std::cout << "\n";
+}
+
+static void PrintLinkHealth(WeyveClient* client, const WeyveLinkHealth& link)
+{
+	std::cout << "    -> " << MemberLabel(client, link.peer) << ": " << (!link.direct ? "down" : link.relayed ? "relayed" : "direct")
+		<< ", " << link.loss_permille / 10.0 << "% lost, " << link.send_rate << " B/s out, " << link.receive_rate << " B/s in\n";
+}
+
+// The host's matrix: one line per link, from the end that reported it.
+static void PrintSessionHealth(WeyveClient* client)
+{
+	uint32_t rows = weyve_get_session_health(client);
+	if (rows == 0) {
+		std::cout << "* no health reports (host-only; every 10 seconds)\n";
+		return;
+	}
+	for (uint32_t row = 0; row < rows; row++) {
+		WeyveMemberHealth member{};
+		weyve_session_health_at(client, row, &member);
+		std::cout << "* " << MemberLabel(client, member.from) << ", " << member.server_rtt << " ms to the server\n";
+		for (uint32_t i = 0; i < member.link_count; i++) {
+			WeyveLinkHealth link{};
+			weyve_session_health_link_at(client, row, i, &link);
+			PrintLinkHealth(client, link);
+		}
+	}
+}
+
+// The same, as the server holds it, from a /health server.
+static void PrintReportedHealth(WeyveClient* client, uint32_t count)
+{
+	for (uint32_t row = 0; row < count; row++) {
+		WeyveMemberHealth member{};
+		uint32_t age = 0;
+		weyve_reported_health_at(client, row, &member, &age);
+		std::cout << "* " << MemberLabel(client, member.from) << ", " << member.server_rtt << " ms to the server, " << age << "s ago\n";
+		for (uint32_t i = 0; i < member.link_count; i++) {
+			WeyveLinkHealth link{};
+			weyve_reported_health_link_at(client, row, i, &link);
+			PrintLinkHealth(client, link);
+		}
+	}
+}
+
+// The room's signed receipts, from a /receipts. An organizer keeps these
+// lines and the key; each signature checks against weyve_join_receipt_text.
+static void PrintReceipts(WeyveClient* client, const WeyveEvent& event)
+{
+	const auto& reply = event.data.join_receipts;
+	std::cout << "* " << reply.count << " receipts, signed by " << std::string(reply.key, reply.key_len) << "\n";
+	WeyveJoinReceipt receipt{};
+	for (uint32_t i = 0; weyve_join_receipt_at(client, i, &receipt); i++) {
+		static const char* roles[] = { "host", "player", "spectator" };
+		std::string who = receipt.name_len ? std::string(receipt.name, receipt.name_len) : "client " + std::to_string(receipt.id); // as signed, not as named now
+		std::cout << "  " << receipt.time << " " << who << (receipt.kind == WEYVE_RECEIPT_JOINED ? " joined as " : " left as ")
+			<< roles[receipt.role] << ", " << std::string(receipt.signature, receipt.signature_len) << "\n";
+	}
+}
+
+// "/setfor 3 team red"; host-only, the value may contain spaces.
+static void SendSetForCommand(WeyveClient* client, const std::string& args)
+{
+	size_t space = args.find(' ');
+	size_t second = space == std::string::npos ? std::string::npos : args.find(' ', space + 1);
+	uint32_t id = ParseId(args.substr(0, space));
+	if (id == 0 || second == std::string::npos || second == space + 1) {
+		std::cout << "usage: /setfor ID KEY VALUE\n";
+		return;
+	}
+
+	std::string key = args.substr(space + 1, second - space - 1);
+	weyve_set_member_data_for(client, id, key.c_str(), args.substr(second + 1).c_str());
+}
+
+// A seat limit for printing; 0 means unlimited.
+static std::string SeatLimit(uint32_t max)
+{
+	return max == 0 ? "-" : std::to_string(max);
+}
+
+// "/cap 8 2"; player and spectator seats, 0 = no limit.
+static void SendCapacityCommand(WeyveClient* client, const std::string& args)
+{
+	size_t space = args.find(' ');
+	if (space == std::string::npos) {
+		std::cout << "usage: /cap PLAYERS SPECTATORS (0 = no limit)\n";
+		return;
+	}
+
+	weyve_set_room_capacity(client, ParseId(args.substr(0, space)), ParseId(args.substr(space + 1)));
+}
+
+// "/simulate 50 120"; 5% of p2p datagrams lost, the rest 120 ms late. "/simulate 0 0" ends it.
+static void SimulateCommand(WeyveClient* client, const std::string& args)
+{
+	size_t space = args.find(' ');
+	if (space == std::string::npos) {
+		std::cout << "usage: /simulate LOSS_PERMILLE LATENCY_MS\n";
+		return;
+	}
+
+	weyve_simulate_network(client, ParseId(args.substr(0, space)), ParseId(args.substr(space + 1)));
+}
+
+static bool ParseLogLevel(const std::string& name, WeyveLogLevel& out)
+{
+	static const std::pair<const char*, WeyveLogLevel> levels[] = {
+		{ "debug", WEYVE_LOG_DEBUG }, { "info", WEYVE_LOG_INFO }, { "warn", WEYVE_LOG_WARN }, { "error", WEYVE_LOG_ERROR },
+	};
+	for (const auto& [text, level] : levels) {
+		if (name == text) {
+			out = level;
+			return true;
+		}
+	}
+	return false;
+}
+
+// "/logs warn 20"; the newest 20 lines at warn or above. Both parts optional.
+static void PrintLogs(WeyveClient* client, const std::string& args)
+{
+	size_t space = args.find(' ');
+	WeyveLogLevel least = WEYVE_LOG_DEBUG;
+	if (!args.empty() && !ParseLogLevel(args.substr(0, space), least)) {
+		std::cout << "usage: /logs [debug|info|warn|error] [COUNT]\n";
+		return;
+	}
+
+	static const char* names[] = { "debug", "info", "warn", "error" };
+	uint32_t count = weyve_recent_logs(client, least, space == std::string::npos ? 20 : ParseId(args.substr(space + 1)));
+	for (uint32_t i = 0; i < count; i++) {
+		WeyveLogEntry entry{};
+		weyve_log_at(client, i, &entry);
+		std::cout << "  [" << names[entry.level] << "] " << std::string(entry.text, entry.text_len) << "\n";
+	}
+	if (count == 0)
+		std::cout << "* nothing logged at that level\n";
+}
+
+// "/afk 300 spectate"; seconds idle before members count as AFK (0 = never),
+// optionally moving them to the spectators.
+static void SendIdleCommand(WeyveClient* client, const std::string& args)
+{
+	size_t space = args.find(' ');
+	std::string action = space == std::string::npos ? "" : args.substr(space + 1);
+	if (action != "" && action != "spectate") {
+		std::cout << "usage: /afk SECONDS [spectate] (0 = never)\n";
+		return;
+	}
+
+	weyve_set_idle_policy(client, ParseId(args.substr(0, space)), action == "spectate");
+}
+
+// "/succession cohost|longest|latency" picks who hosts next by a rule;
+// "/succession 4 7" names them in order instead.
+static void SendSuccessionCommand(WeyveClient* client, const std::string& args)
+{
+	if (args == "cohost" || args == "longest" || args == "latency") {
+		WeyveSuccessionPolicy policy = args == "cohost" ? WEYVE_SUCCESSION_COHOST_FIRST : args == "longest" ? WEYVE_SUCCESSION_LONGEST_CONNECTED : WEYVE_SUCCESSION_LOWEST_LATENCY;
+		weyve_set_succession(client, policy, nullptr, 0);
+		return;
+	}
+
+	std::vector<uint32_t> successors = ParseIds(args);
+	if (successors.empty()) {
+		std::cout << "usage: /succession cohost|longest|latency|ID...\n";
+		return;
+	}
+	weyve_set_succession(client, WEYVE_SUCCESSION_SUCCESSORS, successors.data(), (uint32_t)successors.size());
+}
+
+// "/schedule 30 friday league"; books a room that opens in that many minutes.
+static void SendScheduleCommand(WeyveClient* client, const std::string& args)
+{
+	size_t space = args.find(' ');
+	uint64_t minutes = std::strtoull(args.substr(0, space).c_str(), nullptr, 10);
+	if (space == std::string::npos || minutes == 0) {
+		std::cout << "usage: /schedule MINUTES TITLE\n";
+		return;
+	}
+
+	weyve_schedule_room(client, UnixNow() + minutes * 60, args.substr(space + 1).c_str(), 0, 0, nullptr);
+}
+
+// "/rsvp 4 yes"; yes, maybe or no.
+static void SendRsvpCommand(WeyveClient* client, const std::string& args)
+{
+	size_t space = args.find(' ');
+	uint32_t id = ParseId(args.substr(0, space));
+	std::string answer = space == std::string::npos ? "" : args.substr(space + 1);
+	if (id == 0 || (answer != "yes" && answer != "maybe" && answer != "no")) {
+		std::cout << "usage: /rsvp ID yes|maybe|no\n";
+		return;
+	}
+
+	weyve_rsvp_scheduled_room(client, id, answer == "yes" ? WEYVE_RSVP_GOING : answer == "maybe" ? WEYVE_RSVP_MAYBE : WEYVE_RSVP_NO);
+}
+
+static void PrintScheduledRoom(const WeyveScheduledRoom& room)
+{
+	uint64_t now = UnixNow();
+	std::cout << "  #" << room.id << " \"" << std::string(room.title, room.title_len) << "\" by client " << room.owner
+		<< ", in " << (room.start > now ? (room.start - now + 59) / 60 : 0) << " min, " << room.going << " going" << (room.passworded ? ", password" : "") << "\n";
+}
+
 static void PrintRoomInfo(WeyveClient* client)
 {
 	std::cout << "Room " << RoomId(client) << ", host " << weyve_host_id(client) << (weyve_is_host(client) ? " (you)" : "") << "\n";
 	std::cout << (weyve_room_joinable(client) ? "Open to join" : "Closed") << (weyve_room_passworded(client) ? ", password required" : "") << "\n";
+	std::cout << weyve_player_count(client) << "/" << SeatLimit(weyve_max_players(client)) << " players, "
+		<< weyve_spectator_count(client) << "/" << SeatLimit(weyve_max_spectators(client)) << " watching\n";
+	uint32_t banner_len = 0;
+	if (const char* banner = weyve_room_banner(client, &banner_len); banner_len)
+		std::cout << "Banner " << std::string(banner, banner_len) << "\n";
+	uint32_t rules_len = 0, version = 0;
+	if (const char* rules = weyve_room_rules(client, &rules_len, &version); rules_len)
+		std::cout << "Rules (v" << version << ") " << std::string(rules, rules_len) << "\n";
 
 	uint32_t count = 0;
 	const uint32_t* members = weyve_members(client, &count);
@@ -78,6 +428,14 @@ static void PrintRoomInfo(WeyveClient* client)
 	std::cout << "Members:";
 	for (uint32_t i = 0; i < count; i++) {
 		std::cout << " " << members[i];
+		if (uint32_t len = 0; const char* name = weyve_member_name(client, members[i], &len))
+			std::cout << " \"" << std::string(name, len) << "\"";
+		if (WeyveMemberRole role = weyve_member_role(client, members[i]); role != WEYVE_ROLE_MEMBER)
+			std::cout << " (" << RoleName(role) << ")";
+		if (weyve_is_spectator(client, members[i]))
+			std::cout << " (watching)";
+		if (weyve_is_afk(client, members[i]))
+			std::cout << " (afk)";
 	}
 	std::cout << "\n";
 
@@ -114,6 +472,84 @@ static void SendP2PCommand(WeyveClient* client, const std::string& args)
 		std::cout << "p2p send to client " << id << " failed\n";
 }
 
+// "/w 3 psst"; private text to one member.
+static void SendWhisperCommand(WeyveClient* client, const std::string& args)
+{
+	size_t space = args.find(' ');
+	uint32_t id = ParseId(args.substr(0, space));
+	if (space == std::string::npos || id == 0) {
+		std::cout << "usage: /w ID TEXT\n";
+		return;
+	}
+
+	if (!weyve_send_whisper(client, id, args.substr(space + 1).c_str()))
+		std::cout << "client " << id << " is not in the room\n";
+}
+
+static const char* WhisperPolicyName(WeyveWhisperPolicy policy)
+{
+	switch (policy) {
+	case WEYVE_WHISPERS_STAFF: return "staff";
+	case WEYVE_WHISPERS_NOBODY: return "nobody";
+	default: return "everyone";
+	}
+}
+
+// "/dnd 30"; do-not-disturb for that many minutes, 0 turns it off.
+static void SendDndCommand(WeyveClient* client, const std::string& args)
+{
+	uint64_t minutes = std::strtoull(args.c_str(), nullptr, 10);
+	weyve_set_preferences(client, minutes == 0 ? 0 : UnixNow() + minutes * 60, weyve_whisper_policy(client));
+}
+
+// "/register NAME PASSWORD [EMAIL]" or "/login NAME PASSWORD".
+static void SendAccountCommand(WeyveClient* client, const std::string& args, bool create)
+{
+	size_t space = args.find(' ');
+	if (space == std::string::npos) {
+		std::cout << (create ? "usage: /register NAME PASSWORD [EMAIL]\n" : "usage: /login NAME PASSWORD\n");
+		return;
+	}
+
+	std::string name = args.substr(0, space), password = args.substr(space + 1), email;
+	if (size_t at = password.find(' '); create && at != std::string::npos) {
+		email = password.substr(at + 1);
+		password.resize(at);
+	}
+
+	if (create)
+		weyve_create_account(client, name.c_str(), password.c_str(), email.empty() ? nullptr : email.c_str());
+	else
+		weyve_login(client, name.c_str(), password.c_str());
+}
+
+// "/upload avatar.png"; the id comes back in WEYVE_EVENT_BLOB_UPLOADED.
+static void SendUploadCommand(WeyveClient* client, const std::string& path)
+{
+	std::ifstream file(path, std::ios::binary);
+	if (!file) {
+		std::cout << "Cannot read " << path << "\n";
+		return;
+	}
+
+	std::string bytes{ std::istreambuf_iterator<char>(file), std::istreambuf_iterator<char>() };
+	if (uint32_t transfer = weyve_upload_blob(client, bytes.data(), (uint32_t)bytes.size()))
+		std::cout << "* uploading " << bytes.size() << " bytes as transfer " << transfer << "\n";
+	else
+		std::cout << "Upload not sent (empty, or too large)\n";
+}
+
+// "/whispers staff"; everyone, staff or nobody.
+static void SendWhispersCommand(WeyveClient* client, const std::string& policy)
+{
+	if (policy != "everyone" && policy != "staff" && policy != "nobody") {
+		std::cout << "usage: /whispers everyone|staff|nobody\n";
+		return;
+	}
+
+	weyve_set_preferences(client, weyve_dnd_until(client), policy == "staff" ? WEYVE_WHISPERS_STAFF : policy == "nobody" ? WEYVE_WHISPERS_NOBODY : WEYVE_WHISPERS_EVERYONE);
+}
+
 // "/set KEY VALUE" or "/setme KEY VALUE"; the value may contain spaces.
 static void SendSetCommand(WeyveClient* client, const std::string& args, bool own)
 {
@@ -131,15 +567,257 @@ static void SendSetCommand(WeyveClient* client, const std::string& args, bool ow
 		weyve_set_room_data(client, key.c_str(), value.c_str());
 }
 
+static const char* LimitName(WeyveServerLimit limit)
+{
+	switch (limit) {
+	case WEYVE_LIMIT_USERS: return "users";
+	case WEYVE_LIMIT_ROOMS: return "rooms";
+	case WEYVE_LIMIT_PER_ADDRESS: return "connections from your address";
+	case WEYVE_LIMIT_REQUESTS: return "requests";
+	}
+	return "?";
+}
+
+static std::string LimitText(uint32_t max)
+{
+	return max == 0 ? "unlimited" : std::to_string(max);
+}
+
+// "/knock CODE [MESSAGE]": ask the host of a passworded room to let you in.
+static void SendKnockCommand(WeyveClient* client, const std::string& args)
+{
+	size_t space = args.find(' ');
+	std::string code = args.substr(0, space);
+	std::string message = space == std::string::npos ? "" : args.substr(space + 1);
+	weyve_request_join(client, code.c_str(), message.c_str());
+}
+
+// "/export text|json|csv FILE": every room's chat heard this run.
+static void SendExportCommand(WeyveClient* client, const std::string& args)
+{
+	size_t space = args.find(' ');
+	std::string kind = args.substr(0, space);
+	std::string path = space == std::string::npos ? "" : args.substr(space + 1);
+	WeyveChatFormat format = kind == "json" ? WEYVE_CHAT_JSON : kind == "csv" ? WEYVE_CHAT_CSV : WEYVE_CHAT_TEXT;
+	if (path.empty() || !weyve_export_chat(client, nullptr, format, path.c_str()))
+		std::cout << "* could not write the chat to \"" << path << "\"\n";
+	else
+		std::cout << "* chat written to " << path << "\n";
+}
+
+static void PrintChatLine(const WeyveClient* client, uint32_t index, const char* marker)
+{
+	WeyveChatLine line;
+	if (!weyve_chat_line_at(client, index, &line))
+		return;
+	std::cout << marker << (line.name_len ? std::string(line.name, line.name_len) : "client " + std::to_string(line.from));
+	if (line.to)
+		std::cout << " -> " << (line.to_name_len ? std::string(line.to_name, line.to_name_len) : "client " + std::to_string(line.to));
+	std::cout << ": " << std::string(line.text, line.text_len) << "\n";
+}
+
+// "/search WORDS": the last few matches in this room, each with the line before and after it.
+static void SendSearchCommand(WeyveClient* client, const std::string& query)
+{
+	uint32_t lines[5];
+	uint32_t found = weyve_search_messages(client, query.c_str(), RoomId(client).c_str(), lines, 5);
+	std::cout << "* " << found << (found == 1 ? " match\n" : " matches\n");
+	for (uint32_t i = 0; i < found; i++) {
+		if (lines[i] > 0)
+			PrintChatLine(client, lines[i] - 1, "    ");
+		PrintChatLine(client, lines[i], "  > ");
+		PrintChatLine(client, lines[i] + 1, "    ");
+	}
+}
+
+// "/peek CODE [watch]": what a lobby would show before joining.
+static void SendPeekCommand(WeyveClient* client, const std::string& args)
+{
+	size_t space = args.find(' ');
+	std::string code = args.substr(0, space);
+	bool watch = space != std::string::npos && args.substr(space + 1) == "watch";
+	weyve_get_room_preview(client, code.c_str(), watch);
+}
+
+// "/check CODE [spectate]": would a join go in, before making it?
+static void SendCheckCommand(WeyveClient* client, const std::string& args)
+{
+	size_t space = args.find(' ');
+	std::string code = args.substr(0, space);
+	bool spectating = space != std::string::npos && args.substr(space + 1) == "spectate";
+	weyve_preflight_join(client, code.c_str(), spectating);
+}
+
+static void PrintJoinCheck(const WeyveEvent& event)
+{
+	const auto& check = event.data.join_check;
+	std::cout << "* room " << std::string(check.id, check.id_len) << ": ";
+	if (check.ready) {
+		std::cout << (check.spectating ? "a spectator seat is free" : "a player seat is free");
+	}
+	else {
+		switch (check.blocker) {
+		case WEYVE_ROOM_ERROR_NO_SUCH_ROOM: std::cout << "no such room"; break;
+		case WEYVE_ROOM_ERROR_BANNED: std::cout << "you are banned"; break;
+		case WEYVE_ROOM_ERROR_ROOM_CLOSED: std::cout << "closed to joins"; break;
+		default: std::cout << (check.spectating ? "no spectator seat free" : "no player seat free"); break;
+		}
+	}
+	std::cout << (check.password ? ", needs a password" : "")
+		<< (check.relay ? "" : "; this server has no relay, so some members may be unreachable directly") << "\n";
+}
+
+static void PrintPreview(WeyveClient* client, const WeyveEvent& event)
+{
+	const auto& preview = event.data.room_preview;
+	std::cout << "* room " << std::string(preview.id, preview.id_len) << ": " << preview.players << "/" << LimitText(preview.max_players)
+		<< " players, " << preview.spectators << "/" << LimitText(preview.max_spectators) << " spectators"
+		<< (preview.open ? "" : ", closed") << (preview.passworded ? ", passworded" : "") << "\n";
+
+	uint32_t id = 0, len = 0;
+	const char* name = nullptr;
+	bool spectating = false;
+	for (uint32_t i = 0; weyve_preview_member_at(client, i, &id, &name, &len, &spectating); i++) {
+		std::cout << "  " << (name ? std::string(name, len) : "client " + std::to_string(id))
+			<< (id == preview.host ? " (host)" : "") << (spectating ? " (spectating)" : "") << "\n";
+	}
+
+	const char* key = nullptr;
+	const char* value = nullptr;
+	uint32_t key_len = 0, value_len = 0;
+	for (uint32_t i = 0; weyve_preview_data_at(client, i, &key, &key_len, &value, &value_len); i++)
+		std::cout << "  " << std::string(key, key_len) << " = " << std::string(value, value_len) << "\n";
+}
+
+// What happened before we joined, the way a chat view would show it.
+static void PrintTimeline(WeyveClient* client)
+{
+	WeyveTimelineEntry entry{};
+	for (uint32_t i = 0; weyve_timeline_at(client, i, &entry); i++) {
+		std::string who = entry.name_len ? std::string(entry.name, entry.name_len) : "client " + std::to_string(entry.id);
+		std::string by = MemberLabel(client, entry.by);
+		std::cout << "  " << (UnixNow() - entry.time) / 60 << " min ago: ";
+		switch (entry.kind) {
+		case WEYVE_TIMELINE_JOINED: std::cout << who << " joined"; break;
+		case WEYVE_TIMELINE_SPECTATING: std::cout << who << " came to watch"; break;
+		case WEYVE_TIMELINE_LEFT: std::cout << who << " left"; break;
+		case WEYVE_TIMELINE_KICKED: std::cout << who << " was kicked by " << by; break;
+		case WEYVE_TIMELINE_BANNED: std::cout << who << " was banned by " << by; break;
+		case WEYVE_TIMELINE_HOST_CHANGED: std::cout << who << " became host"; break;
+		case WEYVE_TIMELINE_OPENED: std::cout << by << " opened the room"; break;
+		case WEYVE_TIMELINE_CLOSED: std::cout << by << " closed the room"; break;
+		case WEYVE_TIMELINE_MOVED: std::cout << who << " was moved to another room by " << by; break;
+		}
+		std::cout << "\n";
+	}
+}
+
+static void PrintTeams(WeyveClient* client)
+{
+	if (weyve_team_count(client) == 0) {
+		std::cout << "* no teams\n";
+		return;
+	}
+
+	WeyveTeam team{};
+	for (uint32_t i = 0; weyve_team_at(client, i, &team); i++) {
+		std::cout << "* team " << team.id << " " << std::string(team.name, team.name_len) << " (" << team.member_count;
+		if (team.max_size != 0)
+			std::cout << "/" << team.max_size;
+		std::cout << "):";
+		for (uint32_t m = 0; m < team.member_count; m++)
+			std::cout << " " << MemberLabel(client, team.members[m]);
+		std::cout << "\n";
+	}
+}
+
+// "/team 2 Red Side"; size 0 = no limit, the name may contain spaces.
+static void SendTeamCommand(WeyveClient* client, const std::string& args)
+{
+	size_t space = args.find(' ');
+	if (space == std::string::npos || space + 1 == args.size()) {
+		std::cout << "usage: /team SIZE NAME (SIZE 0 = no limit)\n";
+		return;
+	}
+
+	weyve_create_team(client, args.substr(space + 1).c_str(), ParseId(args.substr(0, space)));
+}
+
+// "/assign 3 1"; team 0 takes them off every team.
+static void SendAssignCommand(WeyveClient* client, const std::string& args)
+{
+	size_t space = args.find(' ');
+	uint32_t id = ParseId(args.substr(0, space));
+	if (id == 0 || space == std::string::npos) {
+		std::cout << "usage: /assign ID TEAM (TEAM 0 = none)\n";
+		return;
+	}
+
+	weyve_assign_team(client, id, ParseId(args.substr(space + 1)));
+}
+
+static void PrintChannels(WeyveClient* client)
+{
+	if (weyve_channel_count(client) == 0) {
+		std::cout << "* no channels\n";
+		return;
+	}
+
+	WeyveChannel channel{};
+	for (uint32_t i = 0; weyve_channel_at(client, i, &channel); i++) {
+		std::cout << "* channel " << channel.id << " " << std::string(channel.name, channel.name_len) << " (" << channel.member_count << "):";
+		for (uint32_t m = 0; m < channel.member_count; m++)
+			std::cout << " " << MemberLabel(client, channel.members[m]);
+		std::cout << "\n";
+	}
+}
+
+// "/place 3 1"; channel 0 takes them out of every channel.
+static void SendPlaceCommand(WeyveClient* client, const std::string& args)
+{
+	size_t space = args.find(' ');
+	uint32_t id = ParseId(args.substr(0, space));
+	if (id == 0 || space == std::string::npos) {
+		std::cout << "usage: /place ID CHANNEL (CHANNEL 0 = none)\n";
+		return;
+	}
+
+	weyve_place_in_channel(client, id, ParseId(args.substr(space + 1)));
+}
+
+static void PrintUserResults(WeyveClient* client, const WeyveEvent& event)
+{
+	const auto& search = event.data.user_search;
+	std::cout << "* " << search.count << " users matching \"" << std::string(search.query, search.query_len) << "\"\n";
+
+	const char* name = nullptr;
+	const char* room = nullptr;
+	uint32_t name_len = 0, id = 0, room_len = 0;
+	for (uint32_t i = 0; weyve_user_result_at(client, i, &name, &name_len, &id, &room, &room_len); i++) {
+		std::cout << "  " << std::string(name, name_len);
+		if (id == 0)
+			std::cout << " (offline)";
+		else
+			std::cout << " (client " << id << ")";
+		if (room_len)
+			std::cout << " in room " << std::string(room, room_len);
+		std::cout << "\n";
+	}
+}
+
 // Ping the server once a second; it replies with a pong.
 static int RunPing(WeyveClient* client)
 {
 	auto last = std::chrono::steady_clock::now();
-	while (weyve_poll(client)) {
+	while (!quitting && weyve_poll(client)) {
 		WeyveEvent event;
 		while (weyve_next(client, &event)) {
 			if (event.type == WEYVE_EVENT_HEARTBEAT)
 				PrintPong(client, event.data.heartbeat.timestamp);
+			else if (event.type == WEYVE_EVENT_SERVER_FULL)
+				std::cout << "Server full (" << LimitName(event.data.server_full.limit) << ")\n";
+			else if (event.type == WEYVE_EVENT_CLIENT_OUTDATED)
+				PrintError(client, event);
 		}
 
 		auto now = std::chrono::steady_clock::now();
@@ -150,39 +828,76 @@ static int RunPing(WeyveClient* client)
 		std::this_thread::sleep_for(std::chrono::milliseconds(1));
 	}
 
-	std::cout << "Connection closed\n";
+	std::cout << (quitting ? "Quitting\n" : "Connection closed\n");
 	return 0;
 }
 
-// Create a room (empty code) or join one, then send typed lines to everyone in it.
-static int RunChat(WeyveClient* client, const std::string& code, const std::string& password)
+// Where chat mode keeps the room it is in, for "clientexample resume".
+static const char* session_file = "clientexample.session";
+
+static void SaveSession(WeyveClient* client)
 {
-	if (code.empty())
-		weyve_create_room(client);
-	else
-		weyve_join_room(client, code.c_str(), password.c_str());
+	uint32_t len = 0;
+	const uint8_t* data = weyve_save_session(client, &len);
+	std::ofstream(session_file, std::ios::binary).write((const char*)data, len);
+}
+
+static bool ResumeSession(WeyveClient* client)
+{
+	std::ifstream file(session_file, std::ios::binary);
+	std::string bytes{ std::istreambuf_iterator<char>(file), std::istreambuf_iterator<char>() };
+	return !bytes.empty() && weyve_resume_session(client, bytes.data(), (uint32_t)bytes.size());
+}
 
+// Send typed lines to everyone in the room being created, joined or resumed.
+static int RunChat(WeyveClient* client)
+{
+	// A translation table would be loaded here; these just put the details back in.
+	weyve_set_error_text(client, "room.chat_too_long", "Message too long (max {max_chars} characters).");
+	weyve_set_error_text(client, "room.no_such_room", "There is no room {room}.");
+	weyve_set_error_text(client, "blob.too_large", "That file is over {max_bytes} bytes.");
 	StartInputThread();
 
-	while (weyve_poll(client)) {
+	while (!quitting && weyve_poll(client)) {
 		WeyveEvent event;
 		while (weyve_next(client, &event)) {
 			switch (event.type) {
 			case WEYVE_EVENT_ROOM_ID_ASSIGNED: {
 				std::string id(event.data.room_assigned.id, event.data.room_assigned.id_len);
 				std::cout << "In room " << id << " (join it: clientexample chat " << id << ")\n";
-				std::cout << "Commands: /who, /set KEY VALUE, /del KEY, /setme KEY VALUE, /delme KEY\n";
-				std::cout << "          /open, /close, /pass [PASSWORD], /kick ID, /ban ID, /host ID, /leave\n";
-				std::cout << "          /p2p ID TEXT (direct, over the mesh)\n";
+				SaveSession(client);
+				std::cout << "Commands: /who, /set KEY VALUE, /del KEY, /setme KEY VALUE, /delme KEY, /setfor ID KEY VALUE\n";
+				std::cout << "          /open, /close, /pass [PASSWORD], /cap PLAYERS SPECTATORS, /kick ID, /ban ID, /host ID, /role ID ROLE, /leave, /quit\n";
+				std::cout << "          /split ID..., /merge CODE (into this room), /acceptmerge CODE, /declinemerge CODE\n";
+				std::cout << "          /w ID TEXT (whisper), /p2p ID TEXT (direct, over the mesh), /redial ID, /hangup ID\n";
+				std::cout << "          /dnd MINUTES (0 = off), /whispers everyone|staff|nobody, /afk SECONDS [spectate], /succession cohost|longest|latency|ID...\n";
+				std::cout << "          /schedule MINUTES TITLE, /schedules, /rsvp ID yes|maybe|no, /attendees ID, /unschedule ID\n";
+				std::cout << "          /guest NAME, /register NAME PASSWORD [EMAIL], /login NAME PASSWORD, /retry ID, /discard ID, /export text|json|csv FILE, /search WORDS\n";
+				std::cout << "          /upload FILE, /download BLOB, /banner [BLOB], /rules [JSON], /background, /foreground, /netchange, /profile low|balanced|high, /linktimeout SECONDS, /load\n";
+				std::cout << "          /peek CODE [watch], /unpeek, /check CODE [spectate], /knock CODE [MESSAGE], /approve ID, /deny ID\n";
+				std::cout << "          /find NAME, /hide, /show (from user searches), /mydata\n";
+				std::cout << "          /team SIZE NAME, /unteam TEAM, /assign ID TEAM, /balance, /teams, /countdown SECONDS (0 = cancel), /pause, /resume\n";
+				std::cout << "          /channel NAME, /unchannel CHANNEL, /place ID CHANNEL, /enter CHANNEL (0 = out), /c TEXT (to your channel), /channels\n";
+				std::cout << "          /server PROGRAM [ARGS...], /rcon COMMAND, /stopserver, /portmap PORT (0 = off), /iface [NAME|ADDRESS|-], /path ID\n";
+				std::cout << "          /ordered [JITTER_MS], /raw (p2p delivery), /fec on|off, /loss ID, /health [server|share|private|off], /simulate LOSS_PERMILLE LATENCY_MS, /receipts\n";
+				std::cout << "          /record FILE, /stoprecord (server traffic, for test fixtures), /logs [LEVEL] [COUNT], /loglevel debug|info|warn|error\n";
 				break;
 			}
 			case WEYVE_EVENT_ROOM_ERROR:
-				PrintRoomError(event);
+				PrintError(client, event);
+				if (RoomId(client).empty() && event.data.room_error.code == WEYVE_ROOM_ERROR_ROOM_FULL
+					&& std::string(event.data.room_error.context, event.data.room_error.context_len) == "players" && !wanted_room.empty()) {
+					weyve_queue_for_room(client, wanted_room.c_str(), wanted_password.c_str());
+					break;
+				}
 				if (RoomId(client).empty())
 					return 1; // create/join failed; nothing to chat in
 				break;
 			case WEYVE_EVENT_CHAT:
-				std::cout << "[client " << event.data.chat.from << "] " << std::string(event.data.chat.text, event.data.chat.text_len) << "\n";
+				std::cout << "[" << MemberLabel(client, event.data.chat.from) << "] " << std::string(event.data.chat.text, event.data.chat.text_len) << "\n";
+				break;
+			case WEYVE_EVENT_WHISPER:
+				std::cout << "[" << MemberLabel(client, event.data.whisper.from) << " whispers] " << std::string(event.data.whisper.text, event.data.whisper.text_len) << "\n";
 				break;
 			case WEYVE_EVENT_PEER_JOINED:
 				std::cout << "* client " << event.data.peer_joined.id << " is here\n";
@@ -190,6 +905,7 @@ static int RunChat(WeyveClient* client, const std::string& code, const std::stri
 			case WEYVE_EVENT_PEER_LEFT:
 				if (event.data.peer_left.id == weyve_id(client)) {
 					std::cout << "Left the room\n";
+					std::remove(session_file); // nothing to resume
 					return 0;
 				}
 				std::cout << "* client " << event.data.peer_left.id << " left\n";
@@ -213,6 +929,20 @@ static int RunChat(WeyveClient* client, const std::string& code, const std::stri
 					std::cout << "* client " << event.data.member_data.id << " data: " << key << " = " << std::string(event.data.member_data.value, event.data.member_data.value_len) << "\n";
 				break;
 			}
+			case WEYVE_EVENT_ROOM_SPLIT:
+				std::cout << "* " << event.data.room_split.members << " member(s) split off into room " << std::string(event.data.room_split.id, event.data.room_split.id_len) << "\n";
+				break;
+			case WEYVE_EVENT_MERGE_REQUESTED: {
+				std::string into(event.data.merge_requested.into, event.data.merge_requested.into_len);
+				std::cout << "* " << MemberLabel(client, event.data.merge_requested.by) << " asks to merge this room into " << into << " (" << event.data.merge_requested.members << " there): /acceptmerge " << into << " or /declinemerge " << into << "\n";
+				break;
+			}
+			case WEYVE_EVENT_MERGE_ANSWERED:
+				std::cout << "* room " << std::string(event.data.merge_answered.id, event.data.merge_answered.id_len) << (event.data.merge_answered.accepted ? " is merging in\n" : " declined to merge\n");
+				break;
+			case WEYVE_EVENT_ROOM_MOVED:
+				std::cout << "* moved to room " << std::string(event.data.room_moved.id, event.data.room_moved.id_len) << " by client " << event.data.room_moved.by << "\n";
+				break;
 			case WEYVE_EVENT_KICKED:
 				std::cout << "Kicked from the room\n";
 				return 0;
@@ -222,6 +952,280 @@ static int RunChat(WeyveClient* client, const std::string& code, const std::stri
 			case WEYVE_EVENT_ROOM_ACCESS_CHANGED:
 				std::cout << "* room is now " << (event.data.room_access.open ? "open" : "closed") << (event.data.room_access.passworded ? " (password required)" : "") << "\n";
 				break;
+			case WEYVE_EVENT_MEMBER_ROLE_CHANGED:
+				std::cout << "* client " << event.data.member_role.id << " is now a " << RoleName(event.data.member_role.role) << "\n";
+				break;
+			case WEYVE_EVENT_MEMBER_SPECTATING:
+				std::cout << "* client " << event.data.member_spectating.id << " is watching" << (event.data.member_spectating.id == weyve_id(client) ? " (you)" : "") << "\n";
+				break;
+			case WEYVE_EVENT_ROOM_CAPACITY_CHANGED:
+				std::cout << "* room seats: " << SeatLimit(event.data.room_capacity.max_players) << " players, " << SeatLimit(event.data.room_capacity.max_spectators) << " watching\n";
+				break;
+			case WEYVE_EVENT_IDLE_POLICY_CHANGED:
+				if (event.data.idle_policy.afk_after == 0)
+					std::cout << "* idle members are never flagged AFK\n";
+				else
+					std::cout << "* members go AFK after " << event.data.idle_policy.afk_after << "s" << (event.data.idle_policy.spectate ? " and lose their seat" : "") << "\n";
+				break;
+			case WEYVE_EVENT_SUCCESSION_CHANGED: {
+				static constexpr const char* policies[] = { "a co-host first", "whoever connected first", "whoever has the lowest latency", "the host's successors" };
+				std::cout << "* when the host leaves, " << policies[event.data.succession.policy] << " hosts next";
+				for (uint32_t i = 0; event.data.succession.policy == WEYVE_SUCCESSION_SUCCESSORS && i < event.data.succession.count; i++)
+					std::cout << (i == 0 ? ": " : ", ") << "client " << event.data.succession.successors[i];
+				std::cout << "\n";
+				break;
+			}
+			case WEYVE_EVENT_MEMBER_AFK:
+				std::cout << "* client " << event.data.member_afk.id << (event.data.member_afk.afk ? " is AFK" : " is back") << "\n";
+				break;
+			case WEYVE_EVENT_SCHEDULED_ROOM_CHANGED:
+				std::cout << "* booking:\n";
+				PrintScheduledRoom(event.data.scheduled_room);
+				break;
+			case WEYVE_EVENT_SCHEDULED_ROOM_LIST: {
+				std::cout << "Booked rooms: " << event.data.scheduled_room_list.count << "\n";
+				WeyveScheduledRoom room;
+				for (uint32_t i = 0; weyve_scheduled_room_at(client, i, &room); i++) {
+					PrintScheduledRoom(room);
+				}
+				break;
+			}
+			case WEYVE_EVENT_SCHEDULED_ROOM_REMINDER:
+				std::cout << "* booking #" << event.data.scheduled_room_reminder.id << " starts in " << (event.data.scheduled_room_reminder.start - std::min(UnixNow(), event.data.scheduled_room_reminder.start) + 59) / 60 << " min\n";
+				break;
+			case WEYVE_EVENT_SCHEDULED_ROOM_OPENED: {
+				std::string id(event.data.scheduled_room_opened.room, event.data.scheduled_room_opened.room_len);
+				std::cout << "* booking #" << event.data.scheduled_room_opened.id << " is open: clientexample chat " << id << "\n";
+				break;
+			}
+			case WEYVE_EVENT_SCHEDULED_ROOM_ATTENDEES: {
+				uint32_t booking = event.data.scheduled_room_attendees.id;
+				std::cout << "* booking #" << booking << " attendees:";
+				uint32_t id = 0;
+				WeyveRsvpStatus status;
+				for (uint32_t i = 0; weyve_attendee_at(client, booking, i, &id, &status); i++) {
+					std::cout << " " << id << (status == WEYVE_RSVP_MAYBE ? " (maybe)" : "");
+				}
+				std::cout << "\n";
+				break;
+			}
+			case WEYVE_EVENT_SCHEDULED_ROOM_CANCELLED:
+				std::cout << "* booking #" << event.data.scheduled_room_cancelled.id << " was called off\n";
+				break;
+			case WEYVE_EVENT_LOGGED_IN:
+				std::cout << "* logged in as " << std::string(event.data.logged_in.name, event.data.logged_in.name_len) << "\n";
+				break;
+			case WEYVE_EVENT_ACCOUNT_ERROR:
+				PrintError(client, event);
+				break;
+			case WEYVE_EVENT_MEMBER_NAME_CHANGED:
+				if (event.data.member_name.id != weyve_id(client))
+					std::cout << "* client " << event.data.member_name.id << " is " << std::string(event.data.member_name.name, event.data.member_name.name_len) << "\n";
+				break;
+			case WEYVE_EVENT_CHAT_LINK_PREVIEW: {
+				const WeyveLinkPreview& card = event.data.link_preview;
+				std::cout << "  [link] " << std::string(card.title, card.title_len);
+				if (card.site_len)
+					std::cout << " (" << std::string(card.site, card.site_len) << ")";
+				if (card.description_len)
+					std::cout << ": " << std::string(card.description, card.description_len);
+				std::cout << "\n";
+				break;
+			}
+			case WEYVE_EVENT_ROOM_RULES_CHANGED:
+				if (event.data.room_rules.rules_len == 0)
+					std::cout << "* the room has no rules\n";
+				else
+					std::cout << "* rules v" << event.data.room_rules.version << ": " << std::string(event.data.room_rules.rules, event.data.room_rules.rules_len) << "\n";
+				break;
+			case WEYVE_EVENT_ROOM_BANNER_CHANGED: {
+				std::string id(event.data.room_banner.id, event.data.room_banner.id_len);
+				if (id.empty()) {
+					std::cout << "* the room has no banner\n";
+				}
+				else if (uint32_t len = 0; weyve_get_blob(client, id.c_str(), &len)) {
+					std::cout << "* banner " << id << ", " << len << " bytes\n";
+				}
+				else {
+					std::cout << "* banner " << id << ", fetching\n"; // BLOB_DOWNLOADED follows
+				}
+				break;
+			}
+			case WEYVE_EVENT_PEER_RETRYING:
+				std::cout << "* direct link to " << MemberLabel(client, event.data.peer_link.id) << " failed (try " << event.data.peer_link.attempt
+					<< "), redialing in " << event.data.peer_link.retry_in << " ms\n";
+				break;
+			case WEYVE_EVENT_PEER_UNREACHABLE:
+				std::cout << "* " << MemberLabel(client, event.data.peer_link.id) << " is unreachable directly (/redial " << event.data.peer_link.id << " to try again)\n";
+				break;
+			case WEYVE_EVENT_PEER_CLOSED:
+				std::cout << "* direct link to " << MemberLabel(client, event.data.peer_link.id) << " closed" << (event.data.peer_link.remote ? " by them" : "") << "\n";
+				break;
+			case WEYVE_EVENT_BLOB_UPLOADED:
+				std::cout << "* stored as " << std::string(event.data.blob_uploaded.id, event.data.blob_uploaded.id_len) << " (/set KEY ID keeps it)\n";
+				break;
+			case WEYVE_EVENT_BLOB_DOWNLOADED: {
+				std::string id(event.data.blob_downloaded.id, event.data.blob_downloaded.id_len);
+				uint32_t len = 0;
+				if (weyve_blob(client, id.c_str(), &len))
+					std::cout << "* downloaded " << id << ", " << len << " bytes\n";
+				break;
+			}
+			case WEYVE_EVENT_BLOB_ERROR:
+				std::cout << "Transfer " << event.data.blob_error.transfer << ": ";
+				PrintError(client, event);
+				break;
+			case WEYVE_EVENT_MESSAGE_SEND_FAILED:
+				std::cout << "* not sent, held as #" << event.data.send_failed.id << " (/retry or /discard it): "
+					<< std::string(event.data.send_failed.text, event.data.send_failed.text_len) << "\n";
+				break;
+			case WEYVE_EVENT_PREFERENCES_CHANGED: {
+				uint64_t until = event.data.preferences.dnd_until;
+				std::cout << "* whispers from " << WhisperPolicyName(event.data.preferences.whispers);
+				if (until > UnixNow())
+					std::cout << ", do-not-disturb for " << (until - UnixNow() + 59) / 60 << " min";
+				std::cout << "\n";
+				break;
+			}
+			case WEYVE_EVENT_SERVER_FULL:
+				std::cout << "* server full (" << LimitName(event.data.server_full.limit) << "), try again in "
+					<< event.data.server_full.retry_after << "s\n";
+				break;
+			case WEYVE_EVENT_REQUEST_TIMED_OUT:
+				std::cout << "* the server did not answer the " << (event.data.request_timeout.request == WEYVE_REQUEST_ROOM ? "room" : "account")
+					<< " request\n";
+				break;
+			case WEYVE_EVENT_SERVER_INFO:
+				std::cout << "* " << event.data.server_info.users << "/" << LimitText(event.data.server_info.max_users) << " users, "
+					<< event.data.server_info.rooms << "/" << LimitText(event.data.server_info.max_rooms) << " rooms\n";
+				break;
+			case WEYVE_EVENT_CLIENT_VERSION_POLICY: {
+				std::string min(event.data.client_version.min_version, event.data.client_version.min_version_len);
+				if (!min.empty())
+					std::cout << "* app version " << min << " or newer, we are " << example_version << "\n";
+				break;
+			}
+			case WEYVE_EVENT_CLIENT_OUTDATED:
+				PrintError(client, event);
+				if (event.data.client_version.update_url_len)
+					std::cout << "* get it from " << std::string(event.data.client_version.update_url, event.data.client_version.update_url_len) << "\n";
+				break;
+			case WEYVE_EVENT_ROOM_PREVIEW:
+				PrintPreview(client, event);
+				break;
+			case WEYVE_EVENT_JOIN_CHECKED:
+				PrintJoinCheck(event);
+				break;
+			case WEYVE_EVENT_TIMELINE:
+				if (event.data.timeline.count > 0) {
+					std::cout << "* earlier in this room:\n";
+					PrintTimeline(client);
+				}
+				break;
+			case WEYVE_EVENT_TEAMS_CHANGED:
+				PrintTeams(client);
+				break;
+			case WEYVE_EVENT_CHANNELS_CHANGED:
+				PrintChannels(client);
+				break;
+			case WEYVE_EVENT_CHANNEL_CHAT:
+				std::cout << "[" << MemberLabel(client, event.data.channel_chat.from) << " in channel " << weyve_channel_of(client, event.data.channel_chat.from) << "] "
+					<< std::string(event.data.channel_chat.text, event.data.channel_chat.text_len) << "\n";
+				break;
+			case WEYVE_EVENT_COUNTDOWN:
+				if (event.data.countdown.seconds == 0)
+					std::cout << "* " << MemberLabel(client, event.data.countdown.by) << " called off the countdown\n";
+				else
+					std::cout << "* " << MemberLabel(client, event.data.countdown.by) << " started a " << event.data.countdown.seconds << "s countdown\n";
+				break;
+			case WEYVE_EVENT_COUNTDOWN_TICK:
+				std::cout << "* " << event.data.countdown_tick.remaining << "...\n";
+				break;
+			case WEYVE_EVENT_MATCH_START:
+				std::cout << "* go!\n";
+				break;
+			case WEYVE_EVENT_MATCH_PAUSED:
+				std::cout << "* " << MemberLabel(client, event.data.match_paused.by) << (event.data.match_paused.paused ? " paused" : " resumed") << " the match\n";
+				break;
+			case WEYVE_EVENT_GAME_SERVER_OUTPUT:
+				std::cout << "[server] " << std::string(event.data.game_output.line, event.data.game_output.line_len) << "\n";
+				break;
+			case WEYVE_EVENT_GAME_SERVER_EXITED:
+				std::cout << "* game server exited with " << event.data.game_exited.exit_code << "\n";
+				break;
+			case WEYVE_EVENT_PORT_MAPPING: {
+				const auto& mapping = event.data.port_mapping;
+				if (!mapping.mapped) {
+					std::cout << "* port " << mapping.internal_port << " not forwarded: " << std::string(mapping.error, mapping.error_len) << "\n";
+					break;
+				}
+				std::cout << "* port " << mapping.internal_port << " forwarded by " << (mapping.method == WEYVE_PORT_MAPPING_UPNP ? "UPnP" : "NAT-PMP")
+					<< " as " << (mapping.external_address_len ? std::string(mapping.external_address, mapping.external_address_len) : "?") << ":" << mapping.external_port << "\n";
+				break;
+			}
+			case WEYVE_EVENT_MEMBER_HEALTH:
+				break; // every 10 seconds per member; /health reads them
+			case WEYVE_EVENT_SESSION_HEALTH:
+				if (event.data.session_health.count == 0)
+					std::cout << "* the server holds no health reports yet\n";
+				PrintReportedHealth(client, event.data.session_health.count);
+				break;
+			case WEYVE_EVENT_JOIN_RECEIPTS:
+				PrintReceipts(client, event);
+				break;
+			case WEYVE_EVENT_HOST_HANDOFF: {
+				static constexpr const char* reasons[] = { "handed over", "left", "dropped" };
+				const auto& handoff = event.data.host_handoff;
+				std::cout << "* you host now; the old host " << reasons[handoff.reason];
+				if (handoff.saved)
+					std::cout << ", its state saved at " << handoff.saved << " (" << handoff.state_len << " bytes)";
+				std::cout << "\n";
+				break;
+			}
+			case WEYVE_EVENT_HOST_STATE_WANTED:
+				std::cout << "* the host dropped; client " << event.data.host_state_wanted.host << " asks for your view of its state\n";
+				break;
+			case WEYVE_EVENT_MEMBER_HOST_STATE:
+				std::cout << "* client " << event.data.member_host_state.from << " reports " << event.data.member_host_state.state_len << " bytes of host state\n";
+				break;
+			case WEYVE_EVENT_JOIN_REQUESTED: {
+				const auto& knock = event.data.join_requested;
+				std::cout << "* " << (knock.name_len ? std::string(knock.name, knock.name_len) : "client " + std::to_string(knock.id)) << " knocks";
+				if (knock.message_len)
+					std::cout << ": " << std::string(knock.message, knock.message_len);
+				std::cout << " (/approve " << knock.id << " or /deny " << knock.id << ")\n";
+				break;
+			}
+			case WEYVE_EVENT_QUEUE_POSITION:
+				if (event.data.queue_position.position == 0) {
+					std::cout << "* out of the line\n";
+					if (RoomId(client).empty())
+						return 1;
+				}
+				else {
+					std::cout << "* waiting for a seat, " << event.data.queue_position.position << " in line\n";
+				}
+				break;
+			case WEYVE_EVENT_SEAT_OFFERED:
+				std::cout << "* a seat is free, taking it\n";
+				weyve_join_room(client, std::string(event.data.seat_offered.room, event.data.seat_offered.room_len).c_str(), wanted_password.c_str());
+				break;
+			case WEYVE_EVENT_USER_SEARCH_RESULTS:
+				PrintUserResults(client, event);
+				break;
+			case WEYVE_EVENT_MY_DATA: {
+				uint32_t len = 0;
+				const char* json = weyve_my_data(client, &len);
+				std::cout << "* what the server holds about you:\n" << std::string(json, len);
+				break;
+			}
+			case WEYVE_EVENT_SEARCHABLE_CHANGED:
+				std::cout << (event.data.searchable.searchable ? "* user searches find you\n" : "* hidden from user searches\n");
+				break;
+			case WEYVE_EVENT_JOIN_REQUEST_ANSWERED:
+				std::cout << "* room " << std::string(event.data.join_answered.room, event.data.join_answered.room_len)
+					<< (event.data.join_answered.approved ? " let you in; join it without the password\n" : " turned you away\n");
+				break;
 			default:
 				break;
 			}
@@ -240,12 +1244,60 @@ static int RunChat(WeyveClient* client, const std::string& code, const std::stri
 				continue;
 			if (line == "/who")
 				PrintRoomInfo(client);
+			else if (line == "/load")
+				weyve_get_server_info(client);
+			else if (line.rfind("/peek ", 0) == 0)
+				SendPeekCommand(client, line.substr(6));
+			else if (line.rfind("/check ", 0) == 0)
+				SendCheckCommand(client, line.substr(7));
+			else if (line == "/unpeek")
+				weyve_stop_room_preview(client);
+			else if (line.rfind("/find ", 0) == 0)
+				weyve_search_users(client, line.substr(6).c_str());
+			else if (line == "/mydata")
+				weyve_export_my_data(client);
+			else if (line.rfind("/team ", 0) == 0)
+				SendTeamCommand(client, line.substr(6));
+			else if (line.rfind("/unteam ", 0) == 0)
+				weyve_remove_team(client, ParseId(line.substr(8)));
+			else if (line.rfind("/assign ", 0) == 0)
+				SendAssignCommand(client, line.substr(8));
+			else if (line == "/balance")
+				weyve_balance_teams(client);
+			else if (line == "/teams")
+				PrintTeams(client);
+			else if (line.rfind("/channel ", 0) == 0)
+				weyve_create_channel(client, line.substr(9).c_str());
+			else if (line.rfind("/unchannel ", 0) == 0)
+				weyve_remove_channel(client, ParseId(line.substr(11)));
+			else if (line.rfind("/place ", 0) == 0)
+				SendPlaceCommand(client, line.substr(7));
+			else if (line.rfind("/enter ", 0) == 0)
+				weyve_place_in_channel(client, weyve_id(client), ParseId(line.substr(7)));
+			else if (line.rfind("/c ", 0) == 0)
+				weyve_send_channel_chat(client, line.substr(3).c_str());
+			else if (line == "/channels")
+				PrintChannels(client);
+			else if (line == "/hide")
+				weyve_set_searchable(client, false);
+			else if (line == "/show")
+				weyve_set_searchable(client, true);
+			else if (line.rfind("/knock ", 0) == 0)
+				SendKnockCommand(client, line.substr(7));
+			else if (line.rfind("/approve ", 0) == 0)
+				weyve_respond_join_request(client, ParseId(line.substr(9)), true);
+			else if (line.rfind("/deny ", 0) == 0)
+				weyve_respond_join_request(client, ParseId(line.substr(6)), false);
+			else if (line == "/quit")
+				quitting = true;
 			else if (line == "/leave")
 				weyve_leave_room(client);
 			else if (line.rfind("/setme ", 0) == 0)
 				SendSetCommand(client, line.substr(7), true);
 			else if (line.rfind("/delme ", 0) == 0)
 				weyve_delete_member_data(client, line.substr(7).c_str());
+			else if (line.rfind("/setfor ", 0) == 0)
+				SendSetForCommand(client, line.substr(8));
 			else if (line.rfind("/set ", 0) == 0)
 				SendSetCommand(client, line.substr(5), false);
 			else if (line.rfind("/del ", 0) == 0)
@@ -258,41 +1310,202 @@ static int RunChat(WeyveClient* client, const std::string& code, const std::stri
 				weyve_set_room_password(client, "");
 			else if (line.rfind("/pass ", 0) == 0)
 				weyve_set_room_password(client, line.substr(6).c_str());
+			else if (line == "/schedules")
+				weyve_list_scheduled_rooms(client);
+			else if (line.rfind("/schedule ", 0) == 0)
+				SendScheduleCommand(client, line.substr(10));
+			else if (line.rfind("/attendees ", 0) == 0)
+				weyve_list_attendees(client, ParseId(line.substr(11)));
+			else if (line.rfind("/unschedule ", 0) == 0)
+				weyve_cancel_scheduled_room(client, ParseId(line.substr(12)));
+			else if (line.rfind("/rsvp ", 0) == 0)
+				SendRsvpCommand(client, line.substr(6));
+			else if (line.rfind("/afk ", 0) == 0)
+				SendIdleCommand(client, line.substr(5));
+			else if (line.rfind("/succession ", 0) == 0)
+				SendSuccessionCommand(client, line.substr(12));
+			else if (line.rfind("/cap ", 0) == 0)
+				SendCapacityCommand(client, line.substr(5));
 			else if (line.rfind("/kick ", 0) == 0)
 				weyve_kick_member(client, ParseId(line.substr(6)));
 			else if (line.rfind("/ban ", 0) == 0)
 				weyve_ban_member(client, ParseId(line.substr(5)));
 			else if (line.rfind("/host ", 0) == 0)
 				weyve_transfer_host(client, ParseId(line.substr(6)));
+			else if (line.rfind("/split ", 0) == 0) {
+				std::vector<uint32_t> members = ParseIds(line.substr(7));
+				weyve_split_room(client, members.data(), (uint32_t)members.size());
+			}
+			else if (line.rfind("/merge ", 0) == 0)
+				weyve_merge_room(client, line.substr(7).c_str());
+			else if (line.rfind("/acceptmerge ", 0) == 0)
+				weyve_answer_merge(client, line.substr(13).c_str(), true);
+			else if (line.rfind("/declinemerge ", 0) == 0)
+				weyve_answer_merge(client, line.substr(14).c_str(), false);
+			else if (line.rfind("/role ", 0) == 0)
+				SendRoleCommand(client, line.substr(6));
+			else if (line.rfind("/w ", 0) == 0)
+				SendWhisperCommand(client, line.substr(3));
+			else if (line.rfind("/dnd ", 0) == 0)
+				SendDndCommand(client, line.substr(5));
+			else if (line.rfind("/retry ", 0) == 0)
+				weyve_retry_message(client, ParseId(line.substr(7)));
+			else if (line.rfind("/discard ", 0) == 0)
+				weyve_discard_message(client, ParseId(line.substr(9)));
+			else if (line.rfind("/export ", 0) == 0)
+				SendExportCommand(client, line.substr(8));
+			else if (line.rfind("/search ", 0) == 0)
+				SendSearchCommand(client, line.substr(8));
+			else if (line.rfind("/guest ", 0) == 0)
+				weyve_guest_login(client, line.substr(7).c_str());
+			else if (line.rfind("/register ", 0) == 0)
+				SendAccountCommand(client, line.substr(10), true);
+			else if (line.rfind("/login ", 0) == 0)
+				SendAccountCommand(client, line.substr(7), false);
+			else if (line.rfind("/upload ", 0) == 0)
+				SendUploadCommand(client, line.substr(8));
+			else if (line == "/banner")
+				weyve_set_room_banner(client, "");
+			else if (line.rfind("/banner ", 0) == 0)
+				weyve_set_room_banner(client, line.substr(8).c_str());
+			else if (line == "/rules")
+				weyve_set_room_rules(client, "");
+			else if (line.rfind("/rules ", 0) == 0)
+				weyve_set_room_rules(client, line.substr(7).c_str());
+			else if (line.rfind("/download ", 0) == 0)
+				weyve_download_blob(client, line.substr(10).c_str());
+			else if (line.rfind("/whispers ", 0) == 0)
+				SendWhispersCommand(client, line.substr(10));
+			else if (line.rfind("/redial ", 0) == 0)
+				weyve_retry_peer_connection(client, ParseId(line.substr(8)));
+			else if (line.rfind("/hangup ", 0) == 0)
+				weyve_close_peer_connection(client, ParseId(line.substr(8)), true);
+			else if (line == "/background")
+				weyve_set_background_mode(client, true);
+			else if (line == "/foreground")
+				weyve_set_background_mode(client, false);
+			else if (line == "/profile low")
+				weyve_set_performance_profile(client, WEYVE_PERFORMANCE_LOW);
+			else if (line == "/profile balanced")
+				weyve_set_performance_profile(client, WEYVE_PERFORMANCE_BALANCED);
+			else if (line == "/profile high")
+				weyve_set_performance_profile(client, WEYVE_PERFORMANCE_HIGH);
+			else if (line == "/pause")
+				weyve_pause_match(client);
+			else if (line == "/resume")
+				weyve_resume_match(client);
+			else if (line.rfind("/countdown ", 0) == 0)
+				weyve_start_countdown(client, ParseId(line.substr(11)));
+			else if (line.rfind("/server ", 0) == 0)
+				LaunchServerCommand(client, line.substr(8));
+			else if (line.rfind("/rcon ", 0) == 0) {
+				if (!weyve_send_console_command(client, line.substr(6).c_str()))
+					std::cout << "* no game server of yours is running\n";
+			}
+			else if (line == "/stopserver")
+				weyve_stop_game_server(client);
+			else if (line.rfind("/portmap ", 0) == 0)
+				weyve_set_port_mapping(client, (uint16_t)ParseId(line.substr(9)));
+			else if (line == "/iface" || line.rfind("/iface ", 0) == 0)
+				InterfaceCommand(client, line.size() > 7 ? line.substr(7) : "");
+			else if (line.rfind("/path ", 0) == 0)
+				PrintLinkPath(client, ParseId(line.substr(6)));
+			else if (line == "/ordered" || line.rfind("/ordered ", 0) == 0) {
+				weyve_set_p2p_ordering(client, true, line.size() > 9 ? ParseId(line.substr(9)) : 30);
+				std::cout << "* p2p in order, gaps waited on for " << weyve_jitter_delay_ms(client) << " ms\n";
+			}
+			else if (line == "/raw")
+				weyve_set_p2p_ordering(client, false, weyve_jitter_delay_ms(client));
+			else if (line == "/fec on" || line == "/fec off")
+				weyve_set_p2p_fec(client, line == "/fec on");
+			else if (line.rfind("/loss ", 0) == 0) {
+				uint32_t id = ParseId(line.substr(6));
+				std::cout << "* " << weyve_peer_loss_permille(client, id) / 10.0 << "% lost from " << MemberLabel(client, id)
+					<< ", parity to them every " << weyve_peer_fec_group(client, id) << " (0 = off)\n";
+			}
+			else if (line.rfind("/simulate ", 0) == 0)
+				SimulateCommand(client, line.substr(10));
+			else if (line.rfind("/record ", 0) == 0) {
+				if (!(weyve_capabilities() & WEYVE_CAPABILITY_RECORDING))
+					std::cout << "* this library cannot record (needs a WEYVE_RECORDING build)\n";
+				else if (!weyve_start_recording(client, line.substr(8).c_str()))
+					std::cout << "* cannot record to " << line.substr(8) << "\n";
+			}
+			else if (line == "/stoprecord")
+				weyve_stop_recording(client);
+			else if (line == "/logs" || line.rfind("/logs ", 0) == 0)
+				PrintLogs(client, line.size() > 6 ? line.substr(6) : "");
+			else if (line.rfind("/loglevel ", 0) == 0) {
+				WeyveLogLevel level;
+				if (ParseLogLevel(line.substr(10), level))
+					weyve_set_log_level(level);
+				else
+					std::cout << "usage: /loglevel debug|info|warn|error\n";
+			}
+			else if (line == "/health")
+				PrintSessionHealth(client);
+			else if (line == "/health server")
+				weyve_request_session_health(client);
+			else if (line == "/receipts")
+				weyve_get_join_receipts(client);
+			else if (line == "/health share" || line == "/health private" || line == "/health off")
+				weyve_set_health_reporting(client, line != "/health off", line == "/health share");
+			else if (line.rfind("/linktimeout ", 0) == 0) {
+				weyve_set_link_timeout(client, ParseId(line.substr(13)));
+				std::cout << "* links get " << weyve_link_timeout(client) << "s to connect\n";
+			}
+			else if (line == "/netchange") { // what a mobile app calls from its network callback
+				if (!weyve_network_changed(client))
+					std::cout << "* server unreachable, still on the old connection\n";
+			}
 			else if (line.rfind("/p2p ", 0) == 0)
 				SendP2PCommand(client, line.substr(5));
 			else
 				weyve_send_chat(client, line.c_str());
 		}
 
-		std::this_thread::sleep_for(std::chrono::milliseconds(1));
+		std::this_thread::sleep_for(std::chrono::milliseconds(weyve_poll_interval(client)));
 	}
 
-	std::cout << "Connection closed\n";
+	std::cout << (quitting ? "Quitting\n" : "Connection closed\n");
 	return 0;
 }
 
 int main(int argc, char* argv[])
 {
 	std::cout << std::unitbuf; // flush every print so redirected output is live
+	std::signal(SIGINT, OnInterrupt);
 
 	std::string mode = argc > 1 ? argv[1] : "";
-	if (mode != "ping" && mode != "chat") {
+	if ((mode != "ping" && mode != "chat" && mode != "watch" && mode != "resume") || (mode == "watch" && argc < 3)) {
 		std::cout << "usage: clientexample ping        heartbeat/rtt demo\n";
 		std::cout << "       clientexample chat        create a room and chat in it\n";
 		std::cout << "       clientexample chat CODE [PASSWORD]   join a room and chat in it\n";
+		std::cout << "       clientexample watch CODE [PASSWORD]  join a room as a spectator\n";
+		std::cout << "       clientexample resume      rejoin the room the last chat was in\n";
+		std::cout << "library: API " << weyve_api_version() << ", protocol " << weyve_protocol_version() << (weyve_capabilities() & WEYVE_CAPABILITY_RECORDING ? ", recording" : "") << "\n";
 		return 1;
 	}
 
+	if (mode == "resume") {
+		WeyveClient* client = weyve_client_create();
+		weyve_set_app_version(client, example_version);
+		if (!ResumeSession(client)) {
+			std::cout << "No session to resume (or its server is down)\n";
+			weyve_client_destroy(client);
+			return 1;
+		}
+
+		int result = RunChat(client);
+		weyve_client_destroy(client);
+		return result;
+	}
+
 	const char* host = "127.0.0.1";
 	uint16_t port = 5555;
 
 	WeyveClient* client = weyve_client_create();
+	weyve_set_app_version(client, example_version);
 	if (!weyve_connect(client, host, port)) {
 		std::cout << "Connect to " << host << ":" << port << " failed\n";
 		weyve_client_destroy(client);
@@ -301,7 +1514,22 @@ int main(int argc, char* argv[])
 
 	std::cout << "Connected to " << host << ":" << port << "\n";
 
-	int result = mode == "ping" ? RunPing(client) : RunChat(client, argc > 2 ? argv[2] : "", argc > 3 ? argv[3] : "");
+	int result = 0;
+	if (mode == "ping") {
+		result = RunPing(client);
+	}
+	else {
+		std::string code = argc > 2 ? argv[2] : "", password = argc > 3 ? argv[3] : "";
+		if (code.empty())
+			weyve_create_room(client);
+		else if (mode == "watch")
+			weyve_spectate_room(client, code.c_str(), password.c_str());
+		else
+			weyve_join_room(client, code.c_str(), password.c_str());
+		if (mode == "chat")
+			wanted_room = code, wanted_password = password;
+		result = RunChat(client);
+	}
 
 	weyve_client_destroy(client);
 	return result;
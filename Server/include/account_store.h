@@ -0,0 +1,68 @@
+#pragma once
+
+#include <cstdint>
+#include <map>
+#include <string>
+#include <vector>
+
+#include "protocol.h"
+
+namespace Weyvelength {
+
+	struct Account {
+		std::string name; // as registered; lookups ignore case
+		std::string email; // empty = none
+		std::string hash; // argon2id in libsodium's crypto_pwhash_str encoding, salt and limits included
+		bool hidden = false; // left out of user searches
+		Proto::NotificationPreferences prefs; // restored on every login
+	};
+
+	// The account half of the storage layer: one flat file, one account per
+	// line, rewritten whole on every change. Fine for the handful of writes
+	// a registration-only store sees.
+	struct AccountStore {
+		bool Open(const std::string& path); // a missing file is an empty store; false if it exists but cannot be read
+		bool IsOpen() const;
+
+		const Account* Find(const std::string& name) const; // case-insensitive
+		bool Add(Account account); // false if the name is taken or the file could not be written
+		bool SetHidden(const std::string& name, bool hidden); // false if there is no such account or the file could not be written
+		bool SetPreferences(const std::string& name, const Proto::NotificationPreferences& prefs); // the same
+		bool Remove(const std::string& name); // for deletion requests; false if there is no such account or the file could not be written
+		std::vector<const Account*> Search(const std::string& query) const; // accounts not hidden whose names match, unordered
+
+	private:
+		bool Save() const;
+
+		std::string _path; // empty = closed
+		std::map<std::string, Account> _accounts; // keyed by the lowercased name
+	};
+
+	constexpr uint64_t guest_lease = 10 * 60; // seconds a guest's name stays held after it disconnects
+
+	struct GuestLease {
+		uint32_t holder = 0; // the connection using the name; 0 = released
+		uint64_t expires = 0; // unix seconds a released name frees up
+	};
+
+	// The guest half: names like "Alice#4821", handed out without an account
+	// and kept only in memory. A name is held while its guest is connected
+	// and for guest_lease after, so the next guest to ask cannot pass for
+	// one who just left.
+	struct GuestRegistry {
+		std::string Claim(const std::string& name, uint32_t holder, uint64_t now, const AccountStore& accounts); // the suffixed name; empty if no suffix is free
+		void Release(uint32_t holder, uint64_t now); // starts the lease running out
+		void Forget(uint32_t holder); // the name became an account, which holds it from now on
+
+	private:
+		std::map<std::string, GuestLease> _leases; // keyed by the lowercased name
+	};
+
+	// How a name matches a search: 0 = starts with the query, 1 = contains it,
+	// -1 = does not. Case is ignored, ASCII only, like account lookups.
+	int NameMatch(const std::string& name, const std::string& query);
+
+	// Both are deliberately slow (that is the point of argon2); keep them off the io thread.
+	std::string HashPassword(const std::string& password); // empty on failure
+	bool VerifyPassword(const std::string& hash, const std::string& password);
+}
@@ -0,0 +1,31 @@
+#pragma once
+
+#include <chrono>
+#include <cstddef>
+#include <cstdint>
+#include <filesystem>
+#include <set>
+#include <span>
+#include <string>
+#include <vector>
+
+namespace Weyvelength {
+
+	// The blob half of the storage layer: one file per blob, named by its
+	// content id, in one directory. Nothing else is kept on disk; which blobs
+	// are still wanted is decided by the caller at Collect time.
+	struct BlobStore {
+		bool Open(const std::string& path); // creates the directory if missing
+		bool IsOpen() const;
+
+		std::string Put(std::span<const std::byte> data); // the content id; empty if it could not be written. Storing a blob again restarts its grace
+		bool Get(const std::string& id, std::vector<std::byte>& out) const; // false if not stored
+		bool Has(const std::string& id) const;
+		size_t Collect(const std::set<std::string>& referenced, std::chrono::seconds grace); // drops unreferenced blobs stored longer ago than grace; returns how many
+		uint64_t Bytes() const; // every blob on disk together
+
+	private:
+		std::filesystem::path _dir; // empty = closed
+		uint64_t _bytes = 0; // summed on Open, then kept by Put and Collect
+	};
+}
@@ -0,0 +1,75 @@
+#pragma once
+
+#include <chrono>
+#include <cstdint>
+#include <functional>
+#include <map>
+#include <memory>
+#include <optional>
+#include <string>
+#include <vector>
+
+#include <thirdparty\asio\asio.hpp>
+#ifndef WEYVE_NO_LINK_PREVIEWS
+#include <thirdparty\asio\asio\ssl.hpp>
+#endif
+
+#include "protocol.h"
+
+namespace Weyvelength {
+
+	struct LinkPreviewConfig {
+		std::vector<std::string> hosts; // the allowlist; a host also covers its subdomains. empty = previews off
+		std::string ca_file; // PEM bundle sites are verified against; empty = the system's default paths
+	};
+
+	constexpr size_t max_link_preview_fetch = 256 * 1024; // bytes read per page, headers included
+	constexpr size_t max_link_previews_cached = 512;
+	constexpr uint32_t max_link_preview_redirects = 3;
+	constexpr std::chrono::seconds link_preview_timeout{ 5 }; // per page, connect to last byte
+	constexpr std::chrono::minutes link_preview_ttl{ 60 };
+	constexpr std::chrono::minutes link_preview_miss_ttl{ 10 }; // pages without a card are not retried sooner
+
+	struct PreviewUrl {
+		std::string host; // lowercased
+		std::string port; // "443" unless the url says otherwise
+		std::string path; // never empty; no fragment
+	};
+
+	// Fetches OpenGraph cards for links posted in chat, so members can see one
+	// without their own machines touching the URL. Opt-in and narrow on
+	// purpose: https only, allowlisted hosts only (redirects included), a
+	// capped read with a deadline, and every result, misses too, cached a
+	// while so a link pasted ten times is fetched once. A build with
+	// WEYVE_NO_LINK_PREVIEWS has none, and no OpenSSL: Init refuses hosts.
+	struct LinkPreviewer {
+		using Done = std::function<void(const Proto::LinkPreview&)>;
+
+		bool Init(asio::io_context& context, const LinkPreviewConfig& config); // false if TLS could not be set up
+		bool Enabled() const;
+
+		std::string FindLink(const std::string& text) const; // the first allowlisted https link; empty if none
+		void Preview(const std::string& url, Done done); // done runs on the io thread, only if the page had a card; maybe before this returns
+
+	private:
+		struct Cached {
+			std::optional<Proto::LinkPreview> preview; // empty = the page had no card, or the fetch failed
+			std::chrono::steady_clock::time_point expires;
+		};
+
+		asio::awaitable<void> Fetch(std::string url);
+		asio::awaitable<std::optional<Proto::LinkPreview>> Load(const std::string& url);
+		asio::awaitable<std::string> Get(const PreviewUrl& url); // the raw response, capped; empty on failure
+
+		bool Allowed(const std::string& host) const;
+		void Remember(const std::string& url, std::optional<Proto::LinkPreview> preview);
+
+		asio::io_context* _context = nullptr;
+#ifndef WEYVE_NO_LINK_PREVIEWS
+		std::unique_ptr<asio::ssl::context> _tls;
+#endif
+		LinkPreviewConfig _config;
+		std::map<std::string, Cached> _cache; // by url
+		std::map<std::string, std::vector<Done>> _pending; // fetches in flight, with whoever is waiting on each
+	};
+}
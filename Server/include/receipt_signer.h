@@ -0,0 +1,24 @@
+#pragma once
+
+#include <array>
+#include <string>
+#include <string_view>
+
+namespace Weyvelength {
+
+	// The server's signing key for join receipts: an ed25519 seed in one
+	// file, made on first use. Without a file the key lives for one run, and
+	// receipts from before a restart no longer match the key it hands out.
+	struct ReceiptSigner {
+		bool Open(const std::string& path); // empty = a key for this run only; false if the file exists but is not a key, or cannot be made
+		bool IsOpen() const;
+
+		std::string PublicKey() const; // hex
+		std::string Sign(std::string_view text) const; // hex detached signature
+
+	private:
+		bool _open = false;
+		std::array<unsigned char, 32> _public{};
+		std::array<unsigned char, 64> _secret{};
+	};
+}
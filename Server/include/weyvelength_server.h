@@ -10,28 +10,133 @@
 #include <deque>
 #include <map>
 #include <memory>
+#include <optional>
+#include <set>
 #include <string>
 #include <unordered_map>
 #include <vector>
 
 #include <thirdparty\asio\asio.hpp>
 
+#include "account_store.h"
+#include "blob_store.h"
+#include "link_preview.h"
 #include "protocol.h"
+#include "receipt_signer.h"
 
 namespace Weyvelength {
 
+	enum class RoomCodeStyle : uint8_t {
+		Letters, // room_code_length characters, none of them easily misread (no 0/O, 1/I)
+		Words, // "BLUE-TIGER-42": two words and a number, easy to read out over voice chat
+	};
+
+	constexpr uint32_t min_room_code_length = 4; // Letters only; shorter runs out of codes
+	constexpr uint32_t max_room_code_length = 16;
+
+	// Fault injection, for exercising reconnects and signaling races on
+	// purpose. Rates are permille; all 0, the default, turns it off, and no
+	// real deployment should do otherwise.
+	struct ChaosConfig {
+		uint32_t drop_permille = 0; // P2PSignals the relay loses
+		uint32_t duplicate_permille = 0; // P2PSignals relayed twice
+		uint32_t delay_permille = 0; // P2PSignals held back, so later ones can overtake them
+		uint32_t delay_ms = 0; // the most one is held; each draws its own
+		uint32_t reset_permille = 0; // per connection, every second: torn down with a TCP reset
+
+		bool Active() const { return drop_permille || duplicate_permille || (delay_permille && delay_ms) || reset_permille; }
+	};
+
+	// Signal limits are counted over a window this long, so joining a full
+	// room, which opens a link to every member at once, fits in one burst.
+	constexpr std::chrono::seconds signal_window{ 10 };
+	constexpr std::chrono::seconds lookup_window{ 10 }; // and lookups over one of these
+	constexpr std::chrono::seconds password_window{ 60 }; // and CreateAccount and Login, which cost an argon2 run each, over one of these
+	constexpr std::chrono::hours upload_window{ 1 }; // and blob uploads over one of these: as long as an upload nothing refers to stays on disk
+
 	struct ServerConfig {
-		uint16_t port = 0;
-		uint32_t room_code_length = 0; // 0 = use the default (8)
+		uint16_t port = 0; // 0 = any free one; Server::Port says which
+		std::string bind_address; // IPv4 or IPv6, e.g. "127.0.0.1" for an embedded server only its host talks to; empty = every IPv4 interface
+		uint16_t signal_port = 0; // a second listener, on the same address, for p2p signals only (SignalEndpoint); 0 = none, signals share the main connection
+		RoomCodeStyle room_code_style = RoomCodeStyle::Letters;
+		uint32_t room_code_length = 0; // Letters only, clamped to the limits above; 0 = use the default (8)
 		Proto::IceServers ice; // stun/turn handed to every client on connect
+		std::string accounts_path; // the account store's file; empty = no accounts, every account request answers Disabled
+		uint32_t max_chat_zero_width = 0; // zero-width characters one chat line may carry; 0 = no limit
+		LinkPreviewConfig link_previews; // off unless it lists hosts
+		std::string blobs_path; // the blob store's directory; empty = no blobs, every blob request answers Disabled
+		uint32_t max_blob_megabytes = 1024; // the blob store's size on disk; uploads that would take it past are refused with BlobError Quota until collection frees room. 0 = no limit
+		std::string receipt_key_path; // the join receipt signing key's file, made if missing; empty = a new key every run
+		uint32_t max_users = 0; // connections at once; 0 = no limit
+		uint32_t max_rooms = 0; // checked on CreateRoom; booked rooms open regardless. 0 = no limit
+		uint32_t max_per_address = 0; // connections from one IP address; 0 = no limit
+		uint32_t max_queued_bytes = 4 * 1024 * 1024; // one connection's unsent backlog before it is dropped for not reading; 0 = no limit
+		uint32_t max_silence = 0; // seconds a connection may send nothing, probe answers included, before it is dropped; 0 = never. Clients from before probes do not answer them
+		uint32_t max_signals = 4000; // P2PSignals one connection may send per signal_window before it is dropped; 0 = no limit
+		uint32_t max_signal_bytes = 1024 * 1024; // their payloads, summed over the same window; 0 = no limit
+		uint32_t max_lookups = 50; // lists and searches one connection may ask for per lookup_window; past it they are refused with ServerFull Requests. 0 = no limit
+		uint32_t max_password_attempts = 5; // CreateAccount and Login one connection, and one address, may send per password_window; refused the same way past it. 0 = no limit
+		uint32_t max_upload_bytes = 8 * 1024 * 1024; // blob bytes one connection may upload per upload_window; past it uploads are refused with BlobError Quota. 0 = no limit
+		std::string min_client_version; // apps reporting an older version in ClientInfo are refused with ClientOutdated; empty = any. Apps that report none are let in
+		std::string update_url; // sent with it, and with every ServerInfo; empty = none
+		ChaosConfig chaos; // off unless set
+	};
+
+	using SharedFrame = std::shared_ptr<const std::vector<std::byte>>; // one broadcast's bytes, queued to every member without copies
+
+	struct BlobUpload {
+		uint32_t size = 0; // as declared by UploadBlob
+		std::vector<std::byte> data;
+	};
+
+	struct AttemptWindow {
+		std::chrono::steady_clock::time_point start; // when the current window began
+		uint32_t count = 0; // taken in it
 	};
 
 	struct Connection {
 		uint32_t id = 0;
+		asio::ip::address address; // the remote end, for max_per_address
 		std::string room; // empty = not in a room
+		std::string name; // the account name once logged in; empty = anonymous
+		bool guest = false; // name is leased from the guest registry, not an account
+		Proto::NotificationPreferences prefs; // an account's are loaded on login; anyone else's go with the connection
+		std::chrono::steady_clock::time_point connected = std::chrono::steady_clock::now(); // for SuccessionPolicy::LongestConnected
+		std::chrono::steady_clock::time_point last_active = std::chrono::steady_clock::now(); // any message but Heartbeat
+		std::chrono::steady_clock::time_point last_heard = std::chrono::steady_clock::now(); // any message at all, for max_silence
+		std::chrono::steady_clock::time_point last_probe; // the last liveness probe sent it
+		bool afk = false; // flagged in the current room; cleared on activity or leaving
+		std::map<uint32_t, BlobUpload> uploads; // in flight, by the client's transfer id
+		std::chrono::steady_clock::time_point upload_window_start; // when the current upload_window began
+		uint64_t uploaded = 0; // bytes declared by the uploads taken in it, for max_upload_bytes
+		bool background = false; // minimized or suspended: room state broadcasts are held back
+		bool missed = false; // some were, since going to the background
+		uint64_t probation = 0; // unix seconds; let in on a reserved seat, it must log in and rejoin the room holding its seat by then. 0 = none
+		bool hangup = false; // WriteLoop closes the socket once out drains
+		uint64_t key = 0; // from an Idempotent, for the message that follows it; 0 = none
+		uint64_t key_scope = 0; // from KeyScope; scopes its keys past this connection. 0 = none
+		std::string watching; // a room previewed with watch set; empty = none
+		std::chrono::steady_clock::time_point watch_until;
+		SharedFrame watched; // the last preview sent for it, so an unchanged one is not sent again
+		std::string queued; // the room whose line it waits in, or holds an offered seat of; empty = none
+		bool searchable = true; // found by SearchUsers; an account's choice is loaded on login
+		uint64_t exported = 0; // unix seconds of the last ExportMyData; 0 = never
+		std::chrono::steady_clock::time_point last_health; // the last ReportHealth taken, for health_report_min_gap
+		uint64_t signal_seq = 0; // the last SequencedSignal relayed from it; 0 = none
+		std::chrono::steady_clock::time_point signal_window_start; // when the current signal_window began
+		uint32_t signals = 0; // sent in it, for max_signals
+		size_t signal_bytes = 0; // and their payloads, for max_signal_bytes
+		std::chrono::steady_clock::time_point lookup_window_start; // when the current lookup_window began
+		uint32_t lookups = 0; // answered in it, for max_lookups
+		AttemptWindow password_attempts; // over password_window, for max_password_attempts
+		uint64_t signal_token = 0; // from its SignalEndpoint; 0 = no signal_port
+		std::shared_ptr<Connection> signal_link; // attached from signal_port; null = signals go over this connection
+		bool signaling = false; // this is a signal_port connection; id is the main connection's once attached, 0 before
+		Proto::ClientInfo client; // as it reported itself; empty from a client that did not
 		asio::ip::tcp::socket socket;
 
-		std::deque<std::vector<std::byte>> out; // outbound queue; WriteLoop is the sole writer
+		std::deque<SharedFrame> out; // outbound queue; WriteLoop is the sole writer
+		size_t backlog = 0; // bytes in out and in the write under way; a shared frame counts in full for every queue holding it
 		asio::steady_timer wake; // cancel() signals "out has work"
 		bool closing = false;
 
@@ -51,47 +156,251 @@ namespace Weyvelength {
 		bool open = true; // joinable right now?
 		std::string password; // empty = none; checked on join, never sent to clients
 		std::vector<uint32_t> banned_members; // ids barred from joining; checked on join
+		std::set<std::string> invited_accounts; // accounts let in past the password on any connection: a booked room's owner and attendees
+		std::vector<uint32_t> invited; // ids let in past the password: a booked room's owner and attendees, approved knocks, a keyed CreateRoom's sender come back
+		std::vector<Proto::JoinRequested> knocks; // pending, oldest first; dropped when answered or the knocker disconnects
+		std::vector<uint32_t> queue; // waiting for a player seat, first in line first
+		std::map<uint32_t, uint64_t> offers; // seats held for whoever left the front of the queue: id -> unix seconds it lapses
+		std::map<std::string, uint64_t> reserved; // seats held for accounts whose connection dropped: name -> unix seconds it lapses. Taken against max_players and max_users alike
+		std::map<uint32_t, Proto::MemberRole> roles; // non-Member roles only; never the host's, dropped when the member leaves
+		std::vector<uint32_t> spectators; // members who joined to watch; a subset of members
+		uint32_t max_players = 0; // 0 = no limit; checked on join
+		uint32_t max_spectators = 0; // 0 = no limit; checked on spectate
+		uint32_t afk_after = 0; // seconds idle before a member is flagged AFK; 0 = never
+		bool afk_spectate = false; // AFK players are also moved to the spectators
+		std::string banner; // a blob id; empty = none
+		std::string rules; // opaque to the server; empty = none
+		uint32_t rules_version = 0; // bumped per change; 0 = never set
+		Proto::CountdownStarted countdown; // the last one started; over once start_at has passed
+		bool paused = false;
+		uint32_t paused_by = 0;
+		uint64_t expires = 0; // unix seconds; a booked room nobody has joined yet is dropped then. 0 = never
+		std::deque<Proto::TimelineEntry> timeline; // the last max_timeline_entries, oldest first
+		uint32_t timeline_seq = 0; // the last entry's seq
+		std::vector<Proto::Team> teams; // in creation order; players only, each on one team at most
+		uint32_t next_team = 1;
+		std::map<uint32_t, std::pair<Proto::MemberHealth, uint64_t>> health; // each member's last ReportHealth and when, unix seconds; dropped when they leave
+		std::deque<Proto::JoinReceipt> receipts; // the last max_join_receipts, oldest first
+		std::string host_state; // the host's last SetHostState, handed to whoever hosts next; opaque
+		uint64_t host_state_saved = 0; // unix seconds; 0 = none
+		std::set<uint32_t> awaiting_host_state; // members sent HostStateWanted since the last hand-off; each may ReportHostState once
+		Proto::SuccessionPolicy succession{};
+		std::vector<uint32_t> successors; // the host's list, for SuccessionPolicy::Successors
+		std::vector<Proto::Channel> channels; // in creation order; each member in one at most
+		uint32_t next_channel = 1;
+		std::map<std::string, uint32_t> merge_offers; // rooms whose host asked to take this one in -> that host; dropped when answered
+	};
+
+	// Rooms are found by RoomCodeKey, so a code typed in lowercase, with
+	// spaces, or without its dashes still finds its room; each room keeps
+	// its id as generated.
+	struct RoomCodeHash {
+		size_t operator()(const std::string& code) const { return std::hash<std::string>{}(Proto::RoomCodeKey(code)); }
 	};
 
+	struct RoomCodeEqual {
+		bool operator()(const std::string& a, const std::string& b) const { return Proto::RoomCodeKey(a) == Proto::RoomCodeKey(b); }
+	};
+
+	struct Booking { // a scheduled room that has not opened yet
+		Proto::ScheduledRoom info; // but owner, which Server::Info fills in as it is sent
+		std::string owner; // who booked it, as a BookingHolder
+		std::string password; // carried over to the room when it opens
+		asio::ip::address address; // the owner's, for max_owner_bookings
+		std::map<std::string, Proto::RsvpStatus> rsvps; // by BookingHolder; never No
+		bool reminded = false;
+	};
+
+	struct RecentKey { // an Idempotent key handled lately, from one sender
+		uint64_t expires = 0; // unix seconds
+		std::string room; // the room a keyed CreateRoom made; empty otherwise
+	};
+
+	struct HealthTotals { // shared ReportHealth since the last log line; no ids kept
+		uint64_t reports = 0;
+		uint64_t links = 0;
+		uint64_t direct = 0;
+		uint64_t relayed = 0;
+		uint64_t loss_permille = 0; // summed over links with a reading
+		uint64_t lossy_links = 0; // links with a reading
+		uint64_t server_rtt = 0; // summed over reports that measured one
+		uint64_t rtt_reports = 0;
+	};
+
+	// The lobby server whole, for main.cpp and for games and tests that
+	// embed it: Init binds, Run serves on the calling thread until Stop, which
+	// any thread may call. Logs go through spdlog's default logger, so the
+	// embedding app decides where they land.
 	struct Server {
-		bool Init(ServerConfig& config);
+		bool Init(const ServerConfig& config);
+		uint16_t Port() const; // the one bound; 0 before Init succeeds
 		void Run();
 		void Stop();
 
 	private:
 		void SendTo(uint32_t id, const Proto::ServerMessage& msg);
 		void SendToMany(const std::vector<uint32_t>& ids, const Proto::ServerMessage& msg);
-		void SendFrame(uint32_t id, std::vector<std::byte> frame);
+		void SendFrame(uint32_t id, SharedFrame frame);
+		void QueueFrame(const std::shared_ptr<Connection>& conn, SharedFrame frame); // SendFrame's half once the connection is found
+		void SendSignal(uint32_t to, const Proto::ServerMessage& msg); // over to's signal link when it has one, else as SendTo
+		void Drop(Connection& conn); // closes it now, unsent frames and all, like a dropped network
 
-		void HandleMessage(std::shared_ptr<Connection> conn, const Proto::ServerMessage& msg);
+		void HandleMessage(std::shared_ptr<Connection> conn, Proto::ServerMessage&& msg); // handlers may take what they forward
+		void HandleSignalLink(const std::shared_ptr<Connection>& link, Proto::ServerMessage&& msg); // HandleMessage's, for signal_port connections
 		void HandleCreateRoom(const std::shared_ptr<Connection>& conn);
 		void HandleJoinRoom(const std::shared_ptr<Connection>& conn, const Proto::JoinRoom& msg);
+		void HandleSpectateRoom(const std::shared_ptr<Connection>& conn, const Proto::SpectateRoom& msg);
 		void HandleLeaveRoom(const std::shared_ptr<Connection>& conn);
 		void HandleRoomChat(const std::shared_ptr<Connection>& conn, const Proto::RoomChat& msg);
-		void HandleP2PSignal(const std::shared_ptr<Connection>& conn, const Proto::P2PSignal& msg);
+		void HandleRoomWhisper(const std::shared_ptr<Connection>& conn, const Proto::RoomWhisper& msg);
+		void HandleP2PSignal(const std::shared_ptr<Connection>& conn, Proto::P2PSignal&& msg, uint64_t seq = 0); // seq 0 = a plain P2PSignal
 		void HandleSetRoomData(const std::shared_ptr<Connection>& conn, const Proto::SetRoomData& msg);
 		void HandleSetMemberData(const std::shared_ptr<Connection>& conn, const Proto::SetMemberData& msg);
+		void HandleSetMemberDataFor(const std::shared_ptr<Connection>& conn, const Proto::SetMemberDataFor& msg);
+		void WriteMemberData(const std::shared_ptr<Connection>& conn, Room& room, uint32_t id, const std::string& key, const std::string& value); // SetMemberData for either sender
 		void HandleKickMember(const std::shared_ptr<Connection>& conn, const Proto::KickMember& msg);
 		void HandleBanMember(const std::shared_ptr<Connection>& conn, const Proto::BanMember& msg);
 		void HandleTransferHost(const std::shared_ptr<Connection>& conn, const Proto::TransferHost& msg);
 		void HandleSetRoomJoinable(const std::shared_ptr<Connection>& conn, const Proto::SetRoomJoinable& msg);
 		void HandleSetRoomPassword(const std::shared_ptr<Connection>& conn, const Proto::SetRoomPassword& msg);
+		void HandleSetMemberRole(const std::shared_ptr<Connection>& conn, const Proto::SetMemberRole& msg);
+		void HandleSetPreferences(const std::shared_ptr<Connection>& conn, const Proto::SetPreferences& msg);
+		void HandleSetRoomCapacity(const std::shared_ptr<Connection>& conn, const Proto::SetRoomCapacity& msg);
+		void HandleSetIdlePolicy(const std::shared_ptr<Connection>& conn, const Proto::SetIdlePolicy& msg);
+		void HandleSetRoomBanner(const std::shared_ptr<Connection>& conn, const Proto::SetRoomBanner& msg);
+		void HandleSetRoomRules(const std::shared_ptr<Connection>& conn, const Proto::SetRoomRules& msg);
+		void HandleStartCountdown(const std::shared_ptr<Connection>& conn, const Proto::StartCountdown& msg);
+		void HandleSetMatchPaused(const std::shared_ptr<Connection>& conn, const Proto::SetMatchPaused& msg);
+		void HandleScheduleRoom(const std::shared_ptr<Connection>& conn, const Proto::ScheduleRoom& msg);
+		void HandleListScheduledRooms(const std::shared_ptr<Connection>& conn);
+		void HandleRsvpScheduledRoom(const std::shared_ptr<Connection>& conn, const Proto::RsvpScheduledRoom& msg);
+		void HandleListAttendees(const std::shared_ptr<Connection>& conn, const Proto::ListAttendees& msg);
+		void HandleCancelScheduledRoom(const std::shared_ptr<Connection>& conn, const Proto::CancelScheduledRoom& msg);
+		void HandleCreateAccount(const std::shared_ptr<Connection>& conn, const Proto::CreateAccount& msg);
+		void HandleLogin(const std::shared_ptr<Connection>& conn, const Proto::Login& msg);
+		void HandleGuestLogin(const std::shared_ptr<Connection>& conn, const Proto::GuestLogin& msg);
+		void HandleUploadBlob(const std::shared_ptr<Connection>& conn, const Proto::UploadBlob& msg);
+		void HandleBlobChunk(const std::shared_ptr<Connection>& conn, const Proto::BlobChunk& msg);
+		bool BlobStoreFits(uint64_t bytes) const; // under max_blob_megabytes with bytes more
+		void HandleDownloadBlob(const std::shared_ptr<Connection>& conn, const Proto::DownloadBlob& msg);
+		void HandleSetBackground(const std::shared_ptr<Connection>& conn, const Proto::SetBackground& msg);
+		void HandleGetServerInfo(const std::shared_ptr<Connection>& conn);
+		bool TakeLookup(const std::shared_ptr<Connection>& conn); // false, refused with ServerFull, past max_lookups
+		bool TakePasswordAttempt(const std::shared_ptr<Connection>& conn); // false, refused with ServerFull, past max_password_attempts
+		void HandleClientInfo(const std::shared_ptr<Connection>& conn, Proto::ClientInfo msg);
+		void HandleGetRoomPreview(const std::shared_ptr<Connection>& conn, const Proto::GetRoomPreview& msg);
+		void HandleRequestJoin(const std::shared_ptr<Connection>& conn, const Proto::RequestJoin& msg);
+		void HandleRespondJoinRequest(const std::shared_ptr<Connection>& conn, const Proto::RespondJoinRequest& msg);
+		void HandleQueueForRoom(const std::shared_ptr<Connection>& conn, const Proto::QueueForRoom& msg);
+		void HandleSetSearchable(const std::shared_ptr<Connection>& conn, const Proto::SetSearchable& msg);
+		void HandleSearchUsers(const std::shared_ptr<Connection>& conn, const Proto::SearchUsers& msg);
+		void HandleExportMyData(const std::shared_ptr<Connection>& conn);
+		void HandleGetTimeline(const std::shared_ptr<Connection>& conn, const Proto::GetTimeline& msg);
+		void HandleCreateTeam(const std::shared_ptr<Connection>& conn, const Proto::CreateTeam& msg);
+		void HandleRemoveTeam(const std::shared_ptr<Connection>& conn, const Proto::RemoveTeam& msg);
+		void HandleAssignTeam(const std::shared_ptr<Connection>& conn, const Proto::AssignTeam& msg);
+		void HandleBalanceTeams(const std::shared_ptr<Connection>& conn);
+		void HandleReportHealth(const std::shared_ptr<Connection>& conn, const Proto::ReportHealth& msg);
+		void HandleGetSessionHealth(const std::shared_ptr<Connection>& conn);
+		void HandleGetJoinReceipts(const std::shared_ptr<Connection>& conn);
+		void HandleSetHostState(const std::shared_ptr<Connection>& conn, Proto::SetHostState&& msg);
+		void HandleReportHostState(const std::shared_ptr<Connection>& conn, Proto::ReportHostState&& msg);
+		void HandleSetSuccession(const std::shared_ptr<Connection>& conn, Proto::SetSuccession&& msg);
+		void HandleCreateChannel(const std::shared_ptr<Connection>& conn, const Proto::CreateChannel& msg);
+		void HandleRemoveChannel(const std::shared_ptr<Connection>& conn, const Proto::RemoveChannel& msg);
+		void HandlePlaceInChannel(const std::shared_ptr<Connection>& conn, const Proto::PlaceInChannel& msg);
+		void HandleChannelChat(const std::shared_ptr<Connection>& conn, const Proto::ChannelChat& msg);
+		void HandleSplitRoom(const std::shared_ptr<Connection>& conn, const Proto::SplitRoom& msg);
+		void HandleMergeRoom(const std::shared_ptr<Connection>& conn, const Proto::MergeRoom& msg);
+		void HandleAnswerMerge(const std::shared_ptr<Connection>& conn, const Proto::AnswerMerge& msg);
+		bool Replayed(const std::shared_ptr<Connection>& conn, uint64_t key, const Proto::ServerMessage& msg); // true if this sender's key was seen, after answering a repeated CreateRoom
+
+		bool CleanChat(const std::shared_ptr<Connection>& conn, std::string& text); // SanitizeChat, sending the error when it refuses
+		bool AccountsUsable(const std::shared_ptr<Connection>& conn); // store open and conn anonymous or a guest, else sends the error
+		void LogIn(const std::shared_ptr<Connection>& conn, const std::string& name, bool guest = false); // binds the name and announces it
+
+		void MarkActive(const std::shared_ptr<Connection>& conn);
+		void SweepIdle(); // flags members idle past their room's afk_after
+		void SweepSchedules(); // sends reminders, opens due bookings, drops booked rooms nobody joined
+		void SweepBlobs(); // collects blobs no room refers to any more
+		void CancelBooking(std::map<uint32_t, Booking>::iterator it); // tells the owner and every attendee still connected
+		void ForgetBookings(const Connection& conn); // a closing connection's RSVPs, and its bookings, unless an account holds them
+		void AdoptBookings(const Connection& conn); // on login to an account: what the connection held becomes the account's
+		void RsvpsChanged(Booking& booking); // recounts going and tells the owner
+		uint32_t HolderId(const std::string& holder) const; // the connection a BookingHolder is on; 0 = none
+		Proto::ScheduledRoom Info(const Booking& booking) const;
+		Proto::ScheduledRoomAttendees Attendees(const Booking& booking) const;
+		bool Invited(const Room& room, uint32_t id) const; // let past the room's password, by id or by account
+		std::string UniqueRoomCode();
 
-		void LeaveRoom(const std::shared_ptr<Connection>& conn);
+		void EnterRoom(const std::shared_ptr<Connection>& conn, const std::string& id, const std::string& password, bool spectating); // join and spectate share one path
+		void AddMember(const std::shared_ptr<Connection>& conn, Room& room, bool spectating); // EnterRoom past its checks
+		void MoveMember(const std::shared_ptr<Connection>& conn, Room& to, uint32_t by); // out of its room and into to, in the same seat kind; the caller checked to has room
+		void SendRoomState(const std::shared_ptr<Connection>& conn, const Room& room); // the whole room as events, AssignRoomId first
+		void LeaveRoom(const std::shared_ptr<Connection>& conn, Proto::TimelineKind why = Proto::TimelineKind::Left, uint32_t by = 0); // why and by are for the timeline
 		Room* HostRoom(const std::shared_ptr<Connection>& conn); // the sender's room if they host it, else null after sending the error
+		Room* RankedRoom(const std::shared_ptr<Connection>& conn, Proto::MemberRole least); // same, for anyone holding at least this role
+		uint32_t NextHost(const Room& room) const; // by the room's succession policy; 0 = nobody left
+		void SetHost(Room& room, uint32_t id, uint32_t by = 0, bool dropped = false); // hands host status and state over and announces it; by = who handed it over, 0 = the old host left, or dropped
+		void AddToTimeline(Room& room, Proto::TimelineKind kind, uint32_t id, uint32_t by = 0); // kept, and sent to every member
+		void IssueReceipt(Room& room, const std::shared_ptr<Connection>& conn, Proto::ReceiptKind kind); // signs and keeps one; conn still in its seat
+
+		std::optional<Proto::ServerLimit> AdmissionLimit(const asio::ip::address& address, bool& reserved_only) const; // the cap a new connection from there would break, if any
+		uint32_t ReservedSeats() const;
+		void ReserveSeat(const std::shared_ptr<Connection>& conn); // the connection dropped mid-room; hold its seat for reconnect_grace
+		void SweepReservations(); // expires held seats and idempotency keys, and hangs up on probation that ran out
+		void HangUp(const std::shared_ptr<Connection>& conn, const Proto::ServerMessage& last); // sends last, then closes
+
+		Proto::RoomPreview Preview(const Room& room, uint32_t viewer) const;
+		void SweepPreviews(); // resends watched previews that changed, and ends watches that ran out
+
+		void DropFromQueue(const std::shared_ptr<Connection>& conn); // out of its line, and any seat offered to it
+		void CloseQueue(Room& room); // the room is going: everyone waiting hears position 0
+		void SendQueuePositions(const Room& room);
+		void SweepQueues(); // lapses stale offers and offers free seats to the front of each line
+		void SweepHealth(); // logs the shared health totals every health_log_interval, then starts them over
+		void SweepChaos(); // resets connections at the chaos rate
+		void SweepMemory(); // logs what the rooms and queues hold every memory_log_interval
+		void SweepSilent(); // probes connections gone quiet, and drops those past max_silence
+		void SweepAttempts(); // forgets address budgets whose password_window ran out
+		void LogClients() const; // and, with it, the connected clients by platform and version
+		size_t Footprint(const Room& room) const; // approximate bytes the room and its members' queues hold
+		void RelayChaotic(uint32_t to, const Proto::ServerMessage& relayed); // HandleP2PSignal's send, dropped, doubled or delayed at the chaos rates
+		asio::awaitable<void> SendLater(uint32_t to, Proto::ServerMessage msg, std::chrono::milliseconds delay);
 
 		asio::awaitable<void> AcceptLoop();
+		asio::awaitable<void> SignalAcceptLoop();
+		asio::awaitable<void> SignalSession(std::shared_ptr<Connection> link);
+		asio::awaitable<void> Refuse(asio::ip::tcp::socket socket, Proto::ServerLimit limit); // ServerFull, then close
+		asio::awaitable<void> TickLoop();
 		asio::awaitable<void> Session(std::shared_ptr<Connection> conn);
 		asio::awaitable<void> ReadLoop(std::shared_ptr<Connection> conn);
 		asio::awaitable<void> WriteLoop(std::shared_ptr<Connection> conn);
 
 		asio::io_context _context;
 		asio::ip::tcp::acceptor _acceptor{ _context };
+		asio::ip::tcp::acceptor _signal_acceptor{ _context }; // open only with a signal_port
 
 		std::unordered_map<uint32_t, std::shared_ptr<Connection>> _connections;
-		std::unordered_map<std::string, Room> _rooms;
+		std::unordered_map<std::string, Room, RoomCodeHash, RoomCodeEqual> _rooms;
+		std::map<uint32_t, Booking> _bookings;
+		std::map<std::pair<std::string, uint64_t>, RecentKey> _recent_keys; // by sender (see KeySender), then key
+		AccountStore _accounts;
+		GuestRegistry _guests;
+		LinkPreviewer _previews;
+		BlobStore _blobs;
+		ReceiptSigner _receipts;
+		std::chrono::steady_clock::time_point _next_blob_sweep;
+		HealthTotals _health;
+		std::chrono::steady_clock::time_point _next_health_log;
+		std::chrono::steady_clock::time_point _next_memory_log;
+		uint64_t _lookups = 0; // answered since the last memory log
+		uint64_t _lookups_refused = 0; // and refused past max_lookups
+		std::map<asio::ip::address, AttemptWindow> _password_attempts; // per address, so reconnecting does not start the budget over
+		asio::thread_pool _hashing{ 1 }; // argon2 runs here, so a login never stalls the io thread
 
 		uint32_t _next_id = 1;   // 0 reserved as "none"
+		uint32_t _next_booking = 1;
 
 		ServerConfig _config;
 	};
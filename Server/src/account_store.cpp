@@ -0,0 +1,224 @@
+#include "account_store.h"
+
+#include <cctype>
+#include <filesystem>
+#include <fstream>
+#include <random>
+#include <sstream>
+
+#include <sodium.h>
+#include <spdlog/spdlog.h>
+
+#include "protocol.h"
+
+namespace Weyvelength {
+
+	static std::string Lower(const std::string& name)
+	{
+		std::string key = name;
+		for (char& c : key)
+			c = (char)std::tolower((unsigned char)c);
+		return key;
+	}
+
+	// Lines are "name\temail\thash\tflags\tdnd_until\twhispers", flags being
+	// "hidden" for an account left out of searches or else empty; older files
+	// stop after the hash or the flags, and get the default preferences.
+	// Names and hashes never hold a tab; the server rejects emails that do.
+	bool AccountStore::Open(const std::string& path)
+	{
+		_accounts.clear();
+		_path = path;
+
+		std::ifstream file(path);
+		if (!file) {
+			std::ofstream create(path, std::ios::app); // probe: can we write here at all?
+			if (!create) {
+				_path.clear();
+				return false;
+			}
+			return true;
+		}
+
+		std::string line;
+		while (std::getline(file, line)) {
+			std::istringstream fields(line);
+			Account account;
+			if (!std::getline(fields, account.name, '\t') || !std::getline(fields, account.email, '\t') || !std::getline(fields, account.hash, '\t'))
+				continue; // a torn last line from a crash mid-write; skip it
+
+			std::string flags;
+			std::getline(fields, flags, '\t');
+			account.hidden = flags == "hidden";
+
+			uint64_t dnd_until = 0;
+			uint32_t whispers = 0;
+			if (fields >> dnd_until >> whispers && whispers <= (uint32_t)Proto::WhisperPolicy::Nobody)
+				account.prefs = { dnd_until, (Proto::WhisperPolicy)whispers };
+			_accounts.emplace(Lower(account.name), std::move(account));
+		}
+
+		spdlog::info("Loaded {} accounts from {}", _accounts.size(), path);
+		return true;
+	}
+
+	bool AccountStore::IsOpen() const
+	{
+		return !_path.empty();
+	}
+
+	const Account* AccountStore::Find(const std::string& name) const
+	{
+		auto it = _accounts.find(Lower(name));
+		return it == _accounts.end() ? nullptr : &it->second;
+	}
+
+	bool AccountStore::Add(Account account)
+	{
+		std::string key = Lower(account.name);
+		if (_accounts.contains(key))
+			return false;
+
+		_accounts.emplace(key, std::move(account));
+		if (!Save()) {
+			_accounts.erase(key);
+			return false;
+		}
+		return true;
+	}
+
+	bool AccountStore::SetHidden(const std::string& name, bool hidden)
+	{
+		auto it = _accounts.find(Lower(name));
+		if (it == _accounts.end())
+			return false;
+
+		bool was = it->second.hidden;
+		it->second.hidden = hidden;
+		if (!Save()) {
+			it->second.hidden = was;
+			return false;
+		}
+		return true;
+	}
+
+	bool AccountStore::SetPreferences(const std::string& name, const Proto::NotificationPreferences& prefs)
+	{
+		auto it = _accounts.find(Lower(name));
+		if (it == _accounts.end())
+			return false;
+
+		if (it->second.prefs == prefs)
+			return true; // a client resending what it has, as on every reconnect; no need to rewrite the file
+
+		Proto::NotificationPreferences was = it->second.prefs;
+		it->second.prefs = prefs;
+		if (!Save()) {
+			it->second.prefs = was;
+			return false;
+		}
+		return true;
+	}
+
+	bool AccountStore::Remove(const std::string& name)
+	{
+		auto it = _accounts.find(Lower(name));
+		if (it == _accounts.end())
+			return false;
+
+		Account removed = std::move(it->second);
+		_accounts.erase(it);
+		if (!Save()) {
+			_accounts.emplace(Lower(removed.name), std::move(removed));
+			return false;
+		}
+		return true;
+	}
+
+	std::vector<const Account*> AccountStore::Search(const std::string& query) const
+	{
+		std::vector<const Account*> found;
+		for (const auto& [key, account] : _accounts) {
+			if (!account.hidden && NameMatch(account.name, query) >= 0)
+				found.push_back(&account);
+		}
+		return found;
+	}
+
+	// Writes a sibling file and renames it over the old one, so a crash
+	// leaves either the old store or the new one, never half of each.
+	bool AccountStore::Save() const
+	{
+		std::string tmp = _path + ".tmp";
+		{
+			std::ofstream file(tmp, std::ios::trunc);
+			if (!file)
+				return false;
+
+			for (const auto& [key, account] : _accounts) {
+				file << account.name << '\t' << account.email << '\t' << account.hash << '\t' << (account.hidden ? "hidden" : "")
+					<< '\t' << account.prefs.dnd_until << '\t' << (uint32_t)account.prefs.whispers << '\n';
+			}
+			if (!file.flush())
+				return false;
+		}
+
+		std::error_code ec;
+		std::filesystem::rename(tmp, _path, ec); // replaces the target, on Windows too
+		return !ec;
+	}
+
+	std::string GuestRegistry::Claim(const std::string& name, uint32_t holder, uint64_t now, const AccountStore& accounts)
+	{
+		std::erase_if(_leases, [&](const auto& entry) { return entry.second.holder == 0 && entry.second.expires <= now; });
+
+		static std::mt19937 rng{ std::random_device{}() };
+		std::uniform_int_distribution<uint32_t> pick{ 1000, 9999 };
+
+		// random, not sequential, so a suffix says nothing about how many came before
+		for (int attempt = 0; attempt < 32; attempt++) {
+			std::string guest = name + Proto::guest_separator + std::to_string(pick(rng));
+			std::string key = Lower(guest);
+			if (_leases.contains(key) || accounts.Find(guest))
+				continue;
+
+			_leases.emplace(key, GuestLease{ holder });
+			return guest;
+		}
+		return {}; // that name is crowded; the guest can pick another
+	}
+
+	void GuestRegistry::Release(uint32_t holder, uint64_t now)
+	{
+		for (auto& [key, lease] : _leases) {
+			if (lease.holder == holder)
+				lease = { 0, now + guest_lease };
+		}
+	}
+
+	void GuestRegistry::Forget(uint32_t holder)
+	{
+		std::erase_if(_leases, [&](const auto& entry) { return entry.second.holder == holder; });
+	}
+
+	int NameMatch(const std::string& name, const std::string& query)
+	{
+		size_t at = Lower(name).find(Lower(query));
+		if (at == std::string::npos)
+			return -1;
+		return at == 0 ? 0 : 1;
+	}
+
+	std::string HashPassword(const std::string& password)
+	{
+		char hash[crypto_pwhash_STRBYTES];
+		if (crypto_pwhash_str(hash, password.data(), password.size(), crypto_pwhash_OPSLIMIT_INTERACTIVE, crypto_pwhash_MEMLIMIT_INTERACTIVE) != 0)
+			return {};
+		return hash;
+	}
+
+	bool VerifyPassword(const std::string& hash, const std::string& password)
+	{
+		return crypto_pwhash_str_verify(hash.c_str(), password.data(), password.size()) == 0;
+	}
+}
@@ -0,0 +1,121 @@
+#include "blob_store.h"
+
+#include <algorithm>
+#include <fstream>
+#include <iterator>
+
+#include <sodium.h>
+#include <spdlog/spdlog.h>
+
+#include "protocol.h"
+
+namespace Weyvelength {
+
+	bool BlobStore::Open(const std::string& path)
+	{
+		std::error_code ec;
+		std::filesystem::create_directories(path, ec);
+		if (ec || !std::filesystem::is_directory(path, ec)) {
+			_dir.clear();
+			return false;
+		}
+
+		_dir = path;
+		_bytes = 0;
+		for (const auto& entry : std::filesystem::directory_iterator(_dir, ec)) {
+			std::error_code file_ec;
+			if (Proto::ValidBlobId(entry.path().filename().string()))
+				_bytes += entry.file_size(file_ec);
+		}
+		return true;
+	}
+
+	bool BlobStore::IsOpen() const
+	{
+		return !_dir.empty();
+	}
+
+	std::string BlobStore::Put(std::span<const std::byte> data)
+	{
+		unsigned char hash[32];
+		crypto_generichash(hash, sizeof(hash), (const unsigned char*)data.data(), data.size(), nullptr, 0);
+
+		char hex[sizeof(hash) * 2 + 1];
+		sodium_bin2hex(hex, sizeof(hex), hash, sizeof(hash));
+		std::string id = hex;
+
+		std::error_code ec;
+		std::filesystem::path path = _dir / id;
+		if (std::filesystem::exists(path, ec)) {
+			std::filesystem::last_write_time(path, std::filesystem::file_time_type::clock::now(), ec);
+			return id; // same bytes, same file; only the clock moves
+		}
+
+		// a sibling file renamed into place, so a reader never sees half a blob
+		std::filesystem::path tmp = path;
+		tmp += ".tmp";
+		{
+			std::ofstream file(tmp, std::ios::binary | std::ios::trunc);
+			if (!file || !file.write((const char*)data.data(), data.size()) || !file.flush())
+				return {};
+		}
+
+		std::filesystem::rename(tmp, path, ec);
+		if (ec)
+			return {};
+		_bytes += data.size();
+		return id;
+	}
+
+	bool BlobStore::Get(const std::string& id, std::vector<std::byte>& out) const
+	{
+		if (!Proto::ValidBlobId(id)) // also keeps ids from naming anything outside the directory
+			return false;
+
+		std::ifstream file(_dir / id, std::ios::binary);
+		if (!file)
+			return false;
+
+		std::vector<char> bytes{ std::istreambuf_iterator<char>(file), std::istreambuf_iterator<char>() };
+		out.assign((const std::byte*)bytes.data(), (const std::byte*)bytes.data() + bytes.size());
+		return true;
+	}
+
+	bool BlobStore::Has(const std::string& id) const
+	{
+		std::error_code ec;
+		return IsOpen() && Proto::ValidBlobId(id) && std::filesystem::is_regular_file(_dir / id, ec);
+	}
+
+	size_t BlobStore::Collect(const std::set<std::string>& referenced, std::chrono::seconds grace)
+	{
+		auto cutoff = std::filesystem::file_time_type::clock::now() - grace;
+		size_t removed = 0;
+
+		std::error_code ec;
+		for (const auto& entry : std::filesystem::directory_iterator(_dir, ec)) {
+			std::string name = entry.path().filename().string();
+			if (!Proto::ValidBlobId(name) || referenced.contains(name))
+				continue; // a .tmp from a crash is left for a human, not guessed at
+
+			std::error_code file_ec;
+			if (entry.last_write_time(file_ec) > cutoff || file_ec)
+				continue;
+
+			uintmax_t size = entry.file_size(file_ec);
+			if (std::filesystem::remove(entry.path(), file_ec)) {
+				_bytes -= std::min<uint64_t>(_bytes, size);
+				removed++;
+			}
+		}
+
+		if (removed)
+			spdlog::info("Collected {} unreferenced blobs", removed);
+		return removed;
+	}
+
+	uint64_t BlobStore::Bytes() const
+	{
+		return _bytes;
+	}
+}
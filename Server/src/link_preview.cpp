@@ -0,0 +1,495 @@
+#include "link_preview.h"
+
+#include <algorithm>
+#include <array>
+#include <cctype>
+#include <charconv>
+#include <memory>
+#include <string_view>
+
+#include <spdlog/spdlog.h>
+
+#include "chat_text.h"
+
+using asio::use_awaitable;
+
+namespace Weyvelength {
+#ifndef WEYVE_NO_LINK_PREVIEWS
+
+	static std::string Lower(std::string_view text)
+	{
+		std::string out{ text };
+		for (char& c : out)
+			c = (char)std::tolower((unsigned char)c);
+		return out;
+	}
+
+	static std::string_view Trim(std::string_view text)
+	{
+		while (!text.empty() && (text.front() == ' ' || text.front() == '\t'))
+			text.remove_prefix(1);
+		while (!text.empty() && (text.back() == ' ' || text.back() == '\t'))
+			text.remove_suffix(1);
+		return text;
+	}
+
+	// Cuts to at most max bytes without splitting a UTF-8 sequence.
+	static void Clip(std::string& text, size_t max)
+	{
+		if (text.size() <= max)
+			return;
+
+		size_t n = max;
+		while (n > 0 && ((unsigned char)text[n] & 0xC0) == 0x80)
+			n--;
+		text.resize(n);
+	}
+
+	// "https://host[:port][/path]". Nothing but https, no userinfo, no IP
+	// literals in brackets, and nothing in the path that could break out of
+	// the request line.
+	static bool ParseUrl(std::string_view url, PreviewUrl& out)
+	{
+		constexpr std::string_view scheme = "https://";
+		if (url.size() > Proto::max_preview_url || Lower(url.substr(0, scheme.size())) != scheme)
+			return false;
+
+		std::string_view rest = url.substr(scheme.size());
+		size_t split = rest.find_first_of("/?#");
+		std::string_view authority = rest.substr(0, split);
+		std::string_view path = split == std::string_view::npos ? std::string_view{} : rest.substr(split);
+
+		out.port = "443";
+		if (size_t colon = authority.rfind(':'); colon != std::string_view::npos) {
+			std::string_view port = authority.substr(colon + 1);
+			if (port.empty() || port.size() > 5 || !std::ranges::all_of(port, [](char c) { return c >= '0' && c <= '9'; }))
+				return false;
+			out.port = port;
+			authority = authority.substr(0, colon);
+		}
+
+		bool host_ok = !authority.empty() && std::ranges::all_of(authority, [](char c) { return std::isalnum((unsigned char)c) || c == '-' || c == '.'; });
+		bool path_ok = std::ranges::none_of(path, [](char c) { return (unsigned char)c <= 0x20 || c == 0x7F; });
+		if (!host_ok || !path_ok)
+			return false;
+
+		out.host = Lower(authority);
+		path = path.substr(0, path.find('#'));
+		out.path = path.empty() || path[0] != '/' ? "/" + std::string(path) : std::string(path);
+		return true;
+	}
+
+	// A redirect's Location against the page that sent it. Relative paths
+	// other than "/..." are rare enough to go without a card.
+	static std::string ResolveLocation(const PreviewUrl& page, std::string_view location)
+	{
+		if (Lower(location.substr(0, 8)) == "https://")
+			return std::string(location);
+		if (location.starts_with("//"))
+			return "https:" + std::string(location);
+		if (location.starts_with("/"))
+			return "https://" + page.host + (page.port == "443" ? "" : ":" + page.port) + std::string(location);
+		return {};
+	}
+
+	struct HttpResponse {
+		int status = 0;
+		std::string location;
+		std::string content_type;
+		std::string_view body;
+	};
+
+	static bool ParseResponse(const std::string& raw, HttpResponse& out)
+	{
+		size_t head_end = raw.find("\r\n\r\n");
+		if (head_end == std::string::npos)
+			return false;
+
+		std::string_view head{ raw.data(), head_end };
+		if (!head.starts_with("HTTP/1.") || head.size() < 12) // "HTTP/1.1 200"
+			return false;
+		if (std::from_chars(head.data() + 9, head.data() + 12, out.status).ec != std::errc{})
+			return false;
+
+		for (size_t line = head.find("\r\n"); line != std::string_view::npos;) {
+			size_t next = head.find("\r\n", line + 2);
+			std::string_view header = head.substr(line + 2, next == std::string_view::npos ? std::string_view::npos : next - line - 2);
+			if (size_t colon = header.find(':'); colon != std::string_view::npos) {
+				std::string name = Lower(header.substr(0, colon));
+				if (name == "location")
+					out.location = Trim(header.substr(colon + 1));
+				else if (name == "content-type")
+					out.content_type = Trim(header.substr(colon + 1));
+			}
+			line = next;
+		}
+
+		out.body = std::string_view(raw).substr(head_end + 4);
+		return true;
+	}
+
+	static void AppendUtf8(std::string& out, char32_t cp)
+	{
+		if (cp < 0x80) {
+			out += (char)cp;
+		}
+		else if (cp < 0x800) {
+			out += (char)(0xC0 | (cp >> 6));
+			out += (char)(0x80 | (cp & 0x3F));
+		}
+		else if (cp < 0x10000) {
+			out += (char)(0xE0 | (cp >> 12));
+			out += (char)(0x80 | ((cp >> 6) & 0x3F));
+			out += (char)(0x80 | (cp & 0x3F));
+		}
+		else {
+			out += (char)(0xF0 | (cp >> 18));
+			out += (char)(0x80 | ((cp >> 12) & 0x3F));
+			out += (char)(0x80 | ((cp >> 6) & 0x3F));
+			out += (char)(0x80 | (cp & 0x3F));
+		}
+	}
+
+	// The handful of entities titles actually use, plus numeric ones.
+	static std::string DecodeEntities(std::string_view text)
+	{
+		static constexpr std::pair<std::string_view, char> named[] = {
+			{ "amp", '&' }, { "lt", '<' }, { "gt", '>' }, { "quot", '"' }, { "apos", '\'' }, { "nbsp", ' ' },
+		};
+
+		std::string out;
+		out.reserve(text.size());
+		for (size_t i = 0; i < text.size(); i++) {
+			size_t semi = text[i] == '&' ? text.find(';', i) : std::string_view::npos;
+			if (semi == std::string_view::npos || semi - i > 10) {
+				out += text[i];
+				continue;
+			}
+
+			std::string_view entity = text.substr(i + 1, semi - i - 1);
+			if (entity.starts_with('#')) {
+				bool hex = entity.size() > 1 && (entity[1] == 'x' || entity[1] == 'X');
+				const char* last = entity.data() + entity.size();
+				uint32_t cp = 0;
+				auto [end, ec] = std::from_chars(entity.data() + (hex ? 2 : 1), last, cp, hex ? 16 : 10);
+				if (ec == std::errc{} && end == last && cp > 0 && cp <= 0x10FFFF && (cp < 0xD800 || cp > 0xDFFF)) {
+					AppendUtf8(out, cp);
+					i = semi;
+					continue;
+				}
+			}
+			else if (auto it = std::ranges::find(named, entity, &std::pair<std::string_view, char>::first); it != std::end(named)) {
+				out += it->second;
+				i = semi;
+				continue;
+			}
+			out += text[i]; // not an entity we know; keep the '&' as typed
+		}
+		return out;
+	}
+
+	// A card's text shown as one line: chat's own cleaning (no control or
+	// bidi characters), then cut to the field's cap. Empty if it cannot be saved.
+	static std::string CleanField(std::string_view raw, size_t max)
+	{
+		std::string text = DecodeEntities(raw);
+		Clip(text, Proto::max_chat_bytes);
+		std::ranges::replace_if(text, [](char c) { return c == '\n' || c == '\r' || c == '\t'; }, ' ');
+		if (Proto::SanitizeChat(text) != Proto::ChatTextIssue::None)
+			return {};
+
+		Clip(text, max);
+		return text;
+	}
+
+	static std::string CleanImage(std::string_view raw, const PreviewUrl& page)
+	{
+		std::string image = ResolveLocation(page, DecodeEntities(Trim(raw)));
+		PreviewUrl parsed;
+		return ParseUrl(image, parsed) ? image : std::string{};
+	}
+
+	// The value of name="..." inside the tag spanning [start, end) of html;
+	// lower is html lowercased, which is where the name is looked for.
+	static std::string_view Attribute(std::string_view html, std::string_view lower, size_t start, size_t end, std::string_view name)
+	{
+		for (size_t at = lower.find(name, start); at < end; at = lower.find(name, at + 1)) {
+			if (!std::isspace((unsigned char)lower[at - 1]))
+				continue; // the tail of some other attribute's name
+
+			size_t i = at + name.size();
+			while (i < end && std::isspace((unsigned char)html[i]))
+				i++;
+			if (i >= end || html[i] != '=')
+				continue;
+			i++;
+			while (i < end && std::isspace((unsigned char)html[i]))
+				i++;
+			if (i >= end)
+				return {};
+
+			if (html[i] == '"' || html[i] == '\'') {
+				size_t close = html.find(html[i], i + 1);
+				return close < end ? html.substr(i + 1, close - i - 1) : std::string_view{};
+			}
+			size_t stop = i;
+			while (stop < end && !std::isspace((unsigned char)html[stop]))
+				stop++;
+			return html.substr(i, stop - i);
+		}
+		return {};
+	}
+
+	// og:title and friends from the page's <head>, with <title> and the plain
+	// description meta as fallbacks. No title, no card.
+	static std::optional<Proto::LinkPreview> ReadCard(std::string_view html, const PreviewUrl& page)
+	{
+		std::string lower = Lower(html);
+		size_t head_end = std::min(lower.find("</head"), lower.size());
+
+		std::string_view title, description, image, site, plain_description;
+		for (size_t at = lower.find("<meta"); at < head_end; at = lower.find("<meta", at + 5)) {
+			size_t end = lower.find('>', at);
+			if (end == std::string::npos)
+				break;
+
+			std::string_view key = Attribute(html, lower, at, end, "property");
+			if (key.empty())
+				key = Attribute(html, lower, at, end, "name");
+			std::string_view content = Attribute(html, lower, at, end, "content");
+
+			std::string kind = Lower(key);
+			if (kind == "og:title")
+				title = content;
+			else if (kind == "og:description")
+				description = content;
+			else if (kind == "og:image")
+				image = content;
+			else if (kind == "og:site_name")
+				site = content;
+			else if (kind == "description")
+				plain_description = content;
+		}
+
+		if (title.empty()) {
+			size_t tag = lower.find("<title");
+			size_t open = tag < head_end ? lower.find('>', tag) : std::string::npos;
+			size_t close = open != std::string::npos ? lower.find("</title", open) : std::string::npos;
+			if (close != std::string::npos)
+				title = html.substr(open + 1, close - open - 1);
+		}
+
+		Proto::LinkPreview card;
+		card.title = CleanField(title, Proto::max_preview_title);
+		if (card.title.empty())
+			return std::nullopt;
+
+		card.description = CleanField(description.empty() ? plain_description : description, Proto::max_preview_description);
+		card.image = CleanImage(image, page);
+		card.site = CleanField(site, Proto::max_preview_site);
+		return card;
+	}
+
+	bool LinkPreviewer::Init(asio::io_context& context, const LinkPreviewConfig& config)
+	{
+		_context = &context;
+		_config = config;
+		if (_config.hosts.empty())
+			return true; // off; nothing to set up
+
+		asio::error_code ec;
+		_tls = std::make_unique<asio::ssl::context>(asio::ssl::context::tls_client);
+		_tls->set_verify_mode(asio::ssl::verify_peer, ec);
+		if (!ec) {
+			if (_config.ca_file.empty())
+				_tls->set_default_verify_paths(ec);
+			else
+				_tls->load_verify_file(_config.ca_file, ec);
+		}
+
+		if (ec) {
+			spdlog::error("Link previews: TLS setup failed: {}", ec.message());
+			_tls.reset();
+			return false;
+		}
+
+		spdlog::info("Link previews on for {} hosts", _config.hosts.size());
+		return true;
+	}
+
+	bool LinkPreviewer::Enabled() const
+	{
+		return _tls != nullptr;
+	}
+
+	std::string LinkPreviewer::FindLink(const std::string& text) const
+	{
+		for (size_t at = text.find("https://"); at != std::string::npos; at = text.find("https://", at + 1)) {
+			size_t end = text.find_first_of(" \t\n<>\"", at);
+			std::string_view link{ text.data() + at, (end == std::string::npos ? text.size() : end) - at };
+			while (!link.empty() && std::string_view{ ".,;:!?)]}'" }.find(link.back()) != std::string_view::npos)
+				link.remove_suffix(1); // sentence punctuation, not part of the link
+
+			PreviewUrl parsed;
+			if (ParseUrl(link, parsed) && Allowed(parsed.host))
+				return std::string(link);
+		}
+		return {};
+	}
+
+	void LinkPreviewer::Preview(const std::string& url, Done done)
+	{
+		if (auto it = _cache.find(url); it != _cache.end() && it->second.expires > std::chrono::steady_clock::now()) {
+			if (it->second.preview)
+				done(*it->second.preview);
+			return;
+		}
+
+		auto [pending, first] = _pending.try_emplace(url);
+		pending->second.push_back(std::move(done));
+		if (first) // later askers ride the fetch already under way
+			asio::co_spawn(*_context, Fetch(url), asio::detached);
+	}
+
+	asio::awaitable<void> LinkPreviewer::Fetch(std::string url)
+	{
+		std::optional<Proto::LinkPreview> preview;
+		try {
+			preview = co_await Load(url);
+		}
+		catch (const std::exception& e) {
+			spdlog::debug("Link preview for {} failed: {}", url, e.what());
+		}
+
+		Remember(url, preview);
+
+		std::vector<Done> waiting = std::move(_pending[url]);
+		_pending.erase(url);
+		if (preview) {
+			for (Done& done : waiting)
+				done(*preview);
+		}
+	}
+
+	asio::awaitable<std::optional<Proto::LinkPreview>> LinkPreviewer::Load(const std::string& url)
+	{
+		std::string target = url;
+		for (uint32_t hop = 0; hop <= max_link_preview_redirects; hop++) {
+			PreviewUrl page;
+			if (!ParseUrl(target, page) || !Allowed(page.host))
+				co_return std::nullopt; // redirects are held to the allowlist too
+
+			std::string raw = co_await Get(page);
+			HttpResponse response;
+			if (!ParseResponse(raw, response))
+				co_return std::nullopt;
+
+			if (response.status >= 300 && response.status < 400 && !response.location.empty()) {
+				target = ResolveLocation(page, response.location);
+				continue;
+			}
+
+			if (response.status != 200 || Lower(response.content_type).find("text/html") == std::string::npos)
+				co_return std::nullopt;
+
+			std::optional<Proto::LinkPreview> card = ReadCard(response.body, page);
+			if (card)
+				card->url = url; // the link as posted, so clients can match it to the chat line
+			co_return card;
+		}
+		co_return std::nullopt; // redirected too often
+	}
+
+	asio::awaitable<std::string> LinkPreviewer::Get(const PreviewUrl& url)
+	{
+		struct Exchange {
+			asio::ip::tcp::resolver resolver;
+			asio::ssl::stream<asio::ip::tcp::socket> stream;
+		};
+		auto exchange = std::make_shared<Exchange>(asio::ip::tcp::resolver{ *_context }, asio::ssl::stream<asio::ip::tcp::socket>{ *_context, *_tls });
+		auto& [resolver, stream] = *exchange;
+
+		// one deadline for the whole exchange; firing it fails whatever is pending.
+		// the handler holds the exchange: an expiry already queued when we
+		// finish still runs, and must find something to close
+		asio::steady_timer deadline{ *_context };
+		deadline.expires_after(link_preview_timeout);
+		deadline.async_wait([exchange](asio::error_code ec) {
+			if (ec)
+				return; // cancelled: the exchange finished
+			exchange->resolver.cancel();
+			exchange->stream.lowest_layer().close(ec);
+		});
+
+		auto endpoints = co_await resolver.async_resolve(url.host, url.port, use_awaitable);
+		co_await asio::async_connect(stream.lowest_layer(), endpoints, use_awaitable);
+
+		SSL_set_tlsext_host_name(stream.native_handle(), url.host.c_str()); // SNI
+		stream.set_verify_callback(asio::ssl::host_name_verification(url.host));
+		co_await stream.async_handshake(asio::ssl::stream_base::client, use_awaitable);
+
+		// HTTP/1.0 on purpose: no chunked bodies to undo, and the server closes when done
+		std::string host = url.port == "443" ? url.host : url.host + ":" + url.port;
+		std::string request = "GET " + url.path + " HTTP/1.0\r\nHost: " + host + "\r\nUser-Agent: Weyvelength-LinkPreview/1\r\nAccept: text/html\r\nConnection: close\r\n\r\n";
+		co_await asio::async_write(stream, asio::buffer(request), use_awaitable);
+
+		std::string response;
+		std::array<char, 4096> chunk;
+		while (response.size() < max_link_preview_fetch) {
+			asio::error_code ec;
+			size_t n = co_await stream.async_read_some(asio::buffer(chunk), asio::redirect_error(use_awaitable, ec));
+			response.append(chunk.data(), n);
+			if (ec)
+				break; // eof, or a site that skips the TLS close; either way the page is in
+			if (Lower(std::string_view(response).substr(response.size() - std::min(response.size(), n + 6))).find("</head") != std::string::npos)
+				break; // the card lives in the head; the body is not worth the bytes
+		}
+		co_return response;
+	}
+
+	bool LinkPreviewer::Allowed(const std::string& host) const
+	{
+		return std::ranges::any_of(_config.hosts, [&](const std::string& entry) {
+			std::string allowed = Lower(entry);
+			if (host == allowed)
+				return true;
+			return host.size() > allowed.size() && host.ends_with(allowed) && host[host.size() - allowed.size() - 1] == '.';
+		});
+	}
+
+	void LinkPreviewer::Remember(const std::string& url, std::optional<Proto::LinkPreview> preview)
+	{
+		auto now = std::chrono::steady_clock::now();
+		std::erase_if(_cache, [&](const auto& entry) { return entry.second.expires <= now; });
+		if (_cache.size() >= max_link_previews_cached)
+			_cache.erase(std::ranges::min_element(_cache, {}, [](const auto& entry) { return entry.second.expires; }));
+
+		auto ttl = preview ? std::chrono::steady_clock::duration(link_preview_ttl) : std::chrono::steady_clock::duration(link_preview_miss_ttl);
+		_cache[url] = { std::move(preview), now + ttl };
+	}
+#else
+	bool LinkPreviewer::Init(asio::io_context& context, const LinkPreviewConfig& config)
+	{
+		_context = &context;
+		_config = config;
+		if (_config.hosts.empty())
+			return true;
+		spdlog::error("Link previews: this server was built without them (WEYVE_NO_LINK_PREVIEWS)");
+		return false;
+	}
+
+	bool LinkPreviewer::Enabled() const
+	{
+		return false;
+	}
+
+	std::string LinkPreviewer::FindLink(const std::string&) const
+	{
+		return {};
+	}
+
+	void LinkPreviewer::Preview(const std::string&, Done)
+	{
+	}
+#endif
+}
@@ -1,10 +1,15 @@
+#include <cstdint>
+#include <cstdlib>
+#include <string>
+#include <vector>
+
 #include <spdlog/async.h>
 #include <spdlog/sinks/stdout_color_sinks.h>
 #include <spdlog/spdlog.h>
 
 #include "weyvelength_server.h"
 
-int main()
+int main(int argc, char* argv[])
 {
 	using namespace Weyvelength;
 
@@ -16,11 +21,100 @@ int main()
 	spdlog::set_level(spdlog::level::debug); // dev server: show the p2p signal traffic
 	spdlog::flush_on(spdlog::level::debug);
 
-	ServerConfig config{ .port = 5555, .ice = { .stun_host = "stun.l.google.com", .stun_port = 19302 } };
+	ServerConfig config{ .port = 5555, .ice = { .stun_host = "stun.l.google.com", .stun_port = 19302 }, .accounts_path = "accounts.tsv", .blobs_path = "blobs", .receipt_key_path = "receipt.key" };
+
+	// --bind ADDRESS: listen on one interface only; every IPv4 one by default
+	// --signal-port N: a second port carrying p2p signals only, for their own firewall and QoS rules
+	// --min-client-version VERSION: refuse apps reporting an older one; --update-url URL tells them where to get it
+	// load caps: --max-users N, --max-rooms N, --max-per-address N. absent or 0 = no limit
+	// --max-queued-bytes N: a connection's unsent backlog before it is dropped; 0 = no limit
+	// --max-silence SECONDS: how long a connection may not even answer a probe before it is dropped; 0 = never
+	// --max-signals N, --max-signal-bytes N: p2p signals, and their payload bytes, a connection
+	// may send per 10 s before it is dropped; 0 = no limit
+	// --max-lookups N: lists and searches a connection may ask for per 10 s; 0 = no limit
+	// --max-password-attempts N: logins and sign-ups a connection, or an address, may send per 60 s; 0 = no limit
+	// room codes: --code-style letters|words, --code-length N (letters only)
+	// fault injection, development only: --chaos-drop, --chaos-duplicate, --chaos-delay and
+	// --chaos-reset take a permille; --chaos-delay-ms N caps each delay
+	std::vector<std::string> purge;
+	uint32_t signal_port = 0;
+	for (int i = 1; i < argc; i += 2) {
+		std::string flag = argv[i];
+		std::string value = i + 1 < argc ? argv[i + 1] : "";
+		if (flag == "--purge-user" && i + 1 < argc) {
+			purge.push_back(value);
+			continue;
+		}
+		if (flag == "--bind" && i + 1 < argc) {
+			config.bind_address = value;
+			continue;
+		}
+		if (flag == "--min-client-version" && i + 1 < argc) {
+			config.min_client_version = value;
+			continue;
+		}
+		if (flag == "--update-url" && i + 1 < argc) {
+			config.update_url = value;
+			continue;
+		}
+		if (flag == "--code-style" && (value == "letters" || value == "words")) {
+			config.room_code_style = value == "words" ? RoomCodeStyle::Words : RoomCodeStyle::Letters;
+			continue;
+		}
+		uint32_t* number = flag == "--max-users" ? &config.max_users
+			: flag == "--max-rooms" ? &config.max_rooms
+			: flag == "--max-per-address" ? &config.max_per_address
+			: flag == "--max-queued-bytes" ? &config.max_queued_bytes
+			: flag == "--max-silence" ? &config.max_silence
+			: flag == "--max-signals" ? &config.max_signals
+			: flag == "--max-signal-bytes" ? &config.max_signal_bytes
+			: flag == "--max-lookups" ? &config.max_lookups
+			: flag == "--max-password-attempts" ? &config.max_password_attempts
+			: flag == "--max-upload-bytes" ? &config.max_upload_bytes
+			: flag == "--max-blob-megabytes" ? &config.max_blob_megabytes
+			: flag == "--signal-port" ? &signal_port
+			: flag == "--code-length" ? &config.room_code_length
+			: flag == "--chaos-drop" ? &config.chaos.drop_permille
+			: flag == "--chaos-duplicate" ? &config.chaos.duplicate_permille
+			: flag == "--chaos-delay" ? &config.chaos.delay_permille
+			: flag == "--chaos-delay-ms" ? &config.chaos.delay_ms
+			: flag == "--chaos-reset" ? &config.chaos.reset_permille
+			: nullptr;
+		if (!number || i + 1 >= argc) {
+			spdlog::error("usage: weyvelength_server [--bind ADDRESS] [--signal-port N] [--min-client-version VERSION] [--update-url URL] [--max-users N] [--max-rooms N] [--max-per-address N] [--max-queued-bytes N] [--max-silence SECONDS] [--max-signals N] [--max-signal-bytes N] [--max-lookups N] [--max-password-attempts N] [--max-upload-bytes N] [--max-blob-megabytes N] [--code-style letters|words] [--code-length N] [--purge-user NAME]... "
+				"[--chaos-drop N] [--chaos-duplicate N] [--chaos-delay N] [--chaos-delay-ms N] [--chaos-reset N]");
+			return 1;
+		}
+		*number = (uint32_t)std::strtoul(value.c_str(), nullptr, 10);
+	}
+	config.signal_port = (uint16_t)signal_port;
+
+	// deletion requests: --purge-user NAME, repeatable, with the server stopped
+	// (a running one would write its own copy of the store back). The account
+	// file is the only store holding anything per user; chat is never kept,
+	// and blobs carry no owner and go once nothing refers to them
+	if (!purge.empty()) {
+		AccountStore accounts;
+		if (!accounts.Open(config.accounts_path)) {
+			spdlog::error("Cannot open the account store {}", config.accounts_path);
+			return 1;
+		}
+		int failed = 0;
+		for (const std::string& name : purge) {
+			if (accounts.Remove(name)) {
+				spdlog::info("Purged account {}", name);
+			}
+			else {
+				spdlog::error("No account {} to purge, or the store could not be written", name);
+				failed = 1;
+			}
+		}
+		return failed;
+	}
 
 	Server server;
 	if (!server.Init(config)) {
-		spdlog::error("Server failed to bind port {}", config.port);
+		spdlog::error("Server failed to listen on port {}", config.port);
 		return 1;
 	}
 
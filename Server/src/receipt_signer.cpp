@@ -0,0 +1,74 @@
+#include "receipt_signer.h"
+
+#include <filesystem>
+#include <fstream>
+
+#include <sodium.h>
+#include <spdlog/spdlog.h>
+
+namespace Weyvelength {
+
+	static std::string Hex(const unsigned char* bytes, size_t size)
+	{
+		std::string hex(size * 2 + 1, '\0');
+		sodium_bin2hex(hex.data(), hex.size(), bytes, size);
+		hex.pop_back();
+		return hex;
+	}
+
+	// The file is the seed as one line of hex. A new one is written to a
+	// sibling and renamed into place, so a crash never leaves half a key.
+	bool ReceiptSigner::Open(const std::string& path)
+	{
+		_open = false;
+		unsigned char seed[crypto_sign_SEEDBYTES];
+		if (path.empty()) {
+			randombytes_buf(seed, sizeof(seed));
+		}
+		else if (std::ifstream file(path); file) {
+			std::string line;
+			std::getline(file, line);
+			size_t size = 0;
+			if (sodium_hex2bin(seed, sizeof(seed), line.data(), line.size(), nullptr, &size, nullptr) != 0 || size != sizeof(seed)) {
+				spdlog::error("Receipt key {} is not a key", path);
+				return false;
+			}
+		}
+		else {
+			randombytes_buf(seed, sizeof(seed));
+			std::string tmp = path + ".tmp";
+			{
+				std::ofstream out(tmp, std::ios::trunc);
+				if (!out || !(out << Hex(seed, sizeof(seed)) << '\n') || !out.flush())
+					return false;
+			}
+			std::error_code ec;
+			std::filesystem::rename(tmp, path, ec);
+			if (ec)
+				return false;
+			spdlog::info("Made a new receipt key in {}", path);
+		}
+
+		crypto_sign_seed_keypair(_public.data(), _secret.data(), seed);
+		sodium_memzero(seed, sizeof(seed));
+		_open = true;
+		return true;
+	}
+
+	bool ReceiptSigner::IsOpen() const
+	{
+		return _open;
+	}
+
+	std::string ReceiptSigner::PublicKey() const
+	{
+		return Hex(_public.data(), _public.size());
+	}
+
+	std::string ReceiptSigner::Sign(std::string_view text) const
+	{
+		unsigned char signature[crypto_sign_BYTES];
+		crypto_sign_detached(signature, nullptr, (const unsigned char*)text.data(), text.size(), _secret.data());
+		return Hex(signature, sizeof(signature));
+	}
+}
@@ -2,8 +2,14 @@
 
 #include <algorithm>
 #include <array>
+#include <chrono>
+#include <cstdlib>
+#include <iterator>
 #include <random>
+#include <ranges>
+#include <set>
 #include <string>
+#include <tuple>
 #include <utility>
 #include <vector>
 
@@ -11,21 +17,61 @@
 #include <thirdparty\asio\asio.hpp>
 #include <thirdparty\zpp_bits\zpp_bits.h>
 
+#include <sodium.h>
+
+#include "chat_text.h"
 #include "framing.h"
+#include "unix_time.h"
 
 using asio::use_awaitable;
 
 namespace Weyvelength {
 
-	static void Enqueue(const std::shared_ptr<Connection>& conn, std::vector<std::byte> frame)
+	static void Enqueue(const std::shared_ptr<Connection>& conn, SharedFrame frame)
 	{
+		conn->backlog += frame->size();
 		conn->out.push_back(std::move(frame));
 		conn->wake.cancel();
 	}
 
-	static std::string MakeRoomCode(uint32_t length)
+	constexpr uint64_t booked_room_grace = 15 * 60; // seconds a booked room waits, empty, for its first join
+	constexpr std::chrono::minutes blob_sweep_interval{ 10 };
+	constexpr std::chrono::hours blob_grace{ 1 }; // an upload's time to get referenced before it can be collected
+	constexpr uint32_t full_retry_after = 30; // seconds, the hint sent with ServerFull
+	constexpr uint64_t reconnect_grace = 60; // seconds a dropped account's seat is held for it
+	constexpr uint64_t reclaim_window = 15; // seconds a connection let in on a held seat gets to log in and rejoin
+	constexpr std::chrono::seconds probe_interval{ 30 }; // of silence before a liveness probe, and between probes
+	constexpr std::chrono::minutes health_log_interval{ 5 };
+	constexpr std::chrono::minutes memory_log_interval{ 5 };
+	constexpr size_t memory_log_rooms = 3; // the largest rooms named in each memory log line
+
+	using Proto::UnixNow;
+	using Proto::UnixNowMs;
+
+	// Short, distinct when spoken, and no two alike in their first three letters.
+	static constexpr std::array<const char*, 64> code_words = {
+		"AMBER", "APPLE", "ARROW", "BADGE", "BEAR", "BLUE", "BOLT", "BRAVE",
+		"CAMEL", "CANDY", "CLOUD", "COBRA", "CORAL", "CROWN", "DAISY", "DELTA",
+		"EAGLE", "EMBER", "FALCON", "FERN", "FLAME", "FROST", "GHOST", "GLOBE",
+		"GOLD", "GRAPE", "HAWK", "HONEY", "IVORY", "JADE", "JELLY", "KITE",
+		"LEMON", "LION", "LUNAR", "MANGO", "MAPLE", "MOOSE", "NOBLE", "OCEAN",
+		"OLIVE", "OTTER", "PANDA", "PEARL", "PIXEL", "PLUM", "QUEEN", "RAVEN",
+		"RIVER", "ROBIN", "SALT", "SHARK", "SILVER", "SOLAR", "STORM", "TIGER",
+		"TOAST", "TULIP", "VELVET", "VIPER", "WALRUS", "WHALE", "YETI", "ZEBRA",
+	};
+
+	// uniform_int_distribution rejects out-of-range draws rather than taking
+	// a modulus, so every symbol is equally likely.
+	static std::string MakeRoomCode(RoomCodeStyle style, uint32_t length)
 	{
 		static std::mt19937 rng{ std::random_device{}() };
+
+		if (style == RoomCodeStyle::Words) {
+			std::uniform_int_distribution<size_t> word{ 0, code_words.size() - 1 };
+			std::uniform_int_distribution<uint32_t> number{ 10, 99 };
+			return std::string(code_words[word(rng)]) + "-" + code_words[word(rng)] + "-" + std::to_string(number(rng));
+		}
+
 		static constexpr char alphabet[] = "ABCDEFGHJKLMNPQRSTUVWXYZ23456789"; // no 0/O/1/I
 		std::uniform_int_distribution<size_t> pick{ 0, sizeof(alphabet) - 2 };
 
@@ -35,35 +81,103 @@ namespace Weyvelength {
 		return code;
 	}
 
-	bool Server::Init(ServerConfig& config)
+	// Chaos mode's dice; nothing else draws from them.
+	static uint32_t Roll(uint32_t below)
+	{
+		static std::minstd_rand rng{ std::random_device{}() };
+		return std::uniform_int_distribution<uint32_t>{ 0, below - 1 }(rng);
+	}
+
+	static bool Chance(uint32_t permille)
+	{
+		return permille != 0 && Roll(1000) < permille;
+	}
+
+	static bool Listen(asio::ip::tcp::acceptor& acceptor, const asio::ip::tcp::endpoint& endpoint)
+	{
+		asio::error_code ec;
+		acceptor.open(endpoint.protocol(), ec);
+		if (ec) return false;
+
+		acceptor.set_option(asio::socket_base::reuse_address(true), ec);
+		if (ec) return false;
+
+		acceptor.bind(endpoint, ec);
+		if (ec) return false;
+
+		acceptor.listen(asio::socket_base::max_listen_connections, ec);
+		return !ec;
+	}
+
+	bool Server::Init(const ServerConfig& config)
 	{
 		_config = config;
 		if (_config.room_code_length == 0)
 			_config.room_code_length = 8;
+		_config.room_code_length = std::clamp(_config.room_code_length, min_room_code_length, max_room_code_length);
 
-		asio::error_code ec;
+		if (const ChaosConfig& chaos = _config.chaos; chaos.Active()) {
+			spdlog::warn("Chaos mode: signals dropped {}/1000, doubled {}/1000, delayed {}/1000 by up to {} ms; connections reset {}/1000 a second",
+				chaos.drop_permille, chaos.duplicate_permille, chaos.delay_permille, chaos.delay_ms, chaos.reset_permille);
+		}
 
-		asio::ip::tcp::endpoint endpoint{ asio::ip::tcp::v4(), config.port };
+		if (sodium_init() < 0)
+			return false;
 
-		_acceptor.open(endpoint.protocol(), ec);
-		if (ec) return false;
+		if (!_config.accounts_path.empty() && !_accounts.Open(_config.accounts_path)) {
+			spdlog::error("Account store {} is unusable", _config.accounts_path);
+			return false;
+		}
 
-		_acceptor.set_option(asio::socket_base::reuse_address(true), ec);
-		if (ec) return false;
+		if (!_config.blobs_path.empty() && !_blobs.Open(_config.blobs_path)) {
+			spdlog::error("Blob store {} is unusable", _config.blobs_path);
+			return false;
+		}
 
-		_acceptor.bind(endpoint, ec);
-		if (ec) return false;
+		if (!_receipts.Open(_config.receipt_key_path)) {
+			spdlog::error("Receipt key {} is unusable", _config.receipt_key_path);
+			return false;
+		}
+		spdlog::info("Join receipts signed by {}{}", _receipts.PublicKey(), _config.receipt_key_path.empty() ? ", for this run only" : "");
 
-		_acceptor.listen(asio::socket_base::max_listen_connections, ec);
-		if (ec) return false;
+		if (!_previews.Init(_context, _config.link_previews))
+			return false;
+
+		asio::error_code ec;
 
-		spdlog::info("Listening on port {}", config.port);
+		asio::ip::address address = asio::ip::address_v4::any();
+		if (!config.bind_address.empty()) {
+			address = asio::ip::make_address(config.bind_address, ec);
+			if (ec) {
+				spdlog::error("Cannot listen on {}: not an address", config.bind_address);
+				return false;
+			}
+		}
+		if (!Listen(_acceptor, { address, config.port }))
+			return false;
+		spdlog::info("Listening on {} port {}", address.to_string(), Port());
+
+		if (config.signal_port != 0) {
+			if (!Listen(_signal_acceptor, { address, config.signal_port }))
+				return false;
+			spdlog::info("Signals on {} port {}", address.to_string(), config.signal_port);
+		}
 		return true;
 	}
 
+	uint16_t Server::Port() const
+	{
+		asio::error_code ec;
+		asio::ip::tcp::endpoint endpoint = _acceptor.local_endpoint(ec);
+		return ec ? 0 : endpoint.port();
+	}
+
 	void Server::Run()
 	{
 		asio::co_spawn(_context, AcceptLoop(), asio::detached);
+		if (_signal_acceptor.is_open())
+			asio::co_spawn(_context, SignalAcceptLoop(), asio::detached);
+		asio::co_spawn(_context, TickLoop(), asio::detached);
 		_context.run();
 	}
 
@@ -77,8 +191,20 @@ namespace Weyvelength {
 		while (true) {
 			asio::ip::tcp::socket socket = co_await _acceptor.async_accept(use_awaitable);
 
+			asio::error_code ec;
+			asio::ip::address address = socket.remote_endpoint(ec).address();
+			bool reserved_only = false;
+			if (auto limit = AdmissionLimit(address, reserved_only)) {
+				spdlog::warn("Refused a connection from {}: server full", address.to_string());
+				asio::co_spawn(_context, Refuse(std::move(socket), *limit), asio::detached);
+				continue;
+			}
+
 			uint32_t id = _next_id++;   // single-threaded io_context: no lock needed
 			auto conn = std::make_shared<Connection>(id, std::move(socket));
+			conn->address = address;
+			if (reserved_only)
+				conn->probation = UnixNow() + reclaim_window;
 			_connections.emplace(id, conn);
 
 			spdlog::info("Client {} connected", id);
@@ -86,12 +212,186 @@ namespace Weyvelength {
 		}
 	}
 
+	// Signal links are not counted against the load caps: each is worthless
+	// until it attaches to a connection that was let in, and is dropped if
+	// its first message does not.
+	asio::awaitable<void> Server::SignalAcceptLoop()
+	{
+		while (true) {
+			asio::ip::tcp::socket socket = co_await _signal_acceptor.async_accept(use_awaitable);
+
+			asio::error_code ec;
+			auto link = std::make_shared<Connection>(0, std::move(socket));
+			link->address = link->socket.remote_endpoint(ec).address();
+			link->signaling = true;
+			asio::co_spawn(_context, SignalSession(link), asio::detached);
+		}
+	}
+
+	asio::awaitable<void> Server::SignalSession(std::shared_ptr<Connection> link)
+	{
+		asio::co_spawn(link->socket.get_executor(), WriteLoop(link), asio::detached);
+
+		try {
+			co_await ReadLoop(link);
+		}
+		catch (...) {
+		}
+
+		link->closing = true;
+		link->wake.cancel();
+		asio::error_code ec;
+		link->socket.close(ec);
+
+		auto it = _connections.find(link->id);
+		if (it != _connections.end() && it->second->signal_link == link) {
+			it->second->signal_link.reset();
+			spdlog::info("Client {} signal link closed; its signals share the main connection again", link->id);
+		}
+	}
+
+	// The counts are the maps themselves; with one io thread nothing can
+	// change them between this check and the insert. Seats held for dropped
+	// members count as taken, except to whoever comes back for one:
+	// reserved_only lets a connection in on probation until it rejoins.
+	std::optional<Proto::ServerLimit> Server::AdmissionLimit(const asio::ip::address& address, bool& reserved_only) const
+	{
+		if (_config.max_users != 0 && _connections.size() >= _config.max_users)
+			return Proto::ServerLimit::Users;
+		reserved_only = _config.max_users != 0 && _connections.size() + ReservedSeats() >= _config.max_users;
+
+		if (_config.max_per_address != 0) {
+			size_t same = std::ranges::count_if(_connections, [&](const auto& entry) { return entry.second->address == address; });
+			if (same >= _config.max_per_address)
+				return Proto::ServerLimit::PerAddress;
+		}
+		return std::nullopt;
+	}
+
+	uint32_t Server::ReservedSeats() const
+	{
+		size_t seats = 0;
+		for (const auto& [code, room] : _rooms)
+			seats += room.reserved.size();
+		return (uint32_t)seats;
+	}
+
+	// Only a connection that drops counts: leaving, kicks and bans free the
+	// seat for good. The seat is held for the account, so only a connection
+	// logged in as it takes the seat back; a guest comes back under a new
+	// suffix and anyone else has no name, so neither can be told from a
+	// stranger and nothing is held for them. The room outlives its last
+	// member while seats are held, ownerless like a booked room, so there is
+	// something to come back to.
+	void Server::ReserveSeat(const std::shared_ptr<Connection>& conn)
+	{
+		if (conn->name.empty() || conn->guest)
+			return;
+
+		auto it = _rooms.find(conn->room);
+		if (it == _rooms.end())
+			return;
+
+		it->second.reserved[conn->name] = UnixNow() + reconnect_grace;
+	}
+
+	void Server::SweepReservations()
+	{
+		uint64_t now = UnixNow();
+		for (auto& [code, room] : _rooms) {
+			std::erase_if(room.reserved, [&](const auto& seat) { return now >= seat.second; });
+		}
+		std::erase_if(_recent_keys, [&](const auto& entry) { return now >= entry.second.expires; });
+
+		for (const auto& [id, conn] : _connections) {
+			if (conn->probation != 0 && now >= conn->probation) {
+				conn->probation = 0;
+				spdlog::info("Client {} did not reclaim a held seat", id);
+				HangUp(conn, Proto::ServerFull{ Proto::ServerLimit::Users, full_retry_after });
+			}
+		}
+	}
+
+	// A peer that vanished without a FIN keeps its connection until a write
+	// to it fails, and a quiet one is never written to. Probing the quiet
+	// ones finds the dead: TCP gives up on the unacknowledged probe, or a
+	// client that stopped reading fills max_queued_bytes. max_silence goes
+	// further and drops whatever does not answer, like a lost network, so
+	// its seat is held.
+	void Server::SweepSilent()
+	{
+		auto now = std::chrono::steady_clock::now();
+		for (const auto& [id, conn] : _connections) {
+			if (conn->closing || conn->hangup)
+				continue;
+
+			if (_config.max_silence != 0 && now - conn->last_heard >= std::chrono::seconds(_config.max_silence)) {
+				spdlog::info("Client {} went silent, dropping it", id);
+				Drop(*conn);
+				continue;
+			}
+
+			if (now - conn->last_heard >= probe_interval && now - conn->last_probe >= probe_interval) {
+				conn->last_probe = now;
+				SendTo(id, Proto::Heartbeat{ 0 });
+			}
+		}
+	}
+
+	void Server::HangUp(const std::shared_ptr<Connection>& conn, const Proto::ServerMessage& last)
+	{
+		SendTo(conn->id, last);
+		conn->hangup = true;
+		conn->wake.cancel();
+	}
+
+	asio::awaitable<void> Server::Refuse(asio::ip::tcp::socket socket, Proto::ServerLimit limit)
+	{
+		std::vector<std::byte> frame = Proto::FrameMessage(Proto::ServerFull{ limit, full_retry_after });
+		try {
+			co_await asio::async_write(socket, asio::buffer(frame), use_awaitable);
+		}
+		catch (...) {
+		}
+
+		asio::error_code ec;
+		socket.shutdown(asio::ip::tcp::socket::shutdown_both, ec);
+		socket.close(ec);
+	}
+
+	// Once a second is plenty: afk_after and bookings are in whole seconds.
+	asio::awaitable<void> Server::TickLoop()
+	{
+		asio::steady_timer timer{ _context };
+		while (true) {
+			timer.expires_after(std::chrono::seconds(1));
+			co_await timer.async_wait(use_awaitable);
+			SweepIdle();
+			SweepSchedules();
+			SweepBlobs();
+			SweepReservations();
+			SweepPreviews();
+			SweepQueues();
+			SweepHealth();
+			SweepChaos();
+			SweepMemory();
+			SweepSilent();
+			SweepAttempts();
+		}
+	}
+
 	asio::awaitable<void> Server::Session(std::shared_ptr<Connection> conn)
 	{
 		asio::co_spawn(conn->socket.get_executor(), WriteLoop(conn), asio::detached);
 
 		SendTo(conn->id, Proto::AssignClientId{ conn->id });
 		SendTo(conn->id, _config.ice); // p2p infrastructure; empty fields = none
+		if (_config.signal_port != 0) {
+			randombytes_buf(&conn->signal_token, sizeof(conn->signal_token));
+			conn->signal_token |= 1; // never 0, which means none
+			SendTo(conn->id, Proto::SignalEndpoint{ _config.signal_port, conn->signal_token });
+		}
+		SendTo(conn->id, Proto::PreferencesChanged{ conn->prefs }); // the defaults, so the client cache starts in sync
 
 		try {
 			co_await ReadLoop(conn);
@@ -105,7 +405,17 @@ namespace Weyvelength {
 		asio::error_code ec;
 
 		conn->socket.close(ec);
+		if (conn->signal_link)
+			Drop(*conn->signal_link);
+		if (!conn->room.empty())
+			ReserveSeat(conn); // gone without a LeaveRoom: maybe a crash or a dead network
 		LeaveRoom(conn);
+		ForgetBookings(*conn); // ids are never reused, so nobody could claim them
+		for (auto& [code, room] : _rooms)
+			std::erase_if(room.knocks, [&](const Proto::JoinRequested& knock) { return knock.id == conn->id; });
+		DropFromQueue(conn);
+		if (conn->guest)
+			_guests.Release(conn->id, UnixNow());
 		_connections.erase(conn->id);
 
 		spdlog::info("Client {} disconnected", conn->id);
@@ -137,7 +447,7 @@ namespace Weyvelength {
 				co_return;
 			body.clear();
 
-			HandleMessage(conn, msg);
+			HandleMessage(conn, std::move(msg));
 		}
 	}
 
@@ -145,33 +455,169 @@ namespace Weyvelength {
 	{
 		try {
 			while (!conn->closing) {
+				if (conn->out.empty() && conn->hangup) {
+					asio::error_code ec;
+					conn->socket.shutdown(asio::ip::tcp::socket::shutdown_both, ec);
+					conn->socket.close(ec); // ReadLoop fails out and Session cleans up
+					break;
+				}
 				if (conn->out.empty()) {
 					asio::error_code ec;
 					co_await conn->wake.async_wait(asio::redirect_error(use_awaitable, ec));
 					continue;
 				}
 
-				std::vector<std::byte> frame = std::move(conn->out.front());
-				conn->out.pop_front();
-				co_await asio::async_write(conn->socket, asio::buffer(frame), use_awaitable);
+				// everything queued goes out in one gather write; the frames
+				// stay alive (and shared with other members' queues) until then
+				std::vector<SharedFrame> batch{ std::make_move_iterator(conn->out.begin()), std::make_move_iterator(conn->out.end()) };
+				conn->out.clear();
+
+				std::vector<asio::const_buffer> buffers;
+				buffers.reserve(batch.size());
+				size_t bytes = 0;
+				for (const SharedFrame& frame : batch) {
+					buffers.push_back(asio::buffer(*frame));
+					bytes += frame->size();
+				}
+				co_await asio::async_write(conn->socket, buffers, use_awaitable);
+				conn->backlog -= bytes;
 			}
 		}
 		catch (...) {
 		}
 	}
 
-	void Server::SendFrame(uint32_t id, std::vector<std::byte> frame)
+	// A connection whose backlog passes max_queued_bytes has stopped reading,
+	// and holding more for it only grows the server. It is closed like a
+	// dropped network: its seat is held, and a client that recovers
+	// reconnects to an empty queue.
+	void Server::SendFrame(uint32_t id, SharedFrame frame)
 	{
 		auto it = _connections.find(id);
-		if (it != _connections.end()) {
-			Enqueue(it->second, std::move(frame));
+		if (it != _connections.end())
+			QueueFrame(it->second, std::move(frame));
+	}
+
+	void Server::QueueFrame(const std::shared_ptr<Connection>& conn, SharedFrame frame)
+	{
+		if (conn->closing)
+			return;
+
+		if (_config.max_queued_bytes != 0 && conn->backlog + frame->size() > _config.max_queued_bytes) {
+			spdlog::warn("Client {} is not reading{}: {} bytes queued, dropping it", conn->id, conn->signaling ? " its signal link" : "", conn->backlog);
+			Drop(*conn);
+			return;
+		}
+
+		Enqueue(conn, std::move(frame));
+	}
+
+	void Server::SendSignal(uint32_t to, const Proto::ServerMessage& msg)
+	{
+		auto it = _connections.find(to);
+		if (it == _connections.end() || !it->second->signal_link || it->second->signal_link->closing) {
+			SendTo(to, msg);
+			return;
+		}
+		QueueFrame(it->second->signal_link, std::make_shared<const std::vector<std::byte>>(Proto::FrameMessage(msg)));
+	}
+
+	void Server::Drop(Connection& conn)
+	{
+		conn.closing = true;
+		conn.out.clear();
+		conn.wake.cancel();
+		asio::error_code ec;
+		conn.socket.close(ec); // ReadLoop fails out and Session cleans up
+	}
+
+	// A signal link's signals are handled as the main connection's own, so
+	// its flood limits, replay check and room all apply unchanged. Not
+	// through HandleMessage: they must not take an Idempotent key meant for
+	// the main connection's next request, nor count as the user's activity.
+	void Server::HandleSignalLink(const std::shared_ptr<Connection>& link, Proto::ServerMessage&& msg)
+	{
+		if (link->id == 0) {
+			auto* attach = std::get_if<Proto::AttachSignalLink>(&msg);
+			auto it = attach ? _connections.find(attach->id) : _connections.end();
+			if (it == _connections.end() || it->second->signal_token == 0 || attach->token != it->second->signal_token
+				|| (it->second->signal_link && !it->second->signal_link->closing)) {
+				spdlog::warn("Refused a signal link from {}: no valid attach", link->address.to_string());
+				Drop(*link);
+				return;
+			}
+			link->id = attach->id;
+			it->second->signal_link = link;
+			spdlog::info("Client {} attached a signal link", link->id);
+			return;
 		}
+
+		auto it = _connections.find(link->id);
+		if (it == _connections.end())
+			return; // going; Session drops the link
+		if (auto* ping = std::get_if<Proto::Heartbeat>(&msg))
+			QueueFrame(link, std::make_shared<const std::vector<std::byte>>(Proto::FrameMessage(Proto::Heartbeat{ ping->timestamp })));
+		else if (auto* signal = std::get_if<Proto::P2PSignal>(&msg))
+			HandleP2PSignal(it->second, std::move(*signal));
+		else if (auto* sequenced = std::get_if<Proto::SequencedSignal>(&msg))
+			HandleP2PSignal(it->second, std::move(sequenced->signal), sequenced->seq);
+		else
+			spdlog::debug("c{} sent message {} over its signal link; dropped", link->id, msg.index());
+	}
+
+	// Whose keys these are. A KeyScope token carries over to any new
+	// connection of the same client, logged in or not; so does a name (an
+	// account's, or a guest's while its lease holds). Without either, the
+	// keys hold for this connection alone. Either way, nobody else's key can
+	// match, however its client drew it.
+	static std::string KeySender(const Connection& conn)
+	{
+		if (conn.key_scope != 0)
+			return "%" + std::to_string(conn.key_scope);
+		return conn.name.empty() ? "#" + std::to_string(conn.id) : conn.name;
 	}
 
-	void Server::HandleMessage(std::shared_ptr<Connection> conn, const Proto::ServerMessage& msg)
+	// Requests answered with a list or a search: they cost the io thread the
+	// most per byte received, so max_lookups caps them.
+	static bool IsLookup(const Proto::ServerMessage& msg)
 	{
+		return std::holds_alternative<Proto::GetServerInfo>(msg) || std::holds_alternative<Proto::GetRoomPreview>(msg)
+			|| std::holds_alternative<Proto::ListScheduledRooms>(msg) || std::holds_alternative<Proto::ListAttendees>(msg)
+			|| std::holds_alternative<Proto::SearchUsers>(msg) || std::holds_alternative<Proto::GetTimeline>(msg)
+			|| std::holds_alternative<Proto::GetSessionHealth>(msg) || std::holds_alternative<Proto::GetJoinReceipts>(msg);
+	}
+
+	void Server::HandleMessage(std::shared_ptr<Connection> conn, Proto::ServerMessage&& msg)
+	{
+		if (conn->signaling) {
+			HandleSignalLink(conn, std::move(msg));
+			return;
+		}
+
+		conn->last_heard = std::chrono::steady_clock::now();
+		if (!std::holds_alternative<Proto::Heartbeat>(msg) && !std::holds_alternative<Proto::ReportHealth>(msg))
+			MarkActive(conn); // keepalives and health reports are automatic, so they say nothing about the user
+
+		if (auto* keyed = std::get_if<Proto::Idempotent>(&msg)) {
+			conn->key = keyed->key; // applies to the next message only
+			return;
+		}
+		if (auto* scope = std::get_if<Proto::KeyScope>(&msg)) {
+			if (conn->key_scope == 0) // once per connection: keys already taken stay where they were
+				conn->key_scope = scope->token;
+			return;
+		}
+
+		uint64_t key = std::exchange(conn->key, 0);
+		if (key != 0 && Replayed(conn, key, msg))
+			return;
+
+		if (IsLookup(msg) && !TakeLookup(conn))
+			return;
+
 		if (auto* ping = std::get_if<Proto::Heartbeat>(&msg)) {
-			SendTo(conn->id, Proto::Heartbeat{ ping->timestamp });
+			if (ping->timestamp != 0) // 0 answers our probe; echoing it would bounce forever
+				SendTo(conn->id, Proto::Heartbeat{ ping->timestamp });
 		}
 		else if (std::get_if<Proto::CreateRoom>(&msg)) {
 			HandleCreateRoom(conn);
@@ -185,8 +631,14 @@ namespace Weyvelength {
 		else if (auto* chat = std::get_if<Proto::RoomChat>(&msg)) {
 			HandleRoomChat(conn, *chat);
 		}
+		else if (auto* whisper = std::get_if<Proto::RoomWhisper>(&msg)) {
+			HandleRoomWhisper(conn, *whisper);
+		}
 		else if (auto* signal = std::get_if<Proto::P2PSignal>(&msg)) {
-			HandleP2PSignal(conn, *signal);
+			HandleP2PSignal(conn, std::move(*signal));
+		}
+		else if (auto* sequenced = std::get_if<Proto::SequencedSignal>(&msg)) {
+			HandleP2PSignal(conn, std::move(sequenced->signal), sequenced->seq);
 		}
 		else if (auto* set = std::get_if<Proto::SetRoomData>(&msg)) {
 			HandleSetRoomData(conn, *set);
@@ -209,6 +661,203 @@ namespace Weyvelength {
 		else if (auto* password = std::get_if<Proto::SetRoomPassword>(&msg)) {
 			HandleSetRoomPassword(conn, *password);
 		}
+		else if (auto* role = std::get_if<Proto::SetMemberRole>(&msg)) {
+			HandleSetMemberRole(conn, *role);
+		}
+		else if (auto* prefs = std::get_if<Proto::SetPreferences>(&msg)) {
+			HandleSetPreferences(conn, *prefs);
+		}
+		else if (auto* spectate = std::get_if<Proto::SpectateRoom>(&msg)) {
+			HandleSpectateRoom(conn, *spectate);
+		}
+		else if (auto* capacity = std::get_if<Proto::SetRoomCapacity>(&msg)) {
+			HandleSetRoomCapacity(conn, *capacity);
+		}
+		else if (auto* idle = std::get_if<Proto::SetIdlePolicy>(&msg)) {
+			HandleSetIdlePolicy(conn, *idle);
+		}
+		else if (auto* banner = std::get_if<Proto::SetRoomBanner>(&msg)) {
+			HandleSetRoomBanner(conn, *banner);
+		}
+		else if (auto* schedule = std::get_if<Proto::ScheduleRoom>(&msg)) {
+			HandleScheduleRoom(conn, *schedule);
+		}
+		else if (std::get_if<Proto::ListScheduledRooms>(&msg)) {
+			HandleListScheduledRooms(conn);
+		}
+		else if (auto* rsvp = std::get_if<Proto::RsvpScheduledRoom>(&msg)) {
+			HandleRsvpScheduledRoom(conn, *rsvp);
+		}
+		else if (auto* attendees = std::get_if<Proto::ListAttendees>(&msg)) {
+			HandleListAttendees(conn, *attendees);
+		}
+		else if (auto* cancel = std::get_if<Proto::CancelScheduledRoom>(&msg)) {
+			HandleCancelScheduledRoom(conn, *cancel);
+		}
+		else if (auto* create = std::get_if<Proto::CreateAccount>(&msg)) {
+			HandleCreateAccount(conn, *create);
+		}
+		else if (auto* login = std::get_if<Proto::Login>(&msg)) {
+			HandleLogin(conn, *login);
+		}
+		else if (auto* guest = std::get_if<Proto::GuestLogin>(&msg)) {
+			HandleGuestLogin(conn, *guest);
+		}
+		else if (auto* upload = std::get_if<Proto::UploadBlob>(&msg)) {
+			HandleUploadBlob(conn, *upload);
+		}
+		else if (auto* chunk = std::get_if<Proto::BlobChunk>(&msg)) {
+			HandleBlobChunk(conn, *chunk);
+		}
+		else if (auto* download = std::get_if<Proto::DownloadBlob>(&msg)) {
+			HandleDownloadBlob(conn, *download);
+		}
+		else if (auto* background = std::get_if<Proto::SetBackground>(&msg)) {
+			HandleSetBackground(conn, *background);
+		}
+		else if (std::get_if<Proto::GetServerInfo>(&msg)) {
+			HandleGetServerInfo(conn);
+		}
+		else if (auto* info = std::get_if<Proto::ClientInfo>(&msg)) {
+			HandleClientInfo(conn, std::move(*info));
+		}
+		else if (auto* preview = std::get_if<Proto::GetRoomPreview>(&msg)) {
+			HandleGetRoomPreview(conn, *preview);
+		}
+		else if (std::get_if<Proto::StopRoomPreview>(&msg)) {
+			conn->watching.clear();
+			conn->watched.reset();
+		}
+		else if (auto* knock = std::get_if<Proto::RequestJoin>(&msg)) {
+			HandleRequestJoin(conn, *knock);
+		}
+		else if (auto* answer = std::get_if<Proto::RespondJoinRequest>(&msg)) {
+			HandleRespondJoinRequest(conn, *answer);
+		}
+		else if (auto* queue = std::get_if<Proto::QueueForRoom>(&msg)) {
+			HandleQueueForRoom(conn, *queue);
+		}
+		else if (std::get_if<Proto::LeaveRoomQueue>(&msg)) {
+			std::string room = conn->queued;
+			DropFromQueue(conn);
+			SendTo(conn->id, Proto::RoomQueuePosition{ room, 0 });
+		}
+		else if (auto* searchable = std::get_if<Proto::SetSearchable>(&msg)) {
+			HandleSetSearchable(conn, *searchable);
+		}
+		else if (auto* search = std::get_if<Proto::SearchUsers>(&msg)) {
+			HandleSearchUsers(conn, *search);
+		}
+		else if (std::get_if<Proto::ExportMyData>(&msg)) {
+			HandleExportMyData(conn);
+		}
+		else if (auto* timeline = std::get_if<Proto::GetTimeline>(&msg)) {
+			HandleGetTimeline(conn, *timeline);
+		}
+		else if (auto* setFor = std::get_if<Proto::SetMemberDataFor>(&msg)) {
+			HandleSetMemberDataFor(conn, *setFor);
+		}
+		else if (auto* create = std::get_if<Proto::CreateTeam>(&msg)) {
+			HandleCreateTeam(conn, *create);
+		}
+		else if (auto* remove = std::get_if<Proto::RemoveTeam>(&msg)) {
+			HandleRemoveTeam(conn, *remove);
+		}
+		else if (auto* assign = std::get_if<Proto::AssignTeam>(&msg)) {
+			HandleAssignTeam(conn, *assign);
+		}
+		else if (std::get_if<Proto::BalanceTeams>(&msg)) {
+			HandleBalanceTeams(conn);
+		}
+		else if (auto* rules = std::get_if<Proto::SetRoomRules>(&msg)) {
+			HandleSetRoomRules(conn, *rules);
+		}
+		else if (auto* countdown = std::get_if<Proto::StartCountdown>(&msg)) {
+			HandleStartCountdown(conn, *countdown);
+		}
+		else if (auto* pause = std::get_if<Proto::SetMatchPaused>(&msg)) {
+			HandleSetMatchPaused(conn, *pause);
+		}
+		else if (auto* health = std::get_if<Proto::ReportHealth>(&msg)) {
+			HandleReportHealth(conn, *health);
+		}
+		else if (std::get_if<Proto::GetSessionHealth>(&msg)) {
+			HandleGetSessionHealth(conn);
+		}
+		else if (std::get_if<Proto::GetJoinReceipts>(&msg)) {
+			HandleGetJoinReceipts(conn);
+		}
+		else if (auto* state = std::get_if<Proto::SetHostState>(&msg)) {
+			HandleSetHostState(conn, std::move(*state));
+		}
+		else if (auto* report = std::get_if<Proto::ReportHostState>(&msg)) {
+			HandleReportHostState(conn, std::move(*report));
+		}
+		else if (auto* succession = std::get_if<Proto::SetSuccession>(&msg)) {
+			HandleSetSuccession(conn, std::move(*succession));
+		}
+		else if (auto* channel = std::get_if<Proto::CreateChannel>(&msg)) {
+			HandleCreateChannel(conn, *channel);
+		}
+		else if (auto* removal = std::get_if<Proto::RemoveChannel>(&msg)) {
+			HandleRemoveChannel(conn, *removal);
+		}
+		else if (auto* placement = std::get_if<Proto::PlaceInChannel>(&msg)) {
+			HandlePlaceInChannel(conn, *placement);
+		}
+		else if (auto* channelChat = std::get_if<Proto::ChannelChat>(&msg)) {
+			HandleChannelChat(conn, *channelChat);
+		}
+		else if (auto* split = std::get_if<Proto::SplitRoom>(&msg)) {
+			HandleSplitRoom(conn, *split);
+		}
+		else if (auto* merge = std::get_if<Proto::MergeRoom>(&msg)) {
+			HandleMergeRoom(conn, *merge);
+		}
+		else if (auto* answer = std::get_if<Proto::AnswerMerge>(&msg)) {
+			HandleAnswerMerge(conn, *answer);
+		}
+
+		// keyed requests are all room requests: outside a room it failed, and a repeat may yet work.
+		// a CreateRoom remembers the room it made, for the repeat to join
+		if (key != 0 && !conn->room.empty())
+			_recent_keys[{ KeySender(*conn), key }] = { UnixNow() + Proto::idempotency_window, std::holds_alternative<Proto::CreateRoom>(msg) ? conn->room : std::string{} };
+	}
+
+	// A repeat of a request already handled, by the same sender. Only
+	// CreateRoom gets an answer: the sender made that room, so a new
+	// connection of theirs is let in as a booking's owner is, by
+	// invitation, and never with the room's password. Anything else just
+	// goes nowhere, as it already went where it was meant to.
+	bool Server::Replayed(const std::shared_ptr<Connection>& conn, uint64_t key, const Proto::ServerMessage& msg)
+	{
+		auto it = _recent_keys.find({ KeySender(*conn), key });
+		if (it == _recent_keys.end())
+			return false;
+
+		if (std::holds_alternative<Proto::CreateRoom>(msg)) {
+			auto room = _rooms.find(it->second.room);
+			if (room == _rooms.end())
+				return false; // closed since; make a new one
+			if (conn->room != room->first) {
+				if (std::ranges::find(room->second.invited, conn->id) == room->second.invited.end())
+					room->second.invited.push_back(conn->id);
+				EnterRoom(conn, room->first, {}, false);
+			}
+		}
+
+		spdlog::debug("c{} repeated request {:x} dropped", conn->id, key);
+		return true;
+	}
+
+	// The host sits one rank above the highest role.
+	static int Rank(const Room& room, uint32_t id)
+	{
+		if (id == room.host)
+			return (int)Proto::MemberRole::CoHost + 1;
+
+		auto it = room.roles.find(id);
+		return it == room.roles.end() ? (int)Proto::MemberRole::Member : (int)it->second;
 	}
 
 	// Shared preamble of every host-only action: resolves the sender's room
@@ -229,6 +878,89 @@ namespace Weyvelength {
 		return &it->second;
 	}
 
+	// HostRoom for actions delegated to roles: the host always passes, other
+	// members need at least the given role.
+	Room* Server::RankedRoom(const std::shared_ptr<Connection>& conn, Proto::MemberRole least)
+	{
+		auto it = _rooms.find(conn->room);
+		if (it == _rooms.end()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NotInRoom, {} });
+			return nullptr;
+		}
+
+		if (Rank(it->second, conn->id) < (int)least) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NotHost, {} });
+			return nullptr;
+		}
+
+		return &it->second;
+	}
+
+	uint32_t Server::NextHost(const Room& room) const
+	{
+		std::vector<Proto::SuccessionCandidate> members;
+		for (uint32_t id : room.members) {
+			Proto::SuccessionCandidate member{ id, Rank(room, id) == (int)Proto::MemberRole::CoHost, std::ranges::find(room.spectators, id) != room.spectators.end() };
+			if (auto conn = _connections.find(id); conn != _connections.end())
+				member.connected = (uint64_t)conn->second->connected.time_since_epoch().count();
+			if (auto health = room.health.find(id); health != room.health.end())
+				member.server_rtt = health->second.first.server_rtt;
+			members.push_back(member);
+		}
+		return Proto::PickSuccessor(room.succession, room.successors, members);
+	}
+
+	// A host holds no role: any they had is dropped, and announced as such so
+	// every member's role cache agrees. The new host gets the old one's last
+	// checkpoint; if the old host dropped, everyone else is asked for theirs.
+	// Answers still owed to the old host are no longer wanted.
+	void Server::SetHost(Room& room, uint32_t id, uint32_t by, bool dropped)
+	{
+		uint32_t from = room.host;
+		room.host = id;
+		room.awaiting_host_state.clear();
+		SendToMany(room.members, Proto::HostChanged{ room.host });
+		if (room.roles.erase(id))
+			SendToMany(room.members, Proto::MemberRoleChanged{ id, Proto::MemberRole::Member });
+		for (const Proto::JoinRequested& knock : room.knocks)
+			SendTo(id, knock); // the old host never answered these
+		AddToTimeline(room, Proto::TimelineKind::HostChanged, id, by);
+
+		Proto::HandoffReason reason = by != 0 ? Proto::HandoffReason::Transferred : dropped ? Proto::HandoffReason::Dropped : Proto::HandoffReason::Left;
+		SendTo(id, Proto::HostHandoff{ from, reason, room.host_state_saved, room.host_state });
+		if (reason == Proto::HandoffReason::Dropped) {
+			for (uint32_t member : room.members) {
+				if (member != id) {
+					room.awaiting_host_state.insert(member);
+					SendTo(member, Proto::HostStateWanted{ id });
+				}
+			}
+		}
+	}
+
+	void Server::AddToTimeline(Room& room, Proto::TimelineKind kind, uint32_t id, uint32_t by)
+	{
+		auto conn = _connections.find(id);
+		Proto::TimelineEntry entry{ ++room.timeline_seq, UnixNow(), kind, id, conn != _connections.end() ? conn->second->name : std::string{}, by };
+		room.timeline.push_back(entry);
+		if (room.timeline.size() > Proto::max_timeline_entries)
+			room.timeline.pop_front();
+		SendToMany(room.members, Proto::TimelineAdded{ std::move(entry) });
+	}
+
+	// Signed as it happens, so a receipt says only what the server saw then.
+	void Server::IssueReceipt(Room& room, const std::shared_ptr<Connection>& conn, Proto::ReceiptKind kind)
+	{
+		Proto::ReceiptRole role = room.host == conn->id ? Proto::ReceiptRole::Host
+			: std::ranges::find(room.spectators, conn->id) != room.spectators.end() ? Proto::ReceiptRole::Spectator
+			: Proto::ReceiptRole::Player;
+		Proto::JoinReceipt receipt{ room.id, conn->id, conn->name, role, kind, UnixNow() };
+		receipt.signature = _receipts.Sign(Proto::ReceiptText(receipt));
+		room.receipts.push_back(std::move(receipt));
+		if (room.receipts.size() > Proto::max_join_receipts)
+			room.receipts.pop_front();
+	}
+
 	void Server::HandleCreateRoom(const std::shared_ptr<Connection>& conn)
 	{
 		if (!conn->room.empty()) {
@@ -236,12 +968,20 @@ namespace Weyvelength {
 			return;
 		}
 
-		std::string code = MakeRoomCode(_config.room_code_length);
-		while (_rooms.contains(code))
-			code = MakeRoomCode(_config.room_code_length);
+		if (conn->probation != 0) { // let in on a held seat, not to open rooms
+			HangUp(conn, Proto::ServerFull{ Proto::ServerLimit::Users, full_retry_after });
+			return;
+		}
+
+		if (_config.max_rooms != 0 && _rooms.size() >= _config.max_rooms) {
+			SendTo(conn->id, Proto::ServerFull{ Proto::ServerLimit::Rooms, full_retry_after });
+			return;
+		}
 
-		_rooms.emplace(code, Room{ code, conn->id, { conn->id } });
+		std::string code = UniqueRoomCode();
+		Room& room = _rooms.emplace(code, Room{ code, conn->id, { conn->id } }).first->second;
 		conn->room = code;
+		IssueReceipt(room, conn, Proto::ReceiptKind::Joined);
 		SendTo(conn->id, Proto::AssignRoomId{ code });
 		SendTo(conn->id, Proto::HostChanged{ conn->id }); // the host cache has a single source: this event
 
@@ -250,45 +990,138 @@ namespace Weyvelength {
 
 	void Server::HandleJoinRoom(const std::shared_ptr<Connection>& conn, const Proto::JoinRoom& msg)
 	{
-		if (!conn->room.empty()) {
-			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::AlreadyInRoom, conn->room });
-			return;
+		EnterRoom(conn, msg.id, msg.password, false);
+	}
+
+	void Server::HandleSpectateRoom(const std::shared_ptr<Connection>& conn, const Proto::SpectateRoom& msg)
+	{
+		EnterRoom(conn, msg.id, msg.password, true);
+	}
+
+	void Server::EnterRoom(const std::shared_ptr<Connection>& conn, const std::string& id, const std::string& password, bool spectating)
+	{
+		if (!conn->room.empty()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::AlreadyInRoom, conn->room });
+			return;
 		}
 
-		auto it = _rooms.find(msg.id);
+		auto it = _rooms.find(id);
 		if (it == _rooms.end()) {
-			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchRoom, msg.id });
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchRoom, id });
 			return;
 		}
 
 		Room& room = it->second;
+		bool holder = !conn->guest && room.reserved.contains(conn->name);
+		if (conn->probation != 0 && !holder) {
+			HangUp(conn, Proto::ServerFull{ Proto::ServerLimit::Users, full_retry_after });
+			return;
+		}
+
+		if (room.members.empty() && !room.reserved.empty() && !holder) { // kept for those who dropped, and hosted by the first of them back
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::RoomClosed, id });
+			return;
+		}
+
 		if (std::ranges::find(room.banned_members, conn->id) != room.banned_members.end()) {
-			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::Banned, msg.id });
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::Banned, id });
 			return;
 		}
 
 		if (!room.open) {
-			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::RoomClosed, msg.id });
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::RoomClosed, id });
 			return;
 		}
 
-		if (!room.password.empty() && msg.password != room.password) {
-			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadPassword, msg.id });
+		if (!room.password.empty() && password != room.password && !Invited(room, conn->id)) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadPassword, id });
 			return;
 		}
 
+		// seats offered to the line or held for those who dropped are taken,
+		// and nobody walks in past the line but a holder coming back
+		size_t players = room.members.size() - room.spectators.size();
+		size_t held = room.offers.size() - room.offers.count(conn->id) + room.reserved.size() - (holder ? 1 : 0);
+		bool line = !room.queue.empty() && !room.offers.contains(conn->id) && !holder;
+		if (spectating ? room.max_spectators != 0 && room.spectators.size() >= room.max_spectators
+			: room.max_players != 0 && (players + held >= room.max_players || line)) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::RoomFull, spectating ? "spectators" : "players" });
+			return;
+		}
+
+		AddMember(conn, room, spectating);
+	}
+
+	void Server::AddMember(const std::shared_ptr<Connection>& conn, Room& room, bool spectating)
+	{
+		if (room.host == 0) { // a booked room that just opened, or one a split made: the first one in hosts it
+			room.host = conn->id;
+			room.expires = 0;
+		}
+
 		SendToMany(room.members, Proto::PeerJoined{ conn->id });
+		if (spectating)
+			SendToMany(room.members, Proto::MemberSpectating{ conn->id });
+		if (!conn->name.empty())
+			SendToMany(room.members, Proto::MemberNameChanged{ conn->id, conn->name });
 
-		// hydrate the joiner with the same events everyone else already
-		// understands: one per existing member, the host, one per data key
-		SendTo(conn->id, Proto::AssignRoomId{ msg.id });
+		room.members.push_back(conn->id);
+		if (spectating)
+			room.spectators.push_back(conn->id);
+		conn->room = room.id; // as the server wrote it, not as typed
+		if (!conn->guest)
+			room.reserved.erase(conn->name);
+		conn->probation = 0;
+		if (!conn->queued.empty())
+			DropFromQueue(conn); // in, so out of every line; an offered seat is this one
+
+		SendRoomState(conn, room);
+		AddToTimeline(room, spectating ? Proto::TimelineKind::Spectating : Proto::TimelineKind::Joined, conn->id); // after the joiner's copy of the log
+		IssueReceipt(room, conn, Proto::ReceiptKind::Joined);
+		if (room.host == conn->id && room.host_state_saved != 0) // back first to a room that sat empty: what its last host left behind
+			SendTo(conn->id, Proto::HostHandoff{ 0, Proto::HandoffReason::Dropped, room.host_state_saved, room.host_state });
+
+		spdlog::info("Client {} {} room {}", conn->id, spectating ? "is spectating" : "joined", room.id);
+	}
+
+	// Hydrates a member with the same events everyone else already
+	// understands: one per other member, the host, one per data key. Sent on
+	// join, and again when a member back from the background missed updates;
+	// AssignRoomId first, so the client starts its caches over either way.
+	void Server::SendRoomState(const std::shared_ptr<Connection>& conn, const Room& room)
+	{
+		conn->missed = false; // this is everything
+		SendTo(conn->id, Proto::AssignRoomId{ room.id });
 
 		for (uint32_t member : room.members) {
-			SendTo(conn->id, Proto::PeerJoined{ member });
+			if (member != conn->id)
+				SendTo(conn->id, Proto::PeerJoined{ member });
+		}
+
+		for (uint32_t spectator : room.spectators) {
+			SendTo(conn->id, Proto::MemberSpectating{ spectator }); // our own seat included, so the counts add up
 		}
 
 		SendTo(conn->id, Proto::HostChanged{ room.host });
 		SendTo(conn->id, Proto::RoomAccessChanged{ room.open, !room.password.empty() });
+		SendTo(conn->id, Proto::RoomCapacityChanged{ room.max_players, room.max_spectators });
+		SendTo(conn->id, Proto::IdlePolicyChanged{ room.afk_after, room.afk_spectate });
+		SendTo(conn->id, Proto::SuccessionChanged{ room.succession, room.successors });
+		if (!room.banner.empty())
+			SendTo(conn->id, Proto::RoomBannerChanged{ room.banner });
+		if (!room.teams.empty())
+			SendTo(conn->id, Proto::TeamsChanged{ room.teams });
+		if (!room.channels.empty())
+			SendTo(conn->id, Proto::ChannelsChanged{ room.channels });
+		if (room.rules_version != 0)
+			SendTo(conn->id, Proto::RoomRulesChanged{ room.rules_version, room.rules });
+		if (uint64_t now = UnixNowMs(); room.countdown.start_at > now) { // still running: the rest of it
+			Proto::CountdownStarted countdown = room.countdown;
+			countdown.remaining = (uint32_t)(countdown.start_at - now);
+			SendTo(conn->id, countdown);
+		}
+		if (room.paused)
+			SendTo(conn->id, Proto::MatchPausedChanged{ true, room.paused_by });
 
 		for (const auto& [key, value] : room.data) {
 			SendTo(conn->id, Proto::RoomDataChanged{ key, value });
@@ -300,10 +1133,21 @@ namespace Weyvelength {
 			}
 		}
 
-		room.members.push_back(conn->id);
-		conn->room = msg.id;
+		for (const auto& [member, role] : room.roles) {
+			SendTo(conn->id, Proto::MemberRoleChanged{ member, role });
+		}
+
+		for (uint32_t member : room.members) {
+			auto other = _connections.find(member);
+			if (other == _connections.end() || member == conn->id)
+				continue;
+			if (!other->second->name.empty())
+				SendTo(conn->id, Proto::MemberNameChanged{ member, other->second->name });
+			if (other->second->afk)
+				SendTo(conn->id, Proto::MemberAfk{ member, true });
+		}
 
-		spdlog::info("Client {} joined room {}", conn->id, msg.id);
+		SendTo(conn->id, Proto::Timeline{ { room.timeline.begin(), room.timeline.end() } });
 	}
 
 	void Server::HandleLeaveRoom(const std::shared_ptr<Connection>& conn)
@@ -325,8 +1169,72 @@ namespace Weyvelength {
 			return;
 		}
 
+		std::string text = msg.text;
+		if (!CleanChat(conn, text))
+			return;
+
+		std::string link = _previews.Enabled() ? _previews.FindLink(text) : std::string{};
+
 		// sender included: everyone in the room sees the same stream
-		SendToMany(it->second.members, Proto::RoomChat{ conn->id, msg.text });
+		SendToMany(it->second.members, Proto::RoomChat{ conn->id, std::move(text) });
+
+		if (!link.empty()) {
+			_previews.Preview(link, [this, room = conn->room, from = conn->id](const Proto::LinkPreview& preview) {
+				auto it = _rooms.find(room);
+				if (it != _rooms.end()) // else everyone left while the page loaded
+					SendToMany(it->second.members, Proto::ChatLinkPreview{ from, preview });
+			});
+		}
+	}
+
+	// The fallback path for whispers; clients send them direct when a p2p link
+	// is up, so the server only sees the ones that could not go that way.
+	void Server::HandleRoomWhisper(const std::shared_ptr<Connection>& conn, const Proto::RoomWhisper& msg)
+	{
+		auto it = _rooms.find(conn->room);
+		if (it == _rooms.end()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NotInRoom, {} });
+			return;
+		}
+
+		if (msg.id == conn->id || std::ranges::find(it->second.members, msg.id) == it->second.members.end()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchMember, std::to_string(msg.id) });
+			return;
+		}
+
+		auto target = _connections.find(msg.id);
+		bool from_staff = Rank(it->second, conn->id) >= (int)Proto::MemberRole::Moderator;
+		if (target == _connections.end() || !Proto::WhisperAllowed(target->second->prefs, from_staff, UnixNow())) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::WhisperBlocked, std::to_string(msg.id) });
+			return;
+		}
+
+		std::string text = msg.text;
+		if (!CleanChat(conn, text))
+			return;
+
+		SendTo(msg.id, Proto::RoomWhisper{ conn->id, std::move(text) }); // forwarded carrying the sender's id
+	}
+
+	bool Server::CleanChat(const std::shared_ptr<Connection>& conn, std::string& text)
+	{
+		switch (Proto::SanitizeChat(text, _config.max_chat_zero_width)) {
+		case Proto::ChatTextIssue::None:
+			return true;
+		case Proto::ChatTextIssue::TooLong:
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::ChatTooLong, std::to_string(Proto::max_chat_chars) });
+			return false;
+		case Proto::ChatTextIssue::Empty:
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadChat, "empty" });
+			return false;
+		case Proto::ChatTextIssue::BadUtf8:
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadChat, "utf8" });
+			return false;
+		case Proto::ChatTextIssue::ZeroWidthSpam:
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadChat, "zero-width" });
+			return false;
+		}
+		return false;
 	}
 
 	static const char* P2PSignalKindName(Proto::P2PSignalKind kind)
@@ -335,14 +1243,38 @@ namespace Weyvelength {
 		case Proto::P2PSignalKind::Description: return "description";
 		case Proto::P2PSignalKind::Candidate: return "candidate";
 		case Proto::P2PSignalKind::GatheringDone: return "gathering done";
+		case Proto::P2PSignalKind::Framing: return "framing";
+		case Proto::P2PSignalKind::Closed: return "closed";
 		}
 		return "unknown";
 	}
 
-	// Relays ICE signaling between room members without reading the sdp. Bad
-	// targets are dropped, not errored: a candidate can race the target's departure.
-	void Server::HandleP2PSignal(const std::shared_ptr<Connection>& conn, const Proto::P2PSignal& msg)
+	// Relays ICE signaling between room members, checking only the payload's
+	// shape. Bad targets are dropped, not errored: a candidate can race the
+	// target's departure. Bad payloads come from no client of ours, nor do
+	// replays. A sequenced signal goes on sequenced. A sender past its
+	// signal caps is flooding rather than negotiating, and is dropped.
+	void Server::HandleP2PSignal(const std::shared_ptr<Connection>& conn, Proto::P2PSignal&& msg, uint64_t seq)
 	{
+		auto now = std::chrono::steady_clock::now();
+		if (now - conn->signal_window_start >= signal_window) {
+			conn->signal_window_start = now;
+			conn->signals = 0;
+			conn->signal_bytes = 0;
+		}
+		conn->signals++;
+		conn->signal_bytes += msg.payload.size();
+		if ((_config.max_signals != 0 && conn->signals > _config.max_signals) || (_config.max_signal_bytes != 0 && conn->signal_bytes > _config.max_signal_bytes)) {
+			spdlog::warn("c{} is flooding the signal relay: {} signals, {} bytes in {} s; dropping it", conn->id, conn->signals, conn->signal_bytes, signal_window.count());
+			Drop(*conn);
+			return;
+		}
+
+		if (seq != 0 && !Proto::FreshSignal(conn->signal_seq, seq)) {
+			spdlog::warn("c{} p2p {} dropped: seq {} replayed (last {})", conn->id, P2PSignalKindName(msg.kind), seq, conn->signal_seq);
+			return;
+		}
+
 		auto it = _rooms.find(conn->room);
 		if (it == _rooms.end()) {
 			spdlog::debug("c{} p2p {} dropped: no room", conn->id, P2PSignalKindName(msg.kind));
@@ -354,11 +1286,54 @@ namespace Weyvelength {
 			return;
 		}
 
+		if (!Proto::ValidSignalPayload(msg.kind, msg.payload)) {
+			spdlog::warn("c{} p2p {} dropped: malformed payload ({} bytes)", conn->id, P2PSignalKindName(msg.kind), msg.payload.size());
+			return;
+		}
+
 		// a size summary at info; the payload (ice creds, local ips) only at debug
 		if (msg.kind == Proto::P2PSignalKind::Description)
 			spdlog::info("c{} -> c{} p2p description ({} bytes)", conn->id, msg.id, msg.payload.size());
 		spdlog::debug("c{} -> c{} p2p {}: {}", conn->id, msg.id, P2PSignalKindName(msg.kind), msg.payload);
-		SendTo(msg.id, Proto::P2PSignal{ conn->id, msg.kind, msg.payload }); // forwarded carrying the sender's id
+
+		// forwarded carrying the sender's id; the payload moves, so framing
+		// is the only copy the relay makes
+		uint32_t to = std::exchange(msg.id, conn->id);
+		Proto::ServerMessage relayed = seq ? Proto::ServerMessage{ Proto::SequencedSignal{ seq, std::move(msg) } } : Proto::ServerMessage{ std::move(msg) };
+		if (_config.chaos.Active())
+			RelayChaotic(to, relayed);
+		else
+			SendSignal(to, relayed);
+	}
+
+	void Server::RelayChaotic(uint32_t to, const Proto::ServerMessage& relayed)
+	{
+		auto* sequenced = std::get_if<Proto::SequencedSignal>(&relayed);
+		const Proto::P2PSignal& msg = sequenced ? sequenced->signal : std::get<Proto::P2PSignal>(relayed);
+		const ChaosConfig& chaos = _config.chaos;
+		if (Chance(chaos.drop_permille)) {
+			spdlog::debug("Chaos: dropped c{} -> c{} p2p {}", msg.id, to, P2PSignalKindName(msg.kind));
+			return;
+		}
+
+		int copies = Chance(chaos.duplicate_permille) ? 2 : 1;
+		for (int i = 0; i < copies; i++) {
+			if (chaos.delay_ms != 0 && Chance(chaos.delay_permille)) {
+				auto delay = std::chrono::milliseconds(Roll(chaos.delay_ms) + 1);
+				spdlog::debug("Chaos: holding c{} -> c{} p2p {} for {} ms", msg.id, to, P2PSignalKindName(msg.kind), delay.count());
+				asio::co_spawn(_context, SendLater(to, relayed, delay), asio::detached);
+			}
+			else {
+				SendSignal(to, relayed);
+			}
+		}
+	}
+
+	asio::awaitable<void> Server::SendLater(uint32_t to, Proto::ServerMessage msg, std::chrono::milliseconds delay)
+	{
+		asio::steady_timer timer{ _context, delay };
+		co_await timer.async_wait(use_awaitable);
+		SendSignal(to, msg); // nothing, if they are gone by now
 	}
 
 	void Server::HandleSetRoomData(const std::shared_ptr<Connection>& conn, const Proto::SetRoomData& msg)
@@ -404,40 +1379,59 @@ namespace Weyvelength {
 			return;
 		}
 
-		if (msg.key.empty() || msg.key.size() > Proto::max_room_data_key || msg.value.size() > Proto::max_room_data_value) {
-			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadRoomData, msg.key });
+		WriteMemberData(conn, it->second, conn->id, msg.key, msg.value);
+	}
+
+	void Server::HandleSetMemberDataFor(const std::shared_ptr<Connection>& conn, const Proto::SetMemberDataFor& msg)
+	{
+		Room* room = HostRoom(conn);
+		if (!room)
+			return;
+
+		if (std::ranges::find(room->members, msg.id) == room->members.end()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchMember, std::to_string(msg.id) });
 			return;
 		}
 
-		Room& room = it->second;
-		if (msg.value.empty()) { // empty value = delete
-			auto member = room.member_data.find(conn->id);
-			if (member == room.member_data.end() || member->second.erase(msg.key) == 0)
+		WriteMemberData(conn, *room, msg.id, msg.key, msg.value);
+	}
+
+	// Either sender's write to id's slots; errors go to the sender.
+	void Server::WriteMemberData(const std::shared_ptr<Connection>& conn, Room& room, uint32_t id, const std::string& key, const std::string& value)
+	{
+		if (key.empty() || key.size() > Proto::max_room_data_key || value.size() > Proto::max_room_data_value) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadRoomData, key });
+			return;
+		}
+
+		if (value.empty()) { // empty value = delete
+			auto member = room.member_data.find(id);
+			if (member == room.member_data.end() || member->second.erase(key) == 0)
 				return; // nothing deleted, nothing to announce
 			if (member->second.empty())
 				room.member_data.erase(member);
 		}
 		else {
-			auto& data = room.member_data[conn->id];
-			auto [entry, inserted] = data.try_emplace(msg.key, msg.value);
+			auto& data = room.member_data[id];
+			auto [entry, inserted] = data.try_emplace(key, value);
 			if (inserted && data.size() > Proto::max_member_data_keys) {
 				data.erase(entry);
-				SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadRoomData, msg.key });
+				SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadRoomData, key });
 				return;
 			}
 			if (!inserted) {
-				if (entry->second == msg.value)
+				if (entry->second == value)
 					return; // unchanged, nothing to announce
-				entry->second = msg.value;
+				entry->second = value;
 			}
 		}
 
-		SendToMany(room.members, Proto::MemberDataChanged{ conn->id, msg.key, msg.value });
+		SendToMany(room.members, Proto::MemberDataChanged{ id, key, value });
 	}
 
 	void Server::HandleKickMember(const std::shared_ptr<Connection>& conn, const Proto::KickMember& msg)
 	{
-		Room* room = HostRoom(conn);
+		Room* room = RankedRoom(conn, Proto::MemberRole::Moderator);
 		if (!room)
 			return;
 
@@ -447,8 +1441,13 @@ namespace Weyvelength {
 			return;
 		}
 
+		if (Rank(*room, msg.id) >= Rank(*room, conn->id)) { // staff only kick below their own rank
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NotHost, std::to_string(msg.id) });
+			return;
+		}
+
 		SendTo(msg.id, Proto::KickedByHost{});
-		LeaveRoom(target->second); // removal + PeerLeft broadcast, same as any other exit
+		LeaveRoom(target->second, Proto::TimelineKind::Kicked, conn->id); // removal + PeerLeft broadcast, same as any other exit
 
 		spdlog::info("Client {} kicked from room {} by client {}", msg.id, room->id, conn->id);
 	}
@@ -469,7 +1468,7 @@ namespace Weyvelength {
 			room->banned_members.push_back(msg.id); // barred until the room closes; join now rejects them
 
 		SendTo(msg.id, Proto::BannedByHost{});
-		LeaveRoom(target->second); // removal + PeerLeft broadcast, same as a kick
+		LeaveRoom(target->second, Proto::TimelineKind::Banned, conn->id); // removal + PeerLeft broadcast, same as a kick
 
 		spdlog::info("Client {} banned from room {} by client {}", msg.id, room->id, conn->id);
 	}
@@ -488,23 +1487,148 @@ namespace Weyvelength {
 			return;
 		}
 
-		room->host = msg.id;
-		SendToMany(room->members, Proto::HostChanged{ room->host });
+		SetHost(*room, msg.id, conn->id);
 
 		spdlog::info("Client {} now hosts room {} (transferred)", room->host, room->id);
 	}
 
-	void Server::HandleSetRoomJoinable(const std::shared_ptr<Connection>& conn, const Proto::SetRoomJoinable& msg)
+	void Server::MoveMember(const std::shared_ptr<Connection>& conn, Room& to, uint32_t by)
+	{
+		auto from = _rooms.find(conn->room);
+		bool spectating = from != _rooms.end() && std::ranges::find(from->second.spectators, conn->id) != from->second.spectators.end();
+		SendTo(conn->id, Proto::RoomMoved{ to.id, by }); // before AssignRoomId: the client drops the old mesh here
+		LeaveRoom(conn, Proto::TimelineKind::Moved, by);
+		if (std::ranges::find(to.invited, conn->id) == to.invited.end())
+			to.invited.push_back(conn->id); // a reconnect finds its way back past the password
+		AddMember(conn, to, spectating);
+	}
+
+	// The new room starts with no settings and no host: the first one moved
+	// in takes it, as in a booked room that just opened.
+	void Server::HandleSplitRoom(const std::shared_ptr<Connection>& conn, const Proto::SplitRoom& msg)
+	{
+		Room* room = HostRoom(conn);
+		if (!room)
+			return;
+
+		if (msg.members.empty()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchMember, {} });
+			return;
+		}
+		for (auto id = msg.members.begin(); id != msg.members.end(); ++id) {
+			if (std::ranges::find(room->members, *id) == room->members.end() || std::find(msg.members.begin(), id, *id) != id) { // each a member, once
+				SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchMember, std::to_string(*id) });
+				return;
+			}
+		}
+
+		if (_config.max_rooms != 0 && _rooms.size() >= _config.max_rooms) {
+			SendTo(conn->id, Proto::ServerFull{ Proto::ServerLimit::Rooms, full_retry_after });
+			return;
+		}
+
+		std::string from = room->id; // room may close once its last member is out
+		std::string code = UniqueRoomCode();
+		Room& split = _rooms.emplace(code, Room{ code }).first->second;
+		SendTo(conn->id, Proto::RoomSplit{ code, (uint32_t)msg.members.size() });
+		for (uint32_t id : msg.members)
+			MoveMember(_connections.at(id), split, conn->id);
+
+		spdlog::info("Room {} split {} member(s) off into room {}", from, msg.members.size(), code);
+	}
+
+	void Server::HandleMergeRoom(const std::shared_ptr<Connection>& conn, const Proto::MergeRoom& msg)
+	{
+		Room* room = HostRoom(conn);
+		if (!room)
+			return;
+
+		auto it = _rooms.find(msg.id);
+		if (it == _rooms.end() || it->second.host == 0) { // an empty room has nobody to answer
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchRoom, msg.id });
+			return;
+		}
+
+		Room& other = it->second;
+		if (&other == room) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::AlreadyInRoom, room->id });
+			return;
+		}
+
+		other.merge_offers[room->id] = conn->id; // asking again just asks again
+		SendTo(other.host, Proto::MergeRequested{ room->id, conn->id, (uint32_t)room->members.size() });
+
+		spdlog::info("Client {} asked to merge room {} into room {}", conn->id, other.id, room->id);
+	}
+
+	// All or nothing: every member of this room fits in the other, or
+	// nobody moves.
+	void Server::HandleAnswerMerge(const std::shared_ptr<Connection>& conn, const Proto::AnswerMerge& msg)
 	{
 		Room* room = HostRoom(conn);
 		if (!room)
 			return;
 
+		auto offer = room->merge_offers.find(msg.into);
+		if (offer == room->merge_offers.end()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchRoom, msg.into });
+			return;
+		}
+
+		uint32_t asker = offer->second;
+		room->merge_offers.erase(offer);
+		auto it = _rooms.find(msg.into);
+		if (it == _rooms.end() || it->second.host != asker) { // gone, or someone else hosts it now
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchRoom, msg.into });
+			return;
+		}
+
+		Room& into = it->second;
+		if (!msg.accept) {
+			SendTo(asker, Proto::MergeAnswered{ room->id, false });
+			return;
+		}
+
+		for (uint32_t id : room->members) {
+			if (std::ranges::find(into.banned_members, id) != into.banned_members.end()) {
+				SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::Banned, into.id });
+				return;
+			}
+		}
+
+		size_t players = into.members.size() - into.spectators.size();
+		size_t joining = room->members.size() - room->spectators.size();
+		if (into.max_players != 0 && players + joining > into.max_players) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::RoomFull, "players" });
+			return;
+		}
+		if (into.max_spectators != 0 && into.spectators.size() + room->spectators.size() > into.max_spectators) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::RoomFull, "spectators" });
+			return;
+		}
+
+		std::string from = room->id; // room closes once its last member is out
+		std::vector<uint32_t> moving = room->members;
+		std::ranges::stable_partition(moving, [&](uint32_t id) { return id != conn->id; }); // the host last, so the room never changes hands on the way out
+		SendTo(asker, Proto::MergeAnswered{ from, true });
+		for (uint32_t id : moving)
+			MoveMember(_connections.at(id), into, conn->id);
+
+		spdlog::info("Room {} merged into room {}", from, into.id);
+	}
+
+	void Server::HandleSetRoomJoinable(const std::shared_ptr<Connection>& conn, const Proto::SetRoomJoinable& msg)
+	{
+		Room* room = RankedRoom(conn, Proto::MemberRole::CoHost);
+		if (!room)
+			return;
+
 		if (room->open == msg.open)
 			return; // unchanged, nothing to announce
 
 		room->open = msg.open;
 		SendToMany(room->members, Proto::RoomAccessChanged{ room->open, !room->password.empty() });
+		AddToTimeline(*room, room->open ? Proto::TimelineKind::Opened : Proto::TimelineKind::Closed, 0, conn->id);
 
 		spdlog::info("Room {} is now {}", room->id, room->open ? "open" : "closed");
 	}
@@ -529,42 +1653,1857 @@ namespace Weyvelength {
 			SendToMany(room->members, Proto::RoomAccessChanged{ room->open, !room->password.empty() });
 	}
 
-	void Server::LeaveRoom(const std::shared_ptr<Connection>& conn)
+	void Server::HandleSetMemberRole(const std::shared_ptr<Connection>& conn, const Proto::SetMemberRole& msg)
 	{
-		if (conn->room.empty())
+		Room* room = HostRoom(conn);
+		if (!room)
 			return;
 
-		auto it = _rooms.find(conn->room);
-		if (it != _rooms.end()) {
-			Room& room = it->second;
-			std::erase(room.members, conn->id);
-			room.member_data.erase(conn->id);
-			if (room.members.empty()) {
-				_rooms.erase(it);
-				spdlog::info("Room {} closed", conn->room);
-			}
-			else {
-				SendToMany(room.members, Proto::PeerLeft{ conn->id });
-				if (room.host == conn->id) {
-					room.host = room.members.front(); // oldest remaining member
-					SendToMany(room.members, Proto::HostChanged{ room.host });
-					spdlog::info("Client {} now hosts room {}", room.host, conn->room);
-				}
-			}
+		if (msg.id == room->host || std::ranges::find(room->members, msg.id) == room->members.end()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchMember, std::to_string(msg.id) });
+			return;
 		}
-		conn->room.clear();
+
+		if (msg.role > Proto::MemberRole::CoHost) { // ranked by value: anything past CoHost would outrank the host
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadRole, std::to_string((int)msg.role) });
+			return;
+		}
+
+		auto it = room->roles.find(msg.id);
+		Proto::MemberRole current = it == room->roles.end() ? Proto::MemberRole::Member : it->second;
+		if (current == msg.role)
+			return; // unchanged, nothing to announce
+
+		if (msg.role == Proto::MemberRole::Member)
+			room->roles.erase(it);
+		else
+			room->roles[msg.id] = msg.role;
+		SendToMany(room->members, Proto::MemberRoleChanged{ msg.id, msg.role });
+
+		spdlog::info("Client {} is now role {} in room {}", msg.id, (int)msg.role, room->id);
 	}
 
-	void Server::SendTo(uint32_t id, const Proto::ServerMessage& msg)
+	// Like searchable: anonymous and guest connections keep them for as long
+	// as they are connected, an account keeps them in the store.
+	void Server::HandleSetPreferences(const std::shared_ptr<Connection>& conn, const Proto::SetPreferences& msg)
 	{
-		SendFrame(id, Proto::FrameMessage(msg));
+		if (msg.prefs.whispers > Proto::WhisperPolicy::Nobody) { // would be saved, then reset to Everyone on the next load
+			SendTo(conn->id, Proto::AccountError{ Proto::AccountErrorCode::BadPreferences, std::to_string((int)msg.prefs.whispers) });
+			return;
+		}
+
+		if (!conn->name.empty() && !conn->guest && !_accounts.SetPreferences(conn->name, msg.prefs)) {
+			SendTo(conn->id, Proto::AccountError{ Proto::AccountErrorCode::StoreFailed, conn->name });
+			SendTo(conn->id, Proto::PreferencesChanged{ conn->prefs });
+			return;
+		}
+
+		conn->prefs = msg.prefs;
+		SendTo(conn->id, Proto::PreferencesChanged{ conn->prefs }); // echoed, so the client cache has one source
 	}
 
-	void Server::SendToMany(const std::vector<uint32_t>& ids, const Proto::ServerMessage& msg)
+	void Server::HandleSetRoomCapacity(const std::shared_ptr<Connection>& conn, const Proto::SetRoomCapacity& msg)
 	{
-		std::vector<std::byte> frame = Proto::FrameMessage(msg);   // serialize once
+		Room* room = HostRoom(conn);
+		if (!room)
+			return;
 
-		for (uint32_t id : ids) {
+		if (room->max_players == msg.max_players && room->max_spectators == msg.max_spectators)
+			return; // unchanged, nothing to announce
+
+		room->max_players = msg.max_players;
+		room->max_spectators = msg.max_spectators;
+		SendToMany(room->members, Proto::RoomCapacityChanged{ room->max_players, room->max_spectators });
+
+		spdlog::info("Room {} capacity is now {} players, {} spectators", room->id, room->max_players, room->max_spectators);
+	}
+
+	void Server::HandleSetIdlePolicy(const std::shared_ptr<Connection>& conn, const Proto::SetIdlePolicy& msg)
+	{
+		Room* room = HostRoom(conn);
+		if (!room)
+			return;
+
+		if (room->afk_after == msg.afk_after && room->afk_spectate == msg.spectate)
+			return; // unchanged, nothing to announce
+
+		room->afk_after = msg.afk_after;
+		room->afk_spectate = msg.spectate;
+		SendToMany(room->members, Proto::IdlePolicyChanged{ room->afk_after, room->afk_spectate });
+
+		spdlog::info("Room {} flags members AFK after {}s{}", room->id, room->afk_after, room->afk_spectate ? ", moving them to the spectators" : "");
+	}
+
+	void Server::HandleSetRoomBanner(const std::shared_ptr<Connection>& conn, const Proto::SetRoomBanner& msg)
+	{
+		Room* room = HostRoom(conn);
+		if (!room)
+			return;
+
+		if (!msg.id.empty() && !_blobs.Has(msg.id)) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadBanner, msg.id.substr(0, Proto::blob_id_size) });
+			return;
+		}
+
+		if (room->banner == msg.id)
+			return; // unchanged, nothing to announce
+
+		room->banner = msg.id;
+		SendToMany(room->members, Proto::RoomBannerChanged{ room->banner });
+
+		spdlog::info("Room {} banner is now {}", room->id, room->banner.empty() ? "none" : room->banner);
+	}
+
+	void Server::HandleSetRoomRules(const std::shared_ptr<Connection>& conn, const Proto::SetRoomRules& msg)
+	{
+		Room* room = HostRoom(conn);
+		if (!room)
+			return;
+
+		if (msg.rules.size() > Proto::max_room_rules) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadRoomData, "rules" });
+			return;
+		}
+
+		if (room->rules == msg.rules)
+			return; // unchanged, nothing to announce
+
+		room->rules = msg.rules;
+		room->rules_version++;
+		SendToMany(room->members, Proto::RoomRulesChanged{ room->rules_version, room->rules });
+
+		spdlog::info("Room {} rules are at version {}, {} bytes", room->id, room->rules_version, room->rules.size());
+	}
+
+	void Server::HandleStartCountdown(const std::shared_ptr<Connection>& conn, const Proto::StartCountdown& msg)
+	{
+		Room* room = HostRoom(conn);
+		if (!room)
+			return;
+
+		if (msg.seconds > Proto::max_countdown) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadCountdown, std::to_string(Proto::max_countdown) });
+			return;
+		}
+
+		uint64_t now = UnixNowMs();
+		if (msg.seconds == 0 && room->countdown.start_at <= now)
+			return; // nothing running to cancel
+
+		uint32_t remaining = msg.seconds * 1000;
+		room->countdown = { room->countdown.id + 1, msg.seconds, now + remaining, remaining, conn->id };
+		SendToMany(room->members, room->countdown);
+
+		spdlog::info("Room {} countdown {}: {}", room->id, room->countdown.id, msg.seconds == 0 ? "cancelled" : std::to_string(msg.seconds) + "s");
+	}
+
+	void Server::HandleSetMatchPaused(const std::shared_ptr<Connection>& conn, const Proto::SetMatchPaused& msg)
+	{
+		Room* room = HostRoom(conn);
+		if (!room)
+			return;
+
+		if (room->paused == msg.paused)
+			return; // unchanged, nothing to announce
+
+		room->paused = msg.paused;
+		room->paused_by = conn->id;
+		if (room->paused && room->countdown.start_at > UnixNowMs()) {
+			room->countdown = { room->countdown.id + 1, 0, 0, 0, conn->id };
+			SendToMany(room->members, room->countdown);
+		}
+		SendToMany(room->members, Proto::MatchPausedChanged{ room->paused, conn->id });
+
+		spdlog::info("Room {} {}", room->id, room->paused ? "paused" : "resumed");
+	}
+
+	// Kept as the sender's latest for GetSessionHealth, and passed to the host
+	// as it came, bar the links past max_health_links. Reports are not
+	// replayed to a backgrounded host: the next one is never more than an
+	// interval away.
+	void Server::HandleReportHealth(const std::shared_ptr<Connection>& conn, const Proto::ReportHealth& msg)
+	{
+		auto it = _rooms.find(conn->room);
+		if (it == _rooms.end())
+			return; // no reply either way: a report racing a leave is not an error
+
+		auto now = std::chrono::steady_clock::now();
+		if (now - conn->last_health < std::chrono::seconds(Proto::health_report_min_gap))
+			return;
+		conn->last_health = now;
+
+		Proto::MemberHealth health{ conn->id, msg.server_rtt, msg.links };
+		if (health.links.size() > Proto::max_health_links)
+			health.links.resize(Proto::max_health_links);
+
+		if (msg.share) {
+			_health.reports++;
+			if (health.server_rtt != 0) {
+				_health.server_rtt += health.server_rtt;
+				_health.rtt_reports++;
+			}
+			for (const auto& link : health.links) {
+				_health.links++;
+				_health.direct += link.direct;
+				_health.relayed += link.relayed;
+				if (link.loss_permille != 0) {
+					_health.loss_permille += std::min<uint32_t>(link.loss_permille, 1000);
+					_health.lossy_links++;
+				}
+			}
+		}
+
+		Room& room = it->second;
+		room.health[conn->id] = { health, UnixNow() };
+		if (room.host == conn->id)
+			return;
+		auto host = _connections.find(room.host);
+		if (host == _connections.end() || host->second->background)
+			return;
+		SendTo(room.host, health);
+	}
+
+	// What the session's members last said about their links: enough to tell
+	// a struggling relay from one member's bad WiFi without asking for logs.
+	void Server::HandleGetSessionHealth(const std::shared_ptr<Connection>& conn)
+	{
+		Room* room = RankedRoom(conn, Proto::MemberRole::CoHost);
+		if (!room)
+			return;
+
+		uint64_t now = UnixNow();
+		size_t links = 0;
+		Proto::SessionHealth reply;
+		for (uint32_t id : room->members) {
+			auto it = room->health.find(id);
+			if (it == room->health.end())
+				continue;
+			const auto& [health, received] = it->second;
+			if (links + health.links.size() > Proto::max_session_health_links)
+				break;
+			links += health.links.size();
+			reply.reports.push_back({ health, (uint32_t)std::min<uint64_t>(now - received, UINT32_MAX) });
+		}
+		SendTo(conn->id, reply);
+	}
+
+	void Server::HandleGetJoinReceipts(const std::shared_ptr<Connection>& conn)
+	{
+		Room* room = RankedRoom(conn, Proto::MemberRole::CoHost);
+		if (!room)
+			return;
+
+		SendTo(conn->id, Proto::JoinReceipts{ _receipts.PublicKey(), { room->receipts.begin(), room->receipts.end() } });
+	}
+
+	void Server::HandleSetHostState(const std::shared_ptr<Connection>& conn, Proto::SetHostState&& msg)
+	{
+		Room* room = HostRoom(conn);
+		if (!room)
+			return;
+
+		if (msg.state.size() > Proto::max_host_state) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadRoomData, "host state" });
+			return;
+		}
+
+		room->host_state = std::move(msg.state);
+		room->host_state_saved = room->host_state.empty() ? 0 : UnixNow();
+	}
+
+	// Only members sent HostStateWanted since the last hand-off may answer,
+	// once each, so nobody can feed the host a view mid-game or flood it.
+	void Server::HandleReportHostState(const std::shared_ptr<Connection>& conn, Proto::ReportHostState&& msg)
+	{
+		auto it = _rooms.find(conn->room);
+		if (it == _rooms.end()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NotInRoom, {} });
+			return;
+		}
+
+		if (msg.state.size() > Proto::max_host_state) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadRoomData, "host state" });
+			return;
+		}
+
+		Room& room = it->second;
+		if (!room.awaiting_host_state.erase(conn->id)) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadRoomData, "unasked" });
+			return;
+		}
+
+		SendTo(room.host, Proto::MemberHostState{ conn->id, std::move(msg.state) });
+	}
+
+	void Server::HandleSetSuccession(const std::shared_ptr<Connection>& conn, Proto::SetSuccession&& msg)
+	{
+		Room* room = HostRoom(conn);
+		if (!room)
+			return;
+
+		if (!Proto::KnownSuccession(msg.policy)) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadSuccession, std::to_string((int)msg.policy) });
+			return;
+		}
+
+		if (msg.successors.size() > Proto::max_successors) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadRoomData, "successors" });
+			return;
+		}
+
+		if (room->succession == msg.policy && room->successors == msg.successors)
+			return; // unchanged, nothing to announce
+
+		room->succession = msg.policy;
+		room->successors = std::move(msg.successors);
+		SendToMany(room->members, Proto::SuccessionChanged{ room->succession, room->successors });
+
+		spdlog::info("Room {} succession policy is now {}", room->id, (int)room->succession);
+	}
+
+	static Proto::Team* FindTeam(Room& room, uint32_t team)
+	{
+		auto it = std::ranges::find(room.teams, team, &Proto::Team::id);
+		return it == room.teams.end() ? nullptr : &*it;
+	}
+
+	// Takes id off whichever team holds it; false if none did.
+	static bool DropFromTeam(Room& room, uint32_t id)
+	{
+		for (Proto::Team& team : room.teams) {
+			if (std::erase(team.members, id) > 0)
+				return true;
+		}
+		return false;
+	}
+
+	void Server::HandleCreateTeam(const std::shared_ptr<Connection>& conn, const Proto::CreateTeam& msg)
+	{
+		Room* room = HostRoom(conn);
+		if (!room)
+			return;
+
+		std::string name = msg.name;
+		if (!CleanChat(conn, name))
+			return;
+
+		if (name.size() > Proto::max_team_name) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadTeam, "name" });
+			return;
+		}
+
+		if (room->teams.size() >= Proto::max_room_teams) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadTeam, "count" });
+			return;
+		}
+
+		room->teams.push_back({ room->next_team++, std::move(name), msg.max_size, {} });
+		SendToMany(room->members, Proto::TeamsChanged{ room->teams });
+
+		spdlog::info("Room {} has team {} now, {} seats", room->id, room->teams.back().id, msg.max_size);
+	}
+
+	void Server::HandleRemoveTeam(const std::shared_ptr<Connection>& conn, const Proto::RemoveTeam& msg)
+	{
+		Room* room = HostRoom(conn);
+		if (!room)
+			return;
+
+		if (std::erase_if(room->teams, [&](const Proto::Team& team) { return team.id == msg.team; }) == 0) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchTeam, std::to_string(msg.team) });
+			return;
+		}
+
+		SendToMany(room->members, Proto::TeamsChanged{ room->teams });
+	}
+
+	void Server::HandleAssignTeam(const std::shared_ptr<Connection>& conn, const Proto::AssignTeam& msg)
+	{
+		Room* room = HostRoom(conn);
+		if (!room)
+			return;
+
+		if (std::ranges::find(room->members, msg.id) == room->members.end()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchMember, std::to_string(msg.id) });
+			return;
+		}
+
+		Proto::Team* team = nullptr;
+		if (msg.team != 0) {
+			team = FindTeam(*room, msg.team);
+			if (!team) {
+				SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchTeam, std::to_string(msg.team) });
+				return;
+			}
+			if (std::ranges::find(room->spectators, msg.id) != room->spectators.end()) {
+				SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadTeam, "spectator" });
+				return;
+			}
+			if (std::ranges::find(team->members, msg.id) != team->members.end())
+				return; // already on it, nothing to announce
+			if (team->max_size != 0 && team->members.size() >= team->max_size) {
+				SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::TeamFull, std::to_string(msg.team) });
+				return;
+			}
+		}
+
+		if (!DropFromTeam(*room, msg.id) && !team)
+			return; // on no team already
+		if (team)
+			team->members.push_back(msg.id);
+		SendToMany(room->members, Proto::TeamsChanged{ room->teams });
+	}
+
+	static Proto::Channel* FindChannel(Room& room, uint32_t channel)
+	{
+		auto it = std::ranges::find(room.channels, channel, &Proto::Channel::id);
+		return it == room.channels.end() ? nullptr : &*it;
+	}
+
+	static Proto::Channel* ChannelOf(Room& room, uint32_t id)
+	{
+		auto it = std::ranges::find_if(room.channels, [&](const Proto::Channel& channel) { return std::ranges::find(channel.members, id) != channel.members.end(); });
+		return it == room.channels.end() ? nullptr : &*it;
+	}
+
+	static bool DropFromChannel(Room& room, uint32_t id)
+	{
+		Proto::Channel* channel = ChannelOf(room, id);
+		return channel && std::erase(channel->members, id) > 0;
+	}
+
+	void Server::HandleCreateChannel(const std::shared_ptr<Connection>& conn, const Proto::CreateChannel& msg)
+	{
+		Room* room = HostRoom(conn);
+		if (!room)
+			return;
+
+		std::string name = msg.name;
+		if (!CleanChat(conn, name))
+			return;
+
+		if (name.size() > Proto::max_channel_name) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadChannel, "name" });
+			return;
+		}
+
+		if (room->channels.size() >= Proto::max_room_channels) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadChannel, "count" });
+			return;
+		}
+
+		room->channels.push_back({ room->next_channel++, std::move(name), {} });
+		SendToMany(room->members, Proto::ChannelsChanged{ room->channels });
+	}
+
+	void Server::HandleRemoveChannel(const std::shared_ptr<Connection>& conn, const Proto::RemoveChannel& msg)
+	{
+		Room* room = HostRoom(conn);
+		if (!room)
+			return;
+
+		if (std::erase_if(room->channels, [&](const Proto::Channel& channel) { return channel.id == msg.channel; }) == 0) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchChannel, std::to_string(msg.channel) });
+			return;
+		}
+
+		SendToMany(room->members, Proto::ChannelsChanged{ room->channels });
+	}
+
+	// Members come and go from breakouts on their own; moving anyone else
+	// is the host's.
+	void Server::HandlePlaceInChannel(const std::shared_ptr<Connection>& conn, const Proto::PlaceInChannel& msg)
+	{
+		Room* room = nullptr;
+		if (msg.id != conn->id) {
+			room = HostRoom(conn);
+		}
+		else if (auto it = _rooms.find(conn->room); it != _rooms.end()) {
+			room = &it->second;
+		}
+		else {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NotInRoom, {} });
+		}
+		if (!room)
+			return;
+
+		if (std::ranges::find(room->members, msg.id) == room->members.end()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchMember, std::to_string(msg.id) });
+			return;
+		}
+
+		Proto::Channel* channel = nullptr;
+		if (msg.channel != 0) {
+			channel = FindChannel(*room, msg.channel);
+			if (!channel) {
+				SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchChannel, std::to_string(msg.channel) });
+				return;
+			}
+			if (std::ranges::find(channel->members, msg.id) != channel->members.end())
+				return; // in it already, nothing to announce
+		}
+
+		if (!DropFromChannel(*room, msg.id) && !channel)
+			return; // in no channel already
+		if (channel)
+			channel->members.push_back(msg.id);
+		SendToMany(room->members, Proto::ChannelsChanged{ room->channels });
+	}
+
+	void Server::HandleChannelChat(const std::shared_ptr<Connection>& conn, const Proto::ChannelChat& msg)
+	{
+		auto it = _rooms.find(conn->room);
+		if (it == _rooms.end()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NotInRoom, {} });
+			return;
+		}
+
+		Proto::Channel* channel = ChannelOf(it->second, conn->id);
+		if (!channel) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchChannel, "0" });
+			return;
+		}
+
+		std::string text = msg.text;
+		if (!CleanChat(conn, text))
+			return;
+
+		SendToMany(channel->members, Proto::ChannelChat{ conn->id, std::move(text) }); // no link previews: those go to the whole room
+	}
+
+	void Server::HandleBalanceTeams(const std::shared_ptr<Connection>& conn)
+	{
+		Room* room = HostRoom(conn);
+		if (!room || room->teams.empty())
+			return;
+
+		std::vector<uint32_t> players;
+		for (uint32_t member : room->members) {
+			if (std::ranges::find(room->spectators, member) == room->spectators.end())
+				players.push_back(member);
+		}
+
+		Proto::DealTeams(room->teams, players);
+		SendToMany(room->members, Proto::TeamsChanged{ room->teams });
+	}
+
+	void Server::MarkActive(const std::shared_ptr<Connection>& conn)
+	{
+		conn->last_active = std::chrono::steady_clock::now();
+		if (!conn->afk)
+			return;
+
+		conn->afk = false;
+		auto it = _rooms.find(conn->room);
+		if (it != _rooms.end())
+			SendToMany(it->second.members, Proto::MemberAfk{ conn->id, false });
+	}
+
+	void Server::SweepIdle()
+	{
+		auto now = std::chrono::steady_clock::now();
+		for (auto& [id, room] : _rooms) {
+			if (room.afk_after == 0)
+				continue;
+
+			for (uint32_t member : room.members) {
+				auto it = _connections.find(member);
+				if (it == _connections.end() || it->second->afk || now - it->second->last_active < std::chrono::seconds(room.afk_after))
+					continue;
+
+				it->second->afk = true;
+				SendToMany(room.members, Proto::MemberAfk{ member, true });
+
+				// past the spectator limit on purpose: the seat is being freed, not taken
+				if (room.afk_spectate && member != room.host && std::ranges::find(room.spectators, member) == room.spectators.end()) {
+					room.spectators.push_back(member);
+					SendToMany(room.members, Proto::MemberSpectating{ member });
+					if (DropFromTeam(room, member))
+						SendToMany(room.members, Proto::TeamsChanged{ room.teams });
+				}
+			}
+		}
+	}
+
+	// Who a booking or an RSVP belongs to. An account is held by name, so
+	// it keeps both across reconnects and gets the room's invitation on
+	// whichever connection it is logged in on. Anyone else is held by the
+	// connection alone (a guest comes back under a new suffix), and loses
+	// both when it closes.
+	static std::string BookingHolder(const Connection& conn)
+	{
+		return conn.guest || conn.name.empty() ? "#" + std::to_string(conn.id) : conn.name;
+	}
+
+	uint32_t Server::HolderId(const std::string& holder) const
+	{
+		if (holder.starts_with('#'))
+			return (uint32_t)std::strtoul(holder.c_str() + 1, nullptr, 10);
+
+		auto it = std::ranges::find_if(_connections, [&](const auto& entry) { return !entry.second->guest && entry.second->name == holder; });
+		return it == _connections.end() ? 0 : it->first;
+	}
+
+	Proto::ScheduledRoom Server::Info(const Booking& booking) const
+	{
+		Proto::ScheduledRoom info = booking.info;
+		info.owner = HolderId(booking.owner);
+		return info;
+	}
+
+	void Server::HandleScheduleRoom(const std::shared_ptr<Connection>& conn, const Proto::ScheduleRoom& msg)
+	{
+		uint64_t now = UnixNow();
+		if (msg.start <= now || msg.start > now + Proto::max_schedule_ahead) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadSchedule, "start" });
+			return;
+		}
+
+		if (msg.title.size() > Proto::max_schedule_title) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadSchedule, "title" });
+			return;
+		}
+
+		if (_bookings.size() >= Proto::max_scheduled_rooms) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadSchedule, "full" });
+			return;
+		}
+
+		// by address too: an account's bookings outlive its connection, so a reconnect would start the count over
+		std::string holder = BookingHolder(*conn);
+		auto held = std::ranges::count_if(_bookings | std::views::values, [&](const Booking& booking) {
+			return booking.owner == holder || booking.address == conn->address;
+		});
+		if (held >= Proto::max_owner_bookings) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadSchedule, "owner" });
+			return;
+		}
+
+		if (msg.password.size() > Proto::max_room_password) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadPassword, {} });
+			return;
+		}
+
+		uint32_t id = _next_booking++;
+		Booking& booking = _bookings[id];
+		booking.info = { id, 0, msg.start, msg.title, msg.max_players, msg.max_spectators, !msg.password.empty() };
+		booking.owner = holder;
+		booking.password = msg.password;
+		booking.address = conn->address;
+		SendTo(conn->id, Proto::ScheduledRoomChanged{ Info(booking) });
+
+		spdlog::info("Client {} booked room {} for {}", conn->id, id, msg.start);
+	}
+
+	void Server::HandleListScheduledRooms(const std::shared_ptr<Connection>& conn)
+	{
+		Proto::ScheduledRoomList list;
+		for (const auto& [id, booking] : _bookings) {
+			list.rooms.push_back(Info(booking));
+		}
+		std::ranges::sort(list.rooms, {}, &Proto::ScheduledRoom::start);
+		SendTo(conn->id, list);
+	}
+
+	void Server::HandleRsvpScheduledRoom(const std::shared_ptr<Connection>& conn, const Proto::RsvpScheduledRoom& msg)
+	{
+		auto it = _bookings.find(msg.id);
+		if (it == _bookings.end()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchSchedule, std::to_string(msg.id) });
+			return;
+		}
+
+		if (msg.status > Proto::RsvpStatus::Maybe) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadSchedule, "rsvp" });
+			return;
+		}
+
+		Booking& booking = it->second;
+		if (msg.status == Proto::RsvpStatus::No)
+			booking.rsvps.erase(BookingHolder(*conn));
+		else
+			booking.rsvps[BookingHolder(*conn)] = msg.status;
+		RsvpsChanged(booking);
+		SendTo(conn->id, Proto::ScheduledRoomChanged{ Info(booking) });
+	}
+
+	// Accounts that are offline have no id to list, but still count in
+	// going and are still invited when the room opens.
+	Proto::ScheduledRoomAttendees Server::Attendees(const Booking& booking) const
+	{
+		Proto::ScheduledRoomAttendees list{ booking.info.id };
+		for (const auto& [holder, status] : booking.rsvps) {
+			if (uint32_t id = HolderId(holder))
+				list.attendees.push_back({ id, status });
+		}
+		std::ranges::sort(list.attendees, {}, &Proto::Attendee::id);
+		return list;
+	}
+
+	void Server::HandleListAttendees(const std::shared_ptr<Connection>& conn, const Proto::ListAttendees& msg)
+	{
+		auto it = _bookings.find(msg.id);
+		if (it == _bookings.end()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchSchedule, std::to_string(msg.id) });
+			return;
+		}
+
+		SendTo(conn->id, Attendees(it->second));
+	}
+
+	void Server::HandleCancelScheduledRoom(const std::shared_ptr<Connection>& conn, const Proto::CancelScheduledRoom& msg)
+	{
+		auto it = _bookings.find(msg.id);
+		if (it == _bookings.end()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchSchedule, std::to_string(msg.id) });
+			return;
+		}
+
+		Booking& booking = it->second;
+		if (booking.owner != BookingHolder(*conn)) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NotHost, std::to_string(msg.id) });
+			return;
+		}
+
+		spdlog::info("Client {} cancelled booking {}", conn->id, msg.id);
+		CancelBooking(it);
+	}
+
+	void Server::CancelBooking(std::map<uint32_t, Booking>::iterator it)
+	{
+		const Booking& booking = it->second;
+		std::vector<uint32_t> told;
+		if (uint32_t owner = HolderId(booking.owner))
+			told.push_back(owner);
+		for (const auto& [holder, status] : booking.rsvps) {
+			uint32_t id = HolderId(holder);
+			if (id != 0 && std::ranges::find(told, id) == told.end())
+				told.push_back(id);
+		}
+		SendToMany(told, Proto::ScheduledRoomCancelled{ booking.info.id });
+		_bookings.erase(it);
+	}
+
+	void Server::RsvpsChanged(Booking& booking)
+	{
+		booking.info.going = (uint32_t)std::ranges::count(booking.rsvps | std::views::values, Proto::RsvpStatus::Going);
+		if (uint32_t owner = HolderId(booking.owner))
+			SendTo(owner, Attendees(booking));
+	}
+
+	void Server::ForgetBookings(const Connection& conn)
+	{
+		std::string holder = BookingHolder(conn);
+		if (!holder.starts_with('#'))
+			return; // the account's, and waiting for it to come back
+
+		for (auto it = _bookings.begin(); it != _bookings.end();) {
+			auto next = std::next(it);
+			if (it->second.owner == holder) {
+				spdlog::info("Booking {} went with client {}", it->first, conn.id);
+				CancelBooking(it);
+			}
+			else if (it->second.rsvps.erase(holder)) {
+				RsvpsChanged(it->second);
+			}
+			it = next;
+		}
+	}
+
+	// Booked or RSVPed anonymously, then logged in: the account takes them
+	// over, so they survive the connection.
+	void Server::AdoptBookings(const Connection& conn)
+	{
+		std::string was = "#" + std::to_string(conn.id);
+		std::string holder = BookingHolder(conn);
+		if (holder == was)
+			return;
+
+		for (auto& [id, booking] : _bookings) {
+			if (booking.owner == was)
+				booking.owner = holder;
+			if (auto rsvp = booking.rsvps.extract(was))
+				booking.rsvps[holder] = rsvp.mapped();
+		}
+	}
+
+	void Server::SweepSchedules()
+	{
+		uint64_t now = UnixNow();
+		for (auto it = _bookings.begin(); it != _bookings.end();) {
+			Booking& booking = it->second;
+			if (!booking.reminded && now + Proto::schedule_reminder_lead >= booking.info.start) {
+				booking.reminded = true;
+				for (const auto& [holder, status] : booking.rsvps) {
+					uint32_t id = HolderId(holder);
+					if (id != 0 && status == Proto::RsvpStatus::Going)
+						SendTo(id, Proto::ScheduledRoomReminder{ booking.info.id, booking.info.start });
+				}
+			}
+
+			if (now < booking.info.start) {
+				++it;
+				continue;
+			}
+
+			// opens empty: host 0 until the first join claims it
+			std::string code = UniqueRoomCode();
+			Room& room = _rooms.emplace(code, Room{ code }).first->second;
+			room.password = booking.password;
+			room.max_players = booking.info.max_players;
+			room.max_spectators = booking.info.max_spectators;
+			room.expires = now + booked_room_grace;
+			std::vector<uint32_t> told;
+			auto invite = [&](const std::string& holder) {
+				if (!holder.starts_with('#'))
+					room.invited_accounts.insert(holder); // on any connection, now or later
+				uint32_t id = HolderId(holder);
+				if (id != 0 && std::ranges::find(told, id) == told.end()) {
+					told.push_back(id);
+					room.invited.push_back(id);
+				}
+			};
+			invite(booking.owner);
+			for (const auto& [holder, status] : booking.rsvps)
+				invite(holder);
+
+			SendToMany(told, Proto::ScheduledRoomOpened{ booking.info.id, code });
+
+			spdlog::info("Booking {} opened as room {}", booking.info.id, code);
+			it = _bookings.erase(it);
+		}
+
+		for (auto it = _rooms.begin(); it != _rooms.end();) {
+			Room& room = it->second;
+			if (!room.members.empty() || room.expires == 0 || now < room.expires) {
+				++it;
+				continue;
+			}
+			CloseQueue(room);
+			it = _rooms.erase(it);
+		}
+	}
+
+	// The shape checks on a registration; the name may still be taken. A
+	// guest may also register the suffixed name it already holds.
+	static bool CheckNewAccount(const Proto::CreateAccount& msg, const Connection& conn, Proto::AccountErrorCode& error)
+	{
+		bool keeping = conn.guest && msg.name == conn.name;
+		if (!keeping && !Proto::ValidAccountName(msg.name)) {
+			error = Proto::AccountErrorCode::BadName;
+			return false;
+		}
+
+		if (msg.password.size() < Proto::min_account_password || msg.password.size() > Proto::max_account_password) {
+			error = Proto::AccountErrorCode::BadPassword;
+			return false;
+		}
+
+		// control characters (the store's tab and newline among them) never make a real address
+		bool garbage = std::ranges::any_of(msg.email, [](char c) { return (unsigned char)c < 0x20; });
+		if (msg.email.size() > Proto::max_account_email || garbage || (!msg.email.empty() && msg.email.find('@') == std::string::npos)) {
+			error = Proto::AccountErrorCode::BadEmail;
+			return false;
+		}
+
+		return true;
+	}
+
+	bool Server::AccountsUsable(const std::shared_ptr<Connection>& conn)
+	{
+		if (!_accounts.IsOpen()) {
+			SendTo(conn->id, Proto::AccountError{ Proto::AccountErrorCode::Disabled, {} });
+			return false;
+		}
+
+		if (!conn->name.empty() && !conn->guest) {
+			SendTo(conn->id, Proto::AccountError{ Proto::AccountErrorCode::AlreadyLoggedIn, conn->name });
+			return false;
+		}
+
+		return true;
+	}
+
+	void Server::HandleCreateAccount(const std::shared_ptr<Connection>& conn, const Proto::CreateAccount& msg)
+	{
+		if (!AccountsUsable(conn) || !TakePasswordAttempt(conn))
+			return;
+
+		Proto::AccountErrorCode error;
+		if (!CheckNewAccount(msg, *conn, error)) {
+			SendTo(conn->id, Proto::AccountError{ error, msg.name });
+			return;
+		}
+
+		if (_accounts.Find(msg.name)) { // checked again once hashed; this just skips the work
+			SendTo(conn->id, Proto::AccountError{ Proto::AccountErrorCode::NameTaken, msg.name });
+			return;
+		}
+
+		asio::post(_hashing, [this, conn, before = conn->name, account = Account{ msg.name, msg.email }, password = msg.password]() mutable {
+			account.hash = HashPassword(password);
+			asio::post(_context, [this, conn, before = std::move(before), account = std::move(account)]() mutable {
+				if (conn->closing || conn->name != before)
+					return; // gone, or a second request won the race
+
+				std::string name = account.name;
+				account.hidden = !conn->searchable; // choices made before registering carry over
+				account.prefs = conn->prefs;
+				if (_accounts.Find(name)) {
+					SendTo(conn->id, Proto::AccountError{ Proto::AccountErrorCode::NameTaken, name });
+					return;
+				}
+
+				if (account.hash.empty() || !_accounts.Add(std::move(account))) {
+					SendTo(conn->id, Proto::AccountError{ Proto::AccountErrorCode::StoreFailed, name });
+					return;
+				}
+
+				spdlog::info("Client {} registered account {}", conn->id, name);
+				LogIn(conn, name);
+			});
+		});
+	}
+
+	void Server::HandleLogin(const std::shared_ptr<Connection>& conn, const Proto::Login& msg)
+	{
+		if (!AccountsUsable(conn) || !TakePasswordAttempt(conn))
+			return;
+
+		if (msg.password.size() > Proto::max_account_password) { // says nothing about the name
+			SendTo(conn->id, Proto::AccountError{ Proto::AccountErrorCode::WrongCredentials, {} });
+			return;
+		}
+
+		// an unknown name is checked against a stand-in hash, so it takes as
+		// long to refuse as a wrong password and the timing names no accounts
+		const Account* account = _accounts.Find(msg.name);
+		asio::post(_hashing, [this, conn, before = conn->name, name = account ? account->name : std::string{}, hash = account ? account->hash : std::string{}, password = msg.password] {
+			static const std::string stand_in = HashPassword("no such account");
+			bool ok = VerifyPassword(name.empty() ? stand_in : hash, password) && !name.empty();
+			asio::post(_context, [this, conn, before, name, ok] {
+				if (conn->closing || conn->name != before)
+					return;
+
+				if (!ok) {
+					SendTo(conn->id, Proto::AccountError{ Proto::AccountErrorCode::WrongCredentials, {} });
+					return;
+				}
+
+				bool in_use = std::ranges::any_of(_connections, [&](const auto& entry) { return entry.second->name == name; });
+				if (in_use) {
+					SendTo(conn->id, Proto::AccountError{ Proto::AccountErrorCode::InUse, name });
+					return;
+				}
+
+				LogIn(conn, name);
+			});
+		});
+	}
+
+	void Server::HandleGuestLogin(const std::shared_ptr<Connection>& conn, const Proto::GuestLogin& msg)
+	{
+		if (!conn->name.empty()) {
+			SendTo(conn->id, Proto::AccountError{ Proto::AccountErrorCode::AlreadyLoggedIn, conn->name });
+			return;
+		}
+
+		if (!Proto::ValidAccountName(msg.name)) {
+			SendTo(conn->id, Proto::AccountError{ Proto::AccountErrorCode::BadName, msg.name });
+			return;
+		}
+
+		std::string name = _guests.Claim(msg.name, conn->id, UnixNow(), _accounts);
+		if (name.empty()) {
+			SendTo(conn->id, Proto::AccountError{ Proto::AccountErrorCode::NameTaken, msg.name });
+			return;
+		}
+
+		LogIn(conn, name, true);
+	}
+
+	void Server::LogIn(const std::shared_ptr<Connection>& conn, const std::string& name, bool guest)
+	{
+		if (conn->guest) { // a guest moving to an account: keeps its name, or lets it lapse
+			if (name == conn->name)
+				_guests.Forget(conn->id);
+			else
+				_guests.Release(conn->id, UnixNow());
+		}
+
+		bool renamed = name != conn->name;
+		conn->name = name;
+		conn->guest = guest;
+		AdoptBookings(*conn);
+		if (const Account* account = guest ? nullptr : _accounts.Find(name)) {
+			conn->searchable = !account->hidden;
+			conn->prefs = account->prefs;
+		}
+		SendTo(conn->id, Proto::LoggedIn{ name });
+		SendTo(conn->id, Proto::SearchableChanged{ conn->searchable });
+		SendTo(conn->id, Proto::PreferencesChanged{ conn->prefs });
+		if (!renamed)
+			return; // a guest registering its own name; the room already knows it
+
+		auto it = _rooms.find(conn->room);
+		if (it != _rooms.end())
+			SendToMany(it->second.members, Proto::MemberNameChanged{ conn->id, name });
+
+		spdlog::info("Client {} logged in as {}", conn->id, name);
+	}
+
+	// Anonymous and guest connections keep the choice for as long as they
+	// are connected; an account keeps it in the store.
+	void Server::HandleSetSearchable(const std::shared_ptr<Connection>& conn, const Proto::SetSearchable& msg)
+	{
+		if (!conn->name.empty() && !conn->guest && conn->searchable != msg.searchable) {
+			if (!_accounts.SetHidden(conn->name, !msg.searchable)) {
+				SendTo(conn->id, Proto::AccountError{ Proto::AccountErrorCode::StoreFailed, conn->name });
+				SendTo(conn->id, Proto::SearchableChanged{ conn->searchable });
+				return;
+			}
+		}
+
+		conn->searchable = msg.searchable;
+		SendTo(conn->id, Proto::SearchableChanged{ conn->searchable });
+	}
+
+	// Whoever is online comes first, since they can be whispered or invited
+	// now; accounts nobody is logged in to fill the rest. A room is named
+	// only when anyone could walk into it anyway.
+	void Server::HandleSearchUsers(const std::shared_ptr<Connection>& conn, const Proto::SearchUsers& msg)
+	{
+		Proto::UserSearchResults results{ msg.query };
+		if (msg.query.size() < Proto::min_user_query || msg.query.size() > Proto::max_user_query) {
+			SendTo(conn->id, results);
+			return;
+		}
+
+		struct Found {
+			int rank = 0; // NameMatch, then offline after online
+			Proto::UserMatch user;
+		};
+		std::vector<Found> found;
+		std::set<std::string> online;
+		for (const auto& [id, other] : _connections) {
+			if (other->name.empty() || other->closing)
+				continue;
+			online.insert(other->name);
+			int rank = NameMatch(other->name, msg.query);
+			if (!other->searchable || rank < 0)
+				continue;
+
+			Proto::UserMatch user{ other->name, id };
+			auto room = _rooms.find(other->room);
+			if (room != _rooms.end() && room->second.open && room->second.password.empty())
+				user.room = room->first;
+			found.push_back({ rank, std::move(user) });
+		}
+
+		for (const Account* account : _accounts.Search(msg.query)) {
+			if (!online.contains(account->name))
+				found.push_back({ 2 + NameMatch(account->name, msg.query), { account->name } });
+		}
+
+		std::ranges::sort(found, [](const Found& a, const Found& b) { return std::tie(a.rank, a.user.name) < std::tie(b.rank, b.user.name); });
+		for (auto& entry : found) {
+			if (results.users.size() == Proto::max_user_results)
+				break;
+			results.users.push_back(std::move(entry.user));
+		}
+		SendTo(conn->id, results);
+	}
+
+	// Gathered from every place the server keeps something about a client.
+	// Logged, as a record that the request was met; capped per connection,
+	// since an export walks the schedule and every room.
+	void Server::HandleExportMyData(const std::shared_ptr<Connection>& conn)
+	{
+		uint64_t now = UnixNow();
+		if (conn->exported != 0 && now < conn->exported + Proto::data_export_interval) {
+			SendTo(conn->id, Proto::AccountError{ Proto::AccountErrorCode::TooSoon, std::to_string(conn->exported + Proto::data_export_interval - now) });
+			return;
+		}
+		conn->exported = now;
+
+		Proto::MyData data{ conn->id, conn->name, conn->guest };
+		if (const Account* account = conn->guest || conn->name.empty() ? nullptr : _accounts.Find(conn->name))
+			data.email = account->email;
+		data.searchable = conn->searchable;
+		data.prefs = conn->prefs;
+		data.room = conn->room;
+		data.queued = conn->queued;
+
+		std::string holder = BookingHolder(*conn);
+		for (const auto& [id, booking] : _bookings) {
+			if (booking.owner == holder)
+				data.bookings.push_back(Info(booking));
+			if (auto it = booking.rsvps.find(holder); it != booking.rsvps.end())
+				data.rsvps.push_back({ id, it->second });
+		}
+		for (const auto& [code, room] : _rooms) {
+			if (std::ranges::find(room.knocks, conn->id, &Proto::JoinRequested::id) != room.knocks.end())
+				data.knocks.push_back(code);
+		}
+
+		spdlog::info("Client {} ({}) exported its data", conn->id, conn->name.empty() ? "anonymous" : conn->name);
+		SendTo(conn->id, data);
+	}
+
+	// Joiners already get the whole log with the room state; this fetches it
+	// again from a seq, for an app that keeps its own copy.
+	void Server::HandleGetTimeline(const std::shared_ptr<Connection>& conn, const Proto::GetTimeline& msg)
+	{
+		auto it = _rooms.find(conn->room);
+		if (it == _rooms.end()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NotInRoom, {} });
+			return;
+		}
+
+		Proto::Timeline reply;
+		for (const Proto::TimelineEntry& entry : it->second.timeline) {
+			if (entry.seq > msg.after)
+				reply.entries.push_back(entry);
+		}
+		SendTo(conn->id, reply);
+	}
+
+	void Server::HandleUploadBlob(const std::shared_ptr<Connection>& conn, const Proto::UploadBlob& msg)
+	{
+		if (!_blobs.IsOpen()) {
+			SendTo(conn->id, Proto::BlobError{ msg.transfer, Proto::BlobErrorCode::Disabled, {} });
+			return;
+		}
+
+		if (msg.size == 0 || msg.size > Proto::max_blob_size) {
+			SendTo(conn->id, Proto::BlobError{ msg.transfer, Proto::BlobErrorCode::TooLarge, std::to_string(Proto::max_blob_size) });
+			return;
+		}
+
+		if (conn->uploads.size() >= Proto::max_blob_uploads || conn->uploads.contains(msg.transfer)) {
+			SendTo(conn->id, Proto::BlobError{ msg.transfer, Proto::BlobErrorCode::Busy, {} });
+			return;
+		}
+
+		// each upload sits on disk for blob_grace whether or not anything
+		// refers to it, so both budgets count what was declared, not what
+		// is referenced. Refusals are not counted
+		auto now = std::chrono::steady_clock::now();
+		if (now - conn->upload_window_start >= upload_window) {
+			conn->upload_window_start = now;
+			conn->uploaded = 0;
+		}
+		if (_config.max_upload_bytes != 0 && conn->uploaded + msg.size > _config.max_upload_bytes) {
+			SendTo(conn->id, Proto::BlobError{ msg.transfer, Proto::BlobErrorCode::Quota, "connection" });
+			return;
+		}
+		if (!BlobStoreFits(msg.size)) {
+			SendTo(conn->id, Proto::BlobError{ msg.transfer, Proto::BlobErrorCode::Quota, "server" });
+			return;
+		}
+		conn->uploaded += msg.size;
+
+		BlobUpload& upload = conn->uploads[msg.transfer];
+		upload.size = msg.size;
+		upload.data.reserve(msg.size);
+	}
+
+	bool Server::BlobStoreFits(uint64_t bytes) const
+	{
+		return _config.max_blob_megabytes == 0 || _blobs.Bytes() + bytes <= (uint64_t)_config.max_blob_megabytes * 1024 * 1024;
+	}
+
+	void Server::HandleBlobChunk(const std::shared_ptr<Connection>& conn, const Proto::BlobChunk& msg)
+	{
+		auto it = conn->uploads.find(msg.transfer);
+		if (it == conn->uploads.end()) {
+			SendTo(conn->id, Proto::BlobError{ msg.transfer, Proto::BlobErrorCode::BadTransfer, {} });
+			return;
+		}
+
+		BlobUpload& upload = it->second;
+		if (upload.data.size() + msg.data.size() > upload.size) {
+			conn->uploads.erase(it); // the rest of this transfer answers BadTransfer too
+			SendTo(conn->id, Proto::BlobError{ msg.transfer, Proto::BlobErrorCode::BadTransfer, {} });
+			return;
+		}
+
+		upload.data.insert(upload.data.end(), msg.data.begin(), msg.data.end());
+		if (upload.data.size() < upload.size)
+			return;
+
+		if (!BlobStoreFits(upload.size)) { // others finished first
+			conn->uploads.erase(it);
+			SendTo(conn->id, Proto::BlobError{ msg.transfer, Proto::BlobErrorCode::Quota, "server" });
+			return;
+		}
+
+		std::string id = _blobs.Put(upload.data);
+		conn->uploads.erase(it);
+		if (id.empty()) {
+			SendTo(conn->id, Proto::BlobError{ msg.transfer, Proto::BlobErrorCode::StoreFailed, {} });
+			return;
+		}
+
+		spdlog::debug("Client {} stored blob {}", conn->id, id);
+		SendTo(conn->id, Proto::BlobUploaded{ msg.transfer, id });
+	}
+
+	void Server::HandleDownloadBlob(const std::shared_ptr<Connection>& conn, const Proto::DownloadBlob& msg)
+	{
+		if (!_blobs.IsOpen()) {
+			SendTo(conn->id, Proto::BlobError{ msg.transfer, Proto::BlobErrorCode::Disabled, {} });
+			return;
+		}
+
+		std::vector<std::byte> data;
+		if (!_blobs.Get(msg.id, data)) {
+			SendTo(conn->id, Proto::BlobError{ msg.transfer, Proto::BlobErrorCode::NoSuchBlob, msg.id });
+			return;
+		}
+
+		// all queued at once; WriteLoop streams them out behind anything already pending
+		for (size_t offset = 0; offset < data.size(); offset += Proto::max_blob_chunk) {
+			size_t len = std::min<size_t>(Proto::max_blob_chunk, data.size() - offset);
+			SendTo(conn->id, Proto::BlobChunk{ msg.transfer, { data.begin() + offset, data.begin() + offset + len } });
+		}
+		SendTo(conn->id, Proto::BlobDownloaded{ msg.transfer, msg.id });
+	}
+
+	void Server::HandleSetBackground(const std::shared_ptr<Connection>& conn, const Proto::SetBackground& msg)
+	{
+		conn->background = msg.background;
+		if (msg.background || !conn->missed)
+			return;
+
+		auto it = _rooms.find(conn->room);
+		if (it != _rooms.end())
+			SendRoomState(conn, it->second); // whatever was held back, as it stands now
+	}
+
+	void Server::HandleGetServerInfo(const std::shared_ptr<Connection>& conn)
+	{
+		SendTo(conn->id, Proto::ServerInfo{ (uint32_t)_connections.size(), _config.max_users, (uint32_t)_rooms.size(), _config.max_rooms });
+		SendTo(conn->id, Proto::ClientVersionPolicy{ _config.min_client_version, _config.update_url });
+	}
+
+	// Counted per connection, so one client polling the lists in a tight
+	// loop is refused while everyone else's lookups, and its own signals and
+	// chat, go on as before. Refusals are not counted: waiting out the window
+	// always works.
+	bool Server::TakeLookup(const std::shared_ptr<Connection>& conn)
+	{
+		auto now = std::chrono::steady_clock::now();
+		if (now - conn->lookup_window_start >= lookup_window) {
+			conn->lookup_window_start = now;
+			conn->lookups = 0;
+		}
+		if (_config.max_lookups != 0 && conn->lookups >= _config.max_lookups) {
+			_lookups_refused++;
+			auto left = std::chrono::ceil<std::chrono::seconds>(conn->lookup_window_start + lookup_window - now);
+			SendTo(conn->id, Proto::ServerFull{ Proto::ServerLimit::Requests, (uint32_t)left.count() });
+			return false;
+		}
+		conn->lookups++;
+		_lookups++;
+		return true;
+	}
+
+	static bool TakeAttempt(AttemptWindow& window, uint32_t max, std::chrono::steady_clock::time_point now, uint32_t& left)
+	{
+		if (now - window.start >= password_window) {
+			window.start = now;
+			window.count = 0;
+		}
+		if (window.count >= max) {
+			left = (uint32_t)std::chrono::ceil<std::chrono::seconds>(window.start + password_window - now).count();
+			return false;
+		}
+		window.count++;
+		return true;
+	}
+
+	// Every CreateAccount and Login queues an argon2 run on the one hashing
+	// thread, so they are budgeted per connection and per address alike: a
+	// client guessing passwords, or many connections from one host, would
+	// otherwise hold up everyone else's logins.
+	bool Server::TakePasswordAttempt(const std::shared_ptr<Connection>& conn)
+	{
+		if (_config.max_password_attempts == 0)
+			return true;
+
+		auto now = std::chrono::steady_clock::now();
+		uint32_t left = 0;
+		if (!TakeAttempt(conn->password_attempts, _config.max_password_attempts, now, left) ||
+			!TakeAttempt(_password_attempts[conn->address], _config.max_password_attempts, now, left)) {
+			SendTo(conn->id, Proto::ServerFull{ Proto::ServerLimit::Requests, left });
+			return false;
+		}
+		return true;
+	}
+
+	void Server::SweepAttempts()
+	{
+		auto now = std::chrono::steady_clock::now();
+		std::erase_if(_password_attempts, [&](const auto& entry) { return now - entry.second.start >= password_window; });
+	}
+
+	// Mostly for the log, so anything that could upset a log line goes.
+	void Server::HandleClientInfo(const std::shared_ptr<Connection>& conn, Proto::ClientInfo msg)
+	{
+		for (std::string* field : { &msg.app_version, &msg.platform }) {
+			std::erase_if(*field, [](char c) { return c < 0x20 || c > 0x7e; });
+			if (field->size() > Proto::max_client_info_field)
+				field->resize(Proto::max_client_info_field);
+		}
+		spdlog::info("Client {} is {} on {}, library {}", conn->id,
+			msg.app_version.empty() ? "an unversioned app" : "app " + msg.app_version,
+			msg.platform.empty() ? "an unnamed platform" : msg.platform, msg.library_version);
+		conn->client = std::move(msg);
+
+		const std::string& version = conn->client.app_version;
+		if (_config.min_client_version.empty() || version.empty() || !Proto::OlderVersion(version, _config.min_client_version))
+			return;
+		spdlog::info("Client {} is older than {}; refusing it", conn->id, _config.min_client_version);
+		HangUp(conn, Proto::ClientOutdated{ _config.min_client_version, _config.update_url });
+	}
+
+	bool Server::Invited(const Room& room, uint32_t id) const
+	{
+		if (std::ranges::find(room.invited, id) != room.invited.end())
+			return true;
+
+		auto it = _connections.find(id);
+		return it != _connections.end() && !it->second->guest && room.invited_accounts.contains(it->second->name);
+	}
+
+	// What the lobby may see of a room. Who is in a passworded room, and what
+	// it is set to, is for those with the password, or invited past it.
+	Proto::RoomPreview Server::Preview(const Room& room, uint32_t viewer) const
+	{
+		Proto::RoomPreview preview{ room.id, room.host, room.open, !room.password.empty(),
+			(uint32_t)(room.members.size() - room.spectators.size()), (uint32_t)room.spectators.size(),
+			room.max_players, room.max_spectators, room.banner };
+		if (preview.passworded && !Invited(room, viewer))
+			return preview;
+
+		for (uint32_t id : room.members) {
+			if (preview.members.size() == Proto::max_preview_members)
+				break;
+			auto it = _connections.find(id);
+			bool spectating = std::ranges::find(room.spectators, id) != room.spectators.end();
+			preview.members.push_back({ id, it != _connections.end() ? it->second->name : std::string{}, spectating });
+		}
+
+		size_t budget = Proto::max_preview_data;
+		for (const auto& [key, value] : room.data) {
+			if (key.size() + value.size() > budget)
+				break;
+			budget -= key.size() + value.size();
+			preview.data.push_back({ key, value });
+		}
+		return preview;
+	}
+
+	void Server::HandleGetRoomPreview(const std::shared_ptr<Connection>& conn, const Proto::GetRoomPreview& msg)
+	{
+		conn->watching.clear(); // a new look replaces the old watch either way
+		conn->watched.reset();
+
+		auto it = _rooms.find(msg.id);
+		if (it == _rooms.end()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchRoom, msg.id });
+			return;
+		}
+
+		const Room& room = it->second;
+		if (std::ranges::find(room.banned_members, conn->id) != room.banned_members.end()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::Banned, msg.id });
+			return;
+		}
+
+		auto frame = std::make_shared<const std::vector<std::byte>>(Proto::FrameMessage(Preview(room, conn->id)));
+		SendFrame(conn->id, frame);
+		if (msg.watch) {
+			conn->watching = room.id;
+			conn->watch_until = std::chrono::steady_clock::now() + std::chrono::seconds(Proto::room_preview_watch);
+			conn->watched = std::move(frame);
+		}
+	}
+
+	// The way into a passworded room without the password: the host says yes
+	// and the knocker joins as if invited. A room anyone can walk into says
+	// yes by itself.
+	void Server::HandleRequestJoin(const std::shared_ptr<Connection>& conn, const Proto::RequestJoin& msg)
+	{
+		auto it = _rooms.find(msg.id);
+		if (it == _rooms.end()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchRoom, msg.id });
+			return;
+		}
+
+		Room& room = it->second;
+		if (conn->room == room.id) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::AlreadyInRoom, conn->room });
+			return;
+		}
+
+		if (std::ranges::find(room.banned_members, conn->id) != room.banned_members.end()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::Banned, msg.id });
+			return;
+		}
+
+		if (!room.open || room.host == 0) { // closed, or nobody in to answer
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::RoomClosed, msg.id });
+			return;
+		}
+
+		if (room.password.empty() || Invited(room, conn->id)) {
+			SendTo(conn->id, Proto::JoinRequestAnswered{ room.id, true });
+			return;
+		}
+
+		std::string message = msg.message;
+		if (!message.empty() && !CleanChat(conn, message))
+			return;
+		if (message.size() > Proto::max_knock_message) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::ChatTooLong, std::to_string(Proto::max_knock_message) });
+			return;
+		}
+
+		auto pending = std::ranges::find(room.knocks, conn->id, &Proto::JoinRequested::id);
+		if (pending != room.knocks.end()) {
+			pending->message = std::move(message); // knocking again just updates the note
+			SendTo(room.host, *pending);
+			return;
+		}
+
+		if (room.knocks.size() >= Proto::max_room_knocks) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::RoomFull, "knocks" });
+			return;
+		}
+
+		room.knocks.push_back({ conn->id, conn->name, std::move(message) });
+		SendTo(room.host, room.knocks.back());
+		spdlog::info("Client {} knocked on room {}", conn->id, room.id);
+	}
+
+	void Server::HandleRespondJoinRequest(const std::shared_ptr<Connection>& conn, const Proto::RespondJoinRequest& msg)
+	{
+		Room* room = HostRoom(conn);
+		if (!room)
+			return;
+
+		auto it = std::ranges::find(room->knocks, msg.id, &Proto::JoinRequested::id);
+		if (it == room->knocks.end()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchMember, std::to_string(msg.id) });
+			return;
+		}
+
+		room->knocks.erase(it);
+		if (msg.approve && std::ranges::find(room->invited, msg.id) == room->invited.end())
+			room->invited.push_back(msg.id);
+		SendTo(msg.id, Proto::JoinRequestAnswered{ room->id, msg.approve });
+	}
+
+	// Only full rooms need a line, but joining one that has a seat free works
+	// too: the next tick offers it. Spectator seats have no line.
+	void Server::HandleQueueForRoom(const std::shared_ptr<Connection>& conn, const Proto::QueueForRoom& msg)
+	{
+		auto it = _rooms.find(msg.id);
+		if (it == _rooms.end()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchRoom, msg.id });
+			return;
+		}
+
+		Room& room = it->second;
+		if (conn->room == room.id) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::AlreadyInRoom, conn->room });
+			return;
+		}
+
+		if (std::ranges::find(room.banned_members, conn->id) != room.banned_members.end()) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::Banned, msg.id });
+			return;
+		}
+
+		if (!room.open) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::RoomClosed, msg.id });
+			return;
+		}
+
+		if (!room.password.empty() && msg.password != room.password && !Invited(room, conn->id)) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::BadPassword, msg.id });
+			return;
+		}
+
+		if (conn->queued == room.id) { // already waiting: just say where
+			auto place = std::ranges::find(room.queue, conn->id);
+			if (place != room.queue.end())
+				SendTo(conn->id, Proto::RoomQueuePosition{ room.id, (uint32_t)(place - room.queue.begin()) + 1 });
+			return;
+		}
+
+		if (room.queue.size() >= Proto::max_room_queue) {
+			SendTo(conn->id, Proto::RoomError{ Proto::RoomErrorCode::RoomFull, "queue" });
+			return;
+		}
+
+		DropFromQueue(conn); // one line at a time
+		room.queue.push_back(conn->id);
+		conn->queued = room.id;
+		SendTo(conn->id, Proto::RoomQueuePosition{ room.id, (uint32_t)room.queue.size() });
+	}
+
+	void Server::DropFromQueue(const std::shared_ptr<Connection>& conn)
+	{
+		if (conn->queued.empty())
+			return;
+
+		auto it = _rooms.find(conn->queued);
+		conn->queued.clear();
+		if (it == _rooms.end())
+			return;
+
+		Room& room = it->second;
+		room.offers.erase(conn->id); // the next tick offers it on
+		if (std::erase(room.queue, conn->id))
+			SendQueuePositions(room);
+	}
+
+	void Server::CloseQueue(Room& room)
+	{
+		for (uint32_t id : room.queue) {
+			if (auto it = _connections.find(id); it != _connections.end())
+				it->second->queued.clear();
+			SendTo(id, Proto::RoomQueuePosition{ room.id, 0 });
+		}
+		for (const auto& [id, until] : room.offers) {
+			if (auto it = _connections.find(id); it != _connections.end())
+				it->second->queued.clear();
+			SendTo(id, Proto::RoomQueuePosition{ room.id, 0 });
+		}
+		room.queue.clear();
+		room.offers.clear();
+	}
+
+	void Server::SendQueuePositions(const Room& room)
+	{
+		for (size_t i = 0; i < room.queue.size(); i++)
+			SendTo(room.queue[i], Proto::RoomQueuePosition{ room.id, (uint32_t)i + 1 });
+	}
+
+	// The operator's view of p2p health across every room, from the reports
+	// members chose to share: one log line per interval, and nothing once
+	// nobody shared. Averages only, so no room or member can be told apart.
+	void Server::SweepHealth()
+	{
+		auto now = std::chrono::steady_clock::now();
+		if (now < _next_health_log)
+			return;
+		_next_health_log = now + health_log_interval;
+
+		HealthTotals totals = std::exchange(_health, {});
+		if (totals.reports == 0)
+			return;
+
+		auto percent = [](uint64_t part, uint64_t whole) { return whole == 0 ? 0.0 : 100.0 * part / whole; };
+		spdlog::info("Health: {} reports, {} links, {:.1f}% direct, {:.1f}% relayed, {:.1f}% average loss, {} ms average server rtt",
+			totals.reports, totals.links, percent(totals.direct, totals.links), percent(totals.relayed, totals.links),
+			totals.lossy_links == 0 ? 0.0 : totals.loss_permille / 10.0 / totals.lossy_links,
+			totals.rtt_reports == 0 ? 0 : totals.server_rtt / totals.rtt_reports);
+	}
+
+	// What a room holds itself is bounded by the per-key and per-list caps,
+	// so one growing out of line in this log means its members' queues are
+	// backing up.
+	void Server::SweepMemory()
+	{
+		auto now = std::chrono::steady_clock::now();
+		if (now < _next_memory_log)
+			return;
+		_next_memory_log = now + memory_log_interval;
+		LogClients();
+		if (_lookups_refused != 0)
+			spdlog::info("Lookups: {} answered, {} refused past max_lookups since the last log", _lookups, _lookups_refused);
+		_lookups = _lookups_refused = 0;
+
+		size_t queued = 0;
+		for (const auto& [id, conn] : _connections)
+			queued += conn->backlog;
+
+		std::vector<std::pair<size_t, std::string>> rooms;
+		size_t total = 0;
+		for (const auto& [code, room] : _rooms) {
+			rooms.emplace_back(Footprint(room), code);
+			total += rooms.back().first;
+		}
+		if (rooms.empty() && queued == 0)
+			return;
+
+		size_t named = std::min(rooms.size(), memory_log_rooms);
+		std::ranges::partial_sort(rooms, rooms.begin() + named, std::greater{});
+		std::string largest;
+		for (size_t i = 0; i < named; i++)
+			largest += (i == 0 ? "" : ", ") + rooms[i].second + " " + std::to_string(rooms[i].first / 1024) + " KiB";
+		spdlog::info("Memory: {} rooms hold ~{} KiB, {} KiB queued to {} connections; largest: {}",
+			rooms.size(), total / 1024, queued / 1024, _connections.size(), largest.empty() ? "none" : largest);
+	}
+
+	// Counts per platform, app version and library version, most common first,
+	// for judging when an old client can be let go. Clients that never sent a
+	// ClientInfo count as "unknown".
+	void Server::LogClients() const
+	{
+		if (_connections.empty())
+			return;
+
+		std::map<std::string, size_t> platforms, apps, libraries;
+		for (const auto& [id, conn] : _connections) {
+			const Proto::ClientInfo& info = conn->client;
+			platforms[info.platform.empty() ? "unknown" : info.platform]++;
+			apps[info.app_version.empty() ? "unknown" : info.app_version]++;
+			libraries[info.library_version == 0 ? "unknown" : std::to_string(info.library_version)]++;
+		}
+
+		auto breakdown = [](const std::map<std::string, size_t>& counts) {
+			std::vector<std::pair<size_t, std::string>> sorted;
+			for (const auto& [name, count] : counts)
+				sorted.emplace_back(count, name);
+			std::ranges::sort(sorted, std::greater{});
+			std::string line;
+			for (const auto& [count, name] : sorted)
+				line += (line.empty() ? "" : ", ") + name + " " + std::to_string(count);
+			return line;
+		};
+		spdlog::info("Clients: {} connected; platforms: {}; app versions: {}; library versions: {}",
+			_connections.size(), breakdown(platforms), breakdown(apps), breakdown(libraries));
+	}
+
+	// Approximate: container overhead is left out, shared frames are counted
+	// in every queue that holds them.
+	size_t Server::Footprint(const Room& room) const
+	{
+		size_t bytes = sizeof(Room) + room.id.size() + room.password.size() + room.banner.size() + room.rules.size() + room.host_state.size();
+		bytes += room.successors.size() * sizeof(uint32_t);
+		for (const Proto::Channel& channel : room.channels)
+			bytes += sizeof(channel) + channel.name.size() + channel.members.size() * sizeof(uint32_t);
+		for (const auto& [offer, host] : room.merge_offers)
+			bytes += offer.size() + sizeof(host);
+		for (const auto& [key, value] : room.data)
+			bytes += key.size() + value.size();
+		for (const auto& [member, data] : room.member_data) {
+			for (const auto& [key, value] : data)
+				bytes += key.size() + value.size();
+		}
+		for (const Proto::TimelineEntry& entry : room.timeline)
+			bytes += sizeof(entry) + entry.name.size();
+		for (const Proto::JoinReceipt& receipt : room.receipts)
+			bytes += sizeof(receipt) + receipt.room.size() + receipt.name.size() + receipt.signature.size();
+		for (const Proto::Team& team : room.teams)
+			bytes += sizeof(team) + team.name.size() + team.members.size() * sizeof(uint32_t);
+		for (const Proto::JoinRequested& knock : room.knocks)
+			bytes += sizeof(knock) + knock.name.size() + knock.message.size();
+		for (const auto& [member, report] : room.health)
+			bytes += sizeof(report) + report.first.links.size() * sizeof(Proto::LinkHealth);
+		bytes += (room.members.size() + room.spectators.size() + room.banned_members.size() + room.invited.size() + room.queue.size()) * sizeof(uint32_t);
+		for (const std::string& account : room.invited_accounts)
+			bytes += sizeof(account) + account.size();
+
+		for (uint32_t member : room.members) {
+			auto it = _connections.find(member);
+			if (it != _connections.end())
+				bytes += it->second->backlog;
+		}
+		return bytes;
+	}
+
+	// Closing the socket fails ReadLoop out, so a reset goes down the same
+	// path as a dropped network: the seat is held and the client rejoins.
+	void Server::SweepChaos()
+	{
+		if (_config.chaos.reset_permille == 0)
+			return;
+
+		for (auto& [id, conn] : _connections) {
+			if (!Chance(_config.chaos.reset_permille))
+				continue;
+			spdlog::info("Chaos: resetting client {}", id);
+			asio::error_code ec;
+			conn->socket.set_option(asio::socket_base::linger(true, 0), ec); // an RST rather than a FIN
+			conn->socket.close(ec);
+		}
+	}
+
+	// Seats free up in many places (leaving, kicks, AFK spectating, a higher
+	// cap), so the line is served here rather than from each of them.
+	void Server::SweepQueues()
+	{
+		uint64_t now = UnixNow();
+		for (auto& [code, room] : _rooms) {
+			for (auto it = room.offers.begin(); it != room.offers.end();) {
+				if (now < it->second) {
+					++it;
+					continue;
+				}
+				if (auto conn = _connections.find(it->first); conn != _connections.end())
+					conn->second->queued.clear();
+				SendTo(it->first, Proto::RoomQueuePosition{ code, 0 }); // lapsed
+				it = room.offers.erase(it);
+			}
+
+			size_t taken = room.members.size() - room.spectators.size() + room.offers.size() + room.reserved.size(); // held seats are not the line's to offer
+			bool moved = false;
+			while (!room.queue.empty() && (room.max_players == 0 || taken < room.max_players)) {
+				uint32_t next = room.queue.front();
+				room.queue.erase(room.queue.begin());
+				room.offers[next] = now + Proto::seat_offer_window;
+				SendTo(next, Proto::SeatOffered{ code, Proto::seat_offer_window });
+				taken++;
+				moved = true;
+			}
+			if (moved)
+				SendQueuePositions(room);
+		}
+	}
+
+	// Compares whole frames rather than tracking what changed: watches are
+	// few and short, and a frame is cheap next to a hook in every handler.
+	void Server::SweepPreviews()
+	{
+		auto now = std::chrono::steady_clock::now();
+		for (const auto& [id, conn] : _connections) {
+			if (conn->watching.empty())
+				continue;
+
+			auto it = _rooms.find(conn->watching);
+			if (it == _rooms.end())
+				SendTo(id, Proto::RoomError{ Proto::RoomErrorCode::NoSuchRoom, conn->watching }); // closed while watched
+			if (it == _rooms.end() || now >= conn->watch_until) {
+				conn->watching.clear();
+				conn->watched.reset();
+				continue;
+			}
+
+			auto frame = std::make_shared<const std::vector<std::byte>>(Proto::FrameMessage(Preview(it->second, id)));
+			if (*frame != *conn->watched) {
+				SendFrame(id, frame);
+				conn->watched = std::move(frame);
+			}
+		}
+	}
+
+	// Rooms live in memory only, so their references do too: after a restart,
+	// every blob gets one grace period to be referenced again.
+	void Server::SweepBlobs()
+	{
+		auto now = std::chrono::steady_clock::now();
+		if (!_blobs.IsOpen() || now < _next_blob_sweep)
+			return;
+		_next_blob_sweep = now + blob_sweep_interval;
+
+		std::set<std::string> referenced;
+		auto refer = [&](const std::string& value) {
+			if (Proto::ValidBlobId(value))
+				referenced.insert(value);
+		};
+
+		for (const auto& [code, room] : _rooms) {
+			refer(room.banner);
+			for (const auto& [key, value] : room.data)
+				refer(value);
+			for (const auto& [member, data] : room.member_data) {
+				for (const auto& [key, value] : data)
+					refer(value);
+			}
+		}
+
+		_blobs.Collect(referenced, blob_grace);
+	}
+
+	std::string Server::UniqueRoomCode()
+	{
+		std::string code = MakeRoomCode(_config.room_code_style, _config.room_code_length);
+		while (_rooms.contains(code))
+			code = MakeRoomCode(_config.room_code_style, _config.room_code_length);
+		return code;
+	}
+
+	void Server::LeaveRoom(const std::shared_ptr<Connection>& conn, Proto::TimelineKind why, uint32_t by)
+	{
+		if (conn->room.empty())
+			return;
+
+		auto it = _rooms.find(conn->room);
+		if (it != _rooms.end()) {
+			Room& room = it->second;
+			IssueReceipt(room, conn, Proto::ReceiptKind::Left); // while its seat still says what it held
+			std::erase(room.members, conn->id);
+			room.member_data.erase(conn->id);
+			room.roles.erase(conn->id);
+			room.health.erase(conn->id);
+			room.awaiting_host_state.erase(conn->id);
+			std::erase(room.spectators, conn->id);
+			bool teamed = DropFromTeam(room, conn->id);
+			bool channeled = DropFromChannel(room, conn->id);
+			if (room.members.empty() && !room.reserved.empty()) {
+				room.host = 0; // whichever holder comes back first hosts it
+				room.expires = std::ranges::max(room.reserved | std::views::values);
+				AddToTimeline(room, why, conn->id, by); // for whoever comes back
+				spdlog::info("Room {} is empty, held for {} reconnect(s)", conn->room, room.reserved.size());
+			}
+			else if (room.members.empty()) {
+				CloseQueue(room);
+				_rooms.erase(it);
+				spdlog::info("Room {} closed", conn->room);
+			}
+			else {
+				SendToMany(room.members, Proto::PeerLeft{ conn->id });
+				if (teamed)
+					SendToMany(room.members, Proto::TeamsChanged{ room.teams });
+				if (channeled)
+					SendToMany(room.members, Proto::ChannelsChanged{ room.channels });
+				AddToTimeline(room, why, conn->id, by);
+				if (room.host == conn->id) {
+					SetHost(room, NextHost(room), 0, conn->closing);
+					spdlog::info("Client {} now hosts room {}", room.host, conn->room);
+				}
+			}
+		}
+		conn->room.clear();
+		conn->afk = false; // the flag belongs to the room
+	}
+
+	void Server::SendTo(uint32_t id, const Proto::ServerMessage& msg)
+	{
+		SendFrame(id, std::make_shared<const std::vector<std::byte>>(Proto::FrameMessage(msg)));
+	}
+
+	// Room state a backgrounded member can catch up on later in one go.
+	// Membership, host changes, chat and signaling are never held back.
+	static bool Deferrable(const Proto::ServerMessage& msg)
+	{
+		return std::holds_alternative<Proto::RoomDataChanged>(msg) || std::holds_alternative<Proto::MemberDataChanged>(msg)
+			|| std::holds_alternative<Proto::MemberAfk>(msg) || std::holds_alternative<Proto::MemberRoleChanged>(msg)
+			|| std::holds_alternative<Proto::RoomAccessChanged>(msg) || std::holds_alternative<Proto::RoomCapacityChanged>(msg)
+			|| std::holds_alternative<Proto::IdlePolicyChanged>(msg) || std::holds_alternative<Proto::RoomBannerChanged>(msg)
+			|| std::holds_alternative<Proto::SuccessionChanged>(msg)
+			|| std::holds_alternative<Proto::TeamsChanged>(msg) || std::holds_alternative<Proto::RoomRulesChanged>(msg)
+			|| std::holds_alternative<Proto::ChannelsChanged>(msg)
+			|| std::holds_alternative<Proto::ChatLinkPreview>(msg); // cosmetic; not replayed
+	}
+
+	void Server::SendToMany(const std::vector<uint32_t>& ids, const Proto::ServerMessage& msg)
+	{
+		auto frame = std::make_shared<const std::vector<std::byte>>(Proto::FrameMessage(msg)); // serialize once, share the bytes
+		bool deferrable = Deferrable(msg);
+
+		for (uint32_t id : ids) {
+			auto it = _connections.find(id);
+			if (deferrable && it != _connections.end() && it->second->background) {
+				it->second->missed = true; // SendRoomState catches them up on the way back
+				continue;
+			}
 			SendFrame(id, frame);
 		}
 	}
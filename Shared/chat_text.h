@@ -0,0 +1,142 @@
+#pragma once
+
+#include <algorithm>
+#include <cstddef>
+#include <cstdint>
+#include <string>
+
+// Chat text as it is relayed: valid UTF-8 with no control characters or
+// bidi overrides (which can make a line read backwards), whitespace runs
+// trimmed to a sane size, and under the length caps. The server cleans every
+// RoomChat and RoomWhisper it relays; clients clean the whispers that arrive
+// direct, since the server never sees those.
+namespace Weyvelength::Proto {
+	enum class ChatTextIssue : uint8_t {
+		None,
+		Empty, // nothing visible left once cleaned
+		TooLong, // over max_chat_chars once cleaned, or over max_chat_bytes before
+		BadUtf8,
+		ZeroWidthSpam, // more zero-width characters than the caller allows
+	};
+
+	constexpr size_t max_chat_chars = 2000; // code points
+	constexpr size_t max_chat_bytes = 4 * max_chat_chars; // checked before decoding anything
+	constexpr size_t max_chat_spaces = 4; // a longer run of spaces or tabs shrinks to this many
+	constexpr size_t max_chat_newlines = 2; // a longer run of line breaks shrinks to this many (one blank line)
+
+	// Reads the code point at text[i] and steps i past it. False on malformed
+	// UTF-8, overlong forms, surrogates and anything past U+10FFFF included.
+	inline bool DecodeUtf8(const std::string& text, size_t& i, char32_t& cp)
+	{
+		unsigned char lead = (unsigned char)text[i];
+		size_t len = 0;
+		char32_t least = 0;
+		if (lead < 0x80) {
+			cp = lead;
+			i++;
+			return true;
+		}
+		else if ((lead & 0xE0) == 0xC0) {
+			len = 2, cp = lead & 0x1F, least = 0x80;
+		}
+		else if ((lead & 0xF0) == 0xE0) {
+			len = 3, cp = lead & 0x0F, least = 0x800;
+		}
+		else if ((lead & 0xF8) == 0xF0) {
+			len = 4, cp = lead & 0x07, least = 0x10000;
+		}
+		else {
+			return false; // a stray continuation byte, or a lead UTF-8 never uses
+		}
+
+		if (text.size() - i < len)
+			return false;
+
+		for (size_t k = 1; k < len; k++) {
+			unsigned char c = (unsigned char)text[i + k];
+			if ((c & 0xC0) != 0x80)
+				return false;
+			cp = (cp << 6) | (c & 0x3F);
+		}
+
+		if (cp < least || cp > 0x10FFFF || (cp >= 0xD800 && cp <= 0xDFFF))
+			return false;
+
+		i += len;
+		return true;
+	}
+
+	// Embeddings, overrides and isolates; the plain LRM/RLM marks stay.
+	inline bool IsBidiControl(char32_t cp)
+	{
+		return (cp >= 0x202A && cp <= 0x202E) || (cp >= 0x2066 && cp <= 0x2069);
+	}
+
+	inline bool IsZeroWidth(char32_t cp)
+	{
+		return cp == 0x200B || cp == 0x200C || cp == 0x200D || cp == 0x2060 || cp == 0xFEFF;
+	}
+
+	// Cleans text in place; it is left untouched unless this returns None.
+	// Leading and trailing whitespace goes, as does whitespace at the end of
+	// a line. max_zero_width = 0 lets any number through; ZWJ glues many
+	// emoji together, so a limit should leave some headroom.
+	inline ChatTextIssue SanitizeChat(std::string& text, uint32_t max_zero_width = 0)
+	{
+		if (text.size() > max_chat_bytes)
+			return ChatTextIssue::TooLong;
+
+		std::string out;
+		out.reserve(text.size());
+		size_t chars = 0, spaces = 0, newlines = 0;
+		uint32_t zero_width = 0;
+		bool visible = false;
+
+		for (size_t i = 0; i < text.size();) {
+			size_t start = i;
+			char32_t cp;
+			if (!DecodeUtf8(text, i, cp))
+				return ChatTextIssue::BadUtf8;
+
+			if (cp == '\n') {
+				newlines++;
+				spaces = 0; // trailing whitespace on a line is dropped
+				continue;
+			}
+			if (cp == ' ' || cp == '\t') {
+				spaces++;
+				continue;
+			}
+			if (cp < 0x20 || (cp >= 0x7F && cp <= 0x9F) || IsBidiControl(cp))
+				continue; // stripped; '\r' goes this way too
+
+			if (IsZeroWidth(cp)) {
+				if (max_zero_width != 0 && ++zero_width > max_zero_width)
+					return ChatTextIssue::ZeroWidthSpam;
+			}
+			else {
+				visible = true;
+			}
+
+			if (!out.empty()) { // whitespace before the first character is dropped
+				newlines = std::min(newlines, max_chat_newlines);
+				spaces = std::min(spaces, max_chat_spaces); // after a line break, this is indentation
+				out.append(newlines, '\n');
+				out.append(spaces, ' ');
+				chars += newlines + spaces;
+			}
+			spaces = newlines = 0;
+
+			out.append(text, start, i - start);
+			chars++;
+		}
+
+		if (!visible)
+			return ChatTextIssue::Empty;
+		if (chars > max_chat_chars)
+			return ChatTextIssue::TooLong;
+
+		text = std::move(out);
+		return ChatTextIssue::None;
+	}
+}
@@ -0,0 +1,109 @@
+#pragma once
+
+#include <cstddef>
+#include <cstdint>
+#include <span>
+#include <vector>
+
+// Wire framing on a direct p2p link: every datagram is [uint8 kind][body], so
+// library traffic can share the link with the app's own datagrams. Peers opt
+// in with a P2PSignalKind::Framing signal sent right after the description; a
+// link whose peer never sent one stays raw (one datagram = one app message),
+// which keeps peers built before framing talking to newer ones.
+namespace Weyvelength::Proto {
+	enum class P2PFrameKind : uint8_t {
+		App, // an opaque app datagram, surfaced via NextP2P
+		Whisper, // RoomWhisper text sent direct, surfaced via Next like a relayed one
+		Sequenced, // an app datagram behind a uint32 sequence number (big-endian), for a receiver that reorders; version 2 on
+		Parity, // a P2PParity over a run of Sequenced frames, to rebuild one of them lost; version 3 on
+		FecRequest, // uint8: parity wanted after every this many Sequenced frames, 0 = none; from the receiver, version 3 on
+	};
+
+	// Carried as decimal text in the Framing signal's payload. Only append
+	// frame kinds, and bump this with them: a peer drops kinds it does not
+	// know, so a sender holds new kinds back from peers on older versions.
+	constexpr uint8_t p2p_framing_version = 3;
+	constexpr size_t p2p_frame_header_size = sizeof(uint8_t);
+	constexpr size_t p2p_sequence_size = sizeof(uint32_t);
+
+	inline std::vector<std::byte> FrameP2P(P2PFrameKind kind, std::span<const std::byte> body)
+	{
+		std::vector<std::byte> datagram;
+		datagram.reserve(p2p_frame_header_size + body.size());
+		datagram.push_back((std::byte)kind);
+		datagram.insert(datagram.end(), body.begin(), body.end());
+		return datagram;
+	}
+
+	inline std::vector<std::byte> FrameSequenced(uint32_t seq, std::span<const std::byte> body)
+	{
+		std::vector<std::byte> datagram;
+		datagram.reserve(p2p_frame_header_size + p2p_sequence_size + body.size());
+		datagram.push_back((std::byte)P2PFrameKind::Sequenced);
+		for (int shift = 24; shift >= 0; shift -= 8)
+			datagram.push_back((std::byte)(seq >> shift));
+		datagram.insert(datagram.end(), body.begin(), body.end());
+		return datagram;
+	}
+
+	// A Sequenced frame's body into its number and the app's bytes; false if
+	// it is too short to carry the number.
+	inline bool UnframeSequenced(std::span<const std::byte> body, uint32_t& seq, std::span<const std::byte>& payload)
+	{
+		if (body.size() < p2p_sequence_size)
+			return false;
+		seq = 0;
+		for (size_t i = 0; i < p2p_sequence_size; i++)
+			seq = seq << 8 | (uint32_t)body[i];
+		payload = body.subspan(p2p_sequence_size);
+		return true;
+	}
+
+	// XOR forward error correction: one parity frame lets the receiver rebuild
+	// any one frame of the run it covers.
+	struct P2PParity {
+		uint32_t first = 0; // the run's first sequence number
+		uint8_t count = 0; // how many frames it covers
+		uint16_t length_xor = 0; // their payload lengths, XORed
+		std::vector<std::byte> data_xor; // their payloads XORed, each zero-padded to the longest
+	};
+
+	constexpr size_t p2p_parity_header_size = sizeof(uint32_t) + sizeof(uint8_t) + sizeof(uint16_t);
+
+	inline std::vector<std::byte> FrameParity(const P2PParity& parity)
+	{
+		std::vector<std::byte> datagram;
+		datagram.reserve(p2p_frame_header_size + p2p_parity_header_size + parity.data_xor.size());
+		datagram.push_back((std::byte)P2PFrameKind::Parity);
+		for (int shift = 24; shift >= 0; shift -= 8)
+			datagram.push_back((std::byte)(parity.first >> shift));
+		datagram.push_back((std::byte)parity.count);
+		datagram.push_back((std::byte)(parity.length_xor >> 8));
+		datagram.push_back((std::byte)parity.length_xor);
+		datagram.insert(datagram.end(), parity.data_xor.begin(), parity.data_xor.end());
+		return datagram;
+	}
+
+	// A Parity frame's body; false if it is too short for the header.
+	inline bool UnframeParity(std::span<const std::byte> body, P2PParity& out)
+	{
+		if (body.size() < p2p_parity_header_size)
+			return false;
+		out.first = (uint32_t)body[0] << 24 | (uint32_t)body[1] << 16 | (uint32_t)body[2] << 8 | (uint32_t)body[3];
+		out.count = (uint8_t)body[4];
+		out.length_xor = (uint16_t)((uint16_t)body[5] << 8 | (uint16_t)body[6]);
+		out.data_xor.assign(body.begin() + p2p_parity_header_size, body.end());
+		return true;
+	}
+
+	// Splits a framed datagram into its kind and body; false if it is too short
+	// to carry a kind at all.
+	inline bool UnframeP2P(std::span<const std::byte> datagram, P2PFrameKind& kind, std::span<const std::byte>& body)
+	{
+		if (datagram.size() < p2p_frame_header_size)
+			return false;
+		kind = (P2PFrameKind)datagram[0];
+		body = datagram.subspan(p2p_frame_header_size);
+		return true;
+	}
+}
@@ -0,0 +1,70 @@
+#pragma once
+
+#include <cstdint>
+#include <string>
+#include <string_view>
+
+// First-party accounts: a server can bind names to passwords instead of
+// leaving every client anonymous. Servers without an account store answer
+// every request here with AccountErrorCode::Disabled, bar GuestLogin.
+//
+// Guests skip registration: the server suffixes the name they ask for
+// ("Alice" -> "Alice#4821") so it is unique, and leases it to them. A guest
+// may then CreateAccount under that exact name to keep it for good.
+namespace Weyvelength::Proto::inline v1 {
+	enum class AccountErrorCode : uint8_t {
+		Disabled, // this server keeps no accounts
+		AlreadyLoggedIn,
+		BadName, // see ValidAccountName
+		NameTaken,
+		BadPassword, // too short or too long on create
+		BadEmail,
+		WrongCredentials, // no such account, or the password does not match
+		InUse, // the account is logged in on another connection
+		StoreFailed, // the server could not save the account
+		TooSoon, // ExportMyData again within data_export_interval; context is the seconds left
+		BadPreferences, // a WhisperPolicy this server does not know; context is the value sent
+	};
+
+	struct CreateAccount { // client -> server: register and log in; server replies LoggedIn or AccountError
+		std::string name;
+		std::string password;
+		std::string email; // optional; empty = none
+	};
+
+	struct Login { // client -> server: server replies LoggedIn or AccountError
+		std::string name;
+		std::string password;
+	};
+
+	struct GuestLogin { std::string name; }; // client -> server: a ValidAccountName; server replies LoggedIn with the suffixed name, or AccountError
+
+	struct LoggedIn { std::string name; }; // server -> client: your account name as stored, which may differ in case from what you typed
+
+	struct AccountError { // server -> client: an account request failed
+		AccountErrorCode code{};
+		std::string context;
+	};
+
+	constexpr uint32_t min_account_name = 3;
+	constexpr uint32_t max_account_name = 24;
+	constexpr uint32_t min_account_password = 8;
+	constexpr uint32_t max_account_password = 128;
+	constexpr uint32_t max_account_email = 254;
+	constexpr char guest_separator = '#'; // between a guest's name and its suffix; never valid in an account name
+
+	// Letters, digits, '_' and '-', within the length limits. Names are
+	// unique ignoring case, so "Alice" and "alice" are one account.
+	inline bool ValidAccountName(std::string_view name)
+	{
+		if (name.size() < min_account_name || name.size() > max_account_name)
+			return false;
+
+		for (char c : name) {
+			bool ok = (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') || (c >= '0' && c <= '9') || c == '_' || c == '-';
+			if (!ok)
+				return false;
+		}
+		return true;
+	}
+}
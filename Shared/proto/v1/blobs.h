@@ -0,0 +1,72 @@
+#pragma once
+
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <string_view>
+#include <vector>
+
+// Small assets (avatars, room thumbnails) stored on the server under the hash
+// of their bytes. Both directions stream as BlobChunks: an upload is
+// UploadBlob then chunks until the declared size is in; a download is chunks
+// then BlobDownloaded. A blob nothing references (room or member data,
+// banners) is collected a while after its last upload.
+namespace Weyvelength::Proto::inline v1 {
+	enum class BlobErrorCode : uint8_t {
+		Disabled, // this server keeps no blobs
+		TooLarge, // context is max_blob_size
+		Busy, // too many uploads in flight, or that transfer id is taken
+		BadTransfer, // a chunk for no upload, or more bytes than declared
+		NoSuchBlob, // not stored (maybe collected); context is the id
+		StoreFailed, // the server could not save it
+		Quota, // context is "connection" (your uploads this hour are used up) or "server" (its storage is full)
+	};
+
+	struct UploadBlob { // client -> server: size bytes follow as BlobChunks; server replies BlobUploaded or BlobError
+		uint32_t transfer = 0; // the client's own id for this transfer
+		uint32_t size = 0;
+	};
+
+	struct BlobChunk { // both ways: a piece of the transfer's bytes, in order
+		uint32_t transfer = 0;
+		std::vector<std::byte> data;
+	};
+
+	struct BlobUploaded { // server -> client: stored, under this id
+		uint32_t transfer = 0;
+		std::string id;
+	};
+
+	struct DownloadBlob { // client -> server: server replies BlobChunks then BlobDownloaded, or BlobError
+		uint32_t transfer = 0;
+		std::string id;
+	};
+
+	struct BlobDownloaded { // server -> client: every chunk of the transfer has been sent
+		uint32_t transfer = 0;
+		std::string id;
+	};
+
+	struct BlobError {
+		uint32_t transfer = 0;
+		BlobErrorCode code{};
+		std::string context;
+	};
+
+	constexpr uint32_t max_blob_size = 256 * 1024;
+	constexpr uint32_t max_blob_chunk = 8 * 1024; // well inside one reassembled message
+	constexpr uint32_t max_blob_uploads = 2; // in flight per connection
+	constexpr size_t blob_id_size = 64; // BLAKE2b-256, lowercase hex
+
+	inline bool ValidBlobId(std::string_view id)
+	{
+		if (id.size() != blob_id_size)
+			return false;
+
+		for (char c : id) {
+			if (!((c >= '0' && c <= '9') || (c >= 'a' && c <= 'f')))
+				return false;
+		}
+		return true;
+	}
+}
@@ -0,0 +1,35 @@
+#pragma once
+
+#include <cstdint>
+#include <string>
+#include <vector>
+
+// Breakout channels inside a room, e.g. one per team for its own talk. The
+// host makes them; members are placed in one channel at most, spectators
+// too. Nobody leaves the room: p2p links stay as they are, and a channel
+// only narrows who a ChannelChat reaches and which peers a client's app
+// sends its own traffic to. Every change resends the whole list, as teams do.
+namespace Weyvelength::Proto::inline v1 {
+	struct Channel {
+		uint32_t id = 0; // per room, from 1; never reused while the room lasts
+		std::string name;
+		std::vector<uint32_t> members; // in the order they were placed
+	};
+
+	struct CreateChannel { std::string name; }; // client -> server: host-only; server replies ChannelsChanged or RoomError
+	struct RemoveChannel { uint32_t channel = 0; }; // client -> server: host-only; its members end up in no channel
+	struct PlaceInChannel { // client -> server: the host places anyone, a member only themselves
+		uint32_t id = 0;
+		uint32_t channel = 0; // 0 = out of every channel
+	};
+
+	struct ChannelsChanged { std::vector<Channel> channels; }; // server -> client: the room's channels, in creation order; on join and after every change
+
+	struct ChannelChat { // client -> server: text to the sender's channel, cleaned like RoomChat
+		uint32_t from = 0; // server -> client: filled in with the sender's id; only the channel's members get it, the sender included
+		std::string text;
+	};
+
+	constexpr uint32_t max_room_channels = 16;
+	constexpr uint32_t max_channel_name = 32; // bytes
+}
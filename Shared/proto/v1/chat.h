@@ -0,0 +1,38 @@
+#pragma once
+
+#include <cstdint>
+#include <string>
+
+// Text chat relayed through the server to the sender's room.
+namespace Weyvelength::Proto::inline v1 {
+	struct RoomChat { // client -> server: broadcast text to the sender's room
+		uint32_t from = 0; // server -> client: filled in with the sender's id
+		std::string text;
+	};
+
+	struct RoomWhisper { // client -> server: private text to one room member; id is the target on send, the sender on receive
+		uint32_t id = 0;
+		std::string text;
+	};
+
+	// An OpenGraph card for a link, fetched by the server (when it is set up
+	// to) so members' machines never touch the URL themselves.
+	struct LinkPreview {
+		std::string url; // as it appeared in the chat line
+		std::string title;
+		std::string description; // may be empty
+		std::string image; // an https url, or empty; fetching it is the client's call
+		std::string site; // og:site_name, or empty
+	};
+
+	struct ChatLinkPreview { // server -> client: a card for the link in from's last RoomChat that carried preview.url
+		uint32_t from = 0;
+		LinkPreview preview;
+	};
+
+	// Fields past these are cut (at a character boundary) by the server.
+	constexpr uint32_t max_preview_url = 512; // url and image alike
+	constexpr uint32_t max_preview_title = 200;
+	constexpr uint32_t max_preview_description = 300;
+	constexpr uint32_t max_preview_site = 64;
+}
@@ -0,0 +1,101 @@
+#pragma once
+
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <string_view>
+#include <vector>
+
+// Connection-level messages: liveness, the client's own identity and the
+// server's load.
+namespace Weyvelength::Proto::inline v1 {
+	enum class ServerLimit : uint8_t {
+		Users, // connections, server-wide
+		Rooms,
+		PerAddress, // connections from one IP address
+		Requests, // lists and searches, or password attempts, from one connection or address in a short while; the connection stays
+	};
+
+	struct Heartbeat { uint64_t timestamp; };   // server <-> client heartbeat; timestamp 0 from the server is a liveness probe, answered in kind and never echoed
+	struct AssignClientId { uint32_t id = 0; };  // server -> client: the client's own connection id
+	struct ReportActivity {}; // client -> server: the user did something the server cannot see (p2p play); clears AFK like any other message but Heartbeat
+	struct SetBackground { bool background = false; }; // client -> server: while set, room state updates are held back; going foreground replays the room
+
+	struct ServerFull { // server -> client: refused for load. On connect the server closes right after; on CreateRoom or a lookup the connection stays
+		ServerLimit limit{};
+		uint32_t retry_after = 0; // seconds; a hint, nothing is reserved
+	};
+
+	struct GetServerInfo {}; // client -> server: server replies ServerInfo
+
+	// client -> server: tags the request right after it. The server drops a
+	// request whose key it handled from the same sender within
+	// idempotency_window, so a client may resend over a new connection
+	// without posting twice; a repeated CreateRoom joins the room the first
+	// one made instead. The sender is the client's KeyScope token, or without
+	// one the logged-in name, or for an anonymous client just the connection.
+	struct Idempotent { uint64_t key = 0; };
+
+	// client -> server: once per connection, before any Idempotent. A random
+	// token the client keeps for its lifetime, so its keys carry over to a
+	// new connection whether or not it logs back in. No reply; 0 = none.
+	struct KeyScope { uint64_t token = 0; };
+
+	constexpr uint32_t idempotency_window = 60; // seconds
+
+	struct ServerInfo {
+		uint32_t users = 0;
+		uint32_t max_users = 0; // 0 = no limit
+		uint32_t rooms = 0;
+		uint32_t max_rooms = 0; // 0 = no limit
+	};
+
+	// client -> server, first thing on every connection: what is connecting,
+	// for the operator's breakdown of clients in the server log. A field past
+	// max_client_info_field is cut there.
+	struct ClientInfo {
+		std::string app_version; // the app's own, as it told the client; empty = not told
+		std::string platform; // "windows", "linux", "macos", ...
+		uint32_t library_version = 0; // the client DLL's WEYVE_API_VERSION
+	};
+
+	constexpr size_t max_client_info_field = 32;
+
+	// server -> client, after every ServerInfo: the app versions this server
+	// takes, so an app can offer its update before requests start failing.
+	struct ClientVersionPolicy {
+		std::string min_app_version; // empty = any
+		std::string update_url; // where a newer app is; empty = not said
+	};
+
+	// server -> client: refused, the ClientInfo's app_version is older than
+	// min_app_version. The server closes right after.
+	struct ClientOutdated {
+		std::string min_app_version;
+		std::string update_url;
+	};
+
+	// How app versions compare: dotted numbers, part by part, so "1.10" is
+	// newer than "1.9" and "1.2" the same as "1.2.0". Whatever follows the
+	// numbers ("-beta") is not compared.
+	inline std::vector<uint32_t> VersionParts(std::string_view version)
+	{
+		std::vector<uint32_t> parts{ 0 };
+		for (char c : version) {
+			if (c == '.')
+				parts.push_back(0);
+			else if (c >= '0' && c <= '9')
+				parts.back() = parts.back() * 10 + (uint32_t)(c - '0');
+			else
+				break;
+		}
+		while (parts.size() > 1 && parts.back() == 0)
+			parts.pop_back();
+		return parts;
+	}
+
+	inline bool OlderVersion(std::string_view version, std::string_view than)
+	{
+		return VersionParts(version) < VersionParts(than);
+	}
+}
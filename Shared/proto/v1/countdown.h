@@ -0,0 +1,21 @@
+#pragma once
+
+#include <cstdint>
+
+// A synchronized start for p2p games. The server stamps when the countdown
+// ends by its own clock and tells every member how far off that is as it
+// sends; each client counts the rest down on its own steady clock, less half
+// its measured round trip, so nobody's wall clock decides who starts first.
+namespace Weyvelength::Proto::inline v1 {
+	struct StartCountdown { uint32_t seconds = 0; }; // client -> server: host-only; 0 cancels the running one. Server replies CountdownStarted to the room, or RoomError
+
+	struct CountdownStarted { // server -> client: to every member; again to a joiner while one runs
+		uint32_t id = 0; // per room, from 1; a newer one replaces the last
+		uint32_t seconds = 0; // as asked; 0 = cancelled
+		uint64_t start_at = 0; // unix milliseconds, the server's clock
+		uint32_t remaining = 0; // milliseconds left as the server sent this
+		uint32_t by = 0; // the host who started it
+	};
+
+	constexpr uint32_t max_countdown = 60; // seconds
+}
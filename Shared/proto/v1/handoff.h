@@ -0,0 +1,108 @@
+#pragma once
+
+#include <algorithm>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+// Host hand-off. Rules, teams, countdowns and the like live on the server
+// and outlive any host; what a game keeps at its host, a history buffer for
+// resync or a dedicated server's world, does not. The host checkpoints it
+// with SetHostState, opaque to the server, and whoever hosts next gets the
+// last checkpoint in HostHandoff. A host leaving on purpose sends its final
+// checkpoint just before LeaveRoom or TransferHost; the connection keeps
+// them in order. When the old host dropped instead, the checkpoint may be
+// stale, so the other members are asked for their own view of it, and their
+// answers reach the new host to rebuild from.
+//
+// Who hosts next is the room's succession policy, set by its host like the
+// other room policies, right after CreateRoom or at any time later.
+namespace Weyvelength::Proto::inline v1 {
+	enum class HandoffReason : uint8_t {
+		Transferred, // the old host handed it over
+		Left, // the old host left the room
+		Dropped, // the old host's connection dropped; every other member got HostStateWanted
+	};
+
+	struct SetHostState { std::string state; }; // client -> server: host-only, replaces the checkpoint; empty clears it. No reply but RoomError
+
+	struct HostHandoff { // server -> client: to the new host only, right after HostChanged
+		uint32_t from = 0; // the old host; 0 when the room sat empty in between
+		HandoffReason reason{};
+		uint64_t saved = 0; // unix seconds of the checkpoint; 0 = there is none
+		std::string state;
+	};
+
+	struct HostStateWanted { uint32_t host = 0; }; // server -> client: to every member but the new host after a Dropped hand-off; answer once with ReportHostState, before the next hand-off
+	struct ReportHostState { std::string state; }; // client -> server: a member's view of the host's state, relayed to the host. Once per HostStateWanted; no reply but RoomError
+	struct MemberHostState { // server -> client: to the host, one member's ReportHostState
+		uint32_t from = 0;
+		std::string state;
+	};
+
+	constexpr uint32_t max_host_state = 12 * 1024; // bytes; fits one reassembled message with its envelope
+
+	enum class SuccessionPolicy : uint8_t { // who hosts when the host leaves; ties go to the member who joined first
+		CoHostFirst, // a co-host, else a player, else a spectator; the default
+		LongestConnected, // the player connected to the server longest, else the spectator
+		LowestLatency, // the player with the lowest server round trip in their last ReportHealth, else the spectator; members without one come last
+		Successors, // the first of the host's successors still in the room, player or spectator; else as CoHostFirst
+	};
+
+	struct SetSuccession { // client -> server: host-only. Server replies SuccessionChanged to the room, or RoomError (BadSuccession for a policy past Successors)
+		SuccessionPolicy policy{};
+		std::vector<uint32_t> successors; // in order, up to max_successors; kept under any policy, read only under Successors
+	};
+
+	struct SuccessionChanged { // server -> client: the room's succession policy, on join and after every change
+		SuccessionPolicy policy{};
+		std::vector<uint32_t> successors;
+	};
+
+	constexpr uint32_t max_successors = 16;
+
+	// Whether the server takes policy: every client casts it straight into
+	// its own enum, so anything past Successors is refused with BadSuccession.
+	inline bool KnownSuccession(SuccessionPolicy policy)
+	{
+		return policy <= SuccessionPolicy::Successors;
+	}
+
+	struct SuccessionCandidate { // a member who could host next, as PickSuccessor weighs them
+		uint32_t id = 0;
+		bool cohost = false;
+		bool spectating = false;
+		uint64_t connected = 0; // when their connection opened, in any steady unit
+		uint32_t server_rtt = 0; // milliseconds, from their last ReportHealth; 0 = none
+	};
+
+	// The server's pick, kept here so it can be tested without one. members
+	// are in join order, the leaving host left out; 0 when there are none.
+	inline uint32_t PickSuccessor(SuccessionPolicy policy, const std::vector<uint32_t>& successors, const std::vector<SuccessionCandidate>& members)
+	{
+		if (policy == SuccessionPolicy::Successors) {
+			for (uint32_t id : successors) {
+				if (std::ranges::find(members, id, &SuccessionCandidate::id) != members.end())
+					return id;
+			}
+			policy = SuccessionPolicy::CoHostFirst;
+		}
+
+		auto ahead = [policy](const SuccessionCandidate& a, const SuccessionCandidate& b) {
+			if (policy == SuccessionPolicy::CoHostFirst && a.cohost != b.cohost)
+				return a.cohost;
+			if (a.spectating != b.spectating)
+				return !a.spectating;
+			if (policy == SuccessionPolicy::LongestConnected)
+				return a.connected < b.connected;
+			if (policy == SuccessionPolicy::LowestLatency) {
+				if ((a.server_rtt == 0) != (b.server_rtt == 0))
+					return a.server_rtt != 0;
+				return a.server_rtt < b.server_rtt;
+			}
+			return false;
+		};
+		auto next = std::ranges::min_element(members, ahead); // the first of the best: join order breaks ties
+		return next != members.end() ? next->id : 0;
+	}
+}
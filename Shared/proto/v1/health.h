@@ -0,0 +1,49 @@
+#pragma once
+
+#include <cstdint>
+#include <vector>
+
+// Session health. Every member sums up its p2p links now and then and
+// reports it; the server keeps the last report of each for the room's
+// session and hands it to the host, who builds a who-reaches-whom matrix
+// from them. The host and co-hosts can also ask the server for what it holds.
+// A report marked share is also folded, with no ids attached, into the
+// totals the server logs for its operator.
+namespace Weyvelength::Proto::inline v1 {
+	struct LinkHealth { // one p2p link, as its sender sees it
+		uint32_t peer = 0;
+		bool direct = false; // ICE connected; false = the link is down or still checking
+		bool relayed = false; // connected through TURN
+		uint32_t loss_permille = 0; // the last reading off ordered traffic; 0 = none yet
+		uint32_t send_rate = 0; // bytes per second since the last report
+		uint32_t receive_rate = 0;
+	};
+
+	struct ReportHealth { // client -> server: in a room, every health_report_interval at most. No reply
+		uint32_t server_rtt = 0; // milliseconds; 0 = not measured
+		std::vector<LinkHealth> links; // max_health_links
+		bool share = false; // also count it, anonymously, in the server's totals
+	};
+
+	struct MemberHealth { // server -> client: to the host only, the report as it came
+		uint32_t from = 0;
+		uint32_t server_rtt = 0;
+		std::vector<LinkHealth> links;
+	};
+
+	struct GetSessionHealth {}; // client -> server: host or co-host. Server replies SessionHealth or RoomError
+
+	struct HealthReport {
+		MemberHealth member;
+		uint32_t age = 0; // seconds since the server got it
+	};
+
+	struct SessionHealth { // server -> client: the last report of each member who sent one, in join order
+		std::vector<HealthReport> reports; // whole reports until max_session_health_links links; the rest left out
+	};
+
+	constexpr uint32_t health_report_interval = 10; // seconds between a client's reports
+	constexpr uint32_t health_report_min_gap = 2; // seconds; the server drops reports sent closer together
+	constexpr uint32_t max_health_links = 32;
+	constexpr uint32_t max_session_health_links = 512; // one SessionHealth's worth, well inside a reassembled message
+}
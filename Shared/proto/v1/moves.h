@@ -0,0 +1,41 @@
+#pragma once
+
+#include <cstdint>
+#include <string>
+#include <vector>
+
+// Moving members between rooms. A split carves some members out of a big
+// lobby into a room of their own, say one per match; a merge folds a whole
+// room into another, once both hosts agree. The server moves everyone in one
+// go, before it reads another message, and tells each of them with RoomMoved
+// just before the new room's AssignRoomId. Their client drops its p2p links
+// there and dials the new room's members as it would after any join.
+namespace Weyvelength::Proto::inline v1 {
+	struct SplitRoom { std::vector<uint32_t> members; }; // client -> server: host-only; moves them, in order, to a new room the first of them hosts. Server replies RoomSplit, or RoomError
+	struct RoomSplit { // server -> client: to the host who split, before anyone moves
+		std::string id; // the new room
+		uint32_t members = 0;
+	};
+
+	struct MergeRoom { std::string id; }; // client -> server: host-only; asks room id's host to move their whole room into yours. Server sends them MergeRequested, or you RoomError
+	struct MergeRequested { // server -> client: to the host; answer with AnswerMerge
+		std::string into; // the asking room
+		uint32_t by = 0; // its host
+		uint32_t members = 0; // in it as of asking
+	};
+
+	struct AnswerMerge { // client -> server: host-only. Accepting moves every member, you last; you get RoomError instead if the asking room is gone, has a new host, is full, or bans one of yours
+		std::string into;
+		bool accept = false;
+	};
+
+	struct MergeAnswered { // server -> client: to the host who asked
+		std::string id; // the room asked
+		bool accepted = false;
+	};
+
+	struct RoomMoved { // server -> client: a split or merge moved you, in the same seat kind; the new room's AssignRoomId and state follow
+		std::string id;
+		uint32_t by = 0; // the host who split, or who accepted the merge
+	};
+}
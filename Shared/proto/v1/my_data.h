@@ -0,0 +1,37 @@
+#pragma once
+
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "schedule.h"
+#include "users.h"
+
+// A copy of everything the server holds about the caller, for data access
+// requests. There is little to hold: the account, if any, this connection's
+// settings, and its part in the schedule. Chat is relayed and never kept;
+// blobs carry no owner.
+namespace Weyvelength::Proto::inline v1 {
+	struct ExportMyData {}; // client -> server: server replies MyData, or AccountError TooSoon
+
+	struct MyRsvp {
+		uint32_t booking = 0;
+		RsvpStatus status{};
+	};
+
+	struct MyData {
+		uint32_t id = 0; // this connection
+		std::string name; // empty = anonymous
+		bool guest = false;
+		std::string email; // as registered; accounts only
+		bool searchable = true;
+		NotificationPreferences prefs;
+		std::string room; // empty = not in one
+		std::string queued; // the room whose line you wait in; empty = none
+		std::vector<ScheduledRoom> bookings; // yours, not yet opened
+		std::vector<MyRsvp> rsvps;
+		std::vector<std::string> knocks; // rooms holding a knock of yours
+	};
+
+	constexpr uint32_t data_export_interval = 60; // seconds between exports on one connection
+}
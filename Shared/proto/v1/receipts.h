@@ -0,0 +1,49 @@
+#pragma once
+
+#include <cstdint>
+#include <string>
+#include <vector>
+
+// Join receipts. The server signs one each time a member enters or leaves a
+// room, so a tournament organizer can later show who was in a lobby and
+// when, though the games themselves ran p2p. The host and co-hosts fetch a
+// room's receipts; anyone holding them and the server's public key checks
+// them offline, with nothing but ReceiptText and an ed25519 verify.
+namespace Weyvelength::Proto::inline v1 {
+	enum class ReceiptKind : uint8_t { Joined, Left }; // Left also covers kicks, bans and drops
+
+	enum class ReceiptRole : uint8_t { Host, Player, Spectator }; // as of the join or leave
+
+	struct JoinReceipt {
+		std::string room;
+		uint32_t id = 0;
+		std::string name; // id's account name at the time; empty = anonymous
+		ReceiptRole role{};
+		ReceiptKind kind{};
+		uint64_t time = 0; // unix seconds, the server's clock
+		std::string signature; // hex ed25519 signature of ReceiptText, by the key in JoinReceipts
+	};
+
+	struct GetJoinReceipts {}; // client -> server: host or co-host, the current room's. Server replies JoinReceipts or RoomError
+	struct JoinReceipts { // server -> client: oldest first; only the last max_join_receipts are kept
+		std::string key; // hex ed25519 public key the server signs with; the same across restarts when it keeps a key file
+		std::vector<JoinReceipt> receipts;
+	};
+
+	constexpr uint32_t max_join_receipts = 256; // per room; the oldest goes first
+
+	// What a receipt's signature covers: its fields as text, one per line,
+	// so a verifier in any language rebuilds the same bytes.
+	inline std::string ReceiptText(const JoinReceipt& receipt)
+	{
+		static constexpr const char* roles[] = { "host", "player", "spectator" };
+		std::string text = "weyvelength join receipt v1\n";
+		text += "room " + receipt.room + "\n";
+		text += "id " + std::to_string(receipt.id) + "\n";
+		text += "name " + receipt.name + "\n";
+		text += std::string("role ") + ((size_t)receipt.role < std::size(roles) ? roles[(size_t)receipt.role] : "unknown") + "\n";
+		text += std::string("kind ") + (receipt.kind == ReceiptKind::Joined ? "joined" : "left") + "\n";
+		text += "time " + std::to_string(receipt.time) + "\n";
+		return text;
+	}
+}
@@ -0,0 +1,269 @@
+#pragma once
+
+#include <cstdint>
+#include <string>
+#include <string_view>
+#include <vector>
+
+// Room lifecycle, membership, host actions and room/member metadata.
+namespace Weyvelength::Proto::inline v1 {
+	struct AssignRoomId { std::string id; }; // server -> client: the room id the client has joined successfully
+
+	enum class RoomErrorCode : uint8_t {
+		AlreadyInRoom,
+		NoSuchRoom,
+		NotInRoom,
+		NotHost, // host-only action attempted by a non-host, or a role too low for it
+		BadRoomData, // key/value over the size limits, or too many keys; context "unasked" for a ReportHostState without a HostStateWanted
+		NoSuchMember, // target id is not another member of the room
+		RoomClosed, // the room is not joinable right now
+		BadPassword, // wrong password on join, or an over-long one on set
+		Banned, // the host has barred this client from the room
+		WhisperBlocked, // the target's preferences refuse your whisper right now
+		RoomFull, // no free player (or spectator) seat, or too many knocks pending; context says which
+		NoSuchSchedule, // no booking with that id, or it has already opened
+		BadSchedule, // start time in the past or too far ahead, title too long, the board or the owner's share of it is full, or an unknown RSVP
+		ChatTooLong, // context is the limit in characters
+		BadChat, // context is "empty", "utf8" or "zero-width"; see SanitizeChat
+		BadBanner, // not a blob id this server holds; context is the id
+		NoSuchTeam, // no team with that id in the room; context is the id
+		TeamFull, // the team is at its max_size; context is the id
+		BadTeam, // context is "name" (over max_team_name once cleaned like chat), "count" (max_room_teams already) or "spectator" (spectators join no team)
+		BadCountdown, // longer than max_countdown; context is the limit in seconds
+		NoSuchChannel, // no channel with that id in the room, or you are in none; context is the id
+		BadChannel, // context is "name" (over max_channel_name once cleaned like chat) or "count" (max_room_channels already)
+		BadRole, // not a MemberRole this server knows; context is the value sent
+		BadSuccession, // not a SuccessionPolicy this server knows; context is the value sent
+	};
+
+	struct CreateRoom {}; // client -> server: create a room and join it
+
+	struct JoinRoom { // client -> server: join an existing room by id
+		std::string id;
+		std::string password; // must match the room's password if one is set
+	};
+
+	struct LeaveRoom {}; // client -> server: leave the current room
+
+	struct RoomError { // server -> client: a room request failed
+		RoomErrorCode code{};
+		std::string context; // extra detail, e.g. the offending room id
+	};
+
+	struct PeerJoined { uint32_t id = 0; }; // server -> client: another client is in the room (live join, or replayed to a joiner per existing member)
+	struct PeerLeft { uint32_t id = 0; }; // server -> client: a client left the room; your own id confirms your LeaveRoom
+	struct HostChanged { uint32_t id = 0; }; // server -> client: the room's current host
+
+	struct SetRoomData { // client -> server: set one room metadata key; empty value deletes it
+		std::string key;
+		std::string value;
+	};
+
+	struct RoomDataChanged { // server -> client: one room metadata key changed; empty value means deleted
+		std::string key;
+		std::string value;
+	};
+
+	struct SetMemberData { // client -> server: set one key of your own member metadata; empty value deletes it
+		std::string key;
+		std::string value;
+	};
+
+	struct SetMemberDataFor { // client -> server: host-only, SetMemberData on another member's slots (teams, colors); any member's, the host's own included
+		uint32_t id = 0;
+		std::string key;
+		std::string value;
+	};
+
+	struct MemberDataChanged { // server -> client: one key of a member's metadata changed; empty value means deleted
+		uint32_t id = 0; // whose data
+		std::string key;
+		std::string value;
+	};
+
+	struct KickMember { uint32_t id = 0; }; // client -> server: host-only, remove a member from the room
+	struct BanMember { uint32_t id = 0; }; // client -> server: host-only, remove a member and bar them from rejoining
+	struct TransferHost { uint32_t id = 0; }; // client -> server: host-only, hand host status to another member
+	struct SetRoomJoinable { bool open = true; }; // client -> server: host-only, open or close the room for joining
+	struct SetRoomPassword { std::string password; }; // client -> server: host-only; empty clears it
+
+	struct KickedByHost {}; // server -> client: the host removed you from the room
+	struct BannedByHost {}; // server -> client: the host removed you and barred you from rejoining
+
+	struct RoomAccessChanged { // server -> client: the room's joinability changed; the password itself never leaves the server
+		bool open = true;
+		bool passworded = false;
+	};
+
+	enum class MemberRole : uint8_t { // ranked lowest first; the host outranks every role
+		Member,
+		Moderator, // can kick plain members
+		CoHost, // can kick members and moderators, and open/close the room; first in line when the host leaves
+	};
+
+	struct SetMemberRole { // client -> server: host-only, promote or demote another member
+		uint32_t id = 0;
+		MemberRole role{};
+	};
+
+	struct MemberRoleChanged { // server -> client: a member's role; Member means demoted (or promoted to host)
+		uint32_t id = 0;
+		MemberRole role{};
+	};
+
+	struct SpectateRoom { // client -> server: join an existing room as a spectator, taking no player seat
+		std::string id;
+		std::string password; // must match the room's password if one is set
+	};
+
+	struct MemberSpectating { uint32_t id = 0; }; // server -> client: follows that member's PeerJoined; they watch rather than play, until they leave
+
+	struct SetRoomCapacity { // client -> server: host-only; members already in stay, only new joins are refused
+		uint32_t max_players = 0; // 0 = no limit
+		uint32_t max_spectators = 0; // 0 = no limit
+	};
+
+	struct RoomCapacityChanged { // server -> client: the room's seat limits; 0 = no limit
+		uint32_t max_players = 0;
+		uint32_t max_spectators = 0;
+	};
+
+	struct SetIdlePolicy { // client -> server: host-only; when members count as AFK and what happens then
+		uint32_t afk_after = 0; // seconds without activity; 0 = never
+		bool spectate = false; // also move AFK players to the spectators (for good; the host keeps their seat)
+	};
+
+	struct IdlePolicyChanged { // server -> client: the room's idle policy
+		uint32_t afk_after = 0;
+		bool spectate = false;
+	};
+
+	struct MemberAfk { // server -> client: a member went idle past afk_after, or came back
+		uint32_t id = 0;
+		bool afk = false;
+	};
+
+	struct SetRoomBanner { std::string id; }; // client -> server: host-only, an uploaded blob to show for the room; empty clears it
+	struct RoomBannerChanged { std::string id; }; // server -> client: the room's banner blob; empty = none. Fetch it with DownloadBlob
+
+	struct SetRoomRules { std::string rules; }; // client -> server: host-only, the match settings (JSON by convention; the server never reads it); empty clears them
+
+	struct RoomRulesChanged { // server -> client: the room's rules, on join when set and after every change
+		uint32_t version = 0; // from 1, up by one per change; 0 = never set
+		std::string rules; // empty = none
+	};
+
+	struct SetMatchPaused { bool paused = true; }; // client -> server: host-only, pause or resume the game; pausing calls off a running countdown
+
+	struct MatchPausedChanged { // server -> client: to every member, spectators included; again to a joiner while paused
+		bool paused = false;
+		uint32_t by = 0; // the host who did it
+	};
+
+	struct MemberNameChanged { // server -> client: a member's display name, bound to their account; follows PeerJoined for named members
+		uint32_t id = 0;
+		std::string name;
+	};
+
+	struct PreviewMember {
+		uint32_t id = 0;
+		std::string name; // empty = anonymous
+		bool spectating = false;
+	};
+
+	struct RoomDataEntry {
+		std::string key;
+		std::string value;
+	};
+
+	struct GetRoomPreview { // client -> server: a room as the lobby sees it, without joining; server replies RoomPreview or RoomError
+		std::string id;
+		bool watch = false; // also resend it on every change, for room_preview_watch; one watch per connection
+	};
+
+	struct StopRoomPreview {}; // client -> server: end a watch early
+
+	struct RoomPreview { // server -> client: on request, and while watched whenever it changes (checked once a second)
+		std::string id;
+		uint32_t host = 0; // 0 until someone is in it: a booking that just opened, or a room holding seats for members who dropped
+		bool open = true;
+		bool passworded = false; // a passworded room's members and data are left out
+		uint32_t players = 0;
+		uint32_t spectators = 0;
+		uint32_t max_players = 0; // 0 = no limit
+		uint32_t max_spectators = 0;
+		std::string banner; // a blob id; empty = none
+		std::vector<PreviewMember> members; // in join order, the first max_preview_members
+		std::vector<RoomDataEntry> data; // in key order, while the keys and values fit max_preview_data
+	};
+
+	struct RequestJoin { // client -> server: knock on a passworded room; the host gets JoinRequested, you get JoinRequestAnswered or RoomError
+		std::string id;
+		std::string message; // optional, cleaned like chat
+	};
+
+	struct JoinRequested { // server -> client: to the host, per knock; again to each new host while it is pending
+		uint32_t id = 0; // who knocked
+		std::string name; // empty = anonymous
+		std::string message;
+	};
+
+	struct RespondJoinRequest { // client -> server: host-only; approving lets them past the password from then on
+		uint32_t id = 0;
+		bool approve = false;
+	};
+
+	struct JoinRequestAnswered { // server -> client: to whoever knocked; approved means JoinRoom (or SpectateRoom) works without the password
+		std::string room;
+		bool approved = false;
+	};
+
+	struct QueueForRoom { // client -> server: wait in line for a player seat in a full room; server replies RoomQueuePosition, SeatOffered or RoomError
+		std::string id;
+		std::string password; // checked now, so nobody waits for a room they cannot enter; JoinRoom still needs it
+	};
+
+	struct LeaveRoomQueue {}; // client -> server: server replies RoomQueuePosition with position 0
+
+	struct RoomQueuePosition { // server -> client: on queueing and whenever your place moves
+		std::string room;
+		uint32_t position = 0; // 1 = next; 0 = out of the line (left, offer lapsed, or the room closed)
+	};
+
+	struct SeatOffered { // server -> client: a player seat is held for you; JoinRoom before it lapses or the next in line gets it
+		std::string room;
+		uint32_t expires_in = 0; // seconds
+	};
+
+	constexpr uint32_t max_preview_members = 32;
+	constexpr uint32_t max_preview_data = 4096; // bytes of keys and values; the rest waits for a join
+	constexpr uint32_t room_preview_watch = 60; // seconds
+	constexpr uint32_t max_knock_message = 256; // bytes, once cleaned
+	constexpr uint32_t max_room_knocks = 16; // pending at once, per room
+	constexpr uint32_t max_room_queue = 64; // waiting at once, per room
+	constexpr uint32_t seat_offer_window = 30; // seconds
+
+	// Metadata limits, shared by room and member data. One key/value pair per
+	// frame, so the pair caps keep every data message under max_message_size.
+	constexpr uint32_t max_room_data_key = 128;
+	constexpr uint32_t max_room_data_value = 512;
+	constexpr uint32_t max_room_data_keys = 64;
+	constexpr uint32_t max_member_data_keys = 16;
+	constexpr uint32_t max_room_password = 64;
+	constexpr uint32_t max_room_rules = 8 * 1024; // bytes; one reassembled message
+
+	// Room codes get read out and typed back in. Two codes are the same
+	// room when their keys match: ASCII uppercased, whitespace and dashes
+	// dropped. "blue tiger 42" is BLUE-TIGER-42.
+	inline std::string RoomCodeKey(std::string_view code)
+	{
+		std::string key;
+		key.reserve(code.size());
+		for (char c : code) {
+			if (c == '-' || c == ' ' || c == '\t' || c == '\r' || c == '\n')
+				continue;
+			key += (c >= 'a' && c <= 'z') ? (char)(c - 'a' + 'A') : c;
+		}
+		return key;
+	}
+}
@@ -0,0 +1,76 @@
+#pragma once
+
+#include <cstdint>
+#include <string>
+#include <vector>
+
+// Rooms booked for a future time: the server keeps the booking, reminds the
+// members who said they are going, and opens a real room at the start time.
+// Bookings and RSVPs made while logged in to an account belong to the
+// account, and last across reconnects; anyone else's go with the connection.
+namespace Weyvelength::Proto::inline v1 {
+	enum class RsvpStatus : uint8_t {
+		No,
+		Going, // reminded ahead of the start, and invited when the room opens
+		Maybe, // invited when the room opens, but not reminded
+	};
+
+	struct Attendee {
+		uint32_t id = 0; // their connection; accounts offline are left out
+		RsvpStatus status{}; // never No; those are not attendees
+	};
+
+	struct ScheduledRoom {
+		uint32_t id = 0;
+		uint32_t owner = 0; // who booked it, as connected now; 0 while the account that did is offline
+		uint64_t start = 0; // unix seconds
+		std::string title;
+		uint32_t max_players = 0; // 0 = no limit, as SetRoomCapacity
+		uint32_t max_spectators = 0;
+		bool passworded = false; // the password itself never leaves the server
+		uint32_t going = 0; // RSVPs currently Going
+	};
+
+	struct ScheduleRoom { // client -> server: book a room; server replies ScheduledRoomChanged or RoomError
+		uint64_t start = 0;
+		std::string title;
+		uint32_t max_players = 0;
+		uint32_t max_spectators = 0;
+		std::string password; // empty = none
+	};
+
+	struct ScheduledRoomChanged { ScheduledRoom room; }; // server -> client: reply to ScheduleRoom and RsvpScheduledRoom
+	struct ListScheduledRooms {}; // client -> server: server replies ScheduledRoomList
+	struct ScheduledRoomList { std::vector<ScheduledRoom> rooms; }; // server -> client: every booking not yet open, soonest first
+
+	struct RsvpScheduledRoom { // client -> server: server replies ScheduledRoomChanged or RoomError
+		uint32_t id = 0;
+		RsvpStatus status{};
+	};
+
+	struct ScheduledRoomReminder { // server -> client: a booking you are going to starts soon
+		uint32_t id = 0;
+		uint64_t start = 0;
+	};
+
+	struct ScheduledRoomOpened { // server -> client: the invitation, to the owner and every attendee connected; it lets them past the room's password, an account on any connection
+		uint32_t id = 0;
+		std::string room;
+	};
+
+	struct ListAttendees { uint32_t id = 0; }; // client -> server: server replies ScheduledRoomAttendees or RoomError
+
+	struct ScheduledRoomAttendees { // server -> client: on request, and to the owner whenever an RSVP changes
+		uint32_t id = 0;
+		std::vector<Attendee> attendees; // in client id order
+	};
+
+	struct CancelScheduledRoom { uint32_t id = 0; }; // client -> server: owner only; server sends ScheduledRoomCancelled or RoomError
+	struct ScheduledRoomCancelled { uint32_t id = 0; }; // server -> client: to the owner and every attendee; the booking is gone
+
+	constexpr uint32_t max_schedule_title = 64;
+	constexpr uint32_t max_scheduled_rooms = 64; // server-wide, so ScheduledRoomList stays one reassembled message
+	constexpr uint32_t max_owner_bookings = 4; // per owner and per address, so one client cannot fill the board
+	constexpr uint64_t max_schedule_ahead = 30 * 24 * 60 * 60; // seconds
+	constexpr uint64_t schedule_reminder_lead = 5 * 60; // seconds before the start
+}
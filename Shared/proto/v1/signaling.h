@@ -0,0 +1,125 @@
+#pragma once
+
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <string_view>
+#include <vector>
+
+// ICE signaling relayed between room members, and the p2p datagram type the
+// resulting direct links carry.
+namespace Weyvelength::Proto::inline v1 {
+	enum class P2PSignalKind : uint8_t {
+		Description,
+		Candidate,
+		GatheringDone,
+		Framing, // p2p framing version the sender speaks, as decimal text; see p2p_framing.h
+		Closed, // the sender closed its link to you on purpose; close yours rather than wait for ICE to fail. No payload
+	};
+
+	struct P2PSignal { // relayed ICE signaling; id is the target on send, the sender on receive
+		uint32_t id = 0;
+		P2PSignalKind kind{};
+		std::string payload; // sdp text
+	};
+
+	// A P2PSignal stamped with its sender's sequence number, which rises with
+	// every signal the sender's client sends, to anyone. The server drops one
+	// not above the last it relayed from that connection, and receivers one
+	// not above the last they took from that sender, so a replayed or doubled
+	// signal cannot restart a negotiation or feed in a stale candidate.
+	// Plain P2PSignals still relay, unchecked, from clients before it.
+	struct SequencedSignal {
+		uint64_t seq = 0; // never 0
+		P2PSignal signal;
+	};
+
+	// The check both ends make: true, and last moves up to seq, if it is new.
+	inline bool FreshSignal(uint64_t& last, uint64_t seq)
+	{
+		if (seq <= last)
+			return false;
+		last = seq;
+		return true;
+	}
+
+	struct TurnServer {
+		std::string host;
+		uint16_t port = 0;
+		std::string username;
+		std::string password;
+	};
+
+	struct IceServers { // server -> client: sent once after connect
+		std::string stun_host; // empty = no stun
+		uint16_t stun_port = 0;
+		std::vector<TurnServer> turn;
+	};
+
+	// server -> client, after IceServers when the server has a signal port:
+	// a second connection to it may carry the signals, so operators can
+	// firewall, prioritize or proxy them apart from chat. The client dials it
+	// on the same host and attaches with the token; until then, and whenever
+	// it is down, signals share the main connection.
+	struct SignalEndpoint {
+		uint16_t port = 0;
+		uint64_t token = 0; // for this connection only
+	};
+
+	// client -> server, the first message on a signal port connection. From
+	// then on it carries P2PSignal, SequencedSignal and Heartbeat, both ways,
+	// on the main connection's behalf; anything else is dropped.
+	struct AttachSignalLink {
+		uint32_t id = 0; // the main connection's, from AssignClientId
+		uint64_t token = 0; // from its SignalEndpoint
+	};
+
+	// Opaque bytes, one datagram per message; the app defines its own encoding.
+	using P2PMessage = std::vector<std::byte>;
+
+	constexpr uint32_t max_p2p_message_size = 1024;
+	constexpr size_t max_signal_description = 4096; // libjuice's own cap on a local description
+	constexpr size_t max_signal_candidate = 256; // and on one candidate line
+
+	// The shape of each kind's payload, checked before it reaches libjuice's
+	// parser: SDP lines ("x=...", CRLF or LF) of printable ASCII, a
+	// description carrying its ICE credentials, a candidate being one
+	// candidate, and nothing at all where nothing is expected. An unknown
+	// kind is never valid.
+	inline bool ValidSignalPayload(P2PSignalKind kind, std::string_view payload)
+	{
+		auto sdp = [&](size_t cap) {
+			if (payload.empty() || payload.size() > cap)
+				return false;
+			size_t line = 0; // offset into the current line
+			for (size_t i = 0; i < payload.size(); i++) {
+				char c = payload[i];
+				if (c == '\n') {
+					line = 0;
+					continue;
+				}
+				if (c == '\r' && i + 1 < payload.size() && payload[i + 1] == '\n')
+					continue;
+				if (c < 0x20 || c > 0x7E)
+					return false;
+				if ((line == 0 && !(c >= 'a' && c <= 'z')) || (line == 1 && c != '='))
+					return false;
+				line++;
+			}
+			return true;
+		};
+
+		switch (kind) {
+		case P2PSignalKind::Description:
+			return sdp(max_signal_description) && payload.find("a=ice-ufrag:") != std::string_view::npos && payload.find("a=ice-pwd:") != std::string_view::npos;
+		case P2PSignalKind::Candidate:
+			return sdp(max_signal_candidate) && payload.starts_with("a=candidate:") && payload.find('\n') >= payload.size() - 1;
+		case P2PSignalKind::Framing:
+			return !payload.empty() && payload.size() <= 3 && payload.find_first_not_of("0123456789") == std::string_view::npos;
+		case P2PSignalKind::GatheringDone:
+		case P2PSignalKind::Closed:
+			return payload.empty();
+		}
+		return false;
+	}
+}
@@ -0,0 +1,55 @@
+#pragma once
+
+#include <cstdint>
+#include <string>
+#include <vector>
+
+// Teams inside a room. The host makes them and places players in them;
+// spectators are on no team. Every change resends the whole list: it is
+// small, and a member who missed one still ends up with the right teams.
+namespace Weyvelength::Proto::inline v1 {
+	struct Team {
+		uint32_t id = 0; // per room, from 1; never reused while the room lasts
+		std::string name;
+		uint32_t max_size = 0; // 0 = no limit
+		std::vector<uint32_t> members; // in the order they were placed
+	};
+
+	struct CreateTeam { // client -> server: host-only; server replies TeamsChanged or RoomError
+		std::string name;
+		uint32_t max_size = 0; // 0 = no limit
+	};
+
+	struct RemoveTeam { uint32_t team = 0; }; // client -> server: host-only; its members end up on no team
+	struct AssignTeam { // client -> server: host-only, move a player onto a team
+		uint32_t id = 0;
+		uint32_t team = 0; // 0 = off every team
+	};
+
+	struct BalanceTeams {}; // client -> server: host-only, deal every player out again; see DealTeams
+	struct TeamsChanged { std::vector<Team> teams; }; // server -> client: the room's teams, in creation order; on join and after every change
+
+	constexpr uint32_t max_room_teams = 16;
+	constexpr uint32_t max_team_name = 32; // bytes
+
+	// Empties the teams and deals players out again in the order given, each
+	// to the team with the fewest members and a free place (the earlier team
+	// on a tie). Sizes end up at most one apart wherever the limits allow;
+	// whoever fits nowhere is left off.
+	inline void DealTeams(std::vector<Team>& teams, const std::vector<uint32_t>& players)
+	{
+		for (Team& team : teams)
+			team.members.clear();
+
+		for (uint32_t player : players) {
+			Team* emptiest = nullptr;
+			for (Team& team : teams) {
+				bool room = team.max_size == 0 || team.members.size() < team.max_size;
+				if (room && (!emptiest || team.members.size() < emptiest->members.size()))
+					emptiest = &team;
+			}
+			if (emptiest)
+				emptiest->members.push_back(player);
+		}
+	}
+}
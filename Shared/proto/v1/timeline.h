@@ -0,0 +1,38 @@
+#pragma once
+
+#include <cstdint>
+#include <string>
+#include <vector>
+
+// A room's recent history as the server saw it: who came and went, host
+// changes, the room opening and closing. Members get each entry live and a
+// joiner fetches what happened before them, so every member's chat view can
+// interleave the same system lines in the same order.
+namespace Weyvelength::Proto::inline v1 {
+	enum class TimelineKind : uint8_t {
+		Joined, // id took a player seat
+		Spectating, // id joined as a spectator
+		Left, // id left, or dropped
+		Kicked, // id was removed by by
+		Banned, // id was removed and barred by by
+		HostChanged, // id hosts now; by handed it over, or 0 when the old host left
+		Opened, // by opened the room for joining; id is 0
+		Closed, // by closed it; id is 0
+		Moved, // id went to another room in by's split or merge
+	};
+
+	struct TimelineEntry {
+		uint32_t seq = 0; // per room, from 1, no gaps; the same at every member
+		uint64_t time = 0; // unix seconds, the server's clock
+		TimelineKind kind{};
+		uint32_t id = 0;
+		std::string name; // id's account name at the time; empty = anonymous
+		uint32_t by = 0; // whoever did it; 0 = nobody in particular
+	};
+
+	struct GetTimeline { uint32_t after = 0; }; // client -> server: the current room's entries past seq after; server replies Timeline or RoomError
+	struct Timeline { std::vector<TimelineEntry> entries; }; // server -> client: oldest first; only the last max_timeline_entries are kept
+	struct TimelineAdded { TimelineEntry entry; }; // server -> client: to every member as it happens, whoever it is about included
+
+	constexpr uint32_t max_timeline_entries = 100; // per room; the oldest goes first
+}
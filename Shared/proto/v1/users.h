@@ -0,0 +1,64 @@
+#pragma once
+
+#include <cstdint>
+#include <string>
+#include <vector>
+
+// Per-user settings that follow the client around, independent of any room.
+namespace Weyvelength::Proto::inline v1 {
+	enum class WhisperPolicy : uint8_t { // who may whisper you
+		Everyone,
+		Staff, // the host, co-hosts and moderators of your room
+		Nobody,
+	};
+
+	// An account keeps these in the store; anyone else keeps them for as long
+	// as they are connected. They cover whispers only. There is no invite
+	// permission because nothing to gate reaches you unasked: the server's
+	// invitations all answer a request of yours (a knock the host approved, a
+	// booking you own or RSVPed to), and MergeRequested goes to hosts alone.
+	struct NotificationPreferences {
+		uint64_t dnd_until = 0; // unix seconds; do-not-disturb (no whispers at all) until then, 0 = off
+		WhisperPolicy whispers{};
+
+		bool operator==(const NotificationPreferences&) const = default;
+	};
+
+	struct SetPreferences { NotificationPreferences prefs; }; // client -> server: replace your stored preferences; AccountError BadPreferences for a WhisperPolicy past Nobody
+	struct PreferencesChanged { NotificationPreferences prefs; }; // server -> client: your stored preferences, once after connect, on login and on every change
+
+	struct SetSearchable { bool searchable = true; }; // client -> server: whether SearchUsers finds you; an account keeps the choice. Server replies SearchableChanged
+	struct SearchableChanged { bool searchable = true; }; // server -> client: on login and on every change; connections start searchable
+
+	struct SearchUsers { std::string query; }; // client -> server: server replies UserSearchResults
+
+	struct UserMatch {
+		std::string name;
+		uint32_t id = 0; // the connection to whisper; 0 = offline, an account nobody is logged in to
+		std::string room; // where they are, only if anyone could walk in (open, no password); empty otherwise
+	};
+
+	struct UserSearchResults { // server -> client: names starting with the query first, then names containing it, case ignored; empty if the query is too short or long
+		std::string query;
+		std::vector<UserMatch> users; // at most max_user_results; anonymous connections never match
+	};
+
+	constexpr uint32_t min_user_query = 2;
+	constexpr uint32_t max_user_query = 32;
+	constexpr uint32_t max_user_results = 20;
+
+	// The one rule both ends apply: the server to relayed whispers, the
+	// receiving client to ones that arrive direct over a p2p link.
+	inline bool WhisperAllowed(const NotificationPreferences& prefs, bool from_staff, uint64_t now)
+	{
+		if (now < prefs.dnd_until)
+			return false;
+
+		switch (prefs.whispers) {
+		case WhisperPolicy::Everyone: return true;
+		case WhisperPolicy::Staff: return from_staff;
+		case WhisperPolicy::Nobody: return false;
+		}
+		return false;
+	}
+}
@@ -0,0 +1,231 @@
+#pragma once
+
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <type_traits>
+#include <vector>
+
+#include <thirdparty\zpp_bits\zpp_bits.h>
+
+// zpp_bits writes a message's members in declaration order with no tags, so
+// adding, removing, reordering or retyping a field breaks peers built against
+// the old layout just like reordering ServerMessage does. Included by
+// protocol.h, these pins fail every build that speaks the protocol (server,
+// client and tests alike) on such a change. A deliberate break belongs in a
+// new package, not an edit here.
+namespace Weyvelength::Proto::inline v1 {
+	template <typename... Fields>
+	struct WireShape {};
+
+	// True if Message's members are exactly Fields, in order.
+	template <typename Message, typename... Fields>
+	constexpr bool HasWireShape()
+	{
+		using Shape = decltype(zpp::bits::access::visit_members_types<Message>([]<typename... Members>() {
+			return WireShape<std::remove_cvref_t<Members>...>{};
+		}));
+		return std::is_same_v<Shape, WireShape<Fields...>>;
+	}
+
+	// accounts.h
+	static_assert(std::is_same_v<std::underlying_type_t<AccountErrorCode>, uint8_t>);
+	static_assert(HasWireShape<CreateAccount, std::string, std::string, std::string>());
+	static_assert(HasWireShape<Login, std::string, std::string>());
+	static_assert(HasWireShape<GuestLogin, std::string>());
+	static_assert(HasWireShape<LoggedIn, std::string>());
+	static_assert(HasWireShape<AccountError, AccountErrorCode, std::string>());
+
+	// blobs.h
+	static_assert(std::is_same_v<std::underlying_type_t<BlobErrorCode>, uint8_t>);
+	static_assert(HasWireShape<UploadBlob, uint32_t, uint32_t>());
+	static_assert(HasWireShape<BlobChunk, uint32_t, std::vector<std::byte>>());
+	static_assert(HasWireShape<BlobUploaded, uint32_t, std::string>());
+	static_assert(HasWireShape<DownloadBlob, uint32_t, std::string>());
+	static_assert(HasWireShape<BlobDownloaded, uint32_t, std::string>());
+	static_assert(HasWireShape<BlobError, uint32_t, BlobErrorCode, std::string>());
+
+	// connection.h
+	static_assert(HasWireShape<Heartbeat, uint64_t>());
+	static_assert(HasWireShape<AssignClientId, uint32_t>());
+	static_assert(HasWireShape<ReportActivity>());
+	static_assert(HasWireShape<SetBackground, bool>());
+	static_assert(std::is_same_v<std::underlying_type_t<ServerLimit>, uint8_t>);
+	static_assert(HasWireShape<ServerFull, ServerLimit, uint32_t>());
+	static_assert(HasWireShape<GetServerInfo>());
+	static_assert(HasWireShape<ServerInfo, uint32_t, uint32_t, uint32_t, uint32_t>());
+	static_assert(HasWireShape<Idempotent, uint64_t>());
+	static_assert(HasWireShape<ClientInfo, std::string, std::string, uint32_t>());
+	static_assert(HasWireShape<ClientVersionPolicy, std::string, std::string>());
+	static_assert(HasWireShape<ClientOutdated, std::string, std::string>());
+
+	// chat.h
+	static_assert(HasWireShape<RoomChat, uint32_t, std::string>());
+	static_assert(HasWireShape<RoomWhisper, uint32_t, std::string>());
+	static_assert(HasWireShape<LinkPreview, std::string, std::string, std::string, std::string, std::string>());
+	static_assert(HasWireShape<ChatLinkPreview, uint32_t, LinkPreview>());
+
+	// rooms.h
+	static_assert(std::is_same_v<std::underlying_type_t<RoomErrorCode>, uint8_t>);
+	static_assert(HasWireShape<AssignRoomId, std::string>());
+	static_assert(HasWireShape<CreateRoom>());
+	static_assert(HasWireShape<JoinRoom, std::string, std::string>());
+	static_assert(HasWireShape<LeaveRoom>());
+	static_assert(HasWireShape<RoomError, RoomErrorCode, std::string>());
+	static_assert(HasWireShape<PeerJoined, uint32_t>());
+	static_assert(HasWireShape<PeerLeft, uint32_t>());
+	static_assert(HasWireShape<HostChanged, uint32_t>());
+	static_assert(HasWireShape<SetRoomData, std::string, std::string>());
+	static_assert(HasWireShape<RoomDataChanged, std::string, std::string>());
+	static_assert(HasWireShape<SetMemberData, std::string, std::string>());
+	static_assert(HasWireShape<SetMemberDataFor, uint32_t, std::string, std::string>());
+	static_assert(HasWireShape<MemberDataChanged, uint32_t, std::string, std::string>());
+	static_assert(HasWireShape<KickMember, uint32_t>());
+	static_assert(HasWireShape<BanMember, uint32_t>());
+	static_assert(HasWireShape<TransferHost, uint32_t>());
+	static_assert(HasWireShape<SetRoomJoinable, bool>());
+	static_assert(HasWireShape<SetRoomPassword, std::string>());
+	static_assert(HasWireShape<KickedByHost>());
+	static_assert(HasWireShape<BannedByHost>());
+	static_assert(HasWireShape<RoomAccessChanged, bool, bool>());
+	static_assert(std::is_same_v<std::underlying_type_t<MemberRole>, uint8_t>);
+	static_assert(HasWireShape<SetMemberRole, uint32_t, MemberRole>());
+	static_assert(HasWireShape<MemberRoleChanged, uint32_t, MemberRole>());
+	static_assert(HasWireShape<SpectateRoom, std::string, std::string>());
+	static_assert(HasWireShape<MemberSpectating, uint32_t>());
+	static_assert(HasWireShape<SetRoomCapacity, uint32_t, uint32_t>());
+	static_assert(HasWireShape<RoomCapacityChanged, uint32_t, uint32_t>());
+	static_assert(HasWireShape<SetIdlePolicy, uint32_t, bool>());
+	static_assert(HasWireShape<IdlePolicyChanged, uint32_t, bool>());
+	static_assert(HasWireShape<MemberAfk, uint32_t, bool>());
+	static_assert(HasWireShape<MemberNameChanged, uint32_t, std::string>());
+	static_assert(HasWireShape<SetRoomBanner, std::string>());
+	static_assert(HasWireShape<RoomBannerChanged, std::string>());
+	static_assert(HasWireShape<SetRoomRules, std::string>());
+	static_assert(HasWireShape<RoomRulesChanged, uint32_t, std::string>());
+	static_assert(HasWireShape<SetMatchPaused, bool>());
+	static_assert(HasWireShape<MatchPausedChanged, bool, uint32_t>());
+	static_assert(HasWireShape<PreviewMember, uint32_t, std::string, bool>());
+	static_assert(HasWireShape<RoomDataEntry, std::string, std::string>());
+	static_assert(HasWireShape<GetRoomPreview, std::string, bool>());
+	static_assert(HasWireShape<StopRoomPreview>());
+	static_assert(HasWireShape<RoomPreview, std::string, uint32_t, bool, bool, uint32_t, uint32_t, uint32_t, uint32_t, std::string,
+		std::vector<PreviewMember>, std::vector<RoomDataEntry>>());
+	static_assert(HasWireShape<RequestJoin, std::string, std::string>());
+	static_assert(HasWireShape<JoinRequested, uint32_t, std::string, std::string>());
+	static_assert(HasWireShape<RespondJoinRequest, uint32_t, bool>());
+	static_assert(HasWireShape<JoinRequestAnswered, std::string, bool>());
+	static_assert(HasWireShape<QueueForRoom, std::string, std::string>());
+	static_assert(HasWireShape<LeaveRoomQueue>());
+	static_assert(HasWireShape<RoomQueuePosition, std::string, uint32_t>());
+	static_assert(HasWireShape<SeatOffered, std::string, uint32_t>());
+
+	// schedule.h
+	static_assert(std::is_same_v<std::underlying_type_t<RsvpStatus>, uint8_t>);
+	static_assert(HasWireShape<ScheduledRoom, uint32_t, uint32_t, uint64_t, std::string, uint32_t, uint32_t, bool, uint32_t>());
+	static_assert(HasWireShape<ScheduleRoom, uint64_t, std::string, uint32_t, uint32_t, std::string>());
+	static_assert(HasWireShape<ScheduledRoomChanged, ScheduledRoom>());
+	static_assert(HasWireShape<ListScheduledRooms>());
+	static_assert(HasWireShape<ScheduledRoomList, std::vector<ScheduledRoom>>());
+	static_assert(HasWireShape<RsvpScheduledRoom, uint32_t, RsvpStatus>());
+	static_assert(HasWireShape<ScheduledRoomReminder, uint32_t, uint64_t>());
+	static_assert(HasWireShape<ScheduledRoomOpened, uint32_t, std::string>());
+	static_assert(HasWireShape<Attendee, uint32_t, RsvpStatus>());
+	static_assert(HasWireShape<ListAttendees, uint32_t>());
+	static_assert(HasWireShape<ScheduledRoomAttendees, uint32_t, std::vector<Attendee>>());
+
+	// signaling.h
+	static_assert(std::is_same_v<std::underlying_type_t<P2PSignalKind>, uint8_t>);
+	static_assert(HasWireShape<P2PSignal, uint32_t, P2PSignalKind, std::string>());
+	static_assert(HasWireShape<SequencedSignal, uint64_t, P2PSignal>());
+	static_assert(HasWireShape<TurnServer, std::string, uint16_t, std::string, std::string>());
+	static_assert(HasWireShape<IceServers, std::string, uint16_t, std::vector<TurnServer>>());
+	static_assert(HasWireShape<SignalEndpoint, uint16_t, uint64_t>());
+	static_assert(HasWireShape<AttachSignalLink, uint32_t, uint64_t>());
+
+	// teams.h
+	static_assert(HasWireShape<Team, uint32_t, std::string, uint32_t, std::vector<uint32_t>>());
+	static_assert(HasWireShape<CreateTeam, std::string, uint32_t>());
+	static_assert(HasWireShape<RemoveTeam, uint32_t>());
+	static_assert(HasWireShape<AssignTeam, uint32_t, uint32_t>());
+	static_assert(HasWireShape<BalanceTeams>());
+	static_assert(HasWireShape<TeamsChanged, std::vector<Team>>());
+
+	// timeline.h
+	static_assert(std::is_same_v<std::underlying_type_t<TimelineKind>, uint8_t>);
+	static_assert(HasWireShape<TimelineEntry, uint32_t, uint64_t, TimelineKind, uint32_t, std::string, uint32_t>());
+	static_assert(HasWireShape<GetTimeline, uint32_t>());
+	static_assert(HasWireShape<Timeline, std::vector<TimelineEntry>>());
+	static_assert(HasWireShape<TimelineAdded, TimelineEntry>());
+
+	// users.h
+	static_assert(std::is_same_v<std::underlying_type_t<WhisperPolicy>, uint8_t>);
+	static_assert(HasWireShape<NotificationPreferences, uint64_t, WhisperPolicy>());
+	static_assert(HasWireShape<SetPreferences, NotificationPreferences>());
+	static_assert(HasWireShape<PreferencesChanged, NotificationPreferences>());
+	static_assert(HasWireShape<SetSearchable, bool>());
+	static_assert(HasWireShape<SearchableChanged, bool>());
+	static_assert(HasWireShape<SearchUsers, std::string>());
+	static_assert(HasWireShape<UserMatch, std::string, uint32_t, std::string>());
+	static_assert(HasWireShape<UserSearchResults, std::string, std::vector<UserMatch>>());
+
+	// my_data.h
+	static_assert(HasWireShape<ExportMyData>());
+	static_assert(HasWireShape<MyRsvp, uint32_t, RsvpStatus>());
+	static_assert(HasWireShape<MyData, uint32_t, std::string, bool, std::string, bool, NotificationPreferences, std::string, std::string,
+		std::vector<ScheduledRoom>, std::vector<MyRsvp>, std::vector<std::string>>());
+
+	// countdown.h
+	static_assert(HasWireShape<StartCountdown, uint32_t>());
+	static_assert(HasWireShape<CountdownStarted, uint32_t, uint32_t, uint64_t, uint32_t, uint32_t>());
+
+	// health.h
+	static_assert(HasWireShape<LinkHealth, uint32_t, bool, bool, uint32_t, uint32_t, uint32_t>());
+	static_assert(HasWireShape<ReportHealth, uint32_t, std::vector<LinkHealth>, bool>());
+	static_assert(HasWireShape<MemberHealth, uint32_t, uint32_t, std::vector<LinkHealth>>());
+	static_assert(HasWireShape<GetSessionHealth>());
+	static_assert(HasWireShape<HealthReport, MemberHealth, uint32_t>());
+	static_assert(HasWireShape<SessionHealth, std::vector<HealthReport>>());
+
+	// receipts.h
+	static_assert(std::is_same_v<std::underlying_type_t<ReceiptKind>, uint8_t>);
+	static_assert(std::is_same_v<std::underlying_type_t<ReceiptRole>, uint8_t>);
+	static_assert(HasWireShape<JoinReceipt, std::string, uint32_t, std::string, ReceiptRole, ReceiptKind, uint64_t, std::string>());
+	static_assert(HasWireShape<GetJoinReceipts>());
+	static_assert(HasWireShape<JoinReceipts, std::string, std::vector<JoinReceipt>>());
+
+	// handoff.h
+	static_assert(std::is_same_v<std::underlying_type_t<HandoffReason>, uint8_t>);
+	static_assert(HasWireShape<SetHostState, std::string>());
+	static_assert(HasWireShape<HostHandoff, uint32_t, HandoffReason, uint64_t, std::string>());
+	static_assert(HasWireShape<HostStateWanted, uint32_t>());
+	static_assert(HasWireShape<ReportHostState, std::string>());
+	static_assert(HasWireShape<MemberHostState, uint32_t, std::string>());
+	static_assert(std::is_same_v<std::underlying_type_t<SuccessionPolicy>, uint8_t>);
+	static_assert(HasWireShape<SetSuccession, SuccessionPolicy, std::vector<uint32_t>>());
+	static_assert(HasWireShape<SuccessionChanged, SuccessionPolicy, std::vector<uint32_t>>());
+
+	// moves.h
+	static_assert(HasWireShape<SplitRoom, std::vector<uint32_t>>());
+	static_assert(HasWireShape<RoomSplit, std::string, uint32_t>());
+	static_assert(HasWireShape<MergeRoom, std::string>());
+	static_assert(HasWireShape<MergeRequested, std::string, uint32_t, uint32_t>());
+	static_assert(HasWireShape<AnswerMerge, std::string, bool>());
+	static_assert(HasWireShape<MergeAnswered, std::string, bool>());
+	static_assert(HasWireShape<RoomMoved, std::string, uint32_t>());
+
+	// channels.h
+	static_assert(HasWireShape<Channel, uint32_t, std::string, std::vector<uint32_t>>());
+	static_assert(HasWireShape<CreateChannel, std::string>());
+	static_assert(HasWireShape<RemoveChannel, uint32_t>());
+	static_assert(HasWireShape<PlaceInChannel, uint32_t, uint32_t>());
+	static_assert(HasWireShape<ChannelsChanged, std::vector<Channel>>());
+	static_assert(HasWireShape<ChannelChat, uint32_t, std::string>());
+
+	// schedule.h, later additions
+	static_assert(HasWireShape<CancelScheduledRoom, uint32_t>());
+	static_assert(HasWireShape<ScheduledRoomCancelled, uint32_t>());
+
+	// connection.h, later additions
+	static_assert(HasWireShape<KeyScope, uint64_t>());
+}
@@ -0,0 +1,80 @@
+#pragma once
+
+#include <algorithm>
+#include <array>
+#include <cstddef>
+#include <cstdint>
+#include <istream>
+#include <iterator>
+#include <optional>
+#include <ostream>
+#include <vector>
+
+#include <thirdparty\zpp_bits\zpp_bits.h>
+
+#include "protocol.h"
+
+// Session recordings, for regression tests: every message one client sent
+// and got over the server connection, timed, so a bug met in a manual
+// session can be kept as a fixture and replayed against the client's event
+// dispatch from then on. Only builds with WEYVE_RECORDING defined write them;
+// reading needs nothing but this header.
+//
+// A file is "WVRC" and the protocol_version, then zpp_bits-encoded
+// RecordedMessages back to back. One from another protocol version is
+// refused rather than misread.
+namespace Weyvelength::Proto {
+	enum class RecordDirection : uint8_t {
+		Sent, // client -> server
+		Received, // server -> client
+	};
+
+	struct RecordedMessage {
+		uint64_t at = 0; // milliseconds since the recording started
+		RecordDirection direction{};
+		ServerMessage message;
+	};
+
+	constexpr std::array<std::byte, 4> recording_magic{ std::byte{ 'W' }, std::byte{ 'V' }, std::byte{ 'R' }, std::byte{ 'C' } };
+
+	inline bool WriteRecordingHeader(std::ostream& out)
+	{
+		std::vector<std::byte> bytes{ recording_magic.begin(), recording_magic.end() };
+		zpp::bits::out{ bytes, zpp::bits::append{} }(protocol_version).or_throw();
+		out.write((const char*)bytes.data(), bytes.size());
+		return (bool)out;
+	}
+
+	inline bool WriteRecorded(std::ostream& out, const RecordedMessage& record)
+	{
+		std::vector<std::byte> bytes;
+		zpp::bits::out{ bytes }(record).or_throw();
+		out.write((const char*)bytes.data(), bytes.size());
+		return (bool)out;
+	}
+
+	// The whole recording, or none if it is not one, is from another
+	// protocol version, or breaks off mid-message.
+	inline std::optional<std::vector<RecordedMessage>> ReadRecording(std::istream& in)
+	{
+		std::vector<char> chars{ std::istreambuf_iterator<char>(in), std::istreambuf_iterator<char>() };
+		std::vector<std::byte> bytes{ (const std::byte*)chars.data(), (const std::byte*)chars.data() + chars.size() };
+		if (bytes.size() < recording_magic.size() || !std::equal(recording_magic.begin(), recording_magic.end(), bytes.begin()))
+			return std::nullopt;
+
+		zpp::bits::in reader{ bytes };
+		reader.position() = recording_magic.size();
+		uint32_t version = 0;
+		if (failure(reader(version)) || version != protocol_version)
+			return std::nullopt;
+
+		std::vector<RecordedMessage> out;
+		while (reader.position() < bytes.size()) {
+			RecordedMessage record;
+			if (failure(reader(record)))
+				return std::nullopt;
+			out.push_back(std::move(record));
+		}
+		return out;
+	}
+}
@@ -0,0 +1,19 @@
+#pragma once
+
+#include <chrono>
+#include <cstdint>
+
+// Wall-clock time as the protocol carries it: unix seconds, or milliseconds
+// where a field says so. Both ends read the clock through these, so a rule
+// they share (WhisperAllowed) compares like with like.
+namespace Weyvelength::Proto {
+	inline uint64_t UnixNow()
+	{
+		return (uint64_t)std::chrono::duration_cast<std::chrono::seconds>(std::chrono::system_clock::now().time_since_epoch()).count();
+	}
+
+	inline uint64_t UnixNowMs()
+	{
+		return (uint64_t)std::chrono::duration_cast<std::chrono::milliseconds>(std::chrono::system_clock::now().time_since_epoch()).count();
+	}
+}
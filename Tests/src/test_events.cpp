@@ -0,0 +1,187 @@
+#include <thirdparty/doctest/doctest.h>
+
+#include <queue>
+#include <set>
+#include <string>
+#include <vector>
+
+#include "marshal.h"
+
+using namespace Weyvelength;
+
+namespace {
+	template <typename T>
+	bool Pop(std::queue<T>& queue, T& out)
+	{
+		if (queue.empty())
+			return false;
+		out = std::move(queue.front());
+		queue.pop();
+		return true;
+	}
+
+	// Stands in for the Client behind weyve_next: each queue is filled by
+	// hand, in whatever order the test wants them raised.
+	struct ScriptedSource {
+		std::queue<UnsentMessage> send_failures;
+		std::queue<PeerEvent> peer_events;
+		std::queue<RequestKind> timeouts;
+		std::queue<JoinCheck> join_checks;
+		std::queue<CountdownTick> ticks;
+		std::queue<ProcessEvent> game_events;
+		std::queue<PortMapping> port_mappings;
+		std::queue<Proto::ServerMessage> inbox;
+		std::string room; // ours; inbox messages take it as they are pulled
+
+		bool NextSendFailure(UnsentMessage& out) { return Pop(send_failures, out); }
+		bool NextPeerEvent(PeerEvent& out) { return Pop(peer_events, out); }
+		bool NextTimeout(RequestKind& out) { return Pop(timeouts, out); }
+		bool NextJoinCheck(JoinCheck& out) { return Pop(join_checks, out); }
+		bool NextCountdownTick(CountdownTick& out) { return Pop(ticks, out); }
+		bool NextGameServerEvent(ProcessEvent& out) { return Pop(game_events, out); }
+		bool NextPortMapping(PortMapping& out) { return Pop(port_mappings, out); }
+		bool Next(Proto::ServerMessage& out, std::string& in) { in = room; return Pop(inbox, out); }
+		const std::string& RoomId() const { return room; }
+	};
+
+	// The recording half: every event one weyve_next loop would hand out,
+	// by type, until it comes back empty.
+	std::vector<WeyveEventType> Drain(ScriptedSource& source)
+	{
+		Marshal::EventScratch scratch;
+		std::vector<WeyveEventType> events;
+		WeyveEvent event{};
+		while (Marshal::NextEvent(source, scratch, &event))
+			events.push_back(event.type);
+		return events;
+	}
+}
+
+TEST_CASE("a held send goes ahead of everything else")
+{
+	ScriptedSource source;
+	source.inbox.push(Proto::RoomChat{ 2, "gg" });
+	source.peer_events.push({ PeerEvent::Kind::Closed, 2 });
+	source.send_failures.push({ 1, 0, "wp" });
+
+	std::vector<WeyveEventType> expected{
+		WEYVE_EVENT_MESSAGE_SEND_FAILED,
+		WEYVE_EVENT_PEER_CLOSED,
+		WEYVE_EVENT_CHAT,
+	};
+	CHECK(Drain(source) == expected);
+}
+
+TEST_CASE("the client's own events come in a fixed order, the server's last")
+{
+	ScriptedSource source;
+	source.inbox.push(Proto::PeerJoined{ 3 });
+	source.port_mappings.push({});
+	source.game_events.push({ ProcessEvent::Kind::Exited, {}, 0 });
+	source.ticks.push({ 1, 0 });
+	source.join_checks.push({ "VY4C3NB9" });
+	source.timeouts.push(RequestKind::Room);
+	source.peer_events.push({ PeerEvent::Kind::Retrying, 3, 1, 500 });
+
+	std::vector<WeyveEventType> expected{
+		WEYVE_EVENT_PEER_RETRYING,
+		WEYVE_EVENT_REQUEST_TIMED_OUT,
+		WEYVE_EVENT_JOIN_CHECKED,
+		WEYVE_EVENT_MATCH_START,
+		WEYVE_EVENT_GAME_SERVER_EXITED,
+		WEYVE_EVENT_PORT_MAPPING,
+		WEYVE_EVENT_PEER_JOINED,
+	};
+	CHECK(Drain(source) == expected);
+}
+
+TEST_CASE("each queue drains oldest first before the next is looked at")
+{
+	ScriptedSource source;
+	source.ticks.push({ 1, 2 });
+	source.ticks.push({ 1, 1 });
+	source.ticks.push({ 1, 0 });
+	source.peer_events.push({ PeerEvent::Kind::Retrying, 3, 1, 500 });
+	source.peer_events.push({ PeerEvent::Kind::Unreachable, 3, 2, 0 });
+
+	std::vector<WeyveEventType> expected{
+		WEYVE_EVENT_PEER_RETRYING,
+		WEYVE_EVENT_PEER_UNREACHABLE,
+		WEYVE_EVENT_COUNTDOWN_TICK,
+		WEYVE_EVENT_COUNTDOWN_TICK,
+		WEYVE_EVENT_MATCH_START,
+	};
+	CHECK(Drain(source) == expected);
+}
+
+TEST_CASE("server messages with no event are skipped, not handed out empty")
+{
+	ScriptedSource source;
+	source.inbox.push(Proto::AssignClientId{ 7 });
+	source.inbox.push(Proto::RoomChat{ 8, "gg" });
+	source.inbox.push(Proto::AssignClientId{ 7 });
+
+	std::vector<WeyveEventType> expected{ WEYVE_EVENT_CHAT };
+	CHECK(Drain(source) == expected);
+	CHECK(source.inbox.empty());
+}
+
+TEST_CASE("the last event's strings stay valid until the next pull")
+{
+	ScriptedSource source;
+	source.send_failures.push({ 4, 9, "still there?" });
+	source.inbox.push(Proto::RoomChat{ 8, "gg" });
+
+	Marshal::EventScratch scratch;
+	WeyveEvent event{};
+	REQUIRE(Marshal::NextEvent(source, scratch, &event));
+	CHECK(event.data.send_failed.id == 4);
+	CHECK(event.data.send_failed.to == 9);
+	CHECK(std::string(event.data.send_failed.text, event.data.send_failed.text_len) == "still there?");
+
+	REQUIRE(Marshal::NextEvent(source, scratch, &event));
+	CHECK(event.type == WEYVE_EVENT_CHAT);
+	CHECK(std::string(event.data.chat.text, event.data.chat.text_len) == "gg");
+}
+
+TEST_CASE("every event type has its own name, none for NONE")
+{
+	std::set<std::string> names;
+	for (int type = WEYVE_EVENT_HEARTBEAT; type <= WEYVE_EVENT_SCHEDULED_ROOM_CANCELLED; type++) { // the last type; move this along as types are added
+		const char* name = Marshal::EventName((WeyveEventType)type);
+		REQUIRE(name);
+		CHECK(names.insert(name).second);
+	}
+	CHECK(Marshal::EventName(WEYVE_EVENT_NONE) == nullptr);
+	CHECK(std::string(Marshal::EventName(WEYVE_EVENT_PEER_JOINED)) == "peer_joined");
+	CHECK(std::string(Marshal::EventName(WEYVE_EVENT_MESSAGE_SEND_FAILED)) == "message_send_failed");
+}
+
+TEST_CASE("every event carries its scope and the room it belongs to")
+{
+	ScriptedSource source;
+	source.room = "VY4C3NB9";
+	source.inbox.push(Proto::RoomChat{ 2, "gg" });
+	source.inbox.push(Proto::Heartbeat{ 1 });
+	source.peer_events.push({ PeerEvent::Kind::Closed, 2 });
+
+	Marshal::EventScratch scratch;
+	WeyveEvent e{};
+	REQUIRE(Marshal::NextEvent(source, scratch, &e));
+	CHECK(e.type == WEYVE_EVENT_PEER_CLOSED);
+	CHECK(e.scope == WEYVE_SCOPE_MEMBER);
+	CHECK(e.scope_id == 2);
+	CHECK(std::string(e.room, e.room_len) == "VY4C3NB9");
+
+	source.room = "K7PQ2MXR"; // the chat came in before a move
+	REQUIRE(Marshal::NextEvent(source, scratch, &e));
+	CHECK(e.type == WEYVE_EVENT_CHAT);
+	CHECK(e.scope == WEYVE_SCOPE_ROOM);
+	CHECK(e.scope_id == 0);
+	CHECK(std::string(e.room, e.room_len) == "K7PQ2MXR");
+
+	REQUIRE(Marshal::NextEvent(source, scratch, &e));
+	CHECK(e.type == WEYVE_EVENT_HEARTBEAT);
+	CHECK(e.scope == WEYVE_SCOPE_CLIENT);
+	CHECK(e.room_len == 0);
+}
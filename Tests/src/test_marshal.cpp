@@ -1,18 +1,55 @@
 #include <thirdparty/doctest/doctest.h>
 
+#include <deque>
 #include <map>
 #include <string>
 #include <variant>
+#include <vector>
 
+#include "fec.h"
 #include "marshal.h"
 
 using namespace Weyvelength;
 
 // A new alternative that reaches the client needs a case in FillEvent, or it
 // falls through to false; these pins flag a variant or error-enum change.
-static_assert(std::variant_size_v<Proto::ServerMessage> == 25);
+static_assert(std::variant_size_v<Proto::ServerMessage> == 133);
 static_assert((int)WEYVE_ROOM_ERROR_ALREADY_IN_ROOM == (int)Proto::RoomErrorCode::AlreadyInRoom);
-static_assert((int)WEYVE_ROOM_ERROR_BANNED == (int)Proto::RoomErrorCode::Banned);
+static_assert((int)WEYVE_ROOM_ERROR_BAD_CHAT == (int)Proto::RoomErrorCode::BadChat);
+static_assert((int)WEYVE_ROOM_ERROR_BAD_BANNER == (int)Proto::RoomErrorCode::BadBanner);
+static_assert((int)WEYVE_ROOM_ERROR_BAD_TEAM == (int)Proto::RoomErrorCode::BadTeam);
+static_assert((int)WEYVE_ROOM_ERROR_BAD_CHANNEL == (int)Proto::RoomErrorCode::BadChannel);
+static_assert((int)WEYVE_ROOM_ERROR_BAD_ROLE == (int)Proto::RoomErrorCode::BadRole);
+static_assert((int)WEYVE_ROOM_ERROR_BAD_SUCCESSION == (int)Proto::RoomErrorCode::BadSuccession);
+static_assert((int)WEYVE_ACCOUNT_ERROR_DISABLED == (int)Proto::AccountErrorCode::Disabled);
+static_assert((int)WEYVE_ACCOUNT_ERROR_STORE_FAILED == (int)Proto::AccountErrorCode::StoreFailed);
+static_assert((int)WEYVE_ACCOUNT_ERROR_TOO_SOON == (int)Proto::AccountErrorCode::TooSoon);
+static_assert((int)WEYVE_ACCOUNT_ERROR_BAD_PREFERENCES == (int)Proto::AccountErrorCode::BadPreferences);
+static_assert((int)WEYVE_BLOB_ERROR_DISABLED == (int)Proto::BlobErrorCode::Disabled);
+static_assert((int)WEYVE_BLOB_ERROR_STORE_FAILED == (int)Proto::BlobErrorCode::StoreFailed);
+static_assert((int)WEYVE_BLOB_ERROR_QUOTA == (int)Proto::BlobErrorCode::Quota);
+static_assert((int)WEYVE_ROLE_MEMBER == (int)Proto::MemberRole::Member);
+static_assert((int)WEYVE_ROLE_CO_HOST == (int)Proto::MemberRole::CoHost);
+static_assert((int)WEYVE_WHISPERS_EVERYONE == (int)Proto::WhisperPolicy::Everyone);
+static_assert((int)WEYVE_WHISPERS_NOBODY == (int)Proto::WhisperPolicy::Nobody);
+static_assert((int)WEYVE_RSVP_MAYBE == (int)Proto::RsvpStatus::Maybe);
+static_assert((int)WEYVE_PORT_MAPPING_UPNP == (int)PortMapping::Method::Upnp);
+static_assert((int)WEYVE_CANDIDATE_RELAYED == (int)CandidateType::Relayed);
+static_assert((int)WEYVE_TIMELINE_JOINED == (int)Proto::TimelineKind::Joined);
+static_assert((int)WEYVE_TIMELINE_MOVED == (int)Proto::TimelineKind::Moved);
+static_assert((int)WEYVE_RECEIPT_LEFT == (int)Proto::ReceiptKind::Left);
+static_assert((int)WEYVE_RECEIPT_SPECTATOR == (int)Proto::ReceiptRole::Spectator);
+static_assert((int)WEYVE_HANDOFF_DROPPED == (int)Proto::HandoffReason::Dropped);
+static_assert((int)WEYVE_SUCCESSION_SUCCESSORS == (int)Proto::SuccessionPolicy::Successors);
+static_assert((int)WEYVE_REQUEST_ROOM == (int)RequestKind::Room);
+static_assert((int)WEYVE_REQUEST_ACCOUNT == (int)RequestKind::Account);
+static_assert((int)WEYVE_LIMIT_USERS == (int)Proto::ServerLimit::Users);
+static_assert((int)WEYVE_LIMIT_PER_ADDRESS == (int)Proto::ServerLimit::PerAddress);
+static_assert((int)WEYVE_LIMIT_REQUESTS == (int)Proto::ServerLimit::Requests);
+static_assert((int)WEYVE_PERFORMANCE_LOW == (int)PerformanceProfile::Low);
+static_assert((int)WEYVE_CHAT_TEXT == (int)ChatExportFormat::Text);
+static_assert((int)WEYVE_CHAT_CSV == (int)ChatExportFormat::Csv);
+static_assert((int)WEYVE_PERFORMANCE_HIGH == (int)PerformanceProfile::High);
 
 namespace {
 	bool Surfaced(const Proto::ServerMessage& msg)
@@ -61,6 +98,16 @@ TEST_CASE("chat carries the sender and text, spaces included")
 	CHECK(std::string(e.data.chat.text, e.data.chat.text_len) == "two words  and spaces");
 }
 
+TEST_CASE("whisper carries the sender and text")
+{
+	Proto::ServerMessage msg = Proto::RoomWhisper{ 9, "just you" };
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(msg, &e));
+	CHECK(e.type == WEYVE_EVENT_WHISPER);
+	CHECK(e.data.whisper.from == 9);
+	CHECK(std::string(e.data.whisper.text, e.data.whisper.text_len) == "just you");
+}
+
 TEST_CASE("peer membership events carry the client id")
 {
 	Proto::ServerMessage joined = Proto::PeerJoined{ 3 };
@@ -125,14 +172,1102 @@ TEST_CASE("kick, ban and access notices map to their data-less events")
 	CHECK(e.data.room_access.passworded == true);
 }
 
+TEST_CASE("member role change carries the id and mapped role")
+{
+	Proto::ServerMessage msg = Proto::MemberRoleChanged{ 4, Proto::MemberRole::Moderator };
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(msg, &e));
+	CHECK(e.type == WEYVE_EVENT_MEMBER_ROLE_CHANGED);
+	CHECK(e.data.member_role.id == 4);
+	CHECK(e.data.member_role.role == WEYVE_ROLE_MODERATOR);
+}
+
+TEST_CASE("preferences change carries the dnd deadline and mapped policy")
+{
+	Proto::ServerMessage msg = Proto::PreferencesChanged{ { 1700000000, Proto::WhisperPolicy::Staff } };
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(msg, &e));
+	CHECK(e.type == WEYVE_EVENT_PREFERENCES_CHANGED);
+	CHECK(e.data.preferences.dnd_until == 1700000000);
+	CHECK(e.data.preferences.whispers == WEYVE_WHISPERS_STAFF);
+}
+
+TEST_CASE("spectating and capacity events carry their fields")
+{
+	Proto::ServerMessage spectator = Proto::MemberSpectating{ 6 };
+	Proto::ServerMessage capacity = Proto::RoomCapacityChanged{ 8, 2 };
+	WeyveEvent e{};
+
+	REQUIRE(Marshal::FillEvent(spectator, &e));
+	CHECK(e.type == WEYVE_EVENT_MEMBER_SPECTATING);
+	CHECK(e.data.member_spectating.id == 6);
+
+	REQUIRE(Marshal::FillEvent(capacity, &e));
+	CHECK(e.type == WEYVE_EVENT_ROOM_CAPACITY_CHANGED);
+	CHECK(e.data.room_capacity.max_players == 8);
+	CHECK(e.data.room_capacity.max_spectators == 2);
+}
+
+TEST_CASE("idle policy and afk events carry their fields")
+{
+	Proto::ServerMessage idle = Proto::IdlePolicyChanged{ 300, true };
+	Proto::ServerMessage afk = Proto::MemberAfk{ 6, true };
+	WeyveEvent e{};
+
+	REQUIRE(Marshal::FillEvent(idle, &e));
+	CHECK(e.type == WEYVE_EVENT_IDLE_POLICY_CHANGED);
+	CHECK(e.data.idle_policy.afk_after == 300);
+	CHECK(e.data.idle_policy.spectate == true);
+
+	REQUIRE(Marshal::FillEvent(afk, &e));
+	CHECK(e.type == WEYVE_EVENT_MEMBER_AFK);
+	CHECK(e.data.member_afk.id == 6);
+	CHECK(e.data.member_afk.afk == true);
+}
+
+TEST_CASE("scheduled room events carry the booking, borrowed")
+{
+	Proto::ServerMessage changed = Proto::ScheduledRoomChanged{ { 4, 3, 1700000000, "friday league", 8, 2, true, 5 } };
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(changed, &e));
+	CHECK(e.type == WEYVE_EVENT_SCHEDULED_ROOM_CHANGED);
+	CHECK(e.data.scheduled_room.id == 4);
+	CHECK(e.data.scheduled_room.owner == 3);
+	CHECK(e.data.scheduled_room.start == 1700000000);
+	CHECK(std::string(e.data.scheduled_room.title, e.data.scheduled_room.title_len) == "friday league");
+	CHECK(e.data.scheduled_room.title == std::get<Proto::ScheduledRoomChanged>(changed).room.title.data());
+	CHECK(e.data.scheduled_room.passworded == true);
+	CHECK(e.data.scheduled_room.going == 5);
+
+	Proto::ServerMessage list = Proto::ScheduledRoomList{ { {}, {} } };
+	REQUIRE(Marshal::FillEvent(list, &e));
+	CHECK(e.type == WEYVE_EVENT_SCHEDULED_ROOM_LIST);
+	CHECK(e.data.scheduled_room_list.count == 2);
+
+	Proto::ServerMessage reminder = Proto::ScheduledRoomReminder{ 4, 1700000000 };
+	REQUIRE(Marshal::FillEvent(reminder, &e));
+	CHECK(e.type == WEYVE_EVENT_SCHEDULED_ROOM_REMINDER);
+	CHECK(e.data.scheduled_room_reminder.id == 4);
+	CHECK(e.data.scheduled_room_reminder.start == 1700000000);
+
+	Proto::ServerMessage opened = Proto::ScheduledRoomOpened{ 4, "VY4C3NB9" };
+	REQUIRE(Marshal::FillEvent(opened, &e));
+	CHECK(e.type == WEYVE_EVENT_SCHEDULED_ROOM_OPENED);
+	CHECK(e.data.scheduled_room_opened.id == 4);
+	CHECK(std::string(e.data.scheduled_room_opened.room, e.data.scheduled_room_opened.room_len) == "VY4C3NB9");
+
+	Proto::ServerMessage attendees = Proto::ScheduledRoomAttendees{ 4, { { 3, Proto::RsvpStatus::Going } } };
+	REQUIRE(Marshal::FillEvent(attendees, &e));
+	CHECK(e.type == WEYVE_EVENT_SCHEDULED_ROOM_ATTENDEES);
+	CHECK(e.data.scheduled_room_attendees.id == 4);
+	CHECK(e.data.scheduled_room_attendees.count == 1);
+
+	REQUIRE(Marshal::FillEvent(Proto::ScheduledRoomCancelled{ 4 }, &e));
+	CHECK(e.type == WEYVE_EVENT_SCHEDULED_ROOM_CANCELLED);
+	CHECK(e.data.scheduled_room_cancelled.id == 4);
+
+	uint32_t id = 0;
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_CLIENT);
+}
+
+TEST_CASE("account events carry names and the mapped error")
+{
+	Proto::ServerMessage logged_in = Proto::LoggedIn{ "Alice" };
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(logged_in, &e));
+	CHECK(e.type == WEYVE_EVENT_LOGGED_IN);
+	CHECK(std::string(e.data.logged_in.name, e.data.logged_in.name_len) == "Alice");
+
+	Proto::ServerMessage error = Proto::AccountError{ Proto::AccountErrorCode::WrongCredentials, {} };
+	REQUIRE(Marshal::FillEvent(error, &e));
+	CHECK(e.type == WEYVE_EVENT_ACCOUNT_ERROR);
+	CHECK(e.data.account_error.code == WEYVE_ACCOUNT_ERROR_WRONG_CREDENTIALS);
+	CHECK(e.data.account_error.context_len == 0);
+
+	Proto::ServerMessage name = Proto::MemberNameChanged{ 3, "Alice" };
+	REQUIRE(Marshal::FillEvent(name, &e));
+	CHECK(e.type == WEYVE_EVENT_MEMBER_NAME_CHANGED);
+	CHECK(e.data.member_name.id == 3);
+	CHECK(std::string(e.data.member_name.name, e.data.member_name.name_len) == "Alice");
+}
+
+TEST_CASE("link previews surface every field")
+{
+	Proto::ServerMessage msg = Proto::ChatLinkPreview{ 4, { "https://example.com", "Title", "", "https://example.com/i.png", "Site" } };
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(msg, &e));
+	CHECK(e.type == WEYVE_EVENT_CHAT_LINK_PREVIEW);
+	const WeyveLinkPreview& preview = e.data.link_preview;
+	CHECK(preview.from == 4);
+	CHECK(std::string(preview.url, preview.url_len) == "https://example.com");
+	CHECK(std::string(preview.title, preview.title_len) == "Title");
+	CHECK(preview.description_len == 0);
+	CHECK(std::string(preview.image, preview.image_len) == "https://example.com/i.png");
+	CHECK(std::string(preview.site, preview.site_len) == "Site");
+}
+
+TEST_CASE("blob events carry the transfer, id and mapped error")
+{
+	std::string id(Proto::blob_id_size, 'c');
+	Proto::ServerMessage uploaded = Proto::BlobUploaded{ 3, id };
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(uploaded, &e));
+	CHECK(e.type == WEYVE_EVENT_BLOB_UPLOADED);
+	CHECK(e.data.blob_uploaded.transfer == 3);
+	CHECK(std::string(e.data.blob_uploaded.id, e.data.blob_uploaded.id_len) == id);
+
+	Proto::ServerMessage downloaded = Proto::BlobDownloaded{ 4, id };
+	REQUIRE(Marshal::FillEvent(downloaded, &e));
+	CHECK(e.type == WEYVE_EVENT_BLOB_DOWNLOADED);
+	CHECK(e.data.blob_downloaded.transfer == 4);
+	CHECK(std::string(e.data.blob_downloaded.id, e.data.blob_downloaded.id_len) == id);
+
+	Proto::ServerMessage error = Proto::BlobError{ 5, Proto::BlobErrorCode::TooLarge, "262144" };
+	REQUIRE(Marshal::FillEvent(error, &e));
+	CHECK(e.type == WEYVE_EVENT_BLOB_ERROR);
+	CHECK(e.data.blob_error.transfer == 5);
+	CHECK(e.data.blob_error.code == WEYVE_BLOB_ERROR_TOO_LARGE);
+	CHECK(std::string(e.data.blob_error.context, e.data.blob_error.context_len) == "262144");
+}
+
+TEST_CASE("banner changes carry the blob id, empty when cleared")
+{
+	std::string id(Proto::blob_id_size, 'e');
+	Proto::ServerMessage set = Proto::RoomBannerChanged{ id };
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(set, &e));
+	CHECK(e.type == WEYVE_EVENT_ROOM_BANNER_CHANGED);
+	CHECK(std::string(e.data.room_banner.id, e.data.room_banner.id_len) == id);
+
+	Proto::ServerMessage cleared = Proto::RoomBannerChanged{};
+	REQUIRE(Marshal::FillEvent(cleared, &e));
+	CHECK(e.data.room_banner.id_len == 0);
+}
+
+TEST_CASE("load events carry the limit, the hint and the counts")
+{
+	Proto::ServerMessage full = Proto::ServerFull{ Proto::ServerLimit::Rooms, 30 };
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(full, &e));
+	CHECK(e.type == WEYVE_EVENT_SERVER_FULL);
+	CHECK(e.data.server_full.limit == WEYVE_LIMIT_ROOMS);
+	CHECK(e.data.server_full.retry_after == 30);
+
+	Proto::ServerMessage info = Proto::ServerInfo{ 12, 100, 3, 0 };
+	REQUIRE(Marshal::FillEvent(info, &e));
+	CHECK(e.type == WEYVE_EVENT_SERVER_INFO);
+	CHECK(e.data.server_info.users == 12);
+	CHECK(e.data.server_info.max_users == 100);
+	CHECK(e.data.server_info.rooms == 3);
+	CHECK(e.data.server_info.max_rooms == 0);
+}
+
+TEST_CASE("an outdated client hears the version it needs, as an error that will not clear by itself")
+{
+	Proto::ServerMessage outdated = Proto::ClientOutdated{ "1.5", "https://example.com/get" };
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(outdated, &e));
+	CHECK(e.type == WEYVE_EVENT_CLIENT_OUTDATED);
+	CHECK(std::string(e.data.client_version.update_url, e.data.client_version.update_url_len) == "https://example.com/get");
+
+	WeyveError error{};
+	REQUIRE(Marshal::FillError(e, &error));
+	CHECK(error.domain == WEYVE_ERROR_CLIENT_OUTDATED);
+	CHECK(!error.retryable);
+	CHECK(Marshal::FormatError(error, nullptr) == "This server needs version 1.5 of the app or newer.");
+}
+
+TEST_CASE("room previews carry the room and the list sizes")
+{
+	Proto::RoomPreview preview{ "ROOMCODE", 3, true, true, 2, 1, 4, 0, "banner" };
+	preview.members = { { 3, "Alice", false } };
+	Proto::ServerMessage msg = preview;
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(msg, &e));
+	CHECK(e.type == WEYVE_EVENT_ROOM_PREVIEW);
+	CHECK(std::string(e.data.room_preview.id, e.data.room_preview.id_len) == "ROOMCODE");
+	CHECK(e.data.room_preview.host == 3);
+	CHECK(e.data.room_preview.passworded);
+	CHECK(e.data.room_preview.players == 2);
+	CHECK(e.data.room_preview.spectators == 1);
+	CHECK(e.data.room_preview.max_players == 4);
+	CHECK(std::string(e.data.room_preview.banner, e.data.room_preview.banner_len) == "banner");
+	CHECK(e.data.room_preview.member_count == 1);
+	CHECK(e.data.room_preview.data_count == 0);
+
+	uint32_t id = 0;
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_CLIENT); // not about the room you are in
+}
+
+TEST_CASE("knock events reach the host's room and the knocker's client")
+{
+	Proto::ServerMessage knock = Proto::JoinRequested{ 9, "Bob", "let me in" };
+	WeyveEvent e{};
+	uint32_t id = 0;
+	REQUIRE(Marshal::FillEvent(knock, &e));
+	CHECK(e.type == WEYVE_EVENT_JOIN_REQUESTED);
+	CHECK(e.data.join_requested.id == 9);
+	CHECK(std::string(e.data.join_requested.name, e.data.join_requested.name_len) == "Bob");
+	CHECK(std::string(e.data.join_requested.message, e.data.join_requested.message_len) == "let me in");
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_ROOM);
+
+	Proto::ServerMessage answered = Proto::JoinRequestAnswered{ "ROOMCODE", true };
+	REQUIRE(Marshal::FillEvent(answered, &e));
+	CHECK(e.type == WEYVE_EVENT_JOIN_REQUEST_ANSWERED);
+	CHECK(std::string(e.data.join_answered.room, e.data.join_answered.room_len) == "ROOMCODE");
+	CHECK(e.data.join_answered.approved);
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_CLIENT);
+}
+
+TEST_CASE("waiting line events carry the room, the place and the offer")
+{
+	Proto::ServerMessage position = Proto::RoomQueuePosition{ "ROOMCODE", 2 };
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(position, &e));
+	CHECK(e.type == WEYVE_EVENT_QUEUE_POSITION);
+	CHECK(std::string(e.data.queue_position.room, e.data.queue_position.room_len) == "ROOMCODE");
+	CHECK(e.data.queue_position.position == 2);
+
+	Proto::ServerMessage offer = Proto::SeatOffered{ "ROOMCODE", 30 };
+	REQUIRE(Marshal::FillEvent(offer, &e));
+	CHECK(e.type == WEYVE_EVENT_SEAT_OFFERED);
+	CHECK(std::string(e.data.seat_offered.room, e.data.seat_offered.room_len) == "ROOMCODE");
+	CHECK(e.data.seat_offered.expires_in == 30);
+}
+
+TEST_CASE("user search events carry the query, the count and the opt-out")
+{
+	Proto::ServerMessage results = Proto::UserSearchResults{ "ali", { { "Alice", 7 }, { "Malik" } } };
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(results, &e));
+	CHECK(e.type == WEYVE_EVENT_USER_SEARCH_RESULTS);
+	CHECK(std::string(e.data.user_search.query, e.data.user_search.query_len) == "ali");
+	CHECK(e.data.user_search.count == 2);
+
+	Proto::ServerMessage searchable = Proto::SearchableChanged{ false };
+	REQUIRE(Marshal::FillEvent(searchable, &e));
+	CHECK(e.type == WEYVE_EVENT_SEARCHABLE_CHANGED);
+	CHECK_FALSE(e.data.searchable.searchable);
+	uint32_t id = 0;
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_CLIENT);
+}
+
+TEST_CASE("a data export surfaces bare and reads back as JSON")
+{
+	Proto::MyData data{ 7, "Al\"ice", false, {}, true, { 0, Proto::WhisperPolicy::Nobody }, "ROOMCODE" };
+	data.rsvps.push_back({ 5, Proto::RsvpStatus::Going });
+	data.knocks.push_back("KNOCKED1");
+
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(Proto::ServerMessage{ data }, &e));
+	CHECK(e.type == WEYVE_EVENT_MY_DATA);
+
+	std::string json = Marshal::MyDataJson(data);
+	CHECK(json.starts_with("{\n  \"id\": 7,\n  \"name\": \"Al\\\"ice\","));
+	CHECK(json.find("\"whispers\": \"nobody\"") != std::string::npos);
+	CHECK(json.find("\"bookings\": [],") != std::string::npos);
+	CHECK(json.find("\"rsvps\": [{ \"booking\": 5, \"status\": \"going\" }]") != std::string::npos);
+	CHECK(json.ends_with("\"knocks\": [\"KNOCKED1\"]\n}\n"));
+}
+
+TEST_CASE("timeline events carry the entry, or the reply's size")
+{
+	WeyveEvent e{};
+	Proto::ServerMessage added = Proto::TimelineAdded{ { 4, 1700000000, Proto::TimelineKind::Kicked, 6, "Bob", 2 } };
+	REQUIRE(Marshal::FillEvent(added, &e));
+	CHECK(e.type == WEYVE_EVENT_TIMELINE_ENTRY);
+	CHECK(e.data.timeline_entry.seq == 4);
+	CHECK(e.data.timeline_entry.time == 1700000000);
+	CHECK(e.data.timeline_entry.kind == WEYVE_TIMELINE_KICKED);
+	CHECK(e.data.timeline_entry.id == 6);
+	CHECK(std::string(e.data.timeline_entry.name, e.data.timeline_entry.name_len) == "Bob");
+	CHECK(e.data.timeline_entry.by == 2);
+
+	Proto::ServerMessage log = Proto::Timeline{ { { 1, 0, Proto::TimelineKind::Joined, 2 }, { 2, 0, Proto::TimelineKind::Opened, 0, {}, 2 } } };
+	REQUIRE(Marshal::FillEvent(log, &e));
+	CHECK(e.type == WEYVE_EVENT_TIMELINE);
+	CHECK(e.data.timeline.count == 2);
+}
+
+TEST_CASE("rules changes carry the version and the payload")
+{
+	WeyveEvent e{};
+	Proto::ServerMessage set = Proto::RoomRulesChanged{ 2, R"({"stocks":3})" };
+	REQUIRE(Marshal::FillEvent(set, &e));
+	CHECK(e.type == WEYVE_EVENT_ROOM_RULES_CHANGED);
+	CHECK(e.data.room_rules.version == 2);
+	CHECK(std::string(e.data.room_rules.rules, e.data.room_rules.rules_len) == R"({"stocks":3})");
+
+	uint32_t id = 0;
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_ROOM);
+}
+
+TEST_CASE("countdowns surface as started, ticking, then the start")
+{
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(Proto::ServerMessage{ Proto::CountdownStarted{ 2, 3, 1700000003000, 3000, 5 } }, &e));
+	CHECK(e.type == WEYVE_EVENT_COUNTDOWN);
+	CHECK(e.data.countdown.id == 2);
+	CHECK(e.data.countdown.seconds == 3);
+	CHECK(e.data.countdown.by == 5);
+
+	Marshal::FillCountdownTick({ 2, 1 }, &e);
+	CHECK(e.type == WEYVE_EVENT_COUNTDOWN_TICK);
+	CHECK(e.data.countdown_tick.id == 2);
+	CHECK(e.data.countdown_tick.remaining == 1);
+
+	Marshal::FillCountdownTick({ 2, 0 }, &e);
+	CHECK(e.type == WEYVE_EVENT_MATCH_START);
+	uint32_t id = 0;
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_ROOM);
+}
+
+TEST_CASE("match pauses carry the state and who set it")
+{
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(Proto::ServerMessage{ Proto::MatchPausedChanged{ true, 3 } }, &e));
+	CHECK(e.type == WEYVE_EVENT_MATCH_PAUSED);
+	CHECK(e.data.match_paused.paused);
+	CHECK(e.data.match_paused.by == 3);
+}
+
+TEST_CASE("member health reaches the host as a member event with its links")
+{
+	Proto::MemberHealth health{ 4, 35, { { 2, true, false, 12, 8000, 7600 }, { 3, false, false, 0, 0, 0 } } };
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(Proto::ServerMessage{ health }, &e));
+	CHECK(e.type == WEYVE_EVENT_MEMBER_HEALTH);
+	CHECK(e.data.member_health.from == 4);
+	CHECK(e.data.member_health.server_rtt == 35);
+	CHECK(e.data.member_health.link_count == 2);
+
+	uint32_t id = 0;
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_MEMBER);
+	CHECK(id == 4);
+
+	WeyveLinkHealth link{};
+	Marshal::FillLinkHealth(health.links[0], &link);
+	CHECK(link.peer == 2);
+	CHECK(link.direct);
+	CHECK(!link.relayed);
+	CHECK(link.loss_permille == 12);
+	CHECK(link.send_rate == 8000);
+	CHECK(link.receive_rate == 7600);
+}
+
+TEST_CASE("the server's session health arrives as a room event with its count")
+{
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(Proto::ServerMessage{ Proto::SessionHealth{ { { { 2, 40, {} }, 3 }, { { 5, 0, {} }, 12 } } } }, &e));
+	CHECK(e.type == WEYVE_EVENT_SESSION_HEALTH);
+	CHECK(e.data.session_health.count == 2);
+
+	uint32_t id = 0;
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_ROOM);
+}
+
+TEST_CASE("join receipts surface their key and count, each receipt its fields")
+{
+	Proto::ServerMessage msg = Proto::JoinReceipts{ "ab12", { { "ROOMCODE", 3, "Alice", Proto::ReceiptRole::Host, Proto::ReceiptKind::Joined, 1769882645, "cd34" } } };
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(msg, &e));
+	CHECK(e.type == WEYVE_EVENT_JOIN_RECEIPTS);
+	CHECK(std::string(e.data.join_receipts.key, e.data.join_receipts.key_len) == "ab12");
+	CHECK(e.data.join_receipts.count == 1);
+
+	uint32_t id = 0;
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_ROOM);
+
+	WeyveJoinReceipt receipt{};
+	Marshal::FillJoinReceipt(std::get<Proto::JoinReceipts>(msg).receipts[0], &receipt);
+	CHECK(std::string(receipt.room, receipt.room_len) == "ROOMCODE");
+	CHECK(receipt.id == 3);
+	CHECK(std::string(receipt.name, receipt.name_len) == "Alice");
+	CHECK(receipt.role == WEYVE_RECEIPT_HOST);
+	CHECK(receipt.kind == WEYVE_RECEIPT_JOINED);
+	CHECK(receipt.time == 1769882645);
+	CHECK(std::string(receipt.signature, receipt.signature_len) == "cd34");
+}
+
+TEST_CASE("a succession policy surfaces with its successors")
+{
+	Proto::ServerMessage msg = Proto::SuccessionChanged{ Proto::SuccessionPolicy::Successors, { 4, 7 } };
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(msg, &e));
+	CHECK(e.type == WEYVE_EVENT_SUCCESSION_CHANGED);
+	CHECK(e.data.succession.policy == WEYVE_SUCCESSION_SUCCESSORS);
+	REQUIRE(e.data.succession.count == 2);
+	CHECK(e.data.succession.successors[0] == 4);
+	CHECK(e.data.succession.successors[1] == 7);
+
+	uint32_t id = 0;
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_ROOM);
+}
+
+TEST_CASE("splits and merges surface the rooms they move members between")
+{
+	Proto::ServerMessage msg = Proto::MergeRequested{ "ROOMCODE", 3, 8 };
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(msg, &e));
+	CHECK(e.type == WEYVE_EVENT_MERGE_REQUESTED);
+	CHECK(std::string(e.data.merge_requested.into, e.data.merge_requested.into_len) == "ROOMCODE");
+	CHECK(e.data.merge_requested.by == 3);
+	CHECK(e.data.merge_requested.members == 8);
+
+	msg = Proto::MergeAnswered{ "OTHERONE", true };
+	REQUIRE(Marshal::FillEvent(msg, &e));
+	CHECK(e.type == WEYVE_EVENT_MERGE_ANSWERED);
+	CHECK(std::string(e.data.merge_answered.id, e.data.merge_answered.id_len) == "OTHERONE");
+	CHECK(e.data.merge_answered.accepted);
+
+	msg = Proto::RoomSplit{ "NEWROOM1", 4 };
+	REQUIRE(Marshal::FillEvent(msg, &e));
+	CHECK(e.type == WEYVE_EVENT_ROOM_SPLIT);
+	CHECK(e.data.room_split.members == 4);
+
+	msg = Proto::RoomMoved{ "NEWROOM1", 3 };
+	REQUIRE(Marshal::FillEvent(msg, &e));
+	CHECK(e.type == WEYVE_EVENT_ROOM_MOVED);
+	CHECK(std::string(e.data.room_moved.id, e.data.room_moved.id_len) == "NEWROOM1");
+	CHECK(e.data.room_moved.by == 3);
+
+	uint32_t id = 0;
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_ROOM);
+}
+
+TEST_CASE("a host hand-off surfaces the saved state to the new host")
+{
+	Proto::ServerMessage msg = Proto::HostHandoff{ 3, Proto::HandoffReason::Dropped, 1769882645, std::string("\0frame", 6) };
+	WeyveEvent e{};
+	REQUIRE(Marshal::FillEvent(msg, &e));
+	CHECK(e.type == WEYVE_EVENT_HOST_HANDOFF);
+	CHECK(e.data.host_handoff.from == 3);
+	CHECK(e.data.host_handoff.reason == WEYVE_HANDOFF_DROPPED);
+	CHECK(e.data.host_handoff.saved == 1769882645);
+	CHECK(std::string((const char*)e.data.host_handoff.state, e.data.host_handoff.state_len) == std::string("\0frame", 6));
+
+	uint32_t id = 0;
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_ROOM);
+
+	msg = Proto::MemberHostState{ 5, "view" };
+	REQUIRE(Marshal::FillEvent(msg, &e));
+	CHECK(e.type == WEYVE_EVENT_MEMBER_HOST_STATE);
+	CHECK(std::string((const char*)e.data.member_host_state.state, e.data.member_host_state.state_len) == "view");
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_MEMBER);
+	CHECK(id == 5);
+}
+
+TEST_CASE("the client log keeps its newest lines at or above its level")
+{
+	LogRing log;
+	log.Add(LogLevel::Debug, "below the level");
+	log.Add(LogLevel::Info, "connection lost");
+	log.Add(LogLevel::Warn, "link to 3 failed, redialing");
+	CHECK(log.entries.size() == 2);
+
+	auto warnings = log.Recent(LogLevel::Warn, 0);
+	REQUIRE(warnings.size() == 1);
+	CHECK(warnings[0].text == "link to 3 failed, redialing");
+
+	for (size_t i = 0; i < max_log_entries; i++)
+		log.Add(LogLevel::Error, "line " + std::to_string(i));
+	CHECK(log.entries.size() == max_log_entries);
+	auto last = log.Recent(LogLevel::Debug, 2);
+	REQUIRE(last.size() == 2);
+	CHECK(last[0].text == "line " + std::to_string(max_log_entries - 2));
+	CHECK(last[1].text == "line " + std::to_string(max_log_entries - 1));
+
+	WeyveLogEntry entry{};
+	Marshal::FillLogEntry(last[1], &entry);
+	CHECK(entry.level == WEYVE_LOG_ERROR);
+	CHECK(std::string(entry.text, entry.text_len) == last[1].text);
+}
+
+TEST_CASE("NAT-PMP requests and replies keep the RFC 6886 layout")
+{
+	auto request = NatPmpMapRequest(7777, 7777, 3600);
+	CHECK(request == std::array<uint8_t, 12>{ 0, 1, 0, 0, 0x1e, 0x61, 0x1e, 0x61, 0, 0, 0x0e, 0x10 });
+
+	const uint8_t granted[16] = { 0, 129, 0, 0, 0, 0, 0, 9, 0x1e, 0x61, 0x9c, 0x40, 0, 0, 0x07, 0x08 };
+	auto map = ParseNatPmpMapResponse(granted, sizeof(granted));
+	REQUIRE(map);
+	CHECK(map->result == 0);
+	CHECK(map->internal_port == 7777);
+	CHECK(map->external_port == 40000);
+	CHECK(map->lifetime == 1800);
+
+	const uint8_t refused[16] = { 0, 129, 0, 2 };
+	CHECK(ParseNatPmpMapResponse(refused, sizeof(refused))->result == 2);
+	CHECK_FALSE(ParseNatPmpMapResponse(granted, 12)); // short
+	CHECK_FALSE(ParseNatPmpMapResponse(refused, 4));
+
+	const uint8_t address[12] = { 0, 128, 0, 0, 0, 0, 0, 9, 203, 0, 113, 7 };
+	CHECK(ParseNatPmpAddressResponse(address, sizeof(address)) == std::array<uint8_t, 4>{ 203, 0, 113, 7 });
+	CHECK_FALSE(ParseNatPmpAddressResponse(granted, sizeof(granted))); // a map reply, not an address one
+}
+
+TEST_CASE("UPnP discovery and description parsing finds the WAN service")
+{
+	std::string ssdp = "HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age=120\r\nlocation:  http://192.168.1.1:5000/rootDesc.xml \r\nST: urn:schemas-upnp-org:device:InternetGatewayDevice:1\r\n\r\n";
+	CHECK(HttpHeader(ssdp, "LOCATION") == "http://192.168.1.1:5000/rootDesc.xml");
+	CHECK(HttpHeader(ssdp, "SERVER").empty());
+
+	auto url = ParseHttpUrl(HttpHeader(ssdp, "Location"));
+	REQUIRE(url);
+	CHECK(url->host == "192.168.1.1");
+	CHECK(url->port == 5000);
+	CHECK(url->path == "/rootDesc.xml");
+	CHECK(ParseHttpUrl("http://10.0.0.1")->path == "/");
+	CHECK_FALSE(ParseHttpUrl("https://10.0.0.1/"));
+	CHECK_FALSE(ParseHttpUrl("http://10.0.0.1:99999/"));
+
+	std::string description =
+		"<root><device><serviceList>"
+		"<service><serviceType>urn:schemas-upnp-org:service:WANPPPConnection:1</serviceType><controlURL>/ctl/PPP</controlURL></service>"
+		"<service><serviceType>urn:schemas-upnp-org:service:WANIPConnection:2</serviceType><controlURL>ctl/IPConn</controlURL></service>"
+		"</serviceList></device></root>";
+	auto service = FindWanService(description);
+	REQUIRE(service);
+	CHECK(service->type == "urn:schemas-upnp-org:service:WANIPConnection:2"); // IP over PPP
+	CHECK(ResolveControlUrl(*url, service->control_url)->path == "/ctl/IPConn");
+	CHECK(ResolveControlUrl(*url, "http://192.168.1.1:6000/x")->port == 6000);
+	CHECK_FALSE(FindWanService("<root><service><serviceType>urn:schemas-upnp-org:service:Layer3Forwarding:1</serviceType><controlURL>/l3f</controlURL></service></root>"));
+
+	CHECK(XmlValue("<s:Body><u:GetExternalIPAddressResponse><NewExternalIPAddress>203.0.113.7</NewExternalIPAddress></u:GetExternalIPAddressResponse></s:Body>", "NewExternalIPAddress") == "203.0.113.7");
+	CHECK(XmlValue("<u:errorCode>725</u:errorCode>", "errorCode") == "725");
+}
+
+TEST_CASE("HTTP replies from a gateway are read whole, chunked or not")
+{
+	auto plain = ParseHttpResponse("HTTP/1.1 500 Internal Server Error\r\nContent-Type: text/xml\r\n\r\n<errorCode>718</errorCode>");
+	REQUIRE(plain);
+	CHECK(plain->status == 500);
+	CHECK(plain->body == "<errorCode>718</errorCode>");
+
+	auto chunked = ParseHttpResponse("HTTP/1.1 200 OK\r\nTransfer-Encoding: Chunked\r\n\r\n5\r\n<root\r\nA;ext=1\r\n></root>xx\r\n0\r\n\r\n");
+	REQUIRE(chunked);
+	CHECK(chunked->status == 200);
+	CHECK(chunked->body == "<root></root>xx");
+
+	CHECK_FALSE(ParseHttpResponse("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nff\r\nshort"));
+	CHECK_FALSE(ParseHttpResponse("SSH-2.0-OpenSSH\r\n\r\n"));
+}
+
+using Datagrams = std::vector<std::vector<std::byte>>;
+
+static std::vector<std::byte> Datagram(uint8_t tag)
+{
+	return { std::byte{ tag } };
+}
+
+TEST_CASE("the jitter buffer hands datagrams on in order, each once")
+{
+	using namespace std::chrono_literals;
+	JitterBuffer buffer;
+	Datagrams out;
+	auto t = JitterBuffer::Clock::time_point{} + 1h;
+
+	CHECK(buffer.Push(11, Datagram(11), t)); // the first to land need not be the first sent
+	CHECK(buffer.Push(10, Datagram(10), t));
+	buffer.Pop(t + 10ms, 30ms, out);
+	CHECK(out.empty()); // nothing released yet, so nothing to count from
+	buffer.Pop(t + 30ms, 30ms, out);
+	CHECK(out == Datagrams{ Datagram(10), Datagram(11) });
+
+	out.clear();
+	CHECK_FALSE(buffer.Push(11, Datagram(11), t + 31ms)); // a duplicate
+	CHECK_FALSE(buffer.Push(9, Datagram(9), t + 31ms)); // too late
+	CHECK(buffer.Push(12, Datagram(12), t + 31ms));
+	buffer.Pop(t + 31ms, 30ms, out);
+	CHECK(out == Datagrams{ Datagram(12) }); // next in line: no wait
+
+	out.clear();
+	CHECK(buffer.Push(14, Datagram(14), t + 40ms)); // 13 is missing
+	buffer.Pop(t + 50ms, 30ms, out);
+	CHECK(out.empty());
+	CHECK(buffer.Push(13, Datagram(13), t + 55ms));
+	buffer.Pop(t + 55ms, 30ms, out);
+	CHECK(out == Datagrams{ Datagram(13), Datagram(14) });
+
+	out.clear();
+	CHECK(buffer.Push(17, Datagram(17), t + 60ms)); // 15 and 16 never come
+	buffer.Pop(t + 90ms, 30ms, out);
+	CHECK(out == Datagrams{ Datagram(17) });
+	CHECK_FALSE(buffer.Push(16, Datagram(16), t + 91ms));
+	CHECK(buffer.Empty());
+}
+
+TEST_CASE("the jitter buffer counts across the wrap")
+{
+	JitterBuffer buffer;
+	Datagrams out;
+	auto t = JitterBuffer::Clock::now();
+	CHECK(SequenceBefore(0xffffffff, 0));
+	buffer.Push(0, Datagram(0), t);
+	buffer.Push(0xffffffff, Datagram(255), t);
+	buffer.Pop(t, std::chrono::milliseconds{ 0 }, out);
+	CHECK(out == Datagrams{ Datagram(255), Datagram(0) });
+}
+
+TEST_CASE("one parity frame rebuilds any one lost frame of its run")
+{
+	std::vector<std::vector<std::byte>> sent = { Datagram(1), { std::byte{ 2 }, std::byte{ 20 }, std::byte{ 200 } }, {}, Datagram(4) };
+	ParityBuilder builder;
+	CHECK_FALSE(builder.Add(0, sent[0])); // off until a peer asks
+	builder.SetGroup(4);
+
+	std::optional<Proto::P2PParity> parity;
+	for (uint32_t i = 0; i < sent.size(); i++) {
+		parity = builder.Add(100 + i, sent[i]);
+		CHECK(parity.has_value() == (i == 3));
+	}
+	REQUIRE(parity);
+	CHECK(parity->first == 100);
+	CHECK(parity->count == 4);
+
+	for (uint32_t lost = 0; lost < sent.size(); lost++) {
+		ParityRecovery recovery;
+		for (uint32_t i = 0; i < sent.size(); i++) {
+			if (i != lost)
+				recovery.Keep(100 + i, sent[i]);
+		}
+		auto rebuilt = recovery.Rebuild(*parity);
+		REQUIRE(rebuilt);
+		CHECK(rebuilt->first == 100 + lost);
+		CHECK(rebuilt->second == sent[lost]);
+	}
+
+	ParityRecovery two_lost;
+	two_lost.Keep(100, sent[0]);
+	two_lost.Keep(101, sent[1]);
+	CHECK_FALSE(two_lost.Rebuild(*parity));
+	two_lost.Keep(102, sent[2]);
+	two_lost.Keep(103, sent[3]);
+	CHECK_FALSE(two_lost.Rebuild(*parity)); // none lost
+}
+
+TEST_CASE("loss is read off sequence gaps, one reading per window")
+{
+	LossMeter meter;
+	std::optional<uint32_t> reading;
+	uint32_t seq = 0;
+	for (uint32_t i = 0; i < fec_loss_window; i++, seq++) {
+		if (i % 10 == 0)
+			seq++; // every tenth lost
+		reading = meter.Add(seq);
+		CHECK(reading.has_value() == (i + 1 == fec_loss_window));
+	}
+	REQUIRE(reading);
+	CHECK(*reading >= fec_on_permille);
+
+	for (uint32_t i = 0; i < fec_loss_window; i++)
+		reading = meter.Add(seq++);
+	CHECK(reading == 0u);
+}
+
+TEST_CASE("selected candidates parse into a link path")
+{
+	auto host = ParseIceCandidate("a=candidate:1 1 UDP 2122317823 10.8.0.2 50000 typ host");
+	REQUIRE(host);
+	CHECK(host->address == "10.8.0.2");
+	CHECK(host->port == 50000);
+	CHECK(host->type == CandidateType::Host);
+
+	auto relay = ParseIceCandidate("candidate:3 1 UDP 16777215 2001:db8::7 3478 typ relay raddr 0.0.0.0 rport 0");
+	REQUIRE(relay);
+	CHECK(relay->address == "2001:db8::7");
+	CHECK(relay->type == CandidateType::Relayed);
+	CHECK(ParseIceCandidate("a=candidate:2 1 UDP 1686052607 203.0.113.7 40000 typ srflx raddr 10.8.0.2 rport 50000")->type == CandidateType::ServerReflexive);
+
+	CHECK_FALSE(ParseIceCandidate("a=end-of-candidates"));
+	CHECK_FALSE(ParseIceCandidate("a=candidate:1 1 UDP 2122317823 10.8.0.2 50000 typ carrier-pigeon"));
+	CHECK_FALSE(ParseIceCandidate("a=candidate:1 1 UDP 2122317823 10.8.0.2 70000 typ host"));
+
+	LinkPath path{ *host, *relay, "tun0" };
+	WeyveLinkPath out{};
+	Marshal::FillLinkPath(path, &out);
+	CHECK(std::string(out.local_address, out.local_address_len) == "10.8.0.2");
+	CHECK(out.local_type == WEYVE_CANDIDATE_HOST);
+	CHECK(out.remote_port == 3478);
+	CHECK(out.remote_type == WEYVE_CANDIDATE_RELAYED);
+	CHECK(std::string(out.interface_name, out.interface_name_len) == "tun0");
+}
+
+TEST_CASE("port mapping events carry the outcome")
+{
+	WeyveEvent e{};
+	PortMapping mapped{ true, PortMapping::Method::NatPmp, 7777, 40000, "203.0.113.7" };
+	Marshal::FillPortMapping(mapped, &e);
+	CHECK(e.type == WEYVE_EVENT_PORT_MAPPING);
+	CHECK(e.data.port_mapping.mapped);
+	CHECK(e.data.port_mapping.method == WEYVE_PORT_MAPPING_NAT_PMP);
+	CHECK(e.data.port_mapping.external_port == 40000);
+	CHECK(std::string(e.data.port_mapping.external_address, e.data.port_mapping.external_address_len) == "203.0.113.7");
+	uint32_t id = 0;
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_CLIENT);
+
+	PortMapping failed{ false, PortMapping::Method::None, 7777 };
+	failed.error = "UPnP: no gateway answered";
+	Marshal::FillPortMapping(failed, &e);
+	CHECK_FALSE(e.data.port_mapping.mapped);
+	CHECK(std::string(e.data.port_mapping.error, e.data.port_mapping.error_len) == failed.error);
+}
+
+TEST_CASE("game server output splits into lines, long ones cut")
+{
+	std::string pending;
+	std::vector<std::string> lines;
+	SplitConsoleLines(pending, "map de_dust\r\nplayers: 2\nsta", lines);
+	CHECK(lines == std::vector<std::string>{ "map de_dust", "players: 2" });
+	CHECK(pending == "sta");
+
+	lines.clear();
+	SplitConsoleLines(pending, "rted\n\n", lines);
+	CHECK(lines == std::vector<std::string>{ "started", "" });
+	CHECK(pending.empty());
+
+	lines.clear();
+	SplitConsoleLines(pending, std::string(max_console_line + 10, 'x'), lines);
+	REQUIRE(lines.size() == 1);
+	CHECK(lines[0].size() == max_console_line);
+	CHECK(pending.size() == 10);
+}
+
+TEST_CASE("game server events carry the line, then the exit code")
+{
+	WeyveEvent e{};
+	ProcessEvent line{ ProcessEvent::Kind::Output, "ready" };
+	Marshal::FillGameServerEvent(line, &e);
+	CHECK(e.type == WEYVE_EVENT_GAME_SERVER_OUTPUT);
+	CHECK(std::string(e.data.game_output.line, e.data.game_output.line_len) == "ready");
+	uint32_t id = 0;
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_CLIENT);
+
+	Marshal::FillGameServerEvent({ ProcessEvent::Kind::Exited, {}, 137 }, &e);
+	CHECK(e.type == WEYVE_EVENT_GAME_SERVER_EXITED);
+	CHECK(e.data.game_exited.exit_code == 137);
+}
+
+TEST_CASE("team changes carry the count, and teams read back borrowed")
+{
+	WeyveEvent e{};
+	Proto::TeamsChanged changed{ { { 1, "Red", 2, { 3, 4 } }, { 2, "Blue", 0, {} } } };
+	REQUIRE(Marshal::FillEvent(Proto::ServerMessage{ changed }, &e));
+	CHECK(e.type == WEYVE_EVENT_TEAMS_CHANGED);
+	CHECK(e.data.teams.count == 2);
+
+	uint32_t id = 7;
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_ROOM);
+	CHECK(id == 0);
+
+	WeyveTeam team{};
+	Marshal::FillTeam(changed.teams[0], &team);
+	CHECK(team.id == 1);
+	CHECK(std::string(team.name, team.name_len) == "Red");
+	CHECK(team.max_size == 2);
+	REQUIRE(team.member_count == 2);
+	CHECK(team.members[0] == 3);
+	CHECK(team.members[1] == 4);
+}
+
+TEST_CASE("channel changes carry the count, and channel chat its sender")
+{
+	WeyveEvent e{};
+	Proto::ChannelsChanged changed{ { { 1, "Red talk", { 3, 4 } } } };
+	REQUIRE(Marshal::FillEvent(Proto::ServerMessage{ changed }, &e));
+	CHECK(e.type == WEYVE_EVENT_CHANNELS_CHANGED);
+	CHECK(e.data.channels.count == 1);
+
+	WeyveChannel channel{};
+	Marshal::FillChannel(changed.channels[0], &channel);
+	CHECK(channel.id == 1);
+	CHECK(std::string(channel.name, channel.name_len) == "Red talk");
+	REQUIRE(channel.member_count == 2);
+	CHECK(channel.members[1] == 4);
+
+	REQUIRE(Marshal::FillEvent(Proto::ChannelChat{ 3, "push mid" }, &e));
+	CHECK(e.type == WEYVE_EVENT_CHANNEL_CHAT);
+	CHECK(e.data.channel_chat.from == 3);
+	CHECK(std::string(e.data.channel_chat.text, e.data.channel_chat.text_len) == "push mid");
+
+	uint32_t id = 0;
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_ROOM);
+}
+
+TEST_CASE("request timeouts carry the mapped request kind")
+{
+	WeyveEvent e{};
+	Marshal::FillTimeout(RequestKind::Account, &e);
+	CHECK(e.type == WEYVE_EVENT_REQUEST_TIMED_OUT);
+	CHECK(e.data.request_timeout.request == WEYVE_REQUEST_ACCOUNT);
+}
+
+TEST_CASE("join checks read the seat, the lock and the password off a preview")
+{
+	Proto::RoomPreview preview{ .id = "ROOMCODE", .host = 1, .passworded = true, .players = 4, .spectators = 1, .max_players = 4 };
+	JoinCheck check = CheckJoin(preview, false, true);
+	CHECK(check.id == "ROOMCODE");
+	CHECK(check.blocker == Proto::RoomErrorCode::RoomFull);
+	CHECK(check.password);
+	CHECK(check.relay);
+
+	check = CheckJoin(preview, true, false); // spectator seats are unlimited here
+	CHECK_FALSE(check.blocker);
+	CHECK(check.spectating);
+
+	preview.open = false;
+	CHECK(CheckJoin(preview, true, false).blocker == Proto::RoomErrorCode::RoomClosed);
+
+	WeyveEvent e{};
+	Marshal::FillJoinCheck(CheckJoin(preview, false, false), &e);
+	CHECK(e.type == WEYVE_EVENT_JOIN_CHECKED);
+	CHECK(std::string(e.data.join_check.id, e.data.join_check.id_len) == "ROOMCODE");
+	CHECK_FALSE(e.data.join_check.ready);
+	CHECK(e.data.join_check.blocker == WEYVE_ROOM_ERROR_ROOM_CLOSED);
+	CHECK(e.data.join_check.password);
+	CHECK_FALSE(e.data.join_check.relay);
+
+	Marshal::FillJoinCheck({ .id = "OPENROOM" }, &e);
+	CHECK(e.data.join_check.ready);
+}
+
+TEST_CASE("held sends surface with their id, target and text")
+{
+	UnsentMessage held{ 7, 3, "see you there" };
+	WeyveEvent e{};
+	Marshal::FillSendFailed(held, &e);
+	CHECK(e.type == WEYVE_EVENT_MESSAGE_SEND_FAILED);
+	CHECK(e.data.send_failed.id == 7);
+	CHECK(e.data.send_failed.to == 3);
+	CHECK(std::string(e.data.send_failed.text, e.data.send_failed.text_len) == "see you there");
+}
+
+TEST_CASE("peer link events carry the attempt and the backoff")
+{
+	WeyveEvent e{};
+	Marshal::FillPeerEvent({ PeerEvent::Kind::Retrying, 6, 2, 2300 }, &e);
+	CHECK(e.type == WEYVE_EVENT_PEER_RETRYING);
+	CHECK(e.data.peer_link.id == 6);
+	CHECK(e.data.peer_link.attempt == 2);
+	CHECK(e.data.peer_link.retry_in == 2300);
+
+	Marshal::FillPeerEvent({ PeerEvent::Kind::Unreachable, 6, 3, 0 }, &e);
+	CHECK(e.type == WEYVE_EVENT_PEER_UNREACHABLE);
+	CHECK(e.data.peer_link.attempt == 3);
+	CHECK(e.data.peer_link.retry_in == 0);
+
+	Marshal::FillPeerEvent({ .kind = PeerEvent::Kind::Closed, .id = 6, .remote = true }, &e);
+	CHECK(e.type == WEYVE_EVENT_PEER_CLOSED);
+	CHECK(e.data.peer_link.id == 6);
+	CHECK(e.data.peer_link.remote);
+}
+
+TEST_CASE("events are scoped to the client, the room or one member")
+{
+	WeyveEvent e{};
+	uint32_t id = 99;
+
+	REQUIRE(Marshal::FillEvent(Proto::ServerMessage{ Proto::Heartbeat{ 1 } }, &e));
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_CLIENT);
+	CHECK(id == 0);
+
+	REQUIRE(Marshal::FillEvent(Proto::ServerMessage{ Proto::RoomChat{ 3, "hi" } }, &e));
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_ROOM);
+
+	REQUIRE(Marshal::FillEvent(Proto::ServerMessage{ Proto::RoomWhisper{ 3, "psst" } }, &e));
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_MEMBER);
+	CHECK(id == 3);
+
+	UnsentMessage chat{ 1, 0, "later" }, whisper{ 2, 4, "later" };
+	Marshal::FillSendFailed(chat, &e);
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_ROOM);
+	Marshal::FillSendFailed(whisper, &e);
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_MEMBER);
+	CHECK(id == 4);
+
+	Marshal::FillPeerEvent({ .kind = PeerEvent::Kind::Closed, .id = 5 }, &e);
+	CHECK(Marshal::EventScope(e, id) == WEYVE_SCOPE_MEMBER);
+	CHECK(id == 5);
+}
+
+TEST_CASE("failure events read as one error shape, others are not errors")
+{
+	WeyveEvent e{};
+	WeyveError error{};
+
+	REQUIRE(Marshal::FillEvent(Proto::ServerMessage{ Proto::RoomError{ Proto::RoomErrorCode::RoomFull, "ABCD" } }, &e));
+	REQUIRE(Marshal::FillError(e, &error));
+	CHECK(error.domain == WEYVE_ERROR_ROOM);
+	CHECK(error.code == WEYVE_ROOM_ERROR_ROOM_FULL);
+	CHECK(error.retryable);
+	CHECK(std::string(error.message) == "The room is full.");
+	CHECK(std::string(error.context, error.context_len) == "ABCD");
+	CHECK(std::string(error.key) == "room.full");
+	CHECK(std::string(error.param) == "seat");
+
+	REQUIRE(Marshal::FillEvent(Proto::ServerMessage{ Proto::RoomError{ Proto::RoomErrorCode::NotHost, "" } }, &e));
+	REQUIRE(Marshal::FillError(e, &error));
+	CHECK_FALSE(error.retryable);
+
+	REQUIRE(Marshal::FillEvent(Proto::ServerMessage{ Proto::ServerFull{ Proto::ServerLimit::Rooms, 30 } }, &e));
+	REQUIRE(Marshal::FillError(e, &error));
+	CHECK(error.domain == WEYVE_ERROR_SERVER_FULL);
+	CHECK(error.code == WEYVE_LIMIT_ROOMS);
+	CHECK(error.retryable);
+	CHECK(error.retry_after == 30);
+	CHECK(error.context_len == 0);
+	CHECK(error.param == nullptr);
+	CHECK(std::string(error.key) == "server.full.rooms");
+
+	REQUIRE(Marshal::FillEvent(Proto::ServerMessage{ Proto::AccountError{ Proto::AccountErrorCode::TooSoon, "42" } }, &e));
+	REQUIRE(Marshal::FillError(e, &error));
+	CHECK(error.domain == WEYVE_ERROR_ACCOUNT);
+	CHECK(error.retryable);
+	CHECK(error.retry_after == 42);
+	CHECK(std::string(error.param) == "seconds");
+
+	Marshal::FillTimeout(RequestKind::Room, &e);
+	REQUIRE(Marshal::FillError(e, &error));
+	CHECK(error.domain == WEYVE_ERROR_TIMEOUT);
+	CHECK(error.code == WEYVE_REQUEST_ROOM);
+
+	WeyveError untouched{ .code = 42 };
+	REQUIRE(Marshal::FillEvent(Proto::ServerMessage{ Proto::RoomChat{ 3, "hi" } }, &e));
+	CHECK_FALSE(Marshal::FillError(e, &untouched));
+	CHECK(untouched.code == 42);
+}
+
+TEST_CASE("error lines use a translation when given and fill in the parameter")
+{
+	WeyveEvent e{};
+	WeyveError error{};
+	REQUIRE(Marshal::FillEvent(Proto::ServerMessage{ Proto::RoomError{ Proto::RoomErrorCode::ChatTooLong, "2000" } }, &e));
+	REQUIRE(Marshal::FillError(e, &error));
+	CHECK(std::string(error.key) == "room.chat_too_long");
+
+	CHECK(Marshal::FormatError(error, nullptr) == "That message is too long.");
+	CHECK(Marshal::FormatError(error, "Max {max_chars}, not {other}; {max_chars}!") == "Max 2000, not {other}; 2000!");
+
+	REQUIRE(Marshal::FillEvent(Proto::ServerMessage{ Proto::RoomError{ Proto::RoomErrorCode::NotInRoom, "" } }, &e));
+	REQUIRE(Marshal::FillError(e, &error));
+	CHECK(Marshal::FormatError(error, "{member} is not here") == "{member} is not here");
+}
+
 TEST_CASE("client->server and transport variants are not surfaced")
 {
 	CHECK(!Surfaced(Proto::AssignClientId{ 5 }));
 	CHECK(!Surfaced(Proto::CreateRoom{}));
 	CHECK(!Surfaced(Proto::JoinRoom{ "ROOMCODE" }));
 	CHECK(!Surfaced(Proto::SetRoomData{ "k", "v" }));
+	CHECK(!Surfaced(Proto::SetMemberRole{ 4, Proto::MemberRole::CoHost }));
+	CHECK(!Surfaced(Proto::SetPreferences{}));
+	CHECK(!Surfaced(Proto::SpectateRoom{ "ROOMCODE" }));
+	CHECK(!Surfaced(Proto::SetRoomCapacity{ 8, 2 }));
+	CHECK(!Surfaced(Proto::ReportActivity{}));
+	CHECK(!Surfaced(Proto::SetBackground{ true }));
+	CHECK(!Surfaced(Proto::GetServerInfo{}));
+	CHECK(!Surfaced(Proto::Idempotent{ 1 }));
+	CHECK(!Surfaced(Proto::KeyScope{ 1 }));
+	CHECK(!Surfaced(Proto::GetRoomPreview{ "ROOMCODE" }));
+	CHECK(!Surfaced(Proto::StopRoomPreview{}));
+	CHECK(!Surfaced(Proto::RequestJoin{ "ROOMCODE" }));
+	CHECK(!Surfaced(Proto::RespondJoinRequest{ 9, true }));
+	CHECK(!Surfaced(Proto::QueueForRoom{ "ROOMCODE" }));
+	CHECK(!Surfaced(Proto::LeaveRoomQueue{}));
+	CHECK(!Surfaced(Proto::SetSearchable{ false }));
+	CHECK(!Surfaced(Proto::SearchUsers{ "ali" }));
+	CHECK(!Surfaced(Proto::ExportMyData{}));
+	CHECK(!Surfaced(Proto::GetTimeline{ 3 }));
+	CHECK(!Surfaced(Proto::SetMemberDataFor{ 3, "team", "red" }));
+	CHECK(!Surfaced(Proto::CreateTeam{ "Red", 2 }));
+	CHECK(!Surfaced(Proto::RemoveTeam{ 1 }));
+	CHECK(!Surfaced(Proto::AssignTeam{ 3, 1 }));
+	CHECK(!Surfaced(Proto::BalanceTeams{}));
+	CHECK(!Surfaced(Proto::SetIdlePolicy{ 300, true }));
+	CHECK(!Surfaced(Proto::ScheduleRoom{ 1700000000, "friday league" }));
+	CHECK(!Surfaced(Proto::ListScheduledRooms{}));
+	CHECK(!Surfaced(Proto::RsvpScheduledRoom{ 4, Proto::RsvpStatus::Going }));
+	CHECK(!Surfaced(Proto::ListAttendees{ 4 }));
+	CHECK(!Surfaced(Proto::CancelScheduledRoom{ 4 }));
+	CHECK(!Surfaced(Proto::CreateAccount{ "Alice", "correct horse" }));
+	CHECK(!Surfaced(Proto::Login{ "Alice", "correct horse" }));
+	CHECK(!Surfaced(Proto::GuestLogin{ "Alice" }));
+	CHECK(!Surfaced(Proto::UploadBlob{ 1, 100 }));
+	CHECK(!Surfaced(Proto::BlobChunk{ 1, {} }));
+	CHECK(!Surfaced(Proto::DownloadBlob{ 1, "id" }));
+	CHECK(!Surfaced(Proto::SetRoomBanner{ "id" }));
+	CHECK(!Surfaced(Proto::SetRoomRules{ "{}" }));
+	CHECK(!Surfaced(Proto::StartCountdown{ 3 }));
+	CHECK(!Surfaced(Proto::SetMatchPaused{ true }));
+	CHECK(!Surfaced(Proto::ReportHealth{ 20, {}, false }));
+	CHECK(!Surfaced(Proto::GetSessionHealth{}));
+	CHECK(!Surfaced(Proto::GetJoinReceipts{}));
+	CHECK(!Surfaced(Proto::SetHostState{ "state" }));
+	CHECK(!Surfaced(Proto::ReportHostState{ "state" }));
+	CHECK(!Surfaced(Proto::SetSuccession{ Proto::SuccessionPolicy::LowestLatency, {} }));
+	CHECK(!Surfaced(Proto::SplitRoom{ { 3, 4 } }));
+	CHECK(!Surfaced(Proto::MergeRoom{ "ROOMCODE" }));
+	CHECK(!Surfaced(Proto::AnswerMerge{ "ROOMCODE", true }));
+	CHECK(!Surfaced(Proto::CreateChannel{ "Red talk" }));
+	CHECK(!Surfaced(Proto::RemoveChannel{ 1 }));
+	CHECK(!Surfaced(Proto::PlaceInChannel{ 3, 1 }));
+	CHECK(!Surfaced(Proto::ClientInfo{ "1.4.2", "windows", 1 }));
 	CHECK(!Surfaced(Proto::P2PSignal{ 1, Proto::P2PSignalKind::Candidate, "x" }));
+	CHECK(!Surfaced(Proto::SequencedSignal{ 9, { 1, Proto::P2PSignalKind::Candidate, "x" } }));
 	CHECK(!Surfaced(Proto::IceServers{ "stun", 3478 }));
+	CHECK(!Surfaced(Proto::SignalEndpoint{ 5556, 0x1234 }));
+	CHECK(!Surfaced(Proto::AttachSignalLink{ 7, 0x1234 }));
+}
+
+TEST_CASE("chat exports filter by room and escape for each format")
+{
+	std::deque<ChatLine> lines{
+		{ 1769882645, "ROOMCODE", 3, "Alice", 0, {}, "gg, \"well\" played\nrematch?" },
+		{ 1769882646, "ROOMCODE", 4, {}, 3, "Alice", "psst" },
+		{ 1769882647, "OTHERROOM", 5, "Bob", 0, {}, "elsewhere" },
+	};
+
+	CHECK(ChatExport::Timestamp(1769882645) == "2026-01-31T18:04:05Z");
+
+	std::string text = ChatExport::Format(lines, "ROOMCODE", ChatExportFormat::Text);
+	CHECK(text == "2026-01-31T18:04:05Z [ROOMCODE] Alice: gg, \"well\" played\n    rematch?\n"
+		"2026-01-31T18:04:06Z [ROOMCODE] client 4 -> Alice: psst\n");
+
+	std::string json = ChatExport::Format(lines, "OTHERROOM", ChatExportFormat::Json);
+	CHECK(json == "[\n  {\"time\":\"2026-01-31T18:04:07Z\",\"room\":\"OTHERROOM\",\"from\":5,\"name\":\"Bob\",\"to\":0,\"to_name\":\"\",\"text\":\"elsewhere\"}\n]\n");
+	CHECK(ChatExport::Json("a\"b\\c\nd\x01") == "\"a\\\"b\\\\c\\nd\\u0001\"");
+	CHECK(ChatExport::Format(lines, "NOSUCHROOM", ChatExportFormat::Json) == "[]\n");
+
+	std::string csv = ChatExport::Format(lines, {}, ChatExportFormat::Csv);
+	CHECK(csv.starts_with("time,room,from,name,to,to_name,text\r\n"));
+	CHECK(csv.find("\"gg, \"\"well\"\" played\nrematch?\"\r\n") != std::string::npos);
+	CHECK(csv.find("OTHERROOM") != std::string::npos);
+}
+
+TEST_CASE("chat search wants every word, ignores case and goes newest first")
+{
+	std::deque<ChatLine> lines{
+		{ 1, "ROOMCODE", 3, "Alice", 0, {}, "Good game everyone" },
+		{ 2, "ROOMCODE", 4, "Bob", 0, {}, "rematch?" },
+		{ 3, "OTHERROOM", 5, "Cid", 0, {}, "what a GAME, good stuff" },
+		{ 4, "ROOMCODE", 3, "Alice", 0, {}, "game on" },
+	};
+
+	CHECK(SearchChat(lines, "good  game", {}, 10) == std::vector<size_t>{ 2, 0 });
+	CHECK(SearchChat(lines, "game", "ROOMCODE", 10) == std::vector<size_t>{ 3, 0 });
+	CHECK(SearchChat(lines, "game", {}, 1) == std::vector<size_t>{ 3 });
+	CHECK(SearchChat(lines, "  ", {}, 10).empty());
+	CHECK(SearchChat(lines, "checkmate", {}, 10).empty());
+
+	WeyveChatLine out{};
+	Marshal::FillChatLine(lines[1], &out);
+	CHECK(out.time == 2);
+	CHECK(std::string(out.room, out.room_len) == "ROOMCODE");
+	CHECK(out.from == 4);
+	CHECK(std::string(out.name, out.name_len) == "Bob");
+	CHECK(out.to == 0);
+	CHECK(out.to_name_len == 0);
+	CHECK(std::string(out.text, out.text_len) == "rematch?");
 }
 
 TEST_CASE("Str turns a C string into std::string, null into empty")
@@ -1,6 +1,7 @@
 #define DOCTEST_CONFIG_IMPLEMENT_WITH_MAIN
 #include <thirdparty/doctest/doctest.h>
 
+#include <algorithm>
 #include <array>
 #include <cstddef>
 #include <cstdint>
@@ -12,7 +13,9 @@
 
 #include <thirdparty/zpp_bits/zpp_bits.h>
 
+#include "chat_text.h"
 #include "framing.h"
+#include "p2p_framing.h"
 #include "protocol.h"
 
 using namespace Weyvelength;
@@ -20,7 +23,7 @@ using namespace Weyvelength;
 // The wire encodes a message as its variant index, so the order below IS the
 // protocol. These pins turn the append-only comment in protocol.h into a
 // compile error: inserting or reordering an alternative fails right here.
-static_assert(std::variant_size_v<Proto::ServerMessage> == 25);
+static_assert(std::variant_size_v<Proto::ServerMessage> == 133);
 static_assert(std::is_same_v<std::variant_alternative_t<0, Proto::ServerMessage>, Proto::Heartbeat>);
 static_assert(std::is_same_v<std::variant_alternative_t<1, Proto::ServerMessage>, Proto::AssignClientId>);
 static_assert(std::is_same_v<std::variant_alternative_t<2, Proto::ServerMessage>, Proto::AssignRoomId>);
@@ -46,6 +49,106 @@ static_assert(std::is_same_v<std::variant_alternative_t<21, Proto::ServerMessage
 static_assert(std::is_same_v<std::variant_alternative_t<22, Proto::ServerMessage>, Proto::BannedByHost>);
 static_assert(std::is_same_v<std::variant_alternative_t<23, Proto::ServerMessage>, Proto::P2PSignal>);
 static_assert(std::is_same_v<std::variant_alternative_t<24, Proto::ServerMessage>, Proto::IceServers>);
+static_assert(std::is_same_v<std::variant_alternative_t<25, Proto::ServerMessage>, Proto::SetMemberRole>);
+static_assert(std::is_same_v<std::variant_alternative_t<26, Proto::ServerMessage>, Proto::MemberRoleChanged>);
+static_assert(std::is_same_v<std::variant_alternative_t<27, Proto::ServerMessage>, Proto::RoomWhisper>);
+static_assert(std::is_same_v<std::variant_alternative_t<28, Proto::ServerMessage>, Proto::SetPreferences>);
+static_assert(std::is_same_v<std::variant_alternative_t<29, Proto::ServerMessage>, Proto::PreferencesChanged>);
+static_assert(std::is_same_v<std::variant_alternative_t<30, Proto::ServerMessage>, Proto::SpectateRoom>);
+static_assert(std::is_same_v<std::variant_alternative_t<31, Proto::ServerMessage>, Proto::MemberSpectating>);
+static_assert(std::is_same_v<std::variant_alternative_t<32, Proto::ServerMessage>, Proto::SetRoomCapacity>);
+static_assert(std::is_same_v<std::variant_alternative_t<33, Proto::ServerMessage>, Proto::RoomCapacityChanged>);
+static_assert(std::is_same_v<std::variant_alternative_t<34, Proto::ServerMessage>, Proto::ReportActivity>);
+static_assert(std::is_same_v<std::variant_alternative_t<35, Proto::ServerMessage>, Proto::SetIdlePolicy>);
+static_assert(std::is_same_v<std::variant_alternative_t<36, Proto::ServerMessage>, Proto::IdlePolicyChanged>);
+static_assert(std::is_same_v<std::variant_alternative_t<37, Proto::ServerMessage>, Proto::MemberAfk>);
+static_assert(std::is_same_v<std::variant_alternative_t<38, Proto::ServerMessage>, Proto::ScheduleRoom>);
+static_assert(std::is_same_v<std::variant_alternative_t<39, Proto::ServerMessage>, Proto::ScheduledRoomChanged>);
+static_assert(std::is_same_v<std::variant_alternative_t<40, Proto::ServerMessage>, Proto::ListScheduledRooms>);
+static_assert(std::is_same_v<std::variant_alternative_t<41, Proto::ServerMessage>, Proto::ScheduledRoomList>);
+static_assert(std::is_same_v<std::variant_alternative_t<42, Proto::ServerMessage>, Proto::RsvpScheduledRoom>);
+static_assert(std::is_same_v<std::variant_alternative_t<43, Proto::ServerMessage>, Proto::ScheduledRoomReminder>);
+static_assert(std::is_same_v<std::variant_alternative_t<44, Proto::ServerMessage>, Proto::ScheduledRoomOpened>);
+static_assert(std::is_same_v<std::variant_alternative_t<45, Proto::ServerMessage>, Proto::ListAttendees>);
+static_assert(std::is_same_v<std::variant_alternative_t<46, Proto::ServerMessage>, Proto::ScheduledRoomAttendees>);
+static_assert(std::is_same_v<std::variant_alternative_t<47, Proto::ServerMessage>, Proto::CreateAccount>);
+static_assert(std::is_same_v<std::variant_alternative_t<48, Proto::ServerMessage>, Proto::Login>);
+static_assert(std::is_same_v<std::variant_alternative_t<49, Proto::ServerMessage>, Proto::LoggedIn>);
+static_assert(std::is_same_v<std::variant_alternative_t<50, Proto::ServerMessage>, Proto::AccountError>);
+static_assert(std::is_same_v<std::variant_alternative_t<51, Proto::ServerMessage>, Proto::MemberNameChanged>);
+static_assert(std::is_same_v<std::variant_alternative_t<52, Proto::ServerMessage>, Proto::GuestLogin>);
+static_assert(std::is_same_v<std::variant_alternative_t<53, Proto::ServerMessage>, Proto::ChatLinkPreview>);
+static_assert(std::is_same_v<std::variant_alternative_t<54, Proto::ServerMessage>, Proto::UploadBlob>);
+static_assert(std::is_same_v<std::variant_alternative_t<55, Proto::ServerMessage>, Proto::BlobChunk>);
+static_assert(std::is_same_v<std::variant_alternative_t<56, Proto::ServerMessage>, Proto::BlobUploaded>);
+static_assert(std::is_same_v<std::variant_alternative_t<57, Proto::ServerMessage>, Proto::DownloadBlob>);
+static_assert(std::is_same_v<std::variant_alternative_t<58, Proto::ServerMessage>, Proto::BlobDownloaded>);
+static_assert(std::is_same_v<std::variant_alternative_t<59, Proto::ServerMessage>, Proto::BlobError>);
+static_assert(std::is_same_v<std::variant_alternative_t<60, Proto::ServerMessage>, Proto::SetRoomBanner>);
+static_assert(std::is_same_v<std::variant_alternative_t<61, Proto::ServerMessage>, Proto::RoomBannerChanged>);
+static_assert(std::is_same_v<std::variant_alternative_t<62, Proto::ServerMessage>, Proto::SetBackground>);
+static_assert(std::is_same_v<std::variant_alternative_t<63, Proto::ServerMessage>, Proto::ServerFull>);
+static_assert(std::is_same_v<std::variant_alternative_t<64, Proto::ServerMessage>, Proto::GetServerInfo>);
+static_assert(std::is_same_v<std::variant_alternative_t<65, Proto::ServerMessage>, Proto::ServerInfo>);
+static_assert(std::is_same_v<std::variant_alternative_t<66, Proto::ServerMessage>, Proto::Idempotent>);
+static_assert(std::is_same_v<std::variant_alternative_t<67, Proto::ServerMessage>, Proto::GetRoomPreview>);
+static_assert(std::is_same_v<std::variant_alternative_t<68, Proto::ServerMessage>, Proto::StopRoomPreview>);
+static_assert(std::is_same_v<std::variant_alternative_t<69, Proto::ServerMessage>, Proto::RoomPreview>);
+static_assert(std::is_same_v<std::variant_alternative_t<70, Proto::ServerMessage>, Proto::RequestJoin>);
+static_assert(std::is_same_v<std::variant_alternative_t<71, Proto::ServerMessage>, Proto::JoinRequested>);
+static_assert(std::is_same_v<std::variant_alternative_t<72, Proto::ServerMessage>, Proto::RespondJoinRequest>);
+static_assert(std::is_same_v<std::variant_alternative_t<73, Proto::ServerMessage>, Proto::JoinRequestAnswered>);
+static_assert(std::is_same_v<std::variant_alternative_t<74, Proto::ServerMessage>, Proto::QueueForRoom>);
+static_assert(std::is_same_v<std::variant_alternative_t<75, Proto::ServerMessage>, Proto::LeaveRoomQueue>);
+static_assert(std::is_same_v<std::variant_alternative_t<76, Proto::ServerMessage>, Proto::RoomQueuePosition>);
+static_assert(std::is_same_v<std::variant_alternative_t<77, Proto::ServerMessage>, Proto::SeatOffered>);
+static_assert(std::is_same_v<std::variant_alternative_t<78, Proto::ServerMessage>, Proto::SetSearchable>);
+static_assert(std::is_same_v<std::variant_alternative_t<79, Proto::ServerMessage>, Proto::SearchableChanged>);
+static_assert(std::is_same_v<std::variant_alternative_t<80, Proto::ServerMessage>, Proto::SearchUsers>);
+static_assert(std::is_same_v<std::variant_alternative_t<81, Proto::ServerMessage>, Proto::UserSearchResults>);
+static_assert(std::is_same_v<std::variant_alternative_t<82, Proto::ServerMessage>, Proto::ExportMyData>);
+static_assert(std::is_same_v<std::variant_alternative_t<83, Proto::ServerMessage>, Proto::MyData>);
+static_assert(std::is_same_v<std::variant_alternative_t<84, Proto::ServerMessage>, Proto::GetTimeline>);
+static_assert(std::is_same_v<std::variant_alternative_t<85, Proto::ServerMessage>, Proto::Timeline>);
+static_assert(std::is_same_v<std::variant_alternative_t<86, Proto::ServerMessage>, Proto::TimelineAdded>);
+static_assert(std::is_same_v<std::variant_alternative_t<87, Proto::ServerMessage>, Proto::SetMemberDataFor>);
+static_assert(std::is_same_v<std::variant_alternative_t<88, Proto::ServerMessage>, Proto::CreateTeam>);
+static_assert(std::is_same_v<std::variant_alternative_t<89, Proto::ServerMessage>, Proto::RemoveTeam>);
+static_assert(std::is_same_v<std::variant_alternative_t<90, Proto::ServerMessage>, Proto::AssignTeam>);
+static_assert(std::is_same_v<std::variant_alternative_t<91, Proto::ServerMessage>, Proto::BalanceTeams>);
+static_assert(std::is_same_v<std::variant_alternative_t<92, Proto::ServerMessage>, Proto::TeamsChanged>);
+static_assert(std::is_same_v<std::variant_alternative_t<93, Proto::ServerMessage>, Proto::SetRoomRules>);
+static_assert(std::is_same_v<std::variant_alternative_t<94, Proto::ServerMessage>, Proto::RoomRulesChanged>);
+static_assert(std::is_same_v<std::variant_alternative_t<95, Proto::ServerMessage>, Proto::StartCountdown>);
+static_assert(std::is_same_v<std::variant_alternative_t<96, Proto::ServerMessage>, Proto::CountdownStarted>);
+static_assert(std::is_same_v<std::variant_alternative_t<97, Proto::ServerMessage>, Proto::SetMatchPaused>);
+static_assert(std::is_same_v<std::variant_alternative_t<98, Proto::ServerMessage>, Proto::MatchPausedChanged>);
+static_assert(std::is_same_v<std::variant_alternative_t<99, Proto::ServerMessage>, Proto::ReportHealth>);
+static_assert(std::is_same_v<std::variant_alternative_t<100, Proto::ServerMessage>, Proto::MemberHealth>);
+static_assert(std::is_same_v<std::variant_alternative_t<101, Proto::ServerMessage>, Proto::GetSessionHealth>);
+static_assert(std::is_same_v<std::variant_alternative_t<102, Proto::ServerMessage>, Proto::SessionHealth>);
+static_assert(std::is_same_v<std::variant_alternative_t<103, Proto::ServerMessage>, Proto::SequencedSignal>);
+static_assert(std::is_same_v<std::variant_alternative_t<104, Proto::ServerMessage>, Proto::ClientInfo>);
+static_assert(std::is_same_v<std::variant_alternative_t<105, Proto::ServerMessage>, Proto::ClientVersionPolicy>);
+static_assert(std::is_same_v<std::variant_alternative_t<106, Proto::ServerMessage>, Proto::ClientOutdated>);
+static_assert(std::is_same_v<std::variant_alternative_t<107, Proto::ServerMessage>, Proto::SignalEndpoint>);
+static_assert(std::is_same_v<std::variant_alternative_t<108, Proto::ServerMessage>, Proto::AttachSignalLink>);
+static_assert(std::is_same_v<std::variant_alternative_t<109, Proto::ServerMessage>, Proto::GetJoinReceipts>);
+static_assert(std::is_same_v<std::variant_alternative_t<110, Proto::ServerMessage>, Proto::JoinReceipts>);
+static_assert(std::is_same_v<std::variant_alternative_t<111, Proto::ServerMessage>, Proto::SetHostState>);
+static_assert(std::is_same_v<std::variant_alternative_t<115, Proto::ServerMessage>, Proto::MemberHostState>);
+static_assert(std::is_same_v<std::variant_alternative_t<116, Proto::ServerMessage>, Proto::SetSuccession>);
+static_assert(std::is_same_v<std::variant_alternative_t<117, Proto::ServerMessage>, Proto::SuccessionChanged>);
+static_assert(std::is_same_v<std::variant_alternative_t<118, Proto::ServerMessage>, Proto::SplitRoom>);
+static_assert(std::is_same_v<std::variant_alternative_t<124, Proto::ServerMessage>, Proto::RoomMoved>);
+static_assert(std::is_same_v<std::variant_alternative_t<125, Proto::ServerMessage>, Proto::CreateChannel>);
+static_assert(std::is_same_v<std::variant_alternative_t<126, Proto::ServerMessage>, Proto::RemoveChannel>);
+static_assert(std::is_same_v<std::variant_alternative_t<127, Proto::ServerMessage>, Proto::PlaceInChannel>);
+static_assert(std::is_same_v<std::variant_alternative_t<128, Proto::ServerMessage>, Proto::ChannelsChanged>);
+static_assert(std::is_same_v<std::variant_alternative_t<129, Proto::ServerMessage>, Proto::ChannelChat>);
+static_assert(std::is_same_v<std::variant_alternative_t<130, Proto::ServerMessage>, Proto::CancelScheduledRoom>);
+static_assert(std::is_same_v<std::variant_alternative_t<131, Proto::ServerMessage>, Proto::ScheduledRoomCancelled>);
+static_assert(std::is_same_v<std::variant_alternative_t<132, Proto::ServerMessage>, Proto::KeyScope>);
 
 // Same idea for the error enum: the values are wire bytes, append only.
 static_assert((uint8_t)Proto::RoomErrorCode::AlreadyInRoom == 0);
@@ -57,11 +160,88 @@ static_assert((uint8_t)Proto::RoomErrorCode::NoSuchMember == 5);
 static_assert((uint8_t)Proto::RoomErrorCode::RoomClosed == 6);
 static_assert((uint8_t)Proto::RoomErrorCode::BadPassword == 7);
 static_assert((uint8_t)Proto::RoomErrorCode::Banned == 8);
+static_assert((uint8_t)Proto::RoomErrorCode::WhisperBlocked == 9);
+static_assert((uint8_t)Proto::RoomErrorCode::RoomFull == 10);
+static_assert((uint8_t)Proto::RoomErrorCode::NoSuchSchedule == 11);
+static_assert((uint8_t)Proto::RoomErrorCode::BadSchedule == 12);
+static_assert((uint8_t)Proto::RoomErrorCode::ChatTooLong == 13);
+static_assert((uint8_t)Proto::RoomErrorCode::BadChat == 14);
+static_assert((uint8_t)Proto::RoomErrorCode::BadBanner == 15);
+static_assert((uint8_t)Proto::RoomErrorCode::NoSuchTeam == 16);
+static_assert((uint8_t)Proto::RoomErrorCode::TeamFull == 17);
+static_assert((uint8_t)Proto::RoomErrorCode::BadTeam == 18);
+static_assert((uint8_t)Proto::RoomErrorCode::BadCountdown == 19);
+static_assert((uint8_t)Proto::RoomErrorCode::NoSuchChannel == 20);
+static_assert((uint8_t)Proto::RoomErrorCode::BadChannel == 21);
+static_assert((uint8_t)Proto::RoomErrorCode::BadRole == 22);
+static_assert((uint8_t)Proto::RoomErrorCode::BadSuccession == 23);
+
+// Member roles are ranked by value, so their order is behavior as well as wire.
+static_assert((uint8_t)Proto::MemberRole::Member == 0);
+static_assert((uint8_t)Proto::MemberRole::Moderator == 1);
+static_assert((uint8_t)Proto::MemberRole::CoHost == 2);
+
+// And the whisper policies.
+static_assert((uint8_t)Proto::WhisperPolicy::Everyone == 0);
+static_assert((uint8_t)Proto::WhisperPolicy::Staff == 1);
+static_assert((uint8_t)Proto::WhisperPolicy::Nobody == 2);
+
+// And the account errors.
+static_assert((uint8_t)Proto::AccountErrorCode::Disabled == 0);
+static_assert((uint8_t)Proto::AccountErrorCode::AlreadyLoggedIn == 1);
+static_assert((uint8_t)Proto::AccountErrorCode::BadName == 2);
+static_assert((uint8_t)Proto::AccountErrorCode::NameTaken == 3);
+static_assert((uint8_t)Proto::AccountErrorCode::BadPassword == 4);
+static_assert((uint8_t)Proto::AccountErrorCode::BadEmail == 5);
+static_assert((uint8_t)Proto::AccountErrorCode::WrongCredentials == 6);
+static_assert((uint8_t)Proto::AccountErrorCode::InUse == 7);
+static_assert((uint8_t)Proto::AccountErrorCode::StoreFailed == 8);
+static_assert((uint8_t)Proto::AccountErrorCode::TooSoon == 9);
+static_assert((uint8_t)Proto::AccountErrorCode::BadPreferences == 10);
+
+// And the blob errors.
+static_assert((uint8_t)Proto::BlobErrorCode::Disabled == 0);
+static_assert((uint8_t)Proto::BlobErrorCode::TooLarge == 1);
+static_assert((uint8_t)Proto::BlobErrorCode::Busy == 2);
+static_assert((uint8_t)Proto::BlobErrorCode::BadTransfer == 3);
+static_assert((uint8_t)Proto::BlobErrorCode::NoSuchBlob == 4);
+static_assert((uint8_t)Proto::BlobErrorCode::StoreFailed == 5);
+static_assert((uint8_t)Proto::BlobErrorCode::Quota == 6);
+
+// And the RSVP answers.
+static_assert((uint8_t)Proto::RsvpStatus::No == 0);
+static_assert((uint8_t)Proto::RsvpStatus::Going == 1);
+static_assert((uint8_t)Proto::RsvpStatus::Maybe == 2);
+
+// And the timeline entry kinds.
+static_assert((uint8_t)Proto::TimelineKind::Joined == 0);
+static_assert((uint8_t)Proto::TimelineKind::Spectating == 1);
+static_assert((uint8_t)Proto::TimelineKind::Left == 2);
+static_assert((uint8_t)Proto::TimelineKind::Kicked == 3);
+static_assert((uint8_t)Proto::TimelineKind::Banned == 4);
+static_assert((uint8_t)Proto::TimelineKind::HostChanged == 5);
+static_assert((uint8_t)Proto::TimelineKind::Opened == 6);
+static_assert((uint8_t)Proto::TimelineKind::Closed == 7);
 
 // And the p2p signal kinds.
 static_assert((uint8_t)Proto::P2PSignalKind::Description == 0);
 static_assert((uint8_t)Proto::P2PSignalKind::Candidate == 1);
 static_assert((uint8_t)Proto::P2PSignalKind::GatheringDone == 2);
+static_assert((uint8_t)Proto::P2PSignalKind::Framing == 3);
+static_assert((uint8_t)Proto::P2PSignalKind::Closed == 4);
+
+// And the p2p frame kinds, the first byte of every framed datagram.
+static_assert((uint8_t)Proto::P2PFrameKind::App == 0);
+static_assert((uint8_t)Proto::P2PFrameKind::Whisper == 1);
+static_assert((uint8_t)Proto::P2PFrameKind::Sequenced == 2);
+static_assert((uint8_t)Proto::P2PFrameKind::Parity == 3);
+static_assert((uint8_t)Proto::P2PFrameKind::FecRequest == 4);
+
+// And the load limits.
+static_assert((uint8_t)Proto::ServerLimit::Users == 0);
+static_assert((uint8_t)Proto::ServerLimit::Rooms == 1);
+static_assert((uint8_t)Proto::ServerLimit::PerAddress == 2);
+static_assert((uint8_t)Proto::ServerLimit::Requests == 3);
 
 namespace {
 	// Frames a message, then walks the fragment stream and reassembles it the
@@ -105,6 +285,46 @@ TEST_CASE("identity and room assignment round trip")
 	CHECK(std::get<Proto::AssignRoomId>(RoundTrip(Proto::AssignRoomId{ "VY4C3NB9" })).id == "VY4C3NB9");
 }
 
+TEST_CASE("load messages round trip")
+{
+	auto full = std::get<Proto::ServerFull>(RoundTrip(Proto::ServerFull{ Proto::ServerLimit::Rooms, 30 }));
+	CHECK(full.limit == Proto::ServerLimit::Rooms);
+	CHECK(full.retry_after == 30);
+
+	RoundTrip(Proto::GetServerInfo{});
+	auto info = std::get<Proto::ServerInfo>(RoundTrip(Proto::ServerInfo{ 12, 100, 3, 0 }));
+	CHECK(info.users == 12);
+	CHECK(info.max_users == 100);
+	CHECK(info.rooms == 3);
+	CHECK(info.max_rooms == 0); // no limit
+}
+
+TEST_CASE("client versions round trip and compare by their numbers")
+{
+	auto hello = std::get<Proto::ClientInfo>(RoundTrip(Proto::ClientInfo{ "1.4.2", "windows", 1 }));
+	CHECK(hello.app_version == "1.4.2");
+	CHECK(hello.platform == "windows");
+	CHECK(hello.library_version == 1);
+	auto outdated = std::get<Proto::ClientOutdated>(RoundTrip(Proto::ClientOutdated{ "1.5", "https://example.com/get" }));
+	CHECK(outdated.min_app_version == "1.5");
+	CHECK(outdated.update_url == "https://example.com/get");
+	CHECK(std::get<Proto::ClientVersionPolicy>(RoundTrip(Proto::ClientVersionPolicy{})).min_app_version.empty());
+
+	CHECK(Proto::OlderVersion("1.9", "1.10"));
+	CHECK(Proto::OlderVersion("1.4.2", "1.5"));
+	CHECK(Proto::OlderVersion("", "0.1"));
+	CHECK(!Proto::OlderVersion("1.2", "1.2.0"));
+	CHECK(!Proto::OlderVersion("1.2.0", "1.2"));
+	CHECK(!Proto::OlderVersion("2.0-beta", "2"));
+	CHECK(!Proto::OlderVersion("1.10", "1.9"));
+}
+
+TEST_CASE("idempotency keys round trip all 64 bits")
+{
+	CHECK(std::get<Proto::Idempotent>(RoundTrip(Proto::Idempotent{ 0xF0E1D2C3B4A59687 })).key == 0xF0E1D2C3B4A59687);
+	CHECK(std::get<Proto::KeyScope>(RoundTrip(Proto::KeyScope{ 0x8796A5B4C3D2E1F0 })).token == 0x8796A5B4C3D2E1F0);
+}
+
 TEST_CASE("room requests round trip")
 {
 	RoundTrip(Proto::CreateRoom{}); // the index check inside is the whole test
@@ -117,6 +337,196 @@ TEST_CASE("room requests round trip")
 	CHECK(std::get<Proto::JoinRoom>(RoundTrip(Proto::JoinRoom{ "ROOMCODE" })).password.empty());
 }
 
+TEST_CASE("room codes match however they are typed")
+{
+	CHECK(Proto::RoomCodeKey("A7K2QX9P") == "A7K2QX9P");
+	CHECK(Proto::RoomCodeKey(" a7k2qx9p\n") == "A7K2QX9P");
+	CHECK(Proto::RoomCodeKey("blue tiger 42") == Proto::RoomCodeKey("BLUE-TIGER-42"));
+	CHECK(Proto::RoomCodeKey("bluetiger42") == Proto::RoomCodeKey("BLUE-TIGER-42"));
+	CHECK(Proto::RoomCodeKey("A7K2QX9P") != Proto::RoomCodeKey("A7K2QX9Q"));
+	CHECK(Proto::RoomCodeKey(" - ").empty());
+}
+
+TEST_CASE("room previews round trip with members and data")
+{
+	auto get = std::get<Proto::GetRoomPreview>(RoundTrip(Proto::GetRoomPreview{ "ROOMCODE", true }));
+	CHECK(get.id == "ROOMCODE");
+	CHECK(get.watch);
+	RoundTrip(Proto::StopRoomPreview{});
+
+	Proto::RoomPreview preview{ "ROOMCODE", 3, false, false, 1, 1, 4, 0, "" };
+	preview.members = { { 3, "Alice", false }, { 7, "", true } };
+	preview.data = { { "mode", "ranked" } };
+	auto out = std::get<Proto::RoomPreview>(RoundTrip(preview));
+	CHECK(out.id == "ROOMCODE");
+	CHECK(out.host == 3);
+	CHECK(out.open == false);
+	CHECK(out.max_players == 4);
+	REQUIRE(out.members.size() == 2);
+	CHECK(out.members[0].name == "Alice");
+	CHECK(out.members[1].spectating);
+	REQUIRE(out.data.size() == 1);
+	CHECK(out.data[0].value == "ranked");
+}
+
+TEST_CASE("a full room preview still fits one reassembled message")
+{
+	Proto::RoomPreview preview{ "ROOMCODE", 1, true, false, 32, 0, 0, 0, std::string(Proto::blob_id_size, 'a') };
+	preview.members.assign(Proto::max_preview_members, { 1, std::string(Proto::max_account_name + 5, 'x'), false }); // a guest's #NNNN suffix included
+	size_t pair = Proto::max_room_data_key + Proto::max_room_data_value;
+	for (size_t used = 0; used + pair <= Proto::max_preview_data; used += pair)
+		preview.data.push_back({ std::string(Proto::max_room_data_key, 'k'), std::string(Proto::max_room_data_value, 'v') });
+
+	std::vector<std::byte> data;
+	REQUIRE(!failure(zpp::bits::out{ data }(Proto::ServerMessage{ preview })));
+	CHECK(data.size() <= Proto::max_reassembled_size);
+}
+
+TEST_CASE("knocks round trip both ways")
+{
+	auto knock = std::get<Proto::RequestJoin>(RoundTrip(Proto::RequestJoin{ "ROOMCODE", "it's Bob from work" }));
+	CHECK(knock.id == "ROOMCODE");
+	CHECK(knock.message == "it's Bob from work");
+
+	auto requested = std::get<Proto::JoinRequested>(RoundTrip(Proto::JoinRequested{ 9, "Bob", "hi" }));
+	CHECK(requested.id == 9);
+	CHECK(requested.name == "Bob");
+	CHECK(requested.message == "hi");
+
+	auto respond = std::get<Proto::RespondJoinRequest>(RoundTrip(Proto::RespondJoinRequest{ 9, true }));
+	CHECK(respond.id == 9);
+	CHECK(respond.approve);
+
+	auto answered = std::get<Proto::JoinRequestAnswered>(RoundTrip(Proto::JoinRequestAnswered{ "ROOMCODE", false }));
+	CHECK(answered.room == "ROOMCODE");
+	CHECK_FALSE(answered.approved);
+}
+
+TEST_CASE("waiting line messages round trip")
+{
+	auto queue = std::get<Proto::QueueForRoom>(RoundTrip(Proto::QueueForRoom{ "ROOMCODE", "hunter2" }));
+	CHECK(queue.id == "ROOMCODE");
+	CHECK(queue.password == "hunter2");
+	RoundTrip(Proto::LeaveRoomQueue{});
+
+	auto position = std::get<Proto::RoomQueuePosition>(RoundTrip(Proto::RoomQueuePosition{ "ROOMCODE", 3 }));
+	CHECK(position.room == "ROOMCODE");
+	CHECK(position.position == 3);
+
+	auto offer = std::get<Proto::SeatOffered>(RoundTrip(Proto::SeatOffered{ "ROOMCODE", Proto::seat_offer_window }));
+	CHECK(offer.room == "ROOMCODE");
+	CHECK(offer.expires_in == Proto::seat_offer_window);
+}
+
+TEST_CASE("user search messages round trip, offline matches included")
+{
+	CHECK_FALSE(std::get<Proto::SetSearchable>(RoundTrip(Proto::SetSearchable{ false })).searchable);
+	CHECK(std::get<Proto::SearchableChanged>(RoundTrip(Proto::SearchableChanged{ true })).searchable);
+	CHECK(std::get<Proto::SearchUsers>(RoundTrip(Proto::SearchUsers{ "ali" })).query == "ali");
+
+	auto results = std::get<Proto::UserSearchResults>(RoundTrip(Proto::UserSearchResults{ "ali", { { "Alice", 7, "ROOMCODE" }, { "Malik" } } }));
+	CHECK(results.query == "ali");
+	REQUIRE(results.users.size() == 2);
+	CHECK(results.users[0].name == "Alice");
+	CHECK(results.users[0].id == 7);
+	CHECK(results.users[0].room == "ROOMCODE");
+	CHECK(results.users[1].name == "Malik");
+	CHECK(results.users[1].id == 0);
+	CHECK(results.users[1].room.empty());
+}
+
+TEST_CASE("data exports round trip with every store's part")
+{
+	RoundTrip(Proto::ExportMyData{});
+
+	Proto::MyData in{ 7, "Alice", false, "alice@example.com", false, { 1700000000, Proto::WhisperPolicy::Staff }, "ROOMCODE", "OTHERROOM" };
+	in.bookings.push_back({ 4, 7, 1700003600, "friday league", 8, 0, true, 2 });
+	in.rsvps.push_back({ 5, Proto::RsvpStatus::Maybe });
+	in.knocks.push_back("KNOCKED1");
+
+	auto out = std::get<Proto::MyData>(RoundTrip(in));
+	CHECK(out.id == 7);
+	CHECK(out.name == "Alice");
+	CHECK(out.email == "alice@example.com");
+	CHECK_FALSE(out.searchable);
+	CHECK(out.prefs.whispers == Proto::WhisperPolicy::Staff);
+	CHECK(out.room == "ROOMCODE");
+	CHECK(out.queued == "OTHERROOM");
+	REQUIRE(out.bookings.size() == 1);
+	CHECK(out.bookings[0].title == "friday league");
+	REQUIRE(out.rsvps.size() == 1);
+	CHECK(out.rsvps[0].booking == 5);
+	CHECK(out.rsvps[0].status == Proto::RsvpStatus::Maybe);
+	CHECK(out.knocks == std::vector<std::string>{ "KNOCKED1" });
+}
+
+TEST_CASE("timeline messages round trip")
+{
+	CHECK(std::get<Proto::GetTimeline>(RoundTrip(Proto::GetTimeline{ 12 })).after == 12);
+
+	auto added = std::get<Proto::TimelineAdded>(RoundTrip(Proto::TimelineAdded{ { 13, 1700000000, Proto::TimelineKind::Kicked, 4, "Bob", 2 } }));
+	CHECK(added.entry.seq == 13);
+	CHECK(added.entry.time == 1700000000);
+	CHECK(added.entry.kind == Proto::TimelineKind::Kicked);
+	CHECK(added.entry.id == 4);
+	CHECK(added.entry.name == "Bob");
+	CHECK(added.entry.by == 2);
+
+	auto log = std::get<Proto::Timeline>(RoundTrip(Proto::Timeline{ { { 1, 0, Proto::TimelineKind::Joined, 2 }, { 2, 0, Proto::TimelineKind::Closed, 0, {}, 2 } } }));
+	REQUIRE(log.entries.size() == 2);
+	CHECK(log.entries[1].kind == Proto::TimelineKind::Closed);
+	CHECK(log.entries[1].by == 2);
+}
+
+TEST_CASE("team messages round trip")
+{
+	auto create = std::get<Proto::CreateTeam>(RoundTrip(Proto::CreateTeam{ "Red", 4 }));
+	CHECK(create.name == "Red");
+	CHECK(create.max_size == 4);
+
+	CHECK(std::get<Proto::RemoveTeam>(RoundTrip(Proto::RemoveTeam{ 2 })).team == 2);
+
+	auto assign = std::get<Proto::AssignTeam>(RoundTrip(Proto::AssignTeam{ 7, 1 }));
+	CHECK(assign.id == 7);
+	CHECK(assign.team == 1);
+
+	CHECK(std::holds_alternative<Proto::BalanceTeams>(RoundTrip(Proto::BalanceTeams{})));
+
+	auto changed = std::get<Proto::TeamsChanged>(RoundTrip(Proto::TeamsChanged{ { { 1, "Red", 2, { 3, 4 } }, { 2, "Blue", 0, {} } } }));
+	REQUIRE(changed.teams.size() == 2);
+	CHECK(changed.teams[0].name == "Red");
+	CHECK(changed.teams[0].members == std::vector<uint32_t>{ 3, 4 });
+	CHECK(changed.teams[1].id == 2);
+	CHECK(changed.teams[1].members.empty());
+}
+
+TEST_CASE("dealing teams evens them out within their limits")
+{
+	std::vector<Proto::Team> teams{ { 1, "Red", 0, { 1, 2, 3 } }, { 2, "Blue", 0, {} } };
+	Proto::DealTeams(teams, { 1, 2, 3, 4, 5 });
+	CHECK(teams[0].members == std::vector<uint32_t>{ 1, 3, 5 });
+	CHECK(teams[1].members == std::vector<uint32_t>{ 2, 4 });
+
+	teams = { { 1, "Red", 1, {} }, { 2, "Blue", 2, {} } };
+	Proto::DealTeams(teams, { 1, 2, 3, 4 });
+	CHECK(teams[0].members == std::vector<uint32_t>{ 1 });
+	CHECK(teams[1].members == std::vector<uint32_t>{ 2, 3 }); // 4 fits nowhere
+
+	std::vector<Proto::Team> none;
+	Proto::DealTeams(none, { 1, 2 });
+	CHECK(none.empty());
+}
+
+TEST_CASE("a full timeline still fits one reassembled message")
+{
+	Proto::Timeline log;
+	log.entries.assign(Proto::max_timeline_entries, { UINT32_MAX, UINT64_MAX, Proto::TimelineKind::HostChanged, UINT32_MAX, std::string(Proto::max_account_name + 5, 'x'), UINT32_MAX });
+
+	std::vector<std::byte> data;
+	REQUIRE(!failure(zpp::bits::out{ data }(Proto::ServerMessage{ log })));
+	CHECK(data.size() <= Proto::max_reassembled_size);
+}
+
 TEST_CASE("room errors round trip with code and context")
 {
 	auto out = std::get<Proto::RoomError>(RoundTrip(Proto::RoomError{ Proto::RoomErrorCode::NoSuchRoom, "ROOMCODE" }));
@@ -133,6 +543,197 @@ TEST_CASE("chat round trips, spaces and empty text included")
 	CHECK(std::get<Proto::RoomChat>(RoundTrip(Proto::RoomChat{ 7, "" })).text.empty());
 }
 
+TEST_CASE("link previews round trip with every field")
+{
+	Proto::LinkPreview preview{ "https://example.com/a", "A title", "Some words", "https://example.com/a.png", "Example" };
+	auto out = std::get<Proto::ChatLinkPreview>(RoundTrip(Proto::ChatLinkPreview{ 5, preview }));
+	CHECK(out.from == 5);
+	CHECK(out.preview.url == preview.url);
+	CHECK(out.preview.title == preview.title);
+	CHECK(out.preview.description == preview.description);
+	CHECK(out.preview.image == preview.image);
+	CHECK(out.preview.site == preview.site);
+}
+
+TEST_CASE("a maxed-out link preview fits one reassembled message")
+{
+	Proto::LinkPreview preview{ std::string(Proto::max_preview_url, 'u'), std::string(Proto::max_preview_title, 't'),
+		std::string(Proto::max_preview_description, 'd'), std::string(Proto::max_preview_url, 'i'), std::string(Proto::max_preview_site, 's') };
+
+	std::vector<std::byte> data;
+	REQUIRE(!failure(zpp::bits::out{ data }(Proto::ServerMessage{ Proto::ChatLinkPreview{ 1, preview } })));
+	CHECK(data.size() <= Proto::max_reassembled_size);
+}
+
+TEST_CASE("blob chunks round trip their bytes")
+{
+	std::vector<std::byte> bytes{ std::byte{ 0x00 }, std::byte{ 0xFF }, std::byte{ 0x7F } };
+	auto out = std::get<Proto::BlobChunk>(RoundTrip(Proto::BlobChunk{ 9, bytes }));
+	CHECK(out.transfer == 9);
+	CHECK(out.data == bytes);
+
+	auto error = std::get<Proto::BlobError>(RoundTrip(Proto::BlobError{ 9, Proto::BlobErrorCode::NoSuchBlob, "abc" }));
+	CHECK(error.transfer == 9);
+	CHECK(error.code == Proto::BlobErrorCode::NoSuchBlob);
+	CHECK(error.context == "abc");
+}
+
+TEST_CASE("a full blob chunk fits one reassembled message")
+{
+	std::vector<std::byte> data;
+	REQUIRE(!failure(zpp::bits::out{ data }(Proto::ServerMessage{ Proto::BlobChunk{ 1, std::vector<std::byte>(Proto::max_blob_chunk) } })));
+	CHECK(data.size() <= Proto::max_reassembled_size);
+}
+
+TEST_CASE("blob ids are 64 lowercase hex digits")
+{
+	std::string id(Proto::blob_id_size, 'a');
+	CHECK(Proto::ValidBlobId(id));
+	CHECK(Proto::ValidBlobId(std::string(32, '0') + std::string(32, 'f')));
+	CHECK(!Proto::ValidBlobId(id.substr(1)));
+	CHECK(!Proto::ValidBlobId(id + "a"));
+	CHECK(!Proto::ValidBlobId(std::string(Proto::blob_id_size, 'A')));
+	CHECK(!Proto::ValidBlobId(std::string(Proto::blob_id_size - 1, 'a') + "g"));
+	CHECK(!Proto::ValidBlobId("../../accounts.tsv"));
+}
+
+TEST_CASE("chat text passes through untouched when already clean")
+{
+	std::string text = "gg, one more?\nsure \xF0\x9F\x91\x8D"; // a thumbs up
+	CHECK(Proto::SanitizeChat(text) == Proto::ChatTextIssue::None);
+	CHECK(text == "gg, one more?\nsure \xF0\x9F\x91\x8D");
+}
+
+TEST_CASE("chat text loses control and bidi characters")
+{
+	std::string text = "a\x01" "b\rc\x7F d\xE2\x80\xAE" "evil\xE2\x81\xA6!"; // U+202E override, U+2066 isolate
+	REQUIRE(Proto::SanitizeChat(text) == Proto::ChatTextIssue::None);
+	CHECK(text == "abc devil!");
+}
+
+TEST_CASE("chat whitespace is trimmed and long runs shrink")
+{
+	std::string text = "  \n hi" + std::string(10, ' ') + "there  \n\n\n\n\n  next\t\t  ";
+	REQUIRE(Proto::SanitizeChat(text) == Proto::ChatTextIssue::None);
+	CHECK(text == "hi" + std::string(Proto::max_chat_spaces, ' ') + "there\n\n  next");
+}
+
+TEST_CASE("chat text with nothing visible is empty")
+{
+	for (std::string text : { "", "   ", "\n\t\r", "\xE2\x80\x8B\xE2\x80\x8B" }) { // the last is two zero-width spaces
+		std::string before = text;
+		CHECK(Proto::SanitizeChat(text) == Proto::ChatTextIssue::Empty);
+		CHECK(text == before); // refused text is left alone
+	}
+}
+
+TEST_CASE("chat text must be well-formed utf-8")
+{
+	for (std::string text : { "\xFF", "ab\xC3", "\xC0\xAF", "\xED\xA0\x80", "\xF4\x90\x80\x80", "\x80" }) { // bad lead, truncated, overlong, surrogate, past U+10FFFF, stray
+		CHECK(Proto::SanitizeChat(text) == Proto::ChatTextIssue::BadUtf8);
+	}
+}
+
+TEST_CASE("chat length caps count characters, not bytes")
+{
+	std::string chars(Proto::max_chat_chars, 'x');
+	CHECK(Proto::SanitizeChat(chars) == Proto::ChatTextIssue::None);
+	chars += 'x';
+	CHECK(Proto::SanitizeChat(chars) == Proto::ChatTextIssue::TooLong);
+
+	std::string wide;
+	for (size_t i = 0; i < Proto::max_chat_chars; i++)
+		wide += "\xC3\xA9"; // two bytes each, still max_chat_chars characters
+	CHECK(Proto::SanitizeChat(wide) == Proto::ChatTextIssue::None);
+
+	std::string huge(Proto::max_chat_bytes + 1, ' '); // refused before any decoding
+	CHECK(Proto::SanitizeChat(huge) == Proto::ChatTextIssue::TooLong);
+}
+
+TEST_CASE("zero-width characters are only counted when a limit is set")
+{
+	std::string zwsp = "\xE2\x80\x8B";
+	std::string text = "h" + zwsp + "e" + zwsp + "y" + zwsp;
+	std::string copy = text;
+	CHECK(Proto::SanitizeChat(copy) == Proto::ChatTextIssue::None);
+
+	copy = text;
+	CHECK(Proto::SanitizeChat(copy, 3) == Proto::ChatTextIssue::None);
+	copy = text;
+	CHECK(Proto::SanitizeChat(copy, 2) == Proto::ChatTextIssue::ZeroWidthSpam);
+}
+
+TEST_CASE("whispers round trip with the peer id")
+{
+	auto out = std::get<Proto::RoomWhisper>(RoundTrip(Proto::RoomWhisper{ 9, "just you" }));
+	CHECK(out.id == 9);
+	CHECK(out.text == "just you");
+}
+
+TEST_CASE("preferences round trip")
+{
+	auto set = std::get<Proto::SetPreferences>(RoundTrip(Proto::SetPreferences{ { 1700000000, Proto::WhisperPolicy::Staff } }));
+	CHECK(set.prefs.dnd_until == 1700000000);
+	CHECK(set.prefs.whispers == Proto::WhisperPolicy::Staff);
+
+	auto changed = std::get<Proto::PreferencesChanged>(RoundTrip(Proto::PreferencesChanged{}));
+	CHECK(changed.prefs.dnd_until == 0);
+	CHECK(changed.prefs.whispers == Proto::WhisperPolicy::Everyone);
+}
+
+TEST_CASE("whisper policy and do-not-disturb gate whispers")
+{
+	Proto::NotificationPreferences prefs;
+	CHECK(Proto::WhisperAllowed(prefs, false, 100));
+
+	prefs.whispers = Proto::WhisperPolicy::Staff;
+	CHECK(!Proto::WhisperAllowed(prefs, false, 100));
+	CHECK(Proto::WhisperAllowed(prefs, true, 100));
+
+	prefs.whispers = Proto::WhisperPolicy::Nobody;
+	CHECK(!Proto::WhisperAllowed(prefs, true, 100));
+
+	prefs = { 200, Proto::WhisperPolicy::Everyone };
+	CHECK(!Proto::WhisperAllowed(prefs, true, 100)); // dnd blocks staff too
+	CHECK(Proto::WhisperAllowed(prefs, false, 200)); // and lapses on its own
+}
+
+TEST_CASE("account messages round trip")
+{
+	auto create = std::get<Proto::CreateAccount>(RoundTrip(Proto::CreateAccount{ "Alice", "correct horse", "alice@example.com" }));
+	CHECK(create.name == "Alice");
+	CHECK(create.password == "correct horse");
+	CHECK(create.email == "alice@example.com");
+
+	auto login = std::get<Proto::Login>(RoundTrip(Proto::Login{ "alice", "correct horse" }));
+	CHECK(login.name == "alice");
+	CHECK(login.password == "correct horse");
+
+	CHECK(std::get<Proto::GuestLogin>(RoundTrip(Proto::GuestLogin{ "Alice" })).name == "Alice");
+	CHECK(std::get<Proto::LoggedIn>(RoundTrip(Proto::LoggedIn{ "Alice#4821" })).name == "Alice#4821");
+
+	auto error = std::get<Proto::AccountError>(RoundTrip(Proto::AccountError{ Proto::AccountErrorCode::NameTaken, "Alice" }));
+	CHECK(error.code == Proto::AccountErrorCode::NameTaken);
+	CHECK(error.context == "Alice");
+
+	auto name = std::get<Proto::MemberNameChanged>(RoundTrip(Proto::MemberNameChanged{ 3, "Alice" }));
+	CHECK(name.id == 3);
+	CHECK(name.name == "Alice");
+}
+
+TEST_CASE("account names are short runs of letters, digits, _ and -")
+{
+	CHECK(Proto::ValidAccountName("Alice"));
+	CHECK(Proto::ValidAccountName("a_b-9"));
+	CHECK(Proto::ValidAccountName(std::string(Proto::max_account_name, 'x')));
+
+	CHECK(!Proto::ValidAccountName("ab")); // too short
+	CHECK(!Proto::ValidAccountName(std::string(Proto::max_account_name + 1, 'x')));
+	CHECK(!Proto::ValidAccountName("two words"));
+	CHECK(!Proto::ValidAccountName("tab\there"));
+	CHECK(!Proto::ValidAccountName("Alice#4821")); // '#' is not allowed
+}
+
 TEST_CASE("room events round trip")
 {
 	CHECK(std::get<Proto::PeerJoined>(RoundTrip(Proto::PeerJoined{ 3 })).id == 3);
@@ -151,6 +752,116 @@ TEST_CASE("host actions round trip")
 	CHECK(std::get<Proto::SetRoomPassword>(RoundTrip(Proto::SetRoomPassword{ "" })).password.empty()); // empty = clear
 }
 
+TEST_CASE("member roles round trip")
+{
+	auto set = std::get<Proto::SetMemberRole>(RoundTrip(Proto::SetMemberRole{ 3, Proto::MemberRole::CoHost }));
+	CHECK(set.id == 3);
+	CHECK(set.role == Proto::MemberRole::CoHost);
+
+	auto changed = std::get<Proto::MemberRoleChanged>(RoundTrip(Proto::MemberRoleChanged{ 3, Proto::MemberRole::Member }));
+	CHECK(changed.id == 3);
+	CHECK(changed.role == Proto::MemberRole::Member);
+}
+
+TEST_CASE("spectating and capacity round trip")
+{
+	auto spectate = std::get<Proto::SpectateRoom>(RoundTrip(Proto::SpectateRoom{ "ROOMCODE", "hunter2" }));
+	CHECK(spectate.id == "ROOMCODE");
+	CHECK(spectate.password == "hunter2");
+
+	CHECK(std::get<Proto::MemberSpectating>(RoundTrip(Proto::MemberSpectating{ 3 })).id == 3);
+
+	auto set = std::get<Proto::SetRoomCapacity>(RoundTrip(Proto::SetRoomCapacity{ 8, 0 }));
+	CHECK(set.max_players == 8);
+	CHECK(set.max_spectators == 0); // 0 = no limit
+
+	auto changed = std::get<Proto::RoomCapacityChanged>(RoundTrip(Proto::RoomCapacityChanged{ 8, 2 }));
+	CHECK(changed.max_players == 8);
+	CHECK(changed.max_spectators == 2);
+}
+
+TEST_CASE("idle policy and afk flags round trip")
+{
+	RoundTrip(Proto::ReportActivity{}); // the index check inside is the whole test
+	CHECK(std::get<Proto::SetBackground>(RoundTrip(Proto::SetBackground{ true })).background);
+
+	auto set = std::get<Proto::SetIdlePolicy>(RoundTrip(Proto::SetIdlePolicy{ 300, true }));
+	CHECK(set.afk_after == 300);
+	CHECK(set.spectate == true);
+
+	auto changed = std::get<Proto::IdlePolicyChanged>(RoundTrip(Proto::IdlePolicyChanged{ 0, false }));
+	CHECK(changed.afk_after == 0); // 0 = never
+	CHECK(changed.spectate == false);
+
+	auto afk = std::get<Proto::MemberAfk>(RoundTrip(Proto::MemberAfk{ 3, true }));
+	CHECK(afk.id == 3);
+	CHECK(afk.afk == true);
+}
+
+TEST_CASE("scheduled rooms round trip")
+{
+	auto book = std::get<Proto::ScheduleRoom>(RoundTrip(Proto::ScheduleRoom{ 1700000000, "friday league", 8, 2, "hunter2" }));
+	CHECK(book.start == 1700000000);
+	CHECK(book.title == "friday league");
+	CHECK(book.max_players == 8);
+	CHECK(book.max_spectators == 2);
+	CHECK(book.password == "hunter2");
+
+	Proto::ScheduledRoom room{ 4, 3, 1700000000, "friday league", 8, 2, true, 5 };
+	auto changed = std::get<Proto::ScheduledRoomChanged>(RoundTrip(Proto::ScheduledRoomChanged{ room }));
+	CHECK(changed.room.id == 4);
+	CHECK(changed.room.owner == 3);
+	CHECK(changed.room.title == "friday league");
+	CHECK(changed.room.passworded == true);
+	CHECK(changed.room.going == 5);
+
+	RoundTrip(Proto::ListScheduledRooms{}); // the index check inside is the whole test
+	auto list = std::get<Proto::ScheduledRoomList>(RoundTrip(Proto::ScheduledRoomList{ { room, room } }));
+	REQUIRE(list.rooms.size() == 2);
+	CHECK(list.rooms[1].start == 1700000000);
+
+	auto rsvp = std::get<Proto::RsvpScheduledRoom>(RoundTrip(Proto::RsvpScheduledRoom{ 4, Proto::RsvpStatus::Going }));
+	CHECK(rsvp.id == 4);
+	CHECK(rsvp.status == Proto::RsvpStatus::Going);
+
+	auto reminder = std::get<Proto::ScheduledRoomReminder>(RoundTrip(Proto::ScheduledRoomReminder{ 4, 1700000000 }));
+	CHECK(reminder.id == 4);
+	CHECK(reminder.start == 1700000000);
+
+	auto opened = std::get<Proto::ScheduledRoomOpened>(RoundTrip(Proto::ScheduledRoomOpened{ 4, "VY4C3NB9" }));
+	CHECK(opened.id == 4);
+	CHECK(opened.room == "VY4C3NB9");
+}
+
+TEST_CASE("attendee lists round trip")
+{
+	CHECK(std::get<Proto::ListAttendees>(RoundTrip(Proto::ListAttendees{ 4 })).id == 4);
+
+	auto list = std::get<Proto::ScheduledRoomAttendees>(RoundTrip(Proto::ScheduledRoomAttendees{ 4, { { 3, Proto::RsvpStatus::Going }, { 7, Proto::RsvpStatus::Maybe } } }));
+	CHECK(list.id == 4);
+	REQUIRE(list.attendees.size() == 2);
+	CHECK(list.attendees[0].id == 3);
+	CHECK(list.attendees[0].status == Proto::RsvpStatus::Going);
+	CHECK(list.attendees[1].id == 7);
+	CHECK(list.attendees[1].status == Proto::RsvpStatus::Maybe);
+}
+
+TEST_CASE("booking cancellations round trip")
+{
+	CHECK(std::get<Proto::CancelScheduledRoom>(RoundTrip(Proto::CancelScheduledRoom{ 4 })).id == 4);
+	CHECK(std::get<Proto::ScheduledRoomCancelled>(RoundTrip(Proto::ScheduledRoomCancelled{ 4 })).id == 4);
+}
+
+TEST_CASE("a full booking board still fits one reassembled message")
+{
+	Proto::ScheduledRoom room{ 1, 1, 1700000000, std::string(Proto::max_schedule_title, 'x'), 8, 2, true, 100 };
+	Proto::ScheduledRoomList list{ std::vector<Proto::ScheduledRoom>(Proto::max_scheduled_rooms, room) };
+
+	std::vector<std::byte> data;
+	REQUIRE(!failure(zpp::bits::out{ data }(Proto::ServerMessage{ list })));
+	CHECK(data.size() <= Proto::max_reassembled_size);
+}
+
 TEST_CASE("room access events round trip")
 {
 	RoundTrip(Proto::KickedByHost{}); // the index check inside is the whole test
@@ -161,6 +872,43 @@ TEST_CASE("room access events round trip")
 	CHECK(access.passworded == true);
 }
 
+TEST_CASE("signal payloads must look like what libjuice sends")
+{
+	using Kind = Proto::P2PSignalKind;
+	CHECK(Proto::ValidSignalPayload(Kind::Description, "a=ice-ufrag:abcd\r\na=ice-pwd:0123456789abcdef0123\r\na=ice-options:ice2,trickle\r\n"));
+	CHECK_FALSE(Proto::ValidSignalPayload(Kind::Description, "a=ice-ufrag:abcd\r\n")); // no password
+	CHECK_FALSE(Proto::ValidSignalPayload(Kind::Description, "a=ice-ufrag:abcd\r\na=ice-pwd:x\r\nnot sdp\r\n"));
+	CHECK_FALSE(Proto::ValidSignalPayload(Kind::Description, "a=ice-ufrag:abcd\r\na=ice-pwd:x\r\na=" + std::string(Proto::max_signal_description, 'x')));
+
+	CHECK(Proto::ValidSignalPayload(Kind::Candidate, "a=candidate:1 1 UDP 2122317823 192.168.0.10 50000 typ host"));
+	CHECK_FALSE(Proto::ValidSignalPayload(Kind::Candidate, "a=candidate:1\r\na=candidate:2"));
+	CHECK_FALSE(Proto::ValidSignalPayload(Kind::Candidate, "a=candidate:1 1 UDP \x01"));
+	CHECK_FALSE(Proto::ValidSignalPayload(Kind::Candidate, "a=candidate:" + std::string(Proto::max_signal_candidate, '1')));
+
+	CHECK(Proto::ValidSignalPayload(Kind::Framing, "1"));
+	CHECK_FALSE(Proto::ValidSignalPayload(Kind::Framing, "1x"));
+	CHECK_FALSE(Proto::ValidSignalPayload(Kind::Framing, ""));
+	CHECK(Proto::ValidSignalPayload(Kind::GatheringDone, ""));
+	CHECK_FALSE(Proto::ValidSignalPayload(Kind::Closed, "bye"));
+	CHECK_FALSE(Proto::ValidSignalPayload((Kind)200, ""));
+}
+
+TEST_CASE("sequenced signals round trip and only newer ones are fresh")
+{
+	auto out = std::get<Proto::SequencedSignal>(RoundTrip(Proto::SequencedSignal{ 12, { 4, Proto::P2PSignalKind::GatheringDone, {} } }));
+	CHECK(out.seq == 12);
+	CHECK(out.signal.id == 4);
+	CHECK(out.signal.kind == Proto::P2PSignalKind::GatheringDone);
+
+	uint64_t last = 0;
+	CHECK(Proto::FreshSignal(last, 1));
+	CHECK(Proto::FreshSignal(last, 5)); // gaps are signals sent to someone else
+	CHECK(last == 5);
+	CHECK_FALSE(Proto::FreshSignal(last, 5)); // doubled
+	CHECK_FALSE(Proto::FreshSignal(last, 3)); // replayed
+	CHECK(last == 5);
+}
+
 TEST_CASE("p2p signaling round trips")
 {
 	auto sig = std::get<Proto::P2PSignal>(RoundTrip(Proto::P2PSignal{ 3, Proto::P2PSignalKind::Candidate, "a=candidate:1 1 UDP 2122317823 192.168.0.10 50000 typ host" }));
@@ -192,6 +940,11 @@ TEST_CASE("data messages round trip, empty value (= delete) included")
 	CHECK(mine.key == "char");
 	CHECK(mine.value == "ryu");
 
+	auto theirs = std::get<Proto::SetMemberDataFor>(RoundTrip(Proto::SetMemberDataFor{ 5, "team", "red" }));
+	CHECK(theirs.id == 5);
+	CHECK(theirs.key == "team");
+	CHECK(theirs.value == "red");
+
 	auto member = std::get<Proto::MemberDataChanged>(RoundTrip(Proto::MemberDataChanged{ 5, "char", "akuma" }));
 	CHECK(member.id == 5);
 	CHECK(member.key == "char");
@@ -214,6 +967,221 @@ TEST_CASE("the largest allowed data pair fits under the frame cap")
 	RoundTrip(Proto::MemberDataChanged{ 0xFFFFFFFF, key, value });
 }
 
+TEST_CASE("room rules round trip with their version, and the largest fits one reassembled message")
+{
+	CHECK(std::get<Proto::SetRoomRules>(RoundTrip(Proto::SetRoomRules{ R"({"map":"fod"})" })).rules == R"({"map":"fod"})");
+
+	std::string rules(Proto::max_room_rules, 'r');
+	auto out = std::get<Proto::RoomRulesChanged>(RoundTrip(Proto::RoomRulesChanged{ 3, rules }));
+	CHECK(out.version == 3);
+	CHECK(out.rules == rules);
+
+	std::vector<std::byte> data;
+	REQUIRE(!failure(zpp::bits::out{ data }(Proto::ServerMessage{ Proto::RoomRulesChanged{ UINT32_MAX, rules } })));
+	CHECK(data.size() <= Proto::max_reassembled_size);
+}
+
+TEST_CASE("countdowns round trip with the server's stamp")
+{
+	CHECK(std::get<Proto::StartCountdown>(RoundTrip(Proto::StartCountdown{ 5 })).seconds == 5);
+
+	auto out = std::get<Proto::CountdownStarted>(RoundTrip(Proto::CountdownStarted{ 4, 5, 1700000005000, 4990, 2 }));
+	CHECK(out.id == 4);
+	CHECK(out.seconds == 5);
+	CHECK(out.start_at == 1700000005000);
+	CHECK(out.remaining == 4990);
+	CHECK(out.by == 2);
+}
+
+TEST_CASE("match pauses round trip")
+{
+	CHECK(!std::get<Proto::SetMatchPaused>(RoundTrip(Proto::SetMatchPaused{ false })).paused);
+
+	auto out = std::get<Proto::MatchPausedChanged>(RoundTrip(Proto::MatchPausedChanged{ true, 4 }));
+	CHECK(out.paused);
+	CHECK(out.by == 4);
+}
+
+TEST_CASE("health reports round trip")
+{
+	auto report = std::get<Proto::ReportHealth>(RoundTrip(Proto::ReportHealth{ 42, { { 3, true, true, 55, 12000, 9000 } }, true }));
+	CHECK(report.server_rtt == 42);
+	REQUIRE(report.links.size() == 1);
+	CHECK(report.links[0].peer == 3);
+	CHECK(report.links[0].direct);
+	CHECK(report.links[0].relayed);
+	CHECK(report.links[0].loss_permille == 55);
+	CHECK(report.links[0].send_rate == 12000);
+	CHECK(report.links[0].receive_rate == 9000);
+	CHECK(report.share);
+
+	auto out = std::get<Proto::MemberHealth>(RoundTrip(Proto::MemberHealth{ 5, 42, report.links }));
+	CHECK(out.from == 5);
+	CHECK(out.server_rtt == 42);
+	CHECK(out.links.size() == 1);
+}
+
+TEST_CASE("session health round trips with each report's age")
+{
+	CHECK(std::holds_alternative<Proto::GetSessionHealth>(RoundTrip(Proto::GetSessionHealth{})));
+
+	Proto::LinkHealth link{ 2, true, false, 7, 500, 450 };
+	auto out = std::get<Proto::SessionHealth>(RoundTrip(Proto::SessionHealth{ { { { 3, 28, { link } }, 6 } } }));
+	REQUIRE(out.reports.size() == 1);
+	CHECK(out.reports[0].member.from == 3);
+	CHECK(out.reports[0].member.server_rtt == 28);
+	REQUIRE(out.reports[0].member.links.size() == 1);
+	CHECK(out.reports[0].member.links[0].peer == 2);
+	CHECK(out.reports[0].member.links[0].receive_rate == 450);
+	CHECK(out.reports[0].age == 6);
+}
+
+TEST_CASE("join receipts round trip with the key they were signed by")
+{
+	CHECK(std::holds_alternative<Proto::GetJoinReceipts>(RoundTrip(Proto::GetJoinReceipts{})));
+
+	Proto::JoinReceipt receipt{ "ROOMCODE", 3, "Alice", Proto::ReceiptRole::Spectator, Proto::ReceiptKind::Left, 1769882645, std::string(128, 'a') };
+	auto out = std::get<Proto::JoinReceipts>(RoundTrip(Proto::JoinReceipts{ std::string(64, 'b'), { receipt } }));
+	CHECK(out.key == std::string(64, 'b'));
+	REQUIRE(out.receipts.size() == 1);
+	CHECK(out.receipts[0].room == "ROOMCODE");
+	CHECK(out.receipts[0].name == "Alice");
+	CHECK(out.receipts[0].role == Proto::ReceiptRole::Spectator);
+	CHECK(out.receipts[0].kind == Proto::ReceiptKind::Left);
+	CHECK(out.receipts[0].time == 1769882645);
+	CHECK(out.receipts[0].signature == receipt.signature);
+}
+
+TEST_CASE("a receipt's signed text covers every field but the signature")
+{
+	Proto::JoinReceipt receipt{ "ROOMCODE", 3, {}, Proto::ReceiptRole::Host, Proto::ReceiptKind::Joined, 1769882645, "ignored" };
+	CHECK(Proto::ReceiptText(receipt) == "weyvelength join receipt v1\nroom ROOMCODE\nid 3\nname \nrole host\nkind joined\ntime 1769882645\n");
+
+	std::string text = Proto::ReceiptText(receipt);
+	receipt.signature = "changed";
+	CHECK(Proto::ReceiptText(receipt) == text);
+	receipt.role = Proto::ReceiptRole::Player;
+	CHECK(Proto::ReceiptText(receipt) != text);
+}
+
+TEST_CASE("host state round trips as opaque bytes")
+{
+	std::string state(Proto::max_host_state, '\0');
+	state[1] = 'x';
+	CHECK(std::get<Proto::SetHostState>(RoundTrip(Proto::SetHostState{ state })).state == state);
+
+	auto out = std::get<Proto::HostHandoff>(RoundTrip(Proto::HostHandoff{ 3, Proto::HandoffReason::Transferred, 1769882645, state }));
+	CHECK(out.from == 3);
+	CHECK(out.reason == Proto::HandoffReason::Transferred);
+	CHECK(out.saved == 1769882645);
+	CHECK(out.state == state);
+
+	CHECK(std::get<Proto::HostStateWanted>(RoundTrip(Proto::HostStateWanted{ 4 })).host == 4);
+	CHECK(std::get<Proto::ReportHostState>(RoundTrip(Proto::ReportHostState{ "view" })).state == "view");
+	auto view = std::get<Proto::MemberHostState>(RoundTrip(Proto::MemberHostState{ 5, "view" }));
+	CHECK(view.from == 5);
+	CHECK(view.state == "view");
+}
+
+TEST_CASE("a succession policy round trips with its successors")
+{
+	auto set = std::get<Proto::SetSuccession>(RoundTrip(Proto::SetSuccession{ Proto::SuccessionPolicy::Successors, { 4, 7 } }));
+	CHECK(set.policy == Proto::SuccessionPolicy::Successors);
+	CHECK(set.successors == std::vector<uint32_t>{ 4, 7 });
+
+	auto changed = std::get<Proto::SuccessionChanged>(RoundTrip(Proto::SuccessionChanged{ Proto::SuccessionPolicy::LongestConnected, {} }));
+	CHECK(changed.policy == Proto::SuccessionPolicy::LongestConnected);
+	CHECK(changed.successors.empty());
+}
+
+TEST_CASE("each succession policy picks its own next host")
+{
+	// in join order: a spectating co-host, a player who connected last but is fastest, a player who connected first, an unmeasured player
+	std::vector<Proto::SuccessionCandidate> members = {
+		{ 2, true, true, 50, 90 },
+		{ 3, false, false, 40, 20 },
+		{ 4, false, false, 10, 60 },
+		{ 5, false, false, 30, 0 },
+	};
+	CHECK(Proto::PickSuccessor(Proto::SuccessionPolicy::CoHostFirst, {}, members) == 2);
+	CHECK(Proto::PickSuccessor(Proto::SuccessionPolicy::LongestConnected, {}, members) == 4);
+	CHECK(Proto::PickSuccessor(Proto::SuccessionPolicy::LowestLatency, {}, members) == 3);
+	CHECK(Proto::PickSuccessor(Proto::SuccessionPolicy::Successors, { 9, 5, 3 }, members) == 5); // 9 left already
+	CHECK(Proto::PickSuccessor(Proto::SuccessionPolicy::Successors, { 9 }, members) == 2); // none left: a co-host first
+
+	members[0].cohost = false;
+	CHECK(Proto::PickSuccessor(Proto::SuccessionPolicy::CoHostFirst, {}, members) == 3); // the first player, ahead of the spectator
+
+	for (auto& member : members)
+		member.server_rtt = 0;
+	CHECK(Proto::PickSuccessor(Proto::SuccessionPolicy::LowestLatency, {}, members) == 3); // nobody measured: join order
+	CHECK(Proto::PickSuccessor(Proto::SuccessionPolicy::LowestLatency, {}, {}) == 0);
+}
+
+// KnownSuccession takes nothing past Successors, so it must stay the last policy.
+static_assert((uint8_t)Proto::SuccessionPolicy::CoHostFirst == 0);
+static_assert((uint8_t)Proto::SuccessionPolicy::LongestConnected == 1);
+static_assert((uint8_t)Proto::SuccessionPolicy::LowestLatency == 2);
+static_assert((uint8_t)Proto::SuccessionPolicy::Successors == 3);
+
+TEST_CASE("only the succession policies this server knows are taken")
+{
+	CHECK(Proto::KnownSuccession(Proto::SuccessionPolicy::CoHostFirst));
+	CHECK(Proto::KnownSuccession(Proto::SuccessionPolicy::Successors));
+
+	auto set = std::get<Proto::SetSuccession>(RoundTrip(Proto::SetSuccession{ (Proto::SuccessionPolicy)4, {} }));
+	CHECK(!Proto::KnownSuccession(set.policy)); // arrives as sent, for the server to refuse
+	CHECK(!Proto::KnownSuccession((Proto::SuccessionPolicy)255));
+}
+
+TEST_CASE("split and merge messages round trip")
+{
+	CHECK(std::get<Proto::SplitRoom>(RoundTrip(Proto::SplitRoom{ { 4, 3, 7 } })).members == std::vector<uint32_t>{ 4, 3, 7 });
+
+	auto split = std::get<Proto::RoomSplit>(RoundTrip(Proto::RoomSplit{ "NEWROOM1", 3 }));
+	CHECK(split.id == "NEWROOM1");
+	CHECK(split.members == 3);
+
+	CHECK(std::get<Proto::MergeRoom>(RoundTrip(Proto::MergeRoom{ "OTHERONE" })).id == "OTHERONE");
+
+	auto asked = std::get<Proto::MergeRequested>(RoundTrip(Proto::MergeRequested{ "ROOMCODE", 3, 8 }));
+	CHECK(asked.into == "ROOMCODE");
+	CHECK(asked.by == 3);
+	CHECK(asked.members == 8);
+
+	auto answer = std::get<Proto::AnswerMerge>(RoundTrip(Proto::AnswerMerge{ "ROOMCODE", true }));
+	CHECK(answer.into == "ROOMCODE");
+	CHECK(answer.accept);
+
+	auto answered = std::get<Proto::MergeAnswered>(RoundTrip(Proto::MergeAnswered{ "OTHERONE", false }));
+	CHECK(answered.id == "OTHERONE");
+	CHECK(!answered.accepted);
+
+	auto moved = std::get<Proto::RoomMoved>(RoundTrip(Proto::RoomMoved{ "NEWROOM1", 3 }));
+	CHECK(moved.id == "NEWROOM1");
+	CHECK(moved.by == 3);
+}
+
+TEST_CASE("channel messages round trip")
+{
+	CHECK(std::get<Proto::CreateChannel>(RoundTrip(Proto::CreateChannel{ "Red talk" })).name == "Red talk");
+	CHECK(std::get<Proto::RemoveChannel>(RoundTrip(Proto::RemoveChannel{ 2 })).channel == 2);
+
+	auto place = std::get<Proto::PlaceInChannel>(RoundTrip(Proto::PlaceInChannel{ 4, 1 }));
+	CHECK(place.id == 4);
+	CHECK(place.channel == 1);
+
+	auto changed = std::get<Proto::ChannelsChanged>(RoundTrip(Proto::ChannelsChanged{ { { 1, "Red talk", { 3, 4 } }, { 2, "Blue talk", {} } } }));
+	REQUIRE(changed.channels.size() == 2);
+	CHECK(changed.channels[0].name == "Red talk");
+	CHECK(changed.channels[0].members == std::vector<uint32_t>{ 3, 4 });
+	CHECK(changed.channels[1].id == 2);
+
+	auto chat = std::get<Proto::ChannelChat>(RoundTrip(Proto::ChannelChat{ 3, "push mid" }));
+	CHECK(chat.from == 3);
+	CHECK(chat.text == "push mid");
+}
+
 TEST_CASE("oversized messages fragment and reassemble")
 {
 	std::string sdp(3 * Proto::max_message_size, 's'); // forces a multi-fragment stream
@@ -251,3 +1219,68 @@ TEST_CASE("frame headers over the cap are rejected, reassembly is capped")
 	std::array<std::byte, 20> extra{};
 	CHECK(!Proto::AppendFragment(body, extra)); // one byte over the reassembly cap fails
 }
+
+TEST_CASE("p2p frames carry their kind ahead of the body")
+{
+	std::array<std::byte, 3> body{ std::byte{ 1 }, std::byte{ 2 }, std::byte{ 3 } };
+	std::vector<std::byte> datagram = Proto::FrameP2P(Proto::P2PFrameKind::App, body);
+	REQUIRE(datagram.size() == Proto::p2p_frame_header_size + body.size());
+
+	Proto::P2PFrameKind kind{};
+	std::span<const std::byte> out;
+	REQUIRE(Proto::UnframeP2P(datagram, kind, out));
+	CHECK(kind == Proto::P2PFrameKind::App);
+	CHECK(std::ranges::equal(out, body));
+}
+
+TEST_CASE("p2p frames may be empty but never headerless")
+{
+	std::vector<std::byte> datagram = Proto::FrameP2P(Proto::P2PFrameKind::App, {});
+	Proto::P2PFrameKind kind{};
+	std::span<const std::byte> out;
+	REQUIRE(Proto::UnframeP2P(datagram, kind, out));
+	CHECK(out.empty());
+
+	CHECK(!Proto::UnframeP2P({}, kind, out));
+}
+
+TEST_CASE("sequenced p2p frames carry a big-endian number ahead of the app's bytes")
+{
+	std::array<std::byte, 2> body{ std::byte{ 7 }, std::byte{ 8 } };
+	std::vector<std::byte> datagram = Proto::FrameSequenced(0x01020304, body);
+	REQUIRE(datagram.size() == Proto::p2p_frame_header_size + Proto::p2p_sequence_size + body.size());
+	CHECK(datagram[1] == std::byte{ 1 });
+	CHECK(datagram[4] == std::byte{ 4 });
+
+	Proto::P2PFrameKind kind{};
+	std::span<const std::byte> frame;
+	REQUIRE(Proto::UnframeP2P(datagram, kind, frame));
+	CHECK(kind == Proto::P2PFrameKind::Sequenced);
+
+	uint32_t seq = 0;
+	std::span<const std::byte> out;
+	REQUIRE(Proto::UnframeSequenced(frame, seq, out));
+	CHECK(seq == 0x01020304);
+	CHECK(std::ranges::equal(out, body));
+	CHECK(!Proto::UnframeSequenced(frame.first(3), seq, out));
+}
+
+TEST_CASE("parity frames round-trip their run")
+{
+	Proto::P2PParity parity{ 0xfffffffe, 4, 0x0102, { std::byte{ 9 }, std::byte{ 0 }, std::byte{ 3 } } };
+	std::vector<std::byte> datagram = Proto::FrameParity(parity);
+	REQUIRE(datagram.size() == Proto::p2p_frame_header_size + Proto::p2p_parity_header_size + 3);
+
+	Proto::P2PFrameKind kind{};
+	std::span<const std::byte> body;
+	REQUIRE(Proto::UnframeP2P(datagram, kind, body));
+	CHECK(kind == Proto::P2PFrameKind::Parity);
+
+	Proto::P2PParity out;
+	REQUIRE(Proto::UnframeParity(body, out));
+	CHECK(out.first == parity.first);
+	CHECK(out.count == 4);
+	CHECK(out.length_xor == 0x0102);
+	CHECK(out.data_xor == parity.data_xor);
+	CHECK(!Proto::UnframeParity(body.first(Proto::p2p_parity_header_size - 1), out));
+}
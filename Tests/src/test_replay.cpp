@@ -0,0 +1,119 @@
+#include <thirdparty/doctest/doctest.h>
+
+#include <sstream>
+#include <string>
+#include <variant>
+#include <vector>
+
+#include "marshal.h"
+#include "recording.h"
+
+using namespace Weyvelength;
+
+namespace {
+	// What a client built with WEYVE_RECORDING writes for a short session:
+	// create a room, a second member joins and chats, leave.
+	std::vector<Proto::RecordedMessage> Session()
+	{
+		using Proto::RecordDirection;
+		return {
+			{ 0, RecordDirection::Received, Proto::AssignClientId{ 7 } },
+			{ 40, RecordDirection::Sent, Proto::Idempotent{ 0xabcdef } },
+			{ 40, RecordDirection::Sent, Proto::CreateRoom{} },
+			{ 62, RecordDirection::Received, Proto::AssignRoomId{ "VY4C3NB9" } },
+			{ 62, RecordDirection::Received, Proto::HostChanged{ 7 } },
+			{ 1800, RecordDirection::Received, Proto::PeerJoined{ 8 } },
+			{ 2500, RecordDirection::Received, Proto::RoomChat{ 8, "gg" } },
+			{ 2600, RecordDirection::Sent, Proto::RoomChat{ 0, "wp" } },
+			{ 2610, RecordDirection::Received, Proto::RoomChat{ 7, "wp" } },
+			{ 3000, RecordDirection::Sent, Proto::LeaveRoom{} },
+			{ 3020, RecordDirection::Received, Proto::PeerLeft{ 7 } },
+		};
+	}
+
+	std::string Written(const std::vector<Proto::RecordedMessage>& records)
+	{
+		std::ostringstream out;
+		Proto::WriteRecordingHeader(out);
+		for (const auto& record : records)
+			Proto::WriteRecorded(out, record);
+		return out.str();
+	}
+
+	// The replay harness: the received half of a recording through the
+	// client's event dispatch, as weyve_next would hand it out, skipping what
+	// never surfaces. The sent half must still decode as what was sent.
+	std::vector<WeyveEventType> Replay(const std::vector<Proto::RecordedMessage>& records)
+	{
+		std::vector<WeyveEventType> events;
+		for (const auto& record : records) {
+			if (record.direction != Proto::RecordDirection::Received)
+				continue;
+			WeyveEvent event{};
+			if (Marshal::FillEvent(record.message, &event))
+				events.push_back(event.type);
+		}
+		return events;
+	}
+}
+
+TEST_CASE("a recording reads back message for message")
+{
+	std::istringstream in(Written(Session()));
+	auto read = Proto::ReadRecording(in);
+	REQUIRE(read);
+	auto expected = Session();
+	REQUIRE(read->size() == expected.size());
+	for (size_t i = 0; i < expected.size(); i++) {
+		CHECK((*read)[i].at == expected[i].at);
+		CHECK((*read)[i].direction == expected[i].direction);
+		CHECK((*read)[i].message.index() == expected[i].message.index());
+	}
+	CHECK(std::get<Proto::RoomChat>((*read)[6].message).text == "gg");
+	CHECK(std::get<Proto::Idempotent>((*read)[1].message).key == 0xabcdef);
+}
+
+TEST_CASE("an empty recording is still a recording")
+{
+	std::istringstream in(Written({}));
+	auto read = Proto::ReadRecording(in);
+	REQUIRE(read);
+	CHECK(read->empty());
+}
+
+TEST_CASE("anything but a whole recording of this protocol is refused")
+{
+	std::string bytes = Written(Session());
+
+	std::string magic = bytes;
+	magic[0] = 'X';
+	std::istringstream bad_magic(magic);
+	CHECK_FALSE(Proto::ReadRecording(bad_magic));
+
+	std::string version = bytes;
+	version[4] ^= 0x01;
+	std::istringstream other_version(version);
+	CHECK_FALSE(Proto::ReadRecording(other_version));
+
+	std::istringstream truncated(bytes.substr(0, bytes.size() - 1));
+	CHECK_FALSE(Proto::ReadRecording(truncated));
+
+	std::istringstream empty("");
+	CHECK_FALSE(Proto::ReadRecording(empty));
+}
+
+TEST_CASE("a replayed session raises the events it raised live")
+{
+	std::istringstream in(Written(Session()));
+	auto read = Proto::ReadRecording(in);
+	REQUIRE(read);
+	std::vector<WeyveEventType> expected{
+		WEYVE_EVENT_ROOM_ID_ASSIGNED,
+		WEYVE_EVENT_HOST_CHANGED,
+		WEYVE_EVENT_PEER_JOINED,
+		WEYVE_EVENT_CHAT,
+		WEYVE_EVENT_CHAT,
+		WEYVE_EVENT_PEER_LEFT,
+	};
+	CHECK(Replay(*read) == expected);
+}